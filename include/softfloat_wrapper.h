@@ -0,0 +1,133 @@
+/*
+ * C API of softfloat-wrapper, enabled by the `capi` feature.
+ *
+ * Every function takes a context holding the rounding mode, the exception flags and the
+ * default NaN mode of the operations, created by sfw_context_new and freed by
+ * sfw_context_free. Rounding modes and exception flags have the values of the
+ * softfloat_round_* and softfloat_flag_* constants of Berkeley SoftFloat. The F128
+ * functions need the `f128` feature.
+ */
+
+#ifndef SOFTFLOAT_WRAPPER_H
+#define SOFTFLOAT_WRAPPER_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct sfw_context sfw_context;
+
+/* the layout of float128_t */
+typedef struct {
+    uint64_t v[2];
+} sfw_float128_t;
+
+sfw_context *sfw_context_new(void);
+void sfw_context_free(sfw_context *ctx);
+uint8_t sfw_get_rounding_mode(const sfw_context *ctx);
+bool sfw_set_rounding_mode(sfw_context *ctx, uint8_t rnd);
+uint8_t sfw_get_flags(const sfw_context *ctx);
+void sfw_set_flags(sfw_context *ctx, uint8_t flags);
+void sfw_set_default_nan_mode(sfw_context *ctx, bool enabled);
+
+
+uint16_t sfw_f16_add(sfw_context *ctx, uint16_t a, uint16_t b);
+uint16_t sfw_f16_sub(sfw_context *ctx, uint16_t a, uint16_t b);
+uint16_t sfw_f16_mul(sfw_context *ctx, uint16_t a, uint16_t b);
+uint16_t sfw_f16_div(sfw_context *ctx, uint16_t a, uint16_t b);
+uint16_t sfw_f16_rem(sfw_context *ctx, uint16_t a, uint16_t b);
+uint16_t sfw_f16_mulAdd(sfw_context *ctx, uint16_t a, uint16_t b, uint16_t c);
+uint16_t sfw_f16_sqrt(sfw_context *ctx, uint16_t a);
+uint16_t sfw_f16_roundToInt(sfw_context *ctx, uint16_t a);
+bool sfw_f16_eq(sfw_context *ctx, uint16_t a, uint16_t b);
+bool sfw_f16_lt(sfw_context *ctx, uint16_t a, uint16_t b);
+bool sfw_f16_le(sfw_context *ctx, uint16_t a, uint16_t b);
+uint32_t sfw_f16_to_ui32(sfw_context *ctx, uint16_t a, bool exact);
+uint64_t sfw_f16_to_ui64(sfw_context *ctx, uint16_t a, bool exact);
+int32_t sfw_f16_to_i32(sfw_context *ctx, uint16_t a, bool exact);
+int64_t sfw_f16_to_i64(sfw_context *ctx, uint16_t a, bool exact);
+uint16_t sfw_ui32_to_f16(sfw_context *ctx, uint32_t a);
+uint16_t sfw_ui64_to_f16(sfw_context *ctx, uint64_t a);
+uint16_t sfw_i32_to_f16(sfw_context *ctx, int32_t a);
+uint16_t sfw_i64_to_f16(sfw_context *ctx, int64_t a);
+uint32_t sfw_f16_to_f32(sfw_context *ctx, uint16_t a);
+uint64_t sfw_f16_to_f64(sfw_context *ctx, uint16_t a);
+sfw_float128_t sfw_f16_to_f128(sfw_context *ctx, uint16_t a);
+
+uint32_t sfw_f32_add(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_sub(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_mul(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_div(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_rem(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_mulAdd(sfw_context *ctx, uint32_t a, uint32_t b, uint32_t c);
+uint32_t sfw_f32_sqrt(sfw_context *ctx, uint32_t a);
+uint32_t sfw_f32_roundToInt(sfw_context *ctx, uint32_t a);
+bool sfw_f32_eq(sfw_context *ctx, uint32_t a, uint32_t b);
+bool sfw_f32_lt(sfw_context *ctx, uint32_t a, uint32_t b);
+bool sfw_f32_le(sfw_context *ctx, uint32_t a, uint32_t b);
+uint32_t sfw_f32_to_ui32(sfw_context *ctx, uint32_t a, bool exact);
+uint64_t sfw_f32_to_ui64(sfw_context *ctx, uint32_t a, bool exact);
+int32_t sfw_f32_to_i32(sfw_context *ctx, uint32_t a, bool exact);
+int64_t sfw_f32_to_i64(sfw_context *ctx, uint32_t a, bool exact);
+uint32_t sfw_ui32_to_f32(sfw_context *ctx, uint32_t a);
+uint32_t sfw_ui64_to_f32(sfw_context *ctx, uint64_t a);
+uint32_t sfw_i32_to_f32(sfw_context *ctx, int32_t a);
+uint32_t sfw_i64_to_f32(sfw_context *ctx, int64_t a);
+uint16_t sfw_f32_to_f16(sfw_context *ctx, uint32_t a);
+uint64_t sfw_f32_to_f64(sfw_context *ctx, uint32_t a);
+sfw_float128_t sfw_f32_to_f128(sfw_context *ctx, uint32_t a);
+
+uint64_t sfw_f64_add(sfw_context *ctx, uint64_t a, uint64_t b);
+uint64_t sfw_f64_sub(sfw_context *ctx, uint64_t a, uint64_t b);
+uint64_t sfw_f64_mul(sfw_context *ctx, uint64_t a, uint64_t b);
+uint64_t sfw_f64_div(sfw_context *ctx, uint64_t a, uint64_t b);
+uint64_t sfw_f64_rem(sfw_context *ctx, uint64_t a, uint64_t b);
+uint64_t sfw_f64_mulAdd(sfw_context *ctx, uint64_t a, uint64_t b, uint64_t c);
+uint64_t sfw_f64_sqrt(sfw_context *ctx, uint64_t a);
+uint64_t sfw_f64_roundToInt(sfw_context *ctx, uint64_t a);
+bool sfw_f64_eq(sfw_context *ctx, uint64_t a, uint64_t b);
+bool sfw_f64_lt(sfw_context *ctx, uint64_t a, uint64_t b);
+bool sfw_f64_le(sfw_context *ctx, uint64_t a, uint64_t b);
+uint32_t sfw_f64_to_ui32(sfw_context *ctx, uint64_t a, bool exact);
+uint64_t sfw_f64_to_ui64(sfw_context *ctx, uint64_t a, bool exact);
+int32_t sfw_f64_to_i32(sfw_context *ctx, uint64_t a, bool exact);
+int64_t sfw_f64_to_i64(sfw_context *ctx, uint64_t a, bool exact);
+uint64_t sfw_ui32_to_f64(sfw_context *ctx, uint32_t a);
+uint64_t sfw_ui64_to_f64(sfw_context *ctx, uint64_t a);
+uint64_t sfw_i32_to_f64(sfw_context *ctx, int32_t a);
+uint64_t sfw_i64_to_f64(sfw_context *ctx, int64_t a);
+uint16_t sfw_f64_to_f16(sfw_context *ctx, uint64_t a);
+uint32_t sfw_f64_to_f32(sfw_context *ctx, uint64_t a);
+sfw_float128_t sfw_f64_to_f128(sfw_context *ctx, uint64_t a);
+
+sfw_float128_t sfw_f128_add(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+sfw_float128_t sfw_f128_sub(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+sfw_float128_t sfw_f128_mul(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+sfw_float128_t sfw_f128_div(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+sfw_float128_t sfw_f128_rem(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+sfw_float128_t sfw_f128_mulAdd(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b, sfw_float128_t c);
+sfw_float128_t sfw_f128_sqrt(sfw_context *ctx, sfw_float128_t a);
+sfw_float128_t sfw_f128_roundToInt(sfw_context *ctx, sfw_float128_t a);
+bool sfw_f128_eq(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+bool sfw_f128_lt(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+bool sfw_f128_le(sfw_context *ctx, sfw_float128_t a, sfw_float128_t b);
+uint32_t sfw_f128_to_ui32(sfw_context *ctx, sfw_float128_t a, bool exact);
+uint64_t sfw_f128_to_ui64(sfw_context *ctx, sfw_float128_t a, bool exact);
+int32_t sfw_f128_to_i32(sfw_context *ctx, sfw_float128_t a, bool exact);
+int64_t sfw_f128_to_i64(sfw_context *ctx, sfw_float128_t a, bool exact);
+sfw_float128_t sfw_ui32_to_f128(sfw_context *ctx, uint32_t a);
+sfw_float128_t sfw_ui64_to_f128(sfw_context *ctx, uint64_t a);
+sfw_float128_t sfw_i32_to_f128(sfw_context *ctx, int32_t a);
+sfw_float128_t sfw_i64_to_f128(sfw_context *ctx, int64_t a);
+uint16_t sfw_f128_to_f16(sfw_context *ctx, sfw_float128_t a);
+uint32_t sfw_f128_to_f32(sfw_context *ctx, sfw_float128_t a);
+uint64_t sfw_f128_to_f64(sfw_context *ctx, sfw_float128_t a);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
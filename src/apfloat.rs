@@ -0,0 +1,120 @@
+//! [rustc_apfloat](https://crates.io/crates/rustc_apfloat) interop
+//!
+//! The float types convert to and from the `IeeeFloat` type of the same format through
+//! their bit patterns, so every value, NaN payloads included, is preserved. `Status` and
+//! `Round` convert to and from `ExceptionFlags` and `RoundingMode`.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{ExceptionFlags, RoundingMode, F16, F32, F64};
+#[cfg(feature = "f128")]
+use ::rustc_apfloat::ieee::Quad;
+use ::rustc_apfloat::ieee::{Double, Half, Single};
+use ::rustc_apfloat::{Float, Round, Status};
+
+macro_rules! impl_apfloat {
+    ($ty:ty, $ieee:ty, $payload:ty) => {
+        impl From<$ty> for $ieee {
+            fn from(x: $ty) -> Self {
+                <$ieee>::from_bits(x.to_bits() as u128)
+            }
+        }
+
+        impl From<$ieee> for $ty {
+            fn from(x: $ieee) -> Self {
+                <$ty>::from_bits(x.to_bits() as $payload)
+            }
+        }
+    };
+}
+
+impl_apfloat!(F16, Half, u16);
+impl_apfloat!(F32, Single, u32);
+impl_apfloat!(F64, Double, u64);
+#[cfg(feature = "f128")]
+impl_apfloat!(F128, Quad, u128);
+
+const STATUS_FLAGS: [(Status, u8); 5] = [
+    (Status::INVALID_OP, ExceptionFlags::FLAG_INVALID),
+    (Status::DIV_BY_ZERO, ExceptionFlags::FLAG_INFINITE),
+    (Status::OVERFLOW, ExceptionFlags::FLAG_OVERFLOW),
+    (Status::UNDERFLOW, ExceptionFlags::FLAG_UNDERFLOW),
+    (Status::INEXACT, ExceptionFlags::FLAG_INEXACT),
+];
+
+impl From<Status> for ExceptionFlags {
+    fn from(x: Status) -> Self {
+        let mut ret = 0;
+        for (status, flag) in STATUS_FLAGS {
+            if x.contains(status) {
+                ret |= flag;
+            }
+        }
+        ExceptionFlags::from_bits(ret)
+    }
+}
+
+impl From<ExceptionFlags> for Status {
+    fn from(x: ExceptionFlags) -> Self {
+        let mut ret = Status::OK;
+        for (status, flag) in STATUS_FLAGS {
+            if x.to_bits() & flag != 0 {
+                ret |= status;
+            }
+        }
+        ret
+    }
+}
+
+impl From<Round> for RoundingMode {
+    fn from(x: Round) -> Self {
+        match x {
+            Round::NearestTiesToEven => RoundingMode::TiesToEven,
+            Round::TowardPositive => RoundingMode::TowardPositive,
+            Round::TowardNegative => RoundingMode::TowardNegative,
+            Round::TowardZero => RoundingMode::TowardZero,
+            Round::NearestTiesToAway => RoundingMode::TiesToAway,
+        }
+    }
+}
+
+impl From<RoundingMode> for Round {
+    fn from(x: RoundingMode) -> Self {
+        match x {
+            RoundingMode::TiesToEven => Round::NearestTiesToEven,
+            RoundingMode::TowardPositive => Round::TowardPositive,
+            RoundingMode::TowardNegative => Round::TowardNegative,
+            RoundingMode::TowardZero => Round::TowardZero,
+            RoundingMode::TiesToAway => Round::NearestTiesToAway,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apfloat_convert() {
+        let a = F32::from_bits(0x7fc00001);
+        let b: Single = a.into();
+        assert_eq!(b.to_bits(), 0x7fc00001);
+        let c: F32 = b.into();
+        assert_eq!(c.to_bits(), 0x7fc00001);
+
+        let a: F16 = Half::from_bits(0x3c00).into();
+        assert_eq!(a.to_bits(), 0x3c00);
+
+        let flags: ExceptionFlags = (Status::OVERFLOW | Status::INEXACT).into();
+        assert!(flags.is_overflow() && flags.is_inexact() && !flags.is_invalid());
+        let status: Status = flags.into();
+        assert_eq!(status, Status::OVERFLOW | Status::INEXACT);
+
+        let rnd: RoundingMode = Round::TowardZero.into();
+        assert!(matches!(rnd, RoundingMode::TowardZero));
+        assert_eq!(
+            Round::from(RoundingMode::TiesToAway),
+            Round::NearestTiesToAway
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! [arbitrary](https://crates.io/crates/arbitrary) support
+//!
+//! Float types are built from raw bit patterns, so fuzzers reach NaN payloads, subnormals
+//! and signed zeros as easily as any other value.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{ExceptionFlags, RoundingMode, F16, F32, F64};
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+macro_rules! impl_arbitrary {
+    ($ty:ty, $payload:ty) => {
+        impl<'a> Arbitrary<'a> for $ty {
+            fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+                Ok(Self::from_bits(<$payload>::arbitrary(u)?))
+            }
+
+            fn size_hint(depth: usize) -> (usize, Option<usize>) {
+                <$payload>::size_hint(depth)
+            }
+        }
+    };
+}
+
+impl_arbitrary!(F16, u16);
+impl_arbitrary!(F32, u32);
+impl_arbitrary!(F64, u64);
+#[cfg(feature = "f128")]
+impl_arbitrary!(F128, u128);
+
+impl<'a> Arbitrary<'a> for RoundingMode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[
+            RoundingMode::TiesToEven,
+            RoundingMode::TowardZero,
+            RoundingMode::TowardNegative,
+            RoundingMode::TowardPositive,
+            RoundingMode::TiesToAway,
+        ])?)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u32::size_hint(depth)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ExceptionFlags {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ExceptionFlags::from_bits(
+            u8::arbitrary(u)? & ExceptionFlags::FLAG_ALL,
+        ))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u8::size_hint(depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arbitrary_from_bytes() {
+        let data = [
+            0x00, 0x3c, 0x00, 0x00, 0xc0, 0x7f, 0xff, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut u = Unstructured::new(&data);
+        assert_eq!(F16::arbitrary(&mut u).unwrap().to_bits(), 0x3c00);
+        assert_eq!(F32::arbitrary(&mut u).unwrap().to_bits(), 0x7fc00000);
+        assert_eq!(ExceptionFlags::arbitrary(&mut u).unwrap().to_bits(), 0x1f);
+        assert!(matches!(
+            RoundingMode::arbitrary(&mut u).unwrap(),
+            RoundingMode::TiesToEven
+        ));
+    }
+}
@@ -0,0 +1,633 @@
+//! AArch64 floating-point instructions
+//!
+//! [`Fpu`] holds the `FPCR` and `FPSR` registers, and runs the scalar data-processing
+//! instructions the way AArch64 does under them: rounding per `FPCR.RMode`, denormal inputs
+//! and tiny results flushed to zero under `FPCR.FZ` (or `FPCR.FZ16` for half precision), NaN
+//! results replaced by the default NaN under `FPCR.DN`, and the alternative half-precision
+//! format under `FPCR.AHP`. Exceptions accumulate in the cumulative bits of `FPSR`; the
+//! exception flags of the thread are left as they were. Underflow is detected before
+//! rounding, as the architecture does.
+//!
+//! Trapped exceptions, enabled by the `xxE` bits of `FPCR`, are not modeled.
+//!
+//! ```
+//! use softfloat_wrapper::arm::{Fpu, FPCR_FZ, FPSR_IDC};
+//! use softfloat_wrapper::F32;
+//!
+//! let mut fpu = Fpu::new(FPCR_FZ);
+//! let denormal = F32::from_bits(0x00000001);
+//! assert_eq!(fpu.fadd(denormal, denormal).to_bits(), 0);
+//! assert_eq!(fpu.fpsr, FPSR_IDC);
+//! ```
+
+use crate::{
+    with_flags, ExceptionFlags, FloatBits, FloatCompare, FloatConvert, RoundingMode, SoftFloat,
+    F16, F32,
+};
+use num_traits::{NumCast, One, ToPrimitive, Zero};
+
+/// alternative half-precision control bit of `FPCR`
+pub const FPCR_AHP: u32 = 1 << 26;
+/// default NaN mode control bit of `FPCR`
+pub const FPCR_DN: u32 = 1 << 25;
+/// flush-to-zero mode control bit of `FPCR`
+pub const FPCR_FZ: u32 = 1 << 24;
+/// flush-to-zero mode control bit of `FPCR` for half precision
+pub const FPCR_FZ16: u32 = 1 << 19;
+
+/// invalid operation cumulative bit of `FPSR`
+pub const FPSR_IOC: u32 = 1 << 0;
+/// division by zero cumulative bit of `FPSR`
+pub const FPSR_DZC: u32 = 1 << 1;
+/// overflow cumulative bit of `FPSR`
+pub const FPSR_OFC: u32 = 1 << 2;
+/// underflow cumulative bit of `FPSR`
+pub const FPSR_UFC: u32 = 1 << 3;
+/// inexact cumulative bit of `FPSR`
+pub const FPSR_IXC: u32 = 1 << 4;
+/// input denormal cumulative bit of `FPSR`
+pub const FPSR_IDC: u32 = 1 << 7;
+
+fn is_half<T: SoftFloat>() -> bool {
+    T::EXPONENT_BITS == 5
+}
+
+/// `x` with its quiet bit set
+fn quieten<T: SoftFloat + Copy>(x: T) -> T {
+    let mut ret = x;
+    ret.set_mantissa(x.mantissa() | T::quiet_nan().mantissa());
+    ret
+}
+
+/// signed zero with the sign of `x`
+fn zero_like<T: SoftFloat>(x: &T) -> T {
+    let mut ret = T::zero();
+    ret.set_sign(x.sign());
+    ret
+}
+
+/// the NaN `x` converted to `U`, keeping its sign and the top bits of its payload
+fn convert_nan<T: SoftFloat, U: SoftFloat>(x: &T) -> U {
+    let frac = x.mantissa().to_u128().unwrap();
+    let frac = if U::MANTISSA_BITS > T::MANTISSA_BITS {
+        frac << (U::MANTISSA_BITS - T::MANTISSA_BITS)
+    } else {
+        frac >> (T::MANTISSA_BITS - U::MANTISSA_BITS)
+    };
+    let mut ret = U::quiet_nan();
+    ret.set_mantissa(ret.mantissa() | <U::Payload as NumCast>::from(frac).unwrap());
+    ret.set_sign(if x.is_negative() {
+        U::Payload::one()
+    } else {
+        U::Payload::zero()
+    });
+    ret
+}
+
+/// the alternative half-precision value `x`, whose largest exponent is a normal one
+fn from_ahp(x: F16) -> F32 {
+    let bits = x.to_bits() as u32;
+    if x.exponent() != 0x1f {
+        return FloatConvert::to_f32(&x, RoundingMode::TiesToEven);
+    }
+    F32::from_bits((bits & 0x8000) << 16 | (0x1f - 15 + 127) << 23 | (bits & 0x3ff) << 13)
+}
+
+/// The floating-point registers of AArch64 which control and report the exceptions.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fpu {
+    /// floating-point control register
+    pub fpcr: u32,
+    /// floating-point status register
+    pub fpsr: u32,
+}
+
+impl Fpu {
+    /// Returns an `Fpu` with `fpcr`, and no exception raised yet.
+    pub fn new(fpcr: u32) -> Self {
+        Self { fpcr, fpsr: 0 }
+    }
+
+    /// Returns the rounding mode of `FPCR.RMode`.
+    pub fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::from_arm_rmode((self.fpcr >> 22) as u8 & 3).unwrap()
+    }
+
+    fn flush_to_zero<T: SoftFloat>(&self) -> bool {
+        let bit = if is_half::<T>() { FPCR_FZ16 } else { FPCR_FZ };
+        self.fpcr & bit != 0
+    }
+
+    fn default_nan(&self) -> bool {
+        self.fpcr & FPCR_DN != 0
+    }
+
+    fn raise(&mut self, flags: ExceptionFlags) {
+        let bits = [
+            (flags.is_invalid(), FPSR_IOC),
+            (flags.is_infinite(), FPSR_DZC),
+            (flags.is_overflow(), FPSR_OFC),
+            (flags.is_underflow(), FPSR_UFC),
+            (flags.is_inexact(), FPSR_IXC),
+        ];
+        for (raised, bit) in bits {
+            if raised {
+                self.fpsr |= bit;
+            }
+        }
+    }
+
+    /// `x`, flushed to zero if it is denormal and `flush`
+    fn input<T: SoftFloat + Copy>(&mut self, x: T, flush: bool) -> T {
+        if flush && x.is_subnormal() {
+            self.fpsr |= FPSR_IDC;
+            zero_like(&x)
+        } else {
+            x
+        }
+    }
+
+    fn inputs<T: SoftFloat + Copy, const N: usize>(&mut self, ops: [T; N]) -> [T; N] {
+        let flush = self.flush_to_zero::<T>();
+        ops.map(|x| self.input(x, flush))
+    }
+
+    /// The NaN result of an operation on `ops` if one of them is a NaN: the first signaling
+    /// one, else the first quiet one.
+    fn nans<T: SoftFloat + Copy>(&mut self, ops: &[T]) -> Option<T> {
+        let nan = ops
+            .iter()
+            .find(|x| x.is_signaling_nan())
+            .or_else(|| ops.iter().find(|x| x.is_nan()))?;
+        if nan.is_signaling_nan() {
+            self.fpsr |= FPSR_IOC;
+        }
+        Some(if self.default_nan() {
+            T::quiet_nan()
+        } else {
+            quieten(*nan)
+        })
+    }
+
+    /// Runs `f` in the rounding mode of `FPCR`, raising its exceptions, and flushes its
+    /// result to zero if it is tiny and `flush`.
+    fn round<T: SoftFloat + Copy>(&mut self, flush: bool, f: impl Fn(RoundingMode) -> T) -> T {
+        let (mut ret, mut flags) = with_flags(|| f(self.rounding_mode()));
+        if ret.is_nan() {
+            // only invalid operations make NaNs from numbers
+            ret = T::quiet_nan();
+        } else if ret.is_subnormal() || flags.is_inexact() && ret.exponent() <= T::Payload::one() {
+            // the result is tiny before rounding if it is tiny when rounded toward zero
+            let (rtz, rtz_flags) = with_flags(|| f(RoundingMode::TowardZero));
+            let tiny = rtz.is_subnormal() || rtz.is_zero() && rtz_flags.is_inexact();
+            let underflow = ExceptionFlags::from_bits(ExceptionFlags::FLAG_UNDERFLOW);
+            if tiny && flush {
+                ret = zero_like(&rtz);
+                flags = ExceptionFlags::from_bits(
+                    flags.to_bits()
+                        & !(ExceptionFlags::FLAG_INEXACT | ExceptionFlags::FLAG_UNDERFLOW),
+                ) | underflow;
+            } else if tiny && flags.is_inexact() {
+                flags |= underflow;
+            }
+        }
+        self.raise(flags);
+        ret
+    }
+
+    /// Runs the arithmetic operation `f` on `ops`.
+    fn arith<T: SoftFloat + Copy, const N: usize>(
+        &mut self,
+        ops: [T; N],
+        f: impl Fn([T; N], RoundingMode) -> T,
+    ) -> T {
+        let ops = self.inputs(ops);
+        if let Some(nan) = self.nans(&ops) {
+            return nan;
+        }
+        let flush = self.flush_to_zero::<T>();
+        self.round(flush, |rnd| f(ops, rnd))
+    }
+
+    /// `FADD`: `a + b`
+    pub fn fadd<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.arith([a, b], |[a, b], rnd| a.add(b, rnd))
+    }
+
+    /// `FSUB`: `a - b`
+    pub fn fsub<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.arith([a, b], |[a, b], rnd| a.sub(b, rnd))
+    }
+
+    /// `FMUL`: `a * b`
+    pub fn fmul<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.arith([a, b], |[a, b], rnd| a.mul(b, rnd))
+    }
+
+    /// `FDIV`: `a / b`
+    pub fn fdiv<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.arith([a, b], |[a, b], rnd| a.div(b, rnd))
+    }
+
+    /// `FSQRT`
+    pub fn fsqrt<T: SoftFloat + Copy>(&mut self, a: T) -> T {
+        self.arith([a], |[a], rnd| a.sqrt(rnd))
+    }
+
+    /// `addend + op1 * op2` with the NaN handling of `FMADD`
+    fn mul_add<T: SoftFloat + Copy>(&mut self, addend: T, op1: T, op2: T) -> T {
+        let [addend, op1, op2] = self.inputs([addend, op1, op2]);
+        let nan = self.nans(&[addend, op1, op2]);
+        // `0 * inf` is invalid even when added to a quiet NaN
+        if addend.is_nan()
+            && !addend.is_signaling_nan()
+            && (op1.is_infinity() && op2.is_zero() || op1.is_zero() && op2.is_infinity())
+        {
+            self.fpsr |= FPSR_IOC;
+            return T::quiet_nan();
+        }
+        if let Some(nan) = nan {
+            return nan;
+        }
+        let flush = self.flush_to_zero::<T>();
+        self.round(flush, |rnd| op1.fused_mul_add(op2, addend, rnd))
+    }
+
+    /// `FMADD`: `a + n * m`
+    pub fn fmadd<T: SoftFloat + Copy>(&mut self, n: T, m: T, a: T) -> T {
+        self.mul_add(a, n, m)
+    }
+
+    /// `FMSUB`: `a - n * m`
+    pub fn fmsub<T: SoftFloat + Copy>(&mut self, n: T, m: T, a: T) -> T {
+        self.mul_add(a, n.neg(), m)
+    }
+
+    /// `FNMADD`: `-a - n * m`
+    pub fn fnmadd<T: SoftFloat + Copy>(&mut self, n: T, m: T, a: T) -> T {
+        self.mul_add(a.neg(), n.neg(), m)
+    }
+
+    /// `FNMSUB`: `-a + n * m`
+    pub fn fnmsub<T: SoftFloat + Copy>(&mut self, n: T, m: T, a: T) -> T {
+        self.mul_add(a.neg(), n, m)
+    }
+
+    /// `FMIN`, `FMAX`, `FMINNM` and `FMAXNM`
+    fn min_max<T: SoftFloat + Copy>(&mut self, a: T, b: T, max: bool, number: bool) -> T {
+        let [a, b] = self.inputs([a, b]);
+        // the NM variants prefer a number to a quiet NaN
+        if number && a.is_nan() != b.is_nan() {
+            if a.is_nan() && !a.is_signaling_nan() {
+                return b;
+            }
+            if b.is_nan() && !b.is_signaling_nan() {
+                return a;
+            }
+        }
+        if let Some(nan) = self.nans(&[a, b]) {
+            return nan;
+        }
+        // -0 is less than +0
+        let less = a.lt_quiet(b) || FloatCompare::eq(&a, b) && a.is_negative();
+        if less != max {
+            a
+        } else {
+            b
+        }
+    }
+
+    /// `FMIN`, which returns a NaN if either operand is one
+    pub fn fmin<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.min_max(a, b, false, false)
+    }
+
+    /// `FMAX`, which returns a NaN if either operand is one
+    pub fn fmax<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.min_max(a, b, true, false)
+    }
+
+    /// `FMINNM`, which returns the other operand if one is a quiet NaN
+    pub fn fminnm<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.min_max(a, b, false, true)
+    }
+
+    /// `FMAXNM`, which returns the other operand if one is a quiet NaN
+    pub fn fmaxnm<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        self.min_max(a, b, true, true)
+    }
+
+    fn compare<T: SoftFloat + Copy>(&mut self, a: T, b: T, signaling: bool) -> u8 {
+        let [a, b] = self.inputs([a, b]);
+        if a.is_nan() || b.is_nan() {
+            if signaling || a.is_signaling_nan() || b.is_signaling_nan() {
+                self.fpsr |= FPSR_IOC;
+            }
+            0b0011
+        } else if FloatCompare::eq(&a, b) {
+            0b0110
+        } else if a.lt_quiet(b) {
+            0b1000
+        } else {
+            0b0010
+        }
+    }
+
+    /// `FCMP`: the `NZCV` flags of comparing `a` with `b`, raising invalid for signaling
+    /// NaNs only
+    pub fn fcmp<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> u8 {
+        self.compare(a, b, false)
+    }
+
+    /// `FCMPE`: the `NZCV` flags of comparing `a` with `b`, raising invalid for any NaN
+    pub fn fcmpe<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> u8 {
+        self.compare(a, b, true)
+    }
+
+    /// `FRINTN`, `FRINTA`, `FRINTP`, `FRINTM` and `FRINTZ`: `a` rounded to an integral value
+    /// per `rnd`, without raising inexact
+    pub fn frint<T: SoftFloat + Copy>(&mut self, a: T, rnd: RoundingMode) -> T {
+        self.arith([a], |[a], _| a.round_to_integral(rnd))
+    }
+
+    /// `FRINTX`: `a` rounded to an integral value in the rounding mode of `FPCR`, raising
+    /// inexact if it changes
+    pub fn frintx<T: SoftFloat + Copy>(&mut self, a: T) -> T {
+        let rnd = self.rounding_mode();
+        let [a] = self.inputs([a]);
+        let ret = self.frint(a, rnd);
+        if a.is_finite() && !FloatCompare::eq(&ret, a) {
+            self.fpsr |= FPSR_IXC;
+        }
+        ret
+    }
+
+    /// `FCVT`: `a` converted to another precision. Half precision is the alternative format
+    /// under `FPCR.AHP`, which has no infinities or NaNs.
+    pub fn fcvt<T: SoftFloat + Copy, U: SoftFloat + Copy>(&mut self, a: T) -> U {
+        let ahp = self.fpcr & FPCR_AHP != 0;
+        // FZ16 does not apply to conversions
+        let fz = self.fpcr & FPCR_FZ != 0;
+        let a = self.input(a, fz && !is_half::<T>());
+        if ahp && is_half::<T>() {
+            let a = F16::from_bits(a.to_bits().to_u16().unwrap());
+            return from_ahp(a).convert(RoundingMode::TiesToEven);
+        }
+        if ahp && is_half::<U>() {
+            let ret = self.convert_ahp(a);
+            return U::from_bits(<U::Payload as NumCast>::from(ret.to_bits()).unwrap());
+        }
+        if a.is_nan() {
+            if a.is_signaling_nan() {
+                self.fpsr |= FPSR_IOC;
+            }
+            return if self.default_nan() {
+                U::quiet_nan()
+            } else {
+                convert_nan(&a)
+            };
+        }
+        self.round(fz && !is_half::<U>(), |rnd| a.convert::<U>(rnd))
+    }
+
+    /// `a` converted to alternative half precision
+    fn convert_ahp<T: SoftFloat + Copy>(&mut self, a: T) -> F16 {
+        let mut saturated = F16::from_bits(0x7fff);
+        saturated.set_sign(a.sign().to_u16().unwrap());
+        if a.is_nan() {
+            self.fpsr |= FPSR_IOC;
+            return zero_like(&saturated);
+        }
+        if a.is_infinity() {
+            self.fpsr |= FPSR_IOC;
+            return saturated;
+        }
+        let (ret, flags) = with_flags(|| a.convert::<F16>(self.rounding_mode()));
+        if !flags.is_overflow() {
+            self.raise(flags);
+            return ret;
+        }
+        // round half of `a`, which is exact, and double it into the largest exponent
+        let two = T::from_i32(2, RoundingMode::TiesToEven);
+        let half = a.div(two, RoundingMode::TiesToEven);
+        let (ret, flags) = with_flags(|| half.convert::<F16>(self.rounding_mode()));
+        if flags.is_overflow() {
+            self.fpsr |= FPSR_IOC;
+            return saturated;
+        }
+        self.raise(flags);
+        F16::from_bits(ret.to_bits() + 0x400)
+    }
+
+    /// Converts `a` to an integer by `f`, saturating to `min` or `max` if it is out of range.
+    /// NaNs convert to 0.
+    fn convert_int<T: SoftFloat + Copy, I: Zero>(
+        &mut self,
+        a: T,
+        f: impl FnOnce(T) -> I,
+        min: I,
+        max: I,
+    ) -> I {
+        let fz = self.fpcr & FPCR_FZ != 0;
+        let a = self.input(a, fz && !is_half::<T>());
+        if a.is_nan() {
+            self.fpsr |= FPSR_IOC;
+            return I::zero();
+        }
+        let (ret, flags) = with_flags(|| f(a));
+        self.raise(flags);
+        if !flags.is_invalid() {
+            ret
+        } else if a.is_negative() {
+            min
+        } else {
+            max
+        }
+    }
+
+    /// `FCVTZS`, `FCVTNS`, `FCVTAS`, `FCVTPS` and `FCVTMS` to a 32-bit register: `a`
+    /// rounded per `rnd`
+    pub fn fcvt_i32<T: SoftFloat + Copy>(&mut self, a: T, rnd: RoundingMode) -> i32 {
+        self.convert_int(a, |a| a.to_i32(rnd, true), i32::MIN, i32::MAX)
+    }
+
+    /// `FCVTZS`, `FCVTNS`, `FCVTAS`, `FCVTPS` and `FCVTMS` to a 64-bit register: `a`
+    /// rounded per `rnd`
+    pub fn fcvt_i64<T: SoftFloat + Copy>(&mut self, a: T, rnd: RoundingMode) -> i64 {
+        self.convert_int(a, |a| a.to_i64(rnd, true), i64::MIN, i64::MAX)
+    }
+
+    /// `FCVTZU`, `FCVTNU`, `FCVTAU`, `FCVTPU` and `FCVTMU` to a 32-bit register: `a`
+    /// rounded per `rnd`
+    pub fn fcvt_u32<T: SoftFloat + Copy>(&mut self, a: T, rnd: RoundingMode) -> u32 {
+        self.convert_int(a, |a| a.to_u32(rnd, true), 0, u32::MAX)
+    }
+
+    /// `FCVTZU`, `FCVTNU`, `FCVTAU`, `FCVTPU` and `FCVTMU` to a 64-bit register: `a`
+    /// rounded per `rnd`
+    pub fn fcvt_u64<T: SoftFloat + Copy>(&mut self, a: T, rnd: RoundingMode) -> u64 {
+        self.convert_int(a, |a| a.to_u64(rnd, true), 0, u64::MAX)
+    }
+
+    /// `SCVTF`: the signed integer `a` in the rounding mode of `FPCR`
+    pub fn scvtf<T: SoftFloat + Copy>(&mut self, a: i64) -> T {
+        self.round(false, |rnd| T::from_i64(a, rnd))
+    }
+
+    /// `UCVTF`: the unsigned integer `a` in the rounding mode of `FPCR`
+    pub fn ucvtf<T: SoftFloat + Copy>(&mut self, a: u64) -> T {
+        self.round(false, |rnd| T::from_u64(a, rnd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, F64};
+
+    const ONE: F32 = F32::from_bits(0x3f800000);
+    const QNAN: F32 = F32::from_bits(0xffc12345);
+    const SNAN: F32 = F32::from_bits(0x7fa00001);
+
+    #[test]
+    fn arm_nan() {
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fadd(QNAN, SNAN).to_bits(), 0x7fe00001);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmul(QNAN, ONE).to_bits(), 0xffc12345);
+        assert_eq!(fpu.fpsr, 0);
+        let mut fpu = Fpu::new(FPCR_DN);
+        assert_eq!(fpu.fadd(QNAN, SNAN).to_bits(), 0x7fc00000);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+
+        // invalid operations give the default NaN, which is positive
+        let mut fpu = Fpu::default();
+        let zero = F32::from_bits(0);
+        assert_eq!(fpu.fmul(zero, F32::infinity()).to_bits(), 0x7fc00000);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        let mut fpu = Fpu::default();
+        let ret = fpu.fmadd(F32::infinity(), zero, QNAN);
+        assert_eq!(ret.to_bits(), 0x7fc00000);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        // the negated operand is propagated
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fnmadd(ONE, ONE, QNAN).to_bits(), 0x7fc12345);
+        assert_eq!(
+            fpu.fmsub(ONE, ONE, F32::from_bits(0x40000000)).to_bits(),
+            0x3f800000
+        );
+    }
+
+    #[test]
+    fn arm_flush_to_zero() {
+        let min = F32::from_bits(0x00800000);
+        let mut fpu = Fpu::default();
+        assert_eq!(
+            fpu.fmul(min, F32::from_bits(0x3f000000)).to_bits(),
+            0x00400000
+        );
+        assert_eq!(fpu.fpsr, 0);
+        let mut fpu = Fpu::new(FPCR_FZ);
+        let ret = fpu.fmul(min.neg(), F32::from_bits(0x3f000000));
+        assert_eq!(ret.to_bits(), 0x80000000);
+        assert_eq!(fpu.fpsr, FPSR_UFC);
+
+        // tininess is detected before rounding
+        let a = F32::from_bits(0x3f7fffff);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmul(min, a).to_bits(), 0x00800000);
+        assert_eq!(fpu.fpsr, FPSR_UFC | FPSR_IXC);
+        let mut fpu = Fpu::new(FPCR_FZ);
+        assert_eq!(fpu.fmul(min, a).to_bits(), 0);
+        assert_eq!(fpu.fpsr, FPSR_UFC);
+
+        // FZ16 flushes half precision only
+        let denormal = F16::from_bits(0x0001);
+        let mut fpu = Fpu::new(FPCR_FZ);
+        assert_eq!(fpu.fadd(denormal, denormal).to_bits(), 0x0002);
+        assert_eq!(fpu.fpsr, 0);
+        fpu.fpcr = FPCR_FZ16;
+        assert_eq!(fpu.fadd(denormal, denormal).to_bits(), 0);
+        assert_eq!(fpu.fpsr, FPSR_IDC);
+    }
+
+    #[test]
+    fn arm_min_max_compare() {
+        let mut fpu = Fpu::default();
+        let zero = F64::from_bits(0);
+        assert_eq!(fpu.fmin(zero.neg(), zero).to_bits(), 0x8000000000000000);
+        assert_eq!(fpu.fmax(zero.neg(), zero).to_bits(), 0);
+        assert_eq!(fpu.fminnm(QNAN, ONE).to_bits(), ONE.to_bits());
+        assert_eq!(fpu.fmin(QNAN, ONE).to_bits(), QNAN.to_bits());
+        assert_eq!(fpu.fcmp(ONE, F32::from_bits(0x40000000)), 0b1000);
+        assert_eq!(fpu.fcmp(ONE, ONE), 0b0110);
+        assert_eq!(fpu.fcmp(ONE, ONE.neg()), 0b0010);
+        assert_eq!(fpu.fcmp(QNAN, ONE), 0b0011);
+        assert_eq!(fpu.fpsr, 0);
+        assert_eq!(fpu.fmaxnm(ONE, SNAN).to_bits(), 0x7fe00001);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcmpe(QNAN, ONE), 0b0011);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+    }
+
+    #[test]
+    fn arm_rounding() {
+        let mut fpu = Fpu::new(3 << 22);
+        assert!(matches!(fpu.rounding_mode(), RoundingMode::TowardZero));
+        let ret = fpu.fdiv(ONE, F32::from_bits(0x40400000));
+        assert_eq!(ret.to_bits(), 0x3eaaaaaa);
+        assert_eq!(fpu.fpsr, FPSR_IXC);
+
+        let a = F32::from_bits(0x3fc00000); // 1.5
+        let mut fpu = Fpu::default();
+        assert_eq!(
+            fpu.frint(a, RoundingMode::TowardZero).to_bits(),
+            ONE.to_bits()
+        );
+        assert_eq!(fpu.fpsr, 0);
+        assert_eq!(fpu.frintx(a).to_bits(), 0x40000000);
+        assert_eq!(fpu.fpsr, FPSR_IXC);
+
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcvt_i32(QNAN, RoundingMode::TowardZero), 0);
+        assert_eq!(
+            fpu.fcvt_u64(F32::infinity().neg(), RoundingMode::TowardZero),
+            0
+        );
+        assert_eq!(
+            fpu.fcvt_i64(F32::infinity(), RoundingMode::TowardZero),
+            i64::MAX
+        );
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcvt_i32(a.neg(), RoundingMode::TiesToAway), -2);
+        assert_eq!(fpu.fpsr, FPSR_IXC);
+        assert_eq!(fpu.scvtf::<F64>(-1).to_bits(), 0xbff0000000000000);
+    }
+
+    #[test]
+    fn arm_convert() {
+        let mut fpu = Fpu::default();
+        let nan = F64::from_bits(0xfff4000000000000);
+        assert_eq!(fpu.fcvt::<F64, F32>(nan).to_bits(), 0xffe00000);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+        let mut fpu = Fpu::default();
+        let a = F32::from_bits(0x4788b800); // 70000
+        assert_eq!(fpu.fcvt::<F32, F16>(a).to_bits(), 0x7c00);
+        assert_eq!(fpu.fpsr, FPSR_OFC | FPSR_IXC);
+
+        let mut fpu = Fpu::new(FPCR_AHP);
+        let ret = fpu.fcvt::<F32, F16>(a);
+        assert_eq!(ret.to_bits(), 0x7c46);
+        assert_eq!(fpu.fpsr, FPSR_IXC);
+        assert_eq!(fpu.fcvt::<F16, F32>(ret).to_bits(), 0x4788c000);
+        let mut fpu = Fpu::new(FPCR_AHP);
+        let ret = fpu.fcvt::<F32, F16>(F32::from_bits(0xc8435000)); // -200000
+        assert_eq!(ret.to_bits(), 0xffff);
+        assert_eq!(fpu.fcvt::<F64, F16>(F64::infinity()).to_bits(), 0x7fff);
+        assert_eq!(fpu.fcvt::<F32, F16>(QNAN).to_bits(), 0x8000);
+        assert_eq!(fpu.fpsr, FPSR_IOC);
+    }
+}
@@ -0,0 +1,260 @@
+//! Pluggable backends
+//!
+//! Every softfloat function the float types call, such as `f32_add` or `f64_to_i32`, is
+//! routed through the [`Backend`] set for the thread with [`set_backend`] or
+//! [`with_backend`]. A backend gets the [`Call`], and returns the bits of the result with
+//! the flags it raises, or `None` to run the implementation the crate is built with, which
+//! the `pure-rust` feature selects. Operations run by the backend itself use the built-in
+//! implementation, so an instrumented backend can compute its results with the float
+//! types, and only raise the flags it returns.
+//!
+//! The result goes through the default NaN mode of the thread, and is then reported to the
+//! trace hook, statistics and trap handlers like a built-in one.
+//!
+//! Conversions which this crate rounds itself rather than with a softfloat function, such
+//! as [`to_fixed`](crate::SoftFloat::to_fixed) or [`convert`](crate::SoftFloat::convert) to
+//! formats other than the four of IEEE 754, always run built-in.
+//!
+//! ```
+//! use softfloat_wrapper::backend::{with_backend, Call};
+//! use softfloat_wrapper::{ExceptionFlags, FloatArith, FloatBits, RoundingMode, F32};
+//!
+//! // flush subnormal results of f32_mul to zero
+//! let ftz = |call: &Call| {
+//!     if call.op != "f32_mul" {
+//!         return None;
+//!     }
+//!     let [a, b] = [0, 1].map(|i| F32::from_bits(call.operands()[i] as u32));
+//!     let ret = a.mul(b, call.rnd.unwrap());
+//!     if ret.is_subnormal() {
+//!         Some((ret.to_bits() as u128 & 0x8000_0000, ExceptionFlags::from_bits(0x03)))
+//!     } else {
+//!         None
+//!     }
+//! };
+//! let min = F32::from_bits(0x00800000);
+//! let half = F32::from_bits(0x3f000000);
+//! let ret = with_backend(ftz, || min.mul(half, RoundingMode::TiesToEven));
+//! assert_eq!(ret.to_bits(), 0);
+//! ```
+
+use crate::trace::TraceResult;
+use crate::{with_flags, ExceptionFlags, RoundingMode};
+use std::cell::Cell;
+
+/// A softfloat function call routed to a [`Backend`]
+#[derive(Copy, Clone, Debug)]
+pub struct Call<'a> {
+    /// name of the softfloat function, such as `f32_add`
+    pub op: &'static str,
+    operands: &'a [u128],
+    /// rounding mode, `None` for comparisons
+    pub rnd: Option<RoundingMode>,
+    /// whether a conversion to an integer raises inexact
+    pub exact: bool,
+}
+
+impl<'a> Call<'a> {
+    /// Returns the operands, as the bits of floats, or of integers in two's complement.
+    pub fn operands(&self) -> &'a [u128] {
+        self.operands
+    }
+}
+
+/// An implementation of the softfloat functions
+pub trait Backend {
+    /// Runs `call`, and returns the bits of its result, as 0 or 1 for comparisons and in
+    /// two's complement for signed integers, with the flags it raises. Returns `None` to
+    /// run the built-in implementation instead.
+    fn run(&self, call: &Call<'_>) -> Option<(u128, ExceptionFlags)>;
+}
+
+impl<F: Fn(&Call<'_>) -> Option<(u128, ExceptionFlags)>> Backend for F {
+    fn run(&self, call: &Call<'_>) -> Option<(u128, ExceptionFlags)> {
+        self(call)
+    }
+}
+
+/// The implementation the crate is built with
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Builtin;
+
+impl Backend for Builtin {
+    fn run(&self, _call: &Call<'_>) -> Option<(u128, ExceptionFlags)> {
+        None
+    }
+}
+
+thread_local! {
+    static BACKEND: Cell<Option<Box<dyn Backend>>> = const { Cell::new(None) };
+}
+
+/// Routes the operations run by this thread to `backend`, replacing the previous one.
+pub fn set_backend(backend: impl Backend + 'static) {
+    BACKEND.with(|x| x.set(Some(Box::new(backend))));
+}
+
+/// Runs the operations of this thread in the built-in implementation again.
+pub fn clear_backend() {
+    BACKEND.with(|x| x.set(None));
+}
+
+/// Returns whether a backend is set for this thread.
+pub(crate) fn has_backend() -> bool {
+    BACKEND.with(|x| {
+        let backend = x.take();
+        let ret = backend.is_some();
+        x.set(backend);
+        ret
+    })
+}
+
+/// Runs `f` with its operations routed to `backend`, and restores the previous backend, even
+/// if `f` panics.
+pub fn with_backend<R>(backend: impl Backend + 'static, f: impl FnOnce() -> R) -> R {
+    let _outer = Outer(BACKEND.with(|x| x.replace(Some(Box::new(backend)))));
+    f()
+}
+
+/// restores the backend of the caller when dropped, even by a panic
+struct Outer(Option<Box<dyn Backend>>);
+
+impl Drop for Outer {
+    fn drop(&mut self) {
+        BACKEND.with(|x| x.set(self.0.take()));
+    }
+}
+
+/// Takes the backend out while it runs, and puts it back when dropped, even by a panic,
+/// unless another one was set meanwhile.
+struct TakenBackend(Option<Box<dyn Backend>>);
+
+impl Drop for TakenBackend {
+    fn drop(&mut self) {
+        if let Some(backend) = self.0.take() {
+            BACKEND.with(|x| {
+                let inner = x.replace(Some(backend));
+                if inner.is_some() {
+                    x.set(inner);
+                }
+            });
+        }
+    }
+}
+
+/// Runs `call` in the backend of the thread, or `f` if there is none or it declines.
+pub(crate) fn routed<R: TraceResult>(
+    op: &'static str,
+    rnd: Option<RoundingMode>,
+    exact: bool,
+    operands: &[u128],
+    f: impl FnOnce() -> R,
+) -> R {
+    // the backend is taken out while it runs, so that its own operations are built-in
+    let taken = TakenBackend(BACKEND.with(|x| x.take()));
+    let backend = match &taken.0 {
+        Some(x) => x,
+        None => return f(),
+    };
+    let call = Call {
+        op,
+        operands,
+        rnd,
+        exact,
+    };
+    // only the flags the backend returns are raised, not those of its own operations
+    let (ret, _) = with_flags(|| backend.run(&call));
+    drop(taken);
+    match ret {
+        Some((bits, flags)) => {
+            flags.raise();
+            R::from_trace_bits(bits)
+        }
+        None => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatCompare, FloatConvert, F32, F64};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn backend_routing() {
+        let rnd = RoundingMode::TowardZero;
+        let one = F64::from_bits(0x3ff0000000000000);
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let log = calls.clone();
+        let backend = move |call: &Call| {
+            log.borrow_mut()
+                .push((call.op, call.operands().to_vec(), call.rnd, call.exact));
+            // operations of the backend itself are built-in, and their flags are not raised
+            let two = one.add(one, RoundingMode::TiesToEven);
+            one.div(F64::from_bits(0), RoundingMode::TiesToEven);
+            match call.op {
+                "f64_add" => Some((two.to_bits() as u128 + 1, ExceptionFlags::from_bits(0x01))),
+                "f64_lt" => Some((1, ExceptionFlags::default())),
+                "f64_to_i32" => Some((-3i32 as u32 as u128, ExceptionFlags::default())),
+                _ => None,
+            }
+        };
+        let (ret, flags) = with_flags(|| {
+            with_backend(backend, || {
+                (
+                    one.add(one, rnd),
+                    one.lt(one),
+                    one.to_i32(rnd, true),
+                    one.mul(one, rnd),
+                )
+            })
+        });
+        assert_eq!(ret.0.to_bits(), 0x4000000000000001);
+        assert!(ret.1);
+        assert_eq!(ret.2, -3);
+        assert_eq!(ret.3.to_bits(), one.to_bits());
+        assert_eq!(flags.to_bits(), 0x01);
+
+        let calls = calls.take();
+        let ops: Vec<_> = calls.iter().map(|x| x.0).collect();
+        assert_eq!(ops, ["f64_add", "f64_lt", "f64_to_i32", "f64_mul"]);
+        assert_eq!(calls[0].1, [0x3ff0000000000000; 2]);
+        assert!(matches!(calls[0].2, Some(RoundingMode::TowardZero)));
+        assert!(calls[1].2.is_none());
+        assert!(calls[2].3);
+
+        // the previous backend is restored
+        let a = F32::from_bits(0x3f800000);
+        set_backend(|_: &Call| Some((0, ExceptionFlags::default())));
+        let ret = with_backend(Builtin, || a.add(a, rnd));
+        assert_eq!(ret.to_bits(), 0x40000000);
+        assert_eq!(a.add(a, rnd).to_bits(), 0);
+        clear_backend();
+        assert_eq!(a.add(a, rnd).to_bits(), 0x40000000);
+    }
+
+    #[test]
+    fn backend_panic() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F32::from_bits(0x3f800000);
+
+        // a panic in `f` restores the previous backend
+        set_backend(|_: &Call| Some((0, ExceptionFlags::default())));
+        let ret = std::panic::catch_unwind(|| {
+            with_backend(Builtin, || {
+                assert_eq!(a.add(a, rnd).to_bits(), 0x40000000);
+                panic!("with_backend");
+            })
+        });
+        assert!(ret.is_err());
+        assert_eq!(a.add(a, rnd).to_bits(), 0);
+
+        // and a panicking backend stays set
+        set_backend(|_: &Call| -> Option<(u128, ExceptionFlags)> { panic!("backend") });
+        assert!(std::panic::catch_unwind(|| a.add(a, rnd)).is_err());
+        assert!(std::panic::catch_unwind(|| a.add(a, rnd)).is_err());
+        clear_backend();
+        assert_eq!(a.add(a, rnd).to_bits(), 0x40000000);
+    }
+}
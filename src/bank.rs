@@ -0,0 +1,139 @@
+//! Exception flags of several emulated cores
+//!
+//! The exception flags of a thread stand for a single sticky register. An emulator of
+//! several harts or cores keeps the flags of each in a [`FlagBank`], and runs the operations
+//! of a core with [`FlagBank::run`], which accumulates the flags they raise in the register
+//! of that core and leaves those of the thread as they were. The registers are atomic, so a
+//! bank shared between threads, such as in an `Arc`, can run the cores concurrently.
+//!
+//! ```
+//! use softfloat_wrapper::{FlagBank, FloatArith, RoundingMode, F32};
+//!
+//! let fflags = FlagBank::new(2);
+//! let one = F32::from_bits(0x3f800000);
+//! fflags.run(0, || one.div(F32::from_bits(0x40400000), RoundingMode::TiesToEven));
+//! fflags.run(1, || one.div(F32::from_bits(0), RoundingMode::TiesToEven));
+//! assert_eq!(fflags.get(0).to_string(), "NX");
+//! assert_eq!(fflags.take(1).to_string(), "DZ");
+//! assert_eq!(fflags.get(1).to_string(), "");
+//! ```
+//!
+//! # Panics
+//!
+//! The methods taking an index panic if it is out of the bank.
+
+use crate::{with_flags, ExceptionFlags};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Sticky exception flag registers, one per emulated core
+#[derive(Debug)]
+pub struct FlagBank {
+    registers: Box<[AtomicU8]>,
+}
+
+impl FlagBank {
+    /// Creates `len` registers with no flags raised.
+    pub fn new(len: usize) -> Self {
+        Self {
+            registers: (0..len).map(|_| AtomicU8::new(0)).collect(),
+        }
+    }
+
+    /// Returns the number of registers.
+    pub fn len(&self) -> usize {
+        self.registers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+
+    /// Runs `f`, and raises the flags it raises in register `index` instead of the flags of
+    /// the thread.
+    pub fn run<R>(&self, index: usize, f: impl FnOnce() -> R) -> R {
+        let (ret, flags) = with_flags(f);
+        self.raise(index, flags);
+        ret
+    }
+
+    /// Returns the flags of register `index`.
+    pub fn get(&self, index: usize) -> ExceptionFlags {
+        ExceptionFlags::from_bits(self.registers[index].load(Ordering::Relaxed))
+    }
+
+    /// Replaces the flags of register `index`, like a write of `fflags`.
+    pub fn set(&self, index: usize, flags: ExceptionFlags) {
+        self.registers[index].store(flags.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Raises `flags` in register `index`, keeping those already raised.
+    pub fn raise(&self, index: usize, flags: ExceptionFlags) {
+        if flags.to_bits() != 0 {
+            self.registers[index].fetch_or(flags.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the flags of register `index`, and clears them.
+    pub fn take(&self, index: usize) -> ExceptionFlags {
+        ExceptionFlags::from_bits(self.registers[index].swap(0, Ordering::Relaxed))
+    }
+
+    /// Clears the flags of every register.
+    pub fn clear(&self) {
+        for x in self.registers.iter() {
+            x.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatBits, RoundingMode, F64};
+
+    #[test]
+    fn bank_run() {
+        let rnd = RoundingMode::TiesToEven;
+        let bank = FlagBank::new(4);
+        assert_eq!(bank.len(), 4);
+        let outer = ExceptionFlags::from_bits(ExceptionFlags::FLAG_UNDERFLOW);
+        outer.set();
+
+        let max = F64::from_bits(0x7fefffffffffffff);
+        std::thread::scope(|s| {
+            for hart in 0..4 {
+                let bank = &bank;
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        bank.run(hart, || {
+                            if hart % 2 == 0 {
+                                max.add(max, rnd)
+                            } else {
+                                max.sub(max, rnd)
+                            }
+                        });
+                    }
+                });
+            }
+        });
+        assert_eq!(bank.get(0).to_string(), "OF NX");
+        assert_eq!(bank.get(1).to_string(), "");
+        assert_eq!(bank.take(2).to_string(), "OF NX");
+        assert_eq!(bank.get(2).to_string(), "");
+
+        // the flags of the thread are left as they were
+        let ret = bank.run(1, || max.mul(max, rnd));
+        assert!(ret.is_infinity());
+        let mut flags = ExceptionFlags::default();
+        flags.get();
+        assert_eq!(flags.to_bits(), outer.to_bits());
+        assert_eq!(bank.get(1).to_string(), "OF NX");
+
+        bank.set(3, ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID));
+        bank.raise(3, ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT));
+        assert_eq!(bank.get(3).to_string(), "NV NX");
+        bank.clear();
+        assert!((0..4).all(|i| bank.get(i).to_bits() == 0));
+        ExceptionFlags::default().set();
+    }
+}
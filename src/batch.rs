@@ -0,0 +1,1057 @@
+//! Slice kernels applying an operation to every element
+//!
+//! Each kernel returns the results together with the exception flags the operation raised,
+//! either merged over all elements, or, with the `_per_lane` variants, one set per element
+//! like vector ISAs report them. The `_into` variants write the results into a slice of the
+//! caller, and the `_in_place` ones over their first operand, and neither allocates.
+//! Reductions, such as the summations, [`norm2`] and
+//! [`mean_variance`], return one value with the merged flags. The exception flags set
+//! before the call are left as they were.
+//!
+//! ```
+//! use softfloat_wrapper::{batch, RoundingMode, F32};
+//!
+//! let a = [F32::from_bits(0x3f800000), F32::from_bits(0x7f7fffff)];
+//! let (ret, flags) = batch::add_per_lane(&a, &a, RoundingMode::TiesToEven);
+//! assert_eq!(ret[0].to_bits(), 0x40000000);
+//! assert!(!flags[0].is_overflow());
+//! assert!(flags[1].is_overflow());
+//! ```
+//!
+//! With the `rayon` feature, [`par`] provides the same kernels running in parallel, and with
+//! the `simd` feature, [`simd`] provides the arithmetic ones on the vector units of the
+//! host.
+//!
+//! # Panics
+//!
+//! The kernels panic if the operand slices have different lengths.
+
+use crate::ops::additive_identity;
+use crate::{round_pack, unpack};
+use crate::{with_flags, ConversionPolicy, ExceptionFlags, FloatError, RoundingMode, SoftFloat};
+
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "simd")]
+pub mod simd;
+
+fn check_len(a: usize, b: usize) {
+    assert_eq!(a, b, "operand slices have different lengths");
+}
+
+macro_rules! binary {
+    (
+        $name:ident,
+        $per_lane:ident,
+        $into:ident,
+        $per_lane_into:ident,
+        $in_place:ident,
+        $op:ident,
+        $doc:literal
+    ) => {
+        #[doc = concat!("Computes ", $doc, " of each pair of elements, merging the exception flags.")]
+        pub fn $name<T: SoftFloat>(a: &[T], b: &[T], rnd: RoundingMode) -> (Vec<T>, ExceptionFlags) {
+            check_len(a.len(), b.len());
+            with_flags(|| a.iter().zip(b).map(|(a, b)| a.$op(b, rnd)).collect())
+        }
+
+        #[doc = concat!("Computes ", $doc, " of each pair of elements, with the exception flags of each.")]
+        pub fn $per_lane<T: SoftFloat>(
+            a: &[T],
+            b: &[T],
+            rnd: RoundingMode,
+        ) -> (Vec<T>, Vec<ExceptionFlags>) {
+            check_len(a.len(), b.len());
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| with_flags(|| a.$op(b, rnd)))
+                .unzip()
+        }
+
+        #[doc = concat!("Writes ", $doc, " of each pair of elements to `dst`, merging the exception flags.")]
+        pub fn $into<T: SoftFloat>(dst: &mut [T], a: &[T], b: &[T], rnd: RoundingMode) -> ExceptionFlags {
+            check_len(dst.len(), a.len());
+            check_len(a.len(), b.len());
+            with_flags(|| {
+                for ((d, a), b) in dst.iter_mut().zip(a).zip(b) {
+                    *d = a.$op(b, rnd);
+                }
+            })
+            .1
+        }
+
+        #[doc = concat!("Writes ", $doc, " of each pair of elements to `dst`, and the exception flags of each to `flags`.")]
+        pub fn $per_lane_into<T: SoftFloat>(
+            dst: &mut [T],
+            flags: &mut [ExceptionFlags],
+            a: &[T],
+            b: &[T],
+            rnd: RoundingMode,
+        ) {
+            check_len(dst.len(), a.len());
+            check_len(flags.len(), a.len());
+            check_len(a.len(), b.len());
+            for (((d, f), a), b) in dst.iter_mut().zip(flags.iter_mut()).zip(a).zip(b) {
+                (*d, *f) = with_flags(|| a.$op(b, rnd));
+            }
+        }
+
+        #[doc = concat!("Replaces each element of `a` with ", $doc, " of it and the element of `b`, merging the exception flags.")]
+        pub fn $in_place<T: SoftFloat>(a: &mut [T], b: &[T], rnd: RoundingMode) -> ExceptionFlags {
+            check_len(a.len(), b.len());
+            with_flags(|| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    *a = a.$op(b, rnd);
+                }
+            })
+            .1
+        }
+    };
+}
+
+binary!(
+    add,
+    add_per_lane,
+    add_into,
+    add_per_lane_into,
+    add_in_place,
+    add,
+    "the sum"
+);
+binary!(
+    sub,
+    sub_per_lane,
+    sub_into,
+    sub_per_lane_into,
+    sub_in_place,
+    sub,
+    "the difference"
+);
+binary!(
+    mul,
+    mul_per_lane,
+    mul_into,
+    mul_per_lane_into,
+    mul_in_place,
+    mul,
+    "the product"
+);
+binary!(
+    div,
+    div_per_lane,
+    div_into,
+    div_per_lane_into,
+    div_in_place,
+    div,
+    "the quotient"
+);
+binary!(
+    rem,
+    rem_per_lane,
+    rem_into,
+    rem_per_lane_into,
+    rem_in_place,
+    rem,
+    "the remainder"
+);
+
+/// Computes `a * b + c` of each triple of elements, merging the exception flags.
+pub fn fused_mul_add<T: SoftFloat>(
+    a: &[T],
+    b: &[T],
+    c: &[T],
+    rnd: RoundingMode,
+) -> (Vec<T>, ExceptionFlags) {
+    check_len(a.len(), b.len());
+    check_len(a.len(), c.len());
+    with_flags(|| {
+        a.iter()
+            .zip(b)
+            .zip(c)
+            .map(|((a, b), c)| a.fused_mul_add(b, c, rnd))
+            .collect()
+    })
+}
+
+/// Computes `a * b + c` of each triple of elements, with the exception flags of each.
+pub fn fused_mul_add_per_lane<T: SoftFloat>(
+    a: &[T],
+    b: &[T],
+    c: &[T],
+    rnd: RoundingMode,
+) -> (Vec<T>, Vec<ExceptionFlags>) {
+    check_len(a.len(), b.len());
+    check_len(a.len(), c.len());
+    a.iter()
+        .zip(b)
+        .zip(c)
+        .map(|((a, b), c)| with_flags(|| a.fused_mul_add(b, c, rnd)))
+        .unzip()
+}
+
+/// Writes `a * b + c` of each triple of elements to `dst`, merging the exception flags.
+pub fn fused_mul_add_into<T: SoftFloat>(
+    dst: &mut [T],
+    a: &[T],
+    b: &[T],
+    c: &[T],
+    rnd: RoundingMode,
+) -> ExceptionFlags {
+    check_len(dst.len(), a.len());
+    check_len(a.len(), b.len());
+    check_len(a.len(), c.len());
+    with_flags(|| {
+        for (((d, a), b), c) in dst.iter_mut().zip(a).zip(b).zip(c) {
+            *d = a.fused_mul_add(b, c, rnd);
+        }
+    })
+    .1
+}
+
+/// Replaces each element of `acc` with `a * b + acc` of the elements of `a` and `b`,
+/// merging the exception flags.
+pub fn fused_mul_add_in_place<T: SoftFloat>(
+    acc: &mut [T],
+    a: &[T],
+    b: &[T],
+    rnd: RoundingMode,
+) -> ExceptionFlags {
+    check_len(acc.len(), a.len());
+    check_len(a.len(), b.len());
+    with_flags(|| {
+        for ((acc, a), b) in acc.iter_mut().zip(a).zip(b) {
+            *acc = a.fused_mul_add(b, &*acc, rnd);
+        }
+    })
+    .1
+}
+
+/// Computes the square root of each element, merging the exception flags.
+pub fn sqrt<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (Vec<T>, ExceptionFlags) {
+    with_flags(|| a.iter().map(|a| a.sqrt(rnd)).collect())
+}
+
+/// Computes the square root of each element, with the exception flags of each.
+pub fn sqrt_per_lane<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (Vec<T>, Vec<ExceptionFlags>) {
+    a.iter().map(|a| with_flags(|| a.sqrt(rnd))).unzip()
+}
+
+/// Writes the square root of each element to `dst`, merging the exception flags.
+pub fn sqrt_into<T: SoftFloat>(dst: &mut [T], a: &[T], rnd: RoundingMode) -> ExceptionFlags {
+    check_len(dst.len(), a.len());
+    with_flags(|| {
+        for (d, a) in dst.iter_mut().zip(a) {
+            *d = a.sqrt(rnd);
+        }
+    })
+    .1
+}
+
+/// Replaces each element with its square root, merging the exception flags.
+pub fn sqrt_in_place<T: SoftFloat>(a: &mut [T], rnd: RoundingMode) -> ExceptionFlags {
+    with_flags(|| {
+        for a in a.iter_mut() {
+            *a = a.sqrt(rnd);
+        }
+    })
+    .1
+}
+
+/// Converts each element of `src`, the bits of values of `S`, to the bits of values of `D`
+/// rounded per `rnd`, merging the exception flags.
+///
+/// ```
+/// use softfloat_wrapper::{batch, RoundingMode, F16, F32};
+///
+/// let half: [u16; 3] = [0x3c00, 0xc000, 0x7c00]; // 1, -2, inf
+/// let (ret, flags) = batch::convert_bits::<F16, F32>(&half, RoundingMode::TiesToEven);
+/// assert_eq!(ret, [0x3f800000, 0xc0000000, 0x7f800000]);
+/// assert_eq!(flags.to_bits(), 0);
+/// ```
+pub fn convert_bits<S: SoftFloat, D: SoftFloat>(
+    src: &[S::Payload],
+    rnd: RoundingMode,
+) -> (Vec<D::Payload>, ExceptionFlags) {
+    with_flags(|| {
+        src.iter()
+            .map(|&x| S::from_bits(x).convert::<D>(rnd).to_bits())
+            .collect()
+    })
+}
+
+/// Writes the bits of each element of `src` converted like [`convert_bits`] to `dst`,
+/// merging the exception flags.
+pub fn convert_bits_into<S: SoftFloat, D: SoftFloat>(
+    dst: &mut [D::Payload],
+    src: &[S::Payload],
+    rnd: RoundingMode,
+) -> ExceptionFlags {
+    check_len(dst.len(), src.len());
+    with_flags(|| {
+        for (d, &x) in dst.iter_mut().zip(src) {
+            *d = S::from_bits(x).convert::<D>(rnd).to_bits();
+        }
+    })
+    .1
+}
+
+/// Computes `acc + a[0] * b[0] + a[1] * b[1] + ...` in the format of `acc`, like a dot
+/// product step of a mixed-precision matrix unit, merging the exception flags.
+///
+/// The elements are converted to the format of `acc`, exactly if it is at least as wide, and
+/// each product is added to the accumulator with a single rounding, in order.
+///
+/// ```
+/// use softfloat_wrapper::{batch, RoundingMode, F16, F32};
+///
+/// // 2052 * 2052 needs 19 bits, which F16 does not have but F32 does
+/// let a = [F16::from_bits(0x6802)];
+/// let (acc, flags) = batch::mixed_dot(F32::from_bits(0), &a, &a, RoundingMode::TiesToEven);
+/// assert_eq!(acc.to_bits(), 0x4a808020);
+/// assert!(!flags.is_inexact());
+/// ```
+pub fn mixed_dot<S: SoftFloat, A: SoftFloat>(
+    acc: A,
+    a: &[S],
+    b: &[S],
+    rnd: RoundingMode,
+) -> (A, ExceptionFlags) {
+    check_len(a.len(), b.len());
+    with_flags(|| {
+        a.iter().zip(b).fold(acc, |acc, (a, b)| {
+            let a: A = a.convert(rnd);
+            a.fused_mul_add(b.convert::<A>(rnd), acc, rnd)
+        })
+    })
+}
+
+/// Sums the elements with Kahan's compensated summation, merging the exception flags.
+///
+/// The error is at most `(2u + O(n u^2)) * (|a[0]| + |a[1]| + ...)`, where `u` is the unit
+/// roundoff, `2^-p` when rounding to nearest in a format of precision `p`, against
+/// `(n - 1) u` times that sum for a loop of additions. An empty slice sums to the additive
+/// identity of `rnd`, as in [`SoftFloatIterExt`](crate::SoftFloatIterExt).
+pub fn sum_kahan<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (T, ExceptionFlags) {
+    with_flags(|| {
+        let mut sum = additive_identity::<T>(rnd);
+        let mut c = sum.neg();
+        for x in a {
+            let y = x.sub(&c, rnd);
+            let t = sum.add(&y, rnd);
+            // the compensation of an infinite sum would be a NaN
+            if t.is_finite() {
+                c = t.sub(&sum, rnd).sub(&y, rnd);
+            }
+            sum = t;
+        }
+        sum
+    })
+}
+
+/// Sums the elements with Neumaier's improved Kahan–Babuška summation, merging the
+/// exception flags.
+///
+/// Unlike [`sum_kahan`], the compensation also holds when an element is larger than the
+/// running sum, and the error is at most `u |s| + O(n u^2) * (|a[0]| + |a[1]| + ...)`, where
+/// `s` is the exact sum: the result is as accurate as rounding `s` once, unless the
+/// elements cancel heavily.
+///
+/// ```
+/// use softfloat_wrapper::{batch, RoundingMode, F64};
+///
+/// let a = [0x3ff0000000000000, 0x4630000000000000, 0x3ff0000000000000, 0xc630000000000000]
+///     .map(F64::from_bits); // 1, 2^100, 1, -2^100
+/// let rnd = RoundingMode::TiesToEven;
+/// assert_eq!(batch::sum_kahan(&a, rnd).0.to_bits(), 0);
+/// assert_eq!(batch::sum_neumaier(&a, rnd).0.to_bits(), 0x4000000000000000);
+/// ```
+pub fn sum_neumaier<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (T, ExceptionFlags) {
+    with_flags(|| {
+        let mut sum = additive_identity::<T>(rnd);
+        let mut c = additive_identity::<T>(rnd);
+        for x in a {
+            let t = sum.add(x, rnd);
+            if t.is_finite() {
+                let err = if x.abs().le_quiet(sum.abs()) {
+                    sum.sub(&t, rnd).add(x, rnd)
+                } else {
+                    x.sub(&t, rnd).add(&sum, rnd)
+                };
+                // adding zeros could only change the sign of a zero sum
+                if !err.is_zero() {
+                    c = c.add(&err, rnd);
+                }
+            }
+            sum = t;
+        }
+        sum.add(&c, rnd)
+    })
+}
+
+/// Sums the elements by adding the sums of the two halves of the slice recursively,
+/// merging the exception flags.
+///
+/// It runs as many additions as a loop does, and the error is at most
+/// `ceil(log2(n)) u * (|a[0]| + |a[1]| + ...)` to first order, against `(n - 1) u` times that
+/// sum for a loop.
+pub fn sum_pairwise<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (T, ExceptionFlags) {
+    fn pairwise<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> T {
+        match a {
+            [] => additive_identity(rnd),
+            [x] => T::from_bits(x.to_bits()),
+            _ => {
+                let (lo, hi) = a.split_at(a.len() / 2);
+                pairwise(lo, rnd).add(pairwise(hi, rnd), rnd)
+            }
+        }
+    }
+    with_flags(|| pairwise(a, rnd))
+}
+
+/// Computes the Euclidean norm `sqrt(a[0]^2 + a[1]^2 + ...)`, merging the exception flags.
+///
+/// The elements are scaled by a power of two which brings the largest to `[1, 2)` before
+/// their squares are accumulated with fused multiply-adds, so the result overflows or
+/// underflows only if it is out of the range of the format, and these flags are only
+/// raised then. The square root of the sum is rounded once, at the exponent of the result,
+/// so subnormal results are not rounded twice. An infinite element gives `+inf`, even if another is a NaN, and an empty
+/// slice gives `+0`.
+///
+/// ```
+/// use softfloat_wrapper::{batch, RoundingMode, F32};
+///
+/// // 3 * 2^100 and 4 * 2^100, whose squares overflow
+/// let a = [F32::from_bits(0x72400000), F32::from_bits(0x72800000)];
+/// let (ret, flags) = batch::norm2(&a, RoundingMode::TiesToEven);
+/// assert_eq!(ret.to_bits(), 0x72a00000);
+/// assert!(!flags.is_overflow() && !flags.is_inexact());
+/// ```
+pub fn norm2<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (T, ExceptionFlags) {
+    with_flags(|| {
+        if a.iter().any(|x| x.is_infinity()) {
+            return T::infinity();
+        }
+        if let Some(x) = a.iter().find(|x| x.is_nan()) {
+            return x.add(x, rnd);
+        }
+        let scale = match a.iter().filter(|x| !x.is_zero()).map(msb_exp).max() {
+            Some(x) => x,
+            None => return T::zero(),
+        };
+        // only inexact is kept from the scaled sum, which cannot overflow
+        let (ss, flags) = with_flags(|| {
+            a.iter().fold(T::zero(), |ss, x| {
+                let (sign, exp, sig) = unpack(x);
+                let x: T = round_pack(sign, exp - scale, sig, rnd);
+                x.fused_mul_add(&x, &ss, rnd)
+            })
+        });
+        if flags.is_inexact() {
+            ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT).raise();
+        }
+        crate::math::sqrt_scaled(&ss, scale, rnd)
+    })
+}
+
+/// exponent of the leading bit of a finite non-zero `x`
+fn msb_exp<T: SoftFloat>(x: &T) -> i32 {
+    let (_, exp, sig) = unpack(x);
+    exp + 127 - sig.leading_zeros() as i32
+}
+
+/// Computes the mean and the population variance of the elements with Welford's
+/// algorithm, merging the exception flags, or `None` for an empty slice.
+///
+/// See [`Welford`] for its accuracy.
+pub fn mean_variance<T: SoftFloat>(a: &[T], rnd: RoundingMode) -> (Option<(T, T)>, ExceptionFlags) {
+    with_flags(|| {
+        let mut acc = Welford::new(rnd);
+        for x in a {
+            acc.push(x);
+        }
+        Some((acc.mean()?, acc.variance()?))
+    })
+}
+
+/// Streaming mean and variance, updated per element with Welford's algorithm
+///
+/// The sum of the squared deviations from the running mean is accumulated instead of the
+/// sum of the squares, so the variance does not suffer from the cancellation of the latter
+/// when the mean is large against the deviations. The operations round per the mode given
+/// to [`new`](Self::new), and raise exception flags as they run.
+///
+/// ```
+/// use softfloat_wrapper::batch::Welford;
+/// use softfloat_wrapper::{RoundingMode, F32};
+///
+/// // 2^20 + 1, 2^20 + 3, 2^20 + 5 and 2^20 + 7, whose squares do not fit in F32
+/// let mut acc = Welford::new(RoundingMode::TiesToEven);
+/// for x in [0x49800008, 0x49800018, 0x49800028, 0x49800038] {
+///     acc.push(&F32::from_bits(x));
+/// }
+/// assert_eq!(acc.mean().unwrap().to_bits(), 0x49800020);
+/// assert_eq!(acc.variance().unwrap().to_bits(), 0x40a00000); // 5
+/// ```
+#[derive(Clone, Debug)]
+pub struct Welford<T> {
+    rnd: RoundingMode,
+    count: u64,
+    mean: T,
+    m2: T,
+}
+
+impl<T: SoftFloat> Welford<T> {
+    /// Returns an accumulator of no elements, rounding per `rnd`.
+    pub fn new(rnd: RoundingMode) -> Self {
+        Self {
+            rnd,
+            count: 0,
+            mean: T::zero(),
+            m2: T::zero(),
+        }
+    }
+
+    /// Adds `x` to the elements.
+    pub fn push(&mut self, x: &T) {
+        self.count += 1;
+        let n = T::from_u64(self.count, self.rnd);
+        let delta = x.sub(&self.mean, self.rnd);
+        self.mean = self.mean.add(delta.div(&n, self.rnd), self.rnd);
+        let delta2 = x.sub(&self.mean, self.rnd);
+        self.m2 = delta.fused_mul_add(&delta2, &self.m2, self.rnd);
+    }
+
+    /// Returns the number of elements.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the mean, or `None` if there are no elements.
+    pub fn mean(&self) -> Option<T> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(T::from_bits(self.mean.to_bits()))
+    }
+
+    /// Returns the population variance, the mean of the squared deviations, or `None` if
+    /// there are no elements.
+    pub fn variance(&self) -> Option<T> {
+        self.m2_over(self.count)
+    }
+
+    /// Returns the sample variance, with Bessel's correction, or `None` if there are less
+    /// than two elements.
+    pub fn sample_variance(&self) -> Option<T> {
+        self.m2_over(self.count.checked_sub(1)?)
+    }
+
+    fn m2_over(&self, n: u64) -> Option<T> {
+        if n == 0 {
+            return None;
+        }
+        Some(self.m2.div(T::from_u64(n, self.rnd), self.rnd))
+    }
+}
+
+fn quantize<T: SoftFloat>(
+    x: &T,
+    scale: &T,
+    rnd: RoundingMode,
+    policy: ConversionPolicy,
+) -> Result<i8, FloatError> {
+    let q = x.div(scale, rnd).to_i32_with_policy(rnd, true, policy)?;
+    if let Ok(q) = i8::try_from(q) {
+        return Ok(q);
+    }
+    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+    Ok(match policy {
+        ConversionPolicy::Saturate | ConversionPolicy::NanToZero => {
+            if q < 0 {
+                i8::MIN
+            } else {
+                i8::MAX
+            }
+        }
+        ConversionPolicy::WrapModular => q as i8,
+        ConversionPolicy::Zero => 0,
+        ConversionPolicy::Indefinite => i8::MIN,
+        ConversionPolicy::Error => return Err(FloatError::Invalid),
+    })
+}
+
+/// Quantizes each element to `x / scale` rounded to an `i8`, merging the exception flags.
+///
+/// The quotient is rounded to the format first, and then to an integer per `rnd`, raising
+/// inexact if it is not one. NaNs and values out of the range of `i8` raise invalid, and give
+/// the value `policy` defines; the call fails only with [`ConversionPolicy::Error`].
+///
+/// ```
+/// use softfloat_wrapper::{batch, ConversionPolicy, RoundingMode, F32};
+///
+/// let a = [0x3f800000, 0xc3480000, 0x42fe0000].map(F32::from_bits); // 1, -200, 127
+/// let scale = F32::from_bits(0x3f000000); // 0.5
+/// let rnd = RoundingMode::TiesToEven;
+/// let (q, flags) = batch::quantize_slice(&a, scale, rnd, ConversionPolicy::Saturate).unwrap();
+/// assert_eq!(q, [2, -128, 127]);
+/// assert!(flags.is_invalid());
+/// ```
+pub fn quantize_slice<T: SoftFloat>(
+    src: &[T],
+    scale: T,
+    rnd: RoundingMode,
+    policy: ConversionPolicy,
+) -> Result<(Vec<i8>, ExceptionFlags), FloatError> {
+    let (ret, flags) = with_flags(|| {
+        src.iter()
+            .map(|x| quantize(x, &scale, rnd, policy))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    Ok((ret?, flags))
+}
+
+/// Computes `x * scale` of each quantized element, merging the exception flags.
+pub fn dequantize_slice<T: SoftFloat>(
+    src: &[i8],
+    scale: T,
+    rnd: RoundingMode,
+) -> (Vec<T>, ExceptionFlags) {
+    with_flags(|| {
+        src.iter()
+            .map(|&x| T::from_i32(x as i32, rnd).mul(&scale, rnd))
+            .collect()
+    })
+}
+
+/// An operation of an [`OpBatch`]
+#[derive(Clone, Debug)]
+enum BatchOp<T> {
+    Add(T, T),
+    Sub(T, T),
+    Mul(T, T),
+    Div(T, T),
+    Rem(T, T),
+    Sqrt(T),
+    MulAdd(T, T, T),
+}
+
+/// A sequence of operations run in one rounding mode, with their exception flags merged.
+///
+/// Interpreters build one per basic block, or per instruction bundle, and run it at once:
+/// the rounding mode is set and the flags of the thread are saved and restored once for
+/// the whole sequence, instead of around every operation.
+///
+/// ```
+/// use softfloat_wrapper::batch::OpBatch;
+/// use softfloat_wrapper::{RoundingMode, F32};
+///
+/// let one = F32::from_bits(0x3f800000);
+/// let three = F32::from_bits(0x40400000);
+/// let (ret, flags) = OpBatch::new(RoundingMode::TiesToEven)
+///     .add(one, three)
+///     .div(one, three)
+///     .run();
+/// assert_eq!(ret[0].to_bits(), 0x40800000);
+/// assert_eq!(ret[1].to_bits(), 0x3eaaaaab);
+/// assert!(flags.is_inexact());
+/// ```
+#[derive(Clone, Debug)]
+pub struct OpBatch<T> {
+    rnd: RoundingMode,
+    ops: Vec<BatchOp<T>>,
+}
+
+impl<T: SoftFloat> OpBatch<T> {
+    /// Returns an empty batch rounding per `rnd`.
+    pub fn new(rnd: RoundingMode) -> Self {
+        Self {
+            rnd,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Appends `a + b`.
+    pub fn add(mut self, a: T, b: T) -> Self {
+        self.ops.push(BatchOp::Add(a, b));
+        self
+    }
+
+    /// Appends `a - b`.
+    pub fn sub(mut self, a: T, b: T) -> Self {
+        self.ops.push(BatchOp::Sub(a, b));
+        self
+    }
+
+    /// Appends `a * b`.
+    pub fn mul(mut self, a: T, b: T) -> Self {
+        self.ops.push(BatchOp::Mul(a, b));
+        self
+    }
+
+    /// Appends `a / b`.
+    pub fn div(mut self, a: T, b: T) -> Self {
+        self.ops.push(BatchOp::Div(a, b));
+        self
+    }
+
+    /// Appends the remainder of `a / b`.
+    pub fn rem(mut self, a: T, b: T) -> Self {
+        self.ops.push(BatchOp::Rem(a, b));
+        self
+    }
+
+    /// Appends the square root of `a`.
+    pub fn sqrt(mut self, a: T) -> Self {
+        self.ops.push(BatchOp::Sqrt(a));
+        self
+    }
+
+    /// Appends `a * b + c` with a single rounding.
+    pub fn fused_mul_add(mut self, a: T, b: T, c: T) -> Self {
+        self.ops.push(BatchOp::MulAdd(a, b, c));
+        self
+    }
+
+    /// Returns the number of operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns whether there are no operations.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    fn run_op(&self, op: &BatchOp<T>) -> T {
+        let rnd = self.rnd;
+        match op {
+            BatchOp::Add(a, b) => a.add(b, rnd),
+            BatchOp::Sub(a, b) => a.sub(b, rnd),
+            BatchOp::Mul(a, b) => a.mul(b, rnd),
+            BatchOp::Div(a, b) => a.div(b, rnd),
+            BatchOp::Rem(a, b) => a.rem(b, rnd),
+            BatchOp::Sqrt(a) => a.sqrt(rnd),
+            BatchOp::MulAdd(a, b, c) => a.fused_mul_add(b, c, rnd),
+        }
+    }
+
+    /// Runs the operations in order, and returns their results with the exception flags
+    /// they raised, merged. The exception flags set before the call are left as they were.
+    pub fn run(&self) -> (Vec<T>, ExceptionFlags) {
+        with_flags(|| {
+            self.rnd.set();
+            self.ops.iter().map(|op| self.run_op(op)).collect()
+        })
+    }
+
+    /// Runs the operations in order, and returns their results with the exception flags of
+    /// each.
+    pub fn run_per_op(&self) -> (Vec<T>, Vec<ExceptionFlags>) {
+        self.ops
+            .iter()
+            .map(|op| with_flags(|| self.run_op(op)))
+            .unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatBits, F16, F32, F64};
+
+    #[test]
+    fn batch_flags() {
+        let a = [0x3f800000, 0x00000000, 0xbf800000].map(F32::from_bits);
+        let b = [0x40000000, 0x00000000, 0x00000000].map(F32::from_bits);
+
+        let (ret, flags) = div(&a, &b, RoundingMode::TiesToEven);
+        assert_eq!(ret[0].to_bits(), 0x3f000000);
+        assert!(ret[1].is_nan());
+        assert_eq!(ret[2].to_bits(), 0xff800000);
+        assert!(flags.is_invalid() && flags.is_infinite() && !flags.is_inexact());
+
+        let (ret, flags) = div_per_lane(&a, &b, RoundingMode::TiesToEven);
+        assert_eq!(ret[0].to_bits(), 0x3f000000);
+        assert_eq!(flags[0].to_bits(), 0);
+        assert!(flags[1].is_invalid() && !flags[1].is_infinite());
+        assert!(flags[2].is_infinite() && !flags[2].is_invalid());
+
+        let (_, flags) = sqrt_per_lane(&a, RoundingMode::TiesToEven);
+        assert!(!flags[0].is_invalid() && flags[2].is_invalid());
+
+        let (ret, flags) = fused_mul_add(&a, &a, &b, RoundingMode::TiesToEven);
+        assert_eq!(ret[0].to_bits(), 0x40400000);
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    fn batch_in_place() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = [0x3f800000, 0x00000000, 0xbf800000].map(F32::from_bits);
+        let b = [0x40000000, 0x00000000, 0x00000000].map(F32::from_bits);
+        let mut dst = [F32::from_bits(0); 3];
+
+        let flags = div_into(&mut dst, &a, &b, rnd);
+        assert_eq!(dst[0].to_bits(), 0x3f000000);
+        assert!(dst[1].is_nan());
+        assert!(flags.is_invalid() && flags.is_infinite() && !flags.is_inexact());
+        let mut lanes = [ExceptionFlags::default(); 3];
+        sub_per_lane_into(&mut dst, &mut lanes, &a, &b, rnd);
+        assert_eq!(dst[2].to_bits(), 0xbf800000);
+        assert!(lanes.iter().all(|x| x.to_bits() == 0));
+
+        let mut acc = a;
+        let flags = mul_in_place(&mut acc, &b, rnd);
+        assert_eq!(
+            acc.map(|x| x.to_bits()),
+            [0x40000000, 0x00000000, 0x80000000]
+        );
+        assert_eq!(flags.to_bits(), 0);
+        let flags = fused_mul_add_in_place(&mut acc, &a, &a, rnd);
+        assert_eq!(
+            acc.map(|x| x.to_bits()),
+            [0x40400000, 0x00000000, 0x3f800000]
+        );
+        assert_eq!(flags.to_bits(), 0);
+        fused_mul_add_into(&mut dst, &a, &b, &a, rnd);
+        assert_eq!(dst[0].to_bits(), 0x40400000);
+        let flags = sqrt_in_place(&mut acc, rnd);
+        assert_eq!(acc[2].to_bits(), 0x3f800000);
+        assert!(flags.is_inexact());
+        sqrt_into(&mut dst, &a, rnd);
+        assert!(dst[2].is_nan());
+
+        let mut half = [0u16; 2];
+        let flags = convert_bits_into::<F32, F16>(&mut half, &[0x3f800000, 0x7f7fffff], rnd);
+        assert_eq!(half, [0x3c00, 0x7c00]);
+        assert!(flags.is_overflow());
+    }
+
+    #[test]
+    fn batch_convert_bits() {
+        let rnd = RoundingMode::TiesToEven;
+        // 1 + 2^-11 ties to even, 65520 overflows, 2^-25 underflows
+        let src = [0x3ff0020000000000, 0x40effe0000000000, 0x3e60000000000000];
+        let (ret, flags) = convert_bits::<F64, F16>(&src, rnd);
+        assert_eq!(ret, [0x3c00, 0x7c00, 0x0000]);
+        assert!(flags.is_inexact() && flags.is_overflow() && flags.is_underflow());
+        let (ret, flags) = convert_bits::<F64, F16>(&src, RoundingMode::TowardZero);
+        assert_eq!(ret, [0x3c00, 0x7bff, 0x0000]);
+        // rounded toward zero, 65520 is 65504 and does not overflow
+        assert!(!flags.is_overflow() && flags.is_inexact());
+
+        let (ret, flags) = convert_bits::<F16, F64>(&[0x0001, 0x7e00], rnd);
+        assert_eq!(ret, [0x3e70000000000000, 0x7ff8000000000000]);
+        assert_eq!(flags.to_bits(), 0);
+        assert!(convert_bits::<F16, F32>(&[], rnd).0.is_empty());
+    }
+
+    #[test]
+    fn batch_mixed_dot() {
+        let rnd = RoundingMode::TiesToEven;
+        // 1 - 2^-11 and 1 + 2^-10, whose product 1 + 2^-11 - 2^-21 is exact in F32
+        let a = [0x3c01, 0x3bff, 0x0001].map(F16::from_bits);
+        let b = [0x3c00, 0x3c01, 0x0001].map(F16::from_bits);
+        let (ret, flags) = mixed_dot(F32::from_bits(0), &a[1..2], &b[1..2], rnd);
+        assert_eq!(ret.to_bits(), 0x3f800ffc);
+        assert_eq!(flags.to_bits(), 0);
+        // the product of the smallest subnormals, 2^-48, is rounded once into the sum
+        let (ret, flags) = mixed_dot(F32::from_bits(0x3f800000), &a, &b, rnd);
+        assert_eq!(ret.to_bits(), 0x404017fe);
+        assert!(flags.is_inexact());
+        let (ret, _) = mixed_dot(
+            F32::from_bits(0x3f800000),
+            &a,
+            &b,
+            RoundingMode::TowardPositive,
+        );
+        assert_eq!(ret.to_bits(), 0x404017ff);
+    }
+
+    #[test]
+    fn batch_quantize() {
+        let rnd = RoundingMode::TiesToEven;
+        // 1.25, -2.5, 1000, NaN
+        let a = [0x3fa00000, 0xc0200000, 0x447a0000, 0x7fc00000].map(F32::from_bits);
+        let scale = F32::from_bits(0x3e800000); // 0.25
+        let policies = [
+            (ConversionPolicy::Saturate, [5, -10, 127, 127]),
+            (ConversionPolicy::NanToZero, [5, -10, 127, 0]),
+            (ConversionPolicy::WrapModular, [5, -10, -96, 0]),
+            (ConversionPolicy::Zero, [5, -10, 0, 0]),
+            (ConversionPolicy::Indefinite, [5, -10, -128, -128]),
+        ];
+        for (policy, expected) in policies {
+            let (q, flags) = quantize_slice(&a, scale, rnd, policy).unwrap();
+            assert_eq!(q, expected, "{:?}", policy);
+            assert!(flags.is_invalid() && !flags.is_inexact());
+        }
+        assert_eq!(
+            quantize_slice(&a, scale, rnd, ConversionPolicy::Error).unwrap_err(),
+            FloatError::Invalid
+        );
+
+        // 0.3 / 0.25 rounds to 1
+        let (q, flags) = quantize_slice(
+            &[F32::from_bits(0x3e99999a)],
+            scale,
+            rnd,
+            ConversionPolicy::Error,
+        )
+        .unwrap();
+        assert_eq!(q, [1]);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+
+        let (ret, flags) = dequantize_slice(&[5, -10, 127], scale, rnd);
+        assert_eq!(
+            ret.iter().map(|x| x.to_bits()).collect::<Vec<_>>(),
+            [0x3fa00000, 0xc0200000, 0x41fe0000]
+        );
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    fn batch_ops() {
+        let rnd = RoundingMode::TowardZero;
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        let max = F64::from_bits(0x7fefffffffffffff);
+        let batch = OpBatch::new(rnd)
+            .add(one, three)
+            .sub(one, three)
+            .mul(max, three)
+            .div(one, three)
+            .rem(three, one.add(one, rnd))
+            .sqrt(three)
+            .fused_mul_add(three, three, one);
+        assert_eq!(batch.len(), 7);
+        let mut before = ExceptionFlags::from_bits(ExceptionFlags::FLAG_INFINITE);
+        before.set();
+        let (ret, flags) = batch.run();
+        let bits: Vec<_> = ret.iter().map(|x| x.to_bits()).collect();
+        assert_eq!(
+            bits,
+            [
+                0x4010000000000000,
+                0xc000000000000000,
+                0x7fefffffffffffff,
+                0x3fd5555555555555,
+                0xbff0000000000000,
+                0x3ffbb67ae8584caa,
+                0x4024000000000000,
+            ]
+        );
+        assert_eq!(
+            flags.to_bits(),
+            ExceptionFlags::FLAG_OVERFLOW | ExceptionFlags::FLAG_INEXACT
+        );
+        before.get();
+        assert_eq!(before.to_bits(), ExceptionFlags::FLAG_INFINITE);
+        ExceptionFlags::default().set();
+
+        let (ret, flags) = batch.run_per_op();
+        assert_eq!(ret.len(), 7);
+        assert_eq!(flags[0].to_bits(), 0);
+        assert!(flags[2].is_overflow());
+        assert!(flags[3].is_inexact() && !flags[3].is_overflow());
+        assert!(OpBatch::<F32>::new(rnd).run().0.is_empty());
+    }
+
+    #[test]
+    fn batch_sums() {
+        let rnd = RoundingMode::TiesToEven;
+        // 1 followed by 16 halves of its ulp, which a loop of additions drops
+        let mut a = vec![F32::from_bits(0x33800000); 17];
+        a[0] = F32::from_bits(0x3f800000);
+        let (ret, flags) = sum_kahan(&a, rnd);
+        assert_eq!(ret.to_bits(), 0x3f800008);
+        assert!(flags.is_inexact());
+        assert_eq!(sum_neumaier(&a, rnd).0.to_bits(), 0x3f800008);
+        // ((1 + 2^-24) + (2^-24 + 2^-24)) keeps one of the halves
+        assert_eq!(sum_pairwise(&a[..4], rnd).0.to_bits(), 0x3f800001);
+        let (ret, flags) = sum_pairwise(&a[1..], rnd);
+        assert_eq!(ret.to_bits(), 0x35800000);
+        assert_eq!(flags.to_bits(), 0);
+
+        // zeros keep their sign, and empty slices give the additive identity
+        let zeros = [F64::from_bits(0x8000000000000000); 2];
+        for sum in [sum_kahan, sum_neumaier, sum_pairwise] {
+            assert_eq!(sum(&zeros, rnd).0.to_bits(), 0x8000000000000000);
+            assert_eq!(sum(&[], rnd).0.to_bits(), 0x8000000000000000);
+            assert_eq!(sum(&[], RoundingMode::TowardNegative).0.to_bits(), 0);
+        }
+
+        // infinities are not compensated into NaNs
+        let a = [
+            F16::infinity(),
+            F16::from_bits(0x3c00),
+            F16::from_bits(0x1400),
+        ];
+        for sum in [sum_kahan, sum_neumaier, sum_pairwise] {
+            let (ret, flags) = sum(&a, rnd);
+            assert!(ret.is_infinity());
+            assert!(!flags.is_invalid());
+        }
+    }
+
+    #[test]
+    fn batch_norm_stats() {
+        let rnd = RoundingMode::TiesToEven;
+        // 3 * 2^-140 and 4 * 2^-140, whose squares underflow to zero
+        let a = [F32::from_bits(0x600), F32::from_bits(0x800)];
+        let (ret, flags) = norm2(&a, rnd);
+        assert_eq!(ret.to_bits(), 0xa00);
+        assert_eq!(flags.to_bits(), 0);
+        // sqrt(1635^2 + 1545^2) = 2249.49994..., which rounds to the tie 2249.5 with 24 bits,
+        // and then to an even 2250 if it is rounded twice
+        let a = [F32::from_bits(1635), F32::from_bits(1545)];
+        let (ret, flags) = norm2(&a, rnd);
+        assert_eq!(ret.to_bits(), 2249);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        let (ret, flags) = norm2(&[F64::from_bits(0x4000000000000000); 2], rnd);
+        assert_eq!(ret.to_bits(), 0x4006a09e667f3bcd);
+        assert!(flags.is_inexact() && !flags.is_underflow());
+        let max = F64::from_bits(0x7fefffffffffffff);
+        let (ret, flags) = norm2(&[max, max], rnd);
+        assert!(ret.is_infinity());
+        assert!(flags.is_overflow());
+
+        let nan = F16::quiet_nan();
+        assert!(norm2(&[F16::infinity().neg(), nan], rnd).0.is_infinity());
+        assert!(norm2(&[F16::from_bits(0x3c00), nan], rnd).0.is_nan());
+        assert_eq!(norm2::<F16>(&[], rnd).0.to_bits(), 0);
+        assert_eq!(norm2(&[F16::from_bits(0x8000)], rnd).0.to_bits(), 0);
+
+        // 1, 3, 5, 7
+        let a = [
+            0x3ff0000000000000,
+            0x4008000000000000,
+            0x4014000000000000,
+            0x401c000000000000,
+        ]
+        .map(F64::from_bits);
+        let (ret, flags) = mean_variance(&a, rnd);
+        let (mean, var) = ret.unwrap();
+        assert_eq!(mean.to_bits(), 0x4010000000000000);
+        assert_eq!(var.to_bits(), 0x4014000000000000);
+        assert_eq!(flags.to_bits(), 0);
+        let mut acc = Welford::new(rnd);
+        assert!(acc.mean().is_none() && acc.variance().is_none());
+        acc.push(&a[0]);
+        assert!(acc.sample_variance().is_none());
+        for x in &a[1..] {
+            acc.push(x);
+        }
+        assert_eq!(acc.count(), 4);
+        // 20 / 3
+        assert_eq!(acc.sample_variance().unwrap().to_bits(), 0x401aaaaaaaaaaaab);
+        assert!(mean_variance::<F64>(&[], rnd).0.is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn batch_length() {
+        let a = [F32::from_bits(0); 2];
+        add(&a, &a[1..], RoundingMode::TiesToEven);
+    }
+}
@@ -0,0 +1,286 @@
+//! Parallel slice kernels on the [rayon](https://crates.io/crates/rayon) thread pool
+//!
+//! The slices are split into chunks which run through the sequential kernels on the worker
+//! threads. The softfloat rounding mode and exception flags are per thread with both
+//! backends, so each chunk collects its own flags and they are merged afterwards, or kept
+//! per lane by the `_per_lane` variants.
+//!
+//! Each chunk runs with the [`SoftFloatState`] and the strict mode of the calling thread,
+//! and the statistics of the workers are added to the ones of the caller. A backend, trace
+//! hook or trap handler belongs to the thread which set it, so while the caller has one of
+//! them, the kernels run sequentially on the caller instead.
+//!
+//! # Panics
+//!
+//! The kernels panic if the operand slices have different lengths.
+
+use super::check_len;
+#[cfg(feature = "stats")]
+use crate::stats::{add as add_counts, counted, OpStats as Counts};
+use crate::strict::{set_strict_mode, strict_mode};
+use crate::{ExceptionFlags, RoundingMode, SoftFloat, SoftFloatState};
+use ::rayon::prelude::*;
+
+/// elements run sequentially by a single task
+const CHUNK: usize = 4096;
+
+/// statistics of a worker, which are only counted with the `stats` feature
+#[cfg(not(feature = "stats"))]
+struct Counts;
+
+#[cfg(not(feature = "stats"))]
+fn counted<R>(f: impl FnOnce() -> R) -> (R, Counts) {
+    (f(), Counts)
+}
+
+#[cfg(not(feature = "stats"))]
+fn add_counts(_: Counts) {}
+
+/// per-thread state of the caller, which the chunks run with on the worker threads
+struct Context {
+    state: SoftFloatState,
+    strict: bool,
+}
+
+impl Context {
+    /// Returns the state of this thread, or `None` if its operations must run on it.
+    fn save() -> Option<Self> {
+        #[cfg(feature = "backend")]
+        if crate::backend::has_backend() {
+            return None;
+        }
+        #[cfg(feature = "trace")]
+        if crate::trace::has_trace_hook() {
+            return None;
+        }
+        #[cfg(feature = "trap")]
+        if crate::trap::has_trap_handler() {
+            return None;
+        }
+        Some(Self {
+            state: SoftFloatState::save(),
+            strict: strict_mode(),
+        })
+    }
+
+    /// Runs `f` with the state of the caller, and restores the one of this thread.
+    fn run<R>(&self, f: impl FnOnce() -> R) -> (R, Counts) {
+        let _strict = Strict(strict_mode());
+        set_strict_mode(self.strict);
+        let mut state = self.state;
+        counted(|| state.run(f))
+    }
+
+    /// Runs `f` on every chunk on the worker threads, and returns the results in order.
+    fn collect<I: ParallelIterator, R: Send>(
+        &self,
+        chunks: I,
+        f: impl Fn(I::Item) -> R + Sync + Send,
+    ) -> Vec<R> {
+        let ret: Vec<_> = chunks.map(|x| self.run(|| f(x))).collect();
+        ret.into_iter()
+            .map(|(x, counts)| {
+                add_counts(counts);
+                x
+            })
+            .collect()
+    }
+}
+
+/// restores the strict mode of a worker thread when dropped, even by a panic
+struct Strict(bool);
+
+impl Drop for Strict {
+    fn drop(&mut self) {
+        set_strict_mode(self.0);
+    }
+}
+
+fn merge<T>(chunks: Vec<(Vec<T>, ExceptionFlags)>) -> (Vec<T>, ExceptionFlags) {
+    let mut flags = 0;
+    let mut ret = Vec::with_capacity(chunks.iter().map(|x| x.0.len()).sum());
+    for (x, f) in chunks {
+        ret.extend(x);
+        flags |= f.to_bits();
+    }
+    (ret, ExceptionFlags::from_bits(flags))
+}
+
+fn concat<T>(chunks: Vec<(Vec<T>, Vec<ExceptionFlags>)>) -> (Vec<T>, Vec<ExceptionFlags>) {
+    chunks
+        .into_iter()
+        .fold((Vec::new(), Vec::new()), |mut acc, (x, f)| {
+            acc.0.extend(x);
+            acc.1.extend(f);
+            acc
+        })
+}
+
+macro_rules! binary {
+    ($name:ident, $per_lane:ident, $doc:literal) => {
+        #[doc = concat!("Computes ", $doc, " of each pair of elements in parallel, merging the exception flags.")]
+        pub fn $name<T: SoftFloat + Send + Sync>(
+            a: &[T],
+            b: &[T],
+            rnd: RoundingMode,
+        ) -> (Vec<T>, ExceptionFlags) {
+            check_len(a.len(), b.len());
+            let ctx = match Context::save() {
+                Some(x) => x,
+                None => return super::$name(a, b, rnd),
+            };
+            merge(ctx.collect(a.par_chunks(CHUNK).zip(b.par_chunks(CHUNK)), |(a, b)| {
+                super::$name(a, b, rnd)
+            }))
+        }
+
+        #[doc = concat!("Computes ", $doc, " of each pair of elements in parallel, with the exception flags of each.")]
+        pub fn $per_lane<T: SoftFloat + Send + Sync>(
+            a: &[T],
+            b: &[T],
+            rnd: RoundingMode,
+        ) -> (Vec<T>, Vec<ExceptionFlags>) {
+            check_len(a.len(), b.len());
+            let ctx = match Context::save() {
+                Some(x) => x,
+                None => return super::$per_lane(a, b, rnd),
+            };
+            concat(ctx.collect(a.par_chunks(CHUNK).zip(b.par_chunks(CHUNK)), |(a, b)| {
+                super::$per_lane(a, b, rnd)
+            }))
+        }
+    };
+}
+
+binary!(add, add_per_lane, "the sum");
+binary!(sub, sub_per_lane, "the difference");
+binary!(mul, mul_per_lane, "the product");
+binary!(div, div_per_lane, "the quotient");
+binary!(rem, rem_per_lane, "the remainder");
+
+/// Computes `a * b + c` of each triple of elements in parallel, merging the exception flags.
+pub fn fused_mul_add<T: SoftFloat + Send + Sync>(
+    a: &[T],
+    b: &[T],
+    c: &[T],
+    rnd: RoundingMode,
+) -> (Vec<T>, ExceptionFlags) {
+    check_len(a.len(), b.len());
+    check_len(a.len(), c.len());
+    let ctx = match Context::save() {
+        Some(x) => x,
+        None => return super::fused_mul_add(a, b, c, rnd),
+    };
+    let chunks = a
+        .par_chunks(CHUNK)
+        .zip(b.par_chunks(CHUNK))
+        .zip(c.par_chunks(CHUNK));
+    merge(ctx.collect(chunks, |((a, b), c)| super::fused_mul_add(a, b, c, rnd)))
+}
+
+/// Computes `a * b + c` of each triple of elements in parallel, with the exception flags of
+/// each.
+pub fn fused_mul_add_per_lane<T: SoftFloat + Send + Sync>(
+    a: &[T],
+    b: &[T],
+    c: &[T],
+    rnd: RoundingMode,
+) -> (Vec<T>, Vec<ExceptionFlags>) {
+    check_len(a.len(), b.len());
+    check_len(a.len(), c.len());
+    let ctx = match Context::save() {
+        Some(x) => x,
+        None => return super::fused_mul_add_per_lane(a, b, c, rnd),
+    };
+    let chunks = a
+        .par_chunks(CHUNK)
+        .zip(b.par_chunks(CHUNK))
+        .zip(c.par_chunks(CHUNK));
+    concat(ctx.collect(chunks, |((a, b), c)| {
+        super::fused_mul_add_per_lane(a, b, c, rnd)
+    }))
+}
+
+/// Computes the square root of each element in parallel, merging the exception flags.
+pub fn sqrt<T: SoftFloat + Send + Sync>(a: &[T], rnd: RoundingMode) -> (Vec<T>, ExceptionFlags) {
+    let ctx = match Context::save() {
+        Some(x) => x,
+        None => return super::sqrt(a, rnd),
+    };
+    merge(ctx.collect(a.par_chunks(CHUNK), |a| super::sqrt(a, rnd)))
+}
+
+/// Computes the square root of each element in parallel, with the exception flags of each.
+pub fn sqrt_per_lane<T: SoftFloat + Send + Sync>(
+    a: &[T],
+    rnd: RoundingMode,
+) -> (Vec<T>, Vec<ExceptionFlags>) {
+    let ctx = match Context::save() {
+        Some(x) => x,
+        None => return super::sqrt_per_lane(a, rnd),
+    };
+    concat(ctx.collect(a.par_chunks(CHUNK), |a| super::sqrt_per_lane(a, rnd)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::F64;
+
+    #[test]
+    fn par_matches_sequential() {
+        let a: Vec<_> = (0..20000u64)
+            .map(|x| F64::from_bits(x.wrapping_mul(0x9e3779b97f4a7c15)))
+            .collect();
+        let b: Vec<_> = a.iter().rev().copied().collect();
+
+        let (x, xf) = super::super::div(&a, &b, RoundingMode::TowardZero);
+        let (y, yf) = div(&a, &b, RoundingMode::TowardZero);
+        assert!(x.iter().zip(&y).all(|(x, y)| x.to_bits() == y.to_bits()));
+        assert_eq!(xf.to_bits(), yf.to_bits());
+
+        let (x, xf) = super::super::mul_per_lane(&a, &b, RoundingMode::TowardPositive);
+        let (y, yf) = mul_per_lane(&a, &b, RoundingMode::TowardPositive);
+        assert!(x.iter().zip(&y).all(|(x, y)| x.to_bits() == y.to_bits()));
+        assert!(xf.iter().zip(&yf).all(|(x, y)| x.to_bits() == y.to_bits()));
+        assert_eq!(y.len(), 20000);
+    }
+
+    #[test]
+    fn par_state_of_caller() {
+        // a NaN with a payload and 1, and a value which is tiny before rounding only
+        let a: Vec<_> = [0x7ff8000000012345, 0x000fffffffffffff]
+            .repeat(10000)
+            .into_iter()
+            .map(F64::from_bits)
+            .collect();
+        let b: Vec<_> = [0x3ff0000000000000, 0x3ff0000000000001]
+            .repeat(10000)
+            .into_iter()
+            .map(F64::from_bits)
+            .collect();
+        SoftFloatState {
+            default_nan_mode: true,
+            tininess_before_rounding: true,
+            ..SoftFloatState::default()
+        }
+        .restore();
+
+        let rnd = RoundingMode::TiesToEven;
+        let (x, xf) = super::super::mul_per_lane(&a, &b, rnd);
+        let (y, yf) = mul_per_lane(&a, &b, rnd);
+        assert!(x.iter().zip(&y).all(|(x, y)| x.to_bits() == y.to_bits()));
+        assert!(xf.iter().zip(&yf).all(|(x, y)| x.to_bits() == y.to_bits()));
+        assert_eq!(y[0].to_bits(), 0x7ff8000000000000);
+        assert!(yf[1].is_underflow());
+
+        // the operations of the workers are counted by the caller
+        #[cfg(feature = "stats")]
+        {
+            crate::stats::reset();
+            mul(&a, &b, rnd);
+            assert_eq!(crate::stats::snapshot().count("f64_mul"), 20000);
+        }
+        SoftFloatState::default().restore();
+    }
+}
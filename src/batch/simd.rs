@@ -0,0 +1,292 @@
+//! Slice kernels on the SIMD units of the host, with `std::simd`
+//!
+//! When rounding to nearest, ties to even, the kernels compute chunks of [`F32`] and [`F64`]
+//! elements with the vector instructions of the host, which round the same way, and detect
+//! inexact results with error-free transformations: the rounding error of a sum is
+//! recovered by 2Sum, and the one of a product, a quotient or a square root by a fused
+//! multiply-add. A chunk with a lane which may not be computed exactly this way, such as a
+//! NaN, an infinity, a division by zero, an overflow or a result small enough to underflow,
+//! runs in softfloat instead, as do the remaining elements and other rounding modes. The
+//! results and flags are those of the sequential kernels.
+//!
+//! Like those of the [fast path](crate::fast), the chunks computed on the host bypass the
+//! backend, the trace hook, the statistics and the trap handlers.
+//!
+//! ```
+//! use softfloat_wrapper::batch::simd;
+//! use softfloat_wrapper::{RoundingMode, F32};
+//!
+//! let a = [F32::from_bits(0x3f800000); 16];
+//! let b = [F32::from_bits(0x40400000); 16];
+//! let (ret, flags) = simd::div(&a, &b, RoundingMode::TiesToEven);
+//! assert!(ret.iter().all(|x| x.to_bits() == 0x3eaaaaab));
+//! assert!(flags.is_inexact());
+//! ```
+//!
+//! # Panics
+//!
+//! The kernels panic if the operand slices have different lengths.
+
+use super::check_len;
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat, F32, F64};
+use std::simd::prelude::*;
+use std::simd::StdFloat;
+
+mod sealed {
+    pub trait Sealed {}
+
+    /// An operation of the kernels
+    #[derive(Copy, Clone, Debug)]
+    pub enum Op {
+        Add,
+        Sub,
+        Mul,
+        Div,
+        Sqrt,
+    }
+}
+
+use sealed::Op;
+
+/// Float types with SIMD kernels: [`F32`] and [`F64`]
+pub trait Vectorized: SoftFloat + Copy + sealed::Sealed {
+    /// number of elements of a chunk
+    #[doc(hidden)]
+    const LANES: usize;
+
+    /// Computes `op` of a chunk of `LANES` elements on the host, and returns whether it is
+    /// inexact, or `None` if a lane has to run in softfloat.
+    #[doc(hidden)]
+    fn chunk(op: Op, dst: &mut [Self], a: &[Self], b: &[Self]) -> Option<bool>;
+}
+
+macro_rules! impl_vectorized {
+    ($ty:ident, $float:ident, $lanes:literal) => {
+        impl sealed::Sealed for $ty {}
+
+        impl Vectorized for $ty {
+            const LANES: usize = $lanes;
+
+            fn chunk(op: Op, dst: &mut [Self], a: &[Self], b: &[Self]) -> Option<bool> {
+                type V = Simd<$float, $lanes>;
+                let load = |x: &[Self]| {
+                    V::from_array(std::array::from_fn(|i| $float::from_bits(x[i].to_bits())))
+                };
+                let (a, b) = (load(a), load(b));
+                let zero = V::splat(0.0);
+                // 2^(emin + 2p): the errors of operations on values at least this large, or
+                // giving results at least this large, are exact
+                let tiny = V::splat(
+                    $float::MIN_POSITIVE * (2.0 as $float).powi(2 * $float::MANTISSA_DIGITS as i32),
+                );
+                let large = |x: V| x.abs().simd_ge(tiny) | x.simd_eq(zero);
+                let finite = a.is_finite() & b.is_finite();
+
+                let (ret, err, exact) = match op {
+                    Op::Add | Op::Sub => {
+                        let b = if matches!(op, Op::Sub) { -b } else { b };
+                        let s = a + b;
+                        let bb = s - a;
+                        let err = (a - (s - bb)) + (b - bb);
+                        // an overflow, including a spurious one, makes the error infinite
+                        // or NaN
+                        (s, err, finite & err.is_finite())
+                    }
+                    Op::Mul => {
+                        let p = a * b;
+                        let err = a.mul_add(b, -p);
+                        let zero_operand = a.simd_eq(zero) | b.simd_eq(zero);
+                        (
+                            p,
+                            err,
+                            finite & p.is_finite() & (p.abs().simd_ge(tiny) | zero_operand),
+                        )
+                    }
+                    Op::Div => {
+                        let q = a / b;
+                        let err = (-q).mul_add(b, a);
+                        let normal = q.abs().simd_ge(V::splat($float::MIN_POSITIVE));
+                        let ok =
+                            b.simd_ne(zero) & q.is_finite() & large(a) & (normal | a.simd_eq(zero));
+                        (q, err, finite & ok)
+                    }
+                    Op::Sqrt => {
+                        let r = a.sqrt();
+                        let err = (-r).mul_add(r, a);
+                        (r, err, a.is_finite() & large(a) & a.simd_ge(zero))
+                    }
+                };
+                if !exact.all() {
+                    return None;
+                }
+                for (d, x) in dst.iter_mut().zip(ret.to_array()) {
+                    *d = $ty::from_bits(x.to_bits());
+                }
+                Some(err.simd_ne(zero).any())
+            }
+        }
+    };
+}
+
+impl_vectorized!(F32, f32, 8);
+impl_vectorized!(F64, f64, 4);
+
+fn run<T: Vectorized>(
+    op: Op,
+    dst: &mut [T],
+    a: &[T],
+    b: &[T],
+    rnd: RoundingMode,
+) -> ExceptionFlags {
+    let host = matches!(rnd, RoundingMode::TiesToEven);
+    with_flags(|| {
+        let mut inexact = false;
+        let chunks = dst
+            .chunks_mut(T::LANES)
+            .zip(a.chunks(T::LANES))
+            .zip(b.chunks(T::LANES));
+        for ((dst, a), b) in chunks {
+            if host && dst.len() == T::LANES {
+                if let Some(x) = T::chunk(op, dst, a, b) {
+                    inexact |= x;
+                    continue;
+                }
+            }
+            for ((d, a), b) in dst.iter_mut().zip(a).zip(b) {
+                *d = match op {
+                    Op::Add => a.add(b, rnd),
+                    Op::Sub => a.sub(b, rnd),
+                    Op::Mul => a.mul(b, rnd),
+                    Op::Div => a.div(b, rnd),
+                    Op::Sqrt => a.sqrt(rnd),
+                };
+            }
+        }
+        if inexact {
+            ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT).raise();
+        }
+    })
+    .1
+}
+
+macro_rules! binary {
+    ($name:ident, $into:ident, $op:ident, $doc:literal) => {
+        #[doc = concat!("Computes ", $doc, " of each pair of elements, merging the exception flags.")]
+        pub fn $name<T: Vectorized>(a: &[T], b: &[T], rnd: RoundingMode) -> (Vec<T>, ExceptionFlags) {
+            let mut dst = a.to_vec();
+            let flags = $into(&mut dst, a, b, rnd);
+            (dst, flags)
+        }
+
+        #[doc = concat!("Writes ", $doc, " of each pair of elements to `dst`, merging the exception flags.")]
+        pub fn $into<T: Vectorized>(dst: &mut [T], a: &[T], b: &[T], rnd: RoundingMode) -> ExceptionFlags {
+            check_len(dst.len(), a.len());
+            check_len(a.len(), b.len());
+            run(Op::$op, dst, a, b, rnd)
+        }
+    };
+}
+
+binary!(add, add_into, Add, "the sum");
+binary!(sub, sub_into, Sub, "the difference");
+binary!(mul, mul_into, Mul, "the product");
+binary!(div, div_into, Div, "the quotient");
+
+/// Computes the square root of each element, merging the exception flags.
+pub fn sqrt<T: Vectorized>(a: &[T], rnd: RoundingMode) -> (Vec<T>, ExceptionFlags) {
+    let mut dst = a.to_vec();
+    let flags = sqrt_into(&mut dst, a, rnd);
+    (dst, flags)
+}
+
+/// Writes the square root of each element to `dst`, merging the exception flags.
+pub fn sqrt_into<T: Vectorized>(dst: &mut [T], a: &[T], rnd: RoundingMode) -> ExceptionFlags {
+    check_len(dst.len(), a.len());
+    run(Op::Sqrt, dst, a, a, rnd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch;
+
+    type Kernel<T> = fn(&[T], &[T], RoundingMode) -> (Vec<T>, ExceptionFlags);
+
+    fn check<T: Vectorized + std::fmt::Debug>(values: &[T]) {
+        let n = values.len();
+        // every pair of values, in chunks with and without special lanes
+        let a: Vec<T> = (0..n * n).map(|i| values[i / n]).collect();
+        let b: Vec<T> = (0..n * n).map(|i| values[i % n]).collect();
+        let simd_ops: [Kernel<T>; 5] = [add, sub, mul, div, |a, _, rnd| sqrt(a, rnd)];
+        let soft_ops: [Kernel<T>; 5] = [
+            batch::add,
+            batch::sub,
+            batch::mul,
+            batch::div,
+            |a, _, rnd| batch::sqrt(a, rnd),
+        ];
+        for rnd in [RoundingMode::TiesToEven, RoundingMode::TowardZero] {
+            for (simd_op, soft_op) in simd_ops.iter().zip(soft_ops) {
+                let compare = |a: &[T], b: &[T]| {
+                    let (ret, flags) = simd_op(a, b, rnd);
+                    let (expected, expected_flags) = soft_op(a, b, rnd);
+                    assert_eq!(flags.to_bits(), expected_flags.to_bits());
+                    for (x, y) in ret.iter().zip(&expected) {
+                        assert!(x.to_bits() == y.to_bits(), "{:?} {:?}", x, y);
+                    }
+                };
+                for len in [n * n, T::LANES * 2, T::LANES + 1] {
+                    compare(&a[..len], &b[..len]);
+                    // one value against the others, so that most chunks have no special lane
+                    for x in values {
+                        compare(&vec![*x; len], &b[..len]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simd_kernels() {
+        check(
+            &[
+                0x00000000, 0x80000000, 0x00000001, 0x00800000, 0x0c000000, 0x1a000000, 0x3eaaaaab,
+                0x3f800000, 0x3f800001, 0x40400000, 0x40800000, 0x4b800001, 0x7f7fffff, 0xbf800000,
+                0xc0490fdb, 0x7f800000, 0x7fc00000,
+            ]
+            .map(F32::from_bits),
+        );
+        check(
+            &[
+                0x0000000000000000,
+                0x0000000000000001,
+                0x0010000000000000,
+                0x0690000000000000,
+                0x3fd5555555555555,
+                0x3ff0000000000000,
+                0x3ff0000000000001,
+                0x4008000000000000,
+                0x4340000000000001,
+                0x7fefffffffffffff,
+                0xbff0000000000000,
+                0xfff0000000000000,
+            ]
+            .map(F64::from_bits),
+        );
+
+        // chunks of normal values run on the host, and those with a special lane do not
+        let mut dst = [F32::from_bits(0); 8];
+        let mut a = [F32::from_bits(0x3f800000); 8];
+        let b = [F32::from_bits(0x40400000); 8];
+        assert_eq!(F32::chunk(Op::Div, &mut dst, &a, &b), Some(true));
+        assert_eq!(F32::chunk(Op::Mul, &mut dst, &a, &b), Some(false));
+        a[7] = F32::from_bits(0x7f800000);
+        assert_eq!(F32::chunk(Op::Add, &mut dst, &a, &b), None);
+
+        let mut dst = [F64::from_bits(0); 4];
+        let a = [0x4010000000000000; 4].map(F64::from_bits);
+        let flags = sqrt_into(&mut dst, &a, RoundingMode::TiesToEven);
+        assert!(dst.iter().all(|x| x.to_bits() == 0x4000000000000000));
+        assert_eq!(flags.to_bits(), 0);
+    }
+}
@@ -0,0 +1,128 @@
+//! [num-bigint](https://crates.io/crates/num-bigint) support
+//!
+//! Big integers convert to the float types with a single rounding, however large they are.
+//! Float values convert back exactly, and only when they are integers.
+
+use crate::{round_pack, unpack, RoundingMode, SoftFloat};
+use ::num_bigint::{BigInt, BigUint, Sign};
+
+/// Rounds `x`, negated if `negative`, to `T`.
+pub(crate) fn from_biguint<T: SoftFloat>(negative: bool, x: &BigUint, rnd: RoundingMode) -> T {
+    // 126 bits are enough for round_pack, if the discarded ones are or'ed into the lowest
+    let shift = x.bits().saturating_sub(126);
+    let digits = (x >> shift).to_u64_digits();
+    let mut sig = digits
+        .iter()
+        .rev()
+        .fold(0u128, |acc, x| acc << 64 | *x as u128);
+    if shift != 0 && x.trailing_zeros().unwrap() < shift {
+        sig |= 1;
+    }
+    // exponents beyond the range of every format overflow all the same
+    round_pack(negative, shift.min(1 << 20) as i32, sig, rnd)
+}
+
+/// Returns `x` if it is an integer.
+pub(crate) fn to_bigint<T: SoftFloat>(x: &T) -> Option<BigInt> {
+    if !x.is_finite() {
+        return None;
+    }
+    let (negative, exp, sig) = unpack(x);
+    let mag = if exp >= 0 {
+        BigUint::from(sig) << exp as usize
+    } else if exp > -128 && sig & ((1 << -exp) - 1) == 0 {
+        BigUint::from(sig >> -exp)
+    } else if sig == 0 {
+        BigUint::from(0u8)
+    } else {
+        return None;
+    };
+    let sign = if negative { Sign::Minus } else { Sign::Plus };
+    Some(BigInt::from_biguint(sign, mag))
+}
+
+/// Returns `x` as an odd significand and a power-of-two exponent.
+pub(crate) fn as_ratio<T: SoftFloat>(x: &T) -> Option<(BigInt, i32)> {
+    if !x.is_finite() {
+        return None;
+    }
+    let (negative, exp, sig) = unpack(x);
+    if sig == 0 {
+        return Some((BigInt::from(0), 0));
+    }
+    let zeros = sig.trailing_zeros();
+    let sign = if negative { Sign::Minus } else { Sign::Plus };
+    let mag = BigUint::from(sig >> zeros);
+    Some((BigInt::from_biguint(sign, mag), exp + zeros as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "f128")]
+    use crate::F128;
+    use crate::{with_flags, FloatBits, RoundingMode, SoftFloat, F16, F64};
+    use ::num_bigint::{BigInt, BigUint};
+
+    #[test]
+    fn bigint_from() {
+        let rnd = RoundingMode::TiesToEven;
+        // 2^200 + 2^147 + 1 is just above a tie in F64
+        let one = BigUint::from(1u8);
+        let x = (&one << 200usize) + (&one << 147usize) + one;
+        let (ret, flags) = with_flags(|| F64::from_biguint(&x, rnd));
+        assert_eq!(ret.to_bits(), 0x4c70000000000001);
+        assert!(flags.is_inexact());
+
+        let x = -BigInt::from(65504i64);
+        let (ret, flags) = with_flags(|| F16::from_bigint(&x, rnd));
+        assert_eq!(ret.to_bits(), 0xfbff);
+        assert_eq!(flags.to_bits(), 0);
+        let x = BigInt::from(BigUint::from(1u8) << 100000usize);
+        let (ret, flags) = with_flags(|| F16::from_bigint(&x, rnd));
+        assert!(ret.is_infinity());
+        assert!(flags.is_overflow());
+        assert_eq!(F16::from_bigint(&BigInt::from(0i64), rnd).to_bits(), 0);
+    }
+
+    #[test]
+    fn bigint_to() {
+        let a = F64::from_bits(0xc340000000000001); // -(2^52 + 1) * 2
+        assert_eq!(a.to_bigint(), Some(-BigInt::from(0x20000000000002i64)));
+        assert_eq!(F64::from_bits(0x3ff8000000000000).to_bigint(), None);
+        assert_eq!(F64::from_bits(0x0000000000000001).to_bigint(), None);
+        assert_eq!(
+            F64::from_bits(0x8000000000000000).to_bigint(),
+            Some(BigInt::from(0i64))
+        );
+        assert_eq!(F64::infinity().to_bigint(), None);
+        assert_eq!(F64::quiet_nan().to_bigint(), None);
+    }
+
+    #[test]
+    fn bigint_as_ratio() {
+        let a = F64::from_bits(0x0000000000000003);
+        assert_eq!(a.as_ratio(), Some((BigInt::from(3), -1074)));
+        let a = F16::from_bits(0x7bff);
+        assert_eq!(a.as_ratio(), Some((BigInt::from(0x7ff), 5)));
+        let a = F16::from_bits(0x8000);
+        assert_eq!(a.as_ratio(), Some((BigInt::from(0), 0)));
+        assert_eq!(F16::quiet_nan().as_ratio(), None);
+    }
+
+    #[cfg(feature = "f128")]
+    #[test]
+    fn bigint_round_trip_f128() {
+        let rnd = RoundingMode::TiesToEven;
+        for bits in [
+            0x7ffeffffffffffffffffffffffffffff,
+            0x406f123456789abcdef0123456789abc,
+            0xc0700000000000000000000000000001,
+        ] {
+            let a = F128::from_bits(bits);
+            let x = a.to_bigint().unwrap();
+            let (ret, flags) = with_flags(|| F128::from_bigint(&x, rnd));
+            assert_eq!(ret.to_bits(), bits);
+            assert_eq!(flags.to_bits(), 0);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! [bytemuck](https://crates.io/crates/bytemuck) support
+//!
+//! The float types are `#[repr(transparent)]` wrappers of their bit patterns without
+//! padding, and every bit pattern is a valid value, so register files and memory images
+//! can be reinterpreted as slices of them.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{F16, F32, F64};
+
+unsafe impl ::bytemuck::Zeroable for F16 {}
+unsafe impl ::bytemuck::Pod for F16 {}
+
+unsafe impl ::bytemuck::Zeroable for F32 {}
+unsafe impl ::bytemuck::Pod for F32 {}
+
+unsafe impl ::bytemuck::Zeroable for F64 {}
+unsafe impl ::bytemuck::Pod for F64 {}
+
+#[cfg(feature = "f128")]
+unsafe impl ::bytemuck::Zeroable for F128 {}
+#[cfg(feature = "f128")]
+unsafe impl ::bytemuck::Pod for F128 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytemuck_cast() {
+        let regs = [0x3f800000u32, 0x40000000];
+        let a: &[F32] = ::bytemuck::cast_slice(&regs);
+        assert_eq!(a[1].to_bits(), 0x40000000);
+
+        let mut regs = [0u16; 2];
+        let a: &mut [F16] = ::bytemuck::cast_slice_mut(&mut regs);
+        a[0] = F16::from_bits(0x3c00);
+        assert_eq!(regs[0], 0x3c00);
+
+        let a: F64 = ::bytemuck::Zeroable::zeroed();
+        assert_eq!(a.to_bits(), 0);
+    }
+}
@@ -0,0 +1,84 @@
+//! Memory representations of the float types
+//!
+//! The byte conversions read and write the interchange encoding in a given byte order, like
+//! those of the primitive types, so values can be copied from and into guest memory images
+//! whatever the endianness of the host.
+//!
+//! ```
+//! use softfloat_wrapper::F32;
+//!
+//! let memory = [0x00, 0x00, 0x80, 0x3f, 0x3f, 0x80, 0x00, 0x00];
+//! let a = F32::from_le_bytes([memory[0], memory[1], memory[2], memory[3]]);
+//! let b = F32::from_be_bytes([memory[4], memory[5], memory[6], memory[7]]);
+//! assert_eq!(a.to_bits(), 0x3f800000);
+//! assert_eq!(b.to_bits(), 0x3f800000);
+//! assert_eq!(a.to_be_bytes(), [0x3f, 0x80, 0x00, 0x00]);
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{F16, F32, F64};
+
+macro_rules! impl_bytes {
+    ($ty:ident, $bits:ty) => {
+        impl $ty {
+            /// Returns the memory representation of `self` in little-endian byte order.
+            pub const fn to_le_bytes(&self) -> [u8; core::mem::size_of::<$bits>()] {
+                self.to_bits().to_le_bytes()
+            }
+
+            /// Returns the memory representation of `self` in big-endian byte order.
+            pub const fn to_be_bytes(&self) -> [u8; core::mem::size_of::<$bits>()] {
+                self.to_bits().to_be_bytes()
+            }
+
+            /// Returns the memory representation of `self` in the byte order of the host.
+            pub const fn to_ne_bytes(&self) -> [u8; core::mem::size_of::<$bits>()] {
+                self.to_bits().to_ne_bytes()
+            }
+
+            /// Creates a value from its memory representation in little-endian byte order.
+            pub const fn from_le_bytes(bytes: [u8; core::mem::size_of::<$bits>()]) -> Self {
+                Self::from_bits(<$bits>::from_le_bytes(bytes))
+            }
+
+            /// Creates a value from its memory representation in big-endian byte order.
+            pub const fn from_be_bytes(bytes: [u8; core::mem::size_of::<$bits>()]) -> Self {
+                Self::from_bits(<$bits>::from_be_bytes(bytes))
+            }
+
+            /// Creates a value from its memory representation in the byte order of the host.
+            pub const fn from_ne_bytes(bytes: [u8; core::mem::size_of::<$bits>()]) -> Self {
+                Self::from_bits(<$bits>::from_ne_bytes(bytes))
+            }
+        }
+    };
+}
+
+impl_bytes!(F16, u16);
+impl_bytes!(F32, u32);
+impl_bytes!(F64, u64);
+#[cfg(feature = "f128")]
+impl_bytes!(F128, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_order() {
+        let a = F16::from_bits(0x3c01);
+        assert_eq!(a.to_le_bytes(), [0x01, 0x3c]);
+        assert_eq!(a.to_be_bytes(), [0x3c, 0x01]);
+        assert_eq!(F16::from_be_bytes([0x3c, 0x01]).to_bits(), 0x3c01);
+        assert_eq!(F16::from_ne_bytes(a.to_ne_bytes()).to_bits(), 0x3c01);
+
+        let b = F64::from_bits(0x0102030405060708);
+        assert_eq!(b.to_le_bytes(), [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(F64::from_le_bytes(b.to_le_bytes()).to_bits(), b.to_bits());
+        assert_eq!(
+            F64::from_be_bytes([1, 2, 3, 4, 5, 6, 7, 8]).to_bits(),
+            b.to_bits()
+        );
+    }
+}
@@ -0,0 +1,538 @@
+//! C API
+//!
+//! `extern "C"` functions for emulators written in C or C++, with the names of the Berkeley
+//! SoftFloat functions they replace prefixed with `sfw_`. Instead of the global rounding
+//! mode and exception flags of SoftFloat, every function takes a context, a
+//! [`SoftFloatState`] created by [`sfw_context_new`], whose rounding mode the operations
+//! round per and whose exception flags they raise, so each guest core or thread of the
+//! emulator keeps its own. Floats are passed as their bits, and `F128` as [`Float128`], of
+//! the layout of SoftFloat's `float128_t`.
+//!
+//! Rounding modes and exception flags have the values of the `softfloat_round_*` and
+//! `softfloat_flag_*` constants of SoftFloat. The declarations are in
+//! `include/softfloat_wrapper.h`, and a shared library is built with
+//! `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! # Safety
+//!
+//! The functions taking a context must be passed a pointer returned by
+//! [`sfw_context_new`] and not yet freed, which no other thread uses at the same time.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{
+    ExceptionFlags, FloatArith, FloatCompare, FloatConvert, RoundingMode, SoftFloat,
+    SoftFloatState, F16, F32, F64,
+};
+
+/// The bits of an `F128`, least significant word first on little-endian targets, like
+/// SoftFloat's `float128_t`
+#[cfg(feature = "f128")]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Float128 {
+    pub v: [u64; 2],
+}
+
+/// Float types with a C representation
+trait CFloat {
+    type C;
+
+    fn from_c(x: Self::C) -> Self;
+
+    fn to_c(self) -> Self::C;
+}
+
+macro_rules! impl_c_float {
+    ($ty:ident, $c:ty) => {
+        impl CFloat for $ty {
+            type C = $c;
+
+            #[inline]
+            fn from_c(x: $c) -> Self {
+                $ty::from_bits(x)
+            }
+
+            #[inline]
+            fn to_c(self) -> $c {
+                self.to_bits()
+            }
+        }
+    };
+}
+
+impl_c_float!(F16, u16);
+impl_c_float!(F32, u32);
+impl_c_float!(F64, u64);
+
+#[cfg(feature = "f128")]
+impl CFloat for F128 {
+    type C = Float128;
+
+    #[inline]
+    fn from_c(x: Float128) -> Self {
+        let (lo, hi) = if cfg!(target_endian = "little") {
+            (x.v[0], x.v[1])
+        } else {
+            (x.v[1], x.v[0])
+        };
+        F128::from_bits((hi as u128) << 64 | lo as u128)
+    }
+
+    #[inline]
+    fn to_c(self) -> Float128 {
+        let (lo, hi) = (self.to_bits() as u64, (self.to_bits() >> 64) as u64);
+        if cfg!(target_endian = "little") {
+            Float128 { v: [lo, hi] }
+        } else {
+            Float128 { v: [hi, lo] }
+        }
+    }
+}
+
+/// Runs `f` with the rounding mode of `ctx`, with `ctx` as the state of the thread.
+#[inline]
+unsafe fn run<R>(ctx: *mut SoftFloatState, f: impl FnOnce(RoundingMode) -> R) -> R {
+    let ctx = &mut *ctx;
+    let rnd = ctx.rounding_mode;
+    ctx.run(|| f(rnd))
+}
+
+/// Creates a context rounding to nearest, ties to even, with no exception flags raised.
+/// It is freed with [`sfw_context_free`].
+#[no_mangle]
+pub extern "C" fn sfw_context_new() -> *mut SoftFloatState {
+    Box::into_raw(Box::default())
+}
+
+/// Frees a context created by [`sfw_context_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `ctx` must be null, or a context which is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_context_free(ctx: *mut SoftFloatState) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// Returns the rounding mode of `ctx`, as a `softfloat_round_*` value.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_get_rounding_mode(ctx: *const SoftFloatState) -> u8 {
+    (*ctx).rounding_mode.to_riscv_frm()
+}
+
+/// Makes the operations on `ctx` round per the `softfloat_round_*` value `rnd`, and returns
+/// whether it is one. `softfloat_round_odd` is not.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_set_rounding_mode(ctx: *mut SoftFloatState, rnd: u8) -> bool {
+    // the softfloat_round_* values are the RISC-V encodings
+    match RoundingMode::from_riscv_frm(rnd) {
+        Some(rnd) => {
+            (*ctx).rounding_mode = rnd;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns the exception flags raised in `ctx`, as `softfloat_flag_*` bits.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_get_flags(ctx: *const SoftFloatState) -> u8 {
+    (*ctx).flags.to_bits()
+}
+
+/// Replaces the exception flags of `ctx` with the `softfloat_flag_*` bits `flags`.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_set_flags(ctx: *mut SoftFloatState, flags: u8) {
+    (*ctx).flags = ExceptionFlags::from_bits(flags);
+}
+
+/// Makes the operations on `ctx` return the default NaN for every NaN result if `enabled`.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sfw_set_default_nan_mode(ctx: *mut SoftFloatState, enabled: bool) {
+    (*ctx).default_nan_mode = enabled;
+}
+
+macro_rules! c_binary {
+    ($ty:ident($c:ty), $($name:ident => $op:ident),*) => {
+        $(
+            /// # Safety
+            ///
+            /// `ctx` must be a valid context.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                ctx: *mut SoftFloatState,
+                a: $c,
+                b: $c,
+            ) -> $c {
+                run(ctx, |rnd| $ty::from_c(a).$op($ty::from_c(b), rnd).to_c())
+            }
+        )*
+    };
+}
+
+macro_rules! c_compare {
+    ($ty:ident($c:ty), $($name:ident => $op:ident),*) => {
+        $(
+            /// # Safety
+            ///
+            /// `ctx` must be a valid context.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                ctx: *mut SoftFloatState,
+                a: $c,
+                b: $c,
+            ) -> bool {
+                run(ctx, |_| FloatCompare::$op(&$ty::from_c(a), $ty::from_c(b)))
+            }
+        )*
+    };
+}
+
+macro_rules! c_to_int {
+    ($ty:ident($c:ty), $($name:ident => $op:ident -> $int:ty),*) => {
+        $(
+            /// Converts `a` per the rounding mode of `ctx`, raising inexact if `exact` and
+            /// the result is not `a`.
+            ///
+            /// # Safety
+            ///
+            /// `ctx` must be a valid context.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                ctx: *mut SoftFloatState,
+                a: $c,
+                exact: bool,
+            ) -> $int {
+                run(ctx, |rnd| $ty::from_c(a).$op(rnd, exact))
+            }
+        )*
+    };
+}
+
+macro_rules! c_from {
+    ($ty:ident($c:ty), $($name:ident => $op:ident($src:ty)),*) => {
+        $(
+            /// # Safety
+            ///
+            /// `ctx` must be a valid context.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                ctx: *mut SoftFloatState,
+                a: $src,
+            ) -> $c {
+                run(ctx, |rnd| <$ty as CFloat>::to_c(<$ty>::$op(a, rnd)))
+            }
+        )*
+    };
+}
+
+macro_rules! c_convert {
+    ($($name:ident => $src:ident($sc:ty) -> $dst:ident($dc:ty)),*) => {
+        $(
+            /// # Safety
+            ///
+            /// `ctx` must be a valid context.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                ctx: *mut SoftFloatState,
+                a: $sc,
+            ) -> $dc {
+                run(ctx, |rnd| $src::from_c(a).convert::<$dst>(rnd).to_c())
+            }
+        )*
+    };
+}
+
+macro_rules! c_format {
+    (
+        $ty:ident($c:ty),
+        $add:ident,
+        $sub:ident,
+        $mul:ident,
+        $div:ident,
+        $rem:ident,
+        $mul_add:ident,
+        $sqrt:ident,
+        $round_to_int:ident,
+        $eq:ident,
+        $lt:ident,
+        $le:ident
+    ) => {
+        c_binary!($ty($c), $add => add, $sub => sub, $mul => mul, $div => div, $rem => rem);
+        c_compare!($ty($c), $eq => eq, $lt => lt, $le => le);
+
+        /// Returns `a * b + c`, rounded once.
+        ///
+        /// # Safety
+        ///
+        /// `ctx` must be a valid context.
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub unsafe extern "C" fn $mul_add(
+            ctx: *mut SoftFloatState,
+            a: $c,
+            b: $c,
+            c: $c,
+        ) -> $c {
+            run(ctx, |rnd| {
+                $ty::from_c(a)
+                    .fused_mul_add($ty::from_c(b), $ty::from_c(c), rnd)
+                    .to_c()
+            })
+        }
+
+        /// # Safety
+        ///
+        /// `ctx` must be a valid context.
+        #[no_mangle]
+        pub unsafe extern "C" fn $sqrt(
+            ctx: *mut SoftFloatState,
+            a: $c,
+        ) -> $c {
+            run(ctx, |rnd| $ty::from_c(a).sqrt(rnd).to_c())
+        }
+
+        /// Rounds `a` to an integer per the rounding mode of `ctx`. Inexact is not raised.
+        ///
+        /// # Safety
+        ///
+        /// `ctx` must be a valid context.
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub unsafe extern "C" fn $round_to_int(
+            ctx: *mut SoftFloatState,
+            a: $c,
+        ) -> $c {
+            run(ctx, |rnd| $ty::from_c(a).round_to_integral(rnd).to_c())
+        }
+    };
+}
+
+c_format!(
+    F16(u16),
+    sfw_f16_add,
+    sfw_f16_sub,
+    sfw_f16_mul,
+    sfw_f16_div,
+    sfw_f16_rem,
+    sfw_f16_mulAdd,
+    sfw_f16_sqrt,
+    sfw_f16_roundToInt,
+    sfw_f16_eq,
+    sfw_f16_lt,
+    sfw_f16_le
+);
+c_format!(
+    F32(u32),
+    sfw_f32_add,
+    sfw_f32_sub,
+    sfw_f32_mul,
+    sfw_f32_div,
+    sfw_f32_rem,
+    sfw_f32_mulAdd,
+    sfw_f32_sqrt,
+    sfw_f32_roundToInt,
+    sfw_f32_eq,
+    sfw_f32_lt,
+    sfw_f32_le
+);
+c_format!(
+    F64(u64),
+    sfw_f64_add,
+    sfw_f64_sub,
+    sfw_f64_mul,
+    sfw_f64_div,
+    sfw_f64_rem,
+    sfw_f64_mulAdd,
+    sfw_f64_sqrt,
+    sfw_f64_roundToInt,
+    sfw_f64_eq,
+    sfw_f64_lt,
+    sfw_f64_le
+);
+#[cfg(feature = "f128")]
+c_format!(
+    F128(Float128),
+    sfw_f128_add,
+    sfw_f128_sub,
+    sfw_f128_mul,
+    sfw_f128_div,
+    sfw_f128_rem,
+    sfw_f128_mulAdd,
+    sfw_f128_sqrt,
+    sfw_f128_roundToInt,
+    sfw_f128_eq,
+    sfw_f128_lt,
+    sfw_f128_le
+);
+
+c_to_int!(
+    F16(u16),
+    sfw_f16_to_ui32 => to_u32 -> u32,
+    sfw_f16_to_ui64 => to_u64 -> u64,
+    sfw_f16_to_i32 => to_i32 -> i32,
+    sfw_f16_to_i64 => to_i64 -> i64
+);
+c_to_int!(
+    F32(u32),
+    sfw_f32_to_ui32 => to_u32 -> u32,
+    sfw_f32_to_ui64 => to_u64 -> u64,
+    sfw_f32_to_i32 => to_i32 -> i32,
+    sfw_f32_to_i64 => to_i64 -> i64
+);
+c_to_int!(
+    F64(u64),
+    sfw_f64_to_ui32 => to_u32 -> u32,
+    sfw_f64_to_ui64 => to_u64 -> u64,
+    sfw_f64_to_i32 => to_i32 -> i32,
+    sfw_f64_to_i64 => to_i64 -> i64
+);
+#[cfg(feature = "f128")]
+c_to_int!(
+    F128(Float128),
+    sfw_f128_to_ui32 => to_u32 -> u32,
+    sfw_f128_to_ui64 => to_u64 -> u64,
+    sfw_f128_to_i32 => to_i32 -> i32,
+    sfw_f128_to_i64 => to_i64 -> i64
+);
+
+c_from!(
+    F16(u16),
+    sfw_ui32_to_f16 => from_u32(u32),
+    sfw_ui64_to_f16 => from_u64(u64),
+    sfw_i32_to_f16 => from_i32(i32),
+    sfw_i64_to_f16 => from_i64(i64)
+);
+c_from!(
+    F32(u32),
+    sfw_ui32_to_f32 => from_u32(u32),
+    sfw_ui64_to_f32 => from_u64(u64),
+    sfw_i32_to_f32 => from_i32(i32),
+    sfw_i64_to_f32 => from_i64(i64)
+);
+c_from!(
+    F64(u64),
+    sfw_ui32_to_f64 => from_u32(u32),
+    sfw_ui64_to_f64 => from_u64(u64),
+    sfw_i32_to_f64 => from_i32(i32),
+    sfw_i64_to_f64 => from_i64(i64)
+);
+#[cfg(feature = "f128")]
+c_from!(
+    F128(Float128),
+    sfw_ui32_to_f128 => from_u32(u32),
+    sfw_ui64_to_f128 => from_u64(u64),
+    sfw_i32_to_f128 => from_i32(i32),
+    sfw_i64_to_f128 => from_i64(i64)
+);
+
+c_convert!(
+    sfw_f16_to_f32 => F16(u16) -> F32(u32),
+    sfw_f16_to_f64 => F16(u16) -> F64(u64),
+    sfw_f32_to_f16 => F32(u32) -> F16(u16),
+    sfw_f32_to_f64 => F32(u32) -> F64(u64),
+    sfw_f64_to_f16 => F64(u64) -> F16(u16),
+    sfw_f64_to_f32 => F64(u64) -> F32(u32)
+);
+#[cfg(feature = "f128")]
+c_convert!(
+    sfw_f16_to_f128 => F16(u16) -> F128(Float128),
+    sfw_f32_to_f128 => F32(u32) -> F128(Float128),
+    sfw_f64_to_f128 => F64(u64) -> F128(Float128),
+    sfw_f128_to_f16 => F128(Float128) -> F16(u16),
+    sfw_f128_to_f32 => F128(Float128) -> F32(u32),
+    sfw_f128_to_f64 => F128(Float128) -> F64(u64)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capi_context() {
+        unsafe {
+            let ctx = sfw_context_new();
+            assert_eq!(sfw_get_rounding_mode(ctx), 0);
+            assert!(sfw_set_rounding_mode(ctx, 1));
+            assert!(!sfw_set_rounding_mode(ctx, 6));
+            assert_eq!(sfw_get_rounding_mode(ctx), 1);
+
+            // 1 / 3 rounded toward zero, raising inexact in the context only
+            assert_eq!(sfw_f32_div(ctx, 0x3f800000, 0x40400000), 0x3eaaaaaa);
+            assert_eq!(sfw_get_flags(ctx), ExceptionFlags::FLAG_INEXACT);
+            let mut flags = ExceptionFlags::default();
+            flags.get();
+            assert_eq!(flags.to_bits(), 0);
+
+            sfw_set_flags(ctx, 0);
+            assert_eq!(
+                sfw_f64_mulAdd(
+                    ctx,
+                    0x4000000000000000,
+                    0x4008000000000000,
+                    0x3ff0000000000000
+                ),
+                0x401c000000000000
+            );
+            assert!(sfw_f16_lt(ctx, 0x3c00, 0x4000) && !sfw_f16_eq(ctx, 0x3c00, 0x4000));
+            assert_eq!(sfw_f32_to_i32(ctx, 0xbfc00000, true), -1);
+            assert_eq!(sfw_i64_to_f64(ctx, -3), 0xc008000000000000);
+            assert_eq!(sfw_f16_to_f32(ctx, 0x3c00), 0x3f800000);
+            assert_eq!(
+                sfw_f64_roundToInt(ctx, 0x3ff8000000000000),
+                0x3ff0000000000000
+            );
+            assert_eq!(sfw_get_flags(ctx), ExceptionFlags::FLAG_INEXACT);
+
+            sfw_set_default_nan_mode(ctx, true);
+            assert_eq!(sfw_f32_add(ctx, 0x7fc12345, 0x3f800000), 0x7fc00000);
+            sfw_context_free(ctx);
+            sfw_context_free(std::ptr::null_mut());
+        }
+    }
+
+    #[cfg(feature = "f128")]
+    #[test]
+    fn capi_f128() {
+        unsafe {
+            let ctx = sfw_context_new();
+            let one = sfw_i32_to_f128(ctx, 1);
+            assert_eq!(
+                F128::from_c(one).to_bits(),
+                0x3fff0000000000000000000000000000
+            );
+            assert_eq!(F128::from_c(one).to_c(), one);
+            let two = sfw_f128_add(ctx, one, one);
+            assert_eq!(sfw_f128_to_f64(ctx, two), 0x4000000000000000);
+            assert_eq!(
+                sfw_f128_to_i64(ctx, sfw_f128_sqrt(ctx, sfw_i32_to_f128(ctx, 9)), true),
+                3
+            );
+            assert_eq!(sfw_get_flags(ctx), 0);
+            sfw_context_free(ctx);
+        }
+    }
+}
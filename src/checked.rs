@@ -0,0 +1,258 @@
+//! Errors of the `try_` operations
+//!
+//! The `try_` methods of [`SoftFloat`](crate::SoftFloat) run an operation and turn the
+//! exceptions it raised into an [`OperationError`]: the [`FloatError`] of the exception,
+//! with the bits of the operands. Inexact results are not errors, as most results are
+//! rounded. The `_exactness` methods report whether the result is exact
+//! instead. The exception flags set before the call are left as they were.
+
+use crate::{with_flags, ExceptionFlags, SoftFloat};
+use num_traits::ToPrimitive;
+use std::fmt;
+
+/// The error type returned when a `try_` operation raises an exception.
+///
+/// When several exceptions are raised, the first variant listed here is returned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FloatError {
+    /// the operation is invalid, such as `0 / 0`, or a conversion is out of range
+    Invalid,
+    /// a finite value is divided by zero
+    DivByZero,
+    /// the result is too large to be represented
+    Overflow,
+    /// the result is too small to be represented without loss of precision
+    Underflow,
+}
+
+impl FloatError {
+    /// Returns the error for the exceptions in `flags`, if any is one.
+    pub fn from_flags(flags: ExceptionFlags) -> Option<Self> {
+        if flags.is_invalid() {
+            Some(FloatError::Invalid)
+        } else if flags.is_infinite() {
+            Some(FloatError::DivByZero)
+        } else if flags.is_overflow() {
+            Some(FloatError::Overflow)
+        } else if flags.is_underflow() {
+            Some(FloatError::Underflow)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for FloatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FloatError::Invalid => "invalid operation",
+            FloatError::DivByZero => "division by zero",
+            FloatError::Overflow => "result overflowed",
+            FloatError::Underflow => "result underflowed",
+        })
+    }
+}
+
+impl std::error::Error for FloatError {}
+
+/// The error type returned when a `try_` operation raises an exception, with the operands
+/// of the operation.
+///
+/// It compares equal to its [`FloatError`].
+///
+/// ```
+/// use softfloat_wrapper::{FloatError, RoundingMode, SoftFloat, F32};
+///
+/// let a = F32::from_bits(0x3f800000);
+/// let e = a.try_div(F32::from_bits(0), RoundingMode::TiesToEven).unwrap_err();
+/// assert_eq!(e, FloatError::DivByZero);
+/// assert_eq!(e.operands(), [0x3f800000, 0]);
+/// assert_eq!(e.to_string(), "division by zero, operands 0x3f800000, 0x0");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OperationError {
+    kind: FloatError,
+    operands: [u128; 3],
+    len: usize,
+}
+
+impl OperationError {
+    fn new(kind: FloatError, operands: &[u128]) -> Self {
+        let mut ret = Self {
+            kind,
+            operands: [0; 3],
+            len: operands.len(),
+        };
+        ret.operands[..operands.len()].copy_from_slice(operands);
+        ret
+    }
+
+    /// Returns the exception raised.
+    pub fn kind(&self) -> FloatError {
+        self.kind
+    }
+
+    /// Returns the bits of the operands, in the order the operation takes them.
+    pub fn operands(&self) -> &[u128] {
+        &self.operands[..self.len]
+    }
+}
+
+impl PartialEq<FloatError> for OperationError {
+    fn eq(&self, other: &FloatError) -> bool {
+        self.kind == *other
+    }
+}
+
+impl From<OperationError> for FloatError {
+    fn from(e: OperationError) -> Self {
+        e.kind
+    }
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, operands ", self.kind)?;
+        for (i, x) in self.operands().iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{:#x}", x)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OperationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// Returns the bits of `x`, widened.
+pub(crate) fn operand<T: SoftFloat>(x: &T) -> u128 {
+    x.to_bits().to_u128().unwrap()
+}
+
+/// Runs `f` on `operands`, failing with the exceptions it raised.
+pub(crate) fn checked<R>(operands: &[u128], f: impl FnOnce() -> R) -> Result<R, OperationError> {
+    let (ret, flags) = with_flags(f);
+    match FloatError::from_flags(flags) {
+        Some(e) => Err(OperationError::new(e, operands)),
+        None => Ok(ret),
+    }
+}
+
+/// Runs `f`, returning whether it did not raise inexact.
+pub(crate) fn exactness<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    let (ret, flags) = with_flags(f);
+    (ret, !flags.is_inexact())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, RoundingMode, SoftFloat, F32, F64};
+
+    #[test]
+    fn try_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+
+        let one = F32::from_bits(0x3f800000);
+        let zero = F32::from_bits(0x00000000);
+        assert_eq!(
+            one.try_div(F32::from_bits(0x40400000), rnd)
+                .unwrap()
+                .to_bits(),
+            0x3eaaaaab
+        );
+        assert_eq!(one.try_div(zero, rnd).unwrap_err(), FloatError::DivByZero);
+        assert_eq!(zero.try_div(zero, rnd).unwrap_err(), FloatError::Invalid);
+        assert_eq!(one.neg().try_sqrt(rnd).unwrap_err(), FloatError::Invalid);
+
+        let max = F32::from_bits(0x7f7fffff);
+        assert_eq!(max.try_add(max, rnd).unwrap_err(), FloatError::Overflow);
+        let min = F32::from_bits(0x00800000);
+        assert_eq!(min.try_mul(min, rnd).unwrap_err(), FloatError::Underflow);
+        // exact subnormal results do not underflow
+        assert!(min.try_sub(F32::from_bits(0x00000001), rnd).is_ok());
+
+        let a = F64::from_bits(0x41e0000000000000);
+        assert_eq!(a.try_to_i32(rnd).unwrap_err(), FloatError::Invalid);
+        assert_eq!(a.try_to_u32(rnd).unwrap(), 0x80000000);
+        assert_eq!(a.neg().try_to_i64(rnd).unwrap(), -0x80000000);
+        assert_eq!(
+            F64::quiet_nan().try_to_u64(rnd).unwrap_err(),
+            FloatError::Invalid
+        );
+        let e = one.try_fused_mul_add(zero, F32::infinity(), rnd).unwrap();
+        assert!(e.is_infinity());
+        let e = F32::infinity()
+            .try_fused_mul_add(zero, one, rnd)
+            .unwrap_err();
+        assert_eq!(e.kind(), FloatError::Invalid);
+        assert_eq!(e.operands(), [0x7f800000, 0, 0x3f800000]);
+        assert_eq!(
+            a.try_to_i32(rnd).unwrap_err().operands(),
+            [0x41e0000000000000]
+        );
+        assert_eq!(FloatError::from(e), FloatError::Invalid);
+
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+
+    #[test]
+    fn checked_to_int() {
+        let rnd = RoundingMode::TowardZero;
+        let mut flag = ExceptionFlags::from_bits(ExceptionFlags::FLAG_OVERFLOW);
+        flag.set();
+
+        let a = F32::from_bits(0xc0fe0000); // -7.9375
+        assert_eq!(a.to_i32_checked(rnd), Some(-7));
+        assert_eq!(a.to_i64_checked(RoundingMode::TiesToEven), Some(-8));
+        assert_eq!(a.to_u32_checked(rnd), None);
+        // rounding into range is not out of range
+        assert_eq!(F32::from_bits(0xbf000000).to_u64_checked(rnd), Some(0));
+        assert_eq!(F32::from_bits(0x4f000000).to_i32_checked(rnd), None);
+        assert_eq!(
+            F32::from_bits(0xcf000000).to_i32_checked(rnd),
+            Some(i32::MIN)
+        );
+        assert_eq!(F32::infinity().to_u64_checked(rnd), None);
+
+        flag.get();
+        assert_eq!(flag.to_bits(), ExceptionFlags::FLAG_OVERFLOW);
+    }
+
+    #[test]
+    fn exactness_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut flag = ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT);
+        flag.set();
+
+        let one = F32::from_bits(0x3f800000);
+        let three = F32::from_bits(0x40400000);
+        let (ret, exact) = one.div_exactness(three, rnd);
+        assert_eq!(ret.to_bits(), 0x3eaaaaab);
+        assert!(!exact);
+        let (ret, exact) = three.sub_exactness(one, rnd);
+        assert_eq!(ret.to_bits(), 0x40000000);
+        assert!(exact);
+        assert!(!one.add_exactness(F32::from_bits(0x33000000), rnd).1);
+        assert!(F32::from_bits(0x41100000).sqrt_exactness(rnd).1);
+        assert!(!three.sqrt_exactness(rnd).1);
+        // overflow is never exact, and invalid operations are
+        assert!(!F32::from_bits(0x7f7fffff).mul_exactness(three, rnd).1);
+        assert!(F32::zero().div_exactness(F32::zero(), rnd).1);
+
+        flag.get();
+        assert_eq!(flag.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        ExceptionFlags::default().set();
+        assert!(three.mul_exactness(three, rnd).1);
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+}
@@ -0,0 +1,252 @@
+//! Precision chopping
+//!
+//! A [`Chop`] describes a format narrower than the one of the values it operates on, by its
+//! precision and optionally its exponent range. Its operations compute the exact result
+//! of an operation of the float type, and round it once to that format, with subnormals
+//! and overflow handled like in an IEEE 754 format of those parameters. The result is
+//! returned in the float type, which holds it exactly. This emulates reduced-precision
+//! hardware, such as bfloat16 or half precision units, without a type for the format.
+//!
+//! ```
+//! use softfloat_wrapper::{Chop, RoundingMode, F32};
+//!
+//! // bfloat16 has the exponent range of F32 and 8 bits of precision
+//! let bf16 = Chop::new(8);
+//! let one = F32::from_bits(0x3f800000);
+//! let three = F32::from_bits(0x40400000);
+//! let ret = bf16.div(&one, &three, RoundingMode::TiesToEven);
+//! assert_eq!(ret.to_bits(), 0x3eab0000);
+//! ```
+//!
+//! Exception flags are raised for the rounding to the chopped format, not for the one of
+//! the float type. Tininess is detected after rounding.
+//!
+//! # Panics
+//!
+//! The operations panic if the precision is zero or greater than the one of the float
+//! type, or if the exponent range is empty or exceeds the one of the float type.
+
+use crate::explain::Operation;
+use crate::{overflows_to_infinity, round_pack, round_shift, unpack, with_flags};
+use crate::{ExceptionFlags, RoundingMode, SoftFloat};
+
+/// A format to round results to, given by its precision and exponent range
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Chop {
+    precision: u32,
+    exponent_range: Option<(i32, i32)>,
+}
+
+impl Chop {
+    /// Creates a format of `precision` bits, including the leading one, with the exponent
+    /// range of the float type it operates on.
+    pub fn new(precision: u32) -> Self {
+        Self {
+            precision,
+            exponent_range: None,
+        }
+    }
+
+    /// Restricts the exponents of normal numbers to `emin..=emax`, such as `-14..=15` for
+    /// half precision, with subnormals below `2^emin`.
+    pub fn with_exponent_range(self, emin: i32, emax: i32) -> Self {
+        Self {
+            exponent_range: Some((emin, emax)),
+            ..self
+        }
+    }
+
+    /// Returns the precision in bits, including the leading one.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns the exponent range set with [`with_exponent_range`](Self::with_exponent_range).
+    pub fn exponent_range(&self) -> Option<(i32, i32)> {
+        self.exponent_range
+    }
+
+    /// Rounds `x` to the format. NaNs and infinities are returned as they are.
+    pub fn round<T: SoftFloat>(&self, x: &T, rnd: RoundingMode) -> T {
+        let params = self.params::<T>();
+        if x.is_nan() || x.is_infinity() || x.is_zero() {
+            return T::from_bits(x.to_bits());
+        }
+        let (sign, exp, sig) = unpack(x);
+        round_to(params, sign, exp, sig, rnd)
+    }
+
+    pub fn add<T: SoftFloat>(&self, a: &T, b: &T, rnd: RoundingMode) -> T {
+        self.run(Operation::Add(a, b), rnd)
+    }
+
+    pub fn sub<T: SoftFloat>(&self, a: &T, b: &T, rnd: RoundingMode) -> T {
+        self.run(Operation::Sub(a, b), rnd)
+    }
+
+    pub fn mul<T: SoftFloat>(&self, a: &T, b: &T, rnd: RoundingMode) -> T {
+        self.run(Operation::Mul(a, b), rnd)
+    }
+
+    pub fn div<T: SoftFloat>(&self, a: &T, b: &T, rnd: RoundingMode) -> T {
+        self.run(Operation::Div(a, b), rnd)
+    }
+
+    pub fn sqrt<T: SoftFloat>(&self, a: &T, rnd: RoundingMode) -> T {
+        self.run(Operation::Sqrt(a), rnd)
+    }
+
+    /// Returns the precision, `emin` and `emax` of the format, checked against `T`.
+    fn params<T: SoftFloat>(&self) -> (i32, i32, i32) {
+        assert!(
+            self.precision >= 1 && self.precision <= T::MANTISSA_DIGITS,
+            "precision out of the range of the float type"
+        );
+        let (emin, emax) = self
+            .exponent_range
+            .unwrap_or((T::MIN_EXP - 1, T::MAX_EXP - 1));
+        assert!(
+            emin <= emax && emin >= T::MIN_EXP - 1 && emax < T::MAX_EXP,
+            "exponent range out of the one of the float type"
+        );
+        (self.precision as i32, emin, emax)
+    }
+
+    fn run<T: SoftFloat>(&self, op: Operation<'_, T>, rnd: RoundingMode) -> T {
+        let params = self.params::<T>();
+        let (result, flags) = with_flags(|| op.run(rnd));
+        // special results, including exact zeros whose sign depends on the rounding mode,
+        // are those of the float type
+        if result.is_nan() || flags.is_invalid() || flags.is_infinite() {
+            flags.raise();
+            return result;
+        }
+        match op.exact() {
+            Some((sign, exp, sig)) if sig != 0 => round_to(params, sign, exp, sig, rnd),
+            _ => {
+                flags.raise();
+                result
+            }
+        }
+    }
+}
+
+/// Rounds a non-zero `sig * 2^exp` to the format of `(prec, emin, emax)`, and returns it
+/// as a `T`, raising exception flags.
+fn round_to<T: SoftFloat>(
+    (prec, emin, emax): (i32, i32, i32),
+    sign: bool,
+    exp: i32,
+    sig: u128,
+    rnd: RoundingMode,
+) -> T {
+    let msb = 127 - sig.leading_zeros() as i32;
+    let e = exp.saturating_add(msb);
+    let keep = if e < emin {
+        prec.saturating_sub(emin.saturating_sub(e))
+    } else {
+        prec
+    };
+    let shift = (msb + 1).saturating_sub(keep);
+    let (q, inexact) = round_shift(sign, sig, shift, rnd);
+    let mut flags = 0;
+    if inexact {
+        flags |= ExceptionFlags::FLAG_INEXACT;
+        let carried = e == emin - 1 && round_shift(sign, sig, msb + 1 - prec, rnd).0 >> prec != 0;
+        if e < emin && !carried {
+            flags |= ExceptionFlags::FLAG_UNDERFLOW;
+        }
+    }
+
+    // the rounded values are exact in `T`
+    let ret = if e > emax || (e == emax && q >> prec != 0) {
+        flags |= ExceptionFlags::FLAG_OVERFLOW | ExceptionFlags::FLAG_INEXACT;
+        if overflows_to_infinity(sign, rnd) {
+            if sign {
+                T::infinity().neg()
+            } else {
+                T::infinity()
+            }
+        } else {
+            round_pack(sign, emax - prec + 1, (1 << prec) - 1, rnd)
+        }
+    } else {
+        round_pack(sign, exp + shift, q, rnd)
+    };
+    if flags != 0 {
+        ExceptionFlags::from_bits(flags).raise();
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, FloatConvert, F32, F64};
+
+    #[test]
+    fn chop_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let one = F32::from_bits(0x3f800000);
+        let three = F32::from_bits(0x40400000);
+
+        // bfloat16: rounded once from the exact quotient
+        let bf16 = Chop::new(8);
+        let (ret, flags) = with_flags(|| bf16.div(&one, &three, rnd));
+        assert_eq!(ret.to_bits(), 0x3eab0000);
+        assert!(flags.is_inexact() && !flags.is_underflow());
+        let ret = bf16.div(&one, &three, RoundingMode::TowardZero);
+        assert_eq!(ret.to_bits(), 0x3eaa0000);
+        // 1 + 2^-8 is a tie rounded to even, 1 + 2^-8 + 2^-31 is above it
+        let tie = F32::from_bits(0x3b800000);
+        assert_eq!(bf16.add(&one, &tie, rnd).to_bits(), 0x3f800000);
+        let above = F32::from_bits(0x3b800001);
+        assert_eq!(bf16.add(&one, &above, rnd).to_bits(), 0x3f810000);
+        assert_eq!(
+            bf16.round(&F32::from_bits(0x3f818000), rnd).to_bits(),
+            0x3f820000
+        );
+        assert_eq!(
+            bf16.sqrt(&F32::from_bits(0x40000000), rnd).to_bits(),
+            0x3fb50000
+        );
+
+        // half precision in F64
+        let half = Chop::new(11).with_exponent_range(-14, 15);
+        assert_eq!(half.precision(), 11);
+        assert_eq!(half.exponent_range(), Some((-14, 15)));
+        let max = F64::from_bits(0x40effc0000000000); // 65504
+        let (ret, flags) = with_flags(|| half.add(&max, &F64::from_bits(0x4030000000000000), rnd));
+        assert!(ret.is_infinity() && ret.is_positive());
+        assert!(flags.is_overflow() && flags.is_inexact());
+        let ret = half.mul(
+            &max.neg(),
+            &F64::from_bits(0x4000000000000000),
+            RoundingMode::TowardZero,
+        );
+        assert_eq!(ret.to_bits(), max.neg().to_bits());
+
+        // subnormals have fewer bits, 3 * 2^-26 rounds to 2^-24
+        let tiny = F64::from_bits(0x3e68000000000000);
+        let (ret, flags) = with_flags(|| half.round(&tiny, rnd));
+        assert_eq!(ret.to_bits(), 0x3e70000000000000);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        // 2^-25 is a tie rounded to zero, keeping its sign
+        let (ret, flags) = with_flags(|| half.round(&F64::from_bits(0xbe60000000000000), rnd));
+        assert_eq!(ret.to_bits(), 0x8000000000000000);
+        assert!(flags.is_underflow());
+
+        // special results are those of the float type
+        let (ret, flags) = with_flags(|| half.div(&one.to_f64(rnd), &F64::zero(), rnd));
+        assert!(ret.is_infinity() && flags.is_infinite());
+        let ret = half.sub(&max, &max, RoundingMode::TowardNegative);
+        assert_eq!(ret.to_bits(), 0x8000000000000000);
+    }
+
+    #[test]
+    #[should_panic(expected = "precision out of the range")]
+    fn chop_precision() {
+        let one = F32::from_bits(0x3f800000);
+        Chop::new(25).add(&one, &one, RoundingMode::TiesToEven);
+    }
+}
@@ -0,0 +1,315 @@
+//! Complex numbers of softfloat parts
+//!
+//! A [`Complex`] holds the real and imaginary parts of a complex number in one format, for
+//! guest code which computes mostly on complex values, such as signal processing. The
+//! operations raise the exception flags of the operations on the parts they are made of, so
+//! a quotient may raise inexact even if it is exact.
+//!
+//! Products compute each part with Kahan's algorithm, whose fused multiply-add recovers the
+//! rounding error of one of the two products, so the parts are accurate to a few ulps even
+//! when the products cancel. Quotients use Smith's algorithm, scaling an operand by a power
+//! of two first if its larger part is near overflow or underflow, as Baudin and Smith do.
+//! The scaling is exact, so it is skipped as far as it would round the smaller part, and
+//! the scale of the result is applied in the last division, so it is rounded once.
+//! [`Complex::abs`] and [`Complex::arg`] are correctly rounded, with
+//! [`math::hypot`](crate::math::hypot) and [`math::atan2`](crate::math::atan2).
+//!
+//! Infinite and NaN parts go through the same formulas as finite ones, so they may give
+//! NaN parts where C99 recovers an infinity.
+//!
+//! ```
+//! use softfloat_wrapper::{Complex, RoundingMode, F64};
+//!
+//! let rnd = RoundingMode::TiesToEven;
+//! let max = F64::from_bits(0x7fefffffffffffff);
+//! let z = Complex::new(max, max);
+//! let one = z.div(&z, rnd);
+//! assert_eq!(one.re.to_bits(), 0x3ff0000000000000);
+//! assert_eq!(one.im.to_bits(), 0);
+//! assert_eq!(z.abs(rnd).to_bits(), 0x7ff0000000000000);
+//! ```
+
+use crate::{math, round_pack, unpack, RoundingMode, SoftFloat};
+
+/// A complex number with parts of type `T`
+#[derive(Copy, Clone, Debug)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T,
+}
+
+/// `a b - c d`, by Kahan's algorithm
+fn diff_of_products<T: SoftFloat>(a: &T, b: &T, c: &T, d: &T, rnd: RoundingMode) -> T {
+    let w = c.mul(d, rnd);
+    let f = a.fused_mul_add(b, &w.neg(), rnd);
+    if !w.is_finite() {
+        return f;
+    }
+    // the rounding error of c d, which is exact unless it underflows
+    let e = c.fused_mul_add(d, &w.neg(), rnd);
+    f.sub(&e, rnd)
+}
+
+/// The exponent of the larger of the finite `x` and `y`, or `None` if both are zero
+fn exponent<T: SoftFloat>(x: &T, y: &T) -> Option<i32> {
+    [x, y]
+        .iter()
+        .filter(|x| !x.is_zero())
+        .map(|x| {
+            let (_, exp, sig) = unpack(*x);
+            exp + 127 - sig.leading_zeros() as i32
+        })
+        .max()
+}
+
+/// `x * 2^n` for a finite `x` or a zero `n`, rounded once
+fn scale<T: SoftFloat>(x: &T, n: i32, rnd: RoundingMode) -> T {
+    if n == 0 {
+        return T::from_bits(x.to_bits());
+    }
+    let (neg, exp, sig) = unpack(x);
+    round_pack(neg, exp + n, sig, rnd)
+}
+
+/// The power of two to scale the finite `x` and `y` by before Smith's algorithm, like
+/// Baudin and Smith do: the larger part is brought to 1 if it is near underflow, and
+/// below a quarter of the overflow threshold if it is near overflow, as far as the
+/// smaller part is still scaled exactly.
+fn scaling<T: SoftFloat>(x: &T, y: &T) -> i32 {
+    let emax = T::EXPONENT_BIAS;
+    let emin = 1 - emax;
+    let e = match exponent(x, y) {
+        Some(e) => e,
+        None => return 0,
+    };
+    if e < emin + T::MANTISSA_DIGITS as i32 {
+        -e
+    } else if e > emax - 2 {
+        let lowest = [x, y]
+            .iter()
+            .filter(|x| !x.is_zero())
+            .map(|x| {
+                let (_, exp, sig) = unpack(*x);
+                exp + sig.trailing_zeros() as i32
+            })
+            .min()
+            .unwrap();
+        (emax - 2 - e).max(emin - T::MANTISSA_BITS as i32 - lowest)
+    } else {
+        0
+    }
+}
+
+/// `x / y * 2^n` for a normal `y`, rounded once.
+///
+/// The scale goes to `y` as far as it stays normal, which is exact, and the rest to `x`,
+/// which only overflows or underflows when the quotient does by far.
+fn div_scaled<T: SoftFloat>(x: &T, y: &T, n: i32, rnd: RoundingMode) -> T {
+    if n == 0 {
+        return x.div(y, rnd);
+    }
+    let emax = T::EXPONENT_BIAS;
+    let e = exponent(y, y).unwrap();
+    let m = if n > 0 {
+        n.min(e - (1 - emax))
+    } else {
+        n.max(e - emax)
+    };
+    scale(x, n - m, rnd).div(scale(y, -m, rnd), rnd)
+}
+
+impl<T: SoftFloat> Complex<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Self { re, im }
+    }
+
+    /// Returns the complex conjugate, which is exact.
+    pub fn conj(&self) -> Self {
+        Self::new(T::from_bits(self.re.to_bits()), self.im.neg())
+    }
+
+    pub fn add(&self, x: &Self, rnd: RoundingMode) -> Self {
+        Self::new(self.re.add(&x.re, rnd), self.im.add(&x.im, rnd))
+    }
+
+    pub fn sub(&self, x: &Self, rnd: RoundingMode) -> Self {
+        Self::new(self.re.sub(&x.re, rnd), self.im.sub(&x.im, rnd))
+    }
+
+    pub fn mul(&self, x: &Self, rnd: RoundingMode) -> Self {
+        let (a, b, c, d) = (&self.re, &self.im, &x.re, &x.im);
+        Self::new(
+            diff_of_products(a, c, b, d, rnd),
+            diff_of_products(a, d, &b.neg(), c, rnd),
+        )
+    }
+
+    /// Divides by `x`. A zero `x` divides both parts by its real part, raising divide by
+    /// zero for nonzero parts and invalid for zero ones.
+    pub fn div(&self, x: &Self, rnd: RoundingMode) -> Self {
+        if x.re.is_zero() && x.im.is_zero() {
+            return Self::new(self.re.div(&x.re, rnd), self.im.div(&x.re, rnd));
+        }
+        let finite = [&self.re, &self.im, &x.re, &x.im]
+            .iter()
+            .all(|x| x.is_finite());
+        let (m, k) = if finite {
+            (scaling(&self.re, &self.im), scaling(&x.re, &x.im))
+        } else {
+            (0, 0)
+        };
+        let (a, b) = (scale(&self.re, m, rnd), scale(&self.im, m, rnd));
+        let (c, d) = (scale(&x.re, k, rnd), scale(&x.im, k, rnd));
+        // the numerators and the denominator, whose quotients are scaled by 2^(k - m)
+        let (re, im, t) = if d.abs().le(c.abs()) {
+            let r = d.div(&c, rnd);
+            (
+                b.fused_mul_add(&r, &a, rnd),
+                a.neg().fused_mul_add(&r, &b, rnd),
+                c.add(d.mul(&r, rnd), rnd),
+            )
+        } else {
+            let r = c.div(&d, rnd);
+            (
+                a.fused_mul_add(&r, &b, rnd),
+                b.fused_mul_add(&r, &a.neg(), rnd),
+                c.fused_mul_add(&r, &d, rnd),
+            )
+        };
+        Self::new(
+            div_scaled(&re, &t, k - m, rnd),
+            div_scaled(&im, &t, k - m, rnd),
+        )
+    }
+
+    /// Returns the magnitude, correctly rounded.
+    pub fn abs(&self, rnd: RoundingMode) -> T {
+        math::hypot(&self.re, &self.im, rnd)
+    }
+
+    /// Returns the argument, in radians between `-pi` and `pi`, correctly rounded.
+    pub fn arg(&self, rnd: RoundingMode) -> T {
+        math::atan2(&self.im, &self.re, rnd)
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($op:ident, $fn:ident) => {
+        impl<T: SoftFloat> core::ops::$op for Complex<T> {
+            type Output = Self;
+
+            #[inline]
+            fn $fn(self, rhs: Self) -> Self {
+                Complex::$fn(&self, &rhs, RoundingMode::thread_default())
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add);
+impl_binary_op!(Sub, sub);
+impl_binary_op!(Mul, mul);
+impl_binary_op!(Div, div);
+
+impl<T: SoftFloat> core::ops::Neg for Complex<T> {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self::new(self.re.neg(), self.im.neg())
+    }
+}
+
+/// Compares both parts, so a NaN part makes the numbers unequal.
+impl<T: SoftFloat> PartialEq for Complex<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.re.eq(&other.re) && self.im.eq(&other.im)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatBits, F32, F64};
+
+    #[test]
+    fn complex_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let c = |re: u64, im: u64| Complex::new(F64::from_bits(re), F64::from_bits(im));
+        let bits = |z: Complex<F64>| (z.re.to_bits(), z.im.to_bits());
+        let (one, two, three) = (0x3ff0000000000000, 0x4000000000000000, 0x4008000000000000);
+
+        // (1 + 2i) (3 - i) = 5 + 5i and (1 + 2i) + (3 - i) = 4 + i, exactly
+        let a = c(one, two);
+        let b = c(three, 0xbff0000000000000);
+        let (ret, flags) = with_flags(|| a * b);
+        assert_eq!(bits(ret), (0x4014000000000000, 0x4014000000000000));
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(bits(a + b), (0x4010000000000000, one));
+        assert_eq!(bits(a - b), (0xc000000000000000, three));
+        assert_eq!(bits(-a.conj()), (0xbff0000000000000, two));
+        // the ratio of the parts of 3 - i is inexact, though the quotient is not
+        let (ret, flags) = with_flags(|| (a * b).div(&b, rnd));
+        assert_eq!(bits(ret), (one, two));
+        assert!(flags.is_inexact());
+        let (ret, flags) = with_flags(|| (a * b).div(&c(two, 0), rnd));
+        assert_eq!(bits(ret), (0x4004000000000000, 0x4004000000000000));
+        assert_eq!(flags.to_bits(), 0);
+
+        // (1 + 2^-30) (1 - 2^-30) - 1 = -2^-60 cancels, and is exact with a fused
+        // multiply-add
+        let x = c(0x3ff0000000400000, one);
+        let y = c(0x3fefffffff800000, one);
+        assert_eq!(bits(x.mul(&y, rnd)), (0xbc30000000000000, two));
+
+        // the squares of the parts overflow, but not the quotient
+        let max = c(0x7fefffffffffffff, 0x7fefffffffffffff);
+        let (ret, flags) = with_flags(|| max / c(0x7fe0000000000000, 0));
+        assert_eq!(bits(ret), (0x3fffffffffffffff, 0x3fffffffffffffff));
+        assert_eq!(flags.to_bits(), 0);
+        let tiny = c(1, 1);
+        assert_eq!(bits(tiny / tiny), (one, 0));
+        let (ret, flags) = with_flags(|| max / tiny);
+        assert!(ret.re.is_infinity() && ret.im.is_zero() && flags.is_overflow());
+
+        // parts far apart keep their bits, as neither is scaled
+        let (ret, flags) = with_flags(|| c(0x7e70000000000000, 0x39b0000000000000) / c(one, 0));
+        assert_eq!(bits(ret), (0x7e70000000000000, 0x39b0000000000000));
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| c(0x7fefffffffffffff, 1) / c(one, 0));
+        assert_eq!(bits(ret), (0x7fefffffffffffff, 1));
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| c(two, 0).div(&c(0x7fefffffffffffff, 1), rnd));
+        assert_eq!(bits(ret), (0x0008000000000000, 0));
+        assert!(flags.is_underflow() && flags.is_inexact() && !flags.is_overflow());
+        let (ret, flags) = with_flags(|| max / c(two, 0));
+        assert_eq!(bits(ret), (0x7fdfffffffffffff, 0x7fdfffffffffffff));
+        assert_eq!(flags.to_bits(), 0);
+        // the subnormal quotient of (3 2^51 + 4) 2^-1074 by 3 is rounded once, to
+        // (2^51 + 1) 2^-1074, where rounding to 53 bits first would give a tie rounding up
+        let (ret, flags) = with_flags(|| c(0x0018000000000004, 0) / c(three, 0));
+        assert_eq!(bits(ret), (0x0008000000000001, 0));
+        assert!(flags.is_underflow() && flags.is_inexact());
+        assert!(!flags.is_overflow() && !flags.is_invalid() && !flags.is_infinite());
+        let (ret, flags) = with_flags(|| c(0, 0x0018000000000004).div(&c(0, three), rnd));
+        assert_eq!(bits(ret), (0x0008000000000001, 0));
+        assert!(flags.is_underflow() && flags.is_inexact());
+
+        let (ret, flags) = with_flags(|| a.div(&c(0, 0x8000000000000000), rnd));
+        assert!(ret.re.is_infinity() && ret.im.is_infinity() && flags.is_infinite());
+        let (ret, flags) = with_flags(|| c(0, one).div(&c(0, 0), rnd));
+        assert!(ret.re.is_nan() && flags.is_invalid());
+        let inf = c(0x7ff0000000000000, 0);
+        assert_eq!(bits(a / inf), (0, 0));
+        assert!(a == a && inf != c(0x7ff0000000000000, 0x7ff8000000000000));
+
+        // 3 + 4i
+        let z = Complex::new(F32::from_bits(0x40400000), F32::from_bits(0x40800000));
+        let (abs, flags) = with_flags(|| z.abs(rnd));
+        assert_eq!(abs.to_bits(), 0x40a00000);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(z.arg(rnd).to_bits(), 0x3f6d6338);
+        assert_eq!(z.conj().arg(RoundingMode::TowardZero).to_bits(), 0xbf6d6338);
+    }
+}
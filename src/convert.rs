@@ -0,0 +1,223 @@
+//! `From` and `TryFrom` conversions into and out of the softfloat types
+//!
+//! `From` is implemented where every value of the source type is exactly representable,
+//! and `TryFrom` otherwise. `TryFrom` rounds to nearest, ties to even, and fails instead of
+//! rounding; it never raises exception flags. NaNs convert to the target format's NaN.
+//!
+//! `TryFrom` converts the softfloat types into the primitive integers too, truncating
+//! toward zero like `as` does, and failing with an [`OperationError`] if the value is NaN
+//! or out of range, without raising exception flags.
+//!
+//! Conversions from native floats are available with the `native-float` feature.
+
+use crate::checked::{checked, operand};
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{with_flags, FloatConvert, OperationError, RoundingMode, F16, F32, F64};
+use std::fmt;
+
+/// The error type returned when a conversion into a softfloat type is not exact.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InexactError(pub(crate) ());
+
+impl fmt::Display for InexactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("value is not exactly representable in the target format")
+    }
+}
+
+impl std::error::Error for InexactError {}
+
+macro_rules! impl_from_int {
+    ($ty:ty, $($int:ty => $from:ident),*) => {
+        $(
+            impl From<$int> for $ty {
+                #[inline]
+                fn from(x: $int) -> Self {
+                    <$ty as FloatConvert>::$from(x, RoundingMode::TiesToEven)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_try_from_int {
+    ($ty:ty, $($int:ty => $from:ident),*) => {
+        $(
+            impl TryFrom<$int> for $ty {
+                type Error = InexactError;
+
+                fn try_from(x: $int) -> Result<Self, Self::Error> {
+                    let (ret, flags) =
+                        with_flags(|| <$ty as FloatConvert>::$from(x, RoundingMode::TiesToEven));
+                    if flags.is_inexact() {
+                        Err(InexactError(()))
+                    } else {
+                        Ok(ret)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(F16, u8 => from_u8, i8 => from_i8);
+impl_try_from_int!(F16, u16 => from_u16, i16 => from_i16, u32 => from_u32, i32 => from_i32, u64 => from_u64, i64 => from_i64);
+
+impl_from_int!(F32, u8 => from_u8, i8 => from_i8, u16 => from_u16, i16 => from_i16);
+impl_try_from_int!(F32, u32 => from_u32, i32 => from_i32, u64 => from_u64, i64 => from_i64);
+
+impl_from_int!(F64, u8 => from_u8, i8 => from_i8, u16 => from_u16, i16 => from_i16, u32 => from_u32, i32 => from_i32);
+impl_try_from_int!(F64, u64 => from_u64, i64 => from_i64);
+
+#[cfg(feature = "f128")]
+impl_from_int!(F128, u8 => from_u8, i8 => from_i8, u16 => from_u16, i16 => from_i16, u32 => from_u32, i32 => from_i32, u64 => from_u64, i64 => from_i64);
+
+macro_rules! impl_try_into_int {
+    ($ty:ty) => {
+        impl_try_into_int!($ty, u8 => to_u8, u16 => to_u16, u32 => to_u32, u64 => to_u64, usize => to_usize);
+        impl_try_into_int!($ty, i8 => to_i8, i16 => to_i16, i32 => to_i32, i64 => to_i64, isize => to_isize);
+    };
+    ($ty:ty, $($int:ty => $to:ident),*) => {
+        $(
+            impl TryFrom<$ty> for $int {
+                type Error = OperationError;
+
+                fn try_from(x: $ty) -> Result<Self, Self::Error> {
+                    checked(&[operand(&x)], || x.$to(RoundingMode::TowardZero, false))
+                }
+            }
+        )*
+    };
+}
+
+impl_try_into_int!(F16);
+impl_try_into_int!(F32);
+impl_try_into_int!(F64);
+#[cfg(feature = "f128")]
+impl_try_into_int!(F128);
+
+#[cfg(feature = "native-float")]
+macro_rules! impl_from_native {
+    ($ty:ty, $($native:ty => $from:ident),*) => {
+        $(
+            impl From<$native> for $ty {
+                #[inline]
+                fn from(x: $native) -> Self {
+                    <$ty as FloatConvert>::$from(x, RoundingMode::TiesToEven)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "native-float")]
+macro_rules! impl_try_from_native {
+    ($ty:ty, $($native:ty => $from:ident),*) => {
+        $(
+            impl TryFrom<$native> for $ty {
+                type Error = InexactError;
+
+                fn try_from(x: $native) -> Result<Self, Self::Error> {
+                    let (ret, flags) =
+                        with_flags(|| <$ty as FloatConvert>::$from(x, RoundingMode::TiesToEven));
+                    if flags.is_inexact() {
+                        Err(InexactError(()))
+                    } else {
+                        Ok(ret)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "native-float")]
+impl_try_from_native!(F16, f32 => from_native_f32_rounded, f64 => from_native_f64_rounded);
+#[cfg(feature = "native-float")]
+impl_from_native!(F32, f32 => from_native_f32_rounded);
+#[cfg(feature = "native-float")]
+impl_try_from_native!(F32, f64 => from_native_f64_rounded);
+#[cfg(feature = "native-float")]
+impl_from_native!(F64, f32 => from_native_f32_rounded, f64 => from_native_f64_rounded);
+#[cfg(all(feature = "f128", feature = "native-float"))]
+impl_from_native!(F128, f32 => from_native_f32_rounded, f64 => from_native_f64_rounded);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExceptionFlags, FloatBits};
+
+    #[test]
+    fn from_int() {
+        assert_eq!(F16::from(-3i8).to_bits(), 0xc200);
+        assert_eq!(F32::from(u16::MAX).to_bits(), 0x477fff00);
+        assert_eq!(F64::from(i32::MIN).to_bits(), 0xc1e0000000000000);
+    }
+
+    #[test]
+    fn try_from_int() {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        assert_eq!(F16::try_from(2048u16).unwrap().to_bits(), 0x6800);
+        assert!(F16::try_from(2049u16).is_err());
+        assert!(F16::try_from(u16::MAX).is_err());
+        assert_eq!(F32::try_from(1i64 << 40).unwrap().to_bits(), 0x53800000);
+        assert!(F32::try_from(16777217u32).is_err());
+        assert!(F64::try_from(u64::MAX).is_err());
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+
+    #[test]
+    fn try_into_int() {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let a = F64::from_bits(0xc05fc00000000000); // -127
+        assert_eq!(i8::try_from(a).unwrap(), -127);
+        assert_eq!(
+            i32::try_from(F64::from_bits(0xc05ff00000000000)).unwrap(),
+            -127
+        );
+        assert!(u8::try_from(a).is_err());
+        assert!(u64::try_from(a).is_err());
+        // truncated like `as`
+        assert_eq!(u16::try_from(F16::from_bits(0x3bff)).unwrap(), 0);
+        assert_eq!(i64::try_from(F32::from_bits(0xbfc00000)).unwrap(), -1);
+        assert_eq!(
+            usize::try_from(F32::from_bits(0x4b800001)).unwrap(),
+            16777218
+        );
+
+        let e = i32::try_from(F32::from_bits(0x4f000000)).unwrap_err();
+        assert_eq!(e.operands(), [0x4f000000]);
+        assert_eq!(u32::try_from(F32::from_bits(0x4f000000)).unwrap(), 1 << 31);
+        assert!(isize::try_from(F64::quiet_nan()).is_err());
+        assert!(i16::try_from(F32::infinity()).is_err());
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn from_native() {
+        assert_eq!(F32::from(0.1f32).to_bits(), 0x3dcccccd);
+        assert_eq!(F64::from(0.1f32).to_bits(), 0x3fb99999a0000000);
+        assert_eq!(F64::from(0.1f64).to_bits(), 0x3fb999999999999a);
+    }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn try_from_native() {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        assert_eq!(F16::try_from(0.5f32).unwrap().to_bits(), 0x3800);
+        assert!(F16::try_from(0.1f32).is_err());
+        assert!(F16::try_from(1e10f64).is_err());
+        assert_eq!(F32::try_from(0.5f64).unwrap().to_bits(), 0x3f000000);
+        assert!(F32::try_from(0.1f64).is_err());
+        assert!(F32::try_from(f64::NAN).unwrap().is_nan());
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+}
@@ -0,0 +1,224 @@
+//! Conversion to decimal strings
+//!
+//! Values are converted exactly and rounded once to the requested number of significant
+//! digits, in the rounding mode given.
+
+use crate::parse::BigUint;
+use crate::{unpack, ExceptionFlags, RoundingMode, SoftFloat};
+use std::cmp::Ordering;
+
+/// `x`, finite and nonzero, rounded per `rnd` to `n` significant digits: the digits, the
+/// decimal exponent of the first one, and whether the rounding was inexact
+pub(crate) fn digits<T: SoftFloat>(x: &T, n: usize, rnd: RoundingMode) -> (Vec<u8>, i32, bool) {
+    let (negative, exp, sig) = unpack(x);
+    let mut num = BigUint::from_u128(sig);
+    let mut den = BigUint::from_u32(1);
+    if exp >= 0 {
+        num = num.shl(exp as i64);
+    } else {
+        den = den.shl(-exp as i64);
+    }
+
+    // |x| / 10^k is in [1, 10) once the estimate of k is corrected
+    let bits = 128 - sig.leading_zeros() as i32 + exp;
+    let mut k = ((bits - 1) as f64 * std::f64::consts::LOG10_2).floor() as i32;
+    for _ in 0..k.max(0) {
+        den.mul_add_small(10, 0);
+    }
+    for _ in k.min(0)..0 {
+        num.mul_add_small(10, 0);
+    }
+    while num < den {
+        num.mul_add_small(10, 0);
+        k -= 1;
+    }
+    loop {
+        let mut next = den.clone();
+        next.mul_add_small(10, 0);
+        if num < next {
+            break;
+        }
+        den = next;
+        k += 1;
+    }
+
+    let mut digits = Vec::with_capacity(n);
+    for _ in 0..n {
+        let mut d = 0;
+        while num >= den {
+            num.sub_assign(&den);
+            d += 1;
+        }
+        digits.push(d);
+        num.mul_add_small(10, 0);
+    }
+
+    // `num` is 10 times the remainder, so compare it with 5 times `den` for the half
+    let mut half = den;
+    half.mul_add_small(5, 0);
+    let inexact = !num.is_zero();
+    let up = match rnd {
+        RoundingMode::TiesToEven => match num.partial_cmp(&half).unwrap() {
+            Ordering::Greater => true,
+            Ordering::Equal => digits[n - 1] % 2 == 1,
+            Ordering::Less => false,
+        },
+        RoundingMode::TiesToAway => num >= half,
+        RoundingMode::TowardZero => false,
+        RoundingMode::TowardNegative => negative && inexact,
+        RoundingMode::TowardPositive => !negative && inexact,
+    };
+    if up {
+        match digits.iter().rposition(|&d| d != 9) {
+            Some(i) => {
+                digits[i] += 1;
+                digits[i + 1..].iter_mut().for_each(|d| *d = 0);
+            }
+            None => {
+                digits.iter_mut().for_each(|d| *d = 0);
+                digits[0] = 1;
+                k += 1;
+            }
+        }
+    }
+    (digits, k, inexact)
+}
+
+/// `x` with `n` significant digits, 1 to 3 of them before the point, and an exponent which
+/// is a multiple of 3
+pub(crate) fn engineering<T: SoftFloat>(x: &T, n: usize, rnd: RoundingMode) -> String {
+    assert!(n > 0, "at least one significant digit is needed");
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    let sign = if x.is_negative() { "-" } else { "" };
+    if x.is_infinity() {
+        return format!("{}inf", sign);
+    }
+    let (mut digits, k) = if x.is_zero() {
+        (vec![0; n], 0)
+    } else {
+        let (digits, k, _) = self::digits(x, n, rnd);
+        (digits, k)
+    };
+
+    let exp = k.div_euclid(3) * 3;
+    let int = (k - exp) as usize + 1;
+    if digits.len() < int {
+        digits.resize(int, 0);
+    }
+    let digits: String = digits.iter().map(|&d| char::from(b'0' + d)).collect();
+    let (int, frac) = digits.split_at(int);
+    if frac.is_empty() {
+        format!("{}{}e{}", sign, int, exp)
+    } else {
+        format!("{}{}.{}e{}", sign, int, frac, exp)
+    }
+}
+
+/// `x` with `n` significant digits and one of them before the point, raising inexact if
+/// they are rounded
+pub(crate) fn scientific<T: SoftFloat>(x: &T, n: usize, rnd: RoundingMode) -> String {
+    assert!(n > 0, "at least one significant digit is needed");
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    let sign = if x.is_negative() { "-" } else { "" };
+    if x.is_infinity() {
+        return format!("{}inf", sign);
+    }
+    let (digits, k) = if x.is_zero() {
+        (vec![0; n], 0)
+    } else {
+        let (digits, k, inexact) = self::digits(x, n, rnd);
+        if inexact {
+            ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT).raise();
+        }
+        (digits, k)
+    };
+
+    let digits: String = digits.iter().map(|&d| char::from(b'0' + d)).collect();
+    let (int, frac) = digits.split_at(1);
+    if frac.is_empty() {
+        format!("{}{}e{}", sign, int, k)
+    } else {
+        format!("{}{}.{}e{}", sign, int, frac, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "f128")]
+    use crate::F128;
+    use crate::{with_flags, ExceptionFlags, FloatBits, RoundingMode, SoftFloat, F16, F32, F64};
+
+    #[test]
+    fn decimal_engineering() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F64::from_bits(0x40c81c8000000000); // 12345
+                                                    // ties to even
+        assert_eq!(a.to_engineering_string(4, rnd), "12.34e3");
+        assert_eq!(
+            a.to_engineering_string(4, RoundingMode::TiesToAway),
+            "12.35e3"
+        );
+        assert_eq!(a.to_engineering_string(1, rnd), "10e3");
+        assert_eq!(a.to_engineering_string(7, rnd), "12.34500e3");
+        let a = F64::from_bits(0xbeb0c6f7a0b5ed8d); // -1e-6
+        assert_eq!(a.to_engineering_string(3, rnd), "-1.00e-6");
+        let a = F32::from_bits(0x3dcccccd); // 0.1f32
+        assert_eq!(a.to_engineering_string(9, rnd), "100.000001e-3");
+        assert_eq!(
+            a.to_engineering_string(3, RoundingMode::TowardNegative),
+            "100e-3"
+        );
+        // 999.95 rounds up into the next group
+        let a = F64::from_bits(0x408f3f999999999a);
+        assert_eq!(a.to_engineering_string(4, rnd), "1.000e3");
+        assert_eq!(F16::from_bits(0x7bff).to_engineering_string(2, rnd), "66e3");
+        assert_eq!(
+            F16::from_bits(0x8000).to_engineering_string(3, rnd),
+            "-0.00e0"
+        );
+        assert_eq!(F16::infinity().to_engineering_string(3, rnd), "inf");
+        assert_eq!(F16::quiet_nan().to_engineering_string(3, rnd), "NaN");
+    }
+
+    #[cfg(feature = "f128")]
+    #[test]
+    fn decimal_f128() {
+        // the smallest subnormal is 6.475...e-4966
+        let a = F128::from_bits(1);
+        let rnd = RoundingMode::TowardPositive;
+        assert_eq!(a.to_engineering_string(3, rnd), "648e-4968");
+    }
+
+    #[test]
+    fn decimal_scientific() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F64::from_bits(0x40c81c8000000000); // 12345
+        let (s, flags) = with_flags(|| a.to_decimal_string(5, rnd));
+        assert_eq!(s, "1.2345e4");
+        assert!(!flags.is_inexact());
+        let (s, flags) = with_flags(|| a.to_decimal_string(3, RoundingMode::TowardPositive));
+        assert_eq!(s, "1.24e4");
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        assert_eq!(a.to_decimal_string(1, rnd), "1e4");
+
+        let a = F32::from_bits(0xbdcccccd); // -0.1f32
+        let (s, flags) = with_flags(|| a.to_decimal_string(9, rnd));
+        assert_eq!(s, "-1.00000001e-1");
+        assert!(flags.is_inexact());
+        assert_eq!(
+            a.to_decimal_string(3, RoundingMode::TowardNegative),
+            "-1.01e-1"
+        );
+        let (s, flags) = with_flags(|| F16::from_bits(0x0001).to_decimal_string(4, rnd));
+        assert_eq!(s, "5.960e-8");
+        assert!(flags.is_inexact());
+        let (s, flags) = with_flags(|| F32::from_bits(0x7fa00000).to_decimal_string(3, rnd));
+        assert_eq!(s, "NaN");
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(F64::from_bits(0).to_decimal_string(2, rnd), "0.0e0");
+    }
+}
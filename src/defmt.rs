@@ -0,0 +1,47 @@
+//! [defmt](https://crates.io/crates/defmt) support
+//!
+//! Values are logged as their bit patterns, which keeps formatting off the target and
+//! shows NaN payloads and signed zeros exactly.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{ExceptionFlags, F16, F32, F64};
+
+impl ::defmt::Format for F16 {
+    fn format(&self, f: ::defmt::Formatter) {
+        ::defmt::write!(f, "F16({=u16:#06x})", self.to_bits())
+    }
+}
+
+impl ::defmt::Format for F32 {
+    fn format(&self, f: ::defmt::Formatter) {
+        ::defmt::write!(f, "F32({=u32:#010x})", self.to_bits())
+    }
+}
+
+impl ::defmt::Format for F64 {
+    fn format(&self, f: ::defmt::Formatter) {
+        ::defmt::write!(f, "F64({=u64:#018x})", self.to_bits())
+    }
+}
+
+#[cfg(feature = "f128")]
+impl ::defmt::Format for F128 {
+    fn format(&self, f: ::defmt::Formatter) {
+        ::defmt::write!(f, "F128({=u128:#034x})", self.to_bits())
+    }
+}
+
+impl ::defmt::Format for ExceptionFlags {
+    fn format(&self, f: ::defmt::Formatter) {
+        ::defmt::write!(
+            f,
+            "ExceptionFlags {{ inexact: {=bool}, infinite: {=bool}, invalid: {=bool}, overflow: {=bool}, underflow: {=bool} }}",
+            self.is_inexact(),
+            self.is_infinite(),
+            self.is_invalid(),
+            self.is_overflow(),
+            self.is_underflow()
+        )
+    }
+}
@@ -0,0 +1,307 @@
+//! Object-safe float values
+//!
+//! [`SoftFloat`] has associated constants and generic methods, so it cannot be made into a
+//! trait object. [`DynSoftFloat`] is an object-safe subset of it, implemented for every
+//! float type, so code such as a plugin can operate on `&dyn DynSoftFloat` values whose
+//! format is chosen at runtime. Its methods are prefixed with `dyn_` so that they do not
+//! clash with the ones of the float types, and return boxed values of the format of
+//! `self`.
+//!
+//! ```
+//! use softfloat_wrapper::{DynSoftFloat, RoundingMode, F16, F64};
+//!
+//! fn halve(x: &dyn DynSoftFloat) -> Box<dyn DynSoftFloat> {
+//!     let two = x.dyn_from_i64(2, RoundingMode::TiesToEven);
+//!     x.dyn_div(&*two, RoundingMode::TiesToEven)
+//! }
+//!
+//! let values: [Box<dyn DynSoftFloat>; 2] = [
+//!     Box::new(F16::from_bits(0x3c00)),
+//!     Box::new(F64::from_bits(0x3ff0000000000000)),
+//! ];
+//! let halves: Vec<_> = values.iter().map(|x| halve(&**x).dyn_to_bits()).collect();
+//! assert_eq!(halves, [0x3800, 0x3fe0000000000000]);
+//! ```
+//!
+//! # Panics
+//!
+//! The operations taking other values panic if these are not of the format of `self`.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{RoundingMode, SoftFloat, F16, F32, F64};
+use num_traits::{NumCast, ToPrimitive};
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt;
+use std::num::FpCategory;
+
+/// An object-safe subset of [`SoftFloat`]
+pub trait DynSoftFloat: fmt::Debug {
+    /// Returns `self` as `Any`, to downcast it to its float type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns a boxed copy of `self`.
+    fn dyn_clone(&self) -> Box<dyn DynSoftFloat>;
+
+    /// Returns the number of bits of the format.
+    fn dyn_width(&self) -> u32;
+
+    /// Returns the number of exponent bits of the format.
+    fn dyn_exponent_bits(&self) -> u32;
+
+    /// Returns the number of mantissa bits of the format, excluding the sign.
+    fn dyn_mantissa_bits(&self) -> u32;
+
+    fn dyn_to_bits(&self) -> u128;
+
+    /// Returns the value of the format of `self` with the low bits of `v`.
+    fn dyn_from_bits(&self, v: u128) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_classify(&self) -> FpCategory;
+
+    fn dyn_is_signaling_nan(&self) -> bool;
+
+    fn dyn_neg(&self) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_abs(&self) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_add(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_sub(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_mul(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_fused_mul_add(
+        &self,
+        x: &dyn DynSoftFloat,
+        y: &dyn DynSoftFloat,
+        rnd: RoundingMode,
+    ) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_div(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_rem(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_sqrt(&self, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_round_to_integral(&self, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_eq(&self, x: &dyn DynSoftFloat) -> bool;
+
+    fn dyn_lt(&self, x: &dyn DynSoftFloat) -> bool;
+
+    fn dyn_le(&self, x: &dyn DynSoftFloat) -> bool;
+
+    fn dyn_compare(&self, x: &dyn DynSoftFloat) -> Option<Ordering>;
+
+    /// Returns the value of the format of `self` converted from `x`.
+    fn dyn_from_i64(&self, x: i64, rnd: RoundingMode) -> Box<dyn DynSoftFloat>;
+
+    fn dyn_to_i64(&self, rnd: RoundingMode, exact: bool) -> i64;
+
+    fn dyn_to_u64(&self, rnd: RoundingMode, exact: bool) -> u64;
+
+    fn dyn_to_f16(&self, rnd: RoundingMode) -> F16;
+
+    fn dyn_to_f32(&self, rnd: RoundingMode) -> F32;
+
+    fn dyn_to_f64(&self, rnd: RoundingMode) -> F64;
+
+    #[cfg(feature = "f128")]
+    fn dyn_to_f128(&self, rnd: RoundingMode) -> F128;
+}
+
+/// Downcasts `x` to the format of `T`.
+fn operand<T: 'static>(x: &dyn DynSoftFloat) -> &T {
+    x.as_any()
+        .downcast_ref()
+        .expect("operand of another format")
+}
+
+impl<T: SoftFloat + Copy + fmt::Debug + 'static> DynSoftFloat for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn dyn_clone(&self) -> Box<dyn DynSoftFloat> {
+        Box::new(*self)
+    }
+
+    fn dyn_width(&self) -> u32 {
+        T::BITS
+    }
+
+    fn dyn_exponent_bits(&self) -> u32 {
+        T::EXPONENT_BITS as u32
+    }
+
+    fn dyn_mantissa_bits(&self) -> u32 {
+        T::MANTISSA_BITS as u32
+    }
+
+    fn dyn_to_bits(&self) -> u128 {
+        self.to_bits().to_u128().unwrap()
+    }
+
+    fn dyn_from_bits(&self, v: u128) -> Box<dyn DynSoftFloat> {
+        let v = v & (u128::MAX >> (128 - self.dyn_width()));
+        Box::new(T::from_bits(<T::Payload as NumCast>::from(v).unwrap()))
+    }
+
+    fn dyn_classify(&self) -> FpCategory {
+        self.classify()
+    }
+
+    fn dyn_is_signaling_nan(&self) -> bool {
+        self.is_signaling_nan()
+    }
+
+    fn dyn_neg(&self) -> Box<dyn DynSoftFloat> {
+        Box::new(self.neg())
+    }
+
+    fn dyn_abs(&self) -> Box<dyn DynSoftFloat> {
+        Box::new(self.abs())
+    }
+
+    fn dyn_add(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.add(operand::<T>(x), rnd))
+    }
+
+    fn dyn_sub(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.sub(operand::<T>(x), rnd))
+    }
+
+    fn dyn_mul(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.mul(operand::<T>(x), rnd))
+    }
+
+    fn dyn_fused_mul_add(
+        &self,
+        x: &dyn DynSoftFloat,
+        y: &dyn DynSoftFloat,
+        rnd: RoundingMode,
+    ) -> Box<dyn DynSoftFloat> {
+        Box::new(self.fused_mul_add(operand::<T>(x), operand::<T>(y), rnd))
+    }
+
+    fn dyn_div(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.div(operand::<T>(x), rnd))
+    }
+
+    fn dyn_rem(&self, x: &dyn DynSoftFloat, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.rem(operand::<T>(x), rnd))
+    }
+
+    fn dyn_sqrt(&self, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.sqrt(rnd))
+    }
+
+    fn dyn_round_to_integral(&self, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(self.round_to_integral(rnd))
+    }
+
+    fn dyn_eq(&self, x: &dyn DynSoftFloat) -> bool {
+        self.eq(operand::<T>(x))
+    }
+
+    fn dyn_lt(&self, x: &dyn DynSoftFloat) -> bool {
+        self.lt(operand::<T>(x))
+    }
+
+    fn dyn_le(&self, x: &dyn DynSoftFloat) -> bool {
+        self.le(operand::<T>(x))
+    }
+
+    fn dyn_compare(&self, x: &dyn DynSoftFloat) -> Option<Ordering> {
+        self.compare(operand::<T>(x))
+    }
+
+    fn dyn_from_i64(&self, x: i64, rnd: RoundingMode) -> Box<dyn DynSoftFloat> {
+        Box::new(T::from_i64(x, rnd))
+    }
+
+    fn dyn_to_i64(&self, rnd: RoundingMode, exact: bool) -> i64 {
+        self.to_i64(rnd, exact)
+    }
+
+    fn dyn_to_u64(&self, rnd: RoundingMode, exact: bool) -> u64 {
+        self.to_u64(rnd, exact)
+    }
+
+    fn dyn_to_f16(&self, rnd: RoundingMode) -> F16 {
+        self.to_f16(rnd)
+    }
+
+    fn dyn_to_f32(&self, rnd: RoundingMode) -> F32 {
+        self.to_f32(rnd)
+    }
+
+    fn dyn_to_f64(&self, rnd: RoundingMode) -> F64 {
+        self.to_f64(rnd)
+    }
+
+    #[cfg(feature = "f128")]
+    fn dyn_to_f128(&self, rnd: RoundingMode) -> F128 {
+        self.to_f128(rnd)
+    }
+}
+
+impl Clone for Box<dyn DynSoftFloat> {
+    fn clone(&self) -> Self {
+        self.dyn_clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_flags;
+
+    #[test]
+    fn dyn_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let one: Box<dyn DynSoftFloat> = Box::new(F32::from_bits(0x3f800000));
+        let three = one.dyn_from_i64(3, rnd);
+        assert_eq!(three.dyn_to_bits(), 0x40400000);
+        assert_eq!(one.dyn_width(), 32);
+        assert_eq!(one.dyn_exponent_bits(), 8);
+        assert_eq!(one.dyn_mantissa_bits(), 23);
+
+        let (ret, flags) = with_flags(|| one.dyn_div(&*three, rnd));
+        assert_eq!(ret.dyn_to_bits(), 0x3eaaaaab);
+        assert!(flags.is_inexact());
+        assert_eq!(
+            ret.as_any().downcast_ref::<F32>().unwrap().to_bits(),
+            0x3eaaaaab
+        );
+        assert_eq!(
+            one.dyn_fused_mul_add(&*three, &*one, rnd).dyn_to_bits(),
+            0x40800000
+        );
+        assert!(one.dyn_lt(&*three) && !three.dyn_le(&*one));
+        assert_eq!(one.dyn_compare(&*three), Some(Ordering::Less));
+        assert_eq!(three.dyn_neg().dyn_to_i64(rnd, true), -3);
+        assert_eq!(three.dyn_to_f64(rnd).to_bits(), 0x4008000000000000);
+
+        let nan = one.dyn_from_bits(0xffff_ff80_0001);
+        assert_eq!(nan.dyn_to_bits(), 0xff800001);
+        assert!(nan.dyn_is_signaling_nan());
+        assert_eq!(nan.dyn_classify(), FpCategory::Nan);
+        assert_eq!(nan.clone().dyn_abs().dyn_to_bits(), 0x7f800001);
+        assert_eq!(nan.dyn_compare(&*one), None);
+
+        let half = F16::from_bits(0x3800);
+        assert_eq!(half.dyn_sqrt(rnd).dyn_to_bits(), 0x39a8);
+        assert_eq!(half.dyn_width(), 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "operand of another format")]
+    fn dyn_format_mismatch() {
+        let a = F32::from_bits(0x3f800000);
+        let b = F64::from_bits(0x3ff0000000000000);
+        a.dyn_add(&b, RoundingMode::TiesToEven);
+    }
+}
@@ -0,0 +1,54 @@
+//! Exact arithmetic for the formats which are not IEEE ones
+//!
+//! The operations work on unpacked values, and return results which hold the exact value,
+//! or enough bits of it with the rest or'ed into a sticky bit, to be rounded once to any
+//! precision of up to 56 bits.
+
+/// sign, and the exact value `sig * 2^exp`
+pub(crate) type Unpacked = (bool, i32, u128);
+
+pub(crate) fn add((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Unpacked {
+    if a == 0 {
+        return (sb, eb, b);
+    }
+    if b == 0 {
+        return (sa, ea, a);
+    }
+    let ((sa, ea, a), (sb, eb, b)) = if ea >= eb {
+        ((sa, ea, a), (sb, eb, b))
+    } else {
+        ((sb, eb, b), (sa, ea, a))
+    };
+    // both significands fit in 56 bits, so shift them up to keep the bits of the smaller one
+    // below the precision of the result, with the rest or'ed into a sticky bit
+    let a = a << 64;
+    let b = b << 64;
+    let shift = (ea - eb) as u32;
+    let b = if shift >= 128 {
+        1
+    } else {
+        b >> shift | (b & ((1 << shift) - 1) != 0) as u128
+    };
+    if sa == sb {
+        (sa, ea - 64, a + b)
+    } else if a >= b {
+        (sa, ea - 64, a - b)
+    } else {
+        (sb, ea - 64, b - a)
+    }
+}
+
+pub(crate) fn mul((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Unpacked {
+    (sa != sb, ea + eb, a * b)
+}
+
+/// The quotient, or `None` when dividing by zero.
+pub(crate) fn div((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Option<Unpacked> {
+    if b == 0 {
+        return None;
+    }
+    // at least 60 bits of quotient, with the remainder as a sticky bit
+    let a = a << 64;
+    let q = (a / b) | (a % b != 0) as u128;
+    Some((sa != sb, ea - eb - 64, q))
+}
@@ -0,0 +1,393 @@
+//! Explanations of rounding
+//!
+//! The `_explained` methods of [`SoftFloat`](crate::SoftFloat) run an operation and return,
+//! with its result and the exception flags it raised, the exact result truncated at the
+//! position where it was rounded, the guard, round and sticky bits below that position, and
+//! the direction the rounding went. The exception flags set before the call are left as
+//! they were.
+
+use crate::{unpack, with_flags, ExceptionFlags, RoundingMode, SoftFloat};
+use std::fmt;
+
+/// Direction of the rounding of a result
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RoundingDirection {
+    /// the exact result is representable
+    Exact,
+    /// the result is less than the exact one
+    Down,
+    /// the result is greater than the exact one
+    Up,
+}
+
+/// The exact result of an operation, truncated at the position where it is rounded
+///
+/// Its value is `significand * 2^exponent` plus the discarded bits, the first of which is
+/// `guard` and the second `round`, and `sticky` is whether any other is set. The precision of
+/// `significand` is the one of the format, or less for subnormal results.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Unrounded {
+    pub negative: bool,
+    pub significand: u128,
+    pub exponent: i32,
+    pub guard: bool,
+    pub round: bool,
+    pub sticky: bool,
+}
+
+impl fmt::Display for Unrounded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{:#b} * 2^{}, guard {}, round {}, sticky {}",
+            if self.negative { "-" } else { "" },
+            self.significand,
+            self.exponent,
+            self.guard as u8,
+            self.round as u8,
+            self.sticky as u8
+        )
+    }
+}
+
+/// The result of an operation with the explanation of its rounding
+///
+/// ```
+/// use softfloat_wrapper::{RoundingDirection, RoundingMode, SoftFloat, F32};
+///
+/// let one = F32::from_bits(0x3f800000);
+/// let e = one.div_explained(F32::from_bits(0x40400000), RoundingMode::TiesToEven);
+/// assert_eq!(e.result.to_bits(), 0x3eaaaaab);
+/// assert_eq!(e.direction, RoundingDirection::Up);
+/// let unrounded = e.unrounded.unwrap();
+/// assert_eq!(unrounded.significand, 0xaaaaaa);
+/// assert_eq!(unrounded.exponent, -25);
+/// assert!(unrounded.guard && !unrounded.round && unrounded.sticky);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Explanation<T> {
+    pub result: T,
+    pub flags: ExceptionFlags,
+    /// the exact result, or `None` if it is not a finite number
+    pub unrounded: Option<Unrounded>,
+    pub direction: RoundingDirection,
+}
+
+impl<T: SoftFloat> fmt::Display for Explanation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.result.to_bits())?;
+        if let Some(unrounded) = &self.unrounded {
+            let direction = match self.direction {
+                RoundingDirection::Exact => "exactly",
+                RoundingDirection::Down => "down",
+                RoundingDirection::Up => "up",
+            };
+            write!(f, " rounded {} from {}", direction, unrounded)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operands of the operations which are explained
+pub(crate) enum Operation<'a, T> {
+    Add(&'a T, &'a T),
+    Sub(&'a T, &'a T),
+    Mul(&'a T, &'a T),
+    Div(&'a T, &'a T),
+    Sqrt(&'a T),
+}
+
+impl<T: SoftFloat> Operation<'_, T> {
+    /// Runs the operation.
+    pub(crate) fn run(&self, rnd: RoundingMode) -> T {
+        match *self {
+            Operation::Add(a, b) => a.add(b, rnd),
+            Operation::Sub(a, b) => a.sub(b, rnd),
+            Operation::Mul(a, b) => a.mul(b, rnd),
+            Operation::Div(a, b) => a.div(b, rnd),
+            Operation::Sqrt(a) => a.sqrt(rnd),
+        }
+    }
+
+    /// Returns the exact result, or `None` if an operand is not a finite number.
+    ///
+    /// Division by zero and the square root of a negative number are not checked.
+    pub(crate) fn exact(&self) -> Option<Unpacked> {
+        match *self {
+            Operation::Add(a, b) => finite2(a, b).map(|(a, b)| add(a, b)),
+            Operation::Sub(a, b) => finite2(a, b).map(|(a, (sb, eb, b))| add(a, (!sb, eb, b))),
+            Operation::Mul(a, b) => finite2(a, b).map(|(a, b)| mul(a, b)),
+            Operation::Div(a, b) => finite2(a, b).map(|(a, b)| div(a, b)),
+            Operation::Sqrt(a) => finite(a).map(sqrt),
+        }
+    }
+}
+
+/// Runs `op` and explains the rounding of its result.
+pub(crate) fn explain<T: SoftFloat>(op: Operation<'_, T>, rnd: RoundingMode) -> Explanation<T> {
+    let (result, flags) = with_flags(|| op.run(rnd));
+    let exact = if result.is_nan() || flags.is_invalid() || flags.is_infinite() {
+        None
+    } else {
+        op.exact()
+    };
+    let unrounded = exact.map(|(sign, exp, sig)| {
+        // the sign of an exact zero depends on the rounding mode
+        truncate::<T>(if sig == 0 { result.is_negative() } else { sign }, exp, sig)
+    });
+
+    let direction = match &unrounded {
+        Some(x) if flags.is_inexact() => {
+            let truncated: T = with_flags(|| {
+                crate::round_pack(false, x.exponent, x.significand, RoundingMode::TowardZero)
+            })
+            .0;
+            // the exact result is between the truncated one and the next one
+            let away = result.abs().to_bits() > truncated.to_bits();
+            if away != x.negative {
+                RoundingDirection::Up
+            } else {
+                RoundingDirection::Down
+            }
+        }
+        _ => RoundingDirection::Exact,
+    };
+    Explanation {
+        result,
+        flags,
+        unrounded,
+        direction,
+    }
+}
+
+/// sign, and the value `sig * 2^exp`, which is exact or has a sticky least significant bit
+pub(crate) type Unpacked = (bool, i32, u128);
+
+fn finite<T: SoftFloat>(x: &T) -> Option<Unpacked> {
+    if x.is_nan() || x.is_infinity() {
+        None
+    } else {
+        Some(unpack(x))
+    }
+}
+
+fn finite2<T: SoftFloat>(a: &T, b: &T) -> Option<(Unpacked, Unpacked)> {
+    Some((finite(a)?, finite(b)?))
+}
+
+/// Shifts `x` right by `shift` bits, or'ing the discarded bits into the least significant one.
+fn jam(x: u128, shift: u32) -> u128 {
+    if shift == 0 {
+        x
+    } else if shift >= 128 {
+        (x != 0) as u128
+    } else {
+        x >> shift | (x & ((1 << shift) - 1) != 0) as u128
+    }
+}
+
+/// Shifts `x` left so that its most significant bit is at bit `msb`.
+fn normalize(x: u128, msb: u32) -> (u128, i32) {
+    let shift = msb as i32 - (127 - x.leading_zeros() as i32);
+    (x << shift, shift)
+}
+
+fn add((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Unpacked {
+    if a == 0 {
+        return (sb, eb, b);
+    }
+    if b == 0 {
+        return (sa, ea, a);
+    }
+    // significands have at most 113 bits, which leaves 14 bits below those of the greater
+    // operand for the smaller one, with the rest or'ed into a sticky bit
+    let (a, sa_shift) = normalize(a, 126);
+    let (b, sb_shift) = normalize(b, 126);
+    let (ea, eb) = (ea - sa_shift, eb - sb_shift);
+    let ((sa, ea, a), (sb, eb, b)) = if (ea, a) >= (eb, b) {
+        ((sa, ea, a), (sb, eb, b))
+    } else {
+        ((sb, eb, b), (sa, ea, a))
+    };
+    let b = jam(b, (ea - eb) as u32);
+    if sa == sb {
+        (sa, ea, a + b)
+    } else {
+        (sa, ea, a - b)
+    }
+}
+
+fn mul((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Unpacked {
+    // 256-bit product of the 64-bit halves
+    let mask = (1u128 << 64) - 1;
+    let (a1, a0) = (a >> 64, a & mask);
+    let (b1, b0) = (b >> 64, b & mask);
+    let mid = a1 * b0 + a0 * b1;
+    let (lo, carry) = (a0 * b0).overflowing_add(mid << 64);
+    let hi = a1 * b1 + (mid >> 64) + carry as u128;
+    let (exp, sig) = if hi == 0 {
+        (0, lo)
+    } else {
+        let shift = 128 - hi.leading_zeros();
+        let sig = (hi << (128 - shift)) | jam(lo, shift);
+        (shift as i32, sig)
+    };
+    (sa != sb, ea + eb + exp, sig)
+}
+
+fn div((sa, ea, a): Unpacked, (sb, eb, b): Unpacked) -> Unpacked {
+    if a == 0 {
+        return (sa != sb, 0, 0);
+    }
+    let (a, sa_shift) = normalize(a, 114);
+    let (b, sb_shift) = normalize(b, 114);
+    // restoring division, 124 bits of quotient with the remainder as a sticky bit
+    let (mut q, mut r) = (0u128, a);
+    for _ in 0..124 {
+        q <<= 1;
+        if r >= b {
+            r -= b;
+            q |= 1;
+        }
+        r <<= 1;
+    }
+    let exp = ea - sa_shift - eb + sb_shift - 123;
+    (sa != sb, exp, q | (r != 0) as u128)
+}
+
+fn sqrt((sign, exp, sig): Unpacked) -> Unpacked {
+    if sig == 0 {
+        return (sign, 0, 0);
+    }
+    let (exp, sig) = if exp % 2 != 0 {
+        (exp - 1, sig << 1)
+    } else {
+        (exp, sig)
+    };
+    // digit-by-digit square root of `sig * 4^(118 - pairs)`, 118 bits of root with the
+    // remainder as a sticky bit
+    let pairs = (128 - sig.leading_zeros() as i32 + 1) / 2;
+    let (mut q, mut r) = (0u128, 0u128);
+    for i in (pairs - 118..pairs).rev() {
+        let pair = if i >= 0 { (sig >> (2 * i)) & 3 } else { 0 };
+        r = (r << 2) | pair;
+        let t = (q << 2) | 1;
+        q <<= 1;
+        if r >= t {
+            r -= t;
+            q |= 1;
+        }
+    }
+    (sign, exp / 2 - (118 - pairs), q | (r != 0) as u128)
+}
+
+/// Truncates `sig * 2^exp` at the rounding position of `T`.
+fn truncate<T: SoftFloat>(negative: bool, exp: i32, sig: u128) -> Unrounded {
+    let bit = |x: u128, i: i32| (0..128).contains(&i) && (x >> i) & 1 != 0;
+    if sig == 0 {
+        return Unrounded {
+            negative,
+            significand: 0,
+            exponent: 0,
+            guard: false,
+            round: false,
+            sticky: false,
+        };
+    }
+    let prec = T::MANTISSA_DIGITS as i32;
+    let bias = T::EXPONENT_BIAS;
+    let emin = 1 - bias;
+    let msb = 127 - sig.leading_zeros() as i32;
+    let e = exp + msb;
+    let keep = if e < emin { prec - (emin - e) } else { prec };
+    let shift = msb + 1 - keep;
+    if shift <= 0 {
+        return Unrounded {
+            negative,
+            significand: sig << -shift,
+            exponent: exp + shift,
+            guard: false,
+            round: false,
+            sticky: false,
+        };
+    }
+    let below = shift - 2;
+    let sticky = below > 0 && (below >= 128 || sig & ((1 << below) - 1) != 0);
+    Unrounded {
+        negative,
+        significand: if shift < 128 { sig >> shift } else { 0 },
+        exponent: exp + shift,
+        guard: bit(sig, shift - 1),
+        round: bit(sig, shift - 2),
+        sticky,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, F16, F64};
+
+    #[test]
+    fn explained_ops() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut flag = ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID);
+        flag.set();
+
+        // 1 + 2^-11 is a tie, rounded down to even
+        let one = F16::from_bits(0x3c00);
+        let e = one.add_explained(F16::from_bits(0x1000), rnd);
+        assert_eq!(e.result.to_bits(), 0x3c00);
+        assert_eq!(e.direction, RoundingDirection::Down);
+        let x = e.unrounded.unwrap();
+        assert_eq!((x.significand, x.exponent), (0x400, -10));
+        assert!(x.guard && !x.round && !x.sticky);
+        assert_eq!(
+            e.to_string(),
+            "0x3c00 rounded down from 0b10000000000 * 2^-10, guard 1, round 0, sticky 0"
+        );
+
+        // the sticky bit decides under other modes
+        let e = one
+            .neg()
+            .sub_explained(F16::from_bits(0x0c01), RoundingMode::TowardZero);
+        assert_eq!(e.result.to_bits(), 0xbc00);
+        assert_eq!(e.direction, RoundingDirection::Up);
+        let x = e.unrounded.unwrap();
+        assert!(x.negative && !x.guard && x.round && x.sticky);
+
+        // subnormal results are rounded at a lower precision
+        let e = F16::from_bits(0x0401).mul_explained(F16::from_bits(0x3400), rnd);
+        assert_eq!(e.result.to_bits(), 0x0100);
+        let x = e.unrounded.unwrap();
+        assert_eq!((x.significand, x.exponent), (0x100, -24));
+        assert!(!x.guard && x.round && !x.sticky);
+        assert!(e.flags.is_underflow());
+
+        let two = F64::from_bits(0x4000000000000000);
+        let e = two.sqrt_explained(rnd);
+        assert_eq!(e.result.to_bits(), 0x3ff6a09e667f3bcd);
+        assert_eq!(e.direction, RoundingDirection::Up);
+        assert_eq!(e.unrounded.unwrap().significand, 0x16a09e667f3bcc);
+        let e = F64::from_bits(0x4022000000000000).sqrt_explained(rnd);
+        assert_eq!(e.result.to_bits(), 0x4008000000000000);
+        assert_eq!(e.direction, RoundingDirection::Exact);
+
+        // overflow rounds from a finite value, invalid operations from none
+        let max = F64::from_bits(0x7fefffffffffffff);
+        let e = max.mul_explained(two, RoundingMode::TowardNegative);
+        assert_eq!(e.result.to_bits(), max.to_bits());
+        assert_eq!(e.direction, RoundingDirection::Down);
+        let e = max
+            .neg()
+            .add_explained(max.neg(), RoundingMode::TowardNegative);
+        assert!(e.result.is_infinity());
+        assert_eq!(e.direction, RoundingDirection::Down);
+        let e = F64::zero().div_explained(F64::zero(), rnd);
+        assert!(e.unrounded.is_none() && e.flags.is_invalid());
+        assert!(!e.to_string().contains("rounded"));
+
+        flag.get();
+        assert_eq!(flag.to_bits(), ExceptionFlags::FLAG_INVALID);
+    }
+}
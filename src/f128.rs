@@ -1,49 +1,130 @@
-use crate::{RoundingMode, SoftFloat, F16, F32, F64};
-use softfloat_sys::float128_t;
+use crate::sys::{self, float128_t};
+use crate::{
+    round_pack, FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, F16, F32, F64,
+};
 use std::borrow::Borrow;
 
 /// standard 128-bit float
+///
+/// The bits are stored in two 64-bit words ordered like the halves of a `u128` in memory, so
+/// the layout is the one of `__float128` and `to_bits` gives the same `u128` on every target.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
 #[repr(transparent)]
-pub struct F128(float128_t);
+pub struct F128([u64; 2]);
+
+/// index of the word holding the low 64 bits
+const LOW: usize = if cfg!(target_endian = "little") { 0 } else { 1 };
 
 impl F128 {
     pub const fn from_bits(v: u128) -> Self {
-        Self(float128_t { v: [v as u64, (v >> 64) as u64] })
+        let mut words = [0; 2];
+        words[LOW] = v as u64;
+        words[1 - LOW] = (v >> 64) as u64;
+        Self(words)
     }
 
     pub const fn to_bits(&self) -> u128 {
-        let mut ret = 0u128;
-        ret |= self.0.v[0] as u128;
-        ret |= (self.0.v[1] as u128) << 64;
-        ret
+        (self.0[1 - LOW] as u128) << 64 | self.0[LOW] as u128
+    }
+
+    /// Returns the value of a softfloat `float128_t`, which has the same layout.
+    #[inline]
+    pub(crate) fn from_sys(x: float128_t) -> Self {
+        Self(x.v)
+    }
+
+    #[inline]
+    pub(crate) fn sys(self) -> float128_t {
+        float128_t { v: self.0 }
+    }
+
+    /// Converts an x87 80-bit extended value, as stored by `fstp tbyte` or in the first 10
+    /// bytes of a `long double` on x86, exactly.
+    ///
+    /// Signaling NaNs stay signaling and keep their payload. Pseudo-NaNs and
+    /// pseudo-infinities, which x87 rejects as invalid operands, give the default NaN;
+    /// unnormals and pseudo-denormals give the value they represent.
+    pub fn from_x87_extended_bytes(bytes: [u8; 10]) -> Self {
+        let mut sig = [0; 8];
+        sig.copy_from_slice(&bytes[..8]);
+        let sig = u64::from_le_bytes(sig);
+        let se = u16::from_le_bytes([bytes[8], bytes[9]]);
+        let sign = (se as u128 >> 15) << 127;
+        let exp = se & 0x7fff;
+        if exp == 0x7fff {
+            if sig >> 63 == 0 {
+                return Self::quiet_nan();
+            }
+            // the fraction of infinities and NaNs is the same, shifted to the top
+            return Self::from_bits(sign | 0x7fff << 112 | ((sig << 1) as u128) << 48);
+        }
+        // the exponent bias is the same as in F128, and the integer bit explicit
+        round_pack(
+            sign != 0,
+            exp.max(1) as i32 - 16383 - 63,
+            sig as u128,
+            RoundingMode::TiesToEven,
+        )
+    }
+
+    /// Converts `self` to the pair of `F64` of a PowerPC IBM `long double`, the high part
+    /// first as in memory.
+    ///
+    /// The high part is `self` rounded to nearest, and the low part the rest rounded per
+    /// `rnd`, so that their sum is `self` rounded per `rnd` to 106 bits. Infinities and NaNs,
+    /// including values which overflow `F64`, have a low part of `+0`.
+    pub fn to_ppc_doubledouble(&self, rnd: RoundingMode) -> [F64; 2] {
+        let hi = self.to_f64(RoundingMode::TiesToEven);
+        if !hi.is_finite() {
+            return [hi, F64::zero()];
+        }
+        // exact, as `hi` is `self` rounded
+        let lo = self.sub(
+            hi.to_f128(RoundingMode::TiesToEven),
+            RoundingMode::TiesToEven,
+        );
+        [hi, lo.to_f64(rnd)]
+    }
+
+    /// Converts the pair of `F64` of a PowerPC IBM `long double`, the high part first as in
+    /// memory, rounding their sum per `rnd`.
+    pub fn from_ppc_doubledouble(x: [F64; 2], rnd: RoundingMode) -> Self {
+        let [hi, lo] = x;
+        let hi = hi.to_f128(rnd);
+        if !hi.is_finite() {
+            return hi;
+        }
+        hi.add(lo.to_f128(rnd), rnd)
     }
 }
 
-impl SoftFloat for F128 {
+impl FloatBits for F128 {
     type Payload = u128;
 
     const MANTISSA_MASK: Self::Payload = 0xffff_ffff_ffff_ffff_ffff_ffff_ffff;
+
     const EXPONENT_MASK: Self::Payload = 0x7fff;
+
     const MANTISSA_BITS: usize = 112;
+
     const EXPONENT_BITS: usize = 15;
-    const EXPONENT_OFFSET: usize = 112;
-    const SIGN_OFFSET: usize = 127;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f32(v: f32) -> Self {
-        F32::from_bits(v.to_bits()).to_f128(RoundingMode::TiesToEven)
-    }
+    const EXPONENT_OFFSET: usize = 112;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f64(v: f64) -> Self {
-        F64::from_bits(v.to_bits()).to_f128(RoundingMode::TiesToEven)
-    }
+    const SIGN_OFFSET: usize = 127;
 
     #[inline]
     fn set_payload(&mut self, x: Self::Payload) {
-        let x = [x as u64, (x >> 64) as u64];
-        self.0.v = x;
+        *self = Self::from_bits(x);
     }
 
     #[inline]
@@ -61,154 +142,283 @@ impl SoftFloat for F128 {
         self.to_bits()
     }
 
+    fn is_signaling_nan(&self) -> bool {
+        unsafe { sys::f128_isSignalingNaN(self.sys()) }
+    }
+}
+
+impl FloatArith for F128 {
     fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_add(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f128_add", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_add(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_sub(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f128_sub", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_sub(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_mul(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f128_mul", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_mul(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_mulAdd(self.0, x.borrow().0, y.borrow().0) };
-        Self(ret)
+        traced!("f128_mulAdd", Some(rnd), [self, x.borrow(), y.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_mulAdd(self.sys(), x.borrow().sys(), y.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_div(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f128_div", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_div(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_rem(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f128_rem", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f128_rem(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sqrt(&self, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_sqrt(self.0) };
-        Self(ret)
+        traced!("f128_sqrt", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f128_sqrt(self.sys()) };
+            Self(ret.v)
+        })
     }
 
+    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
+        traced!("f128_roundToInt", Some(rnd), [self], {
+            let ret = unsafe { sys::f128_roundToInt(self.sys(), rnd.to_softfloat(), false) };
+            Self(ret.v)
+        })
+    }
+}
+
+impl FloatCompare for F128 {
     fn eq<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_eq(self.0, x.borrow().0) }
+        traced!("f128_eq", None, [self, x.borrow()], {
+            unsafe { sys::f128_eq(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_lt(self.0, x.borrow().0) }
+        traced!("f128_lt", None, [self, x.borrow()], {
+            unsafe { sys::f128_lt(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_le(self.0, x.borrow().0) }
+        traced!("f128_le", None, [self, x.borrow()], {
+            unsafe { sys::f128_le(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_lt_quiet(self.0, x.borrow().0) }
+        traced!("f128_lt_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f128_lt_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_le_quiet(self.0, x.borrow().0) }
+        traced!("f128_le_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f128_le_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f128_eq_signaling(self.0, x.borrow().0) }
+        traced!("f128_eq_signaling", None, [self, x.borrow()], {
+            unsafe { sys::f128_eq_signaling(self.sys(), x.borrow().sys()) }
+        })
     }
+}
 
-    fn is_signaling_nan(&self) -> bool {
-        unsafe { softfloat_sys::f128_isSignalingNaN(self.0) }
+impl FloatConvert for F128 {
+    #[cfg(feature = "native-float")]
+    fn from_native_f32(v: f32) -> Self {
+        F32::from_bits(v.to_bits()).to_f128(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64(v: f64) -> Self {
+        F64::from_bits(v.to_bits()).to_f128(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f32_rounded(v: f32, rnd: RoundingMode) -> Self {
+        F32::from_bits(v.to_bits()).to_f128(rnd)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64_rounded(v: f64, rnd: RoundingMode) -> Self {
+        F64::from_bits(v.to_bits()).to_f128(rnd)
     }
 
     fn from_u32(x: u32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui32_to_f128(x) };
-        Self(ret)
+        traced!("ui32_to_f128", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui32_to_f128(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_u64(x: u64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui64_to_f128(x) };
-        Self(ret)
+        traced!("ui64_to_f128", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui64_to_f128(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i32(x: i32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i32_to_f128(x) };
-        Self(ret)
+        traced!("i32_to_f128", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i32_to_f128(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i64(x: i64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i64_to_f128(x) };
-        Self(ret)
+        traced!("i64_to_f128", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i64_to_f128(x) };
+            Self(ret.v)
+        })
     }
 
     fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32 {
-        let ret = unsafe { softfloat_sys::f128_to_ui32(self.0, rnd.to_softfloat(), exact) };
-        ret as u32
+        traced!("f128_to_ui32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f128_to_ui32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as u32
+        })
     }
 
     fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64 {
-        let ret = unsafe { softfloat_sys::f128_to_ui64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f128_to_ui64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f128_to_ui64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32 {
-        let ret = unsafe { softfloat_sys::f128_to_i32(self.0, rnd.to_softfloat(), exact) };
-        ret as i32
+        traced!("f128_to_i32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f128_to_i32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as i32
+        })
     }
 
     fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64 {
-        let ret = unsafe { softfloat_sys::f128_to_i64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f128_to_i64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f128_to_i64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_f16(&self, rnd: RoundingMode) -> F16 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_to_f16(self.0) };
-        F16::from_bits(ret.v)
+        traced!("f128_to_f16", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f128_to_f16(self.sys()) };
+            F16::from_bits(ret.v)
+        })
     }
 
     fn to_f32(&self, rnd: RoundingMode) -> F32 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_to_f32(self.0) };
-        F32::from_bits(ret.v)
+        traced!("f128_to_f32", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f128_to_f32(self.sys()) };
+            F32::from_bits(ret.v)
+        })
     }
 
     fn to_f64(&self, rnd: RoundingMode) -> F64 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f128_to_f64(self.0) };
-        F64::from_bits(ret.v)
+        traced!("f128_to_f64", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f128_to_f64(self.sys()) };
+            F64::from_bits(ret.v)
+        })
     }
 
     fn to_f128(&self, _rnd: RoundingMode) -> F128 {
         Self::from_bits(self.to_bits())
     }
-
-    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
-        let ret = unsafe { softfloat_sys::f128_roundToInt(self.0, rnd.to_softfloat(), false) };
-        Self(ret)
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ExceptionFlags;
+    use crate::{ExceptionFlags, FloatCompare};
     use std::cmp::Ordering;
 
+    #[test]
+    fn f128_bits() {
+        let bits = 0x3fff_0000_0000_0000_0000_0000_0000_0001;
+        let a = F128::from_bits(bits);
+        assert_eq!(a.to_bits(), bits);
+        let words: [u64; 2] = unsafe { std::mem::transmute(bits) };
+        assert_eq!(a.0, words);
+        let mut b = F128::from_bits(0);
+        b.set_payload(bits);
+        assert_eq!(b.to_bits(), bits);
+    }
+
+    #[test]
+    fn f128_layouts() {
+        let a = F128::from_bits(0x3fff_8000_0000_0000_0000_0000_0000_0001);
+        let bytes = a.to_le_bytes();
+        assert_eq!(bytes[0], 1);
+        assert_eq!(bytes[15], 0x3f);
+        assert_eq!(F128::from_le_bytes(bytes).to_bits(), a.to_bits());
+
+        let x87 = |sig: u64, se: u16| {
+            let mut bytes = [0; 10];
+            bytes[..8].copy_from_slice(&sig.to_le_bytes());
+            bytes[8..].copy_from_slice(&se.to_le_bytes());
+            F128::from_x87_extended_bytes(bytes).to_bits()
+        };
+        // 1.5, -∞, a signaling NaN, the smallest denormal, a pseudo-denormal and an unnormal
+        assert_eq!(x87(0xc000000000000000, 0x3fff), 0x3fff_8000 << 96);
+        assert_eq!(x87(0x8000000000000000, 0xffff), 0xffff << 112);
+        assert_eq!(
+            x87(0x8000000000000001, 0x7fff),
+            0x7fff_0000_0000_0000_0000_0000_0000_0000 | 1 << 49
+        );
+        assert_eq!(x87(1, 0), 1 << 49);
+        assert_eq!(x87(0x8000000000000000, 0), 0x0001 << 112);
+        assert_eq!(x87(0x4000000000000000, 0x4000), 0x3fff << 112);
+        assert!(F128::from_bits(x87(0x4000000000000000, 0x7fff)).is_nan());
+
+        // 1 + 2^-54 + 2^-112 needs 113 bits
+        let a = F128::from_bits(0x3fff_0000_0000_0000_0400_0000_0000_0001);
+        let [hi, lo] = a.to_ppc_doubledouble(RoundingMode::TiesToEven);
+        assert_eq!(hi.to_bits(), 0x3ff0000000000000);
+        assert_eq!(lo.to_bits(), 0x3c90000000000000);
+        let b = F128::from_ppc_doubledouble([hi, lo], RoundingMode::TiesToEven);
+        assert_eq!(b.to_bits(), 0x3fff_0000_0000_0000_0400_0000_0000_0000);
+        let [hi, lo] = a.to_ppc_doubledouble(RoundingMode::TowardPositive);
+        assert_eq!(lo.to_bits(), 0x3c90000000000001);
+        let b = F128::from_ppc_doubledouble([hi, lo], RoundingMode::TiesToEven);
+        assert_eq!(b.to_bits(), 0x3fff_0000_0000_0000_0400_0000_0000_0040);
+        let [hi, lo] = F128::infinity().to_ppc_doubledouble(RoundingMode::TiesToEven);
+        assert!(hi.is_infinity() && lo.is_zero());
+    }
+
     #[test]
     fn f128_add() {
         let a = 0x12345678ffffffffffffffffffffffff;
@@ -335,13 +545,13 @@ mod tests {
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&a, a), false);
+        assert_eq!(FloatCompare::eq(&a, a), false);
         flag.get();
         assert_eq!(flag.is_invalid(), true);
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&b, b), false);
+        assert_eq!(FloatCompare::eq(&b, b), false);
         flag.get();
         assert_eq!(flag.is_invalid(), false);
 
@@ -371,4 +581,19 @@ mod tests {
         let a = F128::from_native_f64(0.1);
         assert_eq!(a.to_bits(), 0x3ffb999999999999a000000000000000);
     }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn from_f64_rounded() {
+        // widening is exact, so no rounding mode changes the bits or raises a flag, even
+        // for values which the narrower formats round
+        for rnd in [RoundingMode::TowardPositive, RoundingMode::TowardZero] {
+            let (a, flags) = crate::with_flags(|| F128::from_native_f64_rounded(0.1, rnd));
+            assert_eq!(a.to_bits(), 0x3ffb999999999999a000000000000000);
+            assert_eq!(flags.to_bits(), 0);
+            let (a, flags) = crate::with_flags(|| F128::from_native_f32_rounded(1e-45, rnd));
+            assert_eq!(a.to_bits(), 0x3f6a0000000000000000000000000000);
+            assert_eq!(flags.to_bits(), 0);
+        }
+    }
 }
@@ -1,45 +1,70 @@
-use crate::{RoundingMode, SoftFloat, F32, F64};
-use softfloat_sys::float16_t;
+use crate::sys::{self, float16_t};
+use crate::{FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, F32, F64};
 use std::borrow::Borrow;
+use std::num::FpCategory;
 
 /// standard 16-bit float
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
 #[repr(transparent)]
-pub struct F16(float16_t);
+pub struct F16(u16);
 
 impl F16 {
     pub const fn from_bits(v: u16) -> Self {
-        Self(float16_t { v })
+        Self(v)
     }
 
     pub const fn to_bits(&self) -> u16 {
-        self.0.v
+        self.0
     }
-}
 
-impl SoftFloat for F16 {
-    type Payload = u16;
+    /// Iterates over all 65536 bit patterns, in increasing order of bits.
+    pub fn all() -> impl DoubleEndedIterator<Item = F16> + ExactSizeIterator + Clone {
+        (0..=u16::MAX).map(Self)
+    }
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f32(v: f32) -> Self {
-        F32::from_bits(v.to_bits()).to_f16(RoundingMode::TiesToEven)
+    /// Iterates over all finite values, zeros and subnormals included.
+    pub fn all_finite() -> impl DoubleEndedIterator<Item = F16> + Clone {
+        Self::all().filter(|x| x.is_finite())
     }
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f64(v: f64) -> Self {
-        F64::from_bits(v.to_bits()).to_f16(RoundingMode::TiesToEven)
+    /// Iterates over all values of the given class, such as `FpCategory::Subnormal`.
+    pub fn all_of(class: FpCategory) -> impl DoubleEndedIterator<Item = F16> + Clone {
+        Self::all().filter(move |x| x.classify() == class)
     }
 
+    #[inline]
+    fn sys(self) -> float16_t {
+        float16_t { v: self.0 }
+    }
+}
+
+impl FloatBits for F16 {
+    type Payload = u16;
+
     const MANTISSA_MASK: Self::Payload = 0x3ff;
+
     const EXPONENT_MASK: Self::Payload = 0x1f;
+
     const MANTISSA_BITS: usize = 10;
+
     const EXPONENT_BITS: usize = 5;
+
     const SIGN_OFFSET: usize = 15;
+
     const EXPONENT_OFFSET: usize = 10;
 
     #[inline]
     fn set_payload(&mut self, x: Self::Payload) {
-        self.0.v = x;
+        self.0 = x;
     }
 
     #[inline]
@@ -57,118 +82,193 @@ impl SoftFloat for F16 {
         self.to_bits()
     }
 
+    fn is_signaling_nan(&self) -> bool {
+        unsafe { sys::f16_isSignalingNaN(self.sys()) }
+    }
+}
+
+impl FloatArith for F16 {
     fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_add(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f16_add", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_add(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_sub(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f16_sub", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_sub(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_mul(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f16_mul", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_mul(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_mulAdd(self.0, x.borrow().0, y.borrow().0) };
-        Self(ret)
+        traced!("f16_mulAdd", Some(rnd), [self, x.borrow(), y.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_mulAdd(self.sys(), x.borrow().sys(), y.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_div(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f16_div", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_div(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_rem(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f16_rem", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f16_rem(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sqrt(&self, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_sqrt(self.0) };
-        Self(ret)
+        traced!("f16_sqrt", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f16_sqrt(self.sys()) };
+            Self(ret.v)
+        })
     }
 
+    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
+        traced!("f16_roundToInt", Some(rnd), [self], {
+            let ret = unsafe { sys::f16_roundToInt(self.sys(), rnd.to_softfloat(), false) };
+            Self(ret.v)
+        })
+    }
+}
+
+impl FloatCompare for F16 {
     fn eq<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_eq(self.0, x.borrow().0) }
+        traced!("f16_eq", None, [self, x.borrow()], {
+            unsafe { sys::f16_eq(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_lt(self.0, x.borrow().0) }
+        traced!("f16_lt", None, [self, x.borrow()], {
+            unsafe { sys::f16_lt(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_le(self.0, x.borrow().0) }
+        traced!("f16_le", None, [self, x.borrow()], {
+            unsafe { sys::f16_le(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_lt_quiet(self.0, x.borrow().0) }
+        traced!("f16_lt_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f16_lt_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_le_quiet(self.0, x.borrow().0) }
+        traced!("f16_le_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f16_le_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f16_eq_signaling(self.0, x.borrow().0) }
+        traced!("f16_eq_signaling", None, [self, x.borrow()], {
+            unsafe { sys::f16_eq_signaling(self.sys(), x.borrow().sys()) }
+        })
     }
+}
 
-    fn is_signaling_nan(&self) -> bool {
-        unsafe { softfloat_sys::f16_isSignalingNaN(self.0) }
+impl FloatConvert for F16 {
+    #[cfg(feature = "native-float")]
+    fn from_native_f32(v: f32) -> Self {
+        F32::from_bits(v.to_bits()).to_f16(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64(v: f64) -> Self {
+        F64::from_bits(v.to_bits()).to_f16(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f32_rounded(v: f32, rnd: RoundingMode) -> Self {
+        F32::from_bits(v.to_bits()).to_f16(rnd)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64_rounded(v: f64, rnd: RoundingMode) -> Self {
+        F64::from_bits(v.to_bits()).to_f16(rnd)
     }
 
     fn from_u32(x: u32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui32_to_f16(x) };
-        Self(ret)
+        traced!("ui32_to_f16", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui32_to_f16(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_u64(x: u64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui64_to_f16(x) };
-        Self(ret)
+        traced!("ui64_to_f16", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui64_to_f16(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i32(x: i32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i32_to_f16(x) };
-        Self(ret)
+        traced!("i32_to_f16", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i32_to_f16(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i64(x: i64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i64_to_f16(x) };
-        Self(ret)
+        traced!("i64_to_f16", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i64_to_f16(x) };
+            Self(ret.v)
+        })
     }
 
     fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32 {
-        let ret = unsafe { softfloat_sys::f16_to_ui32(self.0, rnd.to_softfloat(), exact) };
-        ret as u32
+        traced!("f16_to_ui32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f16_to_ui32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as u32
+        })
     }
 
     fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64 {
-        let ret = unsafe { softfloat_sys::f16_to_ui64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f16_to_ui64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f16_to_ui64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32 {
-        let ret = unsafe { softfloat_sys::f16_to_i32(self.0, rnd.to_softfloat(), exact) };
-        ret as i32
+        traced!("f16_to_i32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f16_to_i32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as i32
+        })
     }
 
     fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64 {
-        let ret = unsafe { softfloat_sys::f16_to_i64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f16_to_i64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f16_to_i64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_f16(&self, _rnd: RoundingMode) -> F16 {
@@ -176,37 +276,35 @@ impl SoftFloat for F16 {
     }
 
     fn to_f32(&self, rnd: RoundingMode) -> F32 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_to_f32(self.0) };
-        F32::from_bits(ret.v)
+        traced!("f16_to_f32", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f16_to_f32(self.sys()) };
+            F32::from_bits(ret.v)
+        })
     }
 
     fn to_f64(&self, rnd: RoundingMode) -> F64 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_to_f64(self.0) };
-        F64::from_bits(ret.v)
+        traced!("f16_to_f64", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f16_to_f64(self.sys()) };
+            F64::from_bits(ret.v)
+        })
     }
 
     #[cfg(feature = "f128")]
     fn to_f128(&self, rnd: RoundingMode) -> super::F128 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f16_to_f128(self.0) };
-        let mut v = 0u128;
-        v |= ret.v[0] as u128;
-        v |= (ret.v[1] as u128) << 64;
-        super::F128::from_bits(v)
-    }
-
-    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
-        let ret = unsafe { softfloat_sys::f16_roundToInt(self.0, rnd.to_softfloat(), false) };
-        Self(ret)
+        traced!("f16_to_f128", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f16_to_f128(self.sys()) };
+            super::F128::from_sys(ret)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ExceptionFlags;
+    use crate::{ExceptionFlags, FloatCompare};
     use std::cmp::Ordering;
 
     #[test]
@@ -332,13 +430,13 @@ mod tests {
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&a, a), false);
+        assert_eq!(FloatCompare::eq(&a, a), false);
         flag.get();
         assert_eq!(flag.is_invalid(), true);
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&b, b), false);
+        assert_eq!(FloatCompare::eq(&b, b), false);
         flag.get();
         assert_eq!(flag.is_invalid(), false);
 
@@ -368,4 +466,31 @@ mod tests {
         let a = F16::from_native_f64(0.1);
         assert_eq!(a.to_bits(), 0x2e66);
     }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn from_f64_rounded() {
+        let a = F16::from_native_f64_rounded(0.1, RoundingMode::TowardPositive);
+        assert_eq!(a.to_bits(), 0x2e67);
+        let a = F16::from_native_f64_rounded(0.1, RoundingMode::TowardZero);
+        assert_eq!(a.to_bits(), 0x2e66);
+    }
+
+    #[test]
+    fn f16_all() {
+        assert_eq!(F16::all().len(), 65536);
+        assert_eq!(F16::all_finite().count(), 63488);
+        assert_eq!(F16::all_of(FpCategory::Normal).count(), 61440);
+        assert_eq!(F16::all_of(FpCategory::Subnormal).count(), 2046);
+        assert_eq!(F16::all_of(FpCategory::Nan).count(), 2046);
+        assert_eq!(F16::all_of(FpCategory::Zero).count(), 2);
+        assert_eq!(F16::all().next_back().unwrap().to_bits(), 0xffff);
+
+        for a in F16::all_finite() {
+            let b = a
+                .to_f32(RoundingMode::TiesToEven)
+                .to_f16(RoundingMode::TiesToEven);
+            assert_eq!(a.to_bits(), b.to_bits());
+        }
+    }
 }
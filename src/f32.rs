@@ -1,45 +1,54 @@
-use crate::{RoundingMode, SoftFloat, F16, F64};
-use softfloat_sys::float32_t;
+use crate::sys::{self, float32_t};
+use crate::{FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, F16, F64};
 use std::borrow::Borrow;
 
 /// standard 32-bit float
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
 #[repr(transparent)]
-pub struct F32(float32_t);
+pub struct F32(u32);
 
 impl F32 {
     pub const fn from_bits(v: u32) -> Self {
-        Self(float32_t { v })
+        Self(v)
     }
 
     pub const fn to_bits(&self) -> u32 {
-        self.0.v
+        self.0
+    }
+
+    #[inline]
+    fn sys(self) -> float32_t {
+        float32_t { v: self.0 }
     }
 }
 
-impl SoftFloat for F32 {
+impl FloatBits for F32 {
     type Payload = u32;
 
     const MANTISSA_MASK: Self::Payload = 0x7f_ffff;
+
     const EXPONENT_MASK: Self::Payload = 0xff;
+
     const MANTISSA_BITS: usize = 23;
+
     const EXPONENT_BITS: usize = 8;
-    const SIGN_OFFSET: usize = 31;
-    const EXPONENT_OFFSET: usize = 23;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f32(v: f32) -> Self {
-        Self::from_bits(v.to_bits())
-    }
+    const SIGN_OFFSET: usize = 31;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f64(v: f64) -> Self {
-        F64::from_bits(v.to_bits()).to_f32(RoundingMode::TiesToEven)
-    }
+    const EXPONENT_OFFSET: usize = 23;
 
     #[inline]
     fn set_payload(&mut self, x: Self::Payload) {
-        self.0.v = x;
+        self.0 = x;
     }
 
     #[inline]
@@ -57,124 +66,227 @@ impl SoftFloat for F32 {
         self.to_bits()
     }
 
+    fn is_signaling_nan(&self) -> bool {
+        unsafe { sys::f32_isSignalingNaN(self.sys()) }
+    }
+}
+
+impl FloatArith for F32 {
     fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_add(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a + b) {
+            return ret;
+        }
+        traced!("f32_add", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_add(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_sub(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a - b) {
+            return ret;
+        }
+        traced!("f32_sub", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_sub(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_mul(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a * b) {
+            return ret;
+        }
+        traced!("f32_mul", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_mul(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_mulAdd(self.0, x.borrow().0, y.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow(), *y.borrow()], |[a, b, c]| {
+            a.mul_add(b, c)
+        }) {
+            return ret;
+        }
+        traced!("f32_mulAdd", Some(rnd), [self, x.borrow(), y.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_mulAdd(self.sys(), x.borrow().sys(), y.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_div(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a / b) {
+            return ret;
+        }
+        traced!("f32_div", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_div(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_rem(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f32_rem", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f32_rem(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sqrt(&self, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_sqrt(self.0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self], |[a]| a.sqrt()) {
+            return ret;
+        }
+        traced!("f32_sqrt", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f32_sqrt(self.sys()) };
+            Self(ret.v)
+        })
+    }
+
+    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
+        traced!("f32_roundToInt", Some(rnd), [self], {
+            let ret = unsafe { sys::f32_roundToInt(self.sys(), rnd.to_softfloat(), false) };
+            Self(ret.v)
+        })
     }
+}
 
+impl FloatCompare for F32 {
     fn eq<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_eq(self.0, x.borrow().0) }
+        traced!("f32_eq", None, [self, x.borrow()], {
+            unsafe { sys::f32_eq(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_lt(self.0, x.borrow().0) }
+        traced!("f32_lt", None, [self, x.borrow()], {
+            unsafe { sys::f32_lt(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_le(self.0, x.borrow().0) }
+        traced!("f32_le", None, [self, x.borrow()], {
+            unsafe { sys::f32_le(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_lt_quiet(self.0, x.borrow().0) }
+        traced!("f32_lt_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f32_lt_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_le_quiet(self.0, x.borrow().0) }
+        traced!("f32_le_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f32_le_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f32_eq_signaling(self.0, x.borrow().0) }
+        traced!("f32_eq_signaling", None, [self, x.borrow()], {
+            unsafe { sys::f32_eq_signaling(self.sys(), x.borrow().sys()) }
+        })
     }
+}
 
-    fn is_signaling_nan(&self) -> bool {
-        unsafe { softfloat_sys::f32_isSignalingNaN(self.0) }
+impl FloatConvert for F32 {
+    #[cfg(feature = "native-float")]
+    fn from_native_f32(v: f32) -> Self {
+        Self::from_bits(v.to_bits())
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64(v: f64) -> Self {
+        F64::from_bits(v.to_bits()).to_f32(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f32_rounded(v: f32, _rnd: RoundingMode) -> Self {
+        Self::from_bits(v.to_bits())
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64_rounded(v: f64, rnd: RoundingMode) -> Self {
+        F64::from_bits(v.to_bits()).to_f32(rnd)
     }
 
     fn from_u32(x: u32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui32_to_f32(x) };
-        Self(ret)
+        traced!("ui32_to_f32", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui32_to_f32(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_u64(x: u64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui64_to_f32(x) };
-        Self(ret)
+        traced!("ui64_to_f32", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui64_to_f32(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i32(x: i32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i32_to_f32(x) };
-        Self(ret)
+        traced!("i32_to_f32", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i32_to_f32(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i64(x: i64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i64_to_f32(x) };
-        Self(ret)
+        traced!("i64_to_f32", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i64_to_f32(x) };
+            Self(ret.v)
+        })
     }
 
     fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32 {
-        let ret = unsafe { softfloat_sys::f32_to_ui32(self.0, rnd.to_softfloat(), exact) };
-        ret as u32
+        traced!("f32_to_ui32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f32_to_ui32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as u32
+        })
     }
 
     fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64 {
-        let ret = unsafe { softfloat_sys::f32_to_ui64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f32_to_ui64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f32_to_ui64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32 {
-        let ret = unsafe { softfloat_sys::f32_to_i32(self.0, rnd.to_softfloat(), exact) };
-        ret as i32
+        traced!("f32_to_i32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f32_to_i32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as i32
+        })
     }
 
     fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64 {
-        let ret = unsafe { softfloat_sys::f32_to_i64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f32_to_i64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f32_to_i64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_f16(&self, rnd: RoundingMode) -> F16 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_to_f16(self.0) };
-        F16::from_bits(ret.v)
+        traced!("f32_to_f16", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f32_to_f16(self.sys()) };
+            F16::from_bits(ret.v)
+        })
     }
 
     fn to_f32(&self, _rnd: RoundingMode) -> F32 {
@@ -182,31 +294,27 @@ impl SoftFloat for F32 {
     }
 
     fn to_f64(&self, rnd: RoundingMode) -> F64 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_to_f64(self.0) };
-        F64::from_bits(ret.v)
+        traced!("f32_to_f64", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f32_to_f64(self.sys()) };
+            F64::from_bits(ret.v)
+        })
     }
 
     #[cfg(feature = "f128")]
     fn to_f128(&self, rnd: RoundingMode) -> super::F128 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f32_to_f128(self.0) };
-        let mut v = 0u128;
-        v |= ret.v[0] as u128;
-        v |= (ret.v[1] as u128) << 64;
-        super::F128::from_bits(v)
-    }
-
-    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
-        let ret = unsafe { softfloat_sys::f32_roundToInt(self.0, rnd.to_softfloat(), false) };
-        Self(ret)
+        traced!("f32_to_f128", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f32_to_f128(self.sys()) };
+            super::F128::from_sys(ret)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ExceptionFlags;
+    use crate::{ExceptionFlags, FloatArith, FloatCompare};
     use std::cmp::Ordering;
 
     #[test]
@@ -215,7 +323,7 @@ mod tests {
         let b = 0x76543210;
         let a0 = F32::from_bits(a);
         let b0 = F32::from_bits(b);
-        let d0 = SoftFloat::add(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::add(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F32::from_bits(a);
         let b1 = simple_soft_float::F32::from_bits(b);
         let d1 = a1.add(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -228,7 +336,7 @@ mod tests {
         let b = 0x76543210;
         let a0 = F32::from_bits(a);
         let b0 = F32::from_bits(b);
-        let d0 = SoftFloat::sub(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::sub(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F32::from_bits(a);
         let b1 = simple_soft_float::F32::from_bits(b);
         let d1 = a1.sub(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -241,7 +349,7 @@ mod tests {
         let b = 0x76543210;
         let a0 = F32::from_bits(a);
         let b0 = F32::from_bits(b);
-        let d0 = SoftFloat::mul(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::mul(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F32::from_bits(a);
         let b1 = simple_soft_float::F32::from_bits(b);
         let d1 = a1.mul(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -275,7 +383,7 @@ mod tests {
         let b = 0x12343210;
         let a0 = F32::from_bits(a);
         let b0 = F32::from_bits(b);
-        let d0 = SoftFloat::div(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::div(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F32::from_bits(a);
         let b1 = simple_soft_float::F32::from_bits(b);
         let d1 = a1.div(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -288,7 +396,7 @@ mod tests {
         let b = 0x12343210;
         let a0 = F32::from_bits(a);
         let b0 = F32::from_bits(b);
-        let d0 = SoftFloat::rem(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::rem(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F32::from_bits(a);
         let b1 = simple_soft_float::F32::from_bits(b);
         let d1 = a1.ieee754_remainder(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -332,13 +440,13 @@ mod tests {
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&a, a), false);
+        assert_eq!(FloatCompare::eq(&a, a), false);
         flag.get();
         assert_eq!(flag.is_invalid(), true);
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(SoftFloat::eq(&b, b), false);
+        assert_eq!(FloatCompare::eq(&b, b), false);
         flag.get();
         assert_eq!(flag.is_invalid(), false);
 
@@ -368,4 +476,13 @@ mod tests {
         let a = F32::from_native_f64(0.1);
         assert_eq!(a.to_bits(), 0x3dcccccd);
     }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn from_f64_rounded() {
+        let a = F32::from_native_f64_rounded(0.1, RoundingMode::TowardPositive);
+        assert_eq!(a.to_bits(), 0x3dcccccd);
+        let a = F32::from_native_f64_rounded(0.1, RoundingMode::TowardZero);
+        assert_eq!(a.to_bits(), 0x3dcccccc);
+    }
 }
@@ -1,45 +1,54 @@
-use crate::{RoundingMode, SoftFloat, F16, F32};
-use softfloat_sys::float64_t;
+use crate::sys::{self, float64_t};
+use crate::{FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, F16, F32};
 use std::borrow::Borrow;
 
 /// standard 64-bit float
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
 #[repr(transparent)]
-pub struct F64(float64_t);
+pub struct F64(u64);
 
 impl F64 {
     pub const fn from_bits(v: u64) -> Self {
-        Self(float64_t { v })
+        Self(v)
     }
 
     pub const fn to_bits(&self) -> u64 {
-        self.0.v
+        self.0
+    }
+
+    #[inline]
+    fn sys(self) -> float64_t {
+        float64_t { v: self.0 }
     }
 }
 
-impl SoftFloat for F64 {
+impl FloatBits for F64 {
     type Payload = u64;
 
     const MANTISSA_MASK: Self::Payload = 0xf_ffff_ffff_ffff;
+
     const EXPONENT_MASK: Self::Payload = 0x7ff;
+
     const MANTISSA_BITS: usize = 52;
+
     const EXPONENT_BITS: usize = 11;
-    const SIGN_OFFSET: usize = 63;
-    const EXPONENT_OFFSET: usize = 52;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f32(v: f32) -> Self {
-        F32::from_bits(v.to_bits()).to_f64(RoundingMode::TiesToEven)
-    }
+    const SIGN_OFFSET: usize = 63;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f64(v: f64) -> Self {
-        Self::from_bits(v.to_bits())
-    }
+    const EXPONENT_OFFSET: usize = 52;
 
     #[inline]
     fn set_payload(&mut self, x: Self::Payload) {
-        self.0.v = x;
+        self.0 = x;
     }
 
     #[inline]
@@ -57,130 +66,235 @@ impl SoftFloat for F64 {
         self.to_bits()
     }
 
+    fn is_signaling_nan(&self) -> bool {
+        unsafe { sys::f64_isSignalingNaN(self.sys()) }
+    }
+}
+
+impl FloatArith for F64 {
     fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_add(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a + b) {
+            return ret;
+        }
+        traced!("f64_add", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_add(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_sub(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a - b) {
+            return ret;
+        }
+        traced!("f64_sub", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_sub(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_mul(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a * b) {
+            return ret;
+        }
+        traced!("f64_mul", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_mul(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_mulAdd(self.0, x.borrow().0, y.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow(), *y.borrow()], |[a, b, c]| {
+            a.mul_add(b, c)
+        }) {
+            return ret;
+        }
+        traced!("f64_mulAdd", Some(rnd), [self, x.borrow(), y.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_mulAdd(self.sys(), x.borrow().sys(), y.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_div(self.0, x.borrow().0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self, *x.borrow()], |[a, b]| a / b) {
+            return ret;
+        }
+        traced!("f64_div", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_div(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_rem(self.0, x.borrow().0) };
-        Self(ret)
+        traced!("f64_rem", Some(rnd), [self, x.borrow()], {
+            rnd.set();
+            let ret = unsafe { sys::f64_rem(self.sys(), x.borrow().sys()) };
+            Self(ret.v)
+        })
     }
 
     fn sqrt(&self, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_sqrt(self.0) };
-        Self(ret)
+        #[cfg(feature = "fast-path")]
+        if let Some(ret) = crate::fast::run(rnd, [*self], |[a]| a.sqrt()) {
+            return ret;
+        }
+        traced!("f64_sqrt", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f64_sqrt(self.sys()) };
+            Self(ret.v)
+        })
+    }
+
+    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
+        traced!("f64_roundToInt", Some(rnd), [self], {
+            let ret = unsafe { sys::f64_roundToInt(self.sys(), rnd.to_softfloat(), false) };
+            Self(ret.v)
+        })
     }
+}
 
+impl FloatCompare for F64 {
     fn eq<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_eq(self.0, x.borrow().0) }
+        traced!("f64_eq", None, [self, x.borrow()], {
+            unsafe { sys::f64_eq(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_lt(self.0, x.borrow().0) }
+        traced!("f64_lt", None, [self, x.borrow()], {
+            unsafe { sys::f64_lt(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_le(self.0, x.borrow().0) }
+        traced!("f64_le", None, [self, x.borrow()], {
+            unsafe { sys::f64_le(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_lt_quiet(self.0, x.borrow().0) }
+        traced!("f64_lt_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f64_lt_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_le_quiet(self.0, x.borrow().0) }
+        traced!("f64_le_quiet", None, [self, x.borrow()], {
+            unsafe { sys::f64_le_quiet(self.sys(), x.borrow().sys()) }
+        })
     }
 
     fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool {
-        unsafe { softfloat_sys::f64_eq_signaling(self.0, x.borrow().0) }
+        traced!("f64_eq_signaling", None, [self, x.borrow()], {
+            unsafe { sys::f64_eq_signaling(self.sys(), x.borrow().sys()) }
+        })
     }
+}
 
-    fn is_signaling_nan(&self) -> bool {
-        unsafe { softfloat_sys::f64_isSignalingNaN(self.0) }
+impl FloatConvert for F64 {
+    #[cfg(feature = "native-float")]
+    fn from_native_f32(v: f32) -> Self {
+        F32::from_bits(v.to_bits()).to_f64(RoundingMode::TiesToEven)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64(v: f64) -> Self {
+        Self::from_bits(v.to_bits())
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f32_rounded(v: f32, rnd: RoundingMode) -> Self {
+        F32::from_bits(v.to_bits()).to_f64(rnd)
+    }
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64_rounded(v: f64, _rnd: RoundingMode) -> Self {
+        Self::from_bits(v.to_bits())
     }
 
     fn from_u32(x: u32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui32_to_f64(x) };
-        Self(ret)
+        traced!("ui32_to_f64", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui32_to_f64(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_u64(x: u64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::ui64_to_f64(x) };
-        Self(ret)
+        traced!("ui64_to_f64", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::ui64_to_f64(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i32(x: i32, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i32_to_f64(x) };
-        Self(ret)
+        traced!("i32_to_f64", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i32_to_f64(x) };
+            Self(ret.v)
+        })
     }
 
     fn from_i64(x: i64, rnd: RoundingMode) -> Self {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::i64_to_f64(x) };
-        Self(ret)
+        traced!("i64_to_f64", Some(rnd), [x], {
+            rnd.set();
+            let ret = unsafe { sys::i64_to_f64(x) };
+            Self(ret.v)
+        })
     }
 
     fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32 {
-        let ret = unsafe { softfloat_sys::f64_to_ui32(self.0, rnd.to_softfloat(), exact) };
-        ret as u32
+        traced!("f64_to_ui32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f64_to_ui32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as u32
+        })
     }
 
     fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64 {
-        let ret = unsafe { softfloat_sys::f64_to_ui64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f64_to_ui64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f64_to_ui64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32 {
-        let ret = unsafe { softfloat_sys::f64_to_i32(self.0, rnd.to_softfloat(), exact) };
-        ret as i32
+        traced!("f64_to_i32", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f64_to_i32(self.sys(), rnd.to_softfloat(), exact) };
+            ret as i32
+        })
     }
 
     fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64 {
-        let ret = unsafe { softfloat_sys::f64_to_i64(self.0, rnd.to_softfloat(), exact) };
-        ret
+        traced!("f64_to_i64", Some(rnd), exact, [self], {
+            let ret = unsafe { sys::f64_to_i64(self.sys(), rnd.to_softfloat(), exact) };
+            ret
+        })
     }
 
     fn to_f16(&self, rnd: RoundingMode) -> F16 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_to_f16(self.0) };
-        F16::from_bits(ret.v)
+        traced!("f64_to_f16", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f64_to_f16(self.sys()) };
+            F16::from_bits(ret.v)
+        })
     }
 
     fn to_f32(&self, rnd: RoundingMode) -> F32 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_to_f32(self.0) };
-        F32::from_bits(ret.v)
+        traced!("f64_to_f32", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f64_to_f32(self.sys()) };
+            F32::from_bits(ret.v)
+        })
     }
 
     fn to_f64(&self, _rnd: RoundingMode) -> F64 {
@@ -189,24 +303,18 @@ impl SoftFloat for F64 {
 
     #[cfg(feature = "f128")]
     fn to_f128(&self, rnd: RoundingMode) -> super::F128 {
-        rnd.set();
-        let ret = unsafe { softfloat_sys::f64_to_f128(self.0) };
-        let mut v = 0u128;
-        v |= ret.v[0] as u128;
-        v |= (ret.v[1] as u128) << 64;
-        super::F128::from_bits(v)
-    }
-
-    fn round_to_integral(&self, rnd: RoundingMode) -> Self {
-        let ret = unsafe { softfloat_sys::f64_roundToInt(self.0, rnd.to_softfloat(), false) };
-        Self(ret)
+        traced!("f64_to_f128", Some(rnd), [self], {
+            rnd.set();
+            let ret = unsafe { sys::f64_to_f128(self.sys()) };
+            super::F128::from_sys(ret)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ExceptionFlags;
+    use crate::{ExceptionFlags, FloatArith, FloatCompare};
     use std::cmp::Ordering;
 
     #[test]
@@ -215,7 +323,7 @@ mod tests {
         let b = 0x76546410aaaaaaaa;
         let a0 = F64::from_bits(a);
         let b0 = F64::from_bits(b);
-        let d0 = SoftFloat::add(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::add(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F64::from_bits(a);
         let b1 = simple_soft_float::F64::from_bits(b);
         let d1 = a1.add(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -228,7 +336,7 @@ mod tests {
         let b = 0x76546410aaaaaaaa;
         let a0 = F64::from_bits(a);
         let b0 = F64::from_bits(b);
-        let d0 = SoftFloat::sub(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::sub(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F64::from_bits(a);
         let b1 = simple_soft_float::F64::from_bits(b);
         let d1 = a1.sub(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -241,7 +349,7 @@ mod tests {
         let b = 0x76546410aaaaaaaa;
         let a0 = F64::from_bits(a);
         let b0 = F64::from_bits(b);
-        let d0 = SoftFloat::mul(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::mul(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F64::from_bits(a);
         let b1 = simple_soft_float::F64::from_bits(b);
         let d1 = a1.mul(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -275,7 +383,7 @@ mod tests {
         let b = 0x12346410aaaaaaaa;
         let a0 = F64::from_bits(a);
         let b0 = F64::from_bits(b);
-        let d0 = SoftFloat::div(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::div(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F64::from_bits(a);
         let b1 = simple_soft_float::F64::from_bits(b);
         let d1 = a1.div(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -288,7 +396,7 @@ mod tests {
         let b = 0x12346410aaaaaaaa;
         let a0 = F64::from_bits(a);
         let b0 = F64::from_bits(b);
-        let d0 = SoftFloat::rem(&a0, b0, RoundingMode::TiesToEven);
+        let d0 = FloatArith::rem(&a0, b0, RoundingMode::TiesToEven);
         let a1 = simple_soft_float::F64::from_bits(a);
         let b1 = simple_soft_float::F64::from_bits(b);
         let d1 = a1.ieee754_remainder(&b1, Some(simple_soft_float::RoundingMode::TiesToEven), None);
@@ -332,13 +440,13 @@ mod tests {
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(a.eq(a), false);
+        assert_eq!(FloatCompare::eq(&a, a), false);
         flag.get();
         assert_eq!(flag.is_invalid(), true);
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        assert_eq!(b.eq(b), false);
+        assert_eq!(FloatCompare::eq(&b, b), false);
         flag.get();
         assert_eq!(flag.is_invalid(), false);
 
@@ -368,4 +476,19 @@ mod tests {
         let a = F64::from_native_f64(0.1);
         assert_eq!(a.to_bits(), 0x3fb999999999999a);
     }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn from_f64_rounded() {
+        // widening is exact, so no rounding mode changes the bits or raises a flag, even
+        // for values which the narrower formats round
+        for rnd in [RoundingMode::TowardPositive, RoundingMode::TowardZero] {
+            let (a, flags) = crate::with_flags(|| F64::from_native_f32_rounded(0.1, rnd));
+            assert_eq!(a.to_bits(), 0x3fb99999a0000000);
+            assert_eq!(flags.to_bits(), 0);
+            let (a, flags) = crate::with_flags(|| F64::from_native_f64_rounded(5e-324, rnd));
+            assert_eq!(a.to_bits(), 0x0000000000000001);
+            assert_eq!(flags.to_bits(), 0);
+        }
+    }
 }
@@ -0,0 +1,159 @@
+//! Host FPU fast path
+//!
+//! While the fast path is enabled for a thread with [`set_fast_path`] or [`with_fast_path`],
+//! `add`, `sub`, `mul`, `div`, `sqrt` and `fused_mul_add` of [`F32`] and [`F64`] run on the
+//! native `f32` and `f64` types of the host when the rounding mode is `TiesToEven`, and in
+//! softfloat otherwise. Results of the fast path are correctly rounded, but they raise no
+//! exception flags, their NaNs are the ones of the host, and they bypass the default NaN
+//! mode, the backend, the trace hook, the statistics and the trap handlers. It is meant for
+//! runs which do not observe flags, such as booting a simulated system.
+//!
+//! ```
+//! use softfloat_wrapper::fast::with_fast_path;
+//! use softfloat_wrapper::{ExceptionFlags, FloatArith, RoundingMode, F32};
+//!
+//! let mut flags = ExceptionFlags::default();
+//! flags.set();
+//! let a = F32::from_bits(0x3f800000);
+//! let b = F32::from_bits(0x40400000);
+//! let ret = with_fast_path(|| a.div(b, RoundingMode::TiesToEven));
+//! assert_eq!(ret.to_bits(), 0x3eaaaaab);
+//! flags.get();
+//! assert!(!flags.is_inexact());
+//! ```
+
+use crate::{RoundingMode, F32, F64};
+use std::cell::Cell;
+
+thread_local! {
+    static FAST_PATH: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables the fast path for the operations run by this thread.
+pub fn set_fast_path(enabled: bool) {
+    FAST_PATH.with(|x| x.set(enabled));
+}
+
+/// Returns whether the fast path is enabled for this thread.
+pub fn fast_path() -> bool {
+    FAST_PATH.with(Cell::get)
+}
+
+/// Runs `f` with the fast path enabled, and restores the previous setting, even if `f`
+/// panics.
+pub fn with_fast_path<R>(f: impl FnOnce() -> R) -> R {
+    let _outer = Outer(FAST_PATH.with(|x| x.replace(true)));
+    f()
+}
+
+/// restores the setting of the caller when dropped, even by a panic
+struct Outer(bool);
+
+impl Drop for Outer {
+    fn drop(&mut self) {
+        set_fast_path(self.0);
+    }
+}
+
+/// Float types of the format of a native one
+pub(crate) trait Native: Copy {
+    type Float: Copy;
+
+    fn to_native(self) -> Self::Float;
+
+    fn from_native(x: Self::Float) -> Self;
+}
+
+impl Native for F32 {
+    type Float = f32;
+
+    fn to_native(self) -> f32 {
+        f32::from_bits(self.to_bits())
+    }
+
+    fn from_native(x: f32) -> Self {
+        F32::from_bits(x.to_bits())
+    }
+}
+
+impl Native for F64 {
+    type Float = f64;
+
+    fn to_native(self) -> f64 {
+        f64::from_bits(self.to_bits())
+    }
+
+    fn from_native(x: f64) -> Self {
+        F64::from_bits(x.to_bits())
+    }
+}
+
+/// Runs `f` on the native values of `ops` if the fast path is enabled and `rnd` is
+/// `TiesToEven`.
+#[inline]
+pub(crate) fn run<T: Native, const N: usize>(
+    rnd: RoundingMode,
+    ops: [T; N],
+    f: impl FnOnce([T::Float; N]) -> T::Float,
+) -> Option<T> {
+    if !matches!(rnd, RoundingMode::TiesToEven) || !fast_path() {
+        return None;
+    }
+    Some(T::from_native(f(ops.map(T::to_native))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatArith, FloatBits};
+
+    #[test]
+    fn fast_path_results() {
+        let rnd = RoundingMode::TiesToEven;
+        let values = [
+            0x00000001, 0x00800000, 0x3eaaaaab, 0x3f800000, 0x40400000, 0x7f7fffff, 0x80000000,
+            0xc0490fdb, 0x7f800000,
+        ]
+        .map(F32::from_bits);
+        for a in values {
+            for b in values {
+                let ops: [fn(F32, F32, RoundingMode) -> F32; 6] = [
+                    |a, b, rnd| a.add(b, rnd),
+                    |a, b, rnd| a.sub(b, rnd),
+                    |a, b, rnd| a.mul(b, rnd),
+                    |a, b, rnd| a.div(b, rnd),
+                    |a, _, rnd| a.sqrt(rnd),
+                    |a, b, rnd| a.fused_mul_add(b, a, rnd),
+                ];
+                for op in ops {
+                    let (soft, _) = with_flags(|| op(a, b, rnd));
+                    let (fast, flags) = with_flags(|| with_fast_path(|| op(a, b, rnd)));
+                    assert_eq!(flags.to_bits(), 0);
+                    assert!(
+                        fast.to_bits() == soft.to_bits() || fast.is_nan() && soft.is_nan(),
+                        "{:?} {:?}",
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+
+        // other rounding modes run in softfloat
+        let a = F64::from_bits(0x3ff0000000000000);
+        let b = F64::from_bits(0x4008000000000000);
+        set_fast_path(true);
+        let (ret, flags) = with_flags(|| a.div(b, RoundingMode::TowardZero));
+        assert_eq!(ret.to_bits(), 0x3fd5555555555555);
+        assert!(flags.is_inexact());
+        let (ret, flags) = with_flags(|| a.div(b, rnd));
+        assert_eq!(ret.to_bits(), 0x3fd5555555555555);
+        assert_eq!(flags.to_bits(), 0);
+        set_fast_path(false);
+        assert!(!fast_path());
+
+        // a panic leaves the fast path disabled
+        assert!(std::panic::catch_unwind(|| with_fast_path(|| panic!("fast"))).is_err());
+        assert!(!fast_path());
+    }
+}
@@ -0,0 +1,195 @@
+//! Combining, clearing and printing exception flags
+//!
+//! `|`, `&` and `!` work on the set of raised flags; `!` only yields the five flags defined by
+//! the standard. Flags print with the mnemonics of the RISC-V `fflags` register, most
+//! significant first: `NV` invalid, `DZ` infinite (division by zero), `OF` overflow, `UF`
+//! underflow and `NX` inexact. They parse back from the same mnemonics, in any case and
+//! order, separated by spaces, commas or `|`.
+//!
+//! ```
+//! use softfloat_wrapper::ExceptionFlags;
+//!
+//! let mut flags = ExceptionFlags::from_bits(0x01) | ExceptionFlags::from_bits(0x04);
+//! assert_eq!(flags.to_string(), "OF NX");
+//! flags.clear_inexact();
+//! assert_eq!(flags.to_string(), "OF");
+//! let parsed: ExceptionFlags = "nv,of".parse().unwrap();
+//! assert_eq!(parsed.to_bits(), 0x14);
+//! ```
+
+use crate::ExceptionFlags;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+use std::str::FromStr;
+
+/// flags in display order with their mnemonics
+const NAMES: [(u8, &str); 5] = [
+    (ExceptionFlags::FLAG_INVALID, "NV"),
+    (ExceptionFlags::FLAG_INFINITE, "DZ"),
+    (ExceptionFlags::FLAG_OVERFLOW, "OF"),
+    (ExceptionFlags::FLAG_UNDERFLOW, "UF"),
+    (ExceptionFlags::FLAG_INEXACT, "NX"),
+];
+
+impl ExceptionFlags {
+    /// Raises the flags of `other` in `self` too.
+    pub fn merge(&mut self, other: ExceptionFlags) {
+        self.0 |= other.0;
+    }
+
+    pub fn clear_inexact(&mut self) {
+        self.0 &= !Self::FLAG_INEXACT;
+    }
+
+    pub fn clear_infinite(&mut self) {
+        self.0 &= !Self::FLAG_INFINITE;
+    }
+
+    pub fn clear_invalid(&mut self) {
+        self.0 &= !Self::FLAG_INVALID;
+    }
+
+    pub fn clear_overflow(&mut self) {
+        self.0 &= !Self::FLAG_OVERFLOW;
+    }
+
+    pub fn clear_underflow(&mut self) {
+        self.0 &= !Self::FLAG_UNDERFLOW;
+    }
+
+    /// Returns each raised flag on its own, in display order.
+    pub fn iter(&self) -> impl Iterator<Item = ExceptionFlags> {
+        let x = self.0;
+        NAMES
+            .iter()
+            .filter(move |(flag, _)| x & flag != 0)
+            .map(|(flag, _)| ExceptionFlags(*flag))
+    }
+}
+
+impl BitOr for ExceptionFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for ExceptionFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAnd for ExceptionFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for ExceptionFlags {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl Not for ExceptionFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0 & Self::FLAG_ALL)
+    }
+}
+
+/// Prints the mnemonics of the raised flags separated by spaces, or nothing if none is.
+impl fmt::Display for ExceptionFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (_, name)) in NAMES
+            .iter()
+            .filter(|(flag, _)| self.0 & flag != 0)
+            .enumerate()
+        {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a set of flags, such as `"NV,NX"`. An empty string has no flags raised.
+impl FromStr for ExceptionFlags {
+    type Err = ParseMnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = 0;
+        for name in s
+            .split(|c: char| c == ',' || c == '|' || c.is_whitespace())
+            .filter(|x| !x.is_empty())
+        {
+            let (flag, _) = NAMES
+                .iter()
+                .find(|(_, x)| x.eq_ignore_ascii_case(name))
+                .ok_or(ParseMnemonicError(()))?;
+            flags |= flag;
+        }
+        Ok(ExceptionFlags(flags))
+    }
+}
+
+/// An error returned when parsing an unknown mnemonic of a flag or rounding mode
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseMnemonicError(pub(crate) ());
+
+impl fmt::Display for ParseMnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown mnemonic")
+    }
+}
+
+impl std::error::Error for ParseMnemonicError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_ops() {
+        let nv = ExceptionFlags(ExceptionFlags::FLAG_INVALID);
+        let nx = ExceptionFlags(ExceptionFlags::FLAG_INEXACT);
+
+        let mut flags = nv | nx;
+        assert!(flags.is_invalid() && flags.is_inexact());
+        assert_eq!((flags & nx).to_bits(), nx.to_bits());
+        assert_eq!((!flags).to_string(), "DZ OF UF");
+        assert_eq!((!ExceptionFlags::default()).to_string(), "NV DZ OF UF NX");
+
+        let mut merged = ExceptionFlags(ExceptionFlags::FLAG_OVERFLOW);
+        merged.merge(flags);
+        assert_eq!(merged.to_string(), "NV OF NX");
+        let names: Vec<_> = merged.iter().map(|x| x.to_string()).collect();
+        assert_eq!(names, ["NV", "OF", "NX"]);
+
+        flags.clear_invalid();
+        flags.clear_overflow();
+        assert_eq!(flags.to_bits(), nx.to_bits());
+        flags.clear_inexact();
+        assert_eq!(flags.to_string(), "");
+        assert_eq!(flags.iter().count(), 0);
+    }
+
+    #[test]
+    fn flags_parse() {
+        let flags: ExceptionFlags = "NV,NX".parse().unwrap();
+        assert_eq!(flags.to_bits(), 0x11);
+        let flags: ExceptionFlags = "uf | dz  Of".parse().unwrap();
+        assert_eq!(flags.to_string(), "DZ OF UF");
+        let reparsed: ExceptionFlags = flags.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_bits(), flags.to_bits());
+        assert_eq!("".parse::<ExceptionFlags>().unwrap().to_bits(), 0);
+        assert!("NV,XX".parse::<ExceptionFlags>().is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! Bit pattern formatting
+//!
+//! [`Binary`](fmt::Binary), [`Octal`](fmt::Octal), [`LowerHex`](fmt::LowerHex) and
+//! [`UpperHex`](fmt::UpperHex) format the bits of a value, as they do for the integer
+//! returned by `to_bits`, with the same flags, width and padding.
+//!
+//! ```
+//! use softfloat_wrapper::F32;
+//!
+//! let a = F32::from_bits(0x3f800000);
+//! assert_eq!(format!("{:#x}", a), "0x3f800000");
+//! assert_eq!(format!("{:032b}", F32::from_bits(1)), format!("{:031}1", 0));
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{F16, F32, F64};
+use std::fmt;
+
+macro_rules! impl_format {
+    ($ty:ty) => {
+        impl fmt::Binary for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Binary::fmt(&self.to_bits(), f)
+            }
+        }
+
+        impl fmt::Octal for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Octal::fmt(&self.to_bits(), f)
+            }
+        }
+
+        impl fmt::LowerHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::LowerHex::fmt(&self.to_bits(), f)
+            }
+        }
+
+        impl fmt::UpperHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::UpperHex::fmt(&self.to_bits(), f)
+            }
+        }
+    };
+}
+
+impl_format!(F16);
+impl_format!(F32);
+impl_format!(F64);
+#[cfg(feature = "f128")]
+impl_format!(F128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bits() {
+        let a = F16::from_bits(0xbc00);
+        assert_eq!(format!("{:b}", a), "1011110000000000");
+        assert_eq!(format!("{:o}", a), "136000");
+        assert_eq!(format!("{:#06x}", F16::from_bits(0x3c)), "0x003c");
+        assert_eq!(format!("{:X}", a), "BC00");
+        assert_eq!(
+            format!("{:016x}", F64::from_bits(0x3ff0000000000001)),
+            "3ff0000000000001"
+        );
+    }
+}
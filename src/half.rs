@@ -0,0 +1,33 @@
+//! [half](https://crates.io/crates/half) interop
+//!
+//! `F16` converts to and from `half::f16` through the bit pattern, so every value, NaN
+//! payloads included, is preserved and no exception flags are raised.
+
+use crate::F16;
+
+impl From<::half::f16> for F16 {
+    #[inline]
+    fn from(x: ::half::f16) -> Self {
+        F16::from_bits(x.to_bits())
+    }
+}
+
+impl From<F16> for ::half::f16 {
+    #[inline]
+    fn from(x: F16) -> Self {
+        ::half::f16::from_bits(x.to_bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_convert() {
+        let a: F16 = ::half::f16::from_bits(0x3c00).into();
+        assert_eq!(a.to_bits(), 0x3c00);
+        let b: ::half::f16 = F16::from_bits(0x7e01).into();
+        assert_eq!(b.to_bits(), 0x7e01);
+    }
+}
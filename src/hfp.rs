@@ -0,0 +1,283 @@
+//! IBM hexadecimal floating point
+//!
+//! [`HfpShort`] and [`HfpLong`] are the short and long formats of System/360 and its
+//! successors: a sign, a 7-bit exponent of 16 biased by 64, and a fraction of 6 or 14
+//! hexadecimal digits, with no infinities or NaNs.
+//!
+//! Arithmetic truncates the exact result, and normalizes it; hardware with a single guard
+//! digit may differ in the last digit of some differences. Results which are too large
+//! raise overflow and saturate to the largest magnitude, and results which are too small
+//! raise underflow and are the true zero, as with the exponent-underflow mask clear.
+//! Conversions from and to IEEE formats round per the rounding mode given, and raise the
+//! exception flags of the thread.
+//!
+//! ```
+//! use softfloat_wrapper::hfp::HfpShort;
+//! use softfloat_wrapper::{FloatArith, RoundingMode, F32};
+//!
+//! let a = HfpShort::from_bits(0xc276a000); // -118.625
+//! let b = a.to_float::<F32>(RoundingMode::TiesToEven);
+//! assert_eq!(b.to_bits(), 0xc2ed4000);
+//! let c = HfpShort::from_float(&b, RoundingMode::TiesToEven);
+//! assert_eq!(c.add(c).to_bits(), 0xc2ed4000);
+//! ```
+
+use crate::exact::{add, div, mul, Unpacked};
+use crate::{round_pack, round_shift, unpack, ExceptionFlags, RoundingMode, SoftFloat};
+
+/// Splits `bits`, of the format with `frac` fraction bits, into its exact value.
+fn unpack_hfp(bits: u64, frac: u32) -> Unpacked {
+    let sign = bits >> (frac + 7) & 1 != 0;
+    let exp = (bits >> frac) as i32 & 0x7f;
+    let sig = bits & ((1 << frac) - 1);
+    (sign, 4 * (exp - 64) - frac as i32, sig as u128)
+}
+
+/// Rounds `sig * 2^exp` per `rnd` to the format with `frac` fraction bits, normalized,
+/// raising exception flags.
+///
+/// `sig` must either hold the value exactly, or hold more bits than the fraction with all
+/// discarded bits or'ed into its least significant bit.
+fn pack_hfp((sign, exp, sig): Unpacked, frac: u32, rnd: RoundingMode) -> u64 {
+    let sign_bit = (sign as u64) << (frac + 7);
+    if sig == 0 {
+        return sign_bit;
+    }
+    let bits = 128 - sig.leading_zeros() as i32;
+    // the value is below 16^q, and at least 16^(q - 1)
+    let mut q = (exp + bits + 3).div_euclid(4);
+    let (mut f, inexact) = round_shift(sign, sig, 4 * q - frac as i32 - exp, rnd);
+    if f >> frac != 0 {
+        f >>= 4;
+        q += 1;
+    }
+
+    let mut flags = 0;
+    if inexact {
+        flags |= ExceptionFlags::FLAG_INEXACT;
+    }
+    let ret = if q + 64 > 0x7f {
+        flags |= ExceptionFlags::FLAG_OVERFLOW | ExceptionFlags::FLAG_INEXACT;
+        sign_bit | ((1 << (frac + 7)) - 1)
+    } else if q + 64 < 0 {
+        flags |= ExceptionFlags::FLAG_UNDERFLOW | ExceptionFlags::FLAG_INEXACT;
+        0
+    } else {
+        sign_bit | ((q + 64) as u64) << frac | f as u64
+    };
+    if flags != 0 {
+        ExceptionFlags::from_bits(flags).raise();
+    }
+    ret
+}
+
+/// Truncates an exact, or sticky, result to the format with `frac` fraction bits. Zero
+/// results are the true zero.
+fn truncate((sign, exp, sig): Unpacked, frac: u32) -> u64 {
+    let sign = sign && sig != 0;
+    pack_hfp((sign, exp, sig), frac, RoundingMode::TowardZero)
+}
+
+macro_rules! hfp_format {
+    ($(#[$doc:meta])* $name:ident, $bits:ty, $frac:expr) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug, Default)]
+        #[repr(transparent)]
+        pub struct $name($bits);
+
+        impl $name {
+            const FRAC: u32 = $frac;
+
+            /// Creates a value from its bits.
+            pub const fn from_bits(v: $bits) -> Self {
+                Self(v)
+            }
+
+            /// Returns the bits of `self`.
+            pub const fn to_bits(&self) -> $bits {
+                self.0
+            }
+
+            fn unpack(&self) -> Unpacked {
+                unpack_hfp(self.0 as u64, Self::FRAC)
+            }
+
+            fn truncate(x: Unpacked) -> Self {
+                Self(truncate(x, Self::FRAC) as $bits)
+            }
+
+            /// Returns `true` if `self` is negative, including negative zero.
+            pub fn is_negative(&self) -> bool {
+                self.0 >> (Self::FRAC + 7) != 0
+            }
+
+            /// Returns `true` if the fraction of `self` is zero, whatever its exponent.
+            pub fn is_zero(&self) -> bool {
+                self.0 & ((1 << Self::FRAC) - 1) == 0
+            }
+
+            /// Returns `self` with its sign inverted.
+            pub fn neg(&self) -> Self {
+                Self(self.0 ^ 1 << (Self::FRAC + 7))
+            }
+
+            /// Converts `x` exactly, or rounded per `rnd`.
+            ///
+            /// Infinities and NaNs raise invalid and give the largest magnitude, positive for
+            /// NaNs.
+            pub fn from_float<T: SoftFloat>(x: &T, rnd: RoundingMode) -> Self {
+                if !x.is_finite() {
+                    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+                    let max = Self(!0 >> 1);
+                    return if x.is_infinity() && x.is_negative() {
+                        max.neg()
+                    } else {
+                        max
+                    };
+                }
+                Self(pack_hfp(unpack(x), Self::FRAC, rnd) as $bits)
+            }
+
+            /// Converts `self` to `T`, rounded per `rnd`.
+            pub fn to_float<T: SoftFloat>(&self, rnd: RoundingMode) -> T {
+                let (sign, exp, sig) = self.unpack();
+                round_pack(sign, exp, sig, rnd)
+            }
+
+            /// Adds `x` to `self`, truncating the result.
+            pub fn add(&self, x: Self) -> Self {
+                Self::truncate(add(self.unpack(), x.unpack()))
+            }
+
+            /// Subtracts `x` from `self`, truncating the result.
+            pub fn sub(&self, x: Self) -> Self {
+                self.add(x.neg())
+            }
+
+            /// Multiplies `self` by `x`, truncating the result.
+            pub fn mul(&self, x: Self) -> Self {
+                Self::truncate(mul(self.unpack(), x.unpack()))
+            }
+
+            /// Divides `self` by `x`, truncating the result.
+            ///
+            /// Dividing by zero raises the infinite flag and returns `self`, as the hardware
+            /// suppresses the operation.
+            pub fn div(&self, x: Self) -> Self {
+                match div(self.unpack(), x.unpack()) {
+                    Some(ret) => Self::truncate(ret),
+                    None => {
+                        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INFINITE).raise();
+                        *self
+                    }
+                }
+            }
+        }
+    };
+}
+
+hfp_format!(
+    /// IBM hexadecimal floating point of 32 bits, with 6 hexadecimal digits
+    HfpShort,
+    u32,
+    24
+);
+
+hfp_format!(
+    /// IBM hexadecimal floating point of 64 bits, with 14 hexadecimal digits
+    HfpLong,
+    u64,
+    56
+);
+
+impl HfpShort {
+    /// Converts `self` to the long format exactly.
+    pub fn to_long(&self) -> HfpLong {
+        HfpLong::from_bits((self.0 as u64) << 32)
+    }
+}
+
+impl HfpLong {
+    /// Converts `self` to the short format, rounded per `rnd`. `LOAD ROUNDED` rounds
+    /// [`TiesToAway`](RoundingMode::TiesToAway).
+    pub fn to_short(&self, rnd: RoundingMode) -> HfpShort {
+        HfpShort::from_bits(pack_hfp(self.unpack(), HfpShort::FRAC, rnd) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatBits, F32, F64};
+
+    const ONE: HfpShort = HfpShort::from_bits(0x41100000);
+
+    #[test]
+    fn hfp_convert() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F64::from_bits(0x3fb999999999999a); // 0.1
+        let (ret, flags) = with_flags(|| HfpLong::from_float(&a, rnd));
+        assert_eq!(ret.to_bits(), 0x401999999999999a);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(ret.to_float::<F64>(rnd).to_bits(), a.to_bits());
+        let (ret, flags) = with_flags(|| ret.to_short(RoundingMode::TiesToAway));
+        assert_eq!(ret.to_bits(), 0x4019999a);
+        assert!(flags.is_inexact());
+        assert_eq!(ret.to_long().to_bits(), 0x4019999a00000000);
+        let ret = HfpShort::from_float(&a, RoundingMode::TowardZero);
+        assert_eq!(ret.to_bits(), 0x40199999);
+
+        // the long format has 3 more bits than F64 when its leading digit is 1
+        let b = HfpLong::from_bits(0x41ffffffffffffff);
+        let (ret, flags) = with_flags(|| b.to_float::<F64>(rnd));
+        assert_eq!(ret.to_bits(), 0x4030000000000000);
+        assert!(flags.is_inexact());
+
+        assert_eq!(
+            HfpShort::from_float(&F32::from_bits(0x80000000), rnd).to_bits(),
+            0x80000000
+        );
+        let (ret, flags) = with_flags(|| HfpShort::from_float(&F32::quiet_nan(), rnd));
+        assert_eq!(ret.to_bits(), 0x7fffffff);
+        assert!(flags.is_invalid());
+        let (ret, flags) =
+            with_flags(|| HfpShort::from_float(&F64::from_bits(0x7fefffffffffffff), rnd));
+        assert_eq!(ret.to_bits(), 0x7fffffff);
+        assert!(flags.is_overflow());
+        let (ret, flags) = with_flags(|| HfpLong::from_float(&F64::from_bits(1), rnd));
+        assert_eq!(ret.to_bits(), 0);
+        assert!(flags.is_underflow());
+    }
+
+    #[test]
+    fn hfp_arithmetic() {
+        let a = HfpShort::from_bits(0xc276a000); // -118.625
+        assert_eq!(ONE.add(ONE).to_bits(), 0x41200000);
+        assert_eq!(a.mul(ONE.add(ONE)).to_bits(), 0xc2ed4000);
+        assert_eq!(a.sub(a).to_bits(), 0);
+        // unnormalized operands give normalized results
+        assert_eq!(
+            HfpShort::from_bits(0x42010000).add(ONE).to_bits(),
+            0x41200000
+        );
+
+        let (ret, flags) = with_flags(|| ONE.sub(HfpShort::from_bits(0x20100000)));
+        assert_eq!(ret.to_bits(), 0x40ffffff);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        let three = HfpShort::from_bits(0x41300000);
+        assert_eq!(ONE.div(three).to_bits(), 0x40555555);
+        assert_eq!(ONE.neg().div(three).to_bits(), 0xc0555555);
+
+        let (ret, flags) = with_flags(|| a.div(HfpShort::from_bits(0x41000000)));
+        assert_eq!(ret.to_bits(), a.to_bits());
+        assert!(flags.is_infinite());
+        let max = HfpLong::from_bits(0x7fffffffffffffff);
+        let (ret, flags) = with_flags(|| max.mul(HfpLong::from_bits(0x4210000000000000)));
+        assert_eq!(ret.to_bits(), max.to_bits());
+        assert!(flags.is_overflow());
+        let min = HfpLong::from_bits(0x0010000000000000);
+        let (ret, flags) = with_flags(|| min.neg().mul(min));
+        assert_eq!(ret.to_bits(), 0);
+        assert!(flags.is_underflow());
+    }
+}
@@ -0,0 +1,359 @@
+//! Differential testing against the host FPU
+//!
+//! [`compare`] runs an operation through softfloat and through the native float type of
+//! the same format, and reports a [`Divergence`] when the results or the exception flags
+//! differ. This catches backends built with the wrong specialization, or hosts running with
+//! flush-to-zero.
+//!
+//! On x86-64 the host operation runs in SSE with the requested rounding mode, and its
+//! exception flags are read back from MXCSR; `MulAdd` needs FMA support. Elsewhere only
+//! `TiesToEven` is available, and flags are not compared. Rounding modes the host lacks,
+//! such as `TiesToAway`, are never reported as divergences.
+//!
+//! ```
+//! use softfloat_wrapper::host::{compare, HostOp, KnownDifferences};
+//! use softfloat_wrapper::{RoundingMode, F32};
+//!
+//! let a = F32::from_bits(0x3f800000);
+//! let b = F32::from_bits(0x33800000);
+//! let ret = compare(HostOp::Add, &[a, b], RoundingMode::TiesToEven, KnownDifferences::default());
+//! assert!(ret.is_none());
+//! ```
+
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat, F32, F64};
+
+/// Operation run by [`compare`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HostOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Sqrt,
+    /// `a * b + c` with a single rounding
+    MulAdd,
+}
+
+impl HostOp {
+    /// number of operands
+    pub fn arity(&self) -> usize {
+        match self {
+            HostOp::Sqrt => 1,
+            HostOp::MulAdd => 3,
+            _ => 2,
+        }
+    }
+}
+
+/// Differences which [`compare`] does not report
+#[derive(Copy, Clone, Debug)]
+pub struct KnownDifferences {
+    /// any NaN result matches any other NaN result
+    pub nan_payloads: bool,
+    /// the underflow flag is not compared, for hosts detecting tininess before rounding
+    pub underflow: bool,
+}
+
+impl Default for KnownDifferences {
+    fn default() -> Self {
+        Self {
+            nan_payloads: true,
+            underflow: false,
+        }
+    }
+}
+
+/// Results of an operation which differ between softfloat and the host
+#[derive(Copy, Clone, Debug)]
+pub struct Divergence<T> {
+    pub soft: T,
+    pub soft_flags: ExceptionFlags,
+    pub host: T,
+    /// `None` if the host cannot report exception flags
+    pub host_flags: Option<ExceptionFlags>,
+}
+
+/// Float types with a native counterpart on the host
+pub trait HostFloat: SoftFloat + Copy + sealed::Sealed {}
+
+impl HostFloat for F32 {}
+impl HostFloat for F64 {}
+
+mod sealed {
+    use crate::{ExceptionFlags, RoundingMode};
+
+    use super::HostOp;
+
+    pub trait Sealed: Sized {
+        /// result and flags of `op` on the host, or `None` if the host cannot run it
+        fn host(
+            op: HostOp,
+            operands: &[Self],
+            rnd: RoundingMode,
+        ) -> Option<(Self, Option<ExceptionFlags>)>;
+    }
+}
+
+/// Runs `op` on `operands` through softfloat and the host FPU, and returns how they differ.
+///
+/// Returns `None` if they agree, or if the host cannot run `op` in `rnd`.
+///
+/// # Panics
+///
+/// Panics if the number of operands is not the arity of `op`.
+pub fn compare<T: HostFloat>(
+    op: HostOp,
+    operands: &[T],
+    rnd: RoundingMode,
+    known: KnownDifferences,
+) -> Option<Divergence<T>> {
+    assert_eq!(operands.len(), op.arity(), "wrong number of operands");
+    let (host, host_flags) = T::host(op, operands, rnd)?;
+    let (soft, soft_flags) = with_flags(|| {
+        let a = operands[0];
+        match op {
+            HostOp::Add => a.add(operands[1], rnd),
+            HostOp::Sub => a.sub(operands[1], rnd),
+            HostOp::Mul => a.mul(operands[1], rnd),
+            HostOp::Div => a.div(operands[1], rnd),
+            HostOp::Sqrt => a.sqrt(rnd),
+            HostOp::MulAdd => a.fused_mul_add(operands[1], operands[2], rnd),
+        }
+    });
+
+    let same_result =
+        soft.to_bits() == host.to_bits() || (known.nan_payloads && soft.is_nan() && host.is_nan());
+    let same_flags = match host_flags {
+        Some(host_flags) => {
+            let mut mask = !0;
+            if known.underflow {
+                mask &= !ExceptionFlags::FLAG_UNDERFLOW;
+            }
+            soft_flags.to_bits() & mask == host_flags.to_bits() & mask
+        }
+        None => true,
+    };
+    if same_result && same_flags {
+        None
+    } else {
+        Some(Divergence {
+            soft,
+            soft_flags,
+            host,
+            host_flags,
+        })
+    }
+}
+
+/// native operations, rounding to nearest without reporting flags
+#[cfg(not(target_arch = "x86_64"))]
+macro_rules! native {
+    ($native:ty, $op:expr, $x:expr) => {{
+        let x = $x;
+        match $op {
+            HostOp::Add => x[0] + x[1],
+            HostOp::Sub => x[0] - x[1],
+            HostOp::Mul => x[0] * x[1],
+            HostOp::Div => x[0] / x[1],
+            HostOp::Sqrt => <$native>::sqrt(x[0]),
+            HostOp::MulAdd => <$native>::mul_add(x[0], x[1], x[2]),
+        }
+    }};
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse {
+    use super::HostOp;
+    use crate::{ExceptionFlags, RoundingMode};
+    use std::arch::asm;
+
+    /// MXCSR with all exceptions masked, denormals enabled and rounding per `rnd`
+    fn control(rnd: RoundingMode) -> Option<u32> {
+        Some(0x1f80 | (rnd.to_x86_rc()? as u32) << 13)
+    }
+
+    fn flags(status: u32) -> ExceptionFlags {
+        let mut ret = 0;
+        for (bit, flag) in [
+            (0, ExceptionFlags::FLAG_INVALID),
+            (2, ExceptionFlags::FLAG_INFINITE),
+            (3, ExceptionFlags::FLAG_OVERFLOW),
+            (4, ExceptionFlags::FLAG_UNDERFLOW),
+            (5, ExceptionFlags::FLAG_INEXACT),
+        ] {
+            if status & 1 << bit != 0 {
+                ret |= flag;
+            }
+        }
+        ExceptionFlags::from_bits(ret)
+    }
+
+    /// Runs `insn` on `a`, `b` and `c` with `control` in MXCSR, returning the result in
+    /// `a` and the status of MXCSR; the previous MXCSR is restored.
+    macro_rules! run {
+        ($insn:literal, $a:expr, $b:expr, $c:expr, $control:expr) => {{
+            let mut a = $a;
+            let control: u32 = $control;
+            let mut saved = 0u32;
+            let mut status = 0u32;
+            unsafe {
+                asm!(
+                    "stmxcsr dword ptr [{saved}]",
+                    "ldmxcsr dword ptr [{control}]",
+                    $insn,
+                    // `b` and `c` are only read by some of the instructions
+                    "/* {b} {c} */",
+                    "stmxcsr dword ptr [{status}]",
+                    "ldmxcsr dword ptr [{saved}]",
+                    saved = in(reg) &mut saved,
+                    control = in(reg) &control,
+                    status = in(reg) &mut status,
+                    a = inout(xmm_reg) a,
+                    b = in(xmm_reg) $b,
+                    c = in(xmm_reg) $c,
+                    options(nostack),
+                );
+            }
+            (a, flags(status))
+        }};
+    }
+
+    macro_rules! impl_host {
+        ($name:ident, $native:ty, $add:literal, $sub:literal, $mul:literal, $div:literal, $sqrt:literal, $fma:literal) => {
+            pub fn $name(
+                op: HostOp,
+                x: [$native; 3],
+                rnd: RoundingMode,
+            ) -> Option<($native, ExceptionFlags)> {
+                let control = control(rnd)?;
+                let [a, b, c] = x;
+                Some(match op {
+                    HostOp::Add => run!($add, a, b, c, control),
+                    HostOp::Sub => run!($sub, a, b, c, control),
+                    HostOp::Mul => run!($mul, a, b, c, control),
+                    HostOp::Div => run!($div, a, b, c, control),
+                    HostOp::Sqrt => run!($sqrt, a, b, c, control),
+                    HostOp::MulAdd => {
+                        if !is_x86_feature_detected!("fma") {
+                            return None;
+                        }
+                        run!($fma, a, b, c, control)
+                    }
+                })
+            }
+        };
+    }
+
+    impl_host!(
+        host_f32,
+        f32,
+        "addss {a}, {b}",
+        "subss {a}, {b}",
+        "mulss {a}, {b}",
+        "divss {a}, {b}",
+        "sqrtss {a}, {a}",
+        "vfmadd213ss {a}, {b}, {c}"
+    );
+
+    impl_host!(
+        host_f64,
+        f64,
+        "addsd {a}, {b}",
+        "subsd {a}, {b}",
+        "mulsd {a}, {b}",
+        "divsd {a}, {b}",
+        "sqrtsd {a}, {a}",
+        "vfmadd213sd {a}, {b}, {c}"
+    );
+}
+
+macro_rules! impl_sealed {
+    ($ty:ty, $native:ident, $host:ident) => {
+        impl sealed::Sealed for $ty {
+            fn host(
+                op: HostOp,
+                operands: &[Self],
+                rnd: RoundingMode,
+            ) -> Option<(Self, Option<ExceptionFlags>)> {
+                let mut x = [0.0; 3];
+                for (x, y) in x.iter_mut().zip(operands) {
+                    *x = $native::from_bits(y.to_bits());
+                }
+                #[cfg(target_arch = "x86_64")]
+                {
+                    let (ret, flags) = sse::$host(op, x, rnd)?;
+                    Some((Self::from_bits(ret.to_bits()), Some(flags)))
+                }
+                #[cfg(not(target_arch = "x86_64"))]
+                {
+                    if !matches!(rnd, RoundingMode::TiesToEven) {
+                        return None;
+                    }
+                    let ret = native!($native, op, x);
+                    Some((Self::from_bits(ret.to_bits()), None))
+                }
+            }
+        }
+    };
+}
+
+impl_sealed!(F32, f32, host_f32);
+impl_sealed!(F64, f64, host_f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift64
+    fn next(x: &mut u64) -> u64 {
+        *x ^= *x << 13;
+        *x ^= *x >> 7;
+        *x ^= *x << 17;
+        *x
+    }
+
+    #[test]
+    fn host_compare() {
+        let modes = [
+            RoundingMode::TiesToEven,
+            RoundingMode::TowardZero,
+            RoundingMode::TowardNegative,
+            RoundingMode::TowardPositive,
+            RoundingMode::TiesToAway,
+        ];
+        let ops = [
+            HostOp::Add,
+            HostOp::Sub,
+            HostOp::Mul,
+            HostOp::Div,
+            HostOp::Sqrt,
+            HostOp::MulAdd,
+        ];
+        let mut seed = 0x2545f4914f6cdd1d;
+        for i in 0..20000 {
+            let op = ops[i % ops.len()];
+            let rnd = modes[i / ops.len() % modes.len()];
+            let x: Vec<_> = (0..op.arity()).map(|_| next(&mut seed)).collect();
+
+            let a: Vec<_> = x.iter().map(|x| F64::from_bits(*x)).collect();
+            let ret = compare(op, &a, rnd, KnownDifferences::default());
+            assert!(ret.is_none(), "{:?} {:?} {:x?}: {:x?}", op, rnd, x, ret);
+
+            let a: Vec<_> = x.iter().map(|x| F32::from_bits(*x as u32)).collect();
+            let ret = compare(op, &a, rnd, KnownDifferences::default());
+            assert!(ret.is_none(), "{:?} {:?} {:x?}: {:x?}", op, rnd, x, ret);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn host_compare_arity() {
+        let a = F32::from_bits(0x3f800000);
+        compare(
+            HostOp::Add,
+            &[a],
+            RoundingMode::TiesToEven,
+            KnownDifferences::default(),
+        );
+    }
+}
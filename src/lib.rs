@@ -3,8 +3,10 @@
 //! ## Examples
 //!
 //! ```
-//! use softfloat_wrapper::{SoftFloat, F16, RoundingMode};
+//! use softfloat_wrapper::prelude::*;
+//! use softfloat_wrapper::{RoundingMode, F16};
 //!
+//! # #[cfg(feature = "native-float")]
 //! fn main() {
 //!     let a = 0x1234;
 //!     let b = 0x1479;
@@ -13,30 +15,201 @@
 //!     let b = F16::from_bits(b);
 //!     let d = a.add(b, RoundingMode::TiesToEven);
 //!
-//!     let a = f32::from_bits(a.to_f32(RoundingMode::TiesToEven).to_bits());
-//!     let b = f32::from_bits(b.to_f32(RoundingMode::TiesToEven).to_bits());
-//!     let d = f32::from_bits(d.to_f32(RoundingMode::TiesToEven).to_bits());
+//!     // with the `native-float` feature
+//!     let a = a.to_native_f32(RoundingMode::TiesToEven);
+//!     let b = b.to_native_f32(RoundingMode::TiesToEven);
+//!     let d = d.to_native_f32(RoundingMode::TiesToEven);
 //!
 //!     println!("{} + {} = {}", a, b, d);
 //! }
+//! # #[cfg(not(feature = "native-float"))]
+//! # fn main() {}
 //! ```
 
+#![cfg_attr(feature = "nightly-float", feature(f16, f128))]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+/// Runs the softfloat operation `$op` in `$body` in the default NaN mode of the thread,
+/// routing it to the backend of the thread if the `backend` feature is enabled, and
+/// reporting it to the trace hook if the `trace` feature is enabled.
+macro_rules! traced {
+    ($op:expr, $rnd:expr, [$($x:expr),*], $body:expr) => {
+        traced!($op, $rnd, false, [$($x),*], $body)
+    };
+    ($op:expr, $rnd:expr, $exact:expr, [$($x:expr),*], $body:expr) => {{
+        #[cfg(feature = "backend")]
+        let run = || crate::backend::routed($op, $rnd, $exact, &[$(crate::trace::TraceValue::trace_bits(&$x)),*], || $body);
+        #[cfg(not(feature = "backend"))]
+        let run = || $body;
+        let body = || crate::strict::isolated(|| crate::nan::DefaultNan::default_nan(run()));
+        #[cfg(feature = "trace")]
+        let ret = crate::trace::traced($op, $rnd, $exact, &[$(crate::trace::TraceValue::trace_bits(&$x)),*], body);
+        #[cfg(not(feature = "trace"))]
+        let ret = body();
+        ret
+    }};
+}
+
+#[cfg(feature = "rustc_apfloat")]
+mod apfloat;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "arm")]
+pub mod arm;
+#[cfg(feature = "backend")]
+pub mod backend;
+mod bank;
+pub mod batch;
+#[cfg(feature = "num-bigint")]
+mod bigint;
+#[cfg(feature = "bytemuck")]
+mod bytemuck;
+mod bytes;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod checked;
+mod chop;
+mod complex;
+mod convert;
+mod decimal;
+#[cfg(feature = "defmt")]
+mod defmt;
+mod dynamic;
+#[cfg(any(feature = "hfp", feature = "vax"))]
+mod exact;
+mod explain;
 #[cfg(feature = "f128")]
 mod f128;
 mod f16;
 mod f32;
 mod f64;
+#[cfg(feature = "fast-path")]
+pub mod fast;
+mod flags;
+mod format;
+#[cfg(feature = "half")]
+mod half;
+#[cfg(feature = "hfp")]
+pub mod hfp;
+pub mod host;
+pub mod lockstep;
+pub mod math;
+#[cfg(feature = "mips")]
+pub mod mips;
+pub mod nan;
+#[cfg(feature = "nightly-float")]
+mod nightly;
+mod num;
+mod ops;
+mod packed;
+mod parse;
+mod policy;
+#[cfg(feature = "ppc")]
+pub mod ppc;
+pub mod prelude;
+#[cfg(feature = "quickcheck")]
+mod quickcheck;
+#[cfg(feature = "rand")]
+mod rand;
+#[cfg(feature = "riscv")]
+pub mod riscv;
+mod rounded;
+mod rounding;
+#[cfg(feature = "rug")]
+mod rug;
+#[cfg(any(
+    feature = "pure-rust",
+    all(target_arch = "wasm32", target_os = "unknown")
+))]
+mod soft;
+mod sortable;
+#[cfg(feature = "sparc")]
+pub mod sparc;
+mod specialization;
+mod state;
+#[cfg(feature = "stats")]
+pub mod stats;
+pub mod strict;
+pub mod testfloat;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "trap")]
+pub mod trap;
+#[cfg(feature = "vax")]
+pub mod vax;
+mod view;
+#[cfg(feature = "x87")]
+pub mod x87;
+
+#[cfg(any(
+    feature = "pure-rust",
+    all(target_arch = "wasm32", target_os = "unknown")
+))]
+use crate::soft as sys;
+#[cfg(not(any(
+    feature = "pure-rust",
+    all(target_arch = "wasm32", target_os = "unknown")
+)))]
+use softfloat_sys as sys;
+
+#[cfg(not(any(
+    feature = "softfloat-sys",
+    feature = "pure-rust",
+    all(target_arch = "wasm32", target_os = "unknown")
+)))]
+compile_error!("either the `softfloat-sys` or the `pure-rust` feature must be enabled");
+
+#[cfg(all(feature = "specialize-8086-sse", feature = "specialize-riscv"))]
+compile_error!("the `specialize-8086-sse` and `specialize-riscv` features are exclusive");
+
+#[cfg(all(
+    any(feature = "specialize-8086-sse", feature = "specialize-riscv"),
+    not(any(
+        feature = "pure-rust",
+        all(target_arch = "wasm32", target_os = "unknown")
+    ))
+))]
+compile_error!("the `specialize-` features need the `pure-rust` backend");
+
+pub use crate::bank::FlagBank;
+pub use crate::checked::{FloatError, OperationError};
+pub use crate::chop::Chop;
+pub use crate::complex::Complex;
+pub use crate::convert::InexactError;
+pub use crate::dynamic::DynSoftFloat;
+pub use crate::explain::{Explanation, RoundingDirection, Unrounded};
 #[cfg(feature = "f128")]
 pub use crate::f128::F128;
 pub use crate::f16::F16;
 pub use crate::f32::F32;
 pub use crate::f64::F64;
+pub use crate::flags::ParseMnemonicError;
+pub use crate::ops::SoftFloatIterExt;
+pub use crate::packed::{F16x2, F16x4, F16x8, F32x2, F32x4, F32x8, F64x2, F64x4, Packed};
+pub use crate::parse::ParseError;
+pub use crate::policy::ConversionPolicy;
+#[cfg(feature = "rand")]
+pub use crate::rand::{Target, Targeted, UniformSoftFloat};
+pub use crate::rounded::Rounded;
+pub use crate::specialization::{specialization, Specialization};
+pub use crate::state::SoftFloatState;
+pub use crate::view::{
+    Bits, BitsMut, F16Bits, F16BitsMut, F32Bits, F32BitsMut, F64Bits, F64BitsMut,
+};
+#[cfg(feature = "f128")]
+pub use crate::view::{F128Bits, F128BitsMut};
 
+use crate::checked::{checked, exactness, operand};
+use crate::explain::{explain, Operation};
+use crate::policy::to_int;
+#[cfg(feature = "num-bigint")]
+use num_bigint::{BigInt, BigUint, Sign};
 use num_traits::{
     identities::{One, Zero},
-    PrimInt,
+    NumCast, PrimInt,
 };
 use std::borrow::Borrow;
+use std::cell::Cell;
 use std::cmp::Ordering;
 use std::fmt::{LowerHex, UpperHex};
 
@@ -55,20 +228,85 @@ pub enum RoundingMode {
     TiesToAway,
 }
 
+thread_local! {
+    /// rounding mode last written to softfloat by this thread, `u8::MAX` before the first
+    /// write
+    static LAST_ROUNDING_MODE: Cell<u8> = const { Cell::new(u8::MAX) };
+    /// rounding mode of operations which do not take one
+    static DEFAULT_ROUNDING_MODE: Cell<RoundingMode> =
+        const { Cell::new(RoundingMode::TiesToEven) };
+}
+
 impl RoundingMode {
+    /// Makes softfloat round per `self`, skipping the write if this thread already did.
+    ///
+    /// Writes of the softfloat rounding mode which bypass this function are not seen.
     fn set(&self) {
-        unsafe {
-            softfloat_sys::softfloat_roundingMode_write_helper(self.to_softfloat());
-        }
+        let x = self.to_softfloat();
+        LAST_ROUNDING_MODE.with(|last| {
+            if last.get() != x {
+                unsafe {
+                    sys::softfloat_roundingMode_write_helper(x);
+                }
+                last.set(x);
+            }
+        });
+    }
+
+    /// Makes the next [`set`](Self::set) write the softfloat rounding mode, after a write
+    /// which bypassed it.
+    pub(crate) fn forget_last() {
+        LAST_ROUNDING_MODE.with(|last| last.set(u8::MAX));
+    }
+
+    /// Returns the rounding mode softfloat uses in this thread, as set by the last operation
+    /// which rounds.
+    ///
+    /// It is `TiesToEven` before the first one, or if the mode was set to one this crate does
+    /// not know through the backend directly.
+    pub fn current() -> RoundingMode {
+        let x = strict::isolated(|| unsafe { sys::softfloat_roundingMode_read_helper() });
+        [
+            RoundingMode::TowardZero,
+            RoundingMode::TowardNegative,
+            RoundingMode::TowardPositive,
+            RoundingMode::TiesToAway,
+        ]
+        .into_iter()
+        .find(|rnd| rnd.to_softfloat() == x)
+        .unwrap_or(RoundingMode::TiesToEven)
+    }
+
+    /// Returns the rounding mode of this thread for operations which do not take one, such
+    /// as the operators; `TiesToEven` unless set with
+    /// [`set_thread_default`](Self::set_thread_default).
+    pub fn thread_default() -> RoundingMode {
+        DEFAULT_ROUNDING_MODE.with(Cell::get)
+    }
+
+    /// Makes operations of this thread which do not take a rounding mode round per `rnd`.
+    ///
+    /// ```
+    /// use softfloat_wrapper::{RoundingMode, F32};
+    ///
+    /// let a = F32::from_bits(0x3f800000);
+    /// let b = F32::from_bits(0x40400000);
+    /// RoundingMode::set_thread_default(RoundingMode::TowardZero);
+    /// assert_eq!((a / b).to_bits(), 0x3eaaaaaa);
+    /// RoundingMode::set_thread_default(RoundingMode::TiesToEven);
+    /// assert_eq!((a / b).to_bits(), 0x3eaaaaab);
+    /// ```
+    pub fn set_thread_default(rnd: RoundingMode) {
+        DEFAULT_ROUNDING_MODE.with(|x| x.set(rnd));
     }
 
     fn to_softfloat(&self) -> u8 {
         match self {
-            RoundingMode::TiesToEven => softfloat_sys::softfloat_round_near_even,
-            RoundingMode::TowardZero => softfloat_sys::softfloat_round_minMag,
-            RoundingMode::TowardNegative => softfloat_sys::softfloat_round_min,
-            RoundingMode::TowardPositive => softfloat_sys::softfloat_round_max,
-            RoundingMode::TiesToAway => softfloat_sys::softfloat_round_near_maxMag,
+            RoundingMode::TiesToEven => sys::softfloat_round_near_even,
+            RoundingMode::TowardZero => sys::softfloat_round_minMag,
+            RoundingMode::TowardNegative => sys::softfloat_round_min,
+            RoundingMode::TowardPositive => sys::softfloat_round_max,
+            RoundingMode::TiesToAway => sys::softfloat_round_near_maxMag,
         }
     }
 }
@@ -78,7 +316,8 @@ impl RoundingMode {
 /// ## Examples
 ///
 /// ```
-/// use softfloat_wrapper::{ExceptionFlags, SoftFloat, RoundingMode, F16};
+/// use softfloat_wrapper::prelude::*;
+/// use softfloat_wrapper::{ExceptionFlags, RoundingMode, F16};
 ///
 /// let a = 0x0;
 /// let b = 0x0;
@@ -94,11 +333,16 @@ impl RoundingMode {
 pub struct ExceptionFlags(u8);
 
 impl ExceptionFlags {
-    const FLAG_INEXACT: u8 = softfloat_sys::softfloat_flag_inexact;
-    const FLAG_INFINITE: u8 = softfloat_sys::softfloat_flag_infinite;
-    const FLAG_INVALID: u8 = softfloat_sys::softfloat_flag_invalid;
-    const FLAG_OVERFLOW: u8 = softfloat_sys::softfloat_flag_overflow;
-    const FLAG_UNDERFLOW: u8 = softfloat_sys::softfloat_flag_underflow;
+    const FLAG_INEXACT: u8 = sys::softfloat_flag_inexact;
+    const FLAG_INFINITE: u8 = sys::softfloat_flag_infinite;
+    const FLAG_INVALID: u8 = sys::softfloat_flag_invalid;
+    const FLAG_OVERFLOW: u8 = sys::softfloat_flag_overflow;
+    const FLAG_UNDERFLOW: u8 = sys::softfloat_flag_underflow;
+    const FLAG_ALL: u8 = Self::FLAG_INEXACT
+        | Self::FLAG_INFINITE
+        | Self::FLAG_INVALID
+        | Self::FLAG_OVERFLOW
+        | Self::FLAG_UNDERFLOW;
 
     pub fn from_bits(x: u8) -> Self {
         Self(x)
@@ -133,59 +377,116 @@ impl ExceptionFlags {
         self.0 & Self::FLAG_UNDERFLOW != 0
     }
 
+    /// Replaces the flags raised so far by this thread with `self`.
+    ///
+    /// `ExceptionFlags::default().set()` clears them.
     pub fn set(&self) {
-        unsafe {
-            softfloat_sys::softfloat_exceptionFlags_write_helper(self.to_bits());
-        }
+        strict::isolated(|| unsafe {
+            sys::softfloat_exceptionFlags_write_helper(self.to_bits());
+        });
     }
 
+    /// Overwrites `self` with the flags raised so far by this thread.
+    ///
+    /// Flags in `self` which are not raised are lost; use [`accumulate`](Self::accumulate)
+    /// to keep them.
     pub fn get(&mut self) {
-        let x = unsafe { softfloat_sys::softfloat_exceptionFlags_read_helper() };
+        let x = strict::isolated(|| unsafe { sys::softfloat_exceptionFlags_read_helper() });
         self.0 = x;
     }
+
+    /// Adds the flags raised so far by this thread to `self`, and clears them.
+    ///
+    /// Flags are sticky: once raised they stay in `self` until cleared explicitly, like in
+    /// the flag register of an ISA. Calling this after each emulated instruction keeps the
+    /// flags of all of them, whatever the previous instruction left behind.
+    ///
+    /// ```
+    /// use softfloat_wrapper::prelude::*;
+    /// use softfloat_wrapper::{ExceptionFlags, RoundingMode, F32};
+    ///
+    /// let mut fflags = ExceptionFlags::default();
+    /// ExceptionFlags::default().set();
+    /// let a = F32::from_bits(0x3f800000);
+    /// a.div(F32::from_bits(0), RoundingMode::TiesToEven);
+    /// fflags.accumulate();
+    /// a.div(F32::from_bits(0x40400000), RoundingMode::TiesToEven);
+    /// fflags.accumulate();
+    /// assert!(fflags.is_infinite() && fflags.is_inexact());
+    /// ```
+    pub fn accumulate(&mut self) {
+        let x = strict::isolated(|| unsafe {
+            let x = sys::softfloat_exceptionFlags_read_helper();
+            sys::softfloat_exceptionFlags_write_helper(0);
+            x
+        });
+        self.0 |= x;
+    }
+
+    /// Raises the flags of `self` in addition to the ones already raised, as an operation
+    /// would.
+    ///
+    /// ```
+    /// use softfloat_wrapper::ExceptionFlags;
+    ///
+    /// ExceptionFlags::default().set();
+    /// ExceptionFlags::from_bits(0x01).raise();
+    /// ExceptionFlags::from_bits(0x10).raise();
+    /// let mut flags = ExceptionFlags::default();
+    /// flags.get();
+    /// assert_eq!(flags.to_bits(), 0x11);
+    /// ```
+    pub fn raise(&self) {
+        strict::isolated(|| unsafe {
+            sys::softfloat_raiseFlags(self.to_bits());
+        });
+    }
 }
 
-/// arbitrary floting-point type
-///
-/// ## Examples
-///
-/// `Float` can be used for generic functions.
-///
-/// ```
-/// use softfloat_wrapper::{SoftFloat, RoundingMode, F16, F32};
-///
-/// fn rsqrt<T: SoftFloat>(x: T) -> T {
-///     let ret = x.sqrt(RoundingMode::TiesToEven);
-///     let one = T::from_u8(1, RoundingMode::TiesToEven);
-///     one.div(ret, RoundingMode::TiesToEven)
-/// }
+/// Storage and bit-level queries of a floating-point format
 ///
-/// let a = F16::from_bits(0x1234);
-/// let a = rsqrt(a);
-/// let a = F32::from_bits(0x12345678);
-/// let a = rsqrt(a);
-/// ```
-pub trait SoftFloat {
+/// Formats which are only stored, classified and built from their fields, without
+/// arithmetic, implement it alone.
+pub trait FloatBits {
     /// Actual storage type for concrete softfloat implementation
     type Payload: PrimInt + UpperHex + LowerHex;
+
     /// Mask for mantissa value, starting from 0th bit
     const MANTISSA_MASK: Self::Payload;
+
     /// Mask for exponent value, starting from 0th bit
     const EXPONENT_MASK: Self::Payload;
+
     /// Number of mantissa bits, excluding sign
     const MANTISSA_BITS: usize;
+
     /// Number of exponent bits
     const EXPONENT_BITS: usize;
+
     /// Sign bit offset
     const SIGN_OFFSET: usize;
+
     /// Exponent bits offset
     const EXPONENT_OFFSET: usize;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f32(value: f32) -> Self;
+    /// Number of bits of the format
+    const BITS: u32 = Self::SIGN_OFFSET as u32 + 1;
 
-    #[cfg(feature = "native-float")]
-    fn from_native_f64(value: f64) -> Self;
+    /// Bias of the exponent field
+    const EXPONENT_BIAS: i32 = (1 << (Self::EXPONENT_BITS - 1)) - 1;
+
+    /// One greater than the largest possible power of 2 exponent, like `f32::MAX_EXP`
+    const MAX_EXP: i32 = Self::EXPONENT_BIAS + 1;
+
+    /// One greater than the smallest possible normal power of 2 exponent, like
+    /// `f32::MIN_EXP`
+    const MIN_EXP: i32 = 2 - Self::EXPONENT_BIAS;
+
+    /// Number of significant digits in base 2, including the implicit bit
+    const MANTISSA_DIGITS: u32 = Self::MANTISSA_BITS as u32 + 1;
+
+    /// Approximate number of significant digits in base 10, like `f32::DIGITS`
+    const DIGITS: u32 = (Self::MANTISSA_DIGITS - 1) * 30103 / 100000;
 
     fn set_payload(&mut self, x: Self::Payload);
 
@@ -196,108 +497,8 @@ pub trait SoftFloat {
     #[deprecated(since = "0.3.0", note = "Please use to_bits instead")]
     fn bits(&self) -> Self::Payload;
 
-    fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
-
-    fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
-
-    fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
-
-    fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self;
-
-    fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
-
-    fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
-
-    fn sqrt(&self, rnd: RoundingMode) -> Self;
-
-    fn eq<T: Borrow<Self>>(&self, x: T) -> bool;
-
-    fn lt<T: Borrow<Self>>(&self, x: T) -> bool;
-
-    fn le<T: Borrow<Self>>(&self, x: T) -> bool;
-
-    fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool;
-
-    fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool;
-
-    fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool;
-
     fn is_signaling_nan(&self) -> bool;
 
-    fn from_u32(x: u32, rnd: RoundingMode) -> Self;
-
-    fn from_u64(x: u64, rnd: RoundingMode) -> Self;
-
-    fn from_i32(x: i32, rnd: RoundingMode) -> Self;
-
-    fn from_i64(x: i64, rnd: RoundingMode) -> Self;
-
-    fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32;
-
-    fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64;
-
-    fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32;
-
-    fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64;
-
-    fn to_f16(&self, rnd: RoundingMode) -> F16;
-
-    fn to_f32(&self, rnd: RoundingMode) -> F32;
-
-    fn to_f64(&self, rnd: RoundingMode) -> F64;
-
-    #[cfg(feature = "f128")]
-    fn to_f128(&self, rnd: RoundingMode) -> F128;
-
-    fn round_to_integral(&self, rnd: RoundingMode) -> Self;
-
-    #[inline]
-    fn compare<T: Borrow<Self>>(&self, x: T) -> Option<Ordering> {
-        let eq = self.eq(x.borrow());
-        let lt = self.lt(x.borrow());
-        if self.is_nan() || x.borrow().is_nan() {
-            None
-        } else if eq {
-            Some(Ordering::Equal)
-        } else if lt {
-            Some(Ordering::Less)
-        } else {
-            Some(Ordering::Greater)
-        }
-    }
-
-    #[inline]
-    fn from_u8(x: u8, rnd: RoundingMode) -> Self
-    where
-        Self: Sized,
-    {
-        Self::from_u32(x as u32, rnd)
-    }
-
-    #[inline]
-    fn from_u16(x: u16, rnd: RoundingMode) -> Self
-    where
-        Self: Sized,
-    {
-        Self::from_u32(x as u32, rnd)
-    }
-
-    #[inline]
-    fn from_i8(x: i8, rnd: RoundingMode) -> Self
-    where
-        Self: Sized,
-    {
-        Self::from_i32(x as i32, rnd)
-    }
-
-    #[inline]
-    fn from_i16(x: i16, rnd: RoundingMode) -> Self
-    where
-        Self: Sized,
-    {
-        Self::from_i32(x as i32, rnd)
-    }
-
     #[inline]
     fn neg(&self) -> Self
     where
@@ -353,17 +554,13 @@ pub trait SoftFloat {
 
         if exp == zero && mant == zero {
             FpCategory::Zero
-        }
-        else if exp == zero && mant != zero {
+        } else if exp == zero && mant != zero {
             FpCategory::Subnormal
-        }
-        else if exp == Self::EXPONENT_MASK && mant == zero {
+        } else if exp == Self::EXPONENT_MASK && mant == zero {
             FpCategory::Infinite
-        }
-        else if exp == Self::EXPONENT_MASK && mant != zero {
+        } else if exp == Self::EXPONENT_MASK && mant != zero {
             FpCategory::Nan
-        }
-        else {
+        } else {
             FpCategory::Normal
         }
     }
@@ -400,6 +597,27 @@ pub trait SoftFloat {
         cat != FpCategory::Infinite && cat != FpCategory::Nan
     }
 
+    /// Returns whether `self` and `x` have the same bits, so `+0` and `-0` differ and a NaN
+    /// equals itself.
+    #[inline]
+    fn is_bitwise_eq<T: Borrow<Self>>(&self, x: T) -> bool {
+        self.to_bits() == x.borrow().to_bits()
+    }
+
+    /// Returns whether `self` and `x` have the same bits, or are both quiet NaNs or both
+    /// signaling NaNs, whatever their signs and payloads.
+    ///
+    /// Unlike [`eq`](FloatCompare::eq), this never raises exception flags.
+    #[inline]
+    fn eq_ignoring_nan_payload<T: Borrow<Self>>(&self, x: T) -> bool {
+        let x = x.borrow();
+        if self.is_nan() && x.is_nan() {
+            self.is_signaling_nan() == x.is_signaling_nan()
+        } else {
+            self.is_bitwise_eq(x)
+        }
+    }
+
     #[inline]
     fn set_sign(&mut self, x: Self::Payload) {
         self.set_payload(
@@ -452,92 +670,1443 @@ pub trait SoftFloat {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Arithmetic operations of IEEE 754, rounding per a [`RoundingMode`]
+pub trait FloatArith: FloatBits {
+    fn add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
 
-    #[test]
-    fn flag_inexact() {
-        let a = 0x1234;
-        let b = 0x7654;
-        let a = F16::from_bits(a);
-        let b = F16::from_bits(b);
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let _d = a.add(b, RoundingMode::TiesToEven);
-        flag.get();
-        assert!(flag.is_inexact());
-        assert!(!flag.is_infinite());
-        assert!(!flag.is_invalid());
-        assert!(!flag.is_overflow());
-        assert!(!flag.is_underflow());
-    }
+    fn sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
 
-    #[test]
-    fn flag_infinite() {
-        let a = 0x1234;
-        let b = 0x0;
-        let a = F16::from_bits(a);
-        let b = F16::from_bits(b);
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let _d = a.div(b, RoundingMode::TiesToEven);
-        flag.get();
-        assert!(!flag.is_inexact());
-        assert!(flag.is_infinite());
-        assert!(!flag.is_invalid());
-        assert!(!flag.is_overflow());
-        assert!(!flag.is_underflow());
-    }
+    fn mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
 
-    #[test]
-    fn flag_invalid() {
-        let a = 0x0;
-        let b = 0x0;
-        let a = F16::from_bits(a);
-        let b = F16::from_bits(b);
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let _d = a.div(b, RoundingMode::TiesToEven);
-        flag.get();
-        assert!(!flag.is_inexact());
-        assert!(!flag.is_infinite());
-        assert!(flag.is_invalid());
-        assert!(!flag.is_overflow());
-        assert!(!flag.is_underflow());
-    }
+    fn fused_mul_add<T: Borrow<Self>>(&self, x: T, y: T, rnd: RoundingMode) -> Self;
 
-    #[test]
-    fn flag_overflow() {
-        let a = 0x7bff;
-        let b = 0x7bff;
-        let a = F16::from_bits(a);
-        let b = F16::from_bits(b);
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let _d = a.add(b, RoundingMode::TiesToEven);
-        flag.get();
-        assert!(flag.is_inexact());
-        assert!(!flag.is_infinite());
-        assert!(!flag.is_invalid());
-        assert!(flag.is_overflow());
-        assert!(!flag.is_underflow());
-    }
+    fn div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
 
-    #[test]
-    fn flag_underflow() {
-        let a = 0x0001;
-        let b = 0x0001;
-        let a = F16::from_bits(a);
-        let b = F16::from_bits(b);
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let _d = a.mul(b, RoundingMode::TiesToEven);
-        flag.get();
-        assert!(flag.is_inexact());
-        assert!(!flag.is_infinite());
-        assert!(!flag.is_invalid());
-        assert!(!flag.is_overflow());
-        assert!(flag.is_underflow());
+    fn rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Self;
+
+    fn sqrt(&self, rnd: RoundingMode) -> Self;
+
+    fn round_to_integral(&self, rnd: RoundingMode) -> Self;
+}
+
+/// Comparisons of IEEE 754
+pub trait FloatCompare: FloatBits {
+    fn eq<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    fn lt<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    fn le<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    fn lt_quiet<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    fn le_quiet<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    fn eq_signaling<T: Borrow<Self>>(&self, x: T) -> bool;
+
+    #[inline]
+    fn compare<T: Borrow<Self>>(&self, x: T) -> Option<Ordering> {
+        let eq = self.eq(x.borrow());
+        let lt = self.lt(x.borrow());
+        if self.is_nan() || x.borrow().is_nan() {
+            None
+        } else if eq {
+            Some(Ordering::Equal)
+        } else if lt {
+            Some(Ordering::Less)
+        } else {
+            Some(Ordering::Greater)
+        }
+    }
+}
+
+/// Conversions from and to integers and the formats of this crate
+pub trait FloatConvert: FloatBits {
+    #[cfg(feature = "native-float")]
+    fn from_native_f32(value: f32) -> Self;
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64(value: f64) -> Self;
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f32_rounded(value: f32, rnd: RoundingMode) -> Self;
+
+    #[cfg(feature = "native-float")]
+    fn from_native_f64_rounded(value: f64, rnd: RoundingMode) -> Self;
+
+    fn from_u32(x: u32, rnd: RoundingMode) -> Self;
+
+    fn from_u64(x: u64, rnd: RoundingMode) -> Self;
+
+    fn from_i32(x: i32, rnd: RoundingMode) -> Self;
+
+    fn from_i64(x: i64, rnd: RoundingMode) -> Self;
+
+    fn to_u32(&self, rnd: RoundingMode, exact: bool) -> u32;
+
+    fn to_u64(&self, rnd: RoundingMode, exact: bool) -> u64;
+
+    fn to_i32(&self, rnd: RoundingMode, exact: bool) -> i32;
+
+    fn to_i64(&self, rnd: RoundingMode, exact: bool) -> i64;
+
+    fn to_f16(&self, rnd: RoundingMode) -> F16;
+
+    fn to_f32(&self, rnd: RoundingMode) -> F32;
+
+    fn to_f64(&self, rnd: RoundingMode) -> F64;
+
+    #[cfg(feature = "f128")]
+    fn to_f128(&self, rnd: RoundingMode) -> F128;
+
+    #[cfg(feature = "native-float")]
+    #[inline]
+    fn to_native_f32(&self, rnd: RoundingMode) -> f32 {
+        f32::from_bits(self.to_f32(rnd).to_bits())
+    }
+
+    #[cfg(feature = "native-float")]
+    #[inline]
+    fn to_native_f64(&self, rnd: RoundingMode) -> f64 {
+        f64::from_bits(self.to_f64(rnd).to_bits())
+    }
+
+    #[inline]
+    fn from_u8(x: u8, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_u32(x as u32, rnd)
+    }
+
+    #[inline]
+    fn from_u16(x: u16, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_u32(x as u32, rnd)
+    }
+
+    #[inline]
+    fn from_i8(x: i8, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_i32(x as i32, rnd)
+    }
+
+    #[inline]
+    fn from_i16(x: i16, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_i32(x as i32, rnd)
+    }
+
+    #[inline]
+    fn to_u8(&self, rnd: RoundingMode, exact: bool) -> u8
+    where
+        Self: Sized,
+    {
+        to_narrow(self, |x| x.to_u32(rnd, exact))
+    }
+
+    #[inline]
+    fn to_u16(&self, rnd: RoundingMode, exact: bool) -> u16
+    where
+        Self: Sized,
+    {
+        to_narrow(self, |x| x.to_u32(rnd, exact))
+    }
+
+    #[inline]
+    fn to_i8(&self, rnd: RoundingMode, exact: bool) -> i8
+    where
+        Self: Sized,
+    {
+        to_narrow(self, |x| x.to_i32(rnd, exact))
+    }
+
+    #[inline]
+    fn to_i16(&self, rnd: RoundingMode, exact: bool) -> i16
+    where
+        Self: Sized,
+    {
+        to_narrow(self, |x| x.to_i32(rnd, exact))
+    }
+
+    /// Converts from `usize`, which is exact if it fits in the precision of `Self`.
+    #[inline]
+    fn from_usize(x: usize, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_u64(x as u64, rnd)
+    }
+
+    #[inline]
+    fn from_isize(x: isize, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_i64(x as i64, rnd)
+    }
+
+    /// Converts to `usize`, with the range of the target's pointer width.
+    #[inline]
+    fn to_usize(&self, rnd: RoundingMode, exact: bool) -> usize
+    where
+        Self: Sized,
+    {
+        #[cfg(target_pointer_width = "16")]
+        let ret = self.to_u16(rnd, exact);
+        #[cfg(target_pointer_width = "32")]
+        let ret = self.to_u32(rnd, exact);
+        #[cfg(target_pointer_width = "64")]
+        let ret = self.to_u64(rnd, exact);
+        ret as usize
+    }
+
+    #[inline]
+    fn to_isize(&self, rnd: RoundingMode, exact: bool) -> isize
+    where
+        Self: Sized,
+    {
+        #[cfg(target_pointer_width = "16")]
+        let ret = self.to_i16(rnd, exact);
+        #[cfg(target_pointer_width = "32")]
+        let ret = self.to_i32(rnd, exact);
+        #[cfg(target_pointer_width = "64")]
+        let ret = self.to_i64(rnd, exact);
+        ret as isize
+    }
+}
+
+/// arbitrary floting-point type
+///
+/// ## Examples
+///
+/// `Float` can be used for generic functions.
+///
+/// ```
+/// use softfloat_wrapper::{RoundingMode, SoftFloat, F16, F32};
+///
+/// fn rsqrt<T: SoftFloat>(x: T) -> T {
+///     let ret = x.sqrt(RoundingMode::TiesToEven);
+///     let one = T::from_u8(1, RoundingMode::TiesToEven);
+///     one.div(ret, RoundingMode::TiesToEven)
+/// }
+///
+/// let a = F16::from_bits(0x1234);
+/// let a = rsqrt(a);
+/// let a = F32::from_bits(0x12345678);
+/// let a = rsqrt(a);
+/// ```
+///
+/// It is implemented for every type implementing [`FloatArith`], [`FloatCompare`] and
+/// [`FloatConvert`]. Generic code which needs less can take those traits as bounds, so
+/// formats which cannot support everything still work with it.
+///
+/// Importing `SoftFloat` alone does not bring the methods of those traits into scope for
+/// the concrete types, such as `F16::add`; import [`prelude`]`::*` instead.
+pub trait SoftFloat: FloatArith + FloatCompare + FloatConvert {
+    /// Converts from `x`, rounding once per `rnd` however large it is.
+    #[cfg(feature = "num-bigint")]
+    #[inline]
+    fn from_biguint(x: &BigUint, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        bigint::from_biguint(false, x, rnd)
+    }
+
+    /// Converts from `x`, rounding once per `rnd` however large it is.
+    #[cfg(feature = "num-bigint")]
+    #[inline]
+    fn from_bigint(x: &BigInt, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        bigint::from_biguint(x.sign() == Sign::Minus, x.magnitude(), rnd)
+    }
+
+    /// Returns the value of `self` if it is an integer, and `None` otherwise.
+    #[cfg(feature = "num-bigint")]
+    #[inline]
+    fn to_bigint(&self) -> Option<BigInt>
+    where
+        Self: Sized,
+    {
+        bigint::to_bigint(self)
+    }
+
+    /// Returns `(m, e)` such that `self` is exactly `m * 2^e`, or `None` if `self` is not
+    /// finite.
+    ///
+    /// `m` is odd, or 0 with `e` 0 for zeros of either sign.
+    ///
+    /// ```
+    /// use num_bigint::BigInt;
+    /// use softfloat_wrapper::{SoftFloat, F32};
+    ///
+    /// let a = F32::from_bits(0xbf400000);
+    /// assert_eq!(a.as_ratio(), Some((BigInt::from(-3), -2)));
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[inline]
+    fn as_ratio(&self) -> Option<(BigInt, i32)>
+    where
+        Self: Sized,
+    {
+        bigint::as_ratio(self)
+    }
+
+    /// Returns `self` exactly as a `rug::Float` of the same precision.
+    #[cfg(feature = "rug")]
+    #[inline]
+    fn to_rug_float(&self) -> ::rug::Float
+    where
+        Self: Sized,
+    {
+        rug::to_rug_float(self)
+    }
+
+    /// Converts from `x`, rounding once per `rnd` whatever its precision.
+    #[cfg(feature = "rug")]
+    #[inline]
+    fn from_rug_float(x: &::rug::Float, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        rug::from_rug_float(x, rnd)
+    }
+
+    /// Converts to any other softfloat format, rounding per `rnd`.
+    ///
+    /// Formats with the layout of `F16`, `F32`, `F64` or `F128` convert exactly like
+    /// `to_f16`, `to_f32`, `to_f64` and `to_f128`. Other formats round the exact value
+    /// directly, and map NaNs to their default quiet NaN.
+    fn convert<T: SoftFloat>(&self, rnd: RoundingMode) -> T
+    where
+        Self: Sized,
+    {
+        let cast = |x: u128| T::from_bits(<T::Payload as NumCast>::from(x).unwrap());
+        match (T::EXPONENT_BITS, T::MANTISSA_BITS) {
+            (5, 10) => cast(self.to_f16(rnd).to_bits() as u128),
+            (8, 23) => cast(self.to_f32(rnd).to_bits() as u128),
+            (11, 52) => cast(self.to_f64(rnd).to_bits() as u128),
+            #[cfg(feature = "f128")]
+            (15, 112) => cast(self.to_f128(rnd).to_bits()),
+            _ => convert_generic(self, rnd),
+        }
+    }
+
+    /// Computes the sum like [`add`](Self::add), failing if an exception other than
+    /// inexact is raised.
+    #[inline]
+    fn try_add<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow())];
+        checked(&operands, || self.add(x, rnd))
+    }
+
+    /// Computes the difference like [`sub`](Self::sub), failing if an exception other than
+    /// inexact is raised.
+    #[inline]
+    fn try_sub<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow())];
+        checked(&operands, || self.sub(x, rnd))
+    }
+
+    /// Computes the product like [`mul`](Self::mul), failing if an exception other than
+    /// inexact is raised.
+    #[inline]
+    fn try_mul<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow())];
+        checked(&operands, || self.mul(x, rnd))
+    }
+
+    /// Computes the quotient like [`div`](Self::div), failing if an exception other than
+    /// inexact is raised.
+    #[inline]
+    fn try_div<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow())];
+        checked(&operands, || self.div(x, rnd))
+    }
+
+    /// Computes the remainder like [`rem`](Self::rem), failing if an exception other than
+    /// inexact is raised.
+    #[inline]
+    fn try_rem<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow())];
+        checked(&operands, || self.rem(x, rnd))
+    }
+
+    /// Computes `self * x + y` like [`fused_mul_add`](Self::fused_mul_add), failing if an
+    /// exception other than inexact is raised.
+    #[inline]
+    fn try_fused_mul_add<T: Borrow<Self>>(
+        &self,
+        x: T,
+        y: T,
+        rnd: RoundingMode,
+    ) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        let operands = [operand(self), operand(x.borrow()), operand(y.borrow())];
+        checked(&operands, || self.fused_mul_add(x, y, rnd))
+    }
+
+    /// Computes the square root like [`sqrt`](Self::sqrt), failing if an exception other
+    /// than inexact is raised.
+    #[inline]
+    fn try_sqrt(&self, rnd: RoundingMode) -> Result<Self, OperationError>
+    where
+        Self: Sized,
+    {
+        checked(&[operand(self)], || self.sqrt(rnd))
+    }
+
+    /// Computes the sum like [`add`](Self::add), and returns whether it is exact, leaving the
+    /// exception flags as they were.
+    #[inline]
+    fn add_exactness<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.add(x, rnd))
+    }
+
+    /// Computes the difference like [`sub`](Self::sub), and returns whether it is exact, leaving the
+    /// exception flags as they were.
+    #[inline]
+    fn sub_exactness<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.sub(x, rnd))
+    }
+
+    /// Computes the product like [`mul`](Self::mul), and returns whether it is exact, leaving the
+    /// exception flags as they were.
+    #[inline]
+    fn mul_exactness<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.mul(x, rnd))
+    }
+
+    /// Computes the quotient like [`div`](Self::div), and returns whether it is exact, leaving the
+    /// exception flags as they were.
+    #[inline]
+    fn div_exactness<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.div(x, rnd))
+    }
+
+    /// Computes `self * x + y` like [`fused_mul_add`](Self::fused_mul_add), and returns
+    /// whether it is exact, leaving the exception flags as they were.
+    ///
+    /// ```
+    /// use softfloat_wrapper::{FloatBits, RoundingMode, SoftFloat, F64};
+    ///
+    /// // the error of a product is exact, the residual of Dekker's algorithm
+    /// let rnd = RoundingMode::TiesToEven;
+    /// let a = F64::from_bits(0x3fb999999999999a); // 0.1
+    /// let (p, exact) = a.mul_exactness(a, rnd);
+    /// assert!(!exact);
+    /// let (e, exact) = a.fused_mul_add_exactness(a, p.neg(), rnd);
+    /// assert!(exact);
+    /// assert_eq!(p.add_exactness(e, rnd), (p, false));
+    /// ```
+    #[inline]
+    fn fused_mul_add_exactness<T: Borrow<Self>>(
+        &self,
+        x: T,
+        y: T,
+        rnd: RoundingMode,
+    ) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.fused_mul_add(x, y, rnd))
+    }
+
+    /// Computes the square root like [`sqrt`](Self::sqrt), and returns whether it is
+    /// exact, leaving the exception flags as they were.
+    #[inline]
+    fn sqrt_exactness(&self, rnd: RoundingMode) -> (Self, bool)
+    where
+        Self: Sized,
+    {
+        exactness(|| self.sqrt(rnd))
+    }
+
+    /// Computes the sum like [`add`](Self::add), and explains how it was rounded.
+    #[inline]
+    fn add_explained<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Explanation<Self>
+    where
+        Self: Sized,
+    {
+        explain(Operation::Add(self, x.borrow()), rnd)
+    }
+
+    /// Computes the difference like [`sub`](Self::sub), and explains how it was rounded.
+    #[inline]
+    fn sub_explained<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Explanation<Self>
+    where
+        Self: Sized,
+    {
+        explain(Operation::Sub(self, x.borrow()), rnd)
+    }
+
+    /// Computes the product like [`mul`](Self::mul), and explains how it was rounded.
+    #[inline]
+    fn mul_explained<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Explanation<Self>
+    where
+        Self: Sized,
+    {
+        explain(Operation::Mul(self, x.borrow()), rnd)
+    }
+
+    /// Computes the quotient like [`div`](Self::div), and explains how it was rounded.
+    #[inline]
+    fn div_explained<T: Borrow<Self>>(&self, x: T, rnd: RoundingMode) -> Explanation<Self>
+    where
+        Self: Sized,
+    {
+        explain(Operation::Div(self, x.borrow()), rnd)
+    }
+
+    /// Computes the square root like [`sqrt`](Self::sqrt), and explains how it was rounded.
+    #[inline]
+    fn sqrt_explained(&self, rnd: RoundingMode) -> Explanation<Self>
+    where
+        Self: Sized,
+    {
+        explain(Operation::Sqrt(self), rnd)
+    }
+
+    /// Converts to `u32` like [`to_u32`](Self::to_u32), failing if `self` is NaN or out of
+    /// range.
+    #[inline]
+    fn try_to_u32(&self, rnd: RoundingMode) -> Result<u32, OperationError>
+    where
+        Self: Sized,
+    {
+        checked(&[operand(self)], || self.to_u32(rnd, false))
+    }
+
+    /// Converts to `u64` like [`to_u64`](Self::to_u64), failing if `self` is NaN or out of
+    /// range.
+    #[inline]
+    fn try_to_u64(&self, rnd: RoundingMode) -> Result<u64, OperationError>
+    where
+        Self: Sized,
+    {
+        checked(&[operand(self)], || self.to_u64(rnd, false))
+    }
+
+    /// Converts to `i32` like [`to_i32`](Self::to_i32), failing if `self` is NaN or out of
+    /// range.
+    #[inline]
+    fn try_to_i32(&self, rnd: RoundingMode) -> Result<i32, OperationError>
+    where
+        Self: Sized,
+    {
+        checked(&[operand(self)], || self.to_i32(rnd, false))
+    }
+
+    /// Converts to `i64` like [`to_i64`](Self::to_i64), failing if `self` is NaN or out of
+    /// range.
+    #[inline]
+    fn try_to_i64(&self, rnd: RoundingMode) -> Result<i64, OperationError>
+    where
+        Self: Sized,
+    {
+        checked(&[operand(self)], || self.to_i64(rnd, false))
+    }
+
+    /// Converts to `u32`, or returns `None` if `self` is NaN or out of range.
+    ///
+    /// Unlike [`to_u32`](Self::to_u32), no exception flags are raised.
+    #[inline]
+    fn to_u32_checked(&self, rnd: RoundingMode) -> Option<u32>
+    where
+        Self: Sized,
+    {
+        self.try_to_u32(rnd).ok()
+    }
+
+    /// Converts to `u64`, or returns `None` if `self` is NaN or out of range.
+    ///
+    /// Unlike [`to_u64`](Self::to_u64), no exception flags are raised.
+    #[inline]
+    fn to_u64_checked(&self, rnd: RoundingMode) -> Option<u64>
+    where
+        Self: Sized,
+    {
+        self.try_to_u64(rnd).ok()
+    }
+
+    /// Converts to `i32`, or returns `None` if `self` is NaN or out of range.
+    ///
+    /// Unlike [`to_i32`](Self::to_i32), no exception flags are raised.
+    #[inline]
+    fn to_i32_checked(&self, rnd: RoundingMode) -> Option<i32>
+    where
+        Self: Sized,
+    {
+        self.try_to_i32(rnd).ok()
+    }
+
+    /// Converts to `i64`, or returns `None` if `self` is NaN or out of range.
+    ///
+    /// Unlike [`to_i64`](Self::to_i64), no exception flags are raised.
+    #[inline]
+    fn to_i64_checked(&self, rnd: RoundingMode) -> Option<i64>
+    where
+        Self: Sized,
+    {
+        self.try_to_i64(rnd).ok()
+    }
+
+    /// Converts to `u32` like [`to_u32`](Self::to_u32), returning the value `policy`
+    /// defines if `self` is NaN or out of range.
+    ///
+    /// Fails only with [`ConversionPolicy::Error`].
+    #[inline]
+    fn to_u32_with_policy(
+        &self,
+        rnd: RoundingMode,
+        exact: bool,
+        policy: ConversionPolicy,
+    ) -> Result<u32, FloatError>
+    where
+        Self: Sized,
+    {
+        to_int(self, rnd, policy, || self.to_u32(rnd, exact), |x| x as u32)
+    }
+
+    /// Converts to `u64` like [`to_u64`](Self::to_u64), returning the value `policy`
+    /// defines if `self` is NaN or out of range.
+    ///
+    /// Fails only with [`ConversionPolicy::Error`].
+    #[inline]
+    fn to_u64_with_policy(
+        &self,
+        rnd: RoundingMode,
+        exact: bool,
+        policy: ConversionPolicy,
+    ) -> Result<u64, FloatError>
+    where
+        Self: Sized,
+    {
+        to_int(self, rnd, policy, || self.to_u64(rnd, exact), |x| x as u64)
+    }
+
+    /// Converts to `i32` like [`to_i32`](Self::to_i32), returning the value `policy`
+    /// defines if `self` is NaN or out of range.
+    ///
+    /// Fails only with [`ConversionPolicy::Error`].
+    #[inline]
+    fn to_i32_with_policy(
+        &self,
+        rnd: RoundingMode,
+        exact: bool,
+        policy: ConversionPolicy,
+    ) -> Result<i32, FloatError>
+    where
+        Self: Sized,
+    {
+        to_int(self, rnd, policy, || self.to_i32(rnd, exact), |x| x as i32)
+    }
+
+    /// Converts to `i64` like [`to_i64`](Self::to_i64), returning the value `policy`
+    /// defines if `self` is NaN or out of range.
+    ///
+    /// Fails only with [`ConversionPolicy::Error`].
+    #[inline]
+    fn to_i64_with_policy(
+        &self,
+        rnd: RoundingMode,
+        exact: bool,
+        policy: ConversionPolicy,
+    ) -> Result<i64, FloatError>
+    where
+        Self: Sized,
+    {
+        to_int(self, rnd, policy, || self.to_i64(rnd, exact), |x| x as i64)
+    }
+
+    /// Converts to a fixed-point value with `frac_bits` fractional bits, rounding per `rnd`.
+    ///
+    /// Raises inexact if the value is rounded. NaNs convert to 0, and values out of range of
+    /// `i64` saturate; both raise invalid like integer conversions do.
+    #[inline]
+    fn to_fixed(&self, frac_bits: u32, rnd: RoundingMode) -> i64
+    where
+        Self: Sized,
+    {
+        to_fixed(self, frac_bits, rnd)
+    }
+
+    /// Converts the fixed-point value `bits` with `frac_bits` fractional bits, rounding once
+    /// per `rnd`.
+    #[inline]
+    fn from_fixed(bits: i64, frac_bits: u32, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        // scales beyond the range of `i32` underflow all the same
+        let exp2 = i32::try_from(frac_bits).map_or(i32::MIN, |x| -x);
+        Self::from_i64_scaled(bits, exp2, rnd)
+    }
+
+    /// Computes `x * 2^exp2`, rounding once per `rnd`.
+    ///
+    /// Overflow and underflow are raised as for any other operation.
+    #[inline]
+    fn from_i64_scaled(x: i64, exp2: i32, rnd: RoundingMode) -> Self
+    where
+        Self: Sized,
+    {
+        round_pack(x < 0, exp2, x.unsigned_abs() as u128, rnd)
+    }
+
+    /// Formats `self` in engineering notation with `digits` significant digits rounded per
+    /// `rnd`: 1 to 3 of them before the point, and an exponent which is a multiple of 3.
+    ///
+    /// Trailing zeros are kept, and zeros are added before the point if `digits` is too
+    /// small to fill it. Infinities and NaNs print as `inf`, `-inf` and `NaN`.
+    ///
+    /// ```
+    /// use softfloat_wrapper::{RoundingMode, SoftFloat, F32};
+    ///
+    /// let a = F32::from_bits(0x3a03126f); // 0.0005
+    /// assert_eq!(a.to_engineering_string(3, RoundingMode::TiesToEven), "500e-6");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digits` is 0.
+    fn to_engineering_string(&self, digits: usize, rnd: RoundingMode) -> String
+    where
+        Self: Sized,
+    {
+        decimal::engineering(self, digits, rnd)
+    }
+
+    /// Converts `self` to a decimal string with `digits` significant digits rounded per
+    /// `rnd`, in scientific notation with one digit before the point, as
+    /// convertToDecimalCharacter of IEEE 754 does.
+    ///
+    /// Raises inexact if the digits are rounded. Infinities and NaNs print as `inf`, `-inf`
+    /// and `NaN`.
+    ///
+    /// ```
+    /// use softfloat_wrapper::{ExceptionFlags, RoundingMode, SoftFloat, F32};
+    ///
+    /// let mut flag = ExceptionFlags::default();
+    /// flag.set();
+    /// let a = F32::from_bits(0x3dcccccd); // 0.1
+    /// assert_eq!(a.to_decimal_string(3, RoundingMode::TiesToEven), "1.00e-1");
+    /// flag.get();
+    /// assert!(flag.is_inexact());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digits` is 0.
+    fn to_decimal_string(&self, digits: usize, rnd: RoundingMode) -> String
+    where
+        Self: Sized,
+    {
+        decimal::scientific(self, digits, rnd)
+    }
+
+    /// Negates `self` like [`neg`](FloatBits::neg), but as an arithmetic operation: a signaling
+    /// NaN raises invalid, and is quieted if `quiet`.
+    ///
+    /// ```
+    /// use softfloat_wrapper::{ExceptionFlags, SoftFloat, F32};
+    ///
+    /// ExceptionFlags::default().set();
+    /// let a = F32::from_bits(0x7f800001).neg_arithmetic(true);
+    /// assert_eq!(a.to_bits(), 0xffc00001);
+    /// let mut flags = ExceptionFlags::default();
+    /// flags.get();
+    /// assert!(flags.is_invalid());
+    /// ```
+    #[inline]
+    fn neg_arithmetic(&self, quiet: bool) -> Self
+    where
+        Self: Sized,
+    {
+        signal_nan(self, quiet).neg()
+    }
+
+    /// Returns the absolute value like [`abs`](FloatBits::abs), but as an arithmetic operation: a
+    /// signaling NaN raises invalid, and is quieted if `quiet`.
+    #[inline]
+    fn abs_arithmetic(&self, quiet: bool) -> Self
+    where
+        Self: Sized,
+    {
+        signal_nan(self, quiet).abs()
+    }
+}
+
+impl<T: FloatArith + FloatCompare + FloatConvert> SoftFloat for T {}
+
+/// Returns `x`, raising invalid if it is a signaling NaN, which is quieted if `quiet`.
+fn signal_nan<T: SoftFloat>(x: &T, quiet: bool) -> T {
+    let mut ret = T::from_bits(x.to_bits());
+    if x.is_signaling_nan() {
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+        if quiet {
+            ret.set_mantissa(x.mantissa() | T::Payload::one() << (T::MANTISSA_BITS - 1));
+        }
+    }
+    ret
+}
+
+/// Runs `f` with cleared exception flags and returns the flags it raised.
+///
+/// The flags which were set before the call are restored afterwards.
+pub(crate) fn with_flags<R>(f: impl FnOnce() -> R) -> (R, ExceptionFlags) {
+    let mut saved = ExceptionFlags::default();
+    saved.get();
+    ExceptionFlags::default().set();
+    let ret = f();
+    let mut raised = ExceptionFlags::default();
+    raised.get();
+    saved.set();
+    (ret, raised)
+}
+
+/// Rounds `sig * 2^exp` to `T`, raising exception flags like softfloat does.
+///
+/// `sig` must either hold the value exactly, or hold at least two bits more than the
+/// precision of `T` with all discarded bits or'ed into its least significant bit.
+/// Tininess is detected before or after rounding like the backend of the thread does.
+pub(crate) fn round_pack<T: SoftFloat>(sign: bool, exp: i32, sig: u128, rnd: RoundingMode) -> T {
+    let sign_bit = (sign as u128) << T::SIGN_OFFSET;
+    let payload = |x: u128| T::from_bits(<T::Payload as NumCast>::from(x | sign_bit).unwrap());
+    if sig == 0 {
+        return payload(0);
+    }
+
+    let prec = T::MANTISSA_DIGITS as i32;
+    let bias = T::EXPONENT_BIAS;
+    let emin = 1 - bias;
+    let msb = 127 - sig.leading_zeros() as i32;
+    let e = exp.saturating_add(msb);
+    let keep = if e < emin {
+        prec.saturating_sub(emin.saturating_sub(e))
+    } else {
+        prec
+    };
+
+    let (q, inexact) = round_shift(sign, sig, (msb + 1).saturating_sub(keep), rnd);
+    let mut flags = 0;
+    if inexact {
+        flags |= ExceptionFlags::FLAG_INEXACT;
+        let carried = e == emin - 1 && round_shift(sign, sig, msb + 1 - prec, rnd).0 >> prec != 0;
+        if e < emin && (!carried || tininess_before_rounding()) {
+            flags |= ExceptionFlags::FLAG_UNDERFLOW;
+        }
+    }
+
+    let ret = if e > bias || (e == bias && q >> prec != 0) {
+        flags |= ExceptionFlags::FLAG_OVERFLOW | ExceptionFlags::FLAG_INEXACT;
+        let exp_mask = (1u128 << T::EXPONENT_BITS) - 1;
+        if overflows_to_infinity(sign, rnd) {
+            payload(exp_mask << T::EXPONENT_OFFSET)
+        } else {
+            payload(((exp_mask - 1) << T::EXPONENT_OFFSET) | ((1u128 << T::MANTISSA_BITS) - 1))
+        }
+    } else {
+        // the leading bit of `q` is added into the exponent field, which also
+        // takes care of carries out of the significand and into the normal range
+        let biased = (e.max(emin) + bias - 1) as u128;
+        payload((biased << T::EXPONENT_OFFSET) + q)
+    };
+
+    if flags != 0 {
+        ExceptionFlags::from_bits(flags).raise();
+    }
+    ret
+}
+
+/// Returns whether the backend of the thread detects tininess before rounding.
+fn tininess_before_rounding() -> bool {
+    let tininess = strict::isolated(|| unsafe { sys::softfloat_detectTininess_read_helper() });
+    tininess == sys::softfloat_tininess_beforeRounding
+}
+
+/// Returns whether a result of `sign` which overflows is rounded to an infinity rather
+/// than to the largest finite value.
+pub(crate) fn overflows_to_infinity(sign: bool, rnd: RoundingMode) -> bool {
+    match rnd {
+        RoundingMode::TiesToEven | RoundingMode::TiesToAway => true,
+        RoundingMode::TowardZero => false,
+        RoundingMode::TowardPositive => !sign,
+        RoundingMode::TowardNegative => sign,
+    }
+}
+
+/// Splits a finite `x` into its sign and the exact value `sig * 2^exp`.
+pub(crate) fn unpack<T: SoftFloat>(x: &T) -> (bool, i32, u128) {
+    let bias = T::EXPONENT_BIAS;
+    let biased = <i32 as NumCast>::from(x.exponent()).unwrap();
+    let mut sig = <u128 as NumCast>::from(x.mantissa()).unwrap();
+    if biased != 0 {
+        sig |= 1 << T::MANTISSA_BITS;
+    }
+    (
+        x.is_negative(),
+        biased.max(1) - bias - T::MANTISSA_BITS as i32,
+        sig,
+    )
+}
+
+/// Converts `x` to an integer type narrower than the one of `wide`, raising flags and
+/// saturating out-of-range values like a direct conversion would.
+fn to_narrow<T: FloatConvert, W: PrimInt, N: PrimInt>(x: &T, wide: impl Fn(&T) -> W) -> N {
+    let (ret, flags) = with_flags(|| wide(x));
+    if !flags.is_invalid() {
+        if let Some(ret) = N::from(ret) {
+            flags.raise();
+            return ret;
+        }
+    }
+    // in range of `W` but not of `N`, so saturate as `W` does with an infinity
+    let ret = if flags.is_invalid() {
+        ret
+    } else {
+        let mut inf = T::infinity();
+        inf.set_sign(x.sign());
+        with_flags(|| wide(&inf)).0
+    };
+    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+    N::from(ret).unwrap_or(if ret < W::zero() {
+        N::min_value()
+    } else {
+        N::max_value()
+    })
+}
+
+/// Rounds `x * 2^frac_bits` to `i64`, saturating out-of-range values.
+fn to_fixed<T: SoftFloat>(x: &T, frac_bits: u32, rnd: RoundingMode) -> i64 {
+    let invalid = |ret: i64| {
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+        ret
+    };
+    let saturated = if x.is_negative() { i64::MIN } else { i64::MAX };
+    if x.is_nan() {
+        return invalid(0);
+    }
+    if x.is_infinity() {
+        return invalid(saturated);
+    }
+
+    if x.is_zero() {
+        return 0;
+    }
+
+    let (sign, exp, sig) = unpack(x);
+    let exp = exp as i64 + frac_bits as i64;
+    if exp >= 0 && exp + 128 - sig.leading_zeros() as i64 > 64 {
+        return invalid(saturated);
+    }
+    // in range of `i32`, as the exponent of a finite value is
+    let (q, inexact) = round_shift(sign, sig, -exp as i32, rnd);
+    let ret = if sign { -(q as i128) } else { q as i128 };
+    match i64::try_from(ret) {
+        Ok(ret) => {
+            if inexact {
+                ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT).raise();
+            }
+            ret
+        }
+        Err(_) => invalid(saturated),
+    }
+}
+
+/// format-agnostic conversion, rounding the exact value of `x` once
+fn convert_generic<S: SoftFloat, T: SoftFloat>(x: &S, rnd: RoundingMode) -> T {
+    let mut ret = if x.is_nan() {
+        if x.is_signaling_nan() {
+            ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+        }
+        T::quiet_nan()
+    } else if x.is_infinity() {
+        T::infinity()
+    } else {
+        let (sign, exp, sig) = unpack(x);
+        return round_pack(sign, exp, sig, rnd);
+    };
+    ret.set_sign(if x.is_negative() {
+        T::Payload::one()
+    } else {
+        T::Payload::zero()
+    });
+    ret
+}
+
+/// Shifts `sig` right by `shift` bits, rounding the result per `rnd`.
+///
+/// Returns the rounded value and whether any non-zero bits were discarded. A zero `sig`
+/// stays zero whatever the shift.
+pub(crate) fn round_shift(sign: bool, sig: u128, shift: i32, rnd: RoundingMode) -> (u128, bool) {
+    if sig == 0 {
+        return (0, false);
+    }
+    if shift <= 0 {
+        return (sig << -shift, false);
+    }
+    let (q, rem) = if shift < 128 {
+        (sig >> shift, sig & ((1 << shift) - 1))
+    } else {
+        (0, sig)
+    };
+    let half = if shift <= 128 {
+        rem.cmp(&(1 << (shift - 1)))
+    } else {
+        Ordering::Less
+    };
+    let inexact = rem != 0;
+    let up = match rnd {
+        RoundingMode::TiesToEven => {
+            half == Ordering::Greater || (half == Ordering::Equal && q & 1 != 0)
+        }
+        RoundingMode::TiesToAway => half != Ordering::Less,
+        RoundingMode::TowardZero => false,
+        RoundingMode::TowardPositive => inexact && !sign,
+        RoundingMode::TowardNegative => inexact && sign,
+    };
+    (q + up as u128, inexact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_inexact() {
+        let a = 0x1234;
+        let b = 0x7654;
+        let a = F16::from_bits(a);
+        let b = F16::from_bits(b);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let _d = a.add(b, RoundingMode::TiesToEven);
+        flag.get();
+        assert!(flag.is_inexact());
+        assert!(!flag.is_infinite());
+        assert!(!flag.is_invalid());
+        assert!(!flag.is_overflow());
+        assert!(!flag.is_underflow());
+    }
+
+    #[test]
+    fn flag_infinite() {
+        let a = 0x1234;
+        let b = 0x0;
+        let a = F16::from_bits(a);
+        let b = F16::from_bits(b);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let _d = a.div(b, RoundingMode::TiesToEven);
+        flag.get();
+        assert!(!flag.is_inexact());
+        assert!(flag.is_infinite());
+        assert!(!flag.is_invalid());
+        assert!(!flag.is_overflow());
+        assert!(!flag.is_underflow());
+    }
+
+    #[test]
+    fn flag_invalid() {
+        let a = 0x0;
+        let b = 0x0;
+        let a = F16::from_bits(a);
+        let b = F16::from_bits(b);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let _d = a.div(b, RoundingMode::TiesToEven);
+        flag.get();
+        assert!(!flag.is_inexact());
+        assert!(!flag.is_infinite());
+        assert!(flag.is_invalid());
+        assert!(!flag.is_overflow());
+        assert!(!flag.is_underflow());
+    }
+
+    #[test]
+    fn flag_overflow() {
+        let a = 0x7bff;
+        let b = 0x7bff;
+        let a = F16::from_bits(a);
+        let b = F16::from_bits(b);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let _d = a.add(b, RoundingMode::TiesToEven);
+        flag.get();
+        assert!(flag.is_inexact());
+        assert!(!flag.is_infinite());
+        assert!(!flag.is_invalid());
+        assert!(flag.is_overflow());
+        assert!(!flag.is_underflow());
+    }
+
+    #[test]
+    fn flag_underflow() {
+        let a = 0x0001;
+        let b = 0x0001;
+        let a = F16::from_bits(a);
+        let b = F16::from_bits(b);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let _d = a.mul(b, RoundingMode::TiesToEven);
+        flag.get();
+        assert!(flag.is_inexact());
+        assert!(!flag.is_infinite());
+        assert!(!flag.is_invalid());
+        assert!(!flag.is_overflow());
+        assert!(flag.is_underflow());
+    }
+
+    #[test]
+    fn flag_raise() {
+        let mut flag = ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT);
+        flag.set();
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+        ExceptionFlags::default().raise();
+        flag.get();
+        assert!(flag.is_inexact());
+        assert!(flag.is_invalid());
+        assert!(!flag.is_overflow());
+    }
+
+    #[test]
+    fn flag_accumulate() {
+        let a = F16::from_bits(0x3c00);
+        let mut flag = ExceptionFlags::from_bits(ExceptionFlags::FLAG_UNDERFLOW);
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).set();
+        flag.accumulate();
+        let _d = a.div(F16::from_bits(0x4200), RoundingMode::TiesToEven);
+        flag.accumulate();
+        assert!(flag.is_underflow() && flag.is_invalid() && flag.is_inexact());
+        assert!(!flag.is_infinite());
+
+        // the flags accumulated are cleared
+        let _d = a.add(a, RoundingMode::TiesToEven);
+        let mut raised = ExceptionFlags::default();
+        raised.get();
+        assert_eq!(raised.to_bits(), 0);
+    }
+
+    #[test]
+    fn rounding_mode_cache() {
+        // 1 + 2^-24 is a tie in F32
+        let a = F32::from_bits(0x3f800000);
+        let b = F32::from_bits(0x33800000);
+        let check = move || {
+            for _ in 0..2 {
+                assert_eq!(a.add(b, RoundingMode::TiesToEven).to_bits(), 0x3f800000);
+                assert_eq!(a.add(b, RoundingMode::TiesToEven).to_bits(), 0x3f800000);
+                assert_eq!(a.add(b, RoundingMode::TowardPositive).to_bits(), 0x3f800001);
+                assert_eq!(a.add(b, RoundingMode::TiesToAway).to_bits(), 0x3f800001);
+                assert_eq!(a.add(b, RoundingMode::TiesToAway).to_bits(), 0x3f800001);
+            }
+        };
+        check();
+        std::thread::spawn(check).join().unwrap();
+    }
+
+    #[cfg(feature = "native-float")]
+    #[test]
+    fn to_native() {
+        let a = F16::from_bits(0x3555);
+        assert_eq!(a.to_native_f32(RoundingMode::TiesToEven), 0.33325195);
+        assert_eq!(a.to_native_f64(RoundingMode::TiesToEven), 0.333251953125);
+
+        let a = F64::from_bits(0x3fd5555555555555);
+        assert_eq!(a.to_native_f32(RoundingMode::TiesToEven), 0.33333334);
+        assert_eq!(a.to_native_f32(RoundingMode::TowardZero), 0.3333333);
+    }
+
+    #[test]
+    fn format_metadata() {
+        assert_eq!(F32::BITS, 32);
+        assert_eq!(F32::EXPONENT_BIAS, 127);
+        assert_eq!(F32::MAX_EXP, f32::MAX_EXP);
+        assert_eq!(F32::MIN_EXP, f32::MIN_EXP);
+        assert_eq!(F32::MANTISSA_DIGITS, f32::MANTISSA_DIGITS);
+        assert_eq!(F32::DIGITS, f32::DIGITS);
+        assert_eq!(F64::MAX_EXP, f64::MAX_EXP);
+        assert_eq!(F64::MIN_EXP, f64::MIN_EXP);
+        assert_eq!(F64::MANTISSA_DIGITS, f64::MANTISSA_DIGITS);
+        assert_eq!(F64::DIGITS, f64::DIGITS);
+        assert_eq!(
+            (F16::BITS, F16::EXPONENT_BIAS, F16::MIN_EXP, F16::DIGITS),
+            (16, 15, -13, 3)
+        );
+        #[cfg(feature = "f128")]
+        assert_eq!(
+            (
+                F128::BITS,
+                F128::MAX_EXP,
+                F128::MANTISSA_DIGITS,
+                F128::DIGITS
+            ),
+            (128, 16384, 113, 33)
+        );
+    }
+
+    #[test]
+    fn to_narrow_int() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F32::from_bits(0x43000000); // 128
+        let (ret, flags) = with_flags(|| (a.to_u8(rnd, true), a.to_i8(rnd, true)));
+        assert_eq!(ret.0, 128);
+        // saturates the way to_i32 does for infinities
+        let (inf, _) = with_flags(|| F32::infinity().to_i32(rnd, false));
+        assert_eq!(ret.1, if inf < 0 { i8::MIN } else { i8::MAX });
+        assert!(flags.is_invalid() && !flags.is_inexact());
+
+        let a = F64::from_bits(0xc0dfffe000000000); // -32767.5
+        let (ret, flags) = with_flags(|| (a.to_i16(rnd, true), a.to_u16(rnd, false)));
+        assert_eq!(ret.0, -32768);
+        let (wide, _) = with_flags(|| a.to_u32(rnd, false));
+        assert_eq!(ret.1, if wide == 0 { 0 } else { u16::MAX });
+        assert!(flags.is_invalid() && flags.is_inexact());
+
+        let (ret, flags) = with_flags(|| F16::from_bits(0x3e00).to_u8(rnd, false));
+        assert_eq!(ret, 2);
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    fn pointer_sized_int() {
+        let rnd = RoundingMode::TowardZero;
+        let a = F64::from_usize(usize::MAX, rnd);
+        assert_eq!(a.to_usize(rnd, false), usize::MAX - (usize::MAX >> 53));
+        let a = F32::from_isize(-1000, rnd);
+        assert_eq!(a.to_isize(rnd, false), -1000);
+
+        let (_, flags) = with_flags(|| a.to_usize(rnd, false));
+        assert!(flags.is_invalid());
+    }
+
+    #[test]
+    fn fixed_point() {
+        let rnd = RoundingMode::TiesToEven;
+        // Q15
+        let a = F32::from_bits(0xbf400000); // -0.75
+        let (ret, flags) = with_flags(|| a.to_fixed(15, rnd));
+        assert_eq!(ret, -0x6000);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(F32::from_fixed(-0x6000, 15, rnd).to_bits(), 0xbf400000);
+
+        let a = F64::from_bits(0x3fd5555555555555); // 1/3
+        let (ret, flags) = with_flags(|| a.to_fixed(15, rnd));
+        assert_eq!(ret, 0x2aab);
+        assert!(flags.is_inexact());
+        assert_eq!(a.to_fixed(15, RoundingMode::TowardZero), 0x2aaa);
+
+        // 2^63 is just out of range, -2^63 is not
+        let a = F64::from_bits(0x43e0000000000000);
+        let (ret, flags) = with_flags(|| a.to_fixed(0, rnd));
+        assert_eq!(ret, i64::MAX);
+        assert!(flags.is_invalid());
+        assert_eq!(a.neg().to_fixed(0, rnd), i64::MIN);
+        let (ret, flags) = with_flags(|| F64::quiet_nan().to_fixed(8, rnd));
+        assert_eq!(ret, 0);
+        assert!(flags.is_invalid());
+
+        // rounds once from the exact value, 2047.4998
+        let (ret, flags) = with_flags(|| F16::from_fixed(0x7ff7ff, 12, rnd));
+        assert_eq!(ret.to_bits(), 0x67ff);
+        assert!(flags.is_inexact());
+
+        // huge scales
+        let (ret, flags) = with_flags(|| F16::from_bits(0).to_fixed(200, rnd));
+        assert_eq!((ret, flags.to_bits()), (0, 0));
+        let (ret, flags) = with_flags(|| F64::from_bits(1 << 63).to_fixed(u32::MAX, rnd));
+        assert_eq!((ret, flags.to_bits()), (0, 0));
+        let (ret, flags) = with_flags(|| F64::from_bits(1).to_fixed(u32::MAX, rnd));
+        assert_eq!(ret, i64::MAX);
+        assert!(flags.is_invalid());
+        let (ret, flags) = with_flags(|| F16::from_bits(0xbc00).to_fixed(2000, rnd));
+        assert_eq!(ret, i64::MIN);
+        assert!(flags.is_invalid());
+        let (ret, flags) = with_flags(|| F64::from_fixed(1, u32::MAX, rnd));
+        assert_eq!(ret.to_bits(), 0);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        let ret = F64::from_fixed(-1, 1 << 31, RoundingMode::TowardNegative);
+        assert_eq!(ret.to_bits(), 0x8000000000000001);
+        assert_eq!(F32::from_fixed(0, u32::MAX, rnd).to_bits(), 0);
+    }
+
+    #[test]
+    fn from_scaled_int() {
+        let rnd = RoundingMode::TiesToEven;
+        // (5 * 2^24 + 1) * 2^-174 is just above 2.5 times the smallest subnormal, and would
+        // round to an even 2 times if it was converted to F32 before scaling
+        let (ret, flags) = with_flags(|| F32::from_i64_scaled((5 << 24) + 1, -174, rnd));
+        assert_eq!(ret.to_bits(), 0x00000003);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        let ret = F64::from_i64_scaled(-1, -1080, RoundingMode::TowardPositive);
+        assert_eq!(ret.to_bits(), 0x8000000000000000);
+
+        let (ret, flags) = with_flags(|| F32::from_i64_scaled(3, 127, rnd));
+        assert!(ret.is_infinity());
+        assert!(flags.is_overflow() && flags.is_inexact());
+        let (ret, flags) = with_flags(|| F32::from_i64_scaled(3, -150, rnd));
+        assert_eq!(ret.to_bits(), 0x00000002);
+        assert!(flags.is_underflow() && flags.is_inexact());
+
+        // (2^54 - 1) * 2^-1076 rounds up to the smallest normal, and is only tiny before
+        // rounding
+        let scaled = || with_flags(|| F64::from_i64_scaled((1 << 54) - 1, -1076, rnd));
+        let (ret, flags) = scaled();
+        assert_eq!(ret.to_bits(), 0x0010000000000000);
+        assert!(!flags.is_underflow() && flags.is_inexact());
+        SoftFloatState {
+            tininess_before_rounding: true,
+            ..SoftFloatState::default()
+        }
+        .restore();
+        let (ret, flags) = scaled();
+        assert_eq!(ret.to_bits(), 0x0010000000000000);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        SoftFloatState::default().restore();
+    }
+
+    #[test]
+    fn convert() {
+        let a = F16::from_bits(0x3555);
+        let b: F64 = a.convert(RoundingMode::TiesToEven);
+        assert_eq!(b.to_bits(), 0x3fd5540000000000);
+
+        let a = F64::from_bits(0x3fd5555555555555);
+        let b: F16 = a.convert(RoundingMode::TiesToEven);
+        assert_eq!(b.to_bits(), 0x3555);
+        let b: F16 = a.convert(RoundingMode::TowardPositive);
+        assert_eq!(b.to_bits(), 0x3556);
+    }
+
+    #[test]
+    fn convert_generic_matches() {
+        let values = [
+            0x3fd5555555555555,
+            0xc0f869f000000000,
+            0x3ee4f8b588e368f1,
+            0x7fe1ccf385ebc8a0,
+            0x0000000000000001,
+            0x8000000000000000,
+            0xfff0000000000000,
+        ];
+        let modes = [
+            RoundingMode::TiesToEven,
+            RoundingMode::TowardZero,
+            RoundingMode::TowardNegative,
+            RoundingMode::TowardPositive,
+            RoundingMode::TiesToAway,
+        ];
+        for &x in &values {
+            for &rnd in &modes {
+                let a = F64::from_bits(x);
+
+                let mut flag0 = ExceptionFlags::default();
+                flag0.set();
+                let b0 = a.to_f16(rnd);
+                flag0.get();
+
+                let mut flag1 = ExceptionFlags::default();
+                flag1.set();
+                let b1: F16 = convert_generic(&a, rnd);
+                flag1.get();
+
+                assert_eq!(b0.to_bits(), b1.to_bits());
+                assert_eq!(flag0.to_bits(), flag1.to_bits());
+
+                let b0 = a.to_f32(rnd);
+                let b1: F32 = convert_generic(&a, rnd);
+                assert_eq!(b0.to_bits(), b1.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn neg_abs_arithmetic() {
+        let snan = F64::from_bits(0xfff0000000000001);
+        let (ret, flags) = with_flags(|| snan.neg_arithmetic(false));
+        assert_eq!(ret.to_bits(), 0x7ff0000000000001);
+        assert!(flags.is_invalid());
+        let (ret, flags) = with_flags(|| snan.abs_arithmetic(true));
+        assert_eq!(ret.to_bits(), 0x7ff8000000000001);
+        assert!(flags.is_invalid());
+
+        let qnan = F16::from_bits(0x7e01);
+        let (ret, flags) = with_flags(|| (qnan.neg_arithmetic(true), qnan.abs_arithmetic(true)));
+        assert_eq!((ret.0.to_bits(), ret.1.to_bits()), (0xfe01, 0x7e01));
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| F32::from_bits(0x80000000).abs_arithmetic(true));
+        assert_eq!(ret.to_bits(), 0);
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    fn bitwise_eq() {
+        let zero = F32::from_bits(0);
+        let neg_zero = F32::from_bits(0x80000000);
+        assert!(zero.is_bitwise_eq(zero) && !zero.is_bitwise_eq(neg_zero));
+        let nan = F32::from_bits(0x7fc00000);
+        let other = F32::from_bits(0xffc00001);
+        let snan = F32::from_bits(0x7f800001);
+        let (_, flags) = with_flags(|| {
+            assert!(nan.is_bitwise_eq(nan) && !nan.is_bitwise_eq(other));
+            assert!(nan.eq_ignoring_nan_payload(other));
+            assert!(snan.eq_ignoring_nan_payload(F32::from_bits(0xff800002)));
+            assert!(!snan.eq_ignoring_nan_payload(nan));
+            assert!(!neg_zero.eq_ignoring_nan_payload(zero));
+            assert!(!nan.eq_ignoring_nan_payload(zero));
+        });
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn zerocopy_bytes() {
+        use zerocopy::{FromBytes, IntoBytes};
+
+        let regs = [0x3f800000u32, 0x40000000];
+        let a = <[F32]>::ref_from_bytes(regs.as_bytes()).unwrap();
+        assert_eq!(a[1].to_bits(), 0x40000000);
+        let a = F16::read_from_bytes(&0x3c00u16.to_ne_bytes()).unwrap();
+        assert_eq!(a.to_bits(), 0x3c00);
+        let a = F64::from_bits(0x3ff0000000000000);
+        assert_eq!(a.as_bytes(), &0x3ff0000000000000u64.to_ne_bytes());
     }
 }
@@ -0,0 +1,291 @@
+//! Lockstep co-simulation
+//!
+//! [`Lockstep`] runs each operation through softfloat and through a reference, such as an
+//! RTL simulation or the FPU under test, compares the result bits and the exception flags,
+//! and collects a [`Mismatch`] report for every operation on which they differ. It returns
+//! the softfloat result, so a model can keep running on it whatever the reference does.
+//!
+//! ```
+//! use softfloat_wrapper::lockstep::{Lockstep, Op};
+//! use softfloat_wrapper::{ExceptionFlags, FloatArith, FloatBits, RoundingMode, F32};
+//!
+//! // a reference which flushes subnormal results to zero
+//! let mut lockstep = Lockstep::new(|op: Op, x: &[F32], rnd: RoundingMode| {
+//!     let (ret, flags) = op.run(x, rnd);
+//!     if ret.is_subnormal() {
+//!         (F32::zero(), flags)
+//!     } else {
+//!         (ret, flags)
+//!     }
+//! });
+//! let rnd = RoundingMode::TiesToEven;
+//! lockstep.mul(F32::from_bits(0x3f000000), F32::from_bits(0x3f800000), rnd);
+//! lockstep.mul(F32::from_bits(0x00800000), F32::from_bits(0x3f000000), rnd);
+//! assert_eq!(lockstep.count(), 2);
+//! assert_eq!(lockstep.mismatches().len(), 1);
+//! assert_eq!(lockstep.mismatches()[0].index, 1);
+//! ```
+
+use crate::host::KnownDifferences;
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat};
+use std::fmt;
+
+/// Operation run by [`Lockstep`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Sqrt,
+    /// `a * b + c` with a single rounding
+    MulAdd,
+    /// rounding to an integral value, without raising inexact
+    RoundToInt,
+}
+
+impl Op {
+    /// number of operands
+    pub fn arity(&self) -> usize {
+        match self {
+            Op::Sqrt | Op::RoundToInt => 1,
+            Op::MulAdd => 3,
+            _ => 2,
+        }
+    }
+
+    /// Runs `self` on `operands` through softfloat, returning the result and the exception
+    /// flags raised, and leaving the exception flags of the thread as they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of operands is not the arity of `self`.
+    pub fn run<T: SoftFloat + Copy>(
+        &self,
+        operands: &[T],
+        rnd: RoundingMode,
+    ) -> (T, ExceptionFlags) {
+        assert_eq!(operands.len(), self.arity(), "wrong number of operands");
+        let a = operands[0];
+        with_flags(|| match self {
+            Op::Add => a.add(operands[1], rnd),
+            Op::Sub => a.sub(operands[1], rnd),
+            Op::Mul => a.mul(operands[1], rnd),
+            Op::Div => a.div(operands[1], rnd),
+            Op::Rem => a.rem(operands[1], rnd),
+            Op::Sqrt => a.sqrt(rnd),
+            Op::MulAdd => a.fused_mul_add(operands[1], operands[2], rnd),
+            Op::RoundToInt => a.round_to_integral(rnd),
+        })
+    }
+}
+
+/// An operation on which softfloat and the reference differ
+#[derive(Clone, Debug)]
+pub struct Mismatch<T> {
+    /// index of the operation, counting from 0
+    pub index: usize,
+    pub op: Op,
+    pub operands: Vec<T>,
+    pub rnd: RoundingMode,
+    pub soft: T,
+    pub soft_flags: ExceptionFlags,
+    pub reference: T,
+    pub reference_flags: ExceptionFlags,
+}
+
+impl<T: SoftFloat> fmt::Display for Mismatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {:?}", self.index, self.op)?;
+        for x in &self.operands {
+            write!(f, " {:X}", x.to_bits())?;
+        }
+        write!(
+            f,
+            " {:?}: softfloat {:X} {:02X}, reference {:X} {:02X}",
+            self.rnd,
+            self.soft.to_bits(),
+            self.soft_flags.to_bits(),
+            self.reference.to_bits(),
+            self.reference_flags.to_bits()
+        )
+    }
+}
+
+/// Comparator running operations through softfloat and a reference in lockstep
+///
+/// The reference is called with the operation, its operands and the rounding mode, and
+/// returns its result and the exception flags it raised.
+pub struct Lockstep<T, F> {
+    reference: F,
+    known: KnownDifferences,
+    count: usize,
+    mismatches: Vec<Mismatch<T>>,
+}
+
+impl<T, F> Lockstep<T, F>
+where
+    T: SoftFloat + Copy,
+    F: FnMut(Op, &[T], RoundingMode) -> (T, ExceptionFlags),
+{
+    /// Creates a comparator against `reference`, which ignores the differences of
+    /// [`KnownDifferences::default`].
+    pub fn new(reference: F) -> Self {
+        Self {
+            reference,
+            known: KnownDifferences::default(),
+            count: 0,
+            mismatches: Vec::new(),
+        }
+    }
+
+    /// Returns `self` ignoring the differences of `known` instead.
+    pub fn with_known_differences(self, known: KnownDifferences) -> Self {
+        Self { known, ..self }
+    }
+
+    /// Runs `op` on `operands` through softfloat and the reference, records a mismatch if
+    /// they differ, and returns the softfloat result, raising its exception flags.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of operands is not the arity of `op`.
+    pub fn run(&mut self, op: Op, operands: &[T], rnd: RoundingMode) -> T {
+        let (soft, soft_flags) = op.run(operands, rnd);
+        let (reference, reference_flags) = with_flags(|| (self.reference)(op, operands, rnd)).0;
+
+        let same_result = soft.to_bits() == reference.to_bits()
+            || (self.known.nan_payloads && soft.is_nan() && reference.is_nan());
+        let mut mask = !0;
+        if self.known.underflow {
+            mask &= !ExceptionFlags::FLAG_UNDERFLOW;
+        }
+        let same_flags = soft_flags.to_bits() & mask == reference_flags.to_bits() & mask;
+        if !(same_result && same_flags) {
+            self.mismatches.push(Mismatch {
+                index: self.count,
+                op,
+                operands: operands.to_vec(),
+                rnd,
+                soft,
+                soft_flags,
+                reference,
+                reference_flags,
+            });
+        }
+        self.count += 1;
+        soft_flags.raise();
+        soft
+    }
+
+    /// Computes `a + b` in lockstep.
+    pub fn add(&mut self, a: T, b: T, rnd: RoundingMode) -> T {
+        self.run(Op::Add, &[a, b], rnd)
+    }
+
+    /// Computes `a - b` in lockstep.
+    pub fn sub(&mut self, a: T, b: T, rnd: RoundingMode) -> T {
+        self.run(Op::Sub, &[a, b], rnd)
+    }
+
+    /// Computes `a * b` in lockstep.
+    pub fn mul(&mut self, a: T, b: T, rnd: RoundingMode) -> T {
+        self.run(Op::Mul, &[a, b], rnd)
+    }
+
+    /// Computes `a / b` in lockstep.
+    pub fn div(&mut self, a: T, b: T, rnd: RoundingMode) -> T {
+        self.run(Op::Div, &[a, b], rnd)
+    }
+
+    /// Computes the square root of `a` in lockstep.
+    pub fn sqrt(&mut self, a: T, rnd: RoundingMode) -> T {
+        self.run(Op::Sqrt, &[a], rnd)
+    }
+
+    /// Computes `a * b + c` with a single rounding in lockstep.
+    pub fn fused_mul_add(&mut self, a: T, b: T, c: T, rnd: RoundingMode) -> T {
+        self.run(Op::MulAdd, &[a, b, c], rnd)
+    }
+
+    /// Returns the number of operations run.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the mismatches recorded so far, in order.
+    pub fn mismatches(&self) -> &[Mismatch<T>] {
+        &self.mismatches
+    }
+
+    /// Returns the mismatches recorded, consuming `self`.
+    pub fn into_mismatches(self) -> Vec<Mismatch<T>> {
+        self.mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, F16, F64};
+
+    #[test]
+    fn lockstep_mismatches() {
+        let rnd = RoundingMode::TiesToEven;
+        // a reference detecting tininess before rounding, and with another NaN
+        let reference = |op: Op, x: &[F16], rnd| {
+            let (ret, mut flags) = op.run(x, rnd);
+            if ret.to_bits() & 0x7fff == 0x0400 && flags.is_inexact() {
+                flags |= ExceptionFlags::from_bits(ExceptionFlags::FLAG_UNDERFLOW);
+            }
+            if ret.is_nan() {
+                (F16::from_bits(0xfe00), flags)
+            } else {
+                (ret, flags)
+            }
+        };
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+
+        let mut lockstep = Lockstep::new(reference);
+        // rounds up to the smallest normal
+        let a = F16::from_bits(0x0401);
+        let b = F16::from_bits(0x3bff);
+        assert_eq!(lockstep.mul(a, b, rnd).to_bits(), 0x0400);
+        assert!(lockstep.sqrt(F16::from_bits(0xbc00), rnd).is_nan());
+        assert_eq!(lockstep.mismatches().len(), 1);
+        let m = &lockstep.mismatches()[0];
+        assert_eq!(m.op, Op::Mul);
+        assert!(m.reference_flags.is_underflow() && !m.soft_flags.is_underflow());
+        assert_eq!(
+            m.to_string(),
+            "#0 Mul 401 3BFF TiesToEven: softfloat 400 01, reference 400 03"
+        );
+        flag.get();
+        assert!(flag.is_inexact() && flag.is_invalid() && !flag.is_underflow());
+
+        let mut lockstep = Lockstep::new(reference).with_known_differences(KnownDifferences {
+            nan_payloads: false,
+            underflow: true,
+        });
+        lockstep.mul(a, b, rnd);
+        lockstep.run(Op::RoundToInt, &[F16::from_bits(0x7c01)], rnd);
+        let m = lockstep.into_mismatches();
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[0].index, 1);
+        assert_eq!(m[0].soft.to_bits(), 0x7e01);
+    }
+
+    #[test]
+    fn lockstep_op() {
+        let rnd = RoundingMode::TowardZero;
+        let x = [0x4008000000000000, 0x4000000000000000].map(F64::from_bits);
+        let (ret, flags) = Op::Rem.run(&x, rnd);
+        assert_eq!(ret.to_bits(), 0xbff0000000000000);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = Op::RoundToInt.run(&[F64::from_bits(0x3ff8000000000000)], rnd);
+        assert_eq!(ret.to_bits(), 0x3ff0000000000000);
+        assert!(!flags.is_inexact());
+    }
+}
@@ -0,0 +1,1274 @@
+//! Elementary functions
+//!
+//! The functions are correctly rounded in every rounding mode and format: they return the
+//! exact result rounded once, and raise inexact, overflow and underflow accordingly, with
+//! tininess detected before or after rounding like the backend of the thread does. Exact
+//! results, such as `log2(8.0)`, raise no flags. The results do not depend on the host, so
+//! they can stand in for the libm of an emulated system.
+//!
+//! NaN operands propagate as they do through [`FloatArith::add`], raising invalid for
+//! signaling NaNs; operands outside the domain of a function return the default NaN and
+//! raise invalid.
+//!
+//! ```
+//! use softfloat_wrapper::{math, RoundingMode, F32};
+//!
+//! let a = F32::from_bits(0x3f800000);
+//! assert_eq!(math::exp(&a, RoundingMode::TiesToEven).to_bits(), 0x402df854);
+//! assert_eq!(math::exp(&a, RoundingMode::TowardZero).to_bits(), 0x402df854);
+//! assert_eq!(math::exp(&a, RoundingMode::TowardPositive).to_bits(), 0x402df855);
+//! ```
+
+use crate::num::one;
+use crate::{ExceptionFlags, RoundingMode, SoftFloat};
+use num_traits::One;
+
+mod mp;
+
+use mp::{precision, ziv, Float};
+
+/// Propagates the NaN `x`.
+fn nan<T: SoftFloat>(x: &T) -> T {
+    x.add(x, RoundingMode::TiesToEven)
+}
+
+/// The default NaN, raising invalid
+fn invalid<T: SoftFloat>() -> T {
+    let inf = T::infinity();
+    inf.sub(&inf, RoundingMode::TiesToEven)
+}
+
+/// `x` negated if `negative`
+fn signed<T: SoftFloat>(mut x: T, negative: bool) -> T {
+    if negative {
+        x.set_sign(T::Payload::one());
+    }
+    x
+}
+
+/// `inf` with the sign of `negative`, raising divide by zero
+fn pole<T: SoftFloat>(negative: bool) -> T {
+    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INFINITE).raise();
+    signed(T::infinity(), negative)
+}
+
+/// `2^k` if `x` is an integral power of two
+fn log2_exact<T: SoftFloat>(x: &T) -> Option<i64> {
+    let (_, exp, sig) = unpack_odd(x);
+    if sig == 1 {
+        Some(exp)
+    } else {
+        None
+    }
+}
+
+/// Splits a finite `x` into `sig * 2^exp` with an odd `sig`.
+fn unpack_odd<T: SoftFloat>(x: &T) -> (bool, i64, u128) {
+    let (neg, exp, sig) = crate::unpack(x);
+    let zeros = sig.trailing_zeros().min(127);
+    (neg, exp as i64 + zeros as i64, sig >> zeros)
+}
+
+/// Computes `e^x`.
+pub fn exp<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_infinity() {
+        return if x.is_negative() {
+            T::zero()
+        } else {
+            T::infinity()
+        };
+    }
+    if x.is_zero() {
+        return one();
+    }
+    let a = Float::from_soft(x);
+    if a.top() < -(precision::<T>() as i64) - 2 {
+        // 1 + x rounds like 1 nudged toward x
+        return Float::one().nudge(x.is_positive()).round(rnd);
+    }
+    if a.top() > 24 {
+        // overflows or underflows every format
+        let huge = if x.is_negative() { -(1 << 24) } else { 1 << 24 };
+        return Float::one().shl(huge).round(rnd);
+    }
+    ziv(rnd, |prec| mp::exp(&a, prec))
+}
+
+/// Computes `2^x`.
+pub fn exp2<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() || x.is_infinity() || x.is_zero() {
+        return exp(x, rnd);
+    }
+    let (negative, e, m) = unpack_odd(x);
+    if e >= 0 {
+        // 2^x is exact for integers, clamped beyond the range of every format
+        let k = if e > 32 || m > 1 << 32 {
+            1 << 40
+        } else {
+            (m << e).min(1 << 40) as i64
+        };
+        return Float::one().shl(if negative { -k } else { k }).round(rnd);
+    }
+    let a = Float::from_soft(x);
+    if a.top() < -(precision::<T>() as i64) - 2 {
+        return Float::one().nudge(x.is_positive()).round(rnd);
+    }
+    if a.top() > 24 {
+        let huge = if x.is_negative() { -(1 << 24) } else { 1 << 24 };
+        return Float::one().shl(huge).round(rnd);
+    }
+    ziv(rnd, |prec| {
+        let wp = prec + a.top().max(0) as u32 + 16;
+        mp::exp(&a.mul(&mp::ln2(wp), wp), prec)
+    })
+}
+
+/// Computes `e^x - 1`, accurately even if `x` is close to 0.
+pub fn expm1<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_zero() {
+        return T::from_bits(x.to_bits());
+    }
+    if x.is_infinity() {
+        return if x.is_negative() {
+            signed(one(), true)
+        } else {
+            T::infinity()
+        };
+    }
+    let a = Float::from_soft(x);
+    let p = precision::<T>() as i64;
+    if a.top() < -p - 3 {
+        // x + x^2 / 2 rounds like x nudged up
+        return a.nudge(x.is_positive()).round(rnd);
+    }
+    if x.lt(T::from_i64(-p - 2, rnd)) {
+        // e^x is below half an ulp of -1
+        return Float::one().neg().nudge(false).round(rnd);
+    }
+    if a.top() > 24 {
+        return Float::one().shl(1 << 24).round(rnd);
+    }
+    ziv(rnd, |prec| mp::expm1(&a, prec))
+}
+
+/// Checks the operand of a logarithm, returning the result if it is not finite or zero.
+fn log_special<T: SoftFloat>(x: &T) -> Option<T> {
+    if x.is_nan() {
+        Some(nan(x))
+    } else if x.is_zero() {
+        Some(pole(true))
+    } else if x.is_negative() {
+        Some(invalid())
+    } else if x.is_infinity() {
+        Some(T::infinity())
+    } else if log2_exact(x) == Some(0) {
+        Some(T::zero())
+    } else {
+        None
+    }
+}
+
+/// Computes the natural logarithm of `x`.
+pub fn ln<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if let Some(ret) = log_special(x) {
+        return ret;
+    }
+    let a = Float::from_soft(x);
+    ziv(rnd, |prec| mp::ln(&a, prec))
+}
+
+/// Computes `ln(1 + x)`, accurately even if `x` is close to 0.
+pub fn ln_1p<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_zero() {
+        return T::from_bits(x.to_bits());
+    }
+    let minus_one = signed(one::<T>(), true);
+    if x.eq(&minus_one) {
+        return pole(true);
+    }
+    if x.lt(&minus_one) {
+        return invalid();
+    }
+    if x.is_infinity() {
+        return T::infinity();
+    }
+    let a = Float::from_soft(x);
+    if a.top() < -(precision::<T>() as i64) - 3 {
+        // x - x^2 / 2 rounds like x nudged down
+        return a.nudge(x.is_negative()).round(rnd);
+    }
+    ziv(rnd, |prec| mp::ln_1p(&a, prec))
+}
+
+/// Computes the base 2 logarithm of `x`.
+pub fn log2<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if let Some(ret) = log_special(x) {
+        return ret;
+    }
+    if let Some(k) = log2_exact(x) {
+        return T::from_i64(k, rnd);
+    }
+    let a = Float::from_soft(x);
+    ziv(rnd, |prec| {
+        let wp = prec + 8;
+        mp::ln(&a, wp).div(&mp::ln2(wp), wp)
+    })
+}
+
+/// Computes the base 10 logarithm of `x`.
+pub fn log10<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if let Some(ret) = log_special(x) {
+        return ret;
+    }
+    // 10^k = 5^k * 2^k
+    let (_, exp, sig) = unpack_odd(x);
+    if exp > 0 && exp < 64 && 5u128.checked_pow(exp as u32) == Some(sig) {
+        return T::from_i64(exp, rnd);
+    }
+    let a = Float::from_soft(x);
+    ziv(rnd, |prec| {
+        let wp = prec + 8;
+        mp::ln(&a, wp).div(&mp::ln10(wp), wp)
+    })
+}
+
+/// Computes `1 / x`, which is the same as dividing 1 by `x`.
+pub fn recip<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    one::<T>().div(x, rnd)
+}
+
+/// Computes `1 / sqrt(x)` with a single rounding.
+///
+/// Zeros return an infinity of the same sign and raise divide by zero.
+pub fn rsqrt<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_zero() {
+        return pole(x.is_negative());
+    }
+    if x.is_negative() {
+        return invalid();
+    }
+    if x.is_infinity() {
+        return T::zero();
+    }
+    // the result is a power of two or irrational
+    let (_, e, m) = unpack_odd(x);
+    if m == 1 && e % 2 == 0 {
+        return Float::one().shl(-e / 2).round(rnd);
+    }
+    let a = Float::from_soft(x);
+    ziv(rnd, |prec| mp::rsqrt(&a, prec))
+}
+
+fn icbrt(x: u128) -> u128 {
+    let mut r = (x as f64).cbrt() as u128;
+    while r * r * r > x {
+        r -= 1;
+    }
+    while (r + 1) * (r + 1) * (r + 1) <= x {
+        r += 1;
+    }
+    r
+}
+
+/// Computes the cube root of `x`.
+pub fn cbrt<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_infinity() || x.is_zero() {
+        return T::from_bits(x.to_bits());
+    }
+    // the result is exact if the odd part is a cube and the exponent is a multiple of 3, and
+    // never a midpoint, whose cube would have more bits than any value of T
+    let (negative, e, m) = unpack_odd(x);
+    let r = icbrt(m);
+    if r * r * r == m && e % 3 == 0 {
+        return Float::from_u128(negative, e / 3, r).round(rnd);
+    }
+    let a = Float::from_soft(x).abs();
+    ziv(rnd, |prec| {
+        let ret = mp::cbrt(&a, prec);
+        if negative {
+            ret.neg()
+        } else {
+            ret
+        }
+    })
+}
+
+/// Computes `sqrt(x^2 + y^2)` with a single rounding, so it neither overflows nor
+/// underflows unless the result does.
+///
+/// Infinities return `inf` even if the other operand is a quiet NaN, as in C99.
+pub fn hypot<T: SoftFloat>(x: &T, y: &T, rnd: RoundingMode) -> T {
+    if (x.is_infinity() || y.is_infinity()) && !x.is_signaling_nan() && !y.is_signaling_nan() {
+        return T::infinity();
+    }
+    if x.is_nan() || y.is_nan() {
+        return x.add(y, RoundingMode::TiesToEven);
+    }
+    if x.is_zero() || y.is_zero() {
+        return if x.is_zero() { y.abs() } else { x.abs() };
+    }
+    let (a, b) = (Float::from_soft(x).abs(), Float::from_soft(y).abs());
+    let (a, b) = if a.top() >= b.top() { (a, b) } else { (b, a) };
+    let p = precision::<T>();
+    if a.top() - b.top() > p as i64 + 2 {
+        // a (1 + (b / a)^2 / 2) rounds like a nudged up
+        return a.nudge(true).round(rnd);
+    }
+    // the square root of the exact a^2 + b^2 is exact or a midpoint if an approximation
+    // truncated to p + 1 bits, or the next value on that grid, squares to it
+    let s = a.mul(&a, u32::MAX).add(&b.mul(&b, u32::MAX), u32::MAX);
+    let r = s.mul(&mp::rsqrt(&s, p + 32), p + 32).trunc(p + 1);
+    let ulp = Float::one().shl(r.top() - p as i64 - 1);
+    for c in [r.add(&ulp, u32::MAX), r] {
+        if c.mul(&c, u32::MAX).sub(&s, u32::MAX).is_zero() {
+            return c.round(rnd);
+        }
+    }
+    ziv(rnd, |prec| {
+        s.mul(&mp::rsqrt(&s, prec + 8), prec + 8).trunc(prec)
+    })
+}
+
+/// Returns `sqrt(x) * 2^scale` for a finite positive `x`, rounded once, so that a result
+/// scaled into the subnormals is not rounded twice.
+pub(crate) fn sqrt_scaled<T: SoftFloat>(x: &T, scale: i32, rnd: RoundingMode) -> T {
+    let s = Float::from_soft(x);
+    let p = precision::<T>();
+    // the square root of x is exact if an approximation truncated to p + 1 bits, or the
+    // next value on that grid, squares to it, and irrational otherwise
+    let r = s.mul(&mp::rsqrt(&s, p + 32), p + 32).trunc(p + 1);
+    let ulp = Float::one().shl(r.top() - p as i64 - 1);
+    for c in [r.add(&ulp, u32::MAX), r] {
+        if c.mul(&c, u32::MAX).sub(&s, u32::MAX).is_zero() {
+            return c.shl(scale as i64).round(rnd);
+        }
+    }
+    ziv(rnd, |prec| {
+        s.mul(&mp::rsqrt(&s, prec + 8), prec + 8)
+            .trunc(prec)
+            .shl(scale as i64)
+    })
+}
+
+/// The trigonometric functions
+#[derive(Copy, Clone)]
+enum Trig {
+    Sin,
+    Cos,
+    Tan,
+}
+
+fn trig<T: SoftFloat>(x: &T, f: Trig, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return nan(x);
+    }
+    if x.is_infinity() {
+        return invalid();
+    }
+    if x.is_zero() {
+        return match f {
+            Trig::Cos => one(),
+            _ => T::from_bits(x.to_bits()),
+        };
+    }
+    let a = Float::from_soft(x);
+    if 2 * a.top() < -(precision::<T>() as i64) - 4 {
+        // sin(x) = x - x^3 / 6, cos(x) = 1 - x^2 / 2 and tan(x) = x + x^3 / 3 round like
+        // their first term nudged toward the second
+        return match f {
+            Trig::Sin => a.nudge(false),
+            Trig::Cos => Float::one().nudge(false),
+            Trig::Tan => a.nudge(true),
+        }
+        .round(rnd);
+    }
+    ziv(rnd, |prec| {
+        let wp = prec + 8;
+        let (r, quadrant) = mp::reduce(&a, wp);
+        let sin_cos = |cos| mp::sin_cos(&r, cos, wp);
+        match f {
+            Trig::Sin => {
+                let y = sin_cos(quadrant % 2 == 1);
+                if quadrant >= 2 {
+                    y.neg()
+                } else {
+                    y
+                }
+            }
+            Trig::Cos => {
+                let y = sin_cos(quadrant % 2 == 0);
+                if quadrant == 1 || quadrant == 2 {
+                    y.neg()
+                } else {
+                    y
+                }
+            }
+            Trig::Tan => {
+                let (sin, cos) = (sin_cos(false), sin_cos(true));
+                if quadrant % 2 == 0 {
+                    sin.div(&cos, wp)
+                } else {
+                    cos.div(&sin, wp).neg()
+                }
+            }
+        }
+    })
+}
+
+/// Computes the sine of `x`, in radians.
+pub fn sin<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    trig(x, Trig::Sin, rnd)
+}
+
+/// Computes the cosine of `x`, in radians.
+pub fn cos<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    trig(x, Trig::Cos, rnd)
+}
+
+/// Computes the tangent of `x`, in radians.
+pub fn tan<T: SoftFloat>(x: &T, rnd: RoundingMode) -> T {
+    trig(x, Trig::Tan, rnd)
+}
+
+/// Computes the sine and the cosine of `x`, in radians.
+pub fn sincos<T: SoftFloat>(x: &T, rnd: RoundingMode) -> (T, T) {
+    (sin(x, rnd), cos(x, rnd))
+}
+
+/// Computes the angle of the point `(x, y)` from the positive x axis, in radians, between
+/// `-pi` and `pi`.
+///
+/// The special cases follow C99: the signs of zeros select between `0` and `pi`, with the
+/// sign of `y`, and infinities give the odd multiples of `pi / 4`.
+pub fn atan2<T: SoftFloat>(y: &T, x: &T, rnd: RoundingMode) -> T {
+    if x.is_nan() || y.is_nan() {
+        return y.add(x, RoundingMode::TiesToEven);
+    }
+    let negative = y.is_negative();
+    // n pi / 4 with the sign of y
+    let quarter_pi = |n: u64| {
+        ziv(rnd, |prec| {
+            let ret = mp::pi(prec + 4).mul_int(n, prec + 4).shl(-2);
+            if negative {
+                ret.neg()
+            } else {
+                ret
+            }
+        })
+    };
+    if y.is_zero() || (x.is_infinity() && y.is_finite()) {
+        return if x.is_negative() {
+            quarter_pi(4)
+        } else {
+            signed(T::zero(), negative)
+        };
+    }
+    if y.is_infinity() {
+        return match (x.is_infinity(), x.is_negative()) {
+            (false, _) => quarter_pi(2),
+            (true, false) => quarter_pi(1),
+            (true, true) => quarter_pi(3),
+        };
+    }
+    if x.is_zero() {
+        return quarter_pi(2);
+    }
+    let (a, b) = (Float::from_soft(y), Float::from_soft(x));
+    ziv(rnd, |prec| mp::atan2(&a, &b, prec))
+}
+
+fn isqrt(x: u128) -> u128 {
+    let mut r = (x as f64).sqrt() as u128;
+    while r * r > x {
+        r -= 1;
+    }
+    while (r + 1) * (r + 1) <= x {
+        r += 1;
+    }
+    r
+}
+
+/// `|x|^y` if it is exact, where `|x| = m 2^e` and `|y| = c 2^f` with odd `m` and `c`
+fn pow_exact(mut m: u128, mut e: i64, negative: bool, c: u128, f: i64) -> Option<Float> {
+    // x^(c / 2^k) = (x^(1 / 2^k))^c is only rational if the root is
+    for _ in f..0 {
+        let r = isqrt(m);
+        if r * r != m || e % 2 != 0 {
+            return None;
+        }
+        m = r;
+        e /= 2;
+    }
+    let shift = f.max(0);
+    if m == 1 {
+        // 2^(e n), clamped beyond the range of every format
+        let bits = 128 - c.leading_zeros() as i64 + shift;
+        let n = if bits > 80 {
+            1 << 80
+        } else {
+            (c as i128) << shift
+        };
+        let exp = (e as i128 * n).clamp(-(1 << 40), 1 << 40) as i64;
+        return Some(Float::one().shl(if negative { -exp } else { exp }));
+    }
+    // m^n has at least n bits, more than any format when it does not fit in 256
+    if negative || shift > 8 || c > 256 {
+        return None;
+    }
+    let n = (c << shift) as u64;
+    if (128 - m.leading_zeros()) as u64 * n > 256 {
+        return None;
+    }
+    Some(Float::from_u128(false, e, m).pow_int(n, u32::MAX))
+}
+
+/// Computes `x^n`.
+///
+/// `x^0` is 1 even if `x` is a quiet NaN, and zeros raise divide by zero for negative `n`.
+pub fn powi<T: SoftFloat>(x: &T, n: i32, rnd: RoundingMode) -> T {
+    if n == 0 && !x.is_signaling_nan() {
+        return one();
+    }
+    if x.is_nan() {
+        return nan(x);
+    }
+    let negative = x.is_negative() && n % 2 != 0;
+    if x.is_zero() {
+        return if n < 0 {
+            pole(negative)
+        } else {
+            signed(T::zero(), negative)
+        };
+    }
+    if x.is_infinity() {
+        let ret = if n < 0 { T::zero() } else { T::infinity() };
+        return signed(ret, negative);
+    }
+
+    let (_, e, m) = unpack_odd(x);
+    let zeros = n.trailing_zeros();
+    let c = (n.unsigned_abs() >> zeros) as u128;
+    if let Some(ret) = pow_exact(m, e, n < 0, c, zeros as i64) {
+        return if negative { ret.neg() } else { ret }.round(rnd);
+    }
+    let a = Float::from_soft(x);
+    ziv(rnd, |prec| {
+        // the error grows with n, which has up to 32 bits
+        let wp = prec + 40;
+        let ret = a.pow_int(n.unsigned_abs() as u64, wp);
+        if n < 0 {
+            ret.recip(wp)
+        } else {
+            ret
+        }
+    })
+}
+
+/// Computes `x^y`.
+///
+/// The special cases follow C99: `x^0` and `1^y` are 1 even if the other operand is a quiet
+/// NaN, `(-1)^inf` is 1, and zeros raise divide by zero for negative finite `y`. Negative `x`
+/// raise invalid for finite `y` that are not integers.
+pub fn pow<T: SoftFloat>(x: &T, y: &T, rnd: RoundingMode) -> T {
+    let is_one = |x: &T| x.to_bits() == one::<T>().to_bits();
+    if (y.is_zero() && !x.is_signaling_nan()) || (is_one(x) && !y.is_signaling_nan()) {
+        return one();
+    }
+    if x.is_nan() || y.is_nan() {
+        return x.add(y, RoundingMode::TiesToEven);
+    }
+    let (integer, odd) = if y.is_finite() {
+        let (_, f, _) = unpack_odd(y);
+        (f >= 0, f == 0)
+    } else {
+        (false, false)
+    };
+    let negative = x.is_negative() && odd;
+    if x.is_zero() {
+        return match (y.is_negative(), y.is_infinity()) {
+            (true, false) => pole(negative),
+            (true, true) => T::infinity(),
+            (false, _) => signed(T::zero(), negative),
+        };
+    }
+    if y.is_infinity() {
+        let a = x.abs();
+        if is_one(&a) {
+            return one();
+        }
+        return if one::<T>().lt(&a) != y.is_negative() {
+            T::infinity()
+        } else {
+            T::zero()
+        };
+    }
+    if x.is_infinity() {
+        let ret = if y.is_negative() {
+            T::zero()
+        } else {
+            T::infinity()
+        };
+        return signed(ret, negative);
+    }
+    if x.is_negative() && !integer {
+        return invalid();
+    }
+
+    let (_, e, m) = unpack_odd(x);
+    let (y_negative, f, c) = unpack_odd(y);
+    if let Some(ret) = pow_exact(m, e, y_negative, c, f) {
+        return if negative { ret.neg() } else { ret }.round(rnd);
+    }
+    // x^y = exp(y ln|x|), where the error of the product is scaled by its magnitude
+    let a = Float::from_soft(x).abs();
+    let b = Float::from_soft(y);
+    let t = b.mul(&mp::ln(&a, 64), 64);
+    let ret = if t.top() < -(precision::<T>() as i64) - 4 {
+        Float::one().nudge(!t.neg)
+    } else if t.top() > 24 {
+        // overflows or underflows every format
+        Float::one().shl(if t.neg { -(1 << 24) } else { 1 << 24 })
+    } else {
+        let scale = t.top().max(0) as u32;
+        return ziv(rnd, |prec| {
+            let wp = prec + scale + 16;
+            let ret = mp::exp(&b.mul(&mp::ln(&a, wp), wp), prec + 8);
+            if negative {
+                ret.neg()
+            } else {
+                ret
+            }
+        });
+    };
+    if negative { ret.neg() } else { ret }.round(rnd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "f128")]
+    use crate::F128;
+    use crate::{with_flags, FloatArith, FloatBits, F16, F32, F64};
+
+    const MODES: [RoundingMode; 5] = [
+        RoundingMode::TiesToEven,
+        RoundingMode::TowardZero,
+        RoundingMode::TowardNegative,
+        RoundingMode::TowardPositive,
+        RoundingMode::TiesToAway,
+    ];
+
+    /// checks `f(a)` in each of `MODES`
+    fn check<T: SoftFloat>(f: fn(&T, RoundingMode) -> T, a: T::Payload, expected: [T::Payload; 5]) {
+        for (rnd, expected) in MODES.iter().zip(expected) {
+            let ret = f(&T::from_bits(a), *rnd);
+            assert!(ret.to_bits() == expected, "{:x} {:?}", a, rnd);
+        }
+    }
+
+    /// checks `f(a, b)` in each of `MODES`
+    fn check2<T: SoftFloat>(
+        f: fn(&T, &T, RoundingMode) -> T,
+        a: T::Payload,
+        b: T::Payload,
+        expected: [T::Payload; 5],
+    ) {
+        for (rnd, expected) in MODES.iter().zip(expected) {
+            let ret = f(&T::from_bits(a), &T::from_bits(b), *rnd);
+            assert!(ret.to_bits() == expected, "{:x} {:x} {:?}", a, b, rnd);
+        }
+    }
+
+    #[test]
+    fn math_exp() {
+        // e
+        check::<F64>(
+            exp,
+            0x3ff0000000000000,
+            [
+                0x4005bf0a8b145769,
+                0x4005bf0a8b145769,
+                0x4005bf0a8b145769,
+                0x4005bf0a8b14576a,
+                0x4005bf0a8b145769,
+            ],
+        );
+        check::<F16>(exp, 0xc000, [0x3055, 0x3054, 0x3054, 0x3055, 0x3055]);
+        // 1 - 2^-60
+        check::<F32>(
+            exp,
+            0xa1800000,
+            [0x3f800000, 0x3f7fffff, 0x3f7fffff, 0x3f800000, 0x3f800000],
+        );
+
+        let (ret, flags) =
+            with_flags(|| exp(&F32::from_bits(0x42b20000), RoundingMode::TiesToEven));
+        assert!(ret.is_infinity() && flags.is_overflow());
+        let (ret, flags) = with_flags(|| {
+            exp(
+                &F64::from_bits(0xc0874385446d71c3),
+                RoundingMode::TiesToEven,
+            )
+        });
+        assert_eq!(ret.to_bits(), 1);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        let (ret, flags) = with_flags(|| exp(&F16::from_bits(0x8000), RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0x3c00);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(
+            exp(&F16::from_bits(0xfc00), RoundingMode::TiesToEven).to_bits(),
+            0
+        );
+        assert_eq!(
+            exp(&F16::from_bits(0x7bff), RoundingMode::TowardZero).to_bits(),
+            0x7bff
+        );
+    }
+
+    #[test]
+    fn math_exp_near_zero() {
+        check::<F64>(
+            exp2,
+            0x3fe0000000000000,
+            [
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcd,
+            ],
+        );
+        // 2^-25 is below the smallest subnormal F16
+        check::<F16>(exp2, 0xce40, [0x0000, 0x0000, 0x0000, 0x0001, 0x0001]);
+        // 2^-26
+        check::<F64>(
+            expm1,
+            0x3e50000000000000,
+            [
+                0x3e50000002000000,
+                0x3e50000002000000,
+                0x3e50000002000000,
+                0x3e50000002000001,
+                0x3e50000002000000,
+            ],
+        );
+        check::<F32>(
+            expm1,
+            0x3f000000,
+            [0x3f261299, 0x3f261298, 0x3f261298, 0x3f261299, 0x3f261299],
+        );
+        check::<F32>(
+            expm1,
+            0xc2c80000,
+            [0xbf800000, 0xbf7fffff, 0xbf800000, 0xbf7fffff, 0xbf800000],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        let (ret, flags) = with_flags(|| exp2(&F32::from_bits(0xc3150000), rnd));
+        assert_eq!(ret.to_bits(), 0x00000001);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| exp2(&F64::from_bits(0x4090000000000000), rnd));
+        assert!(ret.is_infinity() && flags.is_overflow());
+        assert_eq!(expm1(&F16::from_bits(0x8000), rnd).to_bits(), 0x8000);
+        assert_eq!(expm1(&F16::from_bits(0xfc00), rnd).to_bits(), 0xbc00);
+    }
+
+    #[test]
+    fn math_log() {
+        check::<F64>(
+            ln,
+            0x4000000000000000,
+            [
+                0x3fe62e42fefa39ef,
+                0x3fe62e42fefa39ef,
+                0x3fe62e42fefa39ef,
+                0x3fe62e42fefa39f0,
+                0x3fe62e42fefa39ef,
+            ],
+        );
+        // 1 + 2^-23
+        check::<F32>(
+            ln,
+            0x3f800001,
+            [0x33ffffff, 0x33ffffff, 0x33ffffff, 0x34000000, 0x33ffffff],
+        );
+        check::<F64>(log10, 0x4024000000000000, [0x3ff0000000000000; 5]);
+        check::<F16>(log2, 0x4a00, [0x432c, 0x432b, 0x432b, 0x432c, 0x432c]);
+
+        // 2^-26
+        check::<F64>(
+            ln_1p,
+            0x3e50000000000000,
+            [
+                0x3e4ffffffc000001,
+                0x3e4ffffffc000000,
+                0x3e4ffffffc000000,
+                0x3e4ffffffc000001,
+                0x3e4ffffffc000001,
+            ],
+        );
+        check::<F32>(
+            ln_1p,
+            0xbf000000,
+            [0xbf317218, 0xbf317217, 0xbf317218, 0xbf317217, 0xbf317218],
+        );
+        check::<F16>(ln_1p, 0x3c00, [0x398c, 0x398b, 0x398b, 0x398c, 0x398c]);
+        let (ret, flags) =
+            with_flags(|| ln_1p(&F32::from_bits(0xbf800000), RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0xff800000);
+        assert!(flags.is_infinite());
+
+        let (ret, flags) =
+            with_flags(|| log2(&F32::from_bits(0x00000001), RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0xc3150000);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| {
+            log10(
+                &F64::from_bits(0x4415af1d78b58c40),
+                RoundingMode::TiesToEven,
+            )
+        });
+        assert_eq!(ret.to_bits(), 0x4034000000000000);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| {
+            ln(
+                &F64::from_bits(0x3ff0000000000000),
+                RoundingMode::TowardNegative,
+            )
+        });
+        assert_eq!(ret.to_bits(), 0);
+        assert_eq!(flags.to_bits(), 0);
+
+        let (ret, flags) = with_flags(|| ln(&F32::from_bits(0x80000000), RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0xff800000);
+        assert!(flags.is_infinite());
+        let (ret, flags) =
+            with_flags(|| log10(&F32::from_bits(0xbf800000), RoundingMode::TiesToEven));
+        assert!(ret.is_nan() && flags.is_invalid());
+    }
+
+    #[test]
+    fn math_trig() {
+        check::<F64>(
+            sin,
+            0x3ff0000000000000,
+            [
+                0x3feaed548f090cee,
+                0x3feaed548f090cee,
+                0x3feaed548f090cee,
+                0x3feaed548f090cef,
+                0x3feaed548f090cee,
+            ],
+        );
+        // pi rounded to F32 is just above pi
+        check::<F32>(
+            cos,
+            0x40490fdb,
+            [0xbf800000, 0xbf7fffff, 0xbf800000, 0xbf7fffff, 0xbf800000],
+        );
+        check::<F32>(
+            tan,
+            0x3fc90fdb,
+            [0xcbae8a4a, 0xcbae8a4a, 0xcbae8a4b, 0xcbae8a4a, 0xcbae8a4a],
+        );
+        check::<F16>(tan, 0x3e48, [0x6809, 0x6809, 0x6809, 0x680a, 0x6809]);
+        // the closest F64 to a multiple of pi / 2
+        check::<F64>(
+            cos,
+            0x7506ac5b262ca1ff,
+            [
+                0xbc214ae72e6ba22f,
+                0xbc214ae72e6ba22e,
+                0xbc214ae72e6ba22f,
+                0xbc214ae72e6ba22e,
+                0xbc214ae72e6ba22f,
+            ],
+        );
+        // 2^-27
+        check::<F64>(
+            sin,
+            0x3e40000000000000,
+            [
+                0x3e40000000000000,
+                0x3e3fffffffffffff,
+                0x3e3fffffffffffff,
+                0x3e40000000000000,
+                0x3e40000000000000,
+            ],
+        );
+
+        let (ret, flags) =
+            with_flags(|| sincos(&F32::from_bits(0x80000000), RoundingMode::TiesToEven));
+        assert_eq!((ret.0.to_bits(), ret.1.to_bits()), (0x80000000, 0x3f800000));
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| sin(&F64::infinity(), RoundingMode::TiesToEven));
+        assert!(ret.is_nan() && flags.is_invalid());
+        let (ret, flags) = with_flags(|| sin(&F16::from_bits(0x0001), RoundingMode::TowardZero));
+        assert_eq!(ret.to_bits(), 0);
+        assert!(flags.is_underflow() && flags.is_inexact());
+    }
+
+    #[test]
+    fn math_pow() {
+        let rnd = RoundingMode::TiesToEven;
+        let pow_f32 = |x: u32, y: u32| pow(&F32::from_bits(x), &F32::from_bits(y), rnd).to_bits();
+        check::<F64>(
+            |x, rnd| pow(x, &F64::from_bits(0x3fe0000000000000), rnd),
+            0x4000000000000000,
+            [
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcd,
+            ],
+        );
+        // (1 + 2^-52)^(2^52)
+        check::<F64>(
+            |x, rnd| pow(x, &F64::from_bits(0x4330000000000000), rnd),
+            0x3ff0000000000001,
+            [
+                0x4005bf0a8b145769,
+                0x4005bf0a8b145768,
+                0x4005bf0a8b145768,
+                0x4005bf0a8b145769,
+                0x4005bf0a8b145769,
+            ],
+        );
+        assert_eq!(pow_f32(0x40490fdb, 0xc0490fdb), 0x3ce0abd6);
+
+        // 9^0.5 and 9^1.5 are exact
+        let (ret, flags) = with_flags(|| pow_f32(0x41100000, 0x3f000000));
+        assert_eq!((ret, flags.to_bits()), (0x40400000, 0));
+        let (ret, flags) = with_flags(|| pow_f32(0x41100000, 0x3fc00000));
+        assert_eq!((ret, flags.to_bits()), (0x41d80000, 0));
+        // (-2)^-3
+        assert_eq!(pow_f32(0xc0000000, 0xc0400000), 0xbe000000);
+
+        assert_eq!(pow_f32(0x7fc00000, 0x80000000), 0x3f800000);
+        assert_eq!(pow_f32(0x3f800000, 0x7fc00000), 0x3f800000);
+        assert_eq!(pow_f32(0xbf800000, 0xff800000), 0x3f800000);
+        assert_eq!(pow_f32(0x3f000000, 0xff800000), 0x7f800000);
+        assert_eq!(pow_f32(0xff800000, 0x40400000), 0xff800000);
+        let (ret, flags) = with_flags(|| pow_f32(0x80000000, 0xbf800000));
+        assert_eq!(ret, 0xff800000);
+        assert!(flags.is_infinite());
+        let (ret, flags) = with_flags(|| pow_f32(0xc0000000, 0x3f000000));
+        assert!(F32::from_bits(ret).is_nan() && flags.is_invalid());
+    }
+
+    #[test]
+    fn math_powi() {
+        check::<F64>(
+            |x, rnd| powi(x, 100, rnd),
+            0x3ff199999999999a,
+            [
+                0x40caea4e6126bb95,
+                0x40caea4e6126bb95,
+                0x40caea4e6126bb95,
+                0x40caea4e6126bb96,
+                0x40caea4e6126bb95,
+            ],
+        );
+        check::<F32>(
+            |x, rnd| powi(x, -3, rnd),
+            0xc0400000,
+            [0xbd17b426, 0xbd17b425, 0xbd17b426, 0xbd17b425, 0xbd17b426],
+        );
+        check::<F16>(
+            |x, rnd| powi(x, 16, rnd),
+            0x4000,
+            [0x7c00, 0x7bff, 0x7bff, 0x7c00, 0x7c00],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        let (ret, flags) = with_flags(|| powi(&F32::from_bits(0x3f800001), i32::MAX, rnd));
+        assert!(ret.is_infinity() && flags.is_overflow());
+        let (ret, flags) = with_flags(|| powi(&F64::from_bits(0xc008000000000000), 5, rnd));
+        assert_eq!(ret.to_bits(), 0xc06e600000000000);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(powi(&F32::quiet_nan(), 0, rnd).to_bits(), 0x3f800000);
+        let (ret, flags) = with_flags(|| powi(&F16::from_bits(0x8000), -1, rnd));
+        assert_eq!(ret.to_bits(), 0xfc00);
+        assert!(flags.is_infinite());
+        assert_eq!(powi(&F16::from_bits(0xfc00), -2, rnd).to_bits(), 0);
+    }
+
+    #[test]
+    fn math_cbrt() {
+        check::<F64>(
+            cbrt,
+            0x4000000000000000,
+            [
+                0x3ff428a2f98d728b,
+                0x3ff428a2f98d728a,
+                0x3ff428a2f98d728a,
+                0x3ff428a2f98d728b,
+                0x3ff428a2f98d728b,
+            ],
+        );
+        check::<F32>(
+            cbrt,
+            0x3dcccccd,
+            [0x3eeda63c, 0x3eeda63b, 0x3eeda63b, 0x3eeda63c, 0x3eeda63c],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        let (ret, flags) = with_flags(|| cbrt(&F32::from_bits(0xc1d80000), rnd));
+        assert_eq!(ret.to_bits(), 0xc0400000);
+        assert_eq!(flags.to_bits(), 0);
+        // 2^-24
+        let (ret, flags) = with_flags(|| cbrt(&F16::from_bits(0x0001), rnd));
+        assert_eq!(ret.to_bits(), 0x1c00);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(
+            cbrt(&F64::from_bits(0x8000000000000000), rnd).to_bits(),
+            0x8000000000000000
+        );
+        assert_eq!(
+            cbrt(&F64::from_bits(0xfff0000000000000), rnd).to_bits(),
+            0xfff0000000000000
+        );
+    }
+
+    #[test]
+    fn math_rsqrt() {
+        check::<F64>(
+            rsqrt,
+            0x4000000000000000,
+            [
+                0x3fe6a09e667f3bcd,
+                0x3fe6a09e667f3bcc,
+                0x3fe6a09e667f3bcc,
+                0x3fe6a09e667f3bcd,
+                0x3fe6a09e667f3bcd,
+            ],
+        );
+        check::<F32>(
+            rsqrt,
+            0x40400000,
+            [0x3f13cd3a, 0x3f13cd3a, 0x3f13cd3a, 0x3f13cd3b, 0x3f13cd3a],
+        );
+        check::<F32>(
+            recip,
+            0x40400000,
+            [0x3eaaaaab, 0x3eaaaaaa, 0x3eaaaaaa, 0x3eaaaaab, 0x3eaaaaab],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        // 1 / sqrt(1 + 2^-10) rounds down, but sqrt rounds to 1 first
+        let a = F16::from_bits(0x3c01);
+        assert_eq!(rsqrt(&a, rnd).to_bits(), 0x3bff);
+        assert_eq!(recip(&a.sqrt(rnd), rnd).to_bits(), 0x3c00);
+
+        let (ret, flags) = with_flags(|| rsqrt(&F32::from_bits(0x3e800000), rnd));
+        assert_eq!(ret.to_bits(), 0x40000000);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| rsqrt(&F32::from_bits(0x80000000), rnd));
+        assert_eq!(ret.to_bits(), 0xff800000);
+        assert!(flags.is_infinite());
+        let (ret, flags) = with_flags(|| rsqrt(&F32::from_bits(0xbf800000), rnd));
+        assert!(ret.is_nan() && flags.is_invalid());
+        assert_eq!(rsqrt(&F32::infinity(), rnd).to_bits(), 0);
+    }
+
+    #[test]
+    fn math_hypot() {
+        check2::<F64>(
+            hypot,
+            0x3ff0000000000000,
+            0x3ff0000000000000,
+            [
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcc,
+                0x3ff6a09e667f3bcd,
+                0x3ff6a09e667f3bcd,
+            ],
+        );
+        // beyond the range of the squares
+        check2::<F32>(
+            hypot,
+            0x7f000000,
+            0x7f000000,
+            [0x7f3504f3, 0x7f3504f3, 0x7f3504f3, 0x7f3504f4, 0x7f3504f3],
+        );
+        check2::<F16>(
+            hypot,
+            0x0001,
+            0x0001,
+            [0x0001, 0x0001, 0x0001, 0x0002, 0x0001],
+        );
+        // 1 and 2^-54
+        check2::<F64>(
+            hypot,
+            0x3c90000000000000,
+            0x3ff0000000000000,
+            [
+                0x3ff0000000000000,
+                0x3ff0000000000000,
+                0x3ff0000000000000,
+                0x3ff0000000000001,
+                0x3ff0000000000000,
+            ],
+        );
+        // 1233, 1644 and 2055, which is a midpoint
+        check2::<F16>(
+            hypot,
+            0x64d1,
+            0x666c,
+            [0x6804, 0x6803, 0x6803, 0x6804, 0x6804],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        let (ret, flags) = with_flags(|| {
+            hypot(
+                &F32::from_bits(0xc0400000),
+                &F32::from_bits(0x40800000),
+                rnd,
+            )
+        });
+        assert_eq!(ret.to_bits(), 0x40a00000);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| hypot(&F64::from_bits(3), &F64::from_bits(4), rnd));
+        assert_eq!(ret.to_bits(), 5);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| {
+            hypot(
+                &F32::from_bits(0x7f7fffff),
+                &F32::from_bits(0x7f7fffff),
+                rnd,
+            )
+        });
+        assert!(ret.is_infinity() && flags.is_overflow());
+        let nan = F32::from_bits(0x7fc00000);
+        assert_eq!(
+            hypot(&nan, &F32::from_bits(0xff800000), rnd).to_bits(),
+            0x7f800000
+        );
+        assert!(hypot(&nan, &F32::from_bits(0x3f800000), rnd).is_nan());
+        assert_eq!(
+            hypot(
+                &F32::from_bits(0x80000000),
+                &F32::from_bits(0xc0000000),
+                rnd
+            )
+            .to_bits(),
+            0x40000000
+        );
+    }
+
+    #[test]
+    fn math_atan2() {
+        // 3 pi / 4
+        check2::<F64>(
+            atan2,
+            0x3ff0000000000000,
+            0xbff0000000000000,
+            [
+                0x4002d97c7f3321d2,
+                0x4002d97c7f3321d2,
+                0x4002d97c7f3321d2,
+                0x4002d97c7f3321d3,
+                0x4002d97c7f3321d2,
+            ],
+        );
+        check2::<F32>(
+            atan2,
+            0x3f800000,
+            0x40000000,
+            [0x3eed6338, 0x3eed6338, 0x3eed6338, 0x3eed6339, 0x3eed6338],
+        );
+        check2::<F64>(
+            atan2,
+            0xc000000000000000,
+            0x3ff0000000000000,
+            [
+                0xbff1b6e192ebbe44,
+                0xbff1b6e192ebbe44,
+                0xbff1b6e192ebbe45,
+                0xbff1b6e192ebbe44,
+                0xbff1b6e192ebbe44,
+            ],
+        );
+        // pi / 2 from the largest and the smallest values
+        check2::<F32>(
+            atan2,
+            0x7f7fffff,
+            0x00000001,
+            [0x3fc90fdb, 0x3fc90fda, 0x3fc90fda, 0x3fc90fdb, 0x3fc90fdb],
+        );
+        check2::<F16>(
+            atan2,
+            0x8001,
+            0x7bff,
+            [0x8000, 0x8000, 0x8001, 0x8000, 0x8000],
+        );
+
+        let rnd = RoundingMode::TiesToEven;
+        let f = |y: u32, x: u32| atan2(&F32::from_bits(y), &F32::from_bits(x), rnd).to_bits();
+        assert_eq!(f(0x80000000, 0x00000000), 0x80000000);
+        assert_eq!(f(0x00000000, 0x80000000), 0x40490fdb);
+        assert_eq!(f(0x80000000, 0xbf800000), 0xc0490fdb);
+        assert_eq!(f(0xbf800000, 0x00000000), 0xbfc90fdb);
+        assert_eq!(f(0x7f800000, 0xff800000), 0x4016cbe4);
+        assert_eq!(f(0xff800000, 0x7f800000), 0xbf490fdb);
+        assert_eq!(f(0x3f800000, 0xff800000), 0x40490fdb);
+        assert_eq!(f(0xbf800000, 0x7f800000), 0x80000000);
+        let (ret, flags) = with_flags(|| {
+            atan2(
+                &F32::from_bits(0x7fa00000),
+                &F32::from_bits(0x3f800000),
+                rnd,
+            )
+        });
+        assert!(ret.is_nan() && flags.is_invalid());
+    }
+
+    #[cfg(feature = "f128")]
+    #[test]
+    fn math_f128() {
+        let a = F128::from_bits(0x3fff0000000000000000000000000000);
+        assert_eq!(
+            exp(&a, RoundingMode::TiesToEven).to_bits(),
+            0x40005bf0a8b1457695355fb8ac404e7a
+        );
+        let a = F128::from_bits(0x40000000000000000000000000000000);
+        assert_eq!(
+            ln(&a, RoundingMode::TiesToEven).to_bits(),
+            0x3ffe62e42fefa39ef35793c7673007e6
+        );
+        assert_eq!(
+            sin(&a, RoundingMode::TowardZero).to_bits(),
+            0x3ffed18f6ead1b445dfab848188009c9
+        );
+        assert_eq!(
+            cbrt(&a, RoundingMode::TiesToEven).to_bits(),
+            0x3fff428a2f98d728ae223ddab715be25
+        );
+        let b = F128::from_bits(0x3fff0000000000000000000000000000);
+        assert_eq!(
+            hypot(&a, &b, RoundingMode::TiesToEven).to_bits(),
+            0x40001e3779b97f4a7c15f39cc0605cee
+        );
+        assert_eq!(
+            atan2(&b, &a, RoundingMode::TiesToEven).to_bits(),
+            0x3ffddac670561bb4f68adfc88bd97875
+        );
+    }
+}
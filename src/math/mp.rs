@@ -0,0 +1,700 @@
+//! Multi-precision floats for the elementary functions
+//!
+//! Results are computed with a few more bits than the target format, and rounded once if
+//! the error bound shows which way they round (Ziv's strategy); otherwise they are computed
+//! again with twice the precision.
+
+use crate::{round_pack, unpack, with_flags, RoundingMode, SoftFloat};
+use std::cmp::Ordering;
+
+/// `(-1)^neg * man * 2^exp`, where `man` holds little-endian limbs without leading zeros
+#[derive(Clone, Debug)]
+pub(crate) struct Float {
+    pub neg: bool,
+    pub exp: i64,
+    man: Vec<u64>,
+}
+
+fn trim(mut x: Vec<u64>) -> Vec<u64> {
+    while x.last() == Some(&0) {
+        x.pop();
+    }
+    x
+}
+
+fn bits(x: &[u64]) -> u64 {
+    match x.last() {
+        Some(top) => x.len() as u64 * 64 - top.leading_zeros() as u64,
+        None => 0,
+    }
+}
+
+fn shl(x: &[u64], n: u64) -> Vec<u64> {
+    if x.is_empty() {
+        return Vec::new();
+    }
+    let (limbs, n) = ((n / 64) as usize, (n % 64) as u32);
+    let mut ret = vec![0; limbs];
+    let mut carry = 0;
+    for &d in x {
+        ret.push(d << n | carry);
+        carry = if n == 0 { 0 } else { d >> (64 - n) };
+    }
+    ret.push(carry);
+    trim(ret)
+}
+
+/// Returns `x >> n` and whether non-zero bits were shifted out.
+fn shr(x: &[u64], n: u64) -> (Vec<u64>, bool) {
+    let (limbs, n) = ((n / 64) as usize, (n % 64) as u32);
+    if limbs >= x.len() {
+        return (Vec::new(), !x.is_empty());
+    }
+    let sticky = x[..limbs].iter().any(|d| *d != 0) || x[limbs] & ((1 << n) - 1) != 0;
+    let ret = (limbs..x.len())
+        .map(|i| {
+            let hi = match x.get(i + 1) {
+                Some(d) if n != 0 => d << (64 - n),
+                _ => 0,
+            };
+            x[i] >> n | hi
+        })
+        .collect();
+    (trim(ret), sticky)
+}
+
+fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut ret = Vec::with_capacity(a.len() + 1);
+    let mut carry = false;
+    for (i, &x) in a.iter().enumerate() {
+        let (s, c1) = x.overflowing_add(*b.get(i).unwrap_or(&0));
+        let (s, c2) = s.overflowing_add(carry as u64);
+        ret.push(s);
+        carry = c1 || c2;
+    }
+    ret.push(carry as u64);
+    trim(ret)
+}
+
+/// `a - b` for `a >= b`
+fn sub(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut ret = Vec::with_capacity(a.len());
+    let mut borrow = false;
+    for (i, &x) in a.iter().enumerate() {
+        let (s, b1) = x.overflowing_sub(*b.get(i).unwrap_or(&0));
+        let (s, b2) = s.overflowing_sub(borrow as u64);
+        ret.push(s);
+        borrow = b1 || b2;
+    }
+    trim(ret)
+}
+
+fn mul(a: &[u64], b: &[u64]) -> Vec<u64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut ret = vec![0u64; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u128 * y as u128 + ret[i + j] as u128 + carry;
+            ret[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        ret[i + b.len()] = carry as u64;
+    }
+    trim(ret)
+}
+
+fn div_small(a: &[u64], d: u64) -> Vec<u64> {
+    let mut ret = vec![0; a.len()];
+    let mut rem = 0u128;
+    for i in (0..a.len()).rev() {
+        let t = rem << 64 | a[i] as u128;
+        ret[i] = (t / d as u128) as u64;
+        rem = t % d as u128;
+    }
+    trim(ret)
+}
+
+impl Float {
+    pub fn one() -> Self {
+        Self::from_u128(false, 0, 1)
+    }
+
+    pub fn from_u128(neg: bool, exp: i64, sig: u128) -> Self {
+        Self {
+            neg,
+            exp,
+            man: trim(vec![sig as u64, (sig >> 64) as u64]),
+        }
+    }
+
+    /// `x` exactly, which must be finite
+    pub fn from_soft<T: SoftFloat>(x: &T) -> Self {
+        let (neg, exp, sig) = unpack(x);
+        Self::from_u128(neg, exp as i64, sig)
+    }
+
+    /// `x` exactly, which must be finite
+    pub fn from_f64(x: f64) -> Self {
+        let bits = x.to_bits();
+        let field = (bits >> 52 & 0x7ff) as i64;
+        let mut sig = bits & ((1 << 52) - 1);
+        if field != 0 {
+            sig |= 1 << 52;
+        }
+        Self::from_u128(x < 0.0, field.max(1) - 1075, sig as u128)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.man.is_empty()
+    }
+
+    /// `e` such that `2^(e - 1) <= |self| < 2^e`
+    pub fn top(&self) -> i64 {
+        self.exp + bits(&self.man) as i64
+    }
+
+    /// `self` truncated to `prec` bits
+    pub fn trunc(mut self, prec: u32) -> Self {
+        let excess = bits(&self.man).saturating_sub(prec as u64);
+        if excess > 0 {
+            self.man = shr(&self.man, excess).0;
+            self.exp += excess as i64;
+        }
+        self
+    }
+
+    /// `self` rounded to the nearest integer, ties away from zero, and that integer modulo 4
+    pub fn round_int(&self) -> (Self, u32) {
+        let (man, exp) = if self.exp >= 0 {
+            (self.man.clone(), self.exp)
+        } else {
+            // keep the bit below the integer bits, add it and drop it
+            let (half, _) = shr(&self.man, (-self.exp - 1) as u64);
+            (shr(&add(&half, &[1]), 1).0, 0)
+        };
+        let low = match (man.first(), exp) {
+            (Some(d), 0) => *d as u32 & 3,
+            (Some(d), 1) => (*d as u32) << 1 & 3,
+            _ => 0,
+        };
+        let low = if self.neg { (4 - low) & 3 } else { low };
+        (
+            Self {
+                neg: self.neg,
+                exp,
+                man,
+            },
+            low,
+        )
+    }
+
+    pub fn neg(mut self) -> Self {
+        self.neg = !self.neg;
+        self
+    }
+
+    pub fn abs(mut self) -> Self {
+        self.neg = false;
+        self
+    }
+
+    /// `self * 2^n`
+    pub fn shl(mut self, n: i64) -> Self {
+        self.exp += n;
+        self
+    }
+
+    /// `self` and `x` added exactly if `prec` is `None`, or with a result of `prec` bits
+    fn sum(&self, x: &Self, prec: Option<u32>) -> Self {
+        if x.is_zero() {
+            return self.clone();
+        }
+        if self.is_zero() {
+            return x.clone();
+        }
+        let (mut a, mut b) = (self.clone(), x.clone());
+        if let Some(prec) = prec {
+            // bits far below the result do not change it beyond its precision
+            let low = a.top().max(b.top()) - prec as i64 - 64;
+            for y in [&mut a, &mut b] {
+                if y.exp < low {
+                    y.man = shr(&y.man, (low - y.exp) as u64).0;
+                    y.exp = low;
+                }
+            }
+        }
+        let exp = a.exp.min(b.exp);
+        let am = shl(&a.man, (a.exp - exp) as u64);
+        let bm = shl(&b.man, (b.exp - exp) as u64);
+        let (neg, man) = if a.neg == b.neg {
+            (a.neg, add(&am, &bm))
+        } else {
+            match cmp(&am, &bm) {
+                Ordering::Less => (b.neg, sub(&bm, &am)),
+                _ => (a.neg, sub(&am, &bm)),
+            }
+        };
+        let ret = Self { neg, exp, man };
+        match prec {
+            Some(prec) => ret.trunc(prec),
+            None => ret,
+        }
+    }
+
+    pub fn add(&self, x: &Self, prec: u32) -> Self {
+        self.sum(x, Some(prec))
+    }
+
+    pub fn sub(&self, x: &Self, prec: u32) -> Self {
+        self.sum(&x.clone().neg(), Some(prec))
+    }
+
+    pub fn mul(&self, x: &Self, prec: u32) -> Self {
+        Self {
+            neg: self.neg != x.neg,
+            exp: self.exp + x.exp,
+            man: mul(&self.man, &x.man),
+        }
+        .trunc(prec)
+    }
+
+    pub fn mul_int(&self, x: u64, prec: u32) -> Self {
+        self.mul(&Self::from_u128(false, 0, x as u128), prec)
+    }
+
+    pub fn div_int(&self, x: u64, prec: u32) -> Self {
+        let extra = (prec as u64 + 64).saturating_sub(bits(&self.man));
+        Self {
+            neg: self.neg,
+            exp: self.exp - extra as i64,
+            man: div_small(&shl(&self.man, extra), x),
+        }
+        .trunc(prec)
+    }
+
+    /// `1 / self` by Newton's iteration
+    pub fn recip(&self, prec: u32) -> Self {
+        let (m, e) = self.approx();
+        let mut y = Self::from_f64(1.0 / m).shl(-e);
+        let mut cur = 48;
+        loop {
+            cur = (cur * 2).min(prec + 16);
+            let e = Self::one().sub(&self.mul(&y, cur), cur);
+            y = y.add(&y.mul(&e, cur), cur);
+            if cur == prec + 16 && (e.is_zero() || e.top() < -(prec as i64) - 8) {
+                return y.trunc(prec);
+            }
+        }
+    }
+
+    pub fn div(&self, x: &Self, prec: u32) -> Self {
+        self.mul(&x.recip(prec + 8), prec)
+    }
+
+    /// `self^n` by squaring, with a relative error below `2 n 2^-prec`
+    pub fn pow_int(&self, mut n: u64, prec: u32) -> Self {
+        let mut ret = Self::one();
+        let mut base = self.clone();
+        loop {
+            if n & 1 != 0 {
+                ret = ret.mul(&base, prec);
+            }
+            n >>= 1;
+            if n == 0 {
+                return ret;
+            }
+            base = base.mul(&base, prec);
+        }
+    }
+
+    /// `(m, e)` such that `self` is about `m * 2^e`, with `1 <= |m| < 2`
+    pub fn approx(&self) -> (f64, i64) {
+        let n = bits(&self.man);
+        let top = shr(&self.man, n.saturating_sub(64)).0;
+        let m = top[0] as f64 / 2f64.powi(n.min(64) as i32 - 1);
+        (if self.neg { -m } else { m }, self.top() - 1)
+    }
+
+    /// `self` with a magnitude changed by much less than an ulp of any format, up if `up`
+    pub fn nudge(&self, up: bool) -> Self {
+        let one = [1];
+        let man = shl(&self.man, 140);
+        Self {
+            neg: self.neg,
+            exp: self.exp - 140,
+            man: if up { add(&man, &one) } else { sub(&man, &one) },
+        }
+    }
+
+    /// `self` rounded to `T`, raising exception flags
+    pub fn round<T: SoftFloat>(&self, rnd: RoundingMode) -> T {
+        // 126 bits are enough for round_pack, if the discarded ones are or'ed into the lowest
+        let shift = bits(&self.man).saturating_sub(126);
+        let (sig, sticky) = shr(&self.man, shift);
+        let sig = sig
+            .iter()
+            .rev()
+            .fold(0u128, |acc, x| acc << 64 | *x as u128)
+            | sticky as u128;
+        // exponents beyond the range of every format underflow or overflow all the same
+        let exp = (self.exp + shift as i64).clamp(-(1 << 20), 1 << 20);
+        round_pack(self.neg, exp as i32, sig, rnd)
+    }
+}
+
+/// precision of `T` in bits
+pub(crate) fn precision<T: SoftFloat>() -> u32 {
+    T::MANTISSA_DIGITS
+}
+
+/// Rounds the result of `f`, which is never exact or a midpoint of `T`.
+///
+/// `f(prec)` must return the result with a relative error below `2^-prec`.
+pub(crate) fn ziv<T: SoftFloat>(rnd: RoundingMode, f: impl Fn(u32) -> Float) -> T {
+    let mut prec = precision::<T>() + 24;
+    loop {
+        let y = f(prec);
+        let err = Float::one().shl(y.top() - prec as i64);
+        let (lo, lo_flags) = with_flags(|| y.sum(&err.clone().neg(), None).round::<T>(rnd));
+        let (hi, hi_flags) = with_flags(|| y.sum(&err, None).round::<T>(rnd));
+        if lo.to_bits() == hi.to_bits() && lo_flags.to_bits() == hi_flags.to_bits() {
+            // the result is inexact, even if `y` happens not to be
+            return y.nudge(true).round(rnd);
+        }
+        prec *= 2;
+    }
+}
+
+fn isqrt(x: u32) -> u32 {
+    (x as f64).sqrt() as u32
+}
+
+/// `atanh(1 / n)`
+fn atanh_inv(n: u64, prec: u32) -> Float {
+    let wp = prec + 32;
+    let mut pow = Float::one().div_int(n, wp);
+    let mut sum = pow.clone();
+    for k in 1.. {
+        pow = pow.div_int(n * n, wp);
+        let term = pow.div_int(2 * k + 1, wp);
+        if term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.trunc(prec)
+}
+
+/// `atan(1 / n)`
+fn atan_inv(n: u64, prec: u32) -> Float {
+    let wp = prec + 32;
+    let mut pow = Float::one().div_int(n, wp);
+    let mut sum = pow.clone();
+    for k in 1.. {
+        pow = pow.div_int(n * n, wp).neg();
+        let term = pow.div_int(2 * k + 1, wp);
+        if term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.trunc(prec)
+}
+
+/// `ln(2)`
+pub(crate) fn ln2(prec: u32) -> Float {
+    atanh_inv(3, prec + 2).shl(1).trunc(prec)
+}
+
+/// `ln(10)`, which is `3 ln(2) + 2 atanh(1/9)`
+pub(crate) fn ln10(prec: u32) -> Float {
+    let wp = prec + 8;
+    ln2(wp)
+        .mul_int(3, wp)
+        .add(&atanh_inv(9, wp).shl(1), wp)
+        .trunc(prec)
+}
+
+/// `pi`, which is `16 atan(1/5) - 4 atan(1/239)`
+pub(crate) fn pi(prec: u32) -> Float {
+    let wp = prec + 8;
+    atan_inv(5, wp)
+        .shl(4)
+        .sub(&atan_inv(239, wp).shl(2), wp)
+        .trunc(prec)
+}
+
+/// `exp(x)` for `|x| < 2^24`
+pub(crate) fn exp(x: &Float, prec: u32) -> Float {
+    let (m, e) = x.approx();
+    let k = (m * 2f64.powi(e as i32) / std::f64::consts::LN_2).round() as i64;
+    // exp(x) = exp(r / 2^s)^(2^s) * 2^k, where squaring doubles the relative error
+    let s = isqrt(prec).max(4);
+    let wp = prec + 2 * s + 40;
+    let kln2 = ln2(wp + 32).mul_int(k.unsigned_abs(), wp + 32);
+    let kln2 = if k < 0 { kln2.neg() } else { kln2 };
+    let r = x.sub(&kln2, wp).shl(-(s as i64));
+
+    let mut sum = Float::one();
+    let mut term = Float::one();
+    for n in 1.. {
+        term = term.mul(&r, wp).div_int(n, wp);
+        if term.is_zero() || term.top() < -(wp as i64) {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    for _ in 0..s {
+        sum = sum.mul(&sum, wp);
+    }
+    sum.shl(k).trunc(prec)
+}
+
+/// `atanh(t)` for `|t| <= 1/2`
+fn atanh(t: &Float, prec: u32) -> Float {
+    if t.is_zero() {
+        return t.clone();
+    }
+    let wp = prec + 8;
+    let t2 = t.mul(t, wp);
+    let mut pow = t.clone();
+    let mut sum = t.clone();
+    for k in 1.. {
+        pow = pow.mul(&t2, wp);
+        let term = pow.div_int(2 * k + 1, wp);
+        if term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.trunc(prec)
+}
+
+/// `ln(x)` for a positive `x`
+pub(crate) fn ln(x: &Float, prec: u32) -> Float {
+    // x = y * 2^e with sqrt(1/2) <= y < sqrt(2), so that ln(y) and e ln(2) never cancel
+    let (m, e) = x.approx();
+    let e = if m > std::f64::consts::SQRT_2 {
+        e + 1
+    } else {
+        e
+    };
+    let wp = prec + 40;
+    let y = x.clone().shl(-e);
+
+    // ln(y) = 2 atanh((y - 1) / (y + 1))
+    let t = y.sub(&Float::one(), wp).div(&y.add(&Float::one(), wp), wp);
+    let mut ret = atanh(&t, wp).shl(1);
+    if e != 0 {
+        let eln2 = ln2(wp + 32).mul_int(e.unsigned_abs(), wp);
+        ret = ret.add(&if e < 0 { eln2.neg() } else { eln2 }, wp);
+    }
+    ret.trunc(prec)
+}
+
+/// `exp(x) - 1` for `|x| < 2^24`
+pub(crate) fn expm1(x: &Float, prec: u32) -> Float {
+    let wp = prec + 16;
+    if x.top() >= 0 {
+        // |x| >= 1/2, so that the subtraction loses at most 2 bits
+        return exp(x, wp).sub(&Float::one(), wp).trunc(prec);
+    }
+    let mut term = x.clone();
+    let mut sum = x.clone();
+    for n in 2.. {
+        term = term.mul(x, wp).div_int(n, wp);
+        if term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.trunc(prec)
+}
+
+/// `ln(1 + x)` for `x > -1`
+pub(crate) fn ln_1p(x: &Float, prec: u32) -> Float {
+    let wp = prec + 16;
+    if x.top() >= 0 {
+        // |x| >= 1/2, so that ln(1 + x) is not close to 0
+        return ln(&x.add(&Float::one(), u32::MAX), prec);
+    }
+    // ln(1 + x) = 2 atanh(x / (2 + x))
+    let t = x.div(&x.add(&Float::one().shl(1), wp), wp);
+    atanh(&t, wp).shl(1).trunc(prec)
+}
+
+/// `1 / sqrt(x)` by Newton's iteration
+pub(crate) fn rsqrt(x: &Float, prec: u32) -> Float {
+    let (m, e) = x.approx();
+    let k = e.div_euclid(2);
+    let mut y = Float::from_f64(1.0 / (m * 2f64.powi((e - 2 * k) as i32)).sqrt()).shl(-k);
+    let mut cur = 48;
+    loop {
+        cur = (cur * 2).min(prec + 16);
+        // y += y (1 - x y^2) / 2
+        let r = Float::one().sub(&x.mul(&y.mul(&y, cur), cur), cur);
+        let d = y.mul(&r, cur).shl(-1);
+        y = y.add(&d, cur);
+        if cur == prec + 16 && (d.is_zero() || d.top() < y.top() - prec as i64 - 8) {
+            return y.trunc(prec);
+        }
+    }
+}
+
+/// `cbrt(x)` by Newton's iteration
+pub(crate) fn cbrt(x: &Float, prec: u32) -> Float {
+    let (m, e) = x.approx();
+    let k = e.div_euclid(3);
+    let mut y = Float::from_f64((m * 2f64.powi((e - 3 * k) as i32)).cbrt()).shl(k);
+    let mut cur = 48;
+    loop {
+        cur = (cur * 2).min(prec + 16);
+        // y += (x / y^2 - y) / 3
+        let d = x.div(&y.mul(&y, cur), cur).sub(&y, cur).div_int(3, cur);
+        y = y.add(&d, cur);
+        if cur == prec + 16 && (d.is_zero() || d.top() < y.top() - prec as i64 - 8) {
+            return y.trunc(prec);
+        }
+    }
+}
+
+/// Returns `r` and `k mod 4`, where `x = r + k pi / 2` and `|r| <= pi / 4`.
+///
+/// `r` is accurate to `prec` bits however close `x` is to a multiple of `pi / 2`.
+pub(crate) fn reduce(x: &Float, prec: u32) -> (Float, u32) {
+    let scale = x.top().max(0);
+    let mut wp = prec + scale as u32 + 64;
+    loop {
+        let half_pi = pi(wp).shl(-1);
+        let (k, quadrant) = x.div(&half_pi, scale as u32 + 64).round_int();
+        let r = x.sub(&k.mul(&half_pi, wp), wp);
+        // the error is about 2^(scale - wp), so cancellation costs the leading zeros of r
+        let lost = scale + 12 + prec as i64 - (r.top() + wp as i64);
+        if r.is_zero() || lost > 0 {
+            wp += lost.max(64) as u32;
+            continue;
+        }
+        return (r, quadrant);
+    }
+}
+
+/// `sin(r)`, or `cos(r)` if `cos`, for `|r| <= 1`
+pub(crate) fn sin_cos(r: &Float, cos: bool, prec: u32) -> Float {
+    let wp = prec + 32;
+    let r2 = r.mul(r, wp).neg();
+    let mut term = if cos { Float::one() } else { r.clone() };
+    let mut sum = term.clone();
+    let mut n = if cos { 0 } else { 1 };
+    loop {
+        term = term.mul(&r2, wp).div_int((n + 1) * (n + 2), wp);
+        n += 2;
+        if term.is_zero() || term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.trunc(prec)
+}
+
+/// `atan(x)`, by halving `x` until the series converges quickly
+pub(crate) fn atan(x: &Float, prec: u32) -> Float {
+    let wp = prec + 32;
+    // atan(x) = 2 atan(x / (1 + sqrt(1 + x^2)))
+    let mut t = x.clone();
+    let mut halvings = 0;
+    while !t.is_zero() && t.top() > -8 {
+        let u = Float::one().add(&t.mul(&t, wp), wp);
+        let root = u.mul(&rsqrt(&u, wp), wp);
+        t = t.div(&Float::one().add(&root, wp), wp);
+        halvings += 1;
+    }
+    let t2 = t.mul(&t, wp).neg();
+    let mut pow = t.clone();
+    let mut sum = t;
+    for k in 1.. {
+        pow = pow.mul(&t2, wp);
+        let term = pow.div_int(2 * k + 1, wp);
+        if term.is_zero() || term.top() < sum.top() - wp as i64 {
+            break;
+        }
+        sum = sum.add(&term, wp);
+    }
+    sum.shl(halvings).trunc(prec)
+}
+
+/// `atan2(y, x)` for nonzero `y` and `x`, reduced to `atan` of a ratio of at most 1
+pub(crate) fn atan2(y: &Float, x: &Float, prec: u32) -> Float {
+    let wp = prec + 8;
+    let steeper = y.clone().abs().sum(&x.clone().abs().neg(), None);
+    let ret = if steeper.is_zero() || steeper.neg {
+        // atan(y / x), plus pi with the sign of y on the left
+        let r = atan(&y.div(x, wp), wp);
+        if x.neg {
+            let pi = pi(wp);
+            r.add(&if y.neg { pi.neg() } else { pi }, wp)
+        } else {
+            r
+        }
+    } else {
+        // pi / 2 with the sign of y, minus atan(x / y)
+        let half_pi = pi(wp).shl(-1);
+        let half_pi = if y.neg { half_pi.neg() } else { half_pi };
+        half_pi.sub(&atan(&x.div(y, wp), wp), wp)
+    };
+    ret.trunc(prec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mp_constants() {
+        // the first 128 bits of each
+        let check = |x: Float, bits: u128, exp: i64| {
+            let x = x.trunc(128);
+            assert_eq!(x.exp, exp);
+            assert_eq!(x.man, [bits as u64, (bits >> 64) as u64]);
+        };
+        check(ln2(200), 0xb17217f7d1cf79abc9e3b39803f2f6af, -128);
+        check(pi(200), 0xc90fdaa22168c234c4c6628b80dc1cd1, -126);
+        check(ln10(200), 0x935d8dddaaa8ac16ea56d62b82d30a28, -126);
+        check(
+            atan(&Float::one(), 200).shl(2),
+            0xc90fdaa22168c234c4c6628b80dc1cd1,
+            -126,
+        );
+    }
+
+    #[test]
+    fn mp_ops() {
+        let a = Float::from_u128(false, -3, 7); // 0.875
+        let b = Float::from_u128(true, 0, 3);
+        assert_eq!(a.add(&b, 64).approx(), (-1.0625, 1));
+        assert_eq!(a.mul(&b, 64).approx(), (-1.3125, 1));
+        assert_eq!(b.pow_int(5, 64).approx(), (-1.8984375, 7));
+        let x = cbrt(&Float::from_u128(false, 0, 1 << 90), 200);
+        let y = rsqrt(&Float::from_u128(false, -3, 1), 200);
+        assert!(y.mul(&y, 200).sub(&Float::one().shl(3), 200).top() < -190);
+        let d = x.sub(&Float::one().shl(30), 200);
+        assert!(d.is_zero() || d.top() < -160);
+        assert_eq!(b.div_int(3, 64).approx(), (-1.0, 0));
+        let x = a.div(&b, 200).mul(&b, 200);
+        assert!(x.sub(&a, 200).top() < -190);
+        assert_eq!(Float::from_f64(-0.1).approx(), (-1.6, -4));
+        let (k, quadrant) = Float::from_f64(-2.5).round_int();
+        assert_eq!((k.approx(), quadrant), ((-1.5, 1), 1));
+        let (k, quadrant) = Float::from_f64(6.0).round_int();
+        assert_eq!((k.approx(), quadrant), ((1.5, 2), 2));
+    }
+}
@@ -0,0 +1,610 @@
+//! MIPS floating-point control and status
+//!
+//! [`Fpu`] holds the `FCSR` register, and runs the floating-point instructions the way MIPS
+//! does under it: rounding per `FCSR.RM`, the cause field set by every instruction, the
+//! sticky flags, the NaN encoding of `FCSR.NAN2008`, the arithmetic `abs` and `neg` of
+//! legacy mode, and flushing of denormal operands and tiny results under `FCSR.FS`. The
+//! exception flags of the thread are left as they were.
+//!
+//! An instruction traps when one of its causes is enabled, or on an unimplemented operation:
+//! it returns `None`, leaves the cause field set and the flags as they were, and the
+//! destination register must not be written. Implementations which leave denormal
+//! operands and tiny results to software, see [`Fpu::unimplemented_denormals`], raise an
+//! unimplemented operation for them when `FS` is clear. When `FS` is set, tiny results are
+//! flushed, unless the underflow or inexact exception is enabled, which raises an
+//! unimplemented operation instead.
+//!
+//! ```
+//! use softfloat_wrapper::mips::{Fpu, FCSR_FLAG_I, FCSR_FS};
+//! use softfloat_wrapper::{FloatArith, F32};
+//!
+//! let mut fpu = Fpu::new(FCSR_FS);
+//! let min = F32::from_bits(0x00800000);
+//! let half = F32::from_bits(0x3f000000);
+//! assert_eq!(fpu.mul(min, half).unwrap().to_bits(), 0);
+//! assert_ne!(fpu.fcsr & FCSR_FLAG_I, 0);
+//! ```
+
+use crate::{with_flags, FloatCompare, RoundingMode, SoftFloat};
+use num_traits::{NumCast, One, ToPrimitive, Zero};
+
+/// inexact bit of the flags field of `FCSR`
+pub const FCSR_FLAG_I: u32 = 1 << 2;
+/// underflow bit of the flags field of `FCSR`
+pub const FCSR_FLAG_U: u32 = 1 << 3;
+/// overflow bit of the flags field of `FCSR`
+pub const FCSR_FLAG_O: u32 = 1 << 4;
+/// division by zero bit of the flags field of `FCSR`
+pub const FCSR_FLAG_Z: u32 = 1 << 5;
+/// invalid operation bit of the flags field of `FCSR`
+pub const FCSR_FLAG_V: u32 = 1 << 6;
+/// inexact bit of the enables field of `FCSR`
+pub const FCSR_ENABLE_I: u32 = 1 << 7;
+/// underflow bit of the enables field of `FCSR`
+pub const FCSR_ENABLE_U: u32 = 1 << 8;
+/// overflow bit of the enables field of `FCSR`
+pub const FCSR_ENABLE_O: u32 = 1 << 9;
+/// division by zero bit of the enables field of `FCSR`
+pub const FCSR_ENABLE_Z: u32 = 1 << 10;
+/// invalid operation bit of the enables field of `FCSR`
+pub const FCSR_ENABLE_V: u32 = 1 << 11;
+/// inexact bit of the cause field of `FCSR`
+pub const FCSR_CAUSE_I: u32 = 1 << 12;
+/// underflow bit of the cause field of `FCSR`
+pub const FCSR_CAUSE_U: u32 = 1 << 13;
+/// overflow bit of the cause field of `FCSR`
+pub const FCSR_CAUSE_O: u32 = 1 << 14;
+/// division by zero bit of the cause field of `FCSR`
+pub const FCSR_CAUSE_Z: u32 = 1 << 15;
+/// invalid operation bit of the cause field of `FCSR`
+pub const FCSR_CAUSE_V: u32 = 1 << 16;
+/// unimplemented operation bit of the cause field of `FCSR`, which cannot be disabled
+pub const FCSR_CAUSE_E: u32 = 1 << 17;
+/// IEEE 754-2008 NaN encoding bit of `FCSR`
+pub const FCSR_NAN2008: u32 = 1 << 18;
+/// non-arithmetic `abs` and `neg` bit of `FCSR`
+pub const FCSR_ABS2008: u32 = 1 << 19;
+/// flush-to-zero bit of `FCSR`
+pub const FCSR_FS: u32 = 1 << 24;
+
+/// the flags field of `FCSR`
+const FLAGS: u32 = 0x1f << 2;
+/// the cause field of `FCSR`
+const CAUSES: u32 = 0x3f << 12;
+
+/// Returns the bit of `FCSR` of the floating-point condition code `cc`, from 0 to 7.
+///
+/// # Panics
+///
+/// Panics if `cc` is greater than 7.
+pub fn fcc_bit(cc: u32) -> u32 {
+    assert!(cc < 8, "invalid condition code");
+    if cc == 0 {
+        1 << 23
+    } else {
+        1 << (24 + cc)
+    }
+}
+
+/// signed zero with the sign of `x`
+fn zero_like<T: SoftFloat>(x: &T) -> T {
+    let mut ret = T::zero();
+    ret.set_sign(x.sign());
+    ret
+}
+
+/// the NaN `x` converted to `U` and quietened, keeping its sign and the top bits of its
+/// payload
+fn convert_nan<T: SoftFloat, U: SoftFloat>(x: &T) -> U {
+    let frac = x.mantissa().to_u128().unwrap();
+    let frac = if U::MANTISSA_BITS > T::MANTISSA_BITS {
+        frac << (U::MANTISSA_BITS - T::MANTISSA_BITS)
+    } else {
+        frac >> (T::MANTISSA_BITS - U::MANTISSA_BITS)
+    };
+    let mut ret = U::quiet_nan();
+    ret.set_mantissa(ret.mantissa() | <U::Payload as NumCast>::from(frac).unwrap());
+    ret.set_sign(if x.is_negative() {
+        U::Payload::one()
+    } else {
+        U::Payload::zero()
+    });
+    ret
+}
+
+/// The floating-point control and status register of MIPS.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fpu {
+    /// floating-point control and status register
+    pub fcsr: u32,
+    /// Whether denormal operands and tiny results raise an unimplemented operation when
+    /// `FS` is clear, as on implementations which leave them to software
+    pub unimplemented_denormals: bool,
+}
+
+impl Fpu {
+    /// Returns an `Fpu` with `fcsr`, and denormals handled in hardware.
+    pub fn new(fcsr: u32) -> Self {
+        Self {
+            fcsr,
+            unimplemented_denormals: false,
+        }
+    }
+
+    /// Returns the rounding mode of `FCSR.RM`.
+    pub fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::from_mips_rm(self.fcsr as u8 & 3).unwrap()
+    }
+
+    /// Returns the floating-point condition code `cc`, from 0 to 7.
+    pub fn fcc(&self, cc: u32) -> bool {
+        self.fcsr & fcc_bit(cc) != 0
+    }
+
+    fn nan2008(&self) -> bool {
+        self.fcsr & FCSR_NAN2008 != 0
+    }
+
+    fn flush(&self) -> bool {
+        self.fcsr & FCSR_FS != 0
+    }
+
+    /// Whether `x` is a signaling NaN in the NaN encoding of `FCSR`
+    fn is_signaling_nan<T: SoftFloat>(&self, x: &T) -> bool {
+        if self.nan2008() {
+            x.is_signaling_nan()
+        } else {
+            // the legacy encoding sets the top bit of the significand of signaling NaNs
+            x.is_nan() && !x.is_signaling_nan()
+        }
+    }
+
+    /// Returns the default NaN in the NaN encoding of `FCSR`.
+    pub fn default_nan<T: SoftFloat>(&self) -> T {
+        let mut ret = T::quiet_nan();
+        if !self.nan2008() {
+            // every bit of the significand but the top one
+            let quiet = ret.mantissa();
+            ret.set_mantissa(quiet - T::Payload::one());
+        }
+        ret
+    }
+
+    /// Ends an instruction which raised `cause`, returning `ret` unless it traps.
+    fn finish<R>(&mut self, cause: u32, ret: R) -> Option<R> {
+        self.fcsr = self.fcsr & !CAUSES | cause;
+        let enabled = (self.fcsr << 5) & cause;
+        if cause & FCSR_CAUSE_E != 0 || enabled != 0 {
+            return None;
+        }
+        self.fcsr |= (cause >> 10) & FLAGS;
+        Some(ret)
+    }
+
+    /// The operands `ops` with denormals flushed under `FS`, or `None` if they raise an
+    /// unimplemented operation.
+    fn inputs<T: SoftFloat + Copy, const N: usize>(&self, ops: [T; N]) -> Option<[T; N]> {
+        if !ops.iter().any(|x| x.is_subnormal()) {
+            return Some(ops);
+        }
+        if self.flush() {
+            Some(ops.map(|x| if x.is_subnormal() { zero_like(&x) } else { x }))
+        } else if self.unimplemented_denormals {
+            None
+        } else {
+            Some(ops)
+        }
+    }
+
+    /// The NaN result of an instruction on `ops` if one of them is a NaN, with its cause.
+    fn nans<T: SoftFloat + Copy>(&self, ops: &[T]) -> Option<(T, u32)> {
+        let snan = ops.iter().find(|x| self.is_signaling_nan(*x));
+        let nan = snan.or_else(|| ops.iter().find(|x| x.is_nan()))?;
+        let cause = if snan.is_some() { FCSR_CAUSE_V } else { 0 };
+        let ret = if !self.nan2008() {
+            // quiet NaNs propagate, and signaling ones become the default NaN
+            if snan.is_some() {
+                self.default_nan()
+            } else {
+                *nan
+            }
+        } else {
+            let mut ret = *nan;
+            ret.set_mantissa(nan.mantissa() | T::quiet_nan().mantissa());
+            ret
+        };
+        Some((ret, cause))
+    }
+
+    /// Runs `f` in the rounding mode of `FCSR`, flushing or escalating tiny results, and
+    /// returns the result with its cause.
+    fn round<T: SoftFloat + Copy>(&self, f: impl FnOnce(RoundingMode) -> T) -> (T, u32) {
+        let rnd = self.rounding_mode();
+        let (mut ret, flags) = with_flags(|| f(rnd));
+        if flags.is_invalid() {
+            return (self.default_nan(), FCSR_CAUSE_V);
+        }
+        let mut cause = 0;
+        let bits = [
+            (flags.is_infinite(), FCSR_CAUSE_Z),
+            (flags.is_overflow(), FCSR_CAUSE_O),
+            (flags.is_underflow(), FCSR_CAUSE_U),
+            (flags.is_inexact(), FCSR_CAUSE_I),
+        ];
+        for (raised, bit) in bits {
+            if raised {
+                cause |= bit;
+            }
+        }
+        let tiny = flags.is_underflow() || ret.is_subnormal();
+        if tiny && self.flush() {
+            if self.fcsr & (FCSR_ENABLE_U | FCSR_ENABLE_I) != 0 {
+                return (ret, FCSR_CAUSE_E);
+            }
+            // tiny results round to zero, or to the smallest normal number away from it
+            let away = match rnd {
+                RoundingMode::TowardPositive => !ret.is_negative(),
+                RoundingMode::TowardNegative => ret.is_negative(),
+                _ => false,
+            };
+            let mut flushed = zero_like(&ret);
+            if away {
+                flushed.set_exponent(T::Payload::one());
+            }
+            ret = flushed;
+            cause |= FCSR_CAUSE_U | FCSR_CAUSE_I;
+        } else if tiny && self.unimplemented_denormals {
+            return (ret, FCSR_CAUSE_E);
+        } else if tiny && self.fcsr & FCSR_ENABLE_U != 0 {
+            // exact tiny results underflow only when the exception is enabled
+            cause |= FCSR_CAUSE_U;
+        }
+        (ret, cause)
+    }
+
+    /// Runs the arithmetic operation `f` on `ops`.
+    fn arith<T: SoftFloat + Copy, const N: usize>(
+        &mut self,
+        ops: [T; N],
+        f: impl FnOnce([T; N], RoundingMode) -> T,
+    ) -> Option<T> {
+        let ops = match self.inputs(ops) {
+            Some(ops) => ops,
+            None => return self.finish(FCSR_CAUSE_E, T::zero()),
+        };
+        if let Some((nan, cause)) = self.nans(&ops) {
+            return self.finish(cause, nan);
+        }
+        let (ret, cause) = self.round(|rnd| f(ops, rnd));
+        self.finish(cause, ret)
+    }
+
+    /// `add.fmt`: `fs + ft`
+    pub fn add<T: SoftFloat + Copy>(&mut self, fs: T, ft: T) -> Option<T> {
+        self.arith([fs, ft], |[a, b], rnd| a.add(b, rnd))
+    }
+
+    /// `sub.fmt`: `fs - ft`
+    pub fn sub<T: SoftFloat + Copy>(&mut self, fs: T, ft: T) -> Option<T> {
+        self.arith([fs, ft], |[a, b], rnd| a.sub(b, rnd))
+    }
+
+    /// `mul.fmt`: `fs * ft`
+    pub fn mul<T: SoftFloat + Copy>(&mut self, fs: T, ft: T) -> Option<T> {
+        self.arith([fs, ft], |[a, b], rnd| a.mul(b, rnd))
+    }
+
+    /// `div.fmt`: `fs / ft`
+    pub fn div<T: SoftFloat + Copy>(&mut self, fs: T, ft: T) -> Option<T> {
+        self.arith([fs, ft], |[a, b], rnd| a.div(b, rnd))
+    }
+
+    /// `sqrt.fmt`
+    pub fn sqrt<T: SoftFloat + Copy>(&mut self, fs: T) -> Option<T> {
+        self.arith([fs], |[a], rnd| a.sqrt(rnd))
+    }
+
+    /// `maddf.fmt`: `fd + fs * ft` with a single rounding
+    pub fn maddf<T: SoftFloat + Copy>(&mut self, fd: T, fs: T, ft: T) -> Option<T> {
+        self.arith([fd, fs, ft], |[d, s, t], rnd| s.fused_mul_add(t, d, rnd))
+    }
+
+    /// `msubf.fmt`: `fd - fs * ft` with a single rounding
+    pub fn msubf<T: SoftFloat + Copy>(&mut self, fd: T, fs: T, ft: T) -> Option<T> {
+        self.arith([fd, fs, ft], |[d, s, t], rnd| {
+            s.neg().fused_mul_add(t, d, rnd)
+        })
+    }
+
+    /// `abs.fmt` and `neg.fmt`, which are arithmetic unless `ABS2008` is set
+    fn sign_op<T: SoftFloat + Copy>(&mut self, fs: T, f: impl FnOnce(T) -> T) -> Option<T> {
+        if self.fcsr & FCSR_ABS2008 != 0 {
+            return Some(f(fs));
+        }
+        let fs = match self.inputs([fs]) {
+            Some([fs]) => fs,
+            None => return self.finish(FCSR_CAUSE_E, T::zero()),
+        };
+        if let Some((nan, cause)) = self.nans(&[fs]) {
+            return self.finish(cause, nan);
+        }
+        self.finish(0, f(fs))
+    }
+
+    /// `abs.fmt`
+    pub fn abs<T: SoftFloat + Copy>(&mut self, fs: T) -> Option<T> {
+        self.sign_op(fs, |x| x.abs())
+    }
+
+    /// `neg.fmt`
+    pub fn neg<T: SoftFloat + Copy>(&mut self, fs: T) -> Option<T> {
+        self.sign_op(fs, |x| x.neg())
+    }
+
+    /// `c.cond.fmt`: compares `fs` with `ft` and sets the condition code `cc` to the
+    /// predicate `cond`, from 0 (`f`) to 15 (`ngt`).
+    ///
+    /// The bits of `cond` select the unordered, equal and less than relations, from the
+    /// least significant, and the top one raises invalid for quiet NaNs too. Returns
+    /// `None` if the comparison traps, leaving the condition code as it was.
+    pub fn c<T: SoftFloat + Copy>(&mut self, cond: u32, fs: T, ft: T, cc: u32) -> Option<bool> {
+        let bit = fcc_bit(cc);
+        let [fs, ft] = match self.inputs([fs, ft]) {
+            Some(ops) => ops,
+            None => return self.finish(FCSR_CAUSE_E, false),
+        };
+        let (ret, cause) = if fs.is_nan() || ft.is_nan() {
+            let snan = self.is_signaling_nan(&fs) || self.is_signaling_nan(&ft);
+            let cause = if snan || cond & 8 != 0 {
+                FCSR_CAUSE_V
+            } else {
+                0
+            };
+            (cond & 1 != 0, cause)
+        } else if FloatCompare::eq(&fs, ft) {
+            (cond & 2 != 0, 0)
+        } else {
+            (cond & 4 != 0 && fs.lt_quiet(ft), 0)
+        };
+        let ret = self.finish(cause, ret)?;
+        if ret {
+            self.fcsr |= bit;
+        } else {
+            self.fcsr &= !bit;
+        }
+        Some(ret)
+    }
+
+    /// `cvt.fmt.fmt`: `fs` converted to another precision
+    pub fn cvt<T: SoftFloat + Copy, U: SoftFloat + Copy>(&mut self, fs: T) -> Option<U> {
+        let fs = match self.inputs([fs]) {
+            Some([fs]) => fs,
+            None => return self.finish(FCSR_CAUSE_E, U::zero()),
+        };
+        if fs.is_nan() {
+            let snan = self.is_signaling_nan(&fs);
+            let ret = if self.nan2008() {
+                convert_nan(&fs)
+            } else {
+                self.default_nan()
+            };
+            return self.finish(if snan { FCSR_CAUSE_V } else { 0 }, ret);
+        }
+        let (ret, cause) = self.round(|rnd| fs.convert::<U>(rnd));
+        self.finish(cause, ret)
+    }
+
+    /// Converts `fs` to an integer by `f`. Invalid conversions give `max` in legacy mode,
+    /// and saturate with NaNs converting to 0 under `NAN2008`.
+    fn convert_int<T: SoftFloat + Copy, I: Zero>(
+        &mut self,
+        fs: T,
+        f: impl FnOnce(T) -> I,
+        min: I,
+        max: I,
+    ) -> Option<I> {
+        let fs = match self.inputs([fs]) {
+            Some([fs]) => fs,
+            None => return self.finish(FCSR_CAUSE_E, I::zero()),
+        };
+        let (ret, flags) = with_flags(|| f(fs));
+        if fs.is_nan() || flags.is_invalid() {
+            let ret = if !self.nan2008() {
+                max
+            } else if fs.is_nan() {
+                I::zero()
+            } else if fs.is_negative() {
+                min
+            } else {
+                max
+            };
+            return self.finish(FCSR_CAUSE_V, ret);
+        }
+        let cause = if flags.is_inexact() { FCSR_CAUSE_I } else { 0 };
+        self.finish(cause, ret)
+    }
+
+    /// `cvt.w.fmt`, or `round.w.fmt`, `trunc.w.fmt`, `ceil.w.fmt` and `floor.w.fmt` with
+    /// the rounding mode `rnd`: `fs` rounded to a 32-bit integer
+    pub fn cvt_w<T: SoftFloat + Copy>(&mut self, fs: T, rnd: RoundingMode) -> Option<i32> {
+        self.convert_int(fs, |x| x.to_i32(rnd, true), i32::MIN, i32::MAX)
+    }
+
+    /// `cvt.l.fmt`, or `round.l.fmt`, `trunc.l.fmt`, `ceil.l.fmt` and `floor.l.fmt` with
+    /// the rounding mode `rnd`: `fs` rounded to a 64-bit integer
+    pub fn cvt_l<T: SoftFloat + Copy>(&mut self, fs: T, rnd: RoundingMode) -> Option<i64> {
+        self.convert_int(fs, |x| x.to_i64(rnd, true), i64::MIN, i64::MAX)
+    }
+
+    /// `cvt.fmt.w`: the 32-bit integer `fs` in the rounding mode of `FCSR`
+    pub fn cvt_from_w<T: SoftFloat + Copy>(&mut self, fs: i32) -> Option<T> {
+        let (ret, cause) = self.round(|rnd| T::from_i32(fs, rnd));
+        self.finish(cause, ret)
+    }
+
+    /// `cvt.fmt.l`: the 64-bit integer `fs` in the rounding mode of `FCSR`
+    pub fn cvt_from_l<T: SoftFloat + Copy>(&mut self, fs: i64) -> Option<T> {
+        let (ret, cause) = self.round(|rnd| T::from_i64(fs, rnd));
+        self.finish(cause, ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatBits, F32, F64};
+
+    const ONE: F32 = F32::from_bits(0x3f800000);
+    const MIN: F32 = F32::from_bits(0x00800000);
+    const HALF: F32 = F32::from_bits(0x3f000000);
+
+    #[test]
+    fn mips_causes() {
+        let mut fpu = Fpu::default();
+        let third = fpu.div(ONE, F32::from_bits(0x40400000)).unwrap();
+        assert_eq!(third.to_bits(), 0x3eaaaaab);
+        assert_eq!(fpu.fcsr, FCSR_CAUSE_I | FCSR_FLAG_I);
+        // the cause field is replaced, the flags accumulate
+        assert_eq!(fpu.add(ONE, ONE).unwrap().to_bits(), 0x40000000);
+        assert_eq!(fpu.fcsr, FCSR_FLAG_I);
+        assert!(fpu.div(ONE, F32::from_bits(0)).unwrap().is_infinity());
+        assert_eq!(fpu.fcsr, FCSR_CAUSE_Z | FCSR_FLAG_Z | FCSR_FLAG_I);
+
+        // enabled causes trap, without updating the flags
+        let mut fpu = Fpu::new(FCSR_ENABLE_O);
+        let max = F32::from_bits(0x7f7fffff);
+        assert_eq!(fpu.mul(max, max), None);
+        assert_eq!(fpu.fcsr, FCSR_ENABLE_O | FCSR_CAUSE_O | FCSR_CAUSE_I);
+        assert!(fpu.mul(ONE, ONE).is_some());
+        assert_eq!(fpu.fcsr, FCSR_ENABLE_O);
+
+        // exact tiny results underflow only when enabled
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.mul(MIN, HALF).unwrap().to_bits(), 0x00400000);
+        assert_eq!(fpu.fcsr, 0);
+        let mut fpu = Fpu::new(FCSR_ENABLE_U);
+        assert_eq!(fpu.mul(MIN, HALF), None);
+        assert_eq!(fpu.fcsr, FCSR_ENABLE_U | FCSR_CAUSE_U);
+    }
+
+    #[test]
+    fn mips_nan() {
+        let zero = F64::from_bits(0);
+        // legacy signaling NaNs have the top bit of the significand set
+        let mut fpu = Fpu::default();
+        let legacy_snan = F32::from_bits(0x7fc00001);
+        let legacy_qnan = F32::from_bits(0xffa00000);
+        assert_eq!(fpu.add(legacy_qnan, ONE).unwrap().to_bits(), 0xffa00000);
+        assert_eq!(fpu.fcsr, 0);
+        assert_eq!(
+            fpu.add(legacy_qnan, legacy_snan).unwrap().to_bits(),
+            0x7fbfffff
+        );
+        assert_eq!(fpu.fcsr, FCSR_CAUSE_V | FCSR_FLAG_V);
+        let ret = fpu.div(zero, zero).unwrap();
+        assert_eq!(ret.to_bits(), 0x7ff7ffffffffffff);
+        assert_eq!(
+            fpu.cvt::<F32, F64>(legacy_qnan).unwrap().to_bits(),
+            0x7ff7ffffffffffff
+        );
+
+        let mut fpu = Fpu::new(FCSR_NAN2008 | FCSR_ABS2008);
+        assert_eq!(
+            fpu.add(legacy_qnan, legacy_snan).unwrap().to_bits(),
+            0xffe00000
+        );
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_V);
+        assert_eq!(fpu.div(zero, zero).unwrap().to_bits(), 0x7ff8000000000000);
+        let ret = fpu.cvt::<F32, F64>(legacy_qnan).unwrap();
+        assert_eq!(ret.to_bits(), 0xfffc000000000000);
+
+        // abs and neg are arithmetic in legacy mode only
+        let snan = F32::from_bits(0xff800001);
+        assert_eq!(fpu.neg(snan).unwrap().to_bits(), 0x7f800001);
+        let mut fpu = Fpu::new(FCSR_NAN2008);
+        assert_eq!(fpu.abs(snan).unwrap().to_bits(), 0xffc00001);
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_V);
+        assert_eq!(fpu.abs(ONE.neg()).unwrap().to_bits(), ONE.to_bits());
+        assert_eq!(fpu.fcsr & CAUSES, 0);
+    }
+
+    #[test]
+    fn mips_flush() {
+        let denormal = F32::from_bits(0x00000001);
+        let mut fpu = Fpu::new(FCSR_FS);
+        assert_eq!(fpu.add(denormal, denormal).unwrap().to_bits(), 0);
+        assert_eq!(fpu.fcsr, FCSR_FS);
+        assert_eq!(fpu.mul(MIN.neg(), HALF).unwrap().to_bits(), 0x80000000);
+        assert_eq!(
+            fpu.fcsr,
+            FCSR_FS | FCSR_CAUSE_U | FCSR_CAUSE_I | FCSR_FLAG_U | FCSR_FLAG_I
+        );
+        // toward an infinity, tiny results flush to the smallest normal number
+        let mut fpu = Fpu::new(FCSR_FS | 2);
+        assert_eq!(fpu.mul(MIN, HALF).unwrap().to_bits(), MIN.to_bits());
+        assert_eq!(fpu.mul(MIN.neg(), HALF).unwrap().to_bits(), 0x80000000);
+
+        // the flushing escalates to an unimplemented operation when U or I is enabled
+        let mut fpu = Fpu::new(FCSR_FS | FCSR_ENABLE_I);
+        assert_eq!(fpu.mul(MIN, HALF), None);
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_E);
+        let mut fpu = Fpu::new(0);
+        fpu.unimplemented_denormals = true;
+        assert_eq!(fpu.add(denormal, ONE), None);
+        assert_eq!(fpu.mul(MIN, HALF), None);
+        assert_eq!(fpu.fcsr, FCSR_CAUSE_E);
+        assert!(fpu.mul(MIN, ONE).is_some());
+        assert_eq!(fpu.fcsr, 0);
+    }
+
+    #[test]
+    fn mips_compare_convert() {
+        let qnan = F32::from_bits(0x7fc00000);
+        let mut fpu = Fpu::new(FCSR_NAN2008);
+        // c.olt
+        assert_eq!(fpu.c(4, ONE.neg(), ONE, 0), Some(true));
+        assert!(fpu.fcc(0));
+        // c.ule
+        assert_eq!(fpu.c(7, qnan, ONE, 3), Some(true));
+        assert!(fpu.fcc(3));
+        assert_eq!(fpu.fcsr & CAUSES, 0);
+        // c.ngt signals for quiet NaNs
+        assert_eq!(fpu.c(15, ONE, qnan.neg(), 3), Some(true));
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_V);
+        assert_eq!(fpu.c(2, ONE, ONE.neg(), 0), Some(false));
+        assert!(!fpu.fcc(0));
+        assert_eq!(fpcc_bits(&fpu), fcc_bit(3));
+        let mut fpu = Fpu::new(FCSR_ENABLE_V);
+        assert_eq!(fpu.c(8, qnan, qnan, 1), None);
+        assert!(!fpu.fcc(1));
+
+        let rnd = RoundingMode::TowardZero;
+        let big = F32::from_bits(0x4f000000);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.cvt_w(big.neg(), rnd), Some(i32::MIN));
+        assert_eq!(fpu.fcsr, 0);
+        assert_eq!(fpu.cvt_w(big, rnd), Some(i32::MAX));
+        assert_eq!(
+            fpu.cvt_w(big.neg().mul(ONE.add(ONE, rnd), rnd), rnd),
+            Some(i32::MAX)
+        );
+        assert_eq!(fpu.cvt_l(qnan, rnd), Some(i64::MAX));
+        let mut fpu = Fpu::new(FCSR_NAN2008);
+        assert_eq!(fpu.cvt_l(qnan, rnd), Some(0));
+        assert_eq!(fpu.cvt_w(F32::infinity().neg(), rnd), Some(i32::MIN));
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_V);
+        assert_eq!(
+            fpu.cvt_w(F32::from_bits(0x3fc00000), RoundingMode::TiesToEven),
+            Some(2)
+        );
+        assert_eq!(fpu.fcsr & CAUSES, FCSR_CAUSE_I);
+        let ret: F64 = fpu.cvt_from_l(-1).unwrap();
+        assert_eq!(ret.to_bits(), 0xbff0000000000000);
+        let ret: F32 = fpu.cvt(F64::from_bits(0x3fb999999999999a)).unwrap();
+        assert_eq!(ret.to_bits(), 0x3dcccccd);
+    }
+
+    fn fpcc_bits(fpu: &Fpu) -> u32 {
+        fpu.fcsr & (0xfe << 24 | 1 << 23)
+    }
+}
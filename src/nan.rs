@@ -0,0 +1,113 @@
+//! Default NaN mode
+//!
+//! By default a NaN result carries the payload of a NaN operand as the backend defines. With
+//! the default NaN mode on, like with `FPCR.DN` set on Arm, every operation of the thread
+//! which returns a NaN returns the default NaN instead: positive, with only the quiet bit of
+//! the significand set. Exception flags are raised as without it, and `neg`, `abs` and the
+//! other bit operations are not affected.
+//!
+//! ```
+//! use softfloat_wrapper::nan::set_default_nan_mode;
+//! use softfloat_wrapper::{FloatArith, RoundingMode, F32};
+//!
+//! let nan = F32::from_bits(0xffc00123);
+//! let one = F32::from_bits(0x3f800000);
+//! set_default_nan_mode(true);
+//! assert_eq!(nan.add(one, RoundingMode::TiesToEven).to_bits(), 0x7fc00000);
+//! set_default_nan_mode(false);
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{FloatBits, F16, F32, F64};
+use std::cell::Cell;
+
+thread_local! {
+    static DEFAULT_NAN_MODE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Makes operations of this thread return the default NaN instead of any other NaN if
+/// `enabled`.
+pub fn set_default_nan_mode(enabled: bool) {
+    DEFAULT_NAN_MODE.with(|x| x.set(enabled));
+}
+
+/// Returns whether the default NaN mode is on in this thread.
+pub fn default_nan_mode() -> bool {
+    DEFAULT_NAN_MODE.with(Cell::get)
+}
+
+/// Results of operations, which are replaced by the default NaN in the default NaN mode
+pub(crate) trait DefaultNan {
+    fn default_nan(self) -> Self;
+}
+
+macro_rules! impl_default_nan {
+    ($($t:ty),*) => {
+        $(
+            impl DefaultNan for $t {
+                #[inline]
+                fn default_nan(self) -> Self {
+                    if self.is_nan() && default_nan_mode() {
+                        Self::quiet_nan()
+                    } else {
+                        self
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_default_nan!(F16, F32, F64);
+#[cfg(feature = "f128")]
+impl_default_nan!(F128);
+
+macro_rules! impl_default_nan_int {
+    ($($t:ty),*) => {
+        $(
+            impl DefaultNan for $t {
+                #[inline]
+                fn default_nan(self) -> Self {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_default_nan_int!(bool, u32, u64, i32, i64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatArith, FloatBits, FloatConvert, RoundingMode};
+
+    #[test]
+    fn nan_default_mode() {
+        let rnd = RoundingMode::TiesToEven;
+        let snan = F64::from_bits(0x7ff0000000000123);
+        let qnan = F64::from_bits(0xfff8000000000456);
+        let one = F64::from_bits(0x3ff0000000000000);
+
+        set_default_nan_mode(true);
+        assert!(default_nan_mode());
+        let (ret, flags) = with_flags(|| snan.mul(one, rnd));
+        assert_eq!(ret.to_bits(), 0x7ff8000000000000);
+        assert!(flags.is_invalid());
+        assert_eq!(qnan.sqrt(rnd).to_bits(), 0x7ff8000000000000);
+        assert_eq!(qnan.to_f32(rnd).to_bits(), 0x7fc00000);
+        assert_eq!(
+            F64::infinity().sub(F64::infinity(), rnd).to_bits(),
+            0x7ff8000000000000
+        );
+        // bit operations keep the payload
+        assert_eq!(qnan.neg().to_bits(), 0x7ff8000000000456);
+        assert_eq!(one.add(one, rnd).to_bits(), 0x4000000000000000);
+
+        set_default_nan_mode(false);
+        if crate::specialization().propagates_nan_payloads() {
+            assert!(qnan.sqrt(rnd).to_bits() != 0x7ff8000000000000);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Interop with the `f16` and `f128` primitive types of nightly Rust
+//!
+//! `F16` and `F128` convert to and from `f16` and `f128` through the bit pattern, so every
+//! value, NaN payloads included, is preserved and no exception flags are raised.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::F16;
+
+impl From<f16> for F16 {
+    #[inline]
+    fn from(x: f16) -> Self {
+        F16::from_bits(x.to_bits())
+    }
+}
+
+impl From<F16> for f16 {
+    #[inline]
+    fn from(x: F16) -> Self {
+        f16::from_bits(x.to_bits())
+    }
+}
+
+#[cfg(feature = "f128")]
+impl From<f128> for F128 {
+    #[inline]
+    fn from(x: f128) -> Self {
+        F128::from_bits(x.to_bits())
+    }
+}
+
+#[cfg(feature = "f128")]
+impl From<F128> for f128 {
+    #[inline]
+    fn from(x: F128) -> Self {
+        f128::from_bits(x.to_bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nightly_f16() {
+        let a: F16 = 1.5f16.into();
+        assert_eq!(a.to_bits(), 0x3e00);
+        let b: f16 = F16::from_bits(0x7e01).into();
+        assert_eq!(b.to_bits(), 0x7e01);
+    }
+
+    #[cfg(feature = "f128")]
+    #[test]
+    fn nightly_f128() {
+        let a: F128 = (-2.0f128).into();
+        assert_eq!(a.to_bits(), 0xc000 << 112);
+        let b: f128 = F128::from_bits(0x7fff_8000_0000_0000_0000_0000_0000_0001).into();
+        assert_eq!(b.to_bits(), 0x7fff_8000_0000_0000_0000_0000_0000_0001);
+    }
+}
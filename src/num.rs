@@ -0,0 +1,255 @@
+//! [num-traits](https://crates.io/crates/num-traits) implementations for the softfloat types
+//!
+//! [`abs_sub`](Signed::abs_sub) rounds to nearest, ties to even, while the operators which
+//! [`Num`] requires round with the default rounding mode of the thread, see
+//! [`RoundingMode::thread_default`]. Conversions to primitive integers truncate
+//! toward zero and return `None` for NaN or out-of-range values, like they do for native
+//! floats, and conversions from and to `f32` and `f64` round to nearest, ties to even, all
+//! without raising exception flags.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{
+    with_flags, FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, SoftFloat, F16,
+    F32, F64,
+};
+use num_traits::{FromPrimitive, Num, One, ParseFloatError, Signed, ToPrimitive, Zero};
+
+/// `1.0` in any format, built without touching the softfloat state
+pub(crate) fn one<T: SoftFloat>() -> T {
+    let mut ret = <T as FloatBits>::zero();
+    ret.set_exponent(T::EXPONENT_MASK >> 1);
+    ret
+}
+
+macro_rules! impl_num_traits {
+    ($ty:ty) => {
+        impl Zero for $ty {
+            #[inline]
+            fn zero() -> Self {
+                <Self as FloatBits>::zero()
+            }
+
+            #[inline]
+            fn is_zero(&self) -> bool {
+                FloatBits::is_zero(self)
+            }
+        }
+
+        impl One for $ty {
+            #[inline]
+            fn one() -> Self {
+                one()
+            }
+        }
+
+        impl Num for $ty {
+            type FromStrRadixErr = ParseFloatError;
+
+            fn from_str_radix(src: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+                Ok(crate::parse::parse(src, radix, RoundingMode::TiesToEven)?)
+            }
+        }
+
+        impl Signed for $ty {
+            /// Computes the absolute value. Returns NaN if the number is NaN.
+            #[inline]
+            fn abs(&self) -> Self {
+                FloatBits::abs(self)
+            }
+
+            /// Returns zero if the number is less than or equal to `other`, otherwise the
+            /// difference between `self` and `other`.
+            #[inline]
+            fn abs_sub(&self, other: &Self) -> Self {
+                if self.le_quiet(other) {
+                    <Self as FloatBits>::zero()
+                } else {
+                    FloatArith::sub(self, *other, RoundingMode::TiesToEven)
+                }
+            }
+
+            /// Returns `1.0` for positive numbers including `+0.0` and infinity, `-1.0` for
+            /// negative numbers including `-0.0` and negative infinity, and NaN for NaN.
+            #[inline]
+            fn signum(&self) -> Self {
+                if self.is_nan() {
+                    Self::quiet_nan()
+                } else {
+                    let mut ret: Self = one();
+                    ret.set_sign(self.sign());
+                    ret
+                }
+            }
+
+            /// Returns `true` if the sign bit is clear, including for `+0.0` and infinity.
+            #[inline]
+            fn is_positive(&self) -> bool {
+                FloatBits::is_positive(self)
+            }
+
+            /// Returns `true` if the sign bit is set, including for `-0.0` and negative infinity.
+            #[inline]
+            fn is_negative(&self) -> bool {
+                FloatBits::is_negative(self)
+            }
+        }
+
+        impl ToPrimitive for $ty {
+            fn to_i64(&self) -> Option<i64> {
+                let (ret, flags) =
+                    with_flags(|| FloatConvert::to_i64(self, RoundingMode::TowardZero, false));
+                if flags.is_invalid() {
+                    None
+                } else {
+                    Some(ret)
+                }
+            }
+
+            fn to_u64(&self) -> Option<u64> {
+                let (ret, flags) =
+                    with_flags(|| FloatConvert::to_u64(self, RoundingMode::TowardZero, false));
+                if flags.is_invalid() {
+                    None
+                } else {
+                    Some(ret)
+                }
+            }
+
+            fn to_f32(&self) -> Option<f32> {
+                let (ret, _) = with_flags(|| FloatConvert::to_f32(self, RoundingMode::TiesToEven));
+                Some(f32::from_bits(ret.to_bits()))
+            }
+
+            fn to_f64(&self) -> Option<f64> {
+                let (ret, _) = with_flags(|| FloatConvert::to_f64(self, RoundingMode::TiesToEven));
+                Some(f64::from_bits(ret.to_bits()))
+            }
+        }
+
+        impl FromPrimitive for $ty {
+            #[inline]
+            fn from_i64(n: i64) -> Option<Self> {
+                Some(<Self as FloatConvert>::from_i64(
+                    n,
+                    RoundingMode::TiesToEven,
+                ))
+            }
+
+            #[inline]
+            fn from_u64(n: u64) -> Option<Self> {
+                Some(<Self as FloatConvert>::from_u64(
+                    n,
+                    RoundingMode::TiesToEven,
+                ))
+            }
+
+            #[inline]
+            fn from_f32(n: f32) -> Option<Self> {
+                let n = F32::from_bits(n.to_bits());
+                Some(with_flags(|| n.convert(RoundingMode::TiesToEven)).0)
+            }
+
+            #[inline]
+            fn from_f64(n: f64) -> Option<Self> {
+                let n = F64::from_bits(n.to_bits());
+                Some(with_flags(|| n.convert(RoundingMode::TiesToEven)).0)
+            }
+        }
+    };
+}
+
+impl_num_traits!(F16);
+impl_num_traits!(F32);
+impl_num_traits!(F64);
+#[cfg(feature = "f128")]
+impl_num_traits!(F128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn num_identities() {
+        assert_eq!(<F16 as One>::one().to_bits(), 0x3c00);
+        assert_eq!(<F32 as One>::one().to_bits(), 0x3f800000);
+        assert_eq!(<F64 as One>::one().to_bits(), 0x3ff0000000000000);
+        assert_eq!(<F32 as Zero>::zero().to_bits(), 0);
+        assert!(Zero::is_zero(&F32::from_bits(0x80000000)));
+    }
+
+    #[test]
+    fn num_from_str_radix() {
+        let a = F32::from_str_radix("0.1", 10).unwrap();
+        assert_eq!(a.to_bits(), 0x3dcccccd);
+        let a = F64::from_str_radix("-1.8p3", 16).unwrap();
+        assert_eq!(a.to_bits(), 0xc028000000000000);
+        assert!(F16::from_str_radix("1x", 10).is_err());
+    }
+
+    #[test]
+    fn num_signed() {
+        let a = F32::from_bits(0xc0400000); // -3.0
+        let b = F32::from_bits(0x40000000); // 2.0
+        assert_eq!(Signed::abs(&a).to_bits(), 0x40400000);
+        assert_eq!(a.abs_sub(&b).to_bits(), 0);
+        assert_eq!(b.abs_sub(&a).to_bits(), 0x40a00000);
+        assert_eq!(a.signum().to_bits(), 0xbf800000);
+        assert!(Signed::is_negative(&a));
+
+        // 1.0 + 2^-30 rounds to nearest whatever the mode of the thread
+        RoundingMode::set_thread_default(RoundingMode::TowardPositive);
+        let a = F32::from_bits(0x3f800000);
+        let b = F32::from_bits(0xb0800000);
+        assert_eq!(a.abs_sub(&b).to_bits(), 0x3f800000);
+        assert_eq!((a - b).to_bits(), 0x3f800001);
+        RoundingMode::set_thread_default(RoundingMode::TiesToEven);
+    }
+
+    #[test]
+    fn num_to_primitive() {
+        let mut flag = crate::ExceptionFlags::default();
+        flag.set();
+        let a = F64::from_bits(0xc00c000000000000); // -3.5
+        assert_eq!(ToPrimitive::to_i64(&a), Some(-3));
+        assert_eq!(ToPrimitive::to_u64(&a), None);
+        assert_eq!(ToPrimitive::to_i32(&F64::quiet_nan()), None);
+        assert_eq!(ToPrimitive::to_u8(&F16::from_bits(0x7bff)), None);
+        assert_eq!(ToPrimitive::to_u16(&F16::from_bits(0x7bff)), Some(65504));
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+
+        let a = <F32 as FromPrimitive>::from_u64(16777217).unwrap();
+        assert_eq!(a.to_bits(), 0x4b800000);
+    }
+
+    #[test]
+    fn num_float_primitive() {
+        let mut flag = crate::ExceptionFlags::default();
+        flag.set();
+        let a = F64::from_bits(0x3ff8000000000000); // 1.5
+        assert_eq!(ToPrimitive::to_f64(&a), Some(1.5));
+        assert_eq!(ToPrimitive::to_f32(&a), Some(1.5));
+        assert!(ToPrimitive::to_f64(&F64::quiet_nan()).unwrap().is_nan());
+        assert!(ToPrimitive::to_f32(&F16::quiet_nan()).unwrap().is_nan());
+        // 0.1 rounds to nearest
+        let a = F64::from_bits(0x3fb999999999999a);
+        assert_eq!(ToPrimitive::to_f32(&a).unwrap().to_bits(), 0x3dcccccd);
+
+        assert_eq!(
+            <F64 as FromPrimitive>::from_f64(1.5).unwrap().to_bits(),
+            0x3ff8000000000000
+        );
+        assert_eq!(
+            <F16 as FromPrimitive>::from_f32(-2.5).unwrap().to_bits(),
+            0xc100
+        );
+        assert_eq!(
+            <F32 as FromPrimitive>::from_f64(0.1).unwrap().to_bits(),
+            0x3dcccccd
+        );
+        assert!(<F32 as FromPrimitive>::from_f64(f64::NAN).unwrap().is_nan());
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+    }
+}
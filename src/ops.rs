@@ -0,0 +1,305 @@
+//! Operator overloading for the softfloat types
+//!
+//! Operators round per [`RoundingMode::thread_default`], to nearest, ties to even like native
+//! floats unless changed, and raise exception flags as the underlying softfloat operations
+//! would. `Sum` and `Product` accumulate sequentially with the same rounding;
+//! [`SoftFloatIterExt`] does the same with a rounding mode given explicitly.
+
+use crate::num::one;
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{FloatArith, FloatBits, FloatCompare, RoundingMode, SoftFloat, F16, F32, F64};
+use num_traits::One;
+
+/// remainder with the sign of the dividend, like `%` on native floats
+pub(crate) fn truncated_rem<T: SoftFloat>(x: &T, y: &T) -> T {
+    let ret = x.rem(y, RoundingMode::TiesToEven);
+    if ret.is_zero() || ret.is_nan() || ret.sign() == x.sign() {
+        ret
+    } else {
+        // `ret` and `x` have opposite signs, so this is exact
+        let mut y = y.abs();
+        y.set_sign(x.sign());
+        ret.add(y, RoundingMode::TiesToEven)
+    }
+}
+
+/// the additive identity, `-0.0` unless rounding toward negative
+pub(crate) fn additive_identity<T: SoftFloat>(rnd: RoundingMode) -> T {
+    let mut ret = T::zero();
+    if !matches!(rnd, RoundingMode::TowardNegative) {
+        ret.set_sign(T::Payload::one());
+    }
+    ret
+}
+
+/// An item of the iterators [`SoftFloatIterExt`] reduces: a softfloat value or a reference
+/// to one.
+pub trait Operand {
+    /// the format of the item
+    type Float: SoftFloat;
+
+    /// Returns the value of the item.
+    fn value(&self) -> &Self::Float;
+}
+
+/// Reductions of iterators of softfloat values, rounding per an explicit mode
+///
+/// Items are accumulated in order, rounding after each operation like a loop would, and
+/// the exception flags of every operation are raised.
+///
+/// ```
+/// use softfloat_wrapper::{RoundingMode, SoftFloatIterExt, F32};
+///
+/// let v = [0x3f800000, 0x33800000, 0x33800000].map(F32::from_bits); // 1.0, 2^-24, 2^-24
+/// assert_eq!(v.iter().softfloat_sum(RoundingMode::TiesToEven).to_bits(), 0x3f800000);
+/// assert_eq!(v.iter().softfloat_sum(RoundingMode::TowardPositive).to_bits(), 0x3f800002);
+/// ```
+pub trait SoftFloatIterExt: Iterator + Sized
+where
+    Self::Item: Operand,
+{
+    /// Sums the items, rounding each addition per `rnd`. An empty iterator sums to the
+    /// additive identity of `rnd`: `-0.0`, or `+0.0` when rounding toward negative.
+    fn softfloat_sum(self, rnd: RoundingMode) -> <Self::Item as Operand>::Float {
+        self.fold(additive_identity(rnd), |acc, x| acc.add(x.value(), rnd))
+    }
+
+    /// Multiplies the items, rounding each multiplication per `rnd`. An empty iterator
+    /// gives `1.0`.
+    fn softfloat_product(self, rnd: RoundingMode) -> <Self::Item as Operand>::Float {
+        self.fold(one(), |acc, x| acc.mul(x.value(), rnd))
+    }
+}
+
+impl<I: Iterator> SoftFloatIterExt for I where I::Item: Operand {}
+
+macro_rules! impl_ops {
+    ($ty:ty) => {
+        impl Operand for $ty {
+            type Float = Self;
+
+            #[inline]
+            fn value(&self) -> &Self {
+                self
+            }
+        }
+
+        impl<'a> Operand for &'a $ty {
+            type Float = $ty;
+
+            #[inline]
+            fn value(&self) -> &$ty {
+                self
+            }
+        }
+
+        impl PartialEq for $ty {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                FloatCompare::eq(self, other)
+            }
+        }
+
+        impl core::ops::Add for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                FloatArith::add(&self, rhs, RoundingMode::thread_default())
+            }
+        }
+
+        impl core::ops::Sub for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                FloatArith::sub(&self, rhs, RoundingMode::thread_default())
+            }
+        }
+
+        impl core::ops::Mul for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: Self) -> Self {
+                FloatArith::mul(&self, rhs, RoundingMode::thread_default())
+            }
+        }
+
+        impl core::ops::Div for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: Self) -> Self {
+                FloatArith::div(&self, rhs, RoundingMode::thread_default())
+            }
+        }
+
+        impl core::ops::Rem for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn rem(self, rhs: Self) -> Self {
+                truncated_rem(&self, &rhs)
+            }
+        }
+
+        impl core::ops::Neg for $ty {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                FloatBits::neg(&self)
+            }
+        }
+
+        impl core::iter::Sum for $ty {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                let rnd = RoundingMode::thread_default();
+                iter.fold(additive_identity(rnd), |acc, x| {
+                    FloatArith::add(&acc, x, rnd)
+                })
+            }
+        }
+
+        impl<'a> core::iter::Sum<&'a $ty> for $ty {
+            fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                let rnd = RoundingMode::thread_default();
+                iter.fold(additive_identity(rnd), |acc, x| {
+                    FloatArith::add(&acc, x, rnd)
+                })
+            }
+        }
+
+        impl core::iter::Product for $ty {
+            fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+                let rnd = RoundingMode::thread_default();
+                iter.fold(<Self as One>::one(), |acc, x| FloatArith::mul(&acc, x, rnd))
+            }
+        }
+
+        impl<'a> core::iter::Product<&'a $ty> for $ty {
+            fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                let rnd = RoundingMode::thread_default();
+                iter.fold(<Self as One>::one(), |acc, x| FloatArith::mul(&acc, x, rnd))
+            }
+        }
+    };
+}
+
+impl_ops!(F16);
+impl_ops!(F32);
+impl_ops!(F64);
+#[cfg(feature = "f128")]
+impl_ops!(F128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ops_arith() {
+        let a = F32::from_bits(0x40400000); // 3.0
+        let b = F32::from_bits(0x40000000); // 2.0
+        assert_eq!((a + b).to_bits(), 0x40a00000);
+        assert_eq!((a - b).to_bits(), 0x3f800000);
+        assert_eq!((a * b).to_bits(), 0x40c00000);
+        assert_eq!((a / b).to_bits(), 0x3fc00000);
+        assert_eq!((-a).to_bits(), 0xc0400000);
+    }
+
+    #[test]
+    fn ops_rem() {
+        let a = F64::from_bits(0x4014000000000000); // 5.0
+        let b = F64::from_bits(0x4008000000000000); // 3.0
+        assert_eq!((a % b).to_bits(), 0x4000000000000000);
+        assert_eq!((-a % b).to_bits(), 0xc000000000000000);
+        assert_eq!((a % -b).to_bits(), 0x4000000000000000);
+    }
+
+    #[test]
+    fn ops_eq() {
+        let a = F16::from_bits(0x0000);
+        let b = F16::from_bits(0x8000);
+        let nan = F16::from_bits(0x7e00);
+        assert!(a == b);
+        assert!(nan != nan);
+    }
+
+    #[test]
+    fn ops_sum_product() {
+        let v = [0x3f800000, 0x40000000, 0x40400000].map(F32::from_bits);
+        assert_eq!(v.iter().sum::<F32>().to_bits(), 0x40c00000);
+        assert_eq!(v.into_iter().product::<F32>().to_bits(), 0x40c00000);
+
+        let empty: [F64; 0] = [];
+        assert_eq!(empty.iter().sum::<F64>().to_bits(), 0x8000000000000000);
+        assert_eq!(empty.iter().product::<F64>().to_bits(), 0x3ff0000000000000);
+
+        // +0 + -0 is +0 when rounding to nearest
+        let zeros = [0x0000, 0x8000].map(F16::from_bits);
+        assert_eq!(zeros.iter().sum::<F16>().to_bits(), 0x0000);
+    }
+
+    #[test]
+    fn ops_iter_ext() {
+        let v = [0x3c00, 0x1000, 0x1000].map(F16::from_bits); // 1.0, 2^-11, 2^-11
+        assert_eq!(
+            v.iter().softfloat_sum(RoundingMode::TiesToEven).to_bits(),
+            0x3c00
+        );
+        assert_eq!(
+            v.into_iter()
+                .softfloat_sum(RoundingMode::TiesToAway)
+                .to_bits(),
+            0x3c02
+        );
+        assert_eq!(
+            v.iter()
+                .rev()
+                .softfloat_sum(RoundingMode::TiesToEven)
+                .to_bits(),
+            0x3c01
+        );
+
+        let three = F64::from_bits(0x4008000000000000);
+        let v = [three, three.neg()];
+        assert_eq!(
+            v.iter()
+                .softfloat_product(RoundingMode::TiesToEven)
+                .to_bits(),
+            0xc022000000000000
+        );
+
+        let empty: [F32; 0] = [];
+        let sum = empty.iter().softfloat_sum(RoundingMode::TowardNegative);
+        assert_eq!(sum.to_bits(), 0x00000000);
+        let sum = empty.iter().softfloat_sum(RoundingMode::TiesToEven);
+        assert_eq!(sum.to_bits(), 0x80000000);
+        let product = empty.iter().softfloat_product(RoundingMode::TowardZero);
+        assert_eq!(product.to_bits(), 0x3f800000);
+    }
+
+    #[test]
+    fn ops_thread_default() {
+        let a = F32::from_bits(0x3f800000);
+        let b = F32::from_bits(0x40400000);
+        RoundingMode::set_thread_default(RoundingMode::TowardPositive);
+        assert_eq!((a / b).to_bits(), 0x3eaaaaab);
+        assert!(matches!(
+            RoundingMode::current(),
+            RoundingMode::TowardPositive
+        ));
+        RoundingMode::set_thread_default(RoundingMode::TowardNegative);
+        assert_eq!((a / b).to_bits(), 0x3eaaaaaa);
+        // -0 + -0 is -0, and +0 + -0 is -0 too
+        let zeros = [0x8000, 0x8000].map(F16::from_bits);
+        assert_eq!(zeros.iter().sum::<F16>().to_bits(), 0x8000);
+        let empty: [F16; 0] = [];
+        assert_eq!(empty.iter().sum::<F16>().to_bits(), 0x0000);
+        RoundingMode::set_thread_default(RoundingMode::TiesToEven);
+        assert_eq!((a / b).to_bits(), 0x3eaaaaab);
+    }
+}
@@ -0,0 +1,163 @@
+//! Packed lane types
+//!
+//! [`Packed`] holds a fixed number of lanes and computes lane-wise, like the packed
+//! instructions of SIMD units. The plain operations raise the exception flags of all lanes
+//! merged, as the status register of an ISA does, and the `_per_lane` variants return the
+//! flags of each lane instead, leaving the exception flags of the thread as they were.
+
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat, F16, F32, F64};
+
+/// A vector of `N` lanes of `T`.
+///
+/// ```
+/// use softfloat_wrapper::{F32x4, FloatArith, FloatBits, RoundingMode, F32};
+///
+/// let rnd = RoundingMode::TiesToEven;
+/// let one = F32::from_bits(0x3f800000);
+/// let a = F32x4::splat(one);
+/// let b = F32x4::new([one, F32::from_bits(0x7f7fffff), F32::zero(), F32::zero()]);
+/// let (c, flags) = a.mul(b, rnd).add_per_lane(b, rnd);
+/// assert_eq!(c.lanes()[0].to_bits(), 0x40000000);
+/// assert!(flags[1].is_overflow());
+/// assert!(!flags[0].is_overflow());
+/// ```
+#[derive(Copy, Clone, Debug)]
+#[repr(transparent)]
+pub struct Packed<T, const N: usize>([T; N]);
+
+/// two lanes of `F16`
+pub type F16x2 = Packed<F16, 2>;
+/// four lanes of `F16`
+pub type F16x4 = Packed<F16, 4>;
+/// eight lanes of `F16`
+pub type F16x8 = Packed<F16, 8>;
+/// two lanes of `F32`
+pub type F32x2 = Packed<F32, 2>;
+/// four lanes of `F32`
+pub type F32x4 = Packed<F32, 4>;
+/// eight lanes of `F32`
+pub type F32x8 = Packed<F32, 8>;
+/// two lanes of `F64`
+pub type F64x2 = Packed<F64, 2>;
+/// four lanes of `F64`
+pub type F64x4 = Packed<F64, 4>;
+
+impl<T, const N: usize> Packed<T, N> {
+    /// Creates a vector of `lanes`.
+    pub const fn new(lanes: [T; N]) -> Self {
+        Self(lanes)
+    }
+
+    /// Returns the lanes of `self`.
+    pub fn lanes(&self) -> &[T; N] {
+        &self.0
+    }
+
+    /// Returns the lanes of `self` by value.
+    pub fn to_array(self) -> [T; N] {
+        self.0
+    }
+}
+
+impl<T: Copy, const N: usize> Packed<T, N> {
+    /// Creates a vector with every lane set to `x`.
+    pub fn splat(x: T) -> Self {
+        Self([x; N])
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Packed<T, N> {
+    fn from(lanes: [T; N]) -> Self {
+        Self(lanes)
+    }
+}
+
+/// Computes `f` of every lane, with the exception flags of each.
+fn per_lane<T, const N: usize>(f: impl Fn(usize) -> T) -> (Packed<T, N>, [ExceptionFlags; N]) {
+    let mut flags = [ExceptionFlags::default(); N];
+    let ret = core::array::from_fn(|i| {
+        let (ret, raised) = with_flags(|| f(i));
+        flags[i] = raised;
+        ret
+    });
+    (Packed(ret), flags)
+}
+
+macro_rules! binary {
+    ($name:ident, $per_lane:ident, $doc:literal) => {
+        #[doc = concat!("Computes ", $doc, " of each pair of lanes, raising the exception flags of all.")]
+        pub fn $name(&self, x: Self, rnd: RoundingMode) -> Self {
+            Self(core::array::from_fn(|i| self.0[i].$name(x.0[i], rnd)))
+        }
+
+        #[doc = concat!("Computes ", $doc, " of each pair of lanes, with the exception flags of each.")]
+        pub fn $per_lane(&self, x: Self, rnd: RoundingMode) -> (Self, [ExceptionFlags; N]) {
+            per_lane(|i| self.0[i].$name(x.0[i], rnd))
+        }
+    };
+}
+
+impl<T: SoftFloat + Copy, const N: usize> Packed<T, N> {
+    binary!(add, add_per_lane, "the sum");
+    binary!(sub, sub_per_lane, "the difference");
+    binary!(mul, mul_per_lane, "the product");
+
+    /// Computes `self * a + b` of each triple of lanes with a single rounding, raising the
+    /// exception flags of all.
+    pub fn fused_mul_add(&self, a: Self, b: Self, rnd: RoundingMode) -> Self {
+        Self(core::array::from_fn(|i| {
+            self.0[i].fused_mul_add(a.0[i], b.0[i], rnd)
+        }))
+    }
+
+    /// Computes `self * a + b` of each triple of lanes with a single rounding, with the
+    /// exception flags of each.
+    pub fn fused_mul_add_per_lane(
+        &self,
+        a: Self,
+        b: Self,
+        rnd: RoundingMode,
+    ) -> (Self, [ExceptionFlags; N]) {
+        per_lane(|i| self.0[i].fused_mul_add(a.0[i], b.0[i], rnd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FloatBits;
+
+    #[test]
+    fn packed_lanes() {
+        let rnd = RoundingMode::TiesToEven;
+        let one = F16::from_bits(0x3c00);
+        let a = F16x2::new([one, F16::from_bits(0x7bff)]);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+
+        let (ret, flags) = a.add_per_lane(a, rnd);
+        assert_eq!(ret.lanes()[0].to_bits(), 0x4000);
+        assert!(ret.lanes()[1].is_infinity());
+        assert_eq!(flags[0].to_bits(), 0);
+        assert!(flags[1].is_overflow());
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+
+        let ret = a.sub(F16x2::splat(one), rnd);
+        assert_eq!(ret.to_array()[0].to_bits(), 0);
+        flag.get();
+        assert!(flag.is_inexact());
+
+        let a = F64x2::from([F64::from_bits(0x3ff0000000000001), F64::from_bits(0)]);
+        let b = F64x2::new([F64::from_bits(0x3ff0000000000001).neg(), F64::from_bits(0)]);
+        let c = F64x2::splat(F64::from_bits(0x3ff0000000000000));
+        // (1 + 2^-52) * -(1 + 2^-52) + 1 = -2^-51 - 2^-104, a tie rounded once
+        let (ret, flags) = a.fused_mul_add_per_lane(b, c, rnd);
+        assert_eq!(ret.lanes()[0].to_bits(), 0xbcc0000000000000);
+        assert!(flags[0].is_inexact());
+        assert_eq!(ret.lanes()[1].to_bits(), 0x3ff0000000000000);
+        assert_eq!(flags[1].to_bits(), 0);
+        let ret = a.mul(b, rnd);
+        assert_eq!(ret.lanes()[0].to_bits(), 0xbff0000000000002);
+    }
+}
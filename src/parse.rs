@@ -0,0 +1,439 @@
+//! Correctly rounded parsing of floating-point strings
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{round_pack, RoundingMode, SoftFloat, F16, F32, F64};
+use num_traits::{identities::One, FloatErrorKind, ParseFloatError};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// The error type returned when a string is not a number.
+///
+/// ```
+/// use softfloat_wrapper::{ParseError, F32};
+///
+/// assert_eq!("1.5".parse::<F32>().unwrap().to_bits(), 0x3fc00000);
+/// let e = "1.2.3".parse::<F32>().unwrap_err();
+/// assert_eq!(e, ParseError::InvalidCharacter { position: 3, character: '.' });
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// the string is empty
+    Empty,
+    /// the radix is not in `2..=36`
+    InvalidRadix(u32),
+    /// a character is not valid where it is, at the byte offset `position`
+    InvalidCharacter { position: usize, character: char },
+    /// the string ends before the digits of the number or of its exponent
+    MissingDigits,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => f.write_str("cannot parse a number from an empty string"),
+            ParseError::InvalidRadix(radix) => write!(f, "invalid radix {}", radix),
+            ParseError::InvalidCharacter {
+                position,
+                character,
+            } => write!(f, "invalid character {:?} at {}", character, position),
+            ParseError::MissingDigits => f.write_str("missing digits"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for ParseFloatError {
+    fn from(e: ParseError) -> Self {
+        let kind = match e {
+            ParseError::Empty => FloatErrorKind::Empty,
+            _ => FloatErrorKind::Invalid,
+        };
+        ParseFloatError { kind }
+    }
+}
+
+/// minimal arbitrary-precision unsigned integer, little-endian 32-bit limbs
+#[derive(Clone, Debug)]
+pub(crate) struct BigUint(Vec<u32>);
+
+impl BigUint {
+    pub(crate) fn from_u32(x: u32) -> Self {
+        let mut ret = Self(vec![x]);
+        ret.normalize();
+        ret
+    }
+
+    pub(crate) fn from_u128(x: u128) -> Self {
+        let mut ret = Self((0..4).map(|i| (x >> (32 * i)) as u32).collect());
+        ret.normalize();
+        ret
+    }
+
+    fn normalize(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn bits(&self) -> i64 {
+        match self.0.last() {
+            Some(x) => self.0.len() as i64 * 32 - x.leading_zeros() as i64,
+            None => 0,
+        }
+    }
+
+    pub(crate) fn mul_add_small(&mut self, m: u32, a: u32) {
+        let mut carry = a as u64;
+        for limb in self.0.iter_mut() {
+            let x = *limb as u64 * m as u64 + carry;
+            *limb = x as u32;
+            carry = x >> 32;
+        }
+        if carry != 0 {
+            self.0.push(carry as u32);
+        }
+        self.normalize();
+    }
+
+    pub(crate) fn shl(&self, n: i64) -> Self {
+        let (limbs, bits) = ((n / 32) as usize, (n % 32) as u32);
+        let mut ret = vec![0; limbs];
+        let mut carry = 0;
+        for &limb in &self.0 {
+            ret.push((limb << bits) | carry);
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        ret.push(carry);
+        let mut ret = Self(ret);
+        ret.normalize();
+        ret
+    }
+
+    fn shr1(&mut self) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut().rev() {
+            let x = *limb;
+            *limb = (x >> 1) | (carry << 31);
+            carry = x & 1;
+        }
+        self.normalize();
+    }
+
+    pub(crate) fn sub_assign(&mut self, x: &Self) {
+        let mut borrow = 0;
+        for (i, limb) in self.0.iter_mut().enumerate() {
+            let y = x.0.get(i).copied().unwrap_or(0) as u64 + borrow;
+            let (v, b) = (*limb as u64).overflowing_sub(y);
+            *limb = v as u32;
+            borrow = b as u64;
+        }
+        self.normalize();
+    }
+
+    /// bits `[shift, shift + 128)` and whether any bit below `shift` is set
+    fn extract(&self, shift: i64) -> (u128, bool) {
+        let mut ret = 0u128;
+        let mut sticky = false;
+        for (i, &limb) in self.0.iter().enumerate() {
+            let pos = i as i64 * 32 - shift;
+            if pos >= 128 {
+                break;
+            } else if pos >= 0 {
+                ret |= (limb as u128) << pos;
+            } else if pos > -32 {
+                ret |= (limb >> -pos) as u128;
+                sticky |= limb << (32 + pos) != 0;
+            } else {
+                sticky |= limb != 0;
+            }
+        }
+        (ret, sticky)
+    }
+}
+
+impl PartialEq for BigUint {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(
+            self.0
+                .len()
+                .cmp(&other.0.len())
+                .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev())),
+        )
+    }
+}
+
+/// `floor(num / den)` and whether the division was inexact, for quotients below `2^128`
+fn divide(mut num: BigUint, den: &BigUint) -> (u128, bool) {
+    let mut den = den.shl(127);
+    let mut ret = 0u128;
+    for i in (0..128).rev() {
+        if num >= den {
+            num.sub_assign(&den);
+            ret |= 1 << i;
+        }
+        den.shr1();
+    }
+    (ret, !num.is_zero())
+}
+
+/// Parses the exponent `src`, which starts at the byte offset `start` of the string.
+fn parse_exponent(src: &str, start: usize) -> Result<i64, ParseError> {
+    let (negative, digits, start) = match src.as_bytes().first() {
+        Some(b'-') => (true, &src[1..], start + 1),
+        Some(b'+') => (false, &src[1..], start + 1),
+        _ => (false, src, start),
+    };
+    if digits.is_empty() {
+        return Err(ParseError::MissingDigits);
+    }
+    let mut ret = 0i64;
+    for (i, c) in digits.char_indices() {
+        let d = c.to_digit(10).ok_or(ParseError::InvalidCharacter {
+            position: start + i,
+            character: c,
+        })?;
+        // anything this large over- or underflows every format anyway
+        ret = (ret * 10 + d as i64).min(1 << 40);
+    }
+    Ok(if negative { -ret } else { ret })
+}
+
+/// Parses `src` as a number in `radix`, rounding it to `T` per `rnd`.
+///
+/// Accepts an optional sign, digits with an optional fraction, and a decimal exponent
+/// introduced by `e` (radix 10, power of 10) or `p` (radix 16, power of 2).
+/// `inf`, `infinity` and `nan` are accepted in any case.
+pub(crate) fn parse<T: SoftFloat>(
+    src: &str,
+    radix: u32,
+    rnd: RoundingMode,
+) -> Result<T, ParseError> {
+    if src.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if !(2..=36).contains(&radix) {
+        return Err(ParseError::InvalidRadix(radix));
+    }
+
+    let (negative, body, start) = match src.as_bytes()[0] {
+        b'-' => (true, &src[1..], 1),
+        b'+' => (false, &src[1..], 1),
+        _ => (false, src, 0),
+    };
+    let signed = |mut x: T| {
+        if negative {
+            x.set_sign(T::Payload::one());
+        }
+        x
+    };
+    if body.eq_ignore_ascii_case("inf") || body.eq_ignore_ascii_case("infinity") {
+        return Ok(signed(T::infinity()));
+    }
+    if body.eq_ignore_ascii_case("nan") {
+        return Ok(signed(T::quiet_nan()));
+    }
+
+    let mut mant = BigUint::from_u32(0);
+    let mut exp = 0i64;
+    let mut exp2 = 0i64;
+    let mut digits = false;
+    let mut fraction = false;
+    for (i, c) in body.char_indices() {
+        if let Some(d) = c.to_digit(radix) {
+            mant.mul_add_small(radix, d);
+            digits = true;
+            if fraction {
+                exp -= 1;
+            }
+        } else if c == '.' && !fraction {
+            fraction = true;
+        } else if digits && (c == 'e' || c == 'E') && radix == 10 {
+            exp += parse_exponent(&body[i + 1..], start + i + 1)?;
+            break;
+        } else if digits && (c == 'p' || c == 'P') && radix == 16 {
+            exp2 = parse_exponent(&body[i + 1..], start + i + 1)?;
+            break;
+        } else {
+            return Err(ParseError::InvalidCharacter {
+                position: start + i,
+                character: c,
+            });
+        }
+    }
+    if !digits {
+        return Err(ParseError::MissingDigits);
+    }
+    if mant.is_zero() {
+        return Ok(signed(T::zero()));
+    }
+
+    // bound the magnitude first, so that absurd exponents don't need huge integers
+    let prec = T::MANTISSA_DIGITS as i64;
+    let bias = T::EXPONENT_BIAS as i64;
+    let log2_lo = 31 - radix.leading_zeros() as i64;
+    let log2_hi = if radix.is_power_of_two() {
+        log2_lo
+    } else {
+        log2_lo + 1
+    };
+    let (scale_lo, scale_hi) = if exp >= 0 {
+        (exp * log2_lo, exp * log2_hi)
+    } else {
+        (exp * log2_hi, exp * log2_lo)
+    };
+    let bits = mant.bits();
+    if bits - 1 + scale_lo + exp2 > bias + 1 {
+        return Ok(round_pack(negative, bias as i32 + 2, 1, rnd));
+    }
+    if bits + scale_hi + exp2 < 1 - bias - prec - 1 {
+        return Ok(round_pack(negative, (1 - bias - prec - 3) as i32, 1, rnd));
+    }
+
+    let (sig, exp2) = if exp >= 0 {
+        for _ in 0..exp {
+            mant.mul_add_small(radix, 0);
+        }
+        let shift = (mant.bits() - 126).max(0);
+        let (sig, sticky) = mant.extract(shift);
+        (sig | sticky as u128, exp2 + shift)
+    } else {
+        let mut den = BigUint::from_u32(1);
+        for _ in 0..-exp {
+            den.mul_add_small(radix, 0);
+        }
+        // scale so that the quotient has 126 or 127 bits
+        let k = 126 + den.bits() - mant.bits();
+        let (sig, sticky) = if k >= 0 {
+            divide(mant.shl(k), &den)
+        } else {
+            divide(mant, &den.shl(-k))
+        };
+        (sig | sticky as u128, exp2 - k)
+    };
+    Ok(round_pack(negative, exp2 as i32, sig, rnd))
+}
+
+macro_rules! impl_from_str {
+    ($ty:ty) => {
+        impl FromStr for $ty {
+            type Err = ParseError;
+
+            /// Parses a decimal number, rounded to nearest, ties to even.
+            fn from_str(src: &str) -> Result<Self, ParseError> {
+                parse(src, 10, RoundingMode::TiesToEven)
+            }
+        }
+    };
+}
+
+impl_from_str!(F16);
+impl_from_str!(F32);
+impl_from_str!(F64);
+#[cfg(feature = "f128")]
+impl_from_str!(F128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{F16, F32, F64};
+
+    fn parse_f32(src: &str) -> u32 {
+        parse::<F32>(src, 10, RoundingMode::TiesToEven)
+            .unwrap()
+            .to_bits()
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(parse_f32("0.1"), 0x3dcccccd);
+        assert_eq!(parse_f32("-2.5e3"), 0xc51c4000);
+        assert_eq!(parse_f32("1e-45"), 0x00000001);
+        assert_eq!(parse_f32("3.4028236e38"), 0x7f800000);
+        assert_eq!(parse_f32("-0"), 0x80000000);
+        assert_eq!(parse_f32("inf"), 0x7f800000);
+        let a = parse::<F64>("0.1", 10, RoundingMode::TiesToEven).unwrap();
+        assert_eq!(a.to_bits(), 0x3fb999999999999a);
+        let a = parse::<F64>("2.2250738585072011e-308", 10, RoundingMode::TiesToEven).unwrap();
+        assert_eq!(a.to_bits(), 0x000fffffffffffff);
+    }
+
+    #[test]
+    fn parse_rounding() {
+        let a = parse::<F16>("65520", 10, RoundingMode::TiesToEven).unwrap();
+        assert_eq!(a.to_bits(), 0x7c00);
+        let a = parse::<F16>("65520", 10, RoundingMode::TowardZero).unwrap();
+        assert_eq!(a.to_bits(), 0x7bff);
+        let a = parse::<F16>("0.1", 10, RoundingMode::TowardPositive).unwrap();
+        assert_eq!(a.to_bits(), 0x2e67);
+        let a = parse::<F16>("1e-100", 10, RoundingMode::TowardPositive).unwrap();
+        assert_eq!(a.to_bits(), 0x0001);
+    }
+
+    #[test]
+    fn parse_radix() {
+        let a = parse::<F32>("1.8p1", 16, RoundingMode::TiesToEven).unwrap();
+        assert_eq!(a.to_bits(), 0x40400000);
+        let a = parse::<F32>("-101.1", 2, RoundingMode::TiesToEven).unwrap();
+        assert_eq!(a.to_bits(), 0xc0b00000);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let rnd = RoundingMode::TiesToEven;
+        assert_eq!(parse::<F32>("", 10, rnd).unwrap_err(), ParseError::Empty);
+        assert_eq!(
+            parse::<F32>("-", 10, rnd).unwrap_err(),
+            ParseError::MissingDigits
+        );
+        assert_eq!(
+            parse::<F32>(".", 10, rnd).unwrap_err(),
+            ParseError::MissingDigits
+        );
+        assert_eq!(
+            parse::<F32>("1e", 10, rnd).unwrap_err(),
+            ParseError::MissingDigits
+        );
+        assert_eq!(
+            parse::<F32>("1.2.3", 10, rnd).unwrap_err(),
+            ParseError::InvalidCharacter {
+                position: 3,
+                character: '.'
+            }
+        );
+        assert_eq!(
+            parse::<F32>("-12", 2, rnd).unwrap_err(),
+            ParseError::InvalidCharacter {
+                position: 2,
+                character: '2'
+            }
+        );
+        assert_eq!(
+            parse::<F32>("1e+x", 10, rnd).unwrap_err(),
+            ParseError::InvalidCharacter {
+                position: 3,
+                character: 'x'
+            }
+        );
+        assert_eq!(
+            parse::<F32>("1", 37, rnd).unwrap_err(),
+            ParseError::InvalidRadix(37)
+        );
+        assert_eq!(
+            "".parse::<F64>().unwrap_err().to_string(),
+            "cannot parse a number from an empty string"
+        );
+    }
+}
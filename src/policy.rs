@@ -0,0 +1,125 @@
+//! Results of out-of-range integer conversions
+//!
+//! Softfloat returns whatever its specialization defines when a float is NaN or out of
+//! range of the target integer type. The `to_*_with_policy` methods of
+//! [`SoftFloat`](crate::SoftFloat) return the value a [`ConversionPolicy`] defines instead,
+//! so one API matches the instruction set or language being emulated. Exception flags are
+//! raised as by the conversion without a policy.
+
+use crate::checked::FloatError;
+use crate::{unpack, with_flags, RoundingMode, SoftFloat};
+use num_traits::PrimInt;
+
+/// Result of converting NaN or an out-of-range value to an integer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConversionPolicy {
+    /// the closest integer, NaN converting to the maximum (RISC-V)
+    Saturate,
+    /// the rounded value modulo 2^N, NaN and infinities converting to 0 (JavaScript)
+    WrapModular,
+    /// an error
+    Error,
+    /// 0
+    Zero,
+    /// the closest integer, NaN converting to 0 (Java, wasm `trunc_sat`)
+    NanToZero,
+    /// the minimum, whatever the value (x86 integer indefinite)
+    Indefinite,
+}
+
+/// Runs `convert` on `x`, and applies `policy` if it is invalid.
+///
+/// `wrap` truncates a value modulo 2^128 to `I`.
+pub(crate) fn to_int<T: SoftFloat, I: PrimInt>(
+    x: &T,
+    rnd: RoundingMode,
+    policy: ConversionPolicy,
+    convert: impl FnOnce() -> I,
+    wrap: impl FnOnce(u128) -> I,
+) -> Result<I, FloatError> {
+    let (ret, flags) = with_flags(convert);
+    flags.raise();
+    if !flags.is_invalid() {
+        return Ok(ret);
+    }
+
+    let saturated = if x.is_negative() {
+        I::min_value()
+    } else {
+        I::max_value()
+    };
+    Ok(match policy {
+        ConversionPolicy::Saturate if x.is_nan() => I::max_value(),
+        ConversionPolicy::Saturate => saturated,
+        ConversionPolicy::NanToZero if x.is_nan() => I::zero(),
+        ConversionPolicy::NanToZero => saturated,
+        ConversionPolicy::WrapModular if !x.is_finite() => I::zero(),
+        ConversionPolicy::WrapModular => {
+            let (int, _) = with_flags(|| x.round_to_integral(rnd));
+            // the value is an integer, so shifting right is exact
+            let (negative, exp, sig) = unpack(&int);
+            let mag = match exp {
+                128.. => 0,
+                0.. => sig << exp,
+                _ => sig >> -exp,
+            };
+            wrap(if negative { mag.wrapping_neg() } else { mag })
+        }
+        ConversionPolicy::Error => return Err(FloatError::Invalid),
+        ConversionPolicy::Zero => I::zero(),
+        ConversionPolicy::Indefinite => I::min_value(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExceptionFlags, FloatBits, F32, F64};
+
+    #[test]
+    fn policy_to_int() {
+        let rnd = RoundingMode::TowardZero;
+        let big = F64::from_bits(0x41f0000000000005); // 2^32 + 5 * 2^-20
+        let neg = F64::from_bits(0xc1e0000000200000); // -2^31 - 1
+        let nan = F64::quiet_nan();
+
+        let policies = [
+            (ConversionPolicy::Saturate, [i32::MAX, i32::MIN, i32::MAX]),
+            (ConversionPolicy::WrapModular, [0, i32::MAX, 0]),
+            (ConversionPolicy::Zero, [0, 0, 0]),
+            (ConversionPolicy::NanToZero, [i32::MAX, i32::MIN, 0]),
+            (ConversionPolicy::Indefinite, [i32::MIN, i32::MIN, i32::MIN]),
+        ];
+        for (policy, expected) in policies {
+            let ret = [big, neg, nan].map(|x| x.to_i32_with_policy(rnd, false, policy).unwrap());
+            assert_eq!(ret, expected, "{:?}", policy);
+        }
+
+        let (ret, flags) =
+            with_flags(|| neg.to_i32_with_policy(rnd, false, ConversionPolicy::Error));
+        assert_eq!(ret, Err(FloatError::Invalid));
+        assert!(flags.is_invalid());
+
+        // values in range are not affected
+        let a = F32::from_bits(0xc0fe0000); // -7.9375
+        let (ret, flags) = with_flags(|| a.to_i64_with_policy(rnd, true, ConversionPolicy::Error));
+        assert_eq!(ret, Ok(-7));
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+
+        // wrapping rounds first, and applies to unsigned types too
+        let a = F32::from_bits(0xc0fe0000);
+        assert_eq!(
+            a.to_u32_with_policy(
+                RoundingMode::TiesToEven,
+                false,
+                ConversionPolicy::WrapModular
+            ),
+            Ok(u32::MAX - 7)
+        );
+        let a = F64::from_bits(0x4430000000000001); // 2^68 + 2^16
+        assert_eq!(
+            a.to_u64_with_policy(rnd, false, ConversionPolicy::WrapModular),
+            Ok(0x10000)
+        );
+    }
+}
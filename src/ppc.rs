@@ -0,0 +1,722 @@
+//! PowerPC floating-point status and control
+//!
+//! [`Fpu`] holds the `FPSCR` register, and runs the floating-point instructions the way
+//! PowerPC does under it: rounding per `FPSCR.RN`, the sticky exception bits with the
+//! detailed causes of invalid operations, the `FX`, `VX` and `FEX` summaries, `FI` and `FR`
+//! for the rounding of the last instruction, and the result class in `FPRF`. Underflow is
+//! detected before rounding, as the architecture does; the exception flags of the thread are
+//! left as they were.
+//!
+//! Enabled exceptions set `FEX`, and for invalid operations and divisions by zero clear `FI`
+//! and `FR` and leave `FPRF` as it was, but the instructions still return the result they
+//! would when the exception is disabled: the caller decides whether to write the target
+//! register and take the program interrupt. The exponent adjustment of the results of
+//! enabled overflows and underflows, and non-IEEE mode under `NI`, are not modeled.
+//!
+//! ```
+//! use softfloat_wrapper::ppc::{Fpu, FPRF_POSITIVE_NORMAL, FPSCR_FI, FPSCR_FPRF, FPSCR_XX};
+//! use softfloat_wrapper::F64;
+//!
+//! let mut fpu = Fpu::default();
+//! let one = F64::from_bits(0x3ff0000000000000);
+//! let three = F64::from_bits(0x4008000000000000);
+//! assert_eq!(fpu.fdiv(one, three).to_bits(), 0x3fd5555555555555);
+//! assert_eq!(fpu.fpscr & FPSCR_FPRF, FPRF_POSITIVE_NORMAL);
+//! assert_ne!(fpu.fpscr & FPSCR_FI, 0);
+//! assert_ne!(fpu.fpscr & FPSCR_XX, 0);
+//! ```
+
+use crate::{with_flags, FloatBits, FloatCompare, FloatConvert, RoundingMode, SoftFloat, F32, F64};
+
+/// exception summary bit of `FPSCR`, set when any exception bit changes from 0 to 1
+pub const FPSCR_FX: u32 = 1 << 31;
+/// enabled exception summary bit of `FPSCR`
+pub const FPSCR_FEX: u32 = 1 << 30;
+/// invalid operation exception summary bit of `FPSCR`
+pub const FPSCR_VX: u32 = 1 << 29;
+/// overflow exception bit of `FPSCR`
+pub const FPSCR_OX: u32 = 1 << 28;
+/// underflow exception bit of `FPSCR`
+pub const FPSCR_UX: u32 = 1 << 27;
+/// zero divide exception bit of `FPSCR`
+pub const FPSCR_ZX: u32 = 1 << 26;
+/// inexact exception bit of `FPSCR`
+pub const FPSCR_XX: u32 = 1 << 25;
+/// invalid operation exception bit of `FPSCR` for signaling NaN operands
+pub const FPSCR_VXSNAN: u32 = 1 << 24;
+/// invalid operation exception bit of `FPSCR` for `inf - inf`
+pub const FPSCR_VXISI: u32 = 1 << 23;
+/// invalid operation exception bit of `FPSCR` for `inf / inf`
+pub const FPSCR_VXIDI: u32 = 1 << 22;
+/// invalid operation exception bit of `FPSCR` for `0 / 0`
+pub const FPSCR_VXZDZ: u32 = 1 << 21;
+/// invalid operation exception bit of `FPSCR` for `inf * 0`
+pub const FPSCR_VXIMZ: u32 = 1 << 20;
+/// invalid operation exception bit of `FPSCR` for ordered comparisons with NaNs
+pub const FPSCR_VXVC: u32 = 1 << 19;
+/// fraction rounded bit of `FPSCR`: the last result was rounded up in magnitude
+pub const FPSCR_FR: u32 = 1 << 18;
+/// fraction inexact bit of `FPSCR`: the last result was inexact
+pub const FPSCR_FI: u32 = 1 << 17;
+/// floating-point result flags field of `FPSCR`: the class bit `C` and the condition code
+/// `FPCC`
+pub const FPSCR_FPRF: u32 = 0x1f << 12;
+/// floating-point condition code field of `FPSCR`, the low four bits of `FPRF`
+pub const FPSCR_FPCC: u32 = 0xf << 12;
+/// invalid operation exception bit of `FPSCR` for software requests
+pub const FPSCR_VXSOFT: u32 = 1 << 10;
+/// invalid operation exception bit of `FPSCR` for square roots of negative numbers
+pub const FPSCR_VXSQRT: u32 = 1 << 9;
+/// invalid operation exception bit of `FPSCR` for integer conversions
+pub const FPSCR_VXCVI: u32 = 1 << 8;
+/// invalid operation exception enable bit of `FPSCR`
+pub const FPSCR_VE: u32 = 1 << 7;
+/// overflow exception enable bit of `FPSCR`
+pub const FPSCR_OE: u32 = 1 << 6;
+/// underflow exception enable bit of `FPSCR`
+pub const FPSCR_UE: u32 = 1 << 5;
+/// zero divide exception enable bit of `FPSCR`
+pub const FPSCR_ZE: u32 = 1 << 4;
+/// inexact exception enable bit of `FPSCR`
+pub const FPSCR_XE: u32 = 1 << 3;
+/// non-IEEE mode bit of `FPSCR`
+pub const FPSCR_NI: u32 = 1 << 2;
+
+/// `FPRF` of quiet NaNs
+pub const FPRF_QUIET_NAN: u32 = 0b10001 << 12;
+/// `FPRF` of negative infinity
+pub const FPRF_NEGATIVE_INFINITY: u32 = 0b01001 << 12;
+/// `FPRF` of negative normal numbers
+pub const FPRF_NEGATIVE_NORMAL: u32 = 0b01000 << 12;
+/// `FPRF` of negative denormal numbers
+pub const FPRF_NEGATIVE_DENORMAL: u32 = 0b11000 << 12;
+/// `FPRF` of negative zero
+pub const FPRF_NEGATIVE_ZERO: u32 = 0b10010 << 12;
+/// `FPRF` of positive zero
+pub const FPRF_POSITIVE_ZERO: u32 = 0b00010 << 12;
+/// `FPRF` of positive denormal numbers
+pub const FPRF_POSITIVE_DENORMAL: u32 = 0b10100 << 12;
+/// `FPRF` of positive normal numbers
+pub const FPRF_POSITIVE_NORMAL: u32 = 0b00100 << 12;
+/// `FPRF` of positive infinity
+pub const FPRF_POSITIVE_INFINITY: u32 = 0b00101 << 12;
+
+/// `FL` bit of a condition register field: less than
+pub const CR_FL: u8 = 0b1000;
+/// `FG` bit of a condition register field: greater than
+pub const CR_FG: u8 = 0b0100;
+/// `FE` bit of a condition register field: equal
+pub const CR_FE: u8 = 0b0010;
+/// `FU` bit of a condition register field: unordered
+pub const CR_FU: u8 = 0b0001;
+
+/// the sticky exception bits, whose changes from 0 to 1 set `FX`
+const EXCEPTIONS: u32 = FPSCR_OX
+    | FPSCR_UX
+    | FPSCR_ZX
+    | FPSCR_XX
+    | FPSCR_VXSNAN
+    | FPSCR_VXISI
+    | FPSCR_VXIDI
+    | FPSCR_VXZDZ
+    | FPSCR_VXIMZ
+    | FPSCR_VXVC
+    | FPSCR_VXSOFT
+    | FPSCR_VXSQRT
+    | FPSCR_VXCVI;
+
+/// the invalid operation exception bits, which `VX` summarizes
+const INVALID: u32 = FPSCR_VXSNAN
+    | FPSCR_VXISI
+    | FPSCR_VXIDI
+    | FPSCR_VXZDZ
+    | FPSCR_VXIMZ
+    | FPSCR_VXVC
+    | FPSCR_VXSOFT
+    | FPSCR_VXSQRT
+    | FPSCR_VXCVI;
+
+/// Returns the `FPRF` of `x`.
+pub fn fprf<T: SoftFloat>(x: &T) -> u32 {
+    use core::num::FpCategory;
+    let negative = x.is_negative();
+    match x.classify() {
+        FpCategory::Nan => FPRF_QUIET_NAN,
+        FpCategory::Infinite if negative => FPRF_NEGATIVE_INFINITY,
+        FpCategory::Infinite => FPRF_POSITIVE_INFINITY,
+        FpCategory::Normal if negative => FPRF_NEGATIVE_NORMAL,
+        FpCategory::Normal => FPRF_POSITIVE_NORMAL,
+        FpCategory::Subnormal if negative => FPRF_NEGATIVE_DENORMAL,
+        FpCategory::Subnormal => FPRF_POSITIVE_DENORMAL,
+        FpCategory::Zero if negative => FPRF_NEGATIVE_ZERO,
+        FpCategory::Zero => FPRF_POSITIVE_ZERO,
+    }
+}
+
+/// `x` with its quiet bit set
+fn quieten<T: SoftFloat + Copy>(x: T) -> T {
+    let mut ret = x;
+    ret.set_mantissa(x.mantissa() | T::quiet_nan().mantissa());
+    ret
+}
+
+/// The floating-point status and control register of PowerPC.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fpu {
+    /// floating-point status and control register
+    pub fpscr: u32,
+}
+
+impl Fpu {
+    /// Returns an `Fpu` with the control bits of `fpscr`, and no exception raised yet.
+    pub fn new(fpscr: u32) -> Self {
+        Self {
+            fpscr: fpscr & (FPSCR_VE | FPSCR_OE | FPSCR_UE | FPSCR_ZE | FPSCR_XE | FPSCR_NI | 3),
+        }
+    }
+
+    /// Returns the rounding mode of `FPSCR.RN`.
+    pub fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::from_ppc_rn(self.fpscr as u8 & 3).unwrap()
+    }
+
+    /// Returns whether an exception is raised which is enabled, that is `FPSCR.FEX`.
+    pub fn enabled_exception(&self) -> bool {
+        self.fpscr & FPSCR_FEX != 0
+    }
+
+    /// Sets the exception bits `bits`, and updates the summaries.
+    fn raise(&mut self, bits: u32) {
+        if bits & EXCEPTIONS & !self.fpscr != 0 {
+            self.fpscr |= FPSCR_FX;
+        }
+        self.fpscr |= bits;
+        self.fpscr &= !(FPSCR_VX | FPSCR_FEX);
+        if self.fpscr & INVALID != 0 {
+            self.fpscr |= FPSCR_VX;
+        }
+        let enabled = [
+            (FPSCR_VX, FPSCR_VE),
+            (FPSCR_OX, FPSCR_OE),
+            (FPSCR_UX, FPSCR_UE),
+            (FPSCR_ZX, FPSCR_ZE),
+            (FPSCR_XX, FPSCR_XE),
+        ];
+        if enabled
+            .iter()
+            .any(|&(x, e)| self.fpscr & x != 0 && self.fpscr & e != 0)
+        {
+            self.fpscr |= FPSCR_FEX;
+        }
+    }
+
+    /// Sets `FI`, `FR` and `FPRF` for an instruction which produced `result`.
+    fn status(&mut self, inexact: bool, rounded: bool, fprf: Option<u32>) {
+        self.fpscr &= !(FPSCR_FI | FPSCR_FR);
+        if inexact {
+            self.fpscr |= FPSCR_FI;
+        }
+        if rounded {
+            self.fpscr |= FPSCR_FR;
+        }
+        if let Some(fprf) = fprf {
+            self.fpscr = self.fpscr & !FPSCR_FPRF | fprf;
+        }
+    }
+
+    /// Raises the invalid operation exceptions `bits`, and returns the NaN `nan`.
+    fn invalid<T: SoftFloat + Copy>(&mut self, bits: u32, nan: T) -> T {
+        self.raise(bits);
+        if self.fpscr & FPSCR_VE != 0 {
+            self.status(false, false, None);
+        } else {
+            self.status(false, false, Some(FPRF_QUIET_NAN));
+        }
+        nan
+    }
+
+    /// The NaN result of an instruction on `ops` if one of them is a NaN: the first one,
+    /// quietened.
+    fn nans<T: SoftFloat + Copy>(&mut self, ops: &[T], invalid: u32) -> Option<T> {
+        let nan = *ops.iter().find(|x| x.is_nan())?;
+        let mut bits = invalid;
+        if ops.iter().any(|x| x.is_signaling_nan()) {
+            bits |= FPSCR_VXSNAN;
+        }
+        if bits != 0 {
+            Some(self.invalid(bits, quieten(nan)))
+        } else {
+            self.status(false, false, Some(FPRF_QUIET_NAN));
+            Some(quieten(nan))
+        }
+    }
+
+    /// Runs `f` in the rounding mode of `FPSCR`, and sets the status of its result.
+    fn round<T: SoftFloat + Copy>(&mut self, f: impl Fn(RoundingMode) -> T) -> T {
+        let (ret, flags) = with_flags(|| f(self.rounding_mode()));
+        let mut bits = 0;
+        let mut rounded = false;
+        if flags.is_inexact() {
+            bits |= FPSCR_XX;
+            let (rtz, rtz_flags) = with_flags(|| f(RoundingMode::TowardZero));
+            rounded = ret.abs().to_bits() > rtz.abs().to_bits();
+            // the result is tiny before rounding if it is tiny when rounded toward zero
+            if rtz.is_subnormal() || rtz.is_zero() && rtz_flags.is_inexact() {
+                bits |= FPSCR_UX;
+            }
+        } else if ret.is_subnormal() && self.fpscr & FPSCR_UE != 0 {
+            // exact tiny results underflow only when the exception is enabled
+            bits |= FPSCR_UX;
+        }
+        if flags.is_overflow() {
+            bits |= FPSCR_OX;
+        }
+        if flags.is_infinite() {
+            bits |= FPSCR_ZX;
+            if self.fpscr & FPSCR_ZE != 0 {
+                self.raise(bits);
+                self.status(false, false, None);
+                return ret;
+            }
+        }
+        self.raise(bits);
+        self.status(flags.is_inexact(), rounded, Some(fprf(&ret)));
+        ret
+    }
+
+    /// Runs `op` on `a` and `b`, with `invalid` the invalid operation exceptions of
+    /// numbers.
+    fn arith<T: SoftFloat + Copy>(
+        &mut self,
+        a: T,
+        b: T,
+        invalid: u32,
+        op: impl Fn(T, T, RoundingMode) -> T,
+    ) -> T {
+        if let Some(nan) = self.nans(&[a, b], 0) {
+            return nan;
+        }
+        if invalid != 0 {
+            return self.invalid(invalid, T::quiet_nan());
+        }
+        self.round(|rnd| op(a, b, rnd))
+    }
+
+    /// `fadd` and `fadds`: `a + b`
+    pub fn fadd<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        let isi = a.is_infinity() && b.is_infinity() && a.sign() != b.sign();
+        self.arith(a, b, if isi { FPSCR_VXISI } else { 0 }, |a, b, rnd| {
+            a.add(b, rnd)
+        })
+    }
+
+    /// `fsub` and `fsubs`: `a - b`
+    pub fn fsub<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        let isi = a.is_infinity() && b.is_infinity() && a.sign() == b.sign();
+        self.arith(a, b, if isi { FPSCR_VXISI } else { 0 }, |a, b, rnd| {
+            a.sub(b, rnd)
+        })
+    }
+
+    /// `fmul` and `fmuls`: `a * c`
+    pub fn fmul<T: SoftFloat + Copy>(&mut self, a: T, c: T) -> T {
+        let imz = a.is_infinity() && c.is_zero() || a.is_zero() && c.is_infinity();
+        self.arith(a, c, if imz { FPSCR_VXIMZ } else { 0 }, |a, c, rnd| {
+            a.mul(c, rnd)
+        })
+    }
+
+    /// `fdiv` and `fdivs`: `a / b`
+    pub fn fdiv<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> T {
+        let invalid = if a.is_infinity() && b.is_infinity() {
+            FPSCR_VXIDI
+        } else if a.is_zero() && b.is_zero() {
+            FPSCR_VXZDZ
+        } else {
+            0
+        };
+        self.arith(a, b, invalid, |a, b, rnd| a.div(b, rnd))
+    }
+
+    /// `fsqrt` and `fsqrts`
+    pub fn fsqrt<T: SoftFloat + Copy>(&mut self, b: T) -> T {
+        let sqrt = b.is_negative() && !b.is_zero() && !b.is_nan();
+        self.arith(b, b, if sqrt { FPSCR_VXSQRT } else { 0 }, |b, _, rnd| {
+            b.sqrt(rnd)
+        })
+    }
+
+    /// `a * c + b`, negated if `negate` unless it is a NaN
+    fn mul_add<T: SoftFloat + Copy>(&mut self, a: T, c: T, b: T, negate: bool) -> T {
+        let mut invalid = 0;
+        if a.is_infinity() && c.is_zero() || a.is_zero() && c.is_infinity() {
+            invalid |= FPSCR_VXIMZ;
+        } else if (a.is_infinity() || c.is_infinity())
+            && b.is_infinity()
+            && !c.is_nan()
+            && !a.is_nan()
+            && (a.sign() ^ c.sign()) != b.sign()
+        {
+            invalid |= FPSCR_VXISI;
+        }
+        if let Some(nan) = self.nans(&[a, b, c], invalid) {
+            return nan;
+        }
+        if invalid != 0 {
+            return self.invalid(invalid, T::quiet_nan());
+        }
+        if negate {
+            // the rounding of the negated result is the one of the result, mirrored
+            self.round(|rnd| {
+                let mirrored = match rnd {
+                    RoundingMode::TowardPositive => RoundingMode::TowardNegative,
+                    RoundingMode::TowardNegative => RoundingMode::TowardPositive,
+                    rnd => rnd,
+                };
+                a.fused_mul_add(c, b, mirrored).neg()
+            })
+        } else {
+            self.round(|rnd| a.fused_mul_add(c, b, rnd))
+        }
+    }
+
+    /// `fmadd` and `fmadds`: `a * c + b`
+    pub fn fmadd<T: SoftFloat + Copy>(&mut self, a: T, c: T, b: T) -> T {
+        self.mul_add(a, c, b, false)
+    }
+
+    /// `fmsub` and `fmsubs`: `a * c - b`
+    pub fn fmsub<T: SoftFloat + Copy>(&mut self, a: T, c: T, b: T) -> T {
+        self.mul_add(a, c, b.neg(), false)
+    }
+
+    /// `fnmadd` and `fnmadds`: `-(a * c + b)`
+    pub fn fnmadd<T: SoftFloat + Copy>(&mut self, a: T, c: T, b: T) -> T {
+        self.mul_add(a, c, b, true)
+    }
+
+    /// `fnmsub` and `fnmsubs`: `-(a * c - b)`
+    pub fn fnmsub<T: SoftFloat + Copy>(&mut self, a: T, c: T, b: T) -> T {
+        self.mul_add(a, c, b.neg(), true)
+    }
+
+    fn compare<T: SoftFloat + Copy>(&mut self, a: T, b: T, ordered: bool) -> u8 {
+        let ret = if a.is_nan() || b.is_nan() {
+            let snan = a.is_signaling_nan() || b.is_signaling_nan();
+            let mut bits = 0;
+            if snan {
+                bits |= FPSCR_VXSNAN;
+            }
+            // a signaling NaN makes an ordered comparison invalid only if that is disabled
+            if ordered && (!snan || self.fpscr & FPSCR_VE == 0) {
+                bits |= FPSCR_VXVC;
+            }
+            self.raise(bits);
+            CR_FU
+        } else if FloatCompare::eq(&a, b) {
+            CR_FE
+        } else if a.lt_quiet(b) {
+            CR_FL
+        } else {
+            CR_FG
+        };
+        self.fpscr = self.fpscr & !FPSCR_FPCC | (ret as u32) << 12;
+        ret
+    }
+
+    /// `fcmpu`: the condition register field of comparing `a` with `b`, also set in
+    /// `FPCC`, raising invalid for signaling NaNs only
+    pub fn fcmpu<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> u8 {
+        self.compare(a, b, false)
+    }
+
+    /// `fcmpo`: the condition register field of comparing `a` with `b`, also set in
+    /// `FPCC`, raising invalid for any NaN
+    pub fn fcmpo<T: SoftFloat + Copy>(&mut self, a: T, b: T) -> u8 {
+        self.compare(a, b, true)
+    }
+
+    /// `frsp`: `b` rounded to single precision
+    pub fn frsp(&mut self, b: F64) -> F32 {
+        if b.is_nan() {
+            let nan = quieten(b);
+            // the sign and the top bits of the payload are kept
+            let bits = nan.to_bits();
+            let ret = F32::from_bits(
+                (bits >> 32) as u32 & 0x8000_0000 | 0x7f80_0000 | (bits >> 29) as u32 & 0x007f_ffff,
+            );
+            return if b.is_signaling_nan() {
+                self.invalid(FPSCR_VXSNAN, ret)
+            } else {
+                self.status(false, false, Some(FPRF_QUIET_NAN));
+                ret
+            };
+        }
+        self.round(|rnd| b.to_f32(rnd))
+    }
+
+    /// Converts `b` to an integer by `f` rounding per `rnd`, saturating to `min` or `max` if it is out of
+    /// range. NaNs convert to `min`, and the result class is left as it was.
+    fn convert_int<T: SoftFloat + Copy, I>(
+        &mut self,
+        b: T,
+        rnd: RoundingMode,
+        f: impl FnOnce(T, RoundingMode) -> I,
+        min: I,
+        max: I,
+    ) -> I {
+        let (ret, flags) = with_flags(|| f(b, rnd));
+        if b.is_nan() || flags.is_invalid() {
+            let snan = if b.is_signaling_nan() {
+                FPSCR_VXSNAN
+            } else {
+                0
+            };
+            self.raise(FPSCR_VXCVI | snan);
+            self.status(false, false, None);
+            return if b.is_nan() || b.is_negative() {
+                min
+            } else {
+                max
+            };
+        }
+        let rounded = flags.is_inexact() && {
+            // the integer is rounded up in magnitude if it differs from the truncated one
+            let rtz = b.round_to_integral(RoundingMode::TowardZero);
+            let rounded = b.round_to_integral(rnd);
+            !FloatCompare::eq(&rtz, rounded)
+        };
+        if flags.is_inexact() {
+            self.raise(FPSCR_XX);
+        }
+        self.status(flags.is_inexact(), rounded, None);
+        ret
+    }
+
+    /// `fctiw`, or `fctiwz` with `RoundingMode::TowardZero`: `b` rounded per `rnd` to a
+    /// 32-bit integer
+    pub fn fctiw<T: SoftFloat + Copy>(&mut self, b: T, rnd: RoundingMode) -> i32 {
+        self.convert_int(b, rnd, |b, rnd| b.to_i32(rnd, true), i32::MIN, i32::MAX)
+    }
+
+    /// `fctid`, or `fctidz` with `RoundingMode::TowardZero`: `b` rounded per `rnd` to a
+    /// 64-bit integer
+    pub fn fctid<T: SoftFloat + Copy>(&mut self, b: T, rnd: RoundingMode) -> i64 {
+        self.convert_int(b, rnd, |b, rnd| b.to_i64(rnd, true), i64::MIN, i64::MAX)
+    }
+
+    /// `fcfid`: the signed integer `b` in the rounding mode of `FPSCR`
+    pub fn fcfid(&mut self, b: i64) -> F64 {
+        self.round(|rnd| F64::from_i64(b, rnd))
+    }
+
+    /// `mtfsb1`: sets the bit `bit` of `FPSCR`, which sets `FX` if it is an exception bit
+    /// changing from 0 to 1, and updates the summaries, which cannot be set directly.
+    pub fn mtfsb1(&mut self, bit: u32) {
+        self.raise(bit & !(FPSCR_FEX | FPSCR_VX));
+    }
+
+    /// `mtfsb0`: clears the bit `bit` of `FPSCR`, and updates the summaries.
+    pub fn mtfsb0(&mut self, bit: u32) {
+        self.fpscr &= !(bit & !(FPSCR_FEX | FPSCR_VX));
+        self.raise(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FloatBits;
+
+    const ONE: F64 = F64::from_bits(0x3ff0000000000000);
+    const QNAN: F64 = F64::from_bits(0xfff8000000012345);
+    const SNAN: F64 = F64::from_bits(0x7ff4000000000001);
+
+    #[test]
+    fn ppc_invalid() {
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fadd(QNAN, SNAN).to_bits(), 0xfff8000000012345);
+        assert_eq!(
+            fpu.fpscr,
+            FPSCR_FX | FPSCR_VX | FPSCR_VXSNAN | FPRF_QUIET_NAN
+        );
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmul(ONE, QNAN).to_bits(), QNAN.to_bits());
+        assert_eq!(fpu.fpscr, FPRF_QUIET_NAN);
+
+        let inf = F64::infinity();
+        let zero = F64::zero();
+        type Instruction = fn(&mut Fpu) -> F64;
+        let causes: [(Instruction, u32); 5] = [
+            (
+                |fpu| fpu.fsub(F64::infinity(), F64::infinity()),
+                FPSCR_VXISI,
+            ),
+            (
+                |fpu| fpu.fdiv(F64::infinity(), F64::infinity()),
+                FPSCR_VXIDI,
+            ),
+            (|fpu| fpu.fdiv(F64::zero(), F64::zero()), FPSCR_VXZDZ),
+            (|fpu| fpu.fmul(F64::zero(), F64::infinity()), FPSCR_VXIMZ),
+            (|fpu| fpu.fsqrt(ONE.neg()), FPSCR_VXSQRT),
+        ];
+        for (f, cause) in causes {
+            let mut fpu = Fpu::default();
+            assert_eq!(f(&mut fpu).to_bits(), 0x7ff8000000000000);
+            assert_eq!(fpu.fpscr, FPSCR_FX | FPSCR_VX | cause | FPRF_QUIET_NAN);
+        }
+
+        // `inf * 0` is invalid even when a NaN is added, which is propagated
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmadd(inf, zero, QNAN).to_bits(), QNAN.to_bits());
+        assert_eq!(fpu.fpscr & !FPSCR_FPRF, FPSCR_FX | FPSCR_VX | FPSCR_VXIMZ);
+        let mut fpu = Fpu::default();
+        fpu.fmsub(inf, ONE, inf);
+        assert_ne!(fpu.fpscr & FPSCR_VXISI, 0);
+
+        // enabled invalid operations leave the result class
+        let mut fpu = Fpu::new(FPSCR_VE);
+        fpu.fadd(ONE, ONE);
+        fpu.fdiv(zero, zero);
+        assert!(fpu.enabled_exception());
+        assert_eq!(fpu.fpscr & FPSCR_FPRF, FPRF_POSITIVE_NORMAL);
+    }
+
+    #[test]
+    fn ppc_status() {
+        // FX is set only by new exceptions
+        let mut fpu = Fpu::default();
+        let third = fpu.fdiv(ONE, F64::from_bits(0x4008000000000000));
+        assert_eq!(third.to_bits(), 0x3fd5555555555555);
+        assert_eq!(
+            fpu.fpscr,
+            FPSCR_FX | FPSCR_XX | FPSCR_FI | FPRF_POSITIVE_NORMAL
+        );
+        fpu.fpscr &= !FPSCR_FX;
+        // 1/10 rounds up, and does not set FX again
+        let ten = F64::from_bits(0x4024000000000000);
+        assert_eq!(fpu.fdiv(ONE, ten).to_bits(), 0x3fb999999999999a);
+        assert_eq!(
+            fpu.fpscr,
+            FPSCR_XX | FPSCR_FI | FPSCR_FR | FPRF_POSITIVE_NORMAL
+        );
+        // exact results clear FI and FR
+        assert_eq!(fpu.fsub(ONE, ONE).to_bits(), 0);
+        assert_eq!(fpu.fpscr, FPSCR_XX | FPRF_POSITIVE_ZERO);
+        // toward negative, exact zeros are negative
+        fpu.fpscr |= 3;
+        fpu.fsub(ONE, ONE);
+        assert_eq!(fpu.fpscr & FPSCR_FPRF, FPRF_NEGATIVE_ZERO);
+
+        let mut fpu = Fpu::new(FPSCR_ZE);
+        let ret = fpu.fdiv(ONE.neg(), F64::zero());
+        assert!(ret.is_infinity());
+        assert_eq!(fpu.fpscr & !3, FPSCR_FX | FPSCR_FEX | FPSCR_ZX | FPSCR_ZE);
+    }
+
+    #[test]
+    fn ppc_underflow() {
+        let min = F64::from_bits(0x0010000000000000);
+        let a = F64::from_bits(0x3fefffffffffffff);
+        // tininess is detected before rounding
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmul(min, a).to_bits(), min.to_bits());
+        assert_eq!(
+            fpu.fpscr,
+            FPSCR_FX | FPSCR_UX | FPSCR_XX | FPSCR_FI | FPSCR_FR | FPRF_POSITIVE_NORMAL
+        );
+        // exact tiny results underflow only when enabled
+        let half = F64::from_bits(0x3fe0000000000000);
+        let mut fpu = Fpu::default();
+        fpu.fmul(min.neg(), half);
+        assert_eq!(fpu.fpscr, FPRF_NEGATIVE_DENORMAL);
+        let mut fpu = Fpu::new(FPSCR_UE);
+        fpu.fmul(min, half);
+        assert_eq!(
+            fpu.fpscr & !FPSCR_FPRF,
+            FPSCR_FX | FPSCR_FEX | FPSCR_UX | FPSCR_UE
+        );
+
+        let mut fpu = Fpu::default();
+        let max = F64::from_bits(0x7fefffffffffffff);
+        assert!(fpu.fadd(max, max).is_infinity());
+        assert_eq!(
+            fpu.fpscr,
+            FPSCR_FX | FPSCR_OX | FPSCR_XX | FPSCR_FI | FPSCR_FR | FPRF_POSITIVE_INFINITY
+        );
+    }
+
+    #[test]
+    fn ppc_fused() {
+        let two = F64::from_bits(0x4000000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmadd(ONE, two, three).to_bits(), 0x4014000000000000);
+        assert_eq!(fpu.fmsub(ONE, two, three).to_bits(), 0xbff0000000000000);
+        assert_eq!(fpu.fnmadd(ONE, two, three).to_bits(), 0xc014000000000000);
+        assert_eq!(fpu.fnmsub(ONE, two, three).to_bits(), 0x3ff0000000000000);
+        assert_eq!(fpu.fpscr & FPSCR_FPRF, FPRF_POSITIVE_NORMAL);
+        // the negation follows the rounding, and not NaNs
+        let tenth = F64::from_bits(0x3fb999999999999a);
+        let mut fpu = Fpu::new(2);
+        let ret = fpu.fnmadd(tenth, tenth, F64::zero());
+        assert_eq!(ret.to_bits(), 0xbf847ae147ae147b);
+        assert_eq!(fpu.fpscr & FPSCR_FR, 0);
+        assert_eq!(fpu.fnmadd(QNAN, ONE, ONE).to_bits(), QNAN.to_bits());
+    }
+
+    #[test]
+    fn ppc_compare_convert() {
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcmpu(ONE, ONE.neg()), CR_FG);
+        assert_eq!(fpu.fcmpu(ONE.neg(), ONE), CR_FL);
+        assert_eq!(fpu.fcmpu(ONE, ONE), CR_FE);
+        assert_eq!(fpu.fcmpu(QNAN, ONE), CR_FU);
+        assert_eq!(fpu.fpscr, (CR_FU as u32) << 12);
+        assert_eq!(fpu.fcmpo(QNAN, ONE), CR_FU);
+        assert_eq!(fpu.fpscr & !FPSCR_FPCC, FPSCR_FX | FPSCR_VX | FPSCR_VXVC);
+        let mut fpu = Fpu::new(FPSCR_VE);
+        fpu.fcmpo(SNAN, ONE);
+        assert_eq!(fpu.fpscr & FPSCR_VXVC, 0);
+        assert_ne!(fpu.fpscr & FPSCR_VXSNAN, 0);
+
+        let rnd = RoundingMode::TiesToEven;
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fctiw(QNAN, rnd), i32::MIN);
+        assert_eq!(fpu.fctiw(F64::infinity(), rnd), i32::MAX);
+        assert_eq!(fpu.fctid(F64::infinity().neg(), rnd), i64::MIN);
+        assert_eq!(fpu.fpscr & !FPSCR_FPRF, FPSCR_FX | FPSCR_VX | FPSCR_VXCVI);
+        let mut fpu = Fpu::default();
+        let a = F64::from_bits(0x3ff8000000000000); // 1.5
+        assert_eq!(fpu.fctiw(a, rnd), 2);
+        assert_eq!(fpu.fpscr, FPSCR_FX | FPSCR_XX | FPSCR_FI | FPSCR_FR);
+        assert_eq!(fpu.fctiw(a, RoundingMode::TowardZero), 1);
+        assert_eq!(fpu.fpscr, FPSCR_FX | FPSCR_XX | FPSCR_FI);
+
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcfid(-1).to_bits(), 0xbff0000000000000);
+        assert_eq!(fpu.fpscr, FPRF_NEGATIVE_NORMAL);
+        assert_eq!(fpu.frsp(SNAN).to_bits(), 0x7fe00000);
+        assert_ne!(fpu.fpscr & FPSCR_VXSNAN, 0);
+        assert_eq!(fpu.frsp(QNAN).to_bits(), 0xffc00000);
+        assert_eq!(
+            fpu.frsp(F64::from_bits(0x3fb999999999999a)).to_bits(),
+            0x3dcccccd
+        );
+        assert_ne!(fpu.fpscr & FPSCR_FR, 0);
+    }
+
+    #[test]
+    fn ppc_mtfsb() {
+        let mut fpu = Fpu::default();
+        fpu.mtfsb1(FPSCR_VXSOFT);
+        assert_eq!(fpu.fpscr, FPSCR_FX | FPSCR_VX | FPSCR_VXSOFT);
+        fpu.mtfsb1(FPSCR_VE);
+        assert!(fpu.enabled_exception());
+        fpu.mtfsb0(FPSCR_VXSOFT);
+        assert_eq!(fpu.fpscr, FPSCR_FX | FPSCR_VE);
+        fpu.mtfsb1(FPSCR_FEX);
+        assert!(!fpu.enabled_exception());
+        assert_eq!(Fpu::new(0xffffffff).fpscr, 0xff);
+    }
+}
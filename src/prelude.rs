@@ -0,0 +1,17 @@
+//! The traits of the float types
+//!
+//! The methods of [`F16`](crate::F16), [`F32`](crate::F32) and the other formats are
+//! declared by [`FloatBits`], [`FloatArith`], [`FloatCompare`] and [`FloatConvert`], so
+//! they are only in scope with those traits imported; a glob import of this module brings
+//! all of them, and [`SoftFloat`] for generic code.
+//!
+//! ```
+//! use softfloat_wrapper::prelude::*;
+//! use softfloat_wrapper::{RoundingMode, F32};
+//!
+//! let a = F32::from_bits(0x3f800000);
+//! let b = a.add(a, RoundingMode::TiesToEven);
+//! assert_eq!(b.to_f64(RoundingMode::TiesToEven).to_bits(), 0x4000000000000000);
+//! ```
+
+pub use crate::{FloatArith, FloatBits, FloatCompare, FloatConvert, SoftFloat};
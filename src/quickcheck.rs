@@ -0,0 +1,176 @@
+//! [quickcheck](https://crates.io/crates/quickcheck) support
+//!
+//! Generated values are random bit patterns, mixed with zeros, ones, infinities, NaNs and
+//! the extremes of the finite range of either sign. Shrinking moves toward zero, one and
+//! powers of two: it drops the sign, then the low half of the mantissa, then halves the
+//! distance of the exponent from the one of `1.0`.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{num::one, SoftFloat, F16, F32, F64};
+use ::quickcheck::{Arbitrary, Gen};
+use num_traits::{One, PrimInt, Zero};
+
+fn arbitrary<T: SoftFloat + Copy>(g: &mut Gen, bits: T::Payload) -> T {
+    // one value in four is a special one
+    if u8::arbitrary(g) >= 64 {
+        return T::from_bits(bits);
+    }
+    let mut max = T::infinity();
+    max.set_exponent(T::EXPONENT_MASK - T::Payload::one());
+    max.set_mantissa(T::MANTISSA_MASK);
+    let specials = [
+        T::zero(),
+        one(),
+        T::infinity(),
+        T::quiet_nan(),
+        T::from_bits(T::Payload::one()),
+        max,
+    ];
+    let mut ret = *g.choose(&specials).unwrap();
+    ret.set_sign(if bool::arbitrary(g) {
+        T::Payload::one()
+    } else {
+        T::Payload::zero()
+    });
+    ret
+}
+
+fn shrink<T: SoftFloat + Copy>(x: T) -> Vec<T> {
+    let zero = T::Payload::zero();
+    let mut ret = Vec::new();
+    if x.is_zero() {
+        if x.is_negative() {
+            ret.push(T::zero());
+        }
+        return ret;
+    }
+    ret.push(T::zero());
+
+    if x.is_nan() {
+        let nan = T::quiet_nan();
+        if x.to_bits() != nan.to_bits() {
+            ret.push(nan);
+        }
+        return ret;
+    }
+    if x.is_negative() {
+        ret.push(x.abs());
+    }
+    let one = one::<T>();
+    if x.abs().to_bits() != one.to_bits() {
+        ret.push(one);
+    }
+    if x.is_infinity() {
+        return ret;
+    }
+
+    let mant = x.mantissa();
+    if mant != zero && x.exponent() != zero {
+        let mut y = x;
+        y.set_mantissa(zero);
+        ret.push(y);
+    }
+    if mant != zero {
+        let trailing = mant.trailing_zeros() as usize;
+        let used = T::MANTISSA_BITS - trailing;
+        let y_mant =
+            mant & !((T::Payload::one() << (trailing + used - used / 2)) - T::Payload::one());
+        if used > 1 && y_mant != zero {
+            let mut y = x;
+            y.set_mantissa(y_mant);
+            ret.push(y);
+        }
+    }
+
+    let exp = x.exponent();
+    let bias = T::EXPONENT_MASK >> 1;
+    if exp != bias {
+        let y_exp = if exp > bias {
+            bias + ((exp - bias) >> 1)
+        } else {
+            bias - ((bias - exp) >> 1)
+        };
+        let mut y = x;
+        y.set_exponent(y_exp);
+        ret.push(y);
+    }
+    ret
+}
+
+macro_rules! impl_arbitrary {
+    ($ty:ty, $payload:ty) => {
+        impl Arbitrary for $ty {
+            fn arbitrary(g: &mut Gen) -> Self {
+                let bits = <$payload>::arbitrary(g);
+                arbitrary(g, bits)
+            }
+
+            fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+                Box::new(shrink(*self).into_iter())
+            }
+        }
+    };
+}
+
+impl_arbitrary!(F16, u16);
+impl_arbitrary!(F32, u32);
+impl_arbitrary!(F64, u64);
+#[cfg(feature = "f128")]
+impl_arbitrary!(F128, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FloatBits;
+
+    /// follows the `pick`th candidate of each shrink step, or the first one when there are
+    /// fewer, as if the property kept failing
+    fn shrink_steps<T: SoftFloat + Copy + Arbitrary>(x: T, pick: usize) -> Vec<T::Payload> {
+        let mut ret = vec![x.to_bits()];
+        let mut x = x;
+        while let Some(y) = x.shrink().nth(pick).or_else(|| x.shrink().next()) {
+            x = y;
+            ret.push(x.to_bits());
+            assert!(ret.len() < 100);
+        }
+        ret
+    }
+
+    #[test]
+    fn quickcheck_arbitrary() {
+        let mut g = Gen::new(100);
+        let mut specials = 0;
+        for _ in 0..1000 {
+            let x = F32::arbitrary(&mut g);
+            if FloatBits::is_zero(&x) || x.is_infinity() || x.is_nan() {
+                specials += 1;
+            }
+        }
+        assert!(specials > 100);
+    }
+
+    #[test]
+    fn quickcheck_shrink() {
+        // -3.0 shrinks to 3.0, then to 1.0 and to 0.0
+        assert_eq!(
+            shrink_steps(F32::from_bits(0xc0400000), 1),
+            [0xc0400000, 0x40400000, 0x3f800000, 0x00000000]
+        );
+        // or through the power of two 2.0
+        assert_eq!(
+            shrink_steps(F32::from_bits(0x40400000), 2),
+            [0x40400000, 0x40000000, 0x3f800000, 0x00000000]
+        );
+
+        // every chain ends at +0.0
+        let mut g = Gen::new(100);
+        for pick in 0..6 {
+            for _ in 0..100 {
+                let x = F64::arbitrary(&mut g);
+                assert_eq!(*shrink_steps(x, pick).last().unwrap(), 0);
+            }
+        }
+        assert!(<F16 as FloatBits>::zero().shrink().next().is_none());
+    }
+}
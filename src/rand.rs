@@ -0,0 +1,432 @@
+//! [rand](https://crates.io/crates/rand) support
+//!
+//! `Standard` samples are uniform in `[0, 1)`: a random integer with as many bits as the
+//! precision of the format, scaled by a power of two, so every sample is exact. Samples of
+//! `Uniform` ranges are computed with a single fused multiply-add of such a value, so they
+//! are correctly rounded whenever `high - low` is representable. Sampling leaves the
+//! exception flags untouched.
+//!
+//! [`Targeted`] samples the values of a [`Target`] class instead, such as subnormals,
+//! signaling NaNs or values around the bounds of integer conversions, for directed random
+//! testing of the corner cases uniform sampling almost never reaches.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{round_pack, with_flags, RoundingMode, SoftFloat, F16, F32, F64};
+use ::rand::distributions::uniform::{SampleBorrow, SampleUniform, UniformSampler};
+use ::rand::distributions::{Distribution, Standard};
+use ::rand::Rng;
+use num_traits::NumCast;
+use std::marker::PhantomData;
+
+/// `k * 2^-p` for a random `k` below `2^p`, where `p` is the precision of `T`
+///
+/// With `inclusive`, `k` may also be `2^p`, giving `1.0`.
+fn unit<T: SoftFloat, R: Rng + ?Sized>(rng: &mut R, inclusive: bool) -> T {
+    let prec = T::MANTISSA_BITS + 1;
+    let k = if inclusive {
+        loop {
+            let k = rng.gen::<u128>() >> (127 - prec);
+            if k <= 1 << prec {
+                break k;
+            }
+        }
+    } else {
+        rng.gen::<u128>() >> (128 - prec)
+    };
+    round_pack(false, -(prec as i32), k, RoundingMode::TiesToEven)
+}
+
+/// Sampler of a `Uniform` range of softfloat values
+#[derive(Clone, Copy, Debug)]
+pub struct UniformSoftFloat<T> {
+    low: T,
+    high: T,
+    scale: T,
+    inclusive: bool,
+}
+
+impl<T: SoftFloat + Copy> UniformSoftFloat<T> {
+    fn new_range<B1, B2>(low: B1, high: B2, inclusive: bool) -> Self
+    where
+        B1: SampleBorrow<T> + Sized,
+        B2: SampleBorrow<T> + Sized,
+    {
+        let low = *low.borrow();
+        let high = *high.borrow();
+        assert!(
+            low.is_finite() && high.is_finite(),
+            "Uniform::new called with non-finite bounds"
+        );
+        if inclusive {
+            assert!(
+                low.le(high),
+                "Uniform::new_inclusive called with `low > high`"
+            );
+        } else {
+            assert!(low.lt(high), "Uniform::new called with `low >= high`");
+        }
+        let (scale, _) = with_flags(|| high.sub(low, RoundingMode::TiesToEven));
+        assert!(scale.is_finite(), "Uniform::new: range overflow");
+        Self {
+            low,
+            high,
+            scale,
+            inclusive,
+        }
+    }
+}
+
+impl<T: SoftFloat + Copy> UniformSampler for UniformSoftFloat<T> {
+    type X = T;
+
+    fn new<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: SampleBorrow<T> + Sized,
+        B2: SampleBorrow<T> + Sized,
+    {
+        Self::new_range(low, high, false)
+    }
+
+    fn new_inclusive<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: SampleBorrow<T> + Sized,
+        B2: SampleBorrow<T> + Sized,
+    {
+        Self::new_range(low, high, true)
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> T {
+        loop {
+            let u = unit::<T, R>(rng, self.inclusive);
+            let (x, _) = with_flags(|| {
+                self.scale
+                    .fused_mul_add(u, self.low, RoundingMode::TiesToEven)
+            });
+            // `scale` may have been rounded up, pushing `x` past `high`
+            if self.inclusive {
+                return if self.high.lt(x) { self.high } else { x };
+            }
+            if x.lt(self.high) {
+                return x;
+            }
+        }
+    }
+}
+
+/// class of the values [`Targeted`] samples
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// zeros
+    Zero,
+    /// subnormal numbers
+    Subnormal,
+    /// normal numbers of any exponent
+    Normal,
+    /// normal numbers of unbiased exponent in `min..=max`, clamped to the normal range
+    Exponent { min: i32, max: i32 },
+    /// normal numbers of the two largest binades, half of them within a few ulps of the
+    /// largest finite value
+    NearOverflow,
+    /// infinities
+    Infinity,
+    /// quiet NaNs with random payloads
+    QuietNan,
+    /// signaling NaNs with random nonzero payloads
+    SignalingNan,
+    /// values within a few ulps, or a few halves, of the bounds of the range of an integer
+    /// of `bits` bits: `0` and `2^bits`, or `-2^(bits - 1)` and `2^(bits - 1)` if `signed`
+    IntBoundary { bits: u32, signed: bool },
+}
+
+/// Distribution of the values of a [`Target`] class
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::{Rng, SeedableRng};
+/// use softfloat_wrapper::{FloatBits, Target, Targeted, F32};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let snan = Targeted::<F32>::new(Target::SignalingNan).with_sign(true);
+/// for _ in 0..100 {
+///     let x = rng.sample(snan);
+///     assert!(x.is_signaling_nan() && x.is_negative());
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Targeted<T> {
+    target: Target,
+    negative: Option<bool>,
+    marker: PhantomData<T>,
+}
+
+impl<T> Targeted<T> {
+    /// Creates a distribution of the values of `target`, of either sign.
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            negative: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the distribution restricted to negative values if `negative`, or to positive
+    /// ones. The sign of values around integer bounds is the one of the bound.
+    pub fn with_sign(self, negative: bool) -> Self {
+        Self {
+            negative: Some(negative),
+            ..self
+        }
+    }
+}
+
+/// a random integer in `0..n`
+fn below<R: Rng + ?Sized>(rng: &mut R, n: u128) -> u128 {
+    rng.gen::<u128>() % n
+}
+
+/// a random offset in `-n..=n`
+fn offset<R: Rng + ?Sized>(rng: &mut R, n: i64) -> i64 {
+    below(rng, 2 * n as u128 + 1) as i64 - n
+}
+
+fn from_u128<T: SoftFloat>(bits: u128) -> T {
+    T::from_bits(<T::Payload as NumCast>::from(bits).unwrap())
+}
+
+/// Returns `x` moved by `k` ulps, across zero if need be, saturating at the infinities.
+fn step<T: SoftFloat>(x: T, k: i64) -> T {
+    let sign_bit = 1u128 << T::SIGN_OFFSET;
+    let inf = <u128 as NumCast>::from(T::infinity().to_bits()).unwrap();
+    let bits = <u128 as NumCast>::from(x.to_bits()).unwrap();
+    // the magnitude, negated for negative values, orders the values as integers
+    let key = if bits & sign_bit != 0 {
+        -((bits & !sign_bit) as i128)
+    } else {
+        bits as i128
+    };
+    let key = (key + k as i128).clamp(-(inf as i128), inf as i128);
+    if key < 0 {
+        from_u128(sign_bit | (-key) as u128)
+    } else {
+        from_u128(key as u128)
+    }
+}
+
+impl<T: SoftFloat> Targeted<T> {
+    fn sample_bits<R: Rng + ?Sized>(&self, rng: &mut R) -> T {
+        let mant_bits = T::MANTISSA_BITS as u32;
+        let mant_mask = (1u128 << mant_bits) - 1;
+        let max_exp = (1u128 << T::EXPONENT_BITS) - 1;
+        let bias = (max_exp >> 1) as i32;
+        let quiet = 1u128 << (mant_bits - 1);
+        let mant = rng.gen::<u128>() & mant_mask;
+        let (exp, mant) = match self.target {
+            Target::Zero => (0, 0),
+            Target::Subnormal => (0, 1 + below(rng, mant_mask)),
+            Target::Normal => (1 + below(rng, max_exp - 1), mant),
+            Target::Exponent { min, max } => {
+                let min = (min + bias).clamp(1, max_exp as i32 - 1) as u128;
+                let max = (max + bias).clamp(min as i32, max_exp as i32 - 1) as u128;
+                (min + below(rng, max - min + 1), mant)
+            }
+            Target::NearOverflow => {
+                let mant = if rng.gen() {
+                    mant_mask - below(rng, 4)
+                } else {
+                    mant
+                };
+                (max_exp - 1 - below(rng, 2), mant)
+            }
+            Target::Infinity => (max_exp, 0),
+            Target::QuietNan => (max_exp, quiet | mant),
+            Target::SignalingNan => (max_exp, 1 + below(rng, quiet - 1)),
+            Target::IntBoundary { .. } => unreachable!(),
+        };
+        let negative = self.negative.unwrap_or_else(|| rng.gen());
+        let sign = (negative as u128) << T::SIGN_OFFSET;
+        from_u128(sign | exp << T::EXPONENT_OFFSET | mant)
+    }
+
+    fn sample_boundary<R: Rng + ?Sized>(&self, rng: &mut R, bits: u32, signed: bool) -> T {
+        // the bounds are powers of two, or zero
+        let (negative, exp) = match (signed, self.negative.unwrap_or_else(|| rng.gen())) {
+            (true, negative) => (negative, Some(bits as i32 - 1)),
+            (false, true) => (false, None),
+            (false, false) => (false, Some(bits as i32)),
+        };
+        let (bound, _) = with_flags(|| match exp {
+            Some(exp) => round_pack::<T>(negative, exp, 1, RoundingMode::TiesToEven),
+            None => T::zero(),
+        });
+        if rng.gen() {
+            return step(bound, offset(rng, 3));
+        }
+        // a few halves away, rounded to nearest
+        let (ret, _) = with_flags(|| {
+            let half = round_pack::<T>(false, -1, 1, RoundingMode::TiesToEven);
+            let k = T::from_i64(offset(rng, 4), RoundingMode::TiesToEven);
+            k.fused_mul_add(half, bound, RoundingMode::TiesToEven)
+        });
+        ret
+    }
+}
+
+impl<T: SoftFloat> Distribution<T> for Targeted<T> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> T {
+        match self.target {
+            Target::IntBoundary { bits, signed } => self.sample_boundary(rng, bits, signed),
+            _ => self.sample_bits(rng),
+        }
+    }
+}
+
+macro_rules! impl_rand {
+    ($ty:ty) => {
+        impl Distribution<$ty> for Standard {
+            fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> $ty {
+                unit(rng, false)
+            }
+        }
+
+        impl SampleUniform for $ty {
+            type Sampler = UniformSoftFloat<$ty>;
+        }
+    };
+}
+
+impl_rand!(F16);
+impl_rand!(F32);
+impl_rand!(F64);
+#[cfg(feature = "f128")]
+impl_rand!(F128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExceptionFlags, FloatArith, FloatBits, FloatCompare, FloatConvert};
+    use ::rand::distributions::Uniform;
+    use ::rand::rngs::StdRng;
+    use ::rand::SeedableRng;
+
+    #[test]
+    fn rand_standard() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let one = F32::from_bits(0x3f800000);
+        let mut sum = F64::zero();
+        for _ in 0..10000 {
+            let x: F32 = rng.gen();
+            assert!(x.is_positive() && x.lt(one));
+            sum = sum.add(x.to_f64(RoundingMode::TiesToEven), RoundingMode::TiesToEven);
+        }
+        assert!(F64::from_u32(4800, RoundingMode::TiesToEven).lt(sum));
+        assert!(sum.lt(F64::from_u32(5200, RoundingMode::TiesToEven)));
+
+        // every sample is a multiple of 2^-11
+        for _ in 0..100 {
+            let x: F16 = rng.gen();
+            assert_eq!(x.to_f32(RoundingMode::TiesToEven).to_bits() & 0x1fff, 0);
+        }
+    }
+
+    #[test]
+    fn rand_uniform() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut flags = ExceptionFlags::default();
+        ExceptionFlags::default().set();
+
+        // 1.0 and 1.0078125 bound a range of 9 values
+        let low = F16::from_bits(0x3c00);
+        let high = F16::from_bits(0x3c08);
+        let half_open = Uniform::new(low, high);
+        let inclusive = Uniform::new_inclusive(low, high);
+        let mut seen = [false; 9];
+        for _ in 0..1000 {
+            let x = rng.sample(half_open);
+            assert!(low.le(x) && x.lt(high));
+            let x = rng.sample(inclusive);
+            assert!(low.le(x) && x.le(high));
+            seen[(x.to_bits() - 0x3c00) as usize] = true;
+        }
+        assert!(seen.iter().all(|x| *x));
+
+        let low = F64::from_bits(0xc000000000000000);
+        let high = F64::from_bits(0x4008000000000000);
+        let range = Uniform::new(low, high);
+        for _ in 0..1000 {
+            let x = rng.sample(range);
+            assert!(low.le(x) && x.lt(high));
+        }
+
+        flags.get();
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    fn rand_targeted() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut flags = ExceptionFlags::default();
+        ExceptionFlags::default().set();
+
+        let sample = |rng: &mut StdRng, target| -> F32 { rng.sample(Targeted::new(target)) };
+        for _ in 0..200 {
+            assert!(sample(&mut rng, Target::Zero).is_zero());
+            assert!(sample(&mut rng, Target::Subnormal).is_subnormal());
+            assert!(sample(&mut rng, Target::Normal).is_normal());
+            assert!(sample(&mut rng, Target::Infinity).is_infinity());
+            let x = sample(&mut rng, Target::QuietNan);
+            assert!(x.is_nan() && !x.is_signaling_nan());
+            assert!(sample(&mut rng, Target::SignalingNan).is_signaling_nan());
+            let x = sample(&mut rng, Target::NearOverflow);
+            assert!(x.is_normal() && x.to_bits() >> 23 & 0xff >= 253);
+            let x = sample(&mut rng, Target::Exponent { min: -3, max: 2 });
+            assert!((124..=129).contains(&(x.to_bits() >> 23 & 0xff)));
+        }
+
+        let neg = Targeted::new(Target::Normal).with_sign(true);
+        assert!((0..100).all(|_| rng.sample::<F16, _>(&neg).is_negative()));
+
+        // i32 bounds, -2^31 and 2^31, whose neighbours in F64 are exact integers or halves
+        let bound = Targeted::<F64>::new(Target::IntBoundary {
+            bits: 32,
+            signed: true,
+        });
+        let mut seen_in_range = false;
+        let mut seen_out_of_range = false;
+        for _ in 0..1000 {
+            let x = rng.sample(bound);
+            let ok = x.try_to_i32(RoundingMode::TowardZero).is_ok();
+            seen_in_range |= ok;
+            seen_out_of_range |= !ok;
+            let d = x
+                .abs()
+                .sub(F64::from_bits(0x41e0000000000000), RoundingMode::TiesToEven);
+            assert!(d.abs().le(F64::from_bits(0x4000000000000000)));
+        }
+        assert!(seen_in_range && seen_out_of_range);
+
+        // u8 bounds, 0 and 256, in F16
+        let bound = Targeted::<F16>::new(Target::IntBoundary {
+            bits: 8,
+            signed: false,
+        });
+        for _ in 0..1000 {
+            let x = rng.sample(bound);
+            let near = |bound: F16| {
+                let d = x.sub(bound, RoundingMode::TiesToEven);
+                d.abs().le(F16::from_bits(0x4000))
+            };
+            assert!(near(F16::zero()) || near(F16::from_bits(0x5c00)));
+        }
+
+        flags.get();
+        assert_eq!(flags.to_bits(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rand_uniform_empty() {
+        let x = F32::from_bits(0x3f800000);
+        Uniform::new(x, x);
+    }
+}
@@ -0,0 +1,396 @@
+//! RISC-V floating-point instructions
+//!
+//! One function per computational instruction of the F, D, Q and Zfh extensions, with the
+//! results and exception flags the ISA defines: NaN results are the canonical NaN,
+//! conversions to integers saturate, and `fmin`/`fmax` are minimumNumber and maximumNumber
+//! of IEEE 754-2019. Flags are raised in the exception flags of the thread, which stand for
+//! `fflags`.
+//!
+//! Instructions with an `rm` field take the rounding mode; the dynamic mode has to be
+//! resolved to `frm` by the caller, see [`RoundingMode::from_riscv_frm`]. Moves, loads and
+//! stores only copy bits and are not here, nor is the NaN-boxing of narrower values in wider
+//! registers.
+//!
+//! ```
+//! use softfloat_wrapper::riscv::{fadd_s, fcvt_w_s};
+//! use softfloat_wrapper::{RoundingMode, F32};
+//!
+//! let rnd = RoundingMode::TiesToEven;
+//! let nan = F32::from_bits(0xffc12345);
+//! assert_eq!(fadd_s(nan, F32::from_bits(0x3f800000), rnd).to_bits(), 0x7fc00000);
+//! assert_eq!(fcvt_w_s(nan, rnd), i32::MAX);
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{
+    with_flags, ExceptionFlags, FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode,
+    SoftFloat, F16, F32, F64,
+};
+
+/// `x`, or the canonical NaN if it is a NaN
+fn canonical<T: SoftFloat>(x: T) -> T {
+    if x.is_nan() {
+        T::quiet_nan()
+    } else {
+        x
+    }
+}
+
+/// minimumNumber, or maximumNumber if `max`
+fn min_max<T: SoftFloat>(a: T, b: T, max: bool) -> T {
+    if a.is_signaling_nan() || b.is_signaling_nan() {
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+    }
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => T::quiet_nan(),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => {
+            // -0 is less than +0
+            let less = a.lt_quiet(&b) || a.eq(&b) && a.is_negative();
+            if less != max {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+fn with_sign<T: SoftFloat>(mut x: T, sign: T::Payload) -> T {
+    x.set_sign(sign);
+    x
+}
+
+/// the mask of `fclass`
+fn class<T: SoftFloat>(x: &T) -> u32 {
+    use core::num::FpCategory;
+    let bit = if x.is_signaling_nan() {
+        8
+    } else if x.is_nan() {
+        9
+    } else {
+        let bit = match x.classify() {
+            FpCategory::Infinite => 0,
+            FpCategory::Normal => 1,
+            FpCategory::Subnormal => 2,
+            FpCategory::Zero | FpCategory::Nan => 3,
+        };
+        if x.is_negative() {
+            bit
+        } else {
+            7 - bit
+        }
+    };
+    1 << bit
+}
+
+/// Runs the conversion `f` of `x` to an integer, saturating to `min` or `max` if it is
+/// invalid. NaNs saturate to `max`.
+fn saturate<T: SoftFloat, I>(x: &T, f: impl FnOnce() -> I, min: I, max: I) -> I {
+    let (ret, flags) = with_flags(f);
+    flags.raise();
+    if !flags.is_invalid() {
+        ret
+    } else if x.is_negative() && !x.is_nan() {
+        min
+    } else {
+        max
+    }
+}
+
+macro_rules! instructions {
+    (
+        $t:ty, $fmt:literal,
+        $add:ident, $sub:ident, $mul:ident, $div:ident, $sqrt:ident,
+        $madd:ident, $msub:ident, $nmsub:ident, $nmadd:ident,
+        $min:ident, $max:ident, $sgnj:ident, $sgnjn:ident, $sgnjx:ident,
+        $eq:ident, $lt:ident, $le:ident, $class:ident,
+        $to_w:ident, $to_wu:ident, $to_l:ident, $to_lu:ident,
+        $from_w:ident, $from_wu:ident, $from_l:ident, $from_lu:ident $(,)?
+    ) => {
+        #[doc = concat!("`fadd.", $fmt, "`")]
+        pub fn $add(a: $t, b: $t, rnd: RoundingMode) -> $t {
+            canonical(a.add(b, rnd))
+        }
+
+        #[doc = concat!("`fsub.", $fmt, "`")]
+        pub fn $sub(a: $t, b: $t, rnd: RoundingMode) -> $t {
+            canonical(a.sub(b, rnd))
+        }
+
+        #[doc = concat!("`fmul.", $fmt, "`")]
+        pub fn $mul(a: $t, b: $t, rnd: RoundingMode) -> $t {
+            canonical(a.mul(b, rnd))
+        }
+
+        #[doc = concat!("`fdiv.", $fmt, "`")]
+        pub fn $div(a: $t, b: $t, rnd: RoundingMode) -> $t {
+            canonical(a.div(b, rnd))
+        }
+
+        #[doc = concat!("`fsqrt.", $fmt, "`")]
+        pub fn $sqrt(a: $t, rnd: RoundingMode) -> $t {
+            canonical(a.sqrt(rnd))
+        }
+
+        #[doc = concat!("`fmadd.", $fmt, "`: `a * b + c`")]
+        pub fn $madd(a: $t, b: $t, c: $t, rnd: RoundingMode) -> $t {
+            canonical(a.fused_mul_add(b, c, rnd))
+        }
+
+        #[doc = concat!("`fmsub.", $fmt, "`: `a * b - c`")]
+        pub fn $msub(a: $t, b: $t, c: $t, rnd: RoundingMode) -> $t {
+            canonical(a.fused_mul_add(b, c.neg(), rnd))
+        }
+
+        #[doc = concat!("`fnmsub.", $fmt, "`: `-(a * b) + c`")]
+        pub fn $nmsub(a: $t, b: $t, c: $t, rnd: RoundingMode) -> $t {
+            canonical(a.neg().fused_mul_add(b, c, rnd))
+        }
+
+        #[doc = concat!("`fnmadd.", $fmt, "`: `-(a * b) - c`")]
+        pub fn $nmadd(a: $t, b: $t, c: $t, rnd: RoundingMode) -> $t {
+            canonical(a.neg().fused_mul_add(b, c.neg(), rnd))
+        }
+
+        #[doc = concat!("`fmin.", $fmt, "`")]
+        pub fn $min(a: $t, b: $t) -> $t {
+            min_max(a, b, false)
+        }
+
+        #[doc = concat!("`fmax.", $fmt, "`")]
+        pub fn $max(a: $t, b: $t) -> $t {
+            min_max(a, b, true)
+        }
+
+        #[doc = concat!("`fsgnj.", $fmt, "`: `a` with the sign of `b`")]
+        pub fn $sgnj(a: $t, b: $t) -> $t {
+            with_sign(a, b.sign())
+        }
+
+        #[doc = concat!("`fsgnjn.", $fmt, "`: `a` with the opposite sign of `b`")]
+        pub fn $sgnjn(a: $t, b: $t) -> $t {
+            with_sign(a, b.sign() ^ 1)
+        }
+
+        #[doc = concat!("`fsgnjx.", $fmt, "`: `a` with the xor of the signs of `a` and `b`")]
+        pub fn $sgnjx(a: $t, b: $t) -> $t {
+            with_sign(a, a.sign() ^ b.sign())
+        }
+
+        #[doc = concat!("`feq.", $fmt, "`, which raises invalid for signaling NaNs only")]
+        pub fn $eq(a: $t, b: $t) -> bool {
+            FloatCompare::eq(&a, b)
+        }
+
+        #[doc = concat!("`flt.", $fmt, "`, which raises invalid for any NaN")]
+        pub fn $lt(a: $t, b: $t) -> bool {
+            FloatCompare::lt(&a, b)
+        }
+
+        #[doc = concat!("`fle.", $fmt, "`, which raises invalid for any NaN")]
+        pub fn $le(a: $t, b: $t) -> bool {
+            FloatCompare::le(&a, b)
+        }
+
+        #[doc = concat!("`fclass.", $fmt, "`: a mask with the bit of the class of `a` set")]
+        pub fn $class(a: $t) -> u32 {
+            class(&a)
+        }
+
+        #[doc = concat!("`fcvt.w.", $fmt, "`")]
+        pub fn $to_w(a: $t, rnd: RoundingMode) -> i32 {
+            saturate(&a, || a.to_i32(rnd, true), i32::MIN, i32::MAX)
+        }
+
+        #[doc = concat!("`fcvt.wu.", $fmt, "`")]
+        pub fn $to_wu(a: $t, rnd: RoundingMode) -> u32 {
+            saturate(&a, || a.to_u32(rnd, true), 0, u32::MAX)
+        }
+
+        #[doc = concat!("`fcvt.l.", $fmt, "`")]
+        pub fn $to_l(a: $t, rnd: RoundingMode) -> i64 {
+            saturate(&a, || a.to_i64(rnd, true), i64::MIN, i64::MAX)
+        }
+
+        #[doc = concat!("`fcvt.lu.", $fmt, "`")]
+        pub fn $to_lu(a: $t, rnd: RoundingMode) -> u64 {
+            saturate(&a, || a.to_u64(rnd, true), 0, u64::MAX)
+        }
+
+        #[doc = concat!("`fcvt.", $fmt, ".w`")]
+        pub fn $from_w(a: i32, rnd: RoundingMode) -> $t {
+            <$t>::from_i32(a, rnd)
+        }
+
+        #[doc = concat!("`fcvt.", $fmt, ".wu`")]
+        pub fn $from_wu(a: u32, rnd: RoundingMode) -> $t {
+            <$t>::from_u32(a, rnd)
+        }
+
+        #[doc = concat!("`fcvt.", $fmt, ".l`")]
+        pub fn $from_l(a: i64, rnd: RoundingMode) -> $t {
+            <$t>::from_i64(a, rnd)
+        }
+
+        #[doc = concat!("`fcvt.", $fmt, ".lu`")]
+        pub fn $from_lu(a: u64, rnd: RoundingMode) -> $t {
+            <$t>::from_u64(a, rnd)
+        }
+    };
+}
+
+macro_rules! conversions {
+    ($($name:ident: $from:ty => $to:ty, $doc:literal;)*) => {
+        $(
+            #[doc = $doc]
+            pub fn $name(a: $from, rnd: RoundingMode) -> $to {
+                canonical(a.convert::<$to>(rnd))
+            }
+        )*
+    };
+}
+
+instructions!(
+    F16, "h", fadd_h, fsub_h, fmul_h, fdiv_h, fsqrt_h, fmadd_h, fmsub_h, fnmsub_h, fnmadd_h,
+    fmin_h, fmax_h, fsgnj_h, fsgnjn_h, fsgnjx_h, feq_h, flt_h, fle_h, fclass_h, fcvt_w_h,
+    fcvt_wu_h, fcvt_l_h, fcvt_lu_h, fcvt_h_w, fcvt_h_wu, fcvt_h_l, fcvt_h_lu,
+);
+
+instructions!(
+    F32, "s", fadd_s, fsub_s, fmul_s, fdiv_s, fsqrt_s, fmadd_s, fmsub_s, fnmsub_s, fnmadd_s,
+    fmin_s, fmax_s, fsgnj_s, fsgnjn_s, fsgnjx_s, feq_s, flt_s, fle_s, fclass_s, fcvt_w_s,
+    fcvt_wu_s, fcvt_l_s, fcvt_lu_s, fcvt_s_w, fcvt_s_wu, fcvt_s_l, fcvt_s_lu,
+);
+
+instructions!(
+    F64, "d", fadd_d, fsub_d, fmul_d, fdiv_d, fsqrt_d, fmadd_d, fmsub_d, fnmsub_d, fnmadd_d,
+    fmin_d, fmax_d, fsgnj_d, fsgnjn_d, fsgnjx_d, feq_d, flt_d, fle_d, fclass_d, fcvt_w_d,
+    fcvt_wu_d, fcvt_l_d, fcvt_lu_d, fcvt_d_w, fcvt_d_wu, fcvt_d_l, fcvt_d_lu,
+);
+
+#[cfg(feature = "f128")]
+instructions!(
+    F128, "q", fadd_q, fsub_q, fmul_q, fdiv_q, fsqrt_q, fmadd_q, fmsub_q, fnmsub_q, fnmadd_q,
+    fmin_q, fmax_q, fsgnj_q, fsgnjn_q, fsgnjx_q, feq_q, flt_q, fle_q, fclass_q, fcvt_w_q,
+    fcvt_wu_q, fcvt_l_q, fcvt_lu_q, fcvt_q_w, fcvt_q_wu, fcvt_q_l, fcvt_q_lu,
+);
+
+conversions! {
+    fcvt_s_h: F16 => F32, "`fcvt.s.h`";
+    fcvt_h_s: F32 => F16, "`fcvt.h.s`";
+    fcvt_d_h: F16 => F64, "`fcvt.d.h`";
+    fcvt_h_d: F64 => F16, "`fcvt.h.d`";
+    fcvt_d_s: F32 => F64, "`fcvt.d.s`";
+    fcvt_s_d: F64 => F32, "`fcvt.s.d`";
+}
+
+#[cfg(feature = "f128")]
+conversions! {
+    fcvt_q_h: F16 => F128, "`fcvt.q.h`";
+    fcvt_h_q: F128 => F16, "`fcvt.h.q`";
+    fcvt_q_s: F32 => F128, "`fcvt.q.s`";
+    fcvt_s_q: F128 => F32, "`fcvt.s.q`";
+    fcvt_q_d: F64 => F128, "`fcvt.q.d`";
+    fcvt_d_q: F128 => F64, "`fcvt.d.q`";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FloatBits;
+
+    const ONE: F32 = F32::from_bits(0x3f800000);
+    const SNAN: F32 = F32::from_bits(0x7fa00000);
+    const QNAN: F32 = F32::from_bits(0xffc12345);
+
+    #[test]
+    fn riscv_nan() {
+        let rnd = RoundingMode::TiesToEven;
+        let (ret, flags) = with_flags(|| fadd_s(SNAN, ONE, rnd));
+        assert_eq!(ret.to_bits(), 0x7fc00000);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INVALID);
+        let (ret, flags) = with_flags(|| fmul_s(QNAN, ONE, rnd));
+        assert_eq!(ret.to_bits(), 0x7fc00000);
+        assert_eq!(flags.to_bits(), 0);
+        let ret = fcvt_d_s(QNAN, rnd);
+        assert_eq!(ret.to_bits(), 0x7ff8000000000000);
+        let ret = fcvt_h_d(F64::from_bits(0xfff0000000000001), rnd);
+        assert_eq!(ret.to_bits(), 0x7e00);
+
+        // sign injection keeps the payload
+        assert_eq!(fsgnjx_s(QNAN, QNAN).to_bits(), 0x7fc12345);
+        assert_eq!(fsgnjn_s(ONE, ONE).to_bits(), 0xbf800000);
+        assert_eq!(fsgnj_s(ONE, QNAN).to_bits(), 0xbf800000);
+
+        let two = F32::from_bits(0x40000000);
+        let three = F32::from_bits(0x40400000);
+        assert_eq!(fmsub_s(ONE, two, three, rnd).to_bits(), 0xbf800000);
+        assert_eq!(fnmsub_s(ONE, two, three, rnd).to_bits(), 0x3f800000);
+        assert_eq!(fnmadd_s(ONE, two, three, rnd).to_bits(), 0xc0a00000);
+    }
+
+    #[test]
+    fn riscv_min_max() {
+        let zero = F64::from_bits(0);
+        let neg_zero = F64::from_bits(0x8000000000000000);
+        assert_eq!(fmin_d(zero, neg_zero).to_bits(), neg_zero.to_bits());
+        assert_eq!(fmax_d(neg_zero, zero).to_bits(), 0);
+
+        let (ret, flags) = with_flags(|| fmin_s(QNAN, ONE));
+        assert_eq!(ret.to_bits(), ONE.to_bits());
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| fmax_s(ONE, SNAN));
+        assert_eq!(ret.to_bits(), ONE.to_bits());
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INVALID);
+        assert_eq!(fmax_s(QNAN, SNAN).to_bits(), 0x7fc00000);
+    }
+
+    #[test]
+    fn riscv_compare_class() {
+        let (ret, flags) = with_flags(|| feq_s(QNAN, QNAN));
+        assert!(!ret);
+        assert_eq!(flags.to_bits(), 0);
+        let (ret, flags) = with_flags(|| flt_s(QNAN, ONE));
+        assert!(!ret);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INVALID);
+        assert!(fle_s(ONE, ONE));
+
+        assert_eq!(fclass_s(F32::infinity().neg()), 1);
+        assert_eq!(fclass_s(ONE.neg()), 1 << 1);
+        assert_eq!(fclass_h(F16::from_bits(0x8001)), 1 << 2);
+        assert_eq!(fclass_d(F64::from_bits(0x8000000000000000)), 1 << 3);
+        assert_eq!(fclass_d(F64::from_bits(0)), 1 << 4);
+        assert_eq!(fclass_h(F16::from_bits(0x0001)), 1 << 5);
+        assert_eq!(fclass_s(ONE), 1 << 6);
+        assert_eq!(fclass_s(F32::infinity()), 1 << 7);
+        assert_eq!(fclass_s(SNAN), 1 << 8);
+        assert_eq!(fclass_s(QNAN), 1 << 9);
+    }
+
+    #[test]
+    fn riscv_to_int() {
+        let rnd = RoundingMode::TiesToEven;
+        let invalid = ExceptionFlags::FLAG_INVALID;
+        let (ret, flags) = with_flags(|| fcvt_w_s(QNAN, rnd));
+        assert_eq!(ret, i32::MAX);
+        assert_eq!(flags.to_bits(), invalid);
+        assert_eq!(fcvt_w_s(F32::infinity().neg(), rnd), i32::MIN);
+        assert_eq!(fcvt_w_s(F32::from_bits(0x4f32d05e), rnd), i32::MAX);
+        assert_eq!(fcvt_wu_s(QNAN, rnd), u32::MAX);
+        assert_eq!(fcvt_lu_d(F64::from_bits(0xbff0000000000000), rnd), 0);
+        assert_eq!(fcvt_l_h(F16::infinity(), rnd), i64::MAX);
+
+        // rounding to zero is not out of range
+        let half = F32::from_bits(0xbf000000);
+        let (ret, flags) = with_flags(|| fcvt_wu_s(half, RoundingMode::TowardZero));
+        assert_eq!(ret, 0);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        let (ret, flags) = with_flags(|| fcvt_l_d(F64::from_bits(0x3ff8000000000000), rnd));
+        assert_eq!(ret, 2);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        assert_eq!(fcvt_s_w(-1, rnd).to_bits(), 0xbf800000);
+    }
+}
@@ -0,0 +1,205 @@
+//! Values carrying their rounding mode
+//!
+//! A [`Rounded`] pairs a softfloat value with the rounding mode of the operations on it, so
+//! an algorithm which rounds one way throughout states it once, and its arithmetic reads
+//! like the one of native floats. Binary operations round per the mode of the left operand,
+//! and the result keeps it. Exception flags are raised as the underlying operations would.
+//!
+//! ```
+//! use softfloat_wrapper::{Rounded, RoundingMode, F32};
+//!
+//! let up = |x| Rounded::new(F32::from_bits(x), RoundingMode::TowardPositive);
+//! let third = up(0x3f800000) / up(0x40400000);
+//! assert_eq!(third.value().to_bits(), 0x3eaaaaab);
+//! let down = third.with_rounding_mode(RoundingMode::TowardNegative);
+//! assert_eq!((down * up(0x40400000)).value().to_bits(), 0x3f800000);
+//! ```
+
+use crate::ops::truncated_rem;
+use crate::{RoundingMode, SoftFloat};
+use std::cmp::Ordering;
+
+/// A softfloat value with the rounding mode of the operations on it
+#[derive(Copy, Clone, Debug)]
+pub struct Rounded<T> {
+    value: T,
+    rnd: RoundingMode,
+}
+
+impl<T: SoftFloat> Rounded<T> {
+    /// Creates a value whose operations round per `rnd`.
+    pub fn new(value: T, rnd: RoundingMode) -> Self {
+        Self { value, rnd }
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value, dropping the rounding mode.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn rounding_mode(&self) -> RoundingMode {
+        self.rnd
+    }
+
+    /// Returns the value with operations rounding per `rnd` instead.
+    pub fn with_rounding_mode(self, rnd: RoundingMode) -> Self {
+        Self { rnd, ..self }
+    }
+
+    fn wrap(&self, value: T) -> Self {
+        Self::new(value, self.rnd)
+    }
+
+    pub fn add(&self, x: &Self) -> Self {
+        self.wrap(self.value.add(&x.value, self.rnd))
+    }
+
+    pub fn sub(&self, x: &Self) -> Self {
+        self.wrap(self.value.sub(&x.value, self.rnd))
+    }
+
+    pub fn mul(&self, x: &Self) -> Self {
+        self.wrap(self.value.mul(&x.value, self.rnd))
+    }
+
+    /// Returns `self * x + y`, rounded once.
+    pub fn fused_mul_add(&self, x: &Self, y: &Self) -> Self {
+        self.wrap(self.value.fused_mul_add(&x.value, &y.value, self.rnd))
+    }
+
+    pub fn div(&self, x: &Self) -> Self {
+        self.wrap(self.value.div(&x.value, self.rnd))
+    }
+
+    /// Returns the IEEE 754 remainder, which is exact. `%` gives the remainder with the
+    /// sign of the dividend instead, like on native floats.
+    pub fn rem(&self, x: &Self) -> Self {
+        self.wrap(self.value.rem(&x.value, self.rnd))
+    }
+
+    pub fn sqrt(&self) -> Self {
+        self.wrap(self.value.sqrt(self.rnd))
+    }
+
+    /// Rounds to an integer per the rounding mode.
+    pub fn round_to_integral(&self) -> Self {
+        self.wrap(self.value.round_to_integral(self.rnd))
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($op:ident, $fn:ident) => {
+        impl<T: SoftFloat> core::ops::$op for Rounded<T> {
+            type Output = Self;
+
+            #[inline]
+            fn $fn(self, rhs: Self) -> Self {
+                Rounded::$fn(&self, &rhs)
+            }
+        }
+
+        impl<T: SoftFloat> core::ops::$op<T> for Rounded<T> {
+            type Output = Self;
+
+            #[inline]
+            fn $fn(self, rhs: T) -> Self {
+                self.wrap(self.value.$fn(rhs, self.rnd))
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add);
+impl_binary_op!(Sub, sub);
+impl_binary_op!(Mul, mul);
+impl_binary_op!(Div, div);
+
+impl<T: SoftFloat> core::ops::Rem for Rounded<T> {
+    type Output = Self;
+
+    #[inline]
+    fn rem(self, rhs: Self) -> Self {
+        self.wrap(truncated_rem(&self.value, &rhs.value))
+    }
+}
+
+impl<T: SoftFloat> core::ops::Rem<T> for Rounded<T> {
+    type Output = Self;
+
+    #[inline]
+    fn rem(self, rhs: T) -> Self {
+        self.wrap(truncated_rem(&self.value, &rhs))
+    }
+}
+
+impl<T: SoftFloat> core::ops::Neg for Rounded<T> {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        self.wrap(self.value.neg())
+    }
+}
+
+/// Compares the values, ignoring the rounding modes.
+impl<T: SoftFloat> PartialEq for Rounded<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value.eq(&other.value)
+    }
+}
+
+/// Compares the values, ignoring the rounding modes.
+impl<T: SoftFloat> PartialOrd for Rounded<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.compare(&other.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, F16, F64};
+
+    #[test]
+    fn rounded_ops() {
+        let rnd = RoundingMode::TowardZero;
+        let one = Rounded::new(F64::from_bits(0x3ff0000000000000), rnd);
+        let three = Rounded::new(F64::from_bits(0x4008000000000000), rnd);
+
+        let (third, flags) = with_flags(|| one / three);
+        assert_eq!(third.value().to_bits(), 0x3fd5555555555555);
+        assert!(flags.is_inexact());
+        assert!(matches!(third.rounding_mode(), RoundingMode::TowardZero));
+        let third = third.with_rounding_mode(RoundingMode::TowardPositive);
+        assert_eq!((third + third).value().to_bits(), 0x3fe5555555555555);
+        assert_eq!(
+            third.fused_mul_add(&three, &-one).value().to_bits(),
+            0xbc90000000000000
+        );
+        assert_eq!(
+            (three * F64::from_bits(0x4000000000000000))
+                .into_inner()
+                .to_bits(),
+            0x4018000000000000
+        );
+        assert_eq!((three - one).value().to_bits(), 0x4000000000000000);
+        assert_eq!((-three % (one + one)).value().to_bits(), 0xbff0000000000000);
+        assert_eq!(
+            three.rem(&(one + one)).value().to_bits(),
+            0xbff0000000000000
+        );
+        assert!(one < three && one == one.with_rounding_mode(RoundingMode::TiesToEven));
+
+        let two = Rounded::new(F16::from_bits(0x4000), RoundingMode::TowardNegative);
+        assert_eq!(two.sqrt().value().to_bits(), 0x3da8);
+        let up = two.sqrt().with_rounding_mode(RoundingMode::TowardPositive);
+        assert_eq!(up.round_to_integral().value().to_bits(), 0x4000);
+    }
+}
@@ -0,0 +1,237 @@
+//! Rounding mode encodings of target ISAs
+//!
+//! Emulators can map the rounding mode fields of guest control registers to [`RoundingMode`]
+//! and back. Reserved encodings, and modes an ISA cannot encode, give `None`.
+//!
+//! Modes print as the RISC-V assembler mnemonics `rne`, `rtz`, `rdn`, `rup` and `rmm`, and
+//! parse from these or from the names of the variants, in any case.
+//!
+//! ```
+//! use softfloat_wrapper::RoundingMode;
+//!
+//! // the frm field of fcsr on RISC-V
+//! let fcsr: u32 = 0x21;
+//! let rnd = RoundingMode::from_riscv_frm((fcsr >> 5) as u8 & 7).unwrap();
+//! assert!(matches!(rnd, RoundingMode::TowardZero));
+//! assert_eq!(rnd.to_x86_rc(), Some(3));
+//! assert_eq!(RoundingMode::TiesToAway.to_arm_rmode(), None);
+//! assert_eq!(rnd.to_string(), "rtz");
+//! assert!(matches!("RDN".parse(), Ok(RoundingMode::TowardNegative)));
+//! ```
+
+use crate::{ParseMnemonicError, RoundingMode};
+use std::fmt;
+use std::str::FromStr;
+
+/// modes in the order of their RISC-V encodings
+const RISCV: [RoundingMode; 5] = [
+    RoundingMode::TiesToEven,
+    RoundingMode::TowardZero,
+    RoundingMode::TowardNegative,
+    RoundingMode::TowardPositive,
+    RoundingMode::TiesToAway,
+];
+
+/// RISC-V mnemonics and variant names, in the order of `RISCV`
+const NAMES: [(&str, &str); 5] = [
+    ("rne", "TiesToEven"),
+    ("rtz", "TowardZero"),
+    ("rdn", "TowardNegative"),
+    ("rup", "TowardPositive"),
+    ("rmm", "TiesToAway"),
+];
+
+/// modes in the order of their x86 encodings
+const X86: [RoundingMode; 4] = [
+    RoundingMode::TiesToEven,
+    RoundingMode::TowardNegative,
+    RoundingMode::TowardPositive,
+    RoundingMode::TowardZero,
+];
+
+/// modes in the order of their Arm encodings
+const ARM: [RoundingMode; 4] = [
+    RoundingMode::TiesToEven,
+    RoundingMode::TowardPositive,
+    RoundingMode::TowardNegative,
+    RoundingMode::TowardZero,
+];
+
+/// modes in the order of their PowerPC encodings, which MIPS and SPARC share
+const PPC: [RoundingMode; 4] = [
+    RoundingMode::TiesToEven,
+    RoundingMode::TowardZero,
+    RoundingMode::TowardPositive,
+    RoundingMode::TowardNegative,
+];
+
+fn position(modes: &[RoundingMode], rnd: RoundingMode) -> Option<u8> {
+    modes
+        .iter()
+        .position(|x| x.to_softfloat() == rnd.to_softfloat())
+        .map(|x| x as u8)
+}
+
+impl RoundingMode {
+    /// Decodes the `frm` field of the RISC-V `fcsr`, or the `rm` field of an instruction.
+    ///
+    /// The reserved encodings 5 and 6 give `None`, like the dynamic mode 7, which has to be
+    /// resolved to `frm` first.
+    pub fn from_riscv_frm(x: u8) -> Option<RoundingMode> {
+        RISCV.get(x as usize).copied()
+    }
+
+    /// Returns the RISC-V `frm` encoding of `self`.
+    pub fn to_riscv_frm(&self) -> u8 {
+        position(&RISCV, *self).unwrap()
+    }
+
+    /// Decodes the `RC` field of the x86 MXCSR or x87 control word, as a 2-bit value.
+    pub fn from_x86_rc(x: u8) -> Option<RoundingMode> {
+        X86.get(x as usize).copied()
+    }
+
+    /// Returns the x86 `RC` encoding of `self`, or `None` for `TiesToAway`, which x86 cannot
+    /// round to.
+    pub fn to_x86_rc(&self) -> Option<u8> {
+        position(&X86, *self)
+    }
+
+    /// Decodes the `RMode` field of the Arm `FPCR` or `FPSCR`, as a 2-bit value.
+    pub fn from_arm_rmode(x: u8) -> Option<RoundingMode> {
+        ARM.get(x as usize).copied()
+    }
+
+    /// Returns the Arm `RMode` encoding of `self`, or `None` for `TiesToAway`, which Arm
+    /// only rounds to in `FRINTA` and `FCVTA*` instructions.
+    pub fn to_arm_rmode(&self) -> Option<u8> {
+        position(&ARM, *self)
+    }
+
+    /// Decodes the `RN` field of the PowerPC `FPSCR`, as a 2-bit value.
+    pub fn from_ppc_rn(x: u8) -> Option<RoundingMode> {
+        PPC.get(x as usize).copied()
+    }
+
+    /// Returns the PowerPC `RN` encoding of `self`, or `None` for `TiesToAway`.
+    pub fn to_ppc_rn(&self) -> Option<u8> {
+        position(&PPC, *self)
+    }
+
+    /// Decodes the `RM` field of the MIPS `FCSR`, as a 2-bit value.
+    pub fn from_mips_rm(x: u8) -> Option<RoundingMode> {
+        PPC.get(x as usize).copied()
+    }
+
+    /// Returns the MIPS `RM` encoding of `self`, or `None` for `TiesToAway`.
+    pub fn to_mips_rm(&self) -> Option<u8> {
+        position(&PPC, *self)
+    }
+
+    /// Decodes the `RD` field of the SPARC `FSR`, as a 2-bit value.
+    pub fn from_sparc_rd(x: u8) -> Option<RoundingMode> {
+        PPC.get(x as usize).copied()
+    }
+
+    /// Returns the SPARC `RD` encoding of `self`, or `None` for `TiesToAway`.
+    pub fn to_sparc_rd(&self) -> Option<u8> {
+        position(&PPC, *self)
+    }
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(NAMES[self.to_riscv_frm() as usize].0)
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = ParseMnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMES
+            .iter()
+            .position(|(mnemonic, name)| {
+                mnemonic.eq_ignore_ascii_case(s) || name.eq_ignore_ascii_case(s)
+            })
+            .map(|i| RISCV[i])
+            .ok_or(ParseMnemonicError(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounding_encodings() {
+        for x in 0..8 {
+            match RoundingMode::from_riscv_frm(x) {
+                Some(rnd) => assert_eq!(rnd.to_riscv_frm(), x),
+                None => assert!(x >= 5),
+            }
+        }
+        for x in 0..8 {
+            match RoundingMode::from_x86_rc(x) {
+                Some(rnd) => assert_eq!(rnd.to_x86_rc(), Some(x)),
+                None => assert!(x >= 4),
+            }
+            match RoundingMode::from_arm_rmode(x) {
+                Some(rnd) => assert_eq!(rnd.to_arm_rmode(), Some(x)),
+                None => assert!(x >= 4),
+            }
+            match RoundingMode::from_ppc_rn(x) {
+                Some(rnd) => assert_eq!(rnd.to_ppc_rn(), Some(x)),
+                None => assert!(x >= 4),
+            }
+            match RoundingMode::from_mips_rm(x) {
+                Some(rnd) => assert_eq!(rnd.to_mips_rm(), Some(x)),
+                None => assert!(x >= 4),
+            }
+            match RoundingMode::from_sparc_rd(x) {
+                Some(rnd) => assert_eq!(rnd.to_sparc_rd(), Some(x)),
+                None => assert!(x >= 4),
+            }
+        }
+        assert!(matches!(
+            RoundingMode::from_riscv_frm(2),
+            Some(RoundingMode::TowardNegative)
+        ));
+        assert!(matches!(
+            RoundingMode::from_x86_rc(2),
+            Some(RoundingMode::TowardPositive)
+        ));
+        assert!(matches!(
+            RoundingMode::from_arm_rmode(2),
+            Some(RoundingMode::TowardNegative)
+        ));
+        assert!(matches!(
+            RoundingMode::from_ppc_rn(1),
+            Some(RoundingMode::TowardZero)
+        ));
+        assert!(matches!(
+            RoundingMode::from_mips_rm(3),
+            Some(RoundingMode::TowardNegative)
+        ));
+        assert!(matches!(
+            RoundingMode::from_sparc_rd(2),
+            Some(RoundingMode::TowardPositive)
+        ));
+        assert_eq!(RoundingMode::TiesToAway.to_riscv_frm(), 4);
+        assert_eq!(RoundingMode::TiesToAway.to_x86_rc(), None);
+    }
+
+    #[test]
+    fn rounding_names() {
+        for rnd in RISCV {
+            let parsed: RoundingMode = rnd.to_string().parse().unwrap();
+            assert_eq!(parsed.to_riscv_frm(), rnd.to_riscv_frm());
+            let parsed: RoundingMode = format!("{:?}", rnd).parse().unwrap();
+            assert_eq!(parsed.to_riscv_frm(), rnd.to_riscv_frm());
+        }
+        assert_eq!(RoundingMode::TiesToEven.to_string(), "rne");
+        assert!(matches!("RMM".parse(), Ok(RoundingMode::TiesToAway)));
+        assert!(matches!("towardzero".parse(), Ok(RoundingMode::TowardZero)));
+        assert!("dyn".parse::<RoundingMode>().is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! [rug](https://crates.io/crates/rug) support
+//!
+//! The float types convert exactly to a `rug::Float` with their own precision, signed zeros,
+//! infinities and NaNs included. `rug::Float` values of any precision convert back with a
+//! single rounding, so a reference computation can run with extra precision in MPFR and be
+//! rounded into the format under test.
+
+use crate::{round_pack, unpack, RoundingMode, SoftFloat};
+use ::rug::float::Special;
+use ::rug::{Float, Integer};
+use num_traits::One;
+
+/// Returns `x` exactly.
+pub(crate) fn to_rug_float<T: SoftFloat>(x: &T) -> Float {
+    let prec = T::MANTISSA_DIGITS;
+    if x.is_nan() {
+        return Float::with_val(prec, Special::Nan);
+    }
+    let special = match (x.is_infinity(), x.is_negative()) {
+        (true, false) => Special::Infinity,
+        (true, true) => Special::NegInfinity,
+        (false, false) => Special::Zero,
+        (false, true) => Special::NegZero,
+    };
+    if x.is_infinity() || x.is_zero() {
+        return Float::with_val(prec, special);
+    }
+
+    let (negative, exp, sig) = unpack(x);
+    let mut ret = Float::with_val(prec, Integer::from(sig));
+    ret <<= exp;
+    if negative {
+        -ret
+    } else {
+        ret
+    }
+}
+
+/// Rounds `x` to `T`.
+pub(crate) fn from_rug_float<T: SoftFloat>(x: &Float, rnd: RoundingMode) -> T {
+    if x.is_nan() {
+        return T::quiet_nan();
+    }
+    if x.is_infinite() || x.is_zero() {
+        let mut ret = if x.is_zero() {
+            T::zero()
+        } else {
+            T::infinity()
+        };
+        if x.is_sign_negative() {
+            ret.set_sign(T::Payload::one());
+        }
+        return ret;
+    }
+
+    let (m, exp) = x.to_integer_exp().unwrap();
+    let m = m.abs();
+    // 126 bits are enough for round_pack, if the discarded ones are or'ed into the lowest
+    let shift = m.significant_bits().saturating_sub(126);
+    let sticky = m.find_one(0).unwrap() < shift;
+    let sig = (m >> shift).to_u128().unwrap() | sticky as u128;
+    // exponents beyond the range of every format underflow or overflow all the same
+    let exp = exp.saturating_add(shift as i32).clamp(-(1 << 20), 1 << 20);
+    round_pack(x.is_sign_negative(), exp, sig, rnd)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{with_flags, FloatBits, RoundingMode, SoftFloat, F16, F32, F64};
+    use ::rug::float::Special;
+    use ::rug::{Float, Integer};
+
+    #[test]
+    fn rug_exact() {
+        for bits in [
+            0x3fd5555555555555,
+            0x8000000000000001,
+            0x7fefffffffffffff,
+            0x8000000000000000,
+            0xfff0000000000000,
+        ] {
+            let a = F64::from_bits(bits);
+            let x = a.to_rug_float();
+            assert_eq!(x.prec(), 53);
+            assert_eq!(x.to_f64().to_bits(), bits);
+            let (ret, flags) = with_flags(|| F64::from_rug_float(&x, RoundingMode::TiesToEven));
+            assert_eq!(ret.to_bits(), bits);
+            assert_eq!(flags.to_bits(), 0);
+        }
+        assert!(F16::quiet_nan().to_rug_float().is_nan());
+        assert!(
+            F16::from_rug_float(&Float::with_val(8, Special::Nan), RoundingMode::TiesToEven)
+                .is_nan()
+        );
+    }
+
+    #[test]
+    fn rug_rounding() {
+        // 2^100 + 2^76 + 1 is just above a tie in F32
+        let x = (Integer::from(1) << 100) + (Integer::from(1) << 76) + Integer::from(1);
+        let mut x = Float::with_val(128, x);
+        x >>= 200;
+        let (ret, flags) = with_flags(|| F32::from_rug_float(&x, RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0x0d800001);
+        assert!(flags.is_inexact());
+        let ret = F32::from_rug_float(&-x, RoundingMode::TowardZero);
+        assert_eq!(ret.to_bits(), 0x8d800000);
+
+        let mut x = Float::with_val(8, 3);
+        x >>= 25;
+        let (ret, flags) = with_flags(|| F16::from_rug_float(&x, RoundingMode::TiesToEven));
+        assert_eq!(ret.to_bits(), 0x0002);
+        assert!(flags.is_underflow());
+        x <<= 100;
+        let (ret, flags) = with_flags(|| F16::from_rug_float(&x, RoundingMode::TiesToEven));
+        assert!(ret.is_infinity());
+        assert!(flags.is_overflow());
+    }
+}
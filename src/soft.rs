@@ -0,0 +1,1192 @@
+//! Pure-Rust implementation of the softfloat-sys functions used by this crate
+//!
+//! Enabled by the `pure-rust` feature, and always used on wasm32-unknown-unknown, it provides the subset of
+//! the softfloat-sys API the float types call, so the rest of the crate does not depend on
+//! which backend is in use.
+//! Results and exception flags are bit-exact with Berkeley SoftFloat built with the
+//! specialization softfloat-sys picks for the target: 8086-SSE on x86 and x86-64, RISC-V
+//! elsewhere, or the one the `specialize-8086-sse` or `specialize-riscv` feature selects.
+//! The rounding mode, exception flags, tininess mode and extF80 rounding precision are
+//! thread-local. There are no extF80 operations, so the rounding precision is only kept.
+
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{round_pack, round_shift, unpack, RoundingMode, SoftFloat, F16, F32, F64};
+use num_traits::{identities::One, NumCast};
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct float16_t {
+    pub v: u16,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct float32_t {
+    pub v: u32,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct float64_t {
+    pub v: u64,
+}
+
+#[cfg(feature = "f128")]
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct float128_t {
+    pub v: [u64; 2],
+}
+
+pub const softfloat_round_near_even: u8 = 0;
+pub const softfloat_round_minMag: u8 = 1;
+pub const softfloat_round_min: u8 = 2;
+pub const softfloat_round_max: u8 = 3;
+pub const softfloat_round_near_maxMag: u8 = 4;
+
+pub const softfloat_tininess_beforeRounding: u8 = 0;
+pub const softfloat_tininess_afterRounding: u8 = 1;
+
+pub const softfloat_flag_inexact: u8 = 1;
+pub const softfloat_flag_underflow: u8 = 2;
+pub const softfloat_flag_overflow: u8 = 4;
+pub const softfloat_flag_infinite: u8 = 8;
+pub const softfloat_flag_invalid: u8 = 16;
+
+/// whether NaN results carry the payload of a NaN operand, like SSE does, rather than
+/// always being the default NaN, like RISC-V does
+const PROPAGATE_PAYLOADS: bool = cfg!(any(
+    feature = "specialize-8086-sse",
+    all(
+        not(feature = "specialize-riscv"),
+        any(target_arch = "x86", target_arch = "x86_64")
+    )
+));
+
+thread_local! {
+    static ROUNDING_MODE: Cell<u8> = const { Cell::new(softfloat_round_near_even) };
+    static EXCEPTION_FLAGS: Cell<u8> = const { Cell::new(0) };
+    static DETECT_TININESS: Cell<u8> = const { Cell::new(softfloat_tininess_afterRounding) };
+    static EXT_F80_ROUNDING_PRECISION: Cell<u8> = const { Cell::new(80) };
+}
+
+pub unsafe fn softfloat_detectTininess_write_helper(x: u8) {
+    DETECT_TININESS.with(|mode| mode.set(x));
+}
+
+pub unsafe fn softfloat_detectTininess_read_helper() -> u8 {
+    DETECT_TININESS.with(Cell::get)
+}
+
+pub unsafe fn extF80_roundingPrecision_write_helper(x: u8) {
+    EXT_F80_ROUNDING_PRECISION.with(|precision| precision.set(x));
+}
+
+pub unsafe fn extF80_roundingPrecision_read_helper() -> u8 {
+    EXT_F80_ROUNDING_PRECISION.with(Cell::get)
+}
+
+pub unsafe fn softfloat_roundingMode_write_helper(x: u8) {
+    ROUNDING_MODE.with(|mode| mode.set(x));
+}
+
+pub unsafe fn softfloat_roundingMode_read_helper() -> u8 {
+    ROUNDING_MODE.with(Cell::get)
+}
+
+pub unsafe fn softfloat_exceptionFlags_write_helper(x: u8) {
+    EXCEPTION_FLAGS.with(|flags| flags.set(x));
+}
+
+pub unsafe fn softfloat_exceptionFlags_read_helper() -> u8 {
+    EXCEPTION_FLAGS.with(Cell::get)
+}
+
+pub unsafe fn softfloat_raiseFlags(x: u8) {
+    raise(x);
+}
+
+fn raise(x: u8) {
+    EXCEPTION_FLAGS.with(|flags| flags.set(flags.get() | x));
+}
+
+fn rounding_mode(x: u8) -> RoundingMode {
+    match x {
+        softfloat_round_minMag => RoundingMode::TowardZero,
+        softfloat_round_min => RoundingMode::TowardNegative,
+        softfloat_round_max => RoundingMode::TowardPositive,
+        softfloat_round_near_maxMag => RoundingMode::TiesToAway,
+        _ => RoundingMode::TiesToEven,
+    }
+}
+
+fn current_rounding_mode() -> RoundingMode {
+    rounding_mode(ROUNDING_MODE.with(Cell::get))
+}
+
+/// 256-bit unsigned integer, wide enough for exact products of two significands
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct U256 {
+    hi: u128,
+    lo: u128,
+}
+
+impl U256 {
+    fn from_u128(x: u128) -> Self {
+        Self { hi: 0, lo: x }
+    }
+
+    fn mul(a: u128, b: u128) -> Self {
+        const MASK: u128 = u64::MAX as u128;
+        let (a1, a0) = (a >> 64, a & MASK);
+        let (b1, b0) = (b >> 64, b & MASK);
+        let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+        let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+        Self {
+            hi: p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64),
+            lo: (p00 & MASK) | (mid << 64),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    fn leading_zeros(&self) -> u32 {
+        if self.hi != 0 {
+            self.hi.leading_zeros()
+        } else {
+            128 + self.lo.leading_zeros()
+        }
+    }
+
+    /// the two bits at `i` and `i + 1`, for `i` below 127 or above 127
+    fn bit_pair(&self, i: u32) -> u128 {
+        if i >= 128 {
+            (self.hi >> (i - 128)) & 3
+        } else {
+            (self.lo >> i) & 3
+        }
+    }
+
+    fn shl(&self, n: u32) -> Self {
+        match n {
+            0 => *self,
+            1..=127 => Self {
+                hi: (self.hi << n) | (self.lo >> (128 - n)),
+                lo: self.lo << n,
+            },
+            _ => Self {
+                hi: self.lo << (n - 128),
+                lo: 0,
+            },
+        }
+    }
+
+    /// Shifts right by `n` bits, or'ing any bits shifted out into the least significant bit.
+    fn shr_jam(&self, n: u32) -> Self {
+        let (hi, lo, sticky) = match n {
+            0 => return *self,
+            1..=127 => (
+                self.hi >> n,
+                (self.lo >> n) | (self.hi << (128 - n)),
+                self.lo << (128 - n) != 0,
+            ),
+            128 => (0, self.hi, self.lo != 0),
+            129..=255 => (
+                0,
+                self.hi >> (n - 128),
+                self.lo != 0 || self.hi << (256 - n) != 0,
+            ),
+            _ => (0, 0, !self.is_zero()),
+        };
+        Self {
+            hi,
+            lo: lo | sticky as u128,
+        }
+    }
+
+    fn add(&self, x: &Self) -> Self {
+        let (lo, carry) = self.lo.overflowing_add(x.lo);
+        Self {
+            hi: self.hi + x.hi + carry as u128,
+            lo,
+        }
+    }
+
+    fn sub(&self, x: &Self) -> Self {
+        let (lo, borrow) = self.lo.overflowing_sub(x.lo);
+        Self {
+            hi: self.hi - x.hi - borrow as u128,
+            lo,
+        }
+    }
+}
+
+fn bits<T: SoftFloat>(x: &T) -> u128 {
+    <u128 as NumCast>::from(x.to_bits()).unwrap()
+}
+
+fn from_bits<T: SoftFloat>(x: u128) -> T {
+    T::from_bits(<T::Payload as NumCast>::from(x).unwrap())
+}
+
+fn copy<T: SoftFloat>(x: &T) -> T {
+    T::from_bits(x.to_bits())
+}
+
+fn quiet_bit<T: SoftFloat>() -> u128 {
+    1 << (T::MANTISSA_BITS - 1)
+}
+
+fn signed_zero<T: SoftFloat>(sign: bool) -> T {
+    from_bits((sign as u128) << T::SIGN_OFFSET)
+}
+
+fn signed_infinity<T: SoftFloat>(sign: bool) -> T {
+    from_bits(bits(&T::infinity()) | (sign as u128) << T::SIGN_OFFSET)
+}
+
+fn is_signaling<T: SoftFloat>(x: &T) -> bool {
+    x.is_nan() && bits(x) & quiet_bit::<T>() == 0
+}
+
+/// the NaN returned by invalid operations
+fn default_nan<T: SoftFloat>() -> T {
+    let mut ret = T::quiet_nan();
+    if PROPAGATE_PAYLOADS {
+        ret.set_sign(T::Payload::one());
+    }
+    ret
+}
+
+fn invalid<T: SoftFloat>() -> T {
+    raise(softfloat_flag_invalid);
+    default_nan()
+}
+
+/// the result of an operation with a NaN operand, raising invalid for signaling NaNs
+fn propagate_nan<T: SoftFloat>(a: &T, b: &T) -> T {
+    if is_signaling(a) || is_signaling(b) {
+        raise(softfloat_flag_invalid);
+    }
+    if PROPAGATE_PAYLOADS {
+        let x = if a.is_nan() { a } else { b };
+        from_bits(bits(x) | quiet_bit::<T>())
+    } else {
+        default_nan()
+    }
+}
+
+/// Rounds `sig * 2^exp`, first folding `sig` into 128 bits.
+fn round_wide<T: SoftFloat>(sign: bool, exp: i32, sig: U256, rnd: RoundingMode) -> T {
+    let shift = 128 - sig.hi.leading_zeros();
+    round_pack(sign, exp + shift as i32, sig.shr_jam(shift).lo, rnd)
+}
+
+/// Rounds the exact sum of two values, each given as its sign, exponent and significand.
+fn add_exact<T: SoftFloat>(a: (bool, i32, U256), b: (bool, i32, U256)) -> T {
+    let rnd = current_rounding_mode();
+    let round_toward_negative = matches!(rnd, RoundingMode::TowardNegative);
+    match (a.2.is_zero(), b.2.is_zero()) {
+        (true, true) if a.0 == b.0 => return signed_zero(a.0),
+        (true, true) => return signed_zero(round_toward_negative),
+        (false, true) => return round_wide(a.0, a.1, a.2, rnd),
+        (true, false) => return round_wide(b.0, b.1, b.2, rnd),
+        (false, false) => (),
+    }
+
+    // align the leading bits at 253, leaving room for the carry
+    let normalize = |(sign, exp, sig): (bool, i32, U256)| {
+        let shift = sig.leading_zeros() - 2;
+        (sign, exp - shift as i32, sig.shl(shift))
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    let (a, b) = if a.1 >= b.1 { (a, b) } else { (b, a) };
+    let sig_b = b.2.shr_jam((a.1 - b.1).min(256) as u32);
+
+    if a.0 == b.0 {
+        round_wide(a.0, a.1, a.2.add(&sig_b), rnd)
+    } else {
+        match a.2.cmp(&sig_b) {
+            Ordering::Greater => round_wide(a.0, a.1, a.2.sub(&sig_b), rnd),
+            Ordering::Less => round_wide(b.0, a.1, sig_b.sub(&a.2), rnd),
+            Ordering::Equal => signed_zero(round_toward_negative),
+        }
+    }
+}
+
+fn add<T: SoftFloat>(a: &T, b: &T, negate_b: bool) -> T {
+    if a.is_nan() || b.is_nan() {
+        return propagate_nan(a, b);
+    }
+    let sign_a = a.is_negative();
+    let sign_b = b.is_negative() != negate_b;
+    if a.is_infinity() {
+        if b.is_infinity() && sign_a != sign_b {
+            return invalid();
+        }
+        return copy(a);
+    }
+    if b.is_infinity() {
+        return signed_infinity(sign_b);
+    }
+    let (_, exp_a, sig_a) = unpack(a);
+    let (_, exp_b, sig_b) = unpack(b);
+    add_exact(
+        (sign_a, exp_a, U256::from_u128(sig_a)),
+        (sign_b, exp_b, U256::from_u128(sig_b)),
+    )
+}
+
+fn mul<T: SoftFloat>(a: &T, b: &T) -> T {
+    if a.is_nan() || b.is_nan() {
+        return propagate_nan(a, b);
+    }
+    let sign = a.is_negative() != b.is_negative();
+    if a.is_infinity() || b.is_infinity() {
+        if a.is_zero() || b.is_zero() {
+            return invalid();
+        }
+        return signed_infinity(sign);
+    }
+    let (_, exp_a, sig_a) = unpack(a);
+    let (_, exp_b, sig_b) = unpack(b);
+    let sig = U256::mul(sig_a, sig_b);
+    round_wide(sign, exp_a + exp_b, sig, current_rounding_mode())
+}
+
+fn mul_add<T: SoftFloat>(a: &T, b: &T, c: &T) -> T {
+    if a.is_nan() || b.is_nan() {
+        return propagate_nan(&propagate_nan(a, b), c);
+    }
+    let sign_prod = a.is_negative() != b.is_negative();
+    if a.is_infinity() || b.is_infinity() {
+        if !a.is_zero() && !b.is_zero() {
+            let ret = signed_infinity(sign_prod);
+            if c.is_nan() {
+                return propagate_nan(&ret, c);
+            }
+            if !c.is_infinity() || c.is_negative() == sign_prod {
+                return ret;
+            }
+        }
+        return propagate_nan(&invalid(), c);
+    }
+    if c.is_nan() {
+        return propagate_nan(&T::zero(), c);
+    }
+    if c.is_infinity() {
+        return copy(c);
+    }
+
+    let (_, exp_a, sig_a) = unpack(a);
+    let (_, exp_b, sig_b) = unpack(b);
+    let (sign_c, exp_c, sig_c) = unpack(c);
+    if sig_a == 0 || sig_b == 0 {
+        if sig_c == 0 && sign_prod != sign_c {
+            return signed_zero(matches!(
+                current_rounding_mode(),
+                RoundingMode::TowardNegative
+            ));
+        }
+        return copy(c);
+    }
+    add_exact(
+        (sign_prod, exp_a + exp_b, U256::mul(sig_a, sig_b)),
+        (sign_c, exp_c, U256::from_u128(sig_c)),
+    )
+}
+
+fn div<T: SoftFloat>(a: &T, b: &T) -> T {
+    if a.is_nan() || b.is_nan() {
+        return propagate_nan(a, b);
+    }
+    let sign = a.is_negative() != b.is_negative();
+    if a.is_infinity() {
+        if b.is_infinity() {
+            return invalid();
+        }
+        return signed_infinity(sign);
+    }
+    if b.is_infinity() {
+        return signed_zero(sign);
+    }
+    if b.is_zero() {
+        if a.is_zero() {
+            return invalid();
+        }
+        raise(softfloat_flag_infinite);
+        return signed_infinity(sign);
+    }
+    if a.is_zero() {
+        return signed_zero(sign);
+    }
+
+    // align the leading bits at 125, so that the quotient has 119 or 120 bits
+    let normalize = |(_, exp, sig): (bool, i32, u128)| {
+        let shift = sig.leading_zeros() - 2;
+        (exp - shift as i32, sig << shift)
+    };
+    let (exp_a, sig_a) = normalize(unpack(a));
+    let (exp_b, sig_b) = normalize(unpack(b));
+    let mut rem = sig_a;
+    let mut q = 0u128;
+    for _ in 0..120 {
+        q <<= 1;
+        if rem >= sig_b {
+            rem -= sig_b;
+            q |= 1;
+        }
+        rem <<= 1;
+    }
+    let sig = q | (rem != 0) as u128;
+    round_pack(sign, exp_a - exp_b - 119, sig, current_rounding_mode())
+}
+
+fn rem<T: SoftFloat>(a: &T, b: &T) -> T {
+    if a.is_nan() || b.is_nan() {
+        return propagate_nan(a, b);
+    }
+    if a.is_infinity() || b.is_zero() {
+        return invalid();
+    }
+    if b.is_infinity() || a.is_zero() {
+        return copy(a);
+    }
+
+    // align the leading bits at 112, so that `|a| < |b| / 2` if `exp_a < exp_b - 1`
+    let normalize = |(sign, exp, sig): (bool, i32, u128)| {
+        let shift = sig.leading_zeros() - 15;
+        (sign, exp - shift as i32, sig << shift)
+    };
+    let (sign, exp_a, sig_a) = normalize(unpack(a));
+    let (_, exp_b, sig_b) = normalize(unpack(b));
+    if exp_a < exp_b - 1 {
+        return copy(a);
+    }
+
+    // reduce `a` modulo `2 * b`, both in units of `2^exp`
+    let (exp, sig_b) = if exp_a >= exp_b {
+        (exp_b, sig_b)
+    } else {
+        (exp_a, sig_b << 1)
+    };
+    let modulus = sig_b << 1;
+    let mut r = sig_a % modulus;
+    let mut n = exp_a - exp;
+    while n > 0 {
+        let k = n.min(12);
+        r = (r << k) % modulus;
+        n -= k;
+    }
+
+    // the quotient rounds to nearest, ties to even, so `-b / 2 <= rem <= b / 2`
+    let twice = r << 1;
+    let (flip, sig) = if twice <= sig_b {
+        (false, r)
+    } else if twice < 3 * sig_b {
+        if r >= sig_b {
+            (false, r - sig_b)
+        } else {
+            (true, sig_b - r)
+        }
+    } else {
+        (true, modulus - r)
+    };
+    round_pack(sign != flip, exp, sig, RoundingMode::TiesToEven)
+}
+
+fn sqrt<T: SoftFloat>(a: &T) -> T {
+    if a.is_nan() {
+        return propagate_nan(a, &T::zero());
+    }
+    if a.is_zero() {
+        return copy(a);
+    }
+    if a.is_negative() {
+        return invalid();
+    }
+    if a.is_infinity() {
+        return copy(a);
+    }
+
+    let (_, exp, sig) = unpack(a);
+    let (exp, sig) = if exp & 1 != 0 {
+        (exp - 1, sig << 1)
+    } else {
+        (exp, sig)
+    };
+    // scale by an even power of two to a 239- or 240-bit radicand, whose root has 120 bits
+    let shift = (240 - (128 - sig.leading_zeros())) & !1;
+    let radicand = U256::from_u128(sig).shl(shift);
+    let mut rem = 0u128;
+    let mut q = 0u128;
+    for i in (0..120).rev() {
+        rem = (rem << 2) | radicand.bit_pair(2 * i);
+        let t = (q << 2) | 1;
+        q <<= 1;
+        if rem >= t {
+            rem -= t;
+            q |= 1;
+        }
+    }
+    let sig = q | (rem != 0) as u128;
+    round_pack(
+        false,
+        (exp - shift as i32) / 2,
+        sig,
+        current_rounding_mode(),
+    )
+}
+
+fn unordered<T: SoftFloat>(a: &T, b: &T, signaling: bool) -> bool {
+    if a.is_nan() || b.is_nan() {
+        if signaling || is_signaling(a) || is_signaling(b) {
+            raise(softfloat_flag_invalid);
+        }
+        true
+    } else {
+        false
+    }
+}
+
+fn eq<T: SoftFloat>(a: &T, b: &T, signaling: bool) -> bool {
+    if unordered(a, b, signaling) {
+        return false;
+    }
+    bits(a) == bits(b) || (a.is_zero() && b.is_zero())
+}
+
+fn lt<T: SoftFloat>(a: &T, b: &T, signaling: bool) -> bool {
+    if unordered(a, b, signaling) {
+        return false;
+    }
+    let (sign_a, sign_b) = (a.is_negative(), b.is_negative());
+    if sign_a != sign_b {
+        sign_a && !(a.is_zero() && b.is_zero())
+    } else {
+        bits(a) != bits(b) && (sign_a != (bits(a) < bits(b)))
+    }
+}
+
+fn le<T: SoftFloat>(a: &T, b: &T, signaling: bool) -> bool {
+    if unordered(a, b, signaling) {
+        return false;
+    }
+    let (sign_a, sign_b) = (a.is_negative(), b.is_negative());
+    if sign_a != sign_b {
+        sign_a || (a.is_zero() && b.is_zero())
+    } else {
+        bits(a) == bits(b) || (sign_a != (bits(a) < bits(b)))
+    }
+}
+
+fn from_int<T: SoftFloat>(sign: bool, x: u64) -> T {
+    round_pack(sign, 0, x as u128, current_rounding_mode())
+}
+
+/// Rounds `a` to an integer of `width` bits, saturating invalid results like softfloat does.
+fn to_int<T: SoftFloat>(a: &T, rnd: RoundingMode, exact: bool, signed: bool, width: u32) -> i128 {
+    let (min, max) = if signed {
+        (-(1i128 << (width - 1)), (1i128 << (width - 1)) - 1)
+    } else {
+        (0, (1i128 << width) - 1)
+    };
+    let overflow = |negative: bool| {
+        raise(softfloat_flag_invalid);
+        match (signed, negative || PROPAGATE_PAYLOADS) {
+            (true, true) => min,
+            (true, false) => max,
+            (false, _) if negative && !PROPAGATE_PAYLOADS => 0,
+            (false, _) => max,
+        }
+    };
+    if a.is_nan() {
+        return overflow(false);
+    }
+    if a.is_infinity() {
+        return overflow(a.is_negative());
+    }
+
+    let (sign, exp, sig) = unpack(a);
+    let (q, inexact) = if exp >= 0 {
+        if exp as u32 + 128 - sig.leading_zeros() > width {
+            return overflow(sign);
+        }
+        (sig << exp, false)
+    } else {
+        round_shift(sign, sig, -exp, rnd)
+    };
+    let ret = if sign { -(q as i128) } else { q as i128 };
+    if ret < min || ret > max {
+        return overflow(sign);
+    }
+    if inexact && exact {
+        raise(softfloat_flag_inexact);
+    }
+    ret
+}
+
+fn round_to_int<T: SoftFloat>(a: &T, rnd: RoundingMode, exact: bool) -> T {
+    if a.is_nan() {
+        return propagate_nan(a, &T::zero());
+    }
+    if a.is_infinity() {
+        return copy(a);
+    }
+    let (sign, exp, sig) = unpack(a);
+    if exp >= 0 {
+        return copy(a);
+    }
+    let (q, inexact) = round_shift(sign, sig, -exp, rnd);
+    if inexact && exact {
+        raise(softfloat_flag_inexact);
+    }
+    round_pack(sign, 0, q, rnd)
+}
+
+fn convert<S: SoftFloat, T: SoftFloat>(a: &S) -> T {
+    let sign = a.is_negative();
+    if a.is_nan() {
+        if is_signaling(a) {
+            raise(softfloat_flag_invalid);
+        }
+        if !PROPAGATE_PAYLOADS {
+            return default_nan();
+        }
+        // the payload keeps its most significant bits
+        let payload = bits(a) & ((1 << S::MANTISSA_BITS) - 1);
+        let payload = if T::MANTISSA_BITS >= S::MANTISSA_BITS {
+            payload << (T::MANTISSA_BITS - S::MANTISSA_BITS)
+        } else {
+            payload >> (S::MANTISSA_BITS - T::MANTISSA_BITS)
+        };
+        let nan = bits(&T::infinity()) | quiet_bit::<T>() | payload;
+        return from_bits(nan | (sign as u128) << T::SIGN_OFFSET);
+    }
+    if a.is_infinity() {
+        return signed_infinity(sign);
+    }
+    let (sign, exp, sig) = unpack(a);
+    round_pack(sign, exp, sig, current_rounding_mode())
+}
+
+/// conversion between a softfloat-sys type and the corresponding float type
+trait Raw {
+    type Float: SoftFloat;
+
+    fn float(self) -> Self::Float;
+
+    fn raw(x: Self::Float) -> Self;
+}
+
+macro_rules! impl_raw {
+    ($raw:ident, $ty:ty) => {
+        impl Raw for $raw {
+            type Float = $ty;
+
+            #[inline]
+            fn float(self) -> $ty {
+                <$ty>::from_bits(self.v)
+            }
+
+            #[inline]
+            fn raw(x: $ty) -> Self {
+                Self { v: x.to_bits() }
+            }
+        }
+    };
+}
+
+impl_raw!(float16_t, F16);
+impl_raw!(float32_t, F32);
+impl_raw!(float64_t, F64);
+
+#[cfg(feature = "f128")]
+impl Raw for float128_t {
+    type Float = F128;
+
+    #[inline]
+    fn float(self) -> F128 {
+        F128::from_sys(self)
+    }
+
+    #[inline]
+    fn raw(x: F128) -> Self {
+        x.sys()
+    }
+}
+
+macro_rules! impl_ops {
+    (
+        $raw:ident,
+        $add:ident,
+        $sub:ident,
+        $mul:ident,
+        $mul_add:ident,
+        $div:ident,
+        $rem:ident,
+        $sqrt:ident,
+        $eq:ident,
+        $lt:ident,
+        $le:ident,
+        $lt_quiet:ident,
+        $le_quiet:ident,
+        $eq_signaling:ident,
+        $is_signaling_nan:ident,
+        $round_to_int:ident,
+        $from_ui32:ident,
+        $from_ui64:ident,
+        $from_i32:ident,
+        $from_i64:ident,
+        $to_ui32:ident,
+        $to_ui64:ident,
+        $to_i32:ident,
+        $to_i64:ident
+    ) => {
+        pub unsafe fn $add(a: $raw, b: $raw) -> $raw {
+            Raw::raw(add(&a.float(), &b.float(), false))
+        }
+
+        pub unsafe fn $sub(a: $raw, b: $raw) -> $raw {
+            Raw::raw(add(&a.float(), &b.float(), true))
+        }
+
+        pub unsafe fn $mul(a: $raw, b: $raw) -> $raw {
+            Raw::raw(mul(&a.float(), &b.float()))
+        }
+
+        pub unsafe fn $mul_add(a: $raw, b: $raw, c: $raw) -> $raw {
+            Raw::raw(mul_add(&a.float(), &b.float(), &c.float()))
+        }
+
+        pub unsafe fn $div(a: $raw, b: $raw) -> $raw {
+            Raw::raw(div(&a.float(), &b.float()))
+        }
+
+        pub unsafe fn $rem(a: $raw, b: $raw) -> $raw {
+            Raw::raw(rem(&a.float(), &b.float()))
+        }
+
+        pub unsafe fn $sqrt(a: $raw) -> $raw {
+            Raw::raw(sqrt(&a.float()))
+        }
+
+        pub unsafe fn $eq(a: $raw, b: $raw) -> bool {
+            eq(&a.float(), &b.float(), false)
+        }
+
+        pub unsafe fn $lt(a: $raw, b: $raw) -> bool {
+            lt(&a.float(), &b.float(), true)
+        }
+
+        pub unsafe fn $le(a: $raw, b: $raw) -> bool {
+            le(&a.float(), &b.float(), true)
+        }
+
+        pub unsafe fn $lt_quiet(a: $raw, b: $raw) -> bool {
+            lt(&a.float(), &b.float(), false)
+        }
+
+        pub unsafe fn $le_quiet(a: $raw, b: $raw) -> bool {
+            le(&a.float(), &b.float(), false)
+        }
+
+        pub unsafe fn $eq_signaling(a: $raw, b: $raw) -> bool {
+            eq(&a.float(), &b.float(), true)
+        }
+
+        pub unsafe fn $is_signaling_nan(a: $raw) -> bool {
+            is_signaling(&a.float())
+        }
+
+        pub unsafe fn $round_to_int(a: $raw, rnd: u8, exact: bool) -> $raw {
+            Raw::raw(round_to_int(&a.float(), rounding_mode(rnd), exact))
+        }
+
+        pub unsafe fn $from_ui32(a: u32) -> $raw {
+            Raw::raw(from_int(false, a as u64))
+        }
+
+        pub unsafe fn $from_ui64(a: u64) -> $raw {
+            Raw::raw(from_int(false, a))
+        }
+
+        pub unsafe fn $from_i32(a: i32) -> $raw {
+            Raw::raw(from_int(a < 0, a.unsigned_abs() as u64))
+        }
+
+        pub unsafe fn $from_i64(a: i64) -> $raw {
+            Raw::raw(from_int(a < 0, a.unsigned_abs()))
+        }
+
+        pub unsafe fn $to_ui32(a: $raw, rnd: u8, exact: bool) -> u64 {
+            to_int(&a.float(), rounding_mode(rnd), exact, false, 32) as u64
+        }
+
+        pub unsafe fn $to_ui64(a: $raw, rnd: u8, exact: bool) -> u64 {
+            to_int(&a.float(), rounding_mode(rnd), exact, false, 64) as u64
+        }
+
+        pub unsafe fn $to_i32(a: $raw, rnd: u8, exact: bool) -> i64 {
+            to_int(&a.float(), rounding_mode(rnd), exact, true, 32) as i64
+        }
+
+        pub unsafe fn $to_i64(a: $raw, rnd: u8, exact: bool) -> i64 {
+            to_int(&a.float(), rounding_mode(rnd), exact, true, 64) as i64
+        }
+    };
+}
+
+macro_rules! impl_convert {
+    ($($convert:ident: $from:ident => $to:ident),*) => {
+        $(
+            pub unsafe fn $convert(a: $from) -> $to {
+                Raw::raw(convert(&a.float()))
+            }
+        )*
+    };
+}
+
+impl_ops!(
+    float16_t,
+    f16_add,
+    f16_sub,
+    f16_mul,
+    f16_mulAdd,
+    f16_div,
+    f16_rem,
+    f16_sqrt,
+    f16_eq,
+    f16_lt,
+    f16_le,
+    f16_lt_quiet,
+    f16_le_quiet,
+    f16_eq_signaling,
+    f16_isSignalingNaN,
+    f16_roundToInt,
+    ui32_to_f16,
+    ui64_to_f16,
+    i32_to_f16,
+    i64_to_f16,
+    f16_to_ui32,
+    f16_to_ui64,
+    f16_to_i32,
+    f16_to_i64
+);
+
+impl_ops!(
+    float32_t,
+    f32_add,
+    f32_sub,
+    f32_mul,
+    f32_mulAdd,
+    f32_div,
+    f32_rem,
+    f32_sqrt,
+    f32_eq,
+    f32_lt,
+    f32_le,
+    f32_lt_quiet,
+    f32_le_quiet,
+    f32_eq_signaling,
+    f32_isSignalingNaN,
+    f32_roundToInt,
+    ui32_to_f32,
+    ui64_to_f32,
+    i32_to_f32,
+    i64_to_f32,
+    f32_to_ui32,
+    f32_to_ui64,
+    f32_to_i32,
+    f32_to_i64
+);
+
+impl_ops!(
+    float64_t,
+    f64_add,
+    f64_sub,
+    f64_mul,
+    f64_mulAdd,
+    f64_div,
+    f64_rem,
+    f64_sqrt,
+    f64_eq,
+    f64_lt,
+    f64_le,
+    f64_lt_quiet,
+    f64_le_quiet,
+    f64_eq_signaling,
+    f64_isSignalingNaN,
+    f64_roundToInt,
+    ui32_to_f64,
+    ui64_to_f64,
+    i32_to_f64,
+    i64_to_f64,
+    f64_to_ui32,
+    f64_to_ui64,
+    f64_to_i32,
+    f64_to_i64
+);
+
+#[cfg(feature = "f128")]
+impl_ops!(
+    float128_t,
+    f128_add,
+    f128_sub,
+    f128_mul,
+    f128_mulAdd,
+    f128_div,
+    f128_rem,
+    f128_sqrt,
+    f128_eq,
+    f128_lt,
+    f128_le,
+    f128_lt_quiet,
+    f128_le_quiet,
+    f128_eq_signaling,
+    f128_isSignalingNaN,
+    f128_roundToInt,
+    ui32_to_f128,
+    ui64_to_f128,
+    i32_to_f128,
+    i64_to_f128,
+    f128_to_ui32,
+    f128_to_ui64,
+    f128_to_i32,
+    f128_to_i64
+);
+
+impl_convert!(
+    f16_to_f32: float16_t => float32_t,
+    f16_to_f64: float16_t => float64_t,
+    f32_to_f16: float32_t => float16_t,
+    f32_to_f64: float32_t => float64_t,
+    f64_to_f16: float64_t => float16_t,
+    f64_to_f32: float64_t => float32_t
+);
+
+#[cfg(feature = "f128")]
+impl_convert!(
+    f16_to_f128: float16_t => float128_t,
+    f32_to_f128: float32_t => float128_t,
+    f64_to_f128: float64_t => float128_t,
+    f128_to_f16: float128_t => float16_t,
+    f128_to_f32: float128_t => float32_t,
+    f128_to_f64: float128_t => float64_t
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatBits, FloatCompare, FloatConvert};
+
+    /// xorshift64, deterministic so that failures reproduce
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// random bits, often with an exponent close to the one of `x`
+        fn near(&mut self, x: u64, exp_offset: u32, exp_mask: u64) -> u64 {
+            let y = self.next();
+            if y & 3 == 0 {
+                return y;
+            }
+            let exp = ((x >> exp_offset) & exp_mask)
+                .wrapping_add(y % 64)
+                .wrapping_sub(32);
+            (y & !(exp_mask << exp_offset)) | ((exp & exp_mask) << exp_offset)
+        }
+    }
+
+    fn same_f64(a: F64, b: f64) -> bool {
+        a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+    }
+
+    fn same_f32(a: F32, b: f32) -> bool {
+        a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+    }
+
+    extern "C" {
+        fn remainder(x: f64, y: f64) -> f64;
+    }
+
+    #[test]
+    fn soft_matches_native_f64() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        for _ in 0..100000 {
+            let x = rng.next();
+            let y = rng.near(x, 52, 0x7ff);
+            let z = rng.near(x.wrapping_add(y), 52, 0x7ff);
+            let (a, b, c) = (F64::from_bits(x), F64::from_bits(y), F64::from_bits(z));
+            let (p, q, r) = (f64::from_bits(x), f64::from_bits(y), f64::from_bits(z));
+            assert!(
+                same_f64(FloatArith::add(&a, b, rnd), p + q),
+                "{:x} + {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f64(FloatArith::sub(&a, b, rnd), p - q),
+                "{:x} - {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f64(FloatArith::mul(&a, b, rnd), p * q),
+                "{:x} * {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f64(FloatArith::div(&a, b, rnd), p / q),
+                "{:x} / {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f64(a.fused_mul_add(b, c, rnd), p.mul_add(q, r)),
+                "{:x} * {:x} + {:x}",
+                x,
+                y,
+                z
+            );
+            assert!(same_f64(a.abs().sqrt(rnd), p.abs().sqrt()), "sqrt {:x}", x);
+            assert!(
+                same_f64(a.rem(b, rnd), unsafe { remainder(p, q) }),
+                "{:x} rem {:x}",
+                x,
+                y
+            );
+            assert_eq!(FloatCompare::lt(&a, b), p < q);
+            assert_eq!(FloatCompare::le(&a, b), p <= q);
+            assert_eq!(FloatCompare::eq(&a, b), p == q);
+            let f = a.to_f32(rnd);
+            assert!(same_f32(f, p as f32), "{:x} as f32", x);
+            assert!(same_f64(f.to_f64(rnd), p as f32 as f64));
+        }
+    }
+
+    #[test]
+    fn soft_matches_native_f32() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for _ in 0..100000 {
+            let x = rng.next() as u32;
+            let y = rng.near(x as u64, 23, 0xff) as u32;
+            let z = rng.near(x.wrapping_add(y) as u64, 23, 0xff) as u32;
+            let (a, b, c) = (F32::from_bits(x), F32::from_bits(y), F32::from_bits(z));
+            let (p, q, r) = (f32::from_bits(x), f32::from_bits(y), f32::from_bits(z));
+            assert!(
+                same_f32(FloatArith::add(&a, b, rnd), p + q),
+                "{:x} + {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f32(FloatArith::mul(&a, b, rnd), p * q),
+                "{:x} * {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f32(FloatArith::div(&a, b, rnd), p / q),
+                "{:x} / {:x}",
+                x,
+                y
+            );
+            assert!(
+                same_f32(a.fused_mul_add(b, c, rnd), p.mul_add(q, r)),
+                "{:x} * {:x} + {:x}",
+                x,
+                y,
+                z
+            );
+            assert!(same_f32(a.abs().sqrt(rnd), p.abs().sqrt()), "sqrt {:x}", x);
+        }
+    }
+
+    /// the normal `x` rounded per `rnd` instead of to nearest, given its exact error `err`
+    fn directed(x: f64, err: f64, rnd: RoundingMode) -> f64 {
+        let up = match rnd {
+            RoundingMode::TowardPositive => true,
+            RoundingMode::TowardNegative => false,
+            _ => x < 0.0,
+        };
+        if err == 0.0 || (err > 0.0) != up {
+            x
+        } else if up == (x > 0.0) {
+            f64::from_bits(x.to_bits() + 1)
+        } else {
+            f64::from_bits(x.to_bits() - 1)
+        }
+    }
+
+    #[test]
+    fn soft_directed_rounding() {
+        let mut rng = Rng(0xd1b54a32d192ed03);
+        for _ in 0..100000 {
+            let x = rng.next();
+            let y = rng.near(x, 52, 0x7ff);
+            let (a, b) = (F64::from_bits(x), F64::from_bits(y));
+            let (p, q) = (f64::from_bits(x), f64::from_bits(y));
+
+            // results rounded to nearest, and their rounding errors computed exactly
+            let s = p + q;
+            let t = s - p;
+            let m = p * q;
+            let d = p / q;
+            let r = p.abs().sqrt();
+            let cases = [
+                (s, (p - (s - t)) + (q - t)),
+                (m, p.mul_add(q, -m)),
+                (d, -d.mul_add(q, -p) / q),
+                (r, -r.mul_add(r, -p.abs())),
+            ];
+            for rnd in [
+                RoundingMode::TowardZero,
+                RoundingMode::TowardNegative,
+                RoundingMode::TowardPositive,
+            ] {
+                let soft = [
+                    FloatArith::add(&a, b, rnd),
+                    FloatArith::mul(&a, b, rnd),
+                    FloatArith::div(&a, b, rnd),
+                    a.abs().sqrt(rnd),
+                ];
+                for (soft, (native, err)) in soft.into_iter().zip(cases) {
+                    let expected = directed(native, err, rnd);
+                    // the error terms are only exact away from overflow and underflow
+                    let tiny = 1e-270;
+                    if native.abs() > tiny
+                        && p.abs() > tiny
+                        && expected.is_normal()
+                        && err.is_finite()
+                    {
+                        assert_eq!(
+                            soft.to_bits(),
+                            expected.to_bits(),
+                            "{:x} {:x} {:?}",
+                            x,
+                            y,
+                            rnd
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
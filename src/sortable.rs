@@ -0,0 +1,102 @@
+//! Order-preserving byte encoding
+//!
+//! `to_sortable_bytes` encodes a value in bytes whose lexicographic order is the
+//! `totalOrder` predicate of IEEE 754: negative NaNs, negative infinity, the negative
+//! numbers, `-0`, `+0`, the positive numbers, positive infinity and positive NaNs, with
+//! NaNs ordered by payload and signaling ones nearer to the numbers than quiet ones. The
+//! bytes of negative values are the bits of the value inverted, and those of positive
+//! values the bits with the sign bit set, most significant byte first, so the encoding can
+//! key ordered stores and radix sorts.
+//!
+//! ```
+//! use softfloat_wrapper::F32;
+//!
+//! let mut v = [0x3f800000, 0xff800000, 0x00000000, 0x80000000].map(F32::from_bits);
+//! v.sort_by_key(|x| x.to_sortable_bytes());
+//! assert_eq!(v.map(|x| x.to_bits()), [0xff800000, 0x80000000, 0x00000000, 0x3f800000]);
+//! assert_eq!(F32::from_sortable_bytes(v[3].to_sortable_bytes()).to_bits(), 0x3f800000);
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{F16, F32, F64};
+
+macro_rules! impl_sortable {
+    ($ty:ident, $bits:ty) => {
+        impl $ty {
+            /// Returns the bytes of `self` whose lexicographic order is the total order of
+            /// the values.
+            pub const fn to_sortable_bytes(&self) -> [u8; core::mem::size_of::<$bits>()] {
+                let bits = self.to_bits();
+                let key = if bits >> (<$bits>::BITS - 1) != 0 {
+                    !bits
+                } else {
+                    bits | 1 << (<$bits>::BITS - 1)
+                };
+                key.to_be_bytes()
+            }
+
+            /// Creates a value from the bytes returned by
+            #[doc = concat!("[`to_sortable_bytes`](", stringify!($ty), "::to_sortable_bytes).")]
+            pub const fn from_sortable_bytes(bytes: [u8; core::mem::size_of::<$bits>()]) -> Self {
+                let key = <$bits>::from_be_bytes(bytes);
+                let bits = if key >> (<$bits>::BITS - 1) != 0 {
+                    key & !(1 << (<$bits>::BITS - 1))
+                } else {
+                    !key
+                };
+                Self::from_bits(bits)
+            }
+        }
+    };
+}
+
+impl_sortable!(F16, u16);
+impl_sortable!(F32, u32);
+impl_sortable!(F64, u64);
+#[cfg(feature = "f128")]
+impl_sortable!(F128, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sortable_total_order() {
+        // in total order
+        let v = [
+            0xffff, // -NaN, largest payload
+            0xfe00, // -qNaN
+            0xfc01, // -sNaN
+            0xfc00, // -inf
+            0xbc00, // -1
+            0x8001, // smallest negative subnormal
+            0x8000, // -0
+            0x0000, // +0
+            0x0001, // smallest positive subnormal
+            0x3c00, // 1
+            0x7bff, // largest finite
+            0x7c00, // +inf
+            0x7c01, // +sNaN
+            0x7e00, // +qNaN
+            0x7fff,
+        ]
+        .map(F16::from_bits);
+        for w in v.windows(2) {
+            assert!(w[0].to_sortable_bytes() < w[1].to_sortable_bytes());
+        }
+        for x in v {
+            let y = F16::from_sortable_bytes(x.to_sortable_bytes());
+            assert_eq!(y.to_bits(), x.to_bits());
+        }
+        assert_eq!(F16::from_bits(0x8000).to_sortable_bytes(), [0x7f, 0xff]);
+        assert_eq!(F16::from_bits(0x0000).to_sortable_bytes(), [0x80, 0x00]);
+
+        let a = F64::from_bits(0x3ff0000000000000);
+        assert_eq!(a.to_sortable_bytes(), [0xbf, 0xf0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            F64::from_sortable_bytes(a.to_sortable_bytes()).to_bits(),
+            a.to_bits()
+        );
+    }
+}
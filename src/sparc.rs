@@ -0,0 +1,451 @@
+//! SPARC floating-point state register
+//!
+//! [`Fpu`] holds the 64-bit `FSR` of SPARC V9, and runs the floating-point instructions the
+//! way SPARC does under it: rounding per `FSR.RD`, the current exceptions `cexc` replaced
+//! by every instruction, the accrued exceptions `aexc`, the four condition codes `fcc0`
+//! to `fcc3`, and the NaN results of SPARC, which prefer the second operand. The exception
+//! flags of the thread are left as they were.
+//!
+//! An instruction traps when one of its exceptions is enabled by `FSR.TEM`: it returns
+//! `None`, sets `ftt` to `IEEE_754_exception` and `cexc` to the trapping exception, leaves
+//! `aexc` as it was, and the destination register must not be written. An enabled
+//! overflow or underflow sets its bit of `cexc` alone, and an enabled underflow traps on
+//! exact tiny results too. The nonstandard mode of `FSR.NS` is implementation dependent,
+//! and not modeled.
+//!
+//! ```
+//! use softfloat_wrapper::sparc::{Fpu, FCC_LESS, FSR_NXA};
+//! use softfloat_wrapper::F32;
+//!
+//! let mut fpu = Fpu::new(0);
+//! let one = F32::from_bits(0x3f800000);
+//! let three = F32::from_bits(0x40400000);
+//! assert_eq!(fpu.fdiv(one, three).unwrap().to_bits(), 0x3eaaaaab);
+//! assert_ne!(fpu.fsr & FSR_NXA, 0);
+//! assert_eq!(fpu.fcmp(2, one, three), Some(FCC_LESS));
+//! assert_eq!(fpu.fcc(2), FCC_LESS);
+//! ```
+
+use crate::{with_flags, FloatCompare, RoundingMode, SoftFloat};
+use num_traits::{NumCast, One, ToPrimitive, Zero};
+
+/// inexact bit of the current exceptions field of `FSR`
+pub const FSR_NXC: u64 = 1 << 0;
+/// division by zero bit of the current exceptions field of `FSR`
+pub const FSR_DZC: u64 = 1 << 1;
+/// underflow bit of the current exceptions field of `FSR`
+pub const FSR_UFC: u64 = 1 << 2;
+/// overflow bit of the current exceptions field of `FSR`
+pub const FSR_OFC: u64 = 1 << 3;
+/// invalid operation bit of the current exceptions field of `FSR`
+pub const FSR_NVC: u64 = 1 << 4;
+/// inexact bit of the accrued exceptions field of `FSR`
+pub const FSR_NXA: u64 = 1 << 5;
+/// division by zero bit of the accrued exceptions field of `FSR`
+pub const FSR_DZA: u64 = 1 << 6;
+/// underflow bit of the accrued exceptions field of `FSR`
+pub const FSR_UFA: u64 = 1 << 7;
+/// overflow bit of the accrued exceptions field of `FSR`
+pub const FSR_OFA: u64 = 1 << 8;
+/// invalid operation bit of the accrued exceptions field of `FSR`
+pub const FSR_NVA: u64 = 1 << 9;
+/// the floating-point trap type field of `FSR`
+pub const FSR_FTT: u64 = 7 << 14;
+/// nonstandard floating-point bit of `FSR`
+pub const FSR_NS: u64 = 1 << 22;
+/// inexact bit of the trap enable mask of `FSR`
+pub const FSR_NXM: u64 = 1 << 23;
+/// division by zero bit of the trap enable mask of `FSR`
+pub const FSR_DZM: u64 = 1 << 24;
+/// underflow bit of the trap enable mask of `FSR`
+pub const FSR_UFM: u64 = 1 << 25;
+/// overflow bit of the trap enable mask of `FSR`
+pub const FSR_OFM: u64 = 1 << 26;
+/// invalid operation bit of the trap enable mask of `FSR`
+pub const FSR_NVM: u64 = 1 << 27;
+
+/// `ftt` of an instruction which raised an enabled exception
+pub const FTT_IEEE_754_EXCEPTION: u64 = 1 << 14;
+
+/// condition code of equal operands
+pub const FCC_EQUAL: u8 = 0;
+/// condition code of a first operand less than the second
+pub const FCC_LESS: u8 = 1;
+/// condition code of a first operand greater than the second
+pub const FCC_GREATER: u8 = 2;
+/// condition code of unordered operands
+pub const FCC_UNORDERED: u8 = 3;
+
+/// the current exceptions field of `FSR`
+const CEXC: u64 = 0x1f;
+
+/// Returns the offset in `FSR` of the condition code `fccn`, from 0 to 3.
+///
+/// # Panics
+///
+/// Panics if `n` is greater than 3.
+pub fn fcc_offset(n: u32) -> u32 {
+    assert!(n < 4, "invalid condition code");
+    if n == 0 {
+        10
+    } else {
+        30 + 2 * n
+    }
+}
+
+/// the NaN `x` converted to `U` and quietened, keeping its sign and the top bits of its
+/// payload
+fn convert_nan<T: SoftFloat, U: SoftFloat>(x: &T) -> U {
+    let frac = x.mantissa().to_u128().unwrap();
+    let frac = if U::MANTISSA_BITS > T::MANTISSA_BITS {
+        frac << (U::MANTISSA_BITS - T::MANTISSA_BITS)
+    } else {
+        frac >> (T::MANTISSA_BITS - U::MANTISSA_BITS)
+    };
+    let mut ret = U::quiet_nan();
+    ret.set_mantissa(ret.mantissa() | <U::Payload as NumCast>::from(frac).unwrap());
+    ret.set_sign(if x.is_negative() {
+        U::Payload::one()
+    } else {
+        U::Payload::zero()
+    });
+    ret
+}
+
+/// The default NaN of SPARC, with every bit of the significand set.
+pub fn default_nan<T: SoftFloat>() -> T {
+    let mut ret = T::quiet_nan();
+    ret.set_sign(T::Payload::zero());
+    let mut all = T::Payload::one();
+    for _ in 0..T::MANTISSA_BITS {
+        all = all + all;
+    }
+    ret.set_mantissa(all - T::Payload::one());
+    ret
+}
+
+/// The floating-point state register of SPARC.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fpu {
+    /// floating-point state register
+    pub fsr: u64,
+}
+
+impl Fpu {
+    /// Returns an `Fpu` with `fsr`.
+    pub fn new(fsr: u64) -> Self {
+        Self { fsr }
+    }
+
+    /// Returns the rounding mode of `FSR.RD`.
+    pub fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::from_sparc_rd((self.fsr >> 30) as u8 & 3).unwrap()
+    }
+
+    /// Returns the condition code `fccn`, from 0 to 3.
+    pub fn fcc(&self, n: u32) -> u8 {
+        (self.fsr >> fcc_offset(n)) as u8 & 3
+    }
+
+    /// Sets the condition code `fccn`, from 0 to 3, to `x`.
+    pub fn set_fcc(&mut self, n: u32, x: u8) {
+        let offset = fcc_offset(n);
+        self.fsr = self.fsr & !(3 << offset) | ((x as u64 & 3) << offset);
+    }
+
+    /// Ends an instruction which raised the exceptions `cexc`, returning `ret` unless it
+    /// traps.
+    fn finish<R>(&mut self, cexc: u64, ret: R) -> Option<R> {
+        let enabled = (self.fsr >> 23) & cexc;
+        let cexc = if enabled & FSR_OFC != 0 {
+            FSR_OFC
+        } else if enabled & FSR_UFC != 0 {
+            FSR_UFC
+        } else {
+            cexc
+        };
+        self.fsr = self.fsr & !(CEXC | FSR_FTT) | cexc;
+        if enabled != 0 {
+            self.fsr |= FTT_IEEE_754_EXCEPTION;
+            return None;
+        }
+        self.fsr |= cexc << 5;
+        Some(ret)
+    }
+
+    /// The NaN result of an instruction on `ops` if one of them is a NaN, with its
+    /// exceptions. Signaling NaNs come before quiet ones, and the last operand first.
+    fn nans<T: SoftFloat + Copy>(&self, ops: &[T]) -> Option<(T, u64)> {
+        let snan = ops.iter().rev().find(|x| x.is_signaling_nan());
+        let nan = snan.or_else(|| ops.iter().rev().find(|x| x.is_nan()))?;
+        let mut ret = *nan;
+        ret.set_mantissa(nan.mantissa() | T::quiet_nan().mantissa());
+        Some((ret, if snan.is_some() { FSR_NVC } else { 0 }))
+    }
+
+    /// Runs `f` in the rounding mode of `FSR`, and returns the result with its exceptions.
+    fn round<T: SoftFloat + Copy>(&self, f: impl FnOnce(RoundingMode) -> T) -> (T, u64) {
+        let (ret, flags) = with_flags(|| f(self.rounding_mode()));
+        if flags.is_invalid() {
+            return (default_nan(), FSR_NVC);
+        }
+        let mut cexc = 0;
+        let bits = [
+            (flags.is_infinite(), FSR_DZC),
+            (flags.is_overflow(), FSR_OFC),
+            (flags.is_underflow(), FSR_UFC),
+            (flags.is_inexact(), FSR_NXC),
+        ];
+        for (raised, bit) in bits {
+            if raised {
+                cexc |= bit;
+            }
+        }
+        if ret.is_subnormal() && self.fsr & FSR_UFM != 0 {
+            // exact tiny results underflow only when the trap is enabled
+            cexc |= FSR_UFC;
+        }
+        (ret, cexc)
+    }
+
+    /// Runs the arithmetic operation `f` on `ops`.
+    fn arith<T: SoftFloat + Copy, const N: usize>(
+        &mut self,
+        ops: [T; N],
+        f: impl FnOnce([T; N], RoundingMode) -> T,
+    ) -> Option<T> {
+        if let Some((nan, cexc)) = self.nans(&ops) {
+            return self.finish(cexc, nan);
+        }
+        let (ret, cexc) = self.round(|rnd| f(ops, rnd));
+        self.finish(cexc, ret)
+    }
+
+    /// `fadd`: `rs1 + rs2`
+    pub fn fadd<T: SoftFloat + Copy>(&mut self, rs1: T, rs2: T) -> Option<T> {
+        self.arith([rs1, rs2], |[a, b], rnd| a.add(b, rnd))
+    }
+
+    /// `fsub`: `rs1 - rs2`
+    pub fn fsub<T: SoftFloat + Copy>(&mut self, rs1: T, rs2: T) -> Option<T> {
+        self.arith([rs1, rs2], |[a, b], rnd| a.sub(b, rnd))
+    }
+
+    /// `fmul`: `rs1 * rs2`
+    pub fn fmul<T: SoftFloat + Copy>(&mut self, rs1: T, rs2: T) -> Option<T> {
+        self.arith([rs1, rs2], |[a, b], rnd| a.mul(b, rnd))
+    }
+
+    /// `fdiv`: `rs1 / rs2`
+    pub fn fdiv<T: SoftFloat + Copy>(&mut self, rs1: T, rs2: T) -> Option<T> {
+        self.arith([rs1, rs2], |[a, b], rnd| a.div(b, rnd))
+    }
+
+    /// `fsqrt`
+    pub fn fsqrt<T: SoftFloat + Copy>(&mut self, rs2: T) -> Option<T> {
+        self.arith([rs2], |[a], rnd| a.sqrt(rnd))
+    }
+
+    /// Compares `rs1` with `rs2` into `fccn`, raising invalid for quiet NaNs if `signal`.
+    fn compare<T: SoftFloat + Copy>(&mut self, n: u32, rs1: T, rs2: T, signal: bool) -> Option<u8> {
+        fcc_offset(n);
+        let (fcc, cexc) = if rs1.is_nan() || rs2.is_nan() {
+            let snan = rs1.is_signaling_nan() || rs2.is_signaling_nan();
+            let cexc = if snan || signal { FSR_NVC } else { 0 };
+            (FCC_UNORDERED, cexc)
+        } else if FloatCompare::eq(&rs1, rs2) {
+            (FCC_EQUAL, 0)
+        } else if rs1.lt_quiet(rs2) {
+            (FCC_LESS, 0)
+        } else {
+            (FCC_GREATER, 0)
+        };
+        let fcc = self.finish(cexc, fcc)?;
+        self.set_fcc(n, fcc);
+        Some(fcc)
+    }
+
+    /// `fcmp`: compares `rs1` with `rs2` into `fccn`, from 0 to 3, raising invalid for
+    /// signaling NaNs only. Returns `None` if the comparison traps, leaving `fccn` as it
+    /// was.
+    pub fn fcmp<T: SoftFloat + Copy>(&mut self, n: u32, rs1: T, rs2: T) -> Option<u8> {
+        self.compare(n, rs1, rs2, false)
+    }
+
+    /// `fcmpe`: compares `rs1` with `rs2` into `fccn`, from 0 to 3, raising invalid for
+    /// any NaN. Returns `None` if the comparison traps, leaving `fccn` as it was.
+    pub fn fcmpe<T: SoftFloat + Copy>(&mut self, n: u32, rs1: T, rs2: T) -> Option<u8> {
+        self.compare(n, rs1, rs2, true)
+    }
+
+    /// `fstod`, `fdtos` and the other conversions between precisions of `rs2`
+    pub fn fto<T: SoftFloat + Copy, U: SoftFloat + Copy>(&mut self, rs2: T) -> Option<U> {
+        if rs2.is_nan() {
+            let cexc = if rs2.is_signaling_nan() { FSR_NVC } else { 0 };
+            return self.finish(cexc, convert_nan(&rs2));
+        }
+        let (ret, cexc) = self.round(|rnd| rs2.convert::<U>(rnd));
+        self.finish(cexc, ret)
+    }
+
+    /// Converts `rs2` to an integer by `f`, rounding toward zero. Invalid conversions
+    /// give `min` for negative operands, and `max` for positive ones and NaNs.
+    fn convert_int<T: SoftFloat + Copy, I>(
+        &mut self,
+        rs2: T,
+        f: impl FnOnce(T) -> I,
+        min: I,
+        max: I,
+    ) -> Option<I> {
+        let (ret, flags) = with_flags(|| f(rs2));
+        if rs2.is_nan() || flags.is_invalid() {
+            let ret = if rs2.is_negative() && !rs2.is_nan() {
+                min
+            } else {
+                max
+            };
+            return self.finish(FSR_NVC, ret);
+        }
+        let cexc = if flags.is_inexact() { FSR_NXC } else { 0 };
+        self.finish(cexc, ret)
+    }
+
+    /// `fstoi`, `fdtoi` and `fqtoi`: `rs2` rounded toward zero to a 32-bit integer
+    pub fn ftoi<T: SoftFloat + Copy>(&mut self, rs2: T) -> Option<i32> {
+        let rnd = RoundingMode::TowardZero;
+        self.convert_int(rs2, |x| x.to_i32(rnd, true), i32::MIN, i32::MAX)
+    }
+
+    /// `fstox`, `fdtox` and `fqtox`: `rs2` rounded toward zero to a 64-bit integer
+    pub fn ftox<T: SoftFloat + Copy>(&mut self, rs2: T) -> Option<i64> {
+        let rnd = RoundingMode::TowardZero;
+        self.convert_int(rs2, |x| x.to_i64(rnd, true), i64::MIN, i64::MAX)
+    }
+
+    /// `fitos`, `fitod` and `fitoq`: the 32-bit integer `rs2` in the rounding mode of `FSR`
+    pub fn fito<T: SoftFloat + Copy>(&mut self, rs2: i32) -> Option<T> {
+        let (ret, cexc) = self.round(|rnd| T::from_i32(rs2, rnd));
+        self.finish(cexc, ret)
+    }
+
+    /// `fxtos`, `fxtod` and `fxtoq`: the 64-bit integer `rs2` in the rounding mode of `FSR`
+    pub fn fxto<T: SoftFloat + Copy>(&mut self, rs2: i64) -> Option<T> {
+        let (ret, cexc) = self.round(|rnd| T::from_i64(rs2, rnd));
+        self.finish(cexc, ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatBits, F32, F64};
+
+    const ONE: F32 = F32::from_bits(0x3f800000);
+    const MIN: F32 = F32::from_bits(0x00800000);
+    const HALF: F32 = F32::from_bits(0x3f000000);
+
+    #[test]
+    fn sparc_exceptions() {
+        let mut fpu = Fpu::default();
+        let third = fpu.fdiv(ONE, F32::from_bits(0x40400000)).unwrap();
+        assert_eq!(third.to_bits(), 0x3eaaaaab);
+        assert_eq!(fpu.fsr, FSR_NXC | FSR_NXA);
+        // cexc is replaced, aexc accumulates
+        assert_eq!(fpu.fadd(ONE, ONE).unwrap().to_bits(), 0x40000000);
+        assert_eq!(fpu.fsr, FSR_NXA);
+        assert!(fpu.fdiv(ONE, F32::from_bits(0)).unwrap().is_infinity());
+        assert_eq!(fpu.fsr, FSR_DZC | FSR_DZA | FSR_NXA);
+
+        // rounding per RD
+        let mut fpu = Fpu::new(3 << 30);
+        let third = fpu.fdiv(ONE, F32::from_bits(0x40400000)).unwrap();
+        assert_eq!(third.to_bits(), 0x3eaaaaaa);
+
+        // enabled exceptions trap, with ftt set and aexc unchanged
+        let max = F32::from_bits(0x7f7fffff);
+        let mut fpu = Fpu::new(FSR_NXM);
+        assert_eq!(fpu.fmul(max, max), None);
+        assert_eq!(
+            fpu.fsr,
+            FSR_NXM | FTT_IEEE_754_EXCEPTION | FSR_OFC | FSR_NXC
+        );
+        assert!(fpu.fmul(ONE, ONE).is_some());
+        assert_eq!(fpu.fsr, FSR_NXM);
+        // an enabled overflow is the only current exception
+        let mut fpu = Fpu::new(FSR_OFM | FSR_NXM);
+        assert_eq!(fpu.fmul(max, max), None);
+        assert_eq!(fpu.fsr & (CEXC | FSR_FTT), FTT_IEEE_754_EXCEPTION | FSR_OFC);
+
+        // exact tiny results underflow only when enabled
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fmul(MIN, HALF).unwrap().to_bits(), 0x00400000);
+        assert_eq!(fpu.fsr, 0);
+        let mut fpu = Fpu::new(FSR_UFM);
+        assert_eq!(fpu.fmul(MIN, HALF), None);
+        assert_eq!(fpu.fsr, FSR_UFM | FTT_IEEE_754_EXCEPTION | FSR_UFC);
+    }
+
+    #[test]
+    fn sparc_nan() {
+        let zero = F64::from_bits(0);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fdiv(zero, zero).unwrap().to_bits(), 0x7fffffffffffffff);
+        assert_eq!(fpu.fsr, FSR_NVC | FSR_NVA);
+        assert_eq!(fpu.fsqrt(ONE.neg()).unwrap().to_bits(), 0x7fffffff);
+
+        // the second operand comes first, but signaling NaNs before quiet ones
+        let qnan = F32::from_bits(0x7fc00001);
+        let snan = F32::from_bits(0xff800002);
+        let other = F32::from_bits(0x7fc00003);
+        assert_eq!(fpu.fadd(qnan, other).unwrap().to_bits(), 0x7fc00003);
+        assert_eq!(fpu.fsr & CEXC, 0);
+        assert_eq!(fpu.fsub(snan, qnan).unwrap().to_bits(), 0xffc00002);
+        assert_eq!(fpu.fsr & CEXC, FSR_NVC);
+        assert_eq!(fpu.fmul(ONE, qnan).unwrap().to_bits(), 0x7fc00001);
+        assert_eq!(fpu.fsr & CEXC, 0);
+
+        let ret: F64 = fpu.fto(snan).unwrap();
+        assert_eq!(ret.to_bits(), 0xfff8000040000000);
+        assert_eq!(fpu.fsr & CEXC, FSR_NVC);
+        let ret: F32 = fpu.fto(F64::from_bits(0x3fb999999999999a)).unwrap();
+        assert_eq!(ret.to_bits(), 0x3dcccccd);
+        assert_eq!(fpu.fsr & CEXC, FSR_NXC);
+
+        let mut fpu = Fpu::new(FSR_NVM);
+        assert_eq!(fpu.fadd(snan, ONE), None);
+        assert_eq!(fpu.fsr, FSR_NVM | FTT_IEEE_754_EXCEPTION | FSR_NVC);
+    }
+
+    #[test]
+    fn sparc_compare_convert() {
+        let qnan = F32::from_bits(0x7fc00000);
+        let mut fpu = Fpu::default();
+        assert_eq!(fpu.fcmp(0, ONE.neg(), ONE), Some(FCC_LESS));
+        assert_eq!(fpu.fcmp(1, ONE, ONE.neg()), Some(FCC_GREATER));
+        assert_eq!(fpu.fcmp(2, ONE, ONE), Some(FCC_EQUAL));
+        assert_eq!(fpu.fcmp(3, qnan, ONE), Some(FCC_UNORDERED));
+        assert_eq!(fpu.fsr, 1 << 10 | 2 << 32 | 3 << 36);
+        assert_eq!(fpu.fcmpe(2, ONE, qnan), Some(FCC_UNORDERED));
+        assert_eq!(fpu.fcc(2), FCC_UNORDERED);
+        assert_eq!(fpu.fsr & CEXC, FSR_NVC);
+        let mut fpu = Fpu::new(FSR_NVM);
+        assert_eq!(fpu.fcmpe(1, qnan, qnan), None);
+        assert_eq!(fpu.fcc(1), FCC_EQUAL);
+
+        // conversions to integers round toward zero whatever RD
+        let mut fpu = Fpu::new(2 << 30);
+        assert_eq!(fpu.ftoi(F32::from_bits(0x3fc00000)), Some(1));
+        assert_eq!(fpu.fsr & CEXC, FSR_NXC);
+        assert_eq!(fpu.ftoi(F32::from_bits(0xcf000000)), Some(i32::MIN));
+        assert_eq!(fpu.fsr & CEXC, 0);
+        assert_eq!(fpu.ftoi(F32::from_bits(0x4f000000)), Some(i32::MAX));
+        assert_eq!(fpu.fsr & CEXC, FSR_NVC);
+        assert_eq!(fpu.ftox(F32::infinity().neg()), Some(i64::MIN));
+        assert_eq!(fpu.ftox(qnan.neg()), Some(i64::MAX));
+        let ret: F32 = fpu.fito(0x1000001).unwrap();
+        assert_eq!(ret.to_bits(), 0x4b800001);
+        assert_eq!(fpu.fsr & CEXC, FSR_NXC);
+        let ret: F64 = fpu.fxto(-1).unwrap();
+        assert_eq!(ret.to_bits(), 0xbff0000000000000);
+    }
+}
@@ -0,0 +1,130 @@
+//! Specialization of the backend
+//!
+//! Berkeley SoftFloat is built with a specialization, which defines what the standard leaves
+//! to implementations: the NaN results of operations, and the results of invalid integer
+//! conversions. softfloat-sys picks it from the target when it is built, 8086-SSE on x86 and
+//! x86-64 and RISC-V elsewhere, and [`specialization`] tells which one the backend in use
+//! has. Both detect tininess after rounding.
+//!
+//! The pure-Rust backend follows the target too, unless the `specialize-8086-sse` or the
+//! `specialize-riscv` feature selects one.
+
+use crate::{strict, sys, with_flags, SoftFloat};
+use num_traits::One;
+
+/// a specialization of Berkeley SoftFloat
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Specialization {
+    /// 8086-SSE: NaN results carry the payload of a NaN operand, the default NaN is negative,
+    /// and invalid integer conversions give the most negative integer, or the largest
+    /// unsigned one
+    X86Sse,
+    /// RISC-V: NaN results are always the default NaN, which is positive, and invalid integer
+    /// conversions saturate, with NaNs giving the largest integer
+    RiscV,
+}
+
+impl Specialization {
+    /// Returns whether NaN results carry the payload of a NaN operand, rather than always
+    /// being the default NaN.
+    pub fn propagates_nan_payloads(&self) -> bool {
+        matches!(self, Specialization::X86Sse)
+    }
+
+    /// Returns the default NaN, returned by invalid operations.
+    pub fn default_nan<T: SoftFloat>(&self) -> T {
+        let mut ret = T::quiet_nan();
+        if let Specialization::X86Sse = self {
+            ret.set_sign(T::Payload::one());
+        }
+        ret
+    }
+
+    /// Returns whether tininess is detected before rounding, rather than after, by default.
+    /// It is after for both specializations; the mode of a thread is part of its
+    /// [`SoftFloatState`](crate::SoftFloatState).
+    pub fn tininess_before_rounding(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the specialization of the backend in use.
+///
+/// The backend is probed with an invalid operation, which does not disturb the exception
+/// flags, so this also tells how a prebuilt softfloat-sys was configured. The probe calls
+/// the backend directly, so the default NaN mode, the fast path, a backend routed with the
+/// `backend` feature, the trace hook, the statistics and the trap handlers do not see it or
+/// change its result.
+///
+/// ```
+/// use softfloat_wrapper::{specialization, Specialization};
+///
+/// let spec = specialization();
+/// if cfg!(all(target_arch = "x86_64", not(feature = "specialize-riscv"))) {
+///     assert_eq!(spec, Specialization::X86Sse);
+/// }
+/// ```
+pub fn specialization() -> Specialization {
+    let zero = sys::float32_t { v: 0 };
+    let (nan, _) = with_flags(|| strict::isolated(|| unsafe { sys::f32_div(zero, zero) }));
+    if nan.v >> 31 != 0 {
+        Specialization::X86Sse
+    } else {
+        Specialization::RiscV
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nan::set_default_nan_mode;
+    use crate::{ExceptionFlags, FloatArith, FloatBits, RoundingMode, F32, F64};
+
+    #[test]
+    fn specialization_probe() {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let spec = specialization();
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+
+        let rnd = RoundingMode::TiesToEven;
+        let nan = F64::from_bits(0x7ff8000000000123);
+        let ret = nan.add(F64::from_bits(0x3ff0000000000000), rnd);
+        if spec.propagates_nan_payloads() {
+            assert_eq!(ret.to_bits(), nan.to_bits());
+        } else {
+            assert_eq!(ret.to_bits(), spec.default_nan::<F64>().to_bits());
+        }
+        let ret = F64::infinity().sub(F64::infinity(), rnd);
+        assert_eq!(ret.to_bits(), spec.default_nan::<F64>().to_bits());
+        assert_eq!(
+            Specialization::X86Sse.default_nan::<F32>().to_bits(),
+            0xffc00000
+        );
+        assert_eq!(
+            Specialization::RiscV.default_nan::<F32>().to_bits(),
+            0x7fc00000
+        );
+    }
+
+    #[test]
+    fn specialization_default_nan_mode() {
+        let spec = specialization();
+        set_default_nan_mode(true);
+        assert_eq!(specialization(), spec);
+        set_default_nan_mode(false);
+        #[cfg(feature = "fast-path")]
+        {
+            crate::fast::set_fast_path(true);
+            assert_eq!(specialization(), spec);
+            crate::fast::set_fast_path(false);
+        }
+        if cfg!(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(feature = "specialize-riscv")
+        )) {
+            assert_eq!(spec, Specialization::X86Sse);
+        }
+    }
+}
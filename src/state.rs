@@ -0,0 +1,301 @@
+//! Snapshots of the floating-point state of a thread
+//!
+//! [`SoftFloatState`] holds the state which affects the operations of a thread, or which they
+//! update: the rounding mode of the operations which do not take one, the exception flags,
+//! the tininess mode and the extF80 rounding precision of the backend, and the default NaN
+//! mode, as well as the fast path with the `fast-path` feature. Emulators
+//! save the state of one guest and restore the one of another on a context switch, and a
+//! library can run its own operations between a save and a restore without disturbing the
+//! ones of its caller.
+//!
+//! A state is plain data, `Send` and `Sync`, so it can serve as the context of a computation
+//! which moves between threads, such as an async task: [`SoftFloatState::run`] makes it the
+//! state of the thread for the operations of a closure only, and keeps what they change, so
+//! nothing of the computation is left in the thread across an `.await`.
+//!
+//! The rounding mode of the backend is not saved: every operation taking a rounding mode
+//! writes it before running, so it is only ever the one of the last operation.
+//!
+//! ```
+//! use softfloat_wrapper::{RoundingMode, SoftFloatState, F32};
+//!
+//! let guest = SoftFloatState {
+//!     rounding_mode: RoundingMode::TowardZero,
+//!     ..SoftFloatState::default()
+//! };
+//! let host = SoftFloatState::save();
+//! guest.restore();
+//! let a = F32::from_bits(0x3f800000) / F32::from_bits(0x40400000);
+//! assert_eq!(a.to_bits(), 0x3eaaaaaa);
+//! let guest = SoftFloatState::save();
+//! assert!(guest.flags.is_inexact());
+//! host.restore();
+//! ```
+
+use crate::nan::{default_nan_mode, set_default_nan_mode};
+use crate::{sys, ExceptionFlags, RoundingMode};
+
+/// The floating-point state of a thread
+#[derive(Copy, Clone, Debug)]
+pub struct SoftFloatState {
+    /// rounding mode of the operations which do not take one, see
+    /// [`RoundingMode::thread_default`]
+    pub rounding_mode: RoundingMode,
+    /// exception flags raised
+    pub flags: ExceptionFlags,
+    /// whether the backend detects tininess before rounding rather than after, which
+    /// decides whether underflow is raised for results rounded up to the smallest normal
+    pub tininess_before_rounding: bool,
+    /// number of bits the backend rounds the significands of extF80 results to: 32, 64 or
+    /// 80
+    pub ext_f80_rounding_precision: u8,
+    /// whether NaN results are replaced by the default NaN, see
+    /// [`set_default_nan_mode`](crate::nan::set_default_nan_mode)
+    pub default_nan_mode: bool,
+    /// whether the fast path is enabled, see [`set_fast_path`](crate::fast::set_fast_path)
+    #[cfg(feature = "fast-path")]
+    pub fast_path: bool,
+}
+
+impl Default for SoftFloatState {
+    /// Returns the state of a new thread: rounding to nearest, ties to even, with no
+    /// exception flags raised, tininess detected after rounding, extF80 results rounded to
+    /// 80 bits, and every mode off.
+    fn default() -> Self {
+        Self {
+            rounding_mode: RoundingMode::TiesToEven,
+            flags: ExceptionFlags::default(),
+            tininess_before_rounding: false,
+            ext_f80_rounding_precision: 80,
+            default_nan_mode: false,
+            #[cfg(feature = "fast-path")]
+            fast_path: false,
+        }
+    }
+}
+
+impl SoftFloatState {
+    /// Returns the state of this thread.
+    pub fn save() -> Self {
+        let mut flags = ExceptionFlags::default();
+        flags.get();
+        let (tininess, precision) = crate::strict::isolated(|| unsafe {
+            (
+                sys::softfloat_detectTininess_read_helper(),
+                sys::extF80_roundingPrecision_read_helper(),
+            )
+        });
+        Self {
+            rounding_mode: RoundingMode::thread_default(),
+            flags,
+            tininess_before_rounding: tininess == sys::softfloat_tininess_beforeRounding,
+            ext_f80_rounding_precision: precision,
+            default_nan_mode: default_nan_mode(),
+            #[cfg(feature = "fast-path")]
+            fast_path: crate::fast::fast_path(),
+        }
+    }
+
+    /// Makes `self` the state of this thread.
+    pub fn restore(&self) {
+        RoundingMode::set_thread_default(self.rounding_mode);
+        self.flags.set();
+        let tininess = if self.tininess_before_rounding {
+            sys::softfloat_tininess_beforeRounding
+        } else {
+            sys::softfloat_tininess_afterRounding
+        };
+        crate::strict::isolated(|| unsafe {
+            sys::softfloat_detectTininess_write_helper(tininess);
+            sys::extF80_roundingPrecision_write_helper(self.ext_f80_rounding_precision);
+        });
+        set_default_nan_mode(self.default_nan_mode);
+        #[cfg(feature = "fast-path")]
+        crate::fast::set_fast_path(self.fast_path);
+    }
+
+    /// Makes `self` the state of this thread, and keeps the previous one in `self`, like a
+    /// context switch.
+    pub fn swap(&mut self) {
+        let outer = Self::save();
+        self.restore();
+        *self = outer;
+    }
+
+    /// Runs `f` with `self` as the state of this thread, and keeps the state `f` leaves in
+    /// `self`. The state of the thread is restored afterwards, even if `f` panics.
+    pub fn run<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.swap();
+        let _back = SwapBack(self);
+        f()
+    }
+}
+
+/// swaps the state of the thread back into the context when dropped, even by a panic
+struct SwapBack<'a>(&'a mut SoftFloatState);
+
+impl Drop for SwapBack<'_> {
+    fn drop(&mut self) {
+        self.0.swap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatArith, F64};
+
+    #[test]
+    fn state_save_restore() {
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        let initial = SoftFloatState::save();
+
+        let mut guest = SoftFloatState::save();
+        guest.rounding_mode = RoundingMode::TowardPositive;
+        guest.flags = ExceptionFlags::from_bits(ExceptionFlags::FLAG_OVERFLOW);
+        guest.default_nan_mode = true;
+        guest.swap();
+        assert_eq!((one / three).to_bits(), 0x3fd5555555555556);
+        let nan = F64::from_bits(0x7ff8000000000123);
+        assert_eq!(
+            nan.add(one, RoundingMode::TiesToEven).to_bits(),
+            0x7ff8000000000000
+        );
+
+        // back to the initial state, with the one of the guest saved
+        guest.swap();
+        assert!(matches!(guest.rounding_mode, RoundingMode::TowardPositive));
+        assert!(guest.flags.is_overflow() && guest.flags.is_inexact());
+        assert!(guest.default_nan_mode);
+        let state = SoftFloatState::save();
+        assert!(matches!(state.rounding_mode, RoundingMode::TiesToEven));
+        assert_eq!(state.flags.to_bits(), initial.flags.to_bits());
+        assert!(!state.default_nan_mode);
+        assert_eq!((one / three).to_bits(), 0x3fd5555555555555);
+
+        SoftFloatState::default().restore();
+    }
+
+    #[test]
+    fn state_tininess() {
+        // (2^-1022 - 2^-1074) (1 + 2^-52) is tiny, but rounds to 2^-1022
+        let a = F64::from_bits(0x000fffffffffffff);
+        let b = F64::from_bits(0x3ff0000000000001);
+        let mul = || with_flags(|| a.mul(b, RoundingMode::TiesToEven));
+
+        let state = SoftFloatState {
+            tininess_before_rounding: true,
+            ext_f80_rounding_precision: 64,
+            ..SoftFloatState::default()
+        };
+        state.restore();
+        let (ret, flags) = mul();
+        assert_eq!(ret.to_bits(), 0x0010000000000000);
+        assert!(flags.is_underflow() && flags.is_inexact());
+        let saved = SoftFloatState::save();
+        assert!(saved.tininess_before_rounding);
+        assert_eq!(saved.ext_f80_rounding_precision, 64);
+
+        SoftFloatState::default().restore();
+        let (ret, flags) = mul();
+        assert_eq!(ret.to_bits(), 0x0010000000000000);
+        assert!(!flags.is_underflow() && flags.is_inexact());
+        let saved = SoftFloatState::save();
+        assert!(!saved.tininess_before_rounding);
+        assert_eq!(saved.ext_f80_rounding_precision, 80);
+    }
+
+    #[test]
+    fn state_strict() {
+        crate::strict::set_strict_mode(true);
+        // the other party detects tininess before rounding, and rounds extF80 results to 32
+        // bits
+        unsafe {
+            sys::softfloat_detectTininess_write_helper(sys::softfloat_tininess_beforeRounding);
+            sys::extF80_roundingPrecision_write_helper(32);
+        }
+        let saved = SoftFloatState::save();
+        assert!(!saved.tininess_before_rounding);
+        assert_eq!(saved.ext_f80_rounding_precision, 80);
+
+        SoftFloatState {
+            ext_f80_rounding_precision: 64,
+            ..saved
+        }
+        .restore();
+        assert_eq!(SoftFloatState::save().ext_f80_rounding_precision, 64);
+        unsafe {
+            assert_eq!(
+                sys::softfloat_detectTininess_read_helper(),
+                sys::softfloat_tininess_beforeRounding
+            );
+            assert_eq!(sys::extF80_roundingPrecision_read_helper(), 32);
+        }
+
+        // the state of this crate becomes the one of the backend again
+        crate::strict::set_strict_mode(false);
+        let saved = SoftFloatState::save();
+        assert!(!saved.tininess_before_rounding);
+        assert_eq!(saved.ext_f80_rounding_precision, 64);
+        SoftFloatState::default().restore();
+    }
+
+    #[test]
+    fn state_run() {
+        fn send_sync<T: Send + Sync>(x: T) -> T {
+            x
+        }
+
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        let mut ctx = send_sync(SoftFloatState::default());
+        ctx.rounding_mode = RoundingMode::TowardZero;
+        let third = ctx.run(|| one / three);
+        assert_eq!(third.to_bits(), 0x3fd5555555555555);
+        assert!(ctx.flags.is_inexact());
+
+        // the context moves to another thread, and carries its state along
+        let ctx = std::thread::spawn(move || {
+            let mut ctx = ctx;
+            ctx.run(|| one.div(F64::from_bits(0), RoundingMode::TiesToEven));
+            let mut flags = ExceptionFlags::default();
+            flags.get();
+            assert!(!flags.is_infinite());
+            ctx
+        })
+        .join()
+        .unwrap();
+        assert!(ctx.flags.is_inexact() && ctx.flags.is_infinite());
+        assert!(matches!(ctx.rounding_mode, RoundingMode::TowardZero));
+        let state = SoftFloatState::save();
+        assert!(!state.flags.is_inexact());
+        assert!(matches!(state.rounding_mode, RoundingMode::TiesToEven));
+    }
+
+    #[test]
+    fn state_run_panic() {
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        let mut ctx = SoftFloatState {
+            rounding_mode: RoundingMode::TowardZero,
+            ..SoftFloatState::default()
+        };
+        let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ctx.run(|| {
+                assert_eq!((one / three).to_bits(), 0x3fd5555555555555);
+                panic!("run");
+            })
+        }));
+        assert!(ret.is_err());
+
+        // the thread is back to its own state, and the context keeps what the closure did
+        assert!(ctx.flags.is_inexact());
+        assert!(matches!(ctx.rounding_mode, RoundingMode::TowardZero));
+        let state = SoftFloatState::save();
+        assert!(!state.flags.is_inexact());
+        assert!(matches!(state.rounding_mode, RoundingMode::TiesToEven));
+        assert_eq!((one / three).to_bits(), 0x3fd5555555555555);
+        SoftFloatState::default().restore();
+    }
+}
@@ -0,0 +1,166 @@
+//! Operation statistics
+//!
+//! Every softfloat operation run by a thread is counted per operation, along with the
+//! exceptions it raised, until [`reset`] is called. Conversions which the crate rounds
+//! without a softfloat function, such as
+//! [`from_i64_scaled`](crate::SoftFloat::from_i64_scaled), are not counted.
+//!
+//! ```
+//! use softfloat_wrapper::{stats, FloatArith, RoundingMode, F32};
+//!
+//! stats::reset();
+//! let a = F32::from_bits(0x3f800000);
+//! a.div(F32::from_bits(0x40400000), RoundingMode::TiesToEven);
+//! let stats = stats::snapshot();
+//! assert_eq!(stats.count("f32_div"), 1);
+//! assert_eq!(stats.inexact(), 1);
+//! ```
+
+use crate::ExceptionFlags;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Operation and exception counts of a thread
+#[derive(Clone, Debug, Default)]
+pub struct OpStats {
+    ops: HashMap<&'static str, u64>,
+    inexact: u64,
+    underflow: u64,
+    overflow: u64,
+    infinite: u64,
+    invalid: u64,
+}
+
+impl OpStats {
+    /// Returns the number of runs of the softfloat function `op`, such as `f32_add`.
+    pub fn count(&self, op: &str) -> u64 {
+        self.ops.get(op).copied().unwrap_or(0)
+    }
+
+    /// Returns the operations run and their counts, the most frequent first.
+    pub fn ops(&self) -> Vec<(&'static str, u64)> {
+        let mut ret: Vec<_> = self.ops.iter().map(|(&op, &n)| (op, n)).collect();
+        ret.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ret
+    }
+
+    /// Returns the number of operations run.
+    pub fn total(&self) -> u64 {
+        self.ops.values().sum()
+    }
+
+    /// Returns the number of operations which raised inexact.
+    pub fn inexact(&self) -> u64 {
+        self.inexact
+    }
+
+    /// Returns the number of operations which raised underflow.
+    pub fn underflow(&self) -> u64 {
+        self.underflow
+    }
+
+    /// Returns the number of operations which raised overflow.
+    pub fn overflow(&self) -> u64 {
+        self.overflow
+    }
+
+    /// Returns the number of operations which raised infinite (division by zero).
+    pub fn infinite(&self) -> u64 {
+        self.infinite
+    }
+
+    /// Returns the number of operations which raised invalid.
+    pub fn invalid(&self) -> u64 {
+        self.invalid
+    }
+}
+
+thread_local! {
+    static STATS: RefCell<OpStats> = RefCell::new(OpStats::default());
+}
+
+/// Returns the counts of this thread.
+pub fn snapshot() -> OpStats {
+    STATS.with(|x| x.borrow().clone())
+}
+
+/// Clears the counts of this thread.
+pub fn reset() {
+    STATS.with(|x| *x.borrow_mut() = OpStats::default());
+}
+
+/// Runs `f`, and returns the counts of its operations instead of adding them to the ones of
+/// this thread.
+pub(crate) fn counted<R>(f: impl FnOnce() -> R) -> (R, OpStats) {
+    let _outer = Outer(STATS.with(|x| x.take()));
+    let ret = f();
+    (ret, STATS.with(|x| x.take()))
+}
+
+/// restores the counts of the caller when dropped, even by a panic
+struct Outer(OpStats);
+
+impl Drop for Outer {
+    fn drop(&mut self) {
+        STATS.with(|x| *x.borrow_mut() = std::mem::take(&mut self.0));
+    }
+}
+
+/// Adds `stats`, counted by another thread, to the counts of this thread.
+pub(crate) fn add(stats: OpStats) {
+    STATS.with(|x| {
+        let mut x = x.borrow_mut();
+        for (op, n) in stats.ops {
+            *x.ops.entry(op).or_insert(0) += n;
+        }
+        x.inexact += stats.inexact;
+        x.underflow += stats.underflow;
+        x.overflow += stats.overflow;
+        x.infinite += stats.infinite;
+        x.invalid += stats.invalid;
+    });
+}
+
+/// Counts a run of `op` which raised `flags`.
+pub(crate) fn count(op: &'static str, flags: ExceptionFlags) {
+    STATS.with(|x| {
+        let mut x = x.borrow_mut();
+        *x.ops.entry(op).or_insert(0) += 1;
+        x.inexact += flags.is_inexact() as u64;
+        x.underflow += flags.is_underflow() as u64;
+        x.overflow += flags.is_overflow() as u64;
+        x.infinite += flags.is_infinite() as u64;
+        x.invalid += flags.is_invalid() as u64;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatConvert, RoundingMode, F32, F64};
+
+    #[test]
+    fn stats_count() {
+        let rnd = RoundingMode::TiesToEven;
+        reset();
+        let a = F32::from_bits(0x3f800000);
+        for _ in 0..3 {
+            a.div(F32::from_bits(0), rnd);
+        }
+        a.add(a, rnd);
+        F64::from_bits(0x7ff0000000000000).to_i32(rnd, true);
+        let stats = snapshot();
+        assert_eq!(
+            stats.ops(),
+            [("f32_div", 3), ("f32_add", 1), ("f64_to_i32", 1)]
+        );
+        assert_eq!(stats.count("f32_sub"), 0);
+        assert_eq!(stats.total(), 5);
+        assert_eq!(stats.infinite(), 3);
+        assert_eq!(stats.invalid(), 1);
+        assert_eq!(stats.inexact(), 0);
+
+        reset();
+        assert_eq!(snapshot().total(), 0);
+    }
+}
@@ -0,0 +1,201 @@
+//! Strict mode
+//!
+//! The softfloat rounding mode, exception flags, tininess mode and extF80 rounding
+//! precision are state of the backend, which code
+//! calling Berkeley SoftFloat directly, from Rust or C, shares with this crate. In the strict
+//! mode, this crate keeps its own copy of the state for the thread, and swaps it with the
+//! one of the backend around every operation and every access of the exception flags, so
+//! neither party sees the modes or flags of the other.
+//!
+//! Turning the mode on makes the copy the state of the backend at that time, and turning
+//! it off makes the backend state the copy again.
+//!
+//! ```
+//! use softfloat_wrapper::strict::set_strict_mode;
+//! use softfloat_wrapper::{ExceptionFlags, FloatArith, RoundingMode, F32};
+//!
+//! set_strict_mode(true);
+//! let a = F32::from_bits(0x3f800000);
+//! a.div(F32::from_bits(0x40400000), RoundingMode::TowardZero);
+//! let mut flags = ExceptionFlags::default();
+//! flags.get();
+//! assert!(flags.is_inexact());
+//! set_strict_mode(false);
+//! ```
+
+use crate::{sys, RoundingMode};
+use std::cell::Cell;
+
+thread_local! {
+    static STRICT_MODE: Cell<bool> = const { Cell::new(false) };
+    /// whether the state of this crate is the one of the backend
+    static SWAPPED: Cell<bool> = const { Cell::new(false) };
+    /// rounding mode, exception flags, tininess mode and extF80 rounding precision of this
+    /// crate, while not swapped in
+    static STATE: Cell<State> = const { Cell::new(INITIAL) };
+}
+
+type State = (u8, u8, u8, u8);
+
+/// state of the backend in a new thread
+const INITIAL: State = (
+    sys::softfloat_round_near_even,
+    0,
+    sys::softfloat_tininess_afterRounding,
+    80,
+);
+
+/// Replaces the state of the backend with `state`, returning the previous one.
+fn swap(state: State) -> State {
+    // the rounding mode this thread last wrote is no longer the one of the backend
+    RoundingMode::forget_last();
+    unsafe {
+        let prev = (
+            sys::softfloat_roundingMode_read_helper(),
+            sys::softfloat_exceptionFlags_read_helper(),
+            sys::softfloat_detectTininess_read_helper(),
+            sys::extF80_roundingPrecision_read_helper(),
+        );
+        sys::softfloat_roundingMode_write_helper(state.0);
+        sys::softfloat_exceptionFlags_write_helper(state.1);
+        sys::softfloat_detectTininess_write_helper(state.2);
+        sys::extF80_roundingPrecision_write_helper(state.3);
+        prev
+    }
+}
+
+/// Turns the strict mode of this thread on if `enabled`, or off.
+pub fn set_strict_mode(enabled: bool) {
+    if enabled == strict_mode() {
+        return;
+    }
+    if enabled {
+        let state = swap(INITIAL);
+        swap(state);
+        STATE.with(|x| x.set(state));
+    } else {
+        swap(STATE.with(Cell::get));
+    }
+    STRICT_MODE.with(|x| x.set(enabled));
+}
+
+/// Returns whether the strict mode is on in this thread.
+pub fn strict_mode() -> bool {
+    STRICT_MODE.with(Cell::get)
+}
+
+/// restores the state of the other party when dropped, even by a panic
+struct Restore(State);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        STATE.with(|x| x.set(swap(self.0)));
+        SWAPPED.with(|x| x.set(false));
+    }
+}
+
+/// Runs `f` with the state of this crate in the backend, in the strict mode.
+pub(crate) fn isolated<R>(f: impl FnOnce() -> R) -> R {
+    if !strict_mode() || SWAPPED.with(Cell::get) {
+        return f();
+    }
+    let _restore = Restore(swap(STATE.with(Cell::get)));
+    SWAPPED.with(|x| x.set(true));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, ExceptionFlags, FloatArith, FloatBits, RoundingMode, F64};
+
+    #[test]
+    fn strict_isolation() {
+        let rnd = RoundingMode::TowardPositive;
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        ExceptionFlags::from_bits(ExceptionFlags::FLAG_UNDERFLOW).set();
+        set_strict_mode(true);
+
+        // the other party rounds down, with overflow raised, detects tininess before
+        // rounding, and rounds extF80 results to 32 bits
+        unsafe {
+            sys::softfloat_roundingMode_write_helper(sys::softfloat_round_min);
+            sys::softfloat_exceptionFlags_write_helper(ExceptionFlags::FLAG_OVERFLOW);
+            sys::softfloat_detectTininess_write_helper(sys::softfloat_tininess_beforeRounding);
+            sys::extF80_roundingPrecision_write_helper(32);
+        }
+        assert_eq!(one.div(three, rnd).to_bits(), 0x3fd5555555555556);
+        // (2^-1022 - 2^-1074) (1 + 2^-52) is tiny before rounding only
+        let tiny = F64::from_bits(0x000fffffffffffff);
+        let (ret, flags) = with_flags(|| tiny.mul(F64::from_bits(0x3ff0000000000001), rnd));
+        assert_eq!(ret.to_bits(), 0x0010000000000000);
+        assert!(!flags.is_underflow() && flags.is_inexact());
+        let (_, flags) = with_flags(|| one.div(F64::zero(), rnd));
+        assert!(flags.is_infinite() && !flags.is_overflow());
+        let mut flag = ExceptionFlags::default();
+        flag.get();
+        assert_eq!(
+            flag.to_bits(),
+            ExceptionFlags::FLAG_UNDERFLOW | ExceptionFlags::FLAG_INEXACT
+        );
+        unsafe {
+            assert_eq!(
+                sys::softfloat_roundingMode_read_helper(),
+                sys::softfloat_round_min
+            );
+            assert_eq!(
+                sys::softfloat_exceptionFlags_read_helper(),
+                ExceptionFlags::FLAG_OVERFLOW
+            );
+            assert_eq!(
+                sys::softfloat_detectTininess_read_helper(),
+                sys::softfloat_tininess_beforeRounding
+            );
+            assert_eq!(sys::extF80_roundingPrecision_read_helper(), 32);
+        }
+
+        set_strict_mode(false);
+        flag.get();
+        assert_eq!(
+            flag.to_bits(),
+            ExceptionFlags::FLAG_UNDERFLOW | ExceptionFlags::FLAG_INEXACT
+        );
+        assert!(matches!(
+            RoundingMode::current(),
+            RoundingMode::TowardPositive
+        ));
+        unsafe {
+            assert_eq!(
+                sys::softfloat_detectTininess_read_helper(),
+                sys::softfloat_tininess_afterRounding
+            );
+            assert_eq!(sys::extF80_roundingPrecision_read_helper(), 80);
+        }
+        ExceptionFlags::default().set();
+    }
+
+    #[test]
+    fn strict_foreign_rounding_mode() {
+        let rnd = RoundingMode::TowardPositive;
+        let one = F64::from_bits(0x3ff0000000000000);
+        let three = F64::from_bits(0x4008000000000000);
+        assert_eq!(one.div(three, rnd).to_bits(), 0x3fd5555555555556);
+
+        // the other party rounds down before the strict mode copies its state, and the
+        // next operation rounds up again, as this thread last did
+        unsafe {
+            sys::softfloat_roundingMode_write_helper(sys::softfloat_round_min);
+        }
+        set_strict_mode(true);
+        assert_eq!(one.div(three, rnd).to_bits(), 0x3fd5555555555556);
+        assert_eq!(
+            one.div(three, RoundingMode::TowardNegative).to_bits(),
+            0x3fd5555555555555
+        );
+        assert_eq!(one.div(three, rnd).to_bits(), 0x3fd5555555555556);
+        set_strict_mode(false);
+        assert_eq!(one.div(three, rnd).to_bits(), 0x3fd5555555555556);
+        ExceptionFlags::default().set();
+    }
+}
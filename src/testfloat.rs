@@ -0,0 +1,828 @@
+//! Verification against [Berkeley TestFloat](https://github.com/ucb-bar/berkeley-testfloat-3)
+//!
+//! `testfloat_gen` prints one test vector per line: the operands, the expected result and
+//! the expected exception flags, all in hexadecimal. [`verify`] runs the named function on
+//! every vector and returns the ones whose result or flags differ.
+//!
+//! Like `testfloat_ver` by default, any NaN matches an expected NaN, and the result of an
+//! integer conversion is not checked when it raises the expected invalid exception.
+//!
+//! ```
+//! use softfloat_wrapper::testfloat::verify;
+//! use softfloat_wrapper::RoundingMode;
+//!
+//! // output of `testfloat_gen -rnear_even f32_add`
+//! let vectors = "3F800000 3F800000 40000000 00\n\
+//!                7F7FFFFF 7F7FFFFF 7F800000 05\n";
+//! let mismatches = verify("f32_add", RoundingMode::TiesToEven, false, vectors).unwrap();
+//! assert!(mismatches.is_empty());
+//! ```
+//!
+//! A [`Generator`] goes the other way, producing vectors for testbenches from operands of
+//! chosen classes, in the same format.
+//!
+//! ```
+//! use softfloat_wrapper::testfloat::{Generator, OperandClass};
+//! use softfloat_wrapper::RoundingMode;
+//!
+//! let mut out = Vec::new();
+//! Generator::new("f16_mul", RoundingMode::TiesToEven, false)
+//!     .unwrap()
+//!     .with_classes(&[OperandClass::Subnormal, OperandClass::Normal])
+//!     .write(100, &mut out)
+//!     .unwrap();
+//! let out = String::from_utf8(out).unwrap();
+//! assert_eq!(out.lines().count(), 100);
+//! assert_eq!(out.lines().next().unwrap().len(), "0000 0000 0000 00".len());
+//! ```
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat, F16, F32, F64};
+use num_traits::NumCast;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// The error type returned when TestFloat output cannot be verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestFloatError {
+    /// the function is not one this crate implements
+    UnknownFunction(String),
+    /// the line, counting from 1, is not a test vector of the function
+    InvalidLine(usize),
+}
+
+impl fmt::Display for TestFloatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestFloatError::UnknownFunction(x) => write!(f, "unknown TestFloat function `{}`", x),
+            TestFloatError::InvalidLine(x) => write!(f, "invalid test vector on line {}", x),
+        }
+    }
+}
+
+impl std::error::Error for TestFloatError {}
+
+/// One line of `testfloat_gen` output
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub operands: Vec<u128>,
+    pub result: u128,
+    pub flags: ExceptionFlags,
+}
+
+/// The error type returned when a line is not a test vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseVectorError {
+    /// field which failed, counting from 1, or 0 if there are too few fields
+    field: usize,
+}
+
+impl ParseVectorError {
+    /// Returns the field which failed, counting from 1, or `None` if the line has fewer
+    /// than the three fields of a vector.
+    pub fn field(&self) -> Option<usize> {
+        match self.field {
+            0 => None,
+            x => Some(x),
+        }
+    }
+}
+
+impl fmt::Display for ParseVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field() {
+            Some(x) => write!(f, "invalid test vector field {}", x),
+            None => f.write_str("expected operands, a result and flags"),
+        }
+    }
+}
+
+impl std::error::Error for ParseVectorError {}
+
+impl FromStr for TestVector {
+    type Err = ParseVectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, x)| {
+                u128::from_str_radix(x, 16).map_err(|_| ParseVectorError { field: i + 1 })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if fields.len() < 3 {
+            return Err(ParseVectorError { field: 0 });
+        }
+        let field = fields.len();
+        let flags = u8::try_from(fields.pop().unwrap()).map_err(|_| ParseVectorError { field })?;
+        let result = fields.pop().unwrap();
+        Ok(TestVector {
+            operands: fields,
+            result,
+            flags: ExceptionFlags::from_bits(flags),
+        })
+    }
+}
+
+/// A test vector the function failed
+#[derive(Clone, Debug)]
+pub struct Mismatch {
+    /// line of the vector, counting from 1
+    pub line: usize,
+    pub vector: TestVector,
+    /// actual result
+    pub result: u128,
+    /// actual exception flags
+    pub flags: ExceptionFlags,
+}
+
+/// Runs the TestFloat function named `function`, such as `f32_mulAdd` or `f64_to_ui32`, on
+/// every test vector of `input` and returns the ones which fail.
+///
+/// `rnd` and `exact` must match the `-r` and `-exact` options given to `testfloat_gen`.
+/// Blank lines are skipped.
+pub fn verify(
+    function: &str,
+    rnd: RoundingMode,
+    exact: bool,
+    input: &str,
+) -> Result<Vec<Mismatch>, TestFloatError> {
+    let function: Function = function.parse()?;
+
+    let mut ret = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || TestFloatError::InvalidLine(i + 1);
+        let vector: TestVector = line.parse().map_err(|_| invalid())?;
+        if vector.operands.len() != function.arity() {
+            return Err(invalid());
+        }
+        let (result, flags) = with_flags(|| function.run(&vector.operands, rnd, exact));
+        let result = result.ok_or_else(invalid)?;
+        if !function.matches(&vector, result, flags) {
+            ret.push(Mismatch {
+                line: i + 1,
+                vector,
+                result,
+                flags,
+            });
+        }
+    }
+    Ok(ret)
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Format {
+    F16,
+    F32,
+    F64,
+    #[cfg(feature = "f128")]
+    F128,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Int {
+    I32,
+    U32,
+    I64,
+    U64,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    MulAdd,
+    Div,
+    Rem,
+    Sqrt,
+    RoundToInt,
+    Eq,
+    Le,
+    Lt,
+    EqSignaling,
+    LeQuiet,
+    LtQuiet,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Function {
+    Float(Format, Op),
+    ToFloat(Format, Format),
+    ToInt(Format, Int),
+    FromInt(Int, Format),
+}
+
+impl FromStr for Format {
+    type Err = TestFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f16" => Ok(Format::F16),
+            "f32" => Ok(Format::F32),
+            "f64" => Ok(Format::F64),
+            #[cfg(feature = "f128")]
+            "f128" => Ok(Format::F128),
+            _ => Err(TestFloatError::UnknownFunction(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Int {
+    type Err = TestFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i32" => Ok(Int::I32),
+            "ui32" => Ok(Int::U32),
+            "i64" => Ok(Int::I64),
+            "ui64" => Ok(Int::U64),
+            _ => Err(TestFloatError::UnknownFunction(s.to_string())),
+        }
+    }
+}
+
+/// Parses a function name, where an unknown part makes the whole name unknown
+impl FromStr for Function {
+    type Err = TestFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || TestFloatError::UnknownFunction(s.to_string());
+        let (src, op) = s.split_once('_').ok_or_else(unknown)?;
+        if let Some(dst) = op.strip_prefix("to_") {
+            return match (src.parse::<Format>(), dst.parse::<Format>()) {
+                (Ok(src), Ok(dst)) => Ok(Function::ToFloat(src, dst)),
+                (Ok(src), Err(_)) => Ok(Function::ToInt(src, dst.parse().map_err(|_| unknown())?)),
+                (Err(_), _) => Ok(Function::FromInt(
+                    src.parse().map_err(|_| unknown())?,
+                    dst.parse().map_err(|_| unknown())?,
+                )),
+            };
+        }
+        let op = match op {
+            "add" => Op::Add,
+            "sub" => Op::Sub,
+            "mul" => Op::Mul,
+            "mulAdd" => Op::MulAdd,
+            "div" => Op::Div,
+            "rem" => Op::Rem,
+            "sqrt" => Op::Sqrt,
+            "roundToInt" => Op::RoundToInt,
+            "eq" => Op::Eq,
+            "le" => Op::Le,
+            "lt" => Op::Lt,
+            "eq_signaling" => Op::EqSignaling,
+            "le_quiet" => Op::LeQuiet,
+            "lt_quiet" => Op::LtQuiet,
+            _ => return Err(unknown()),
+        };
+        Ok(Function::Float(src.parse().map_err(|_| unknown())?, op))
+    }
+}
+
+macro_rules! dispatch {
+    ($format:expr, $f:ident::<_>($($arg:expr),*)) => {
+        match $format {
+            Format::F16 => $f::<F16>($($arg),*),
+            Format::F32 => $f::<F32>($($arg),*),
+            Format::F64 => $f::<F64>($($arg),*),
+            #[cfg(feature = "f128")]
+            Format::F128 => $f::<F128>($($arg),*),
+        }
+    };
+}
+
+fn from_bits<T: SoftFloat>(x: u128) -> Option<T> {
+    Some(T::from_bits(NumCast::from(x)?))
+}
+
+fn to_bits<T: SoftFloat>(x: T) -> u128 {
+    NumCast::from(x.to_bits()).unwrap()
+}
+
+fn is_nan<T: SoftFloat>(x: u128) -> bool {
+    matches!(from_bits::<T>(x), Some(x) if x.is_nan())
+}
+
+fn run_float<T: SoftFloat>(
+    op: Op,
+    operands: &[u128],
+    rnd: RoundingMode,
+    exact: bool,
+) -> Option<u128> {
+    let a: T = from_bits(operands[0])?;
+    let b = || from_bits::<T>(operands[1]);
+    let ret = match op {
+        Op::Add => a.add(b()?, rnd),
+        Op::Sub => a.sub(b()?, rnd),
+        Op::Mul => a.mul(b()?, rnd),
+        Op::MulAdd => a.fused_mul_add(b()?, from_bits(operands[2])?, rnd),
+        Op::Div => a.div(b()?, rnd),
+        Op::Rem => a.rem(b()?, rnd),
+        Op::Sqrt => a.sqrt(rnd),
+        Op::RoundToInt => {
+            let ret = a.round_to_integral(rnd);
+            if exact && a.is_finite() && !ret.eq(&a) {
+                ExceptionFlags::from_bits(ExceptionFlags::FLAG_INEXACT).raise();
+            }
+            ret
+        }
+        Op::Eq => return Some(a.eq(b()?) as u128),
+        Op::Le => return Some(a.le(b()?) as u128),
+        Op::Lt => return Some(a.lt(b()?) as u128),
+        Op::EqSignaling => return Some(a.eq_signaling(b()?) as u128),
+        Op::LeQuiet => return Some(a.le_quiet(b()?) as u128),
+        Op::LtQuiet => return Some(a.lt_quiet(b()?) as u128),
+    };
+    Some(to_bits(ret))
+}
+
+fn run_to_float<T: SoftFloat>(dst: Format, x: u128, rnd: RoundingMode) -> Option<u128> {
+    let x: T = from_bits(x)?;
+    Some(match dst {
+        Format::F16 => to_bits(x.to_f16(rnd)),
+        Format::F32 => to_bits(x.to_f32(rnd)),
+        Format::F64 => to_bits(x.to_f64(rnd)),
+        #[cfg(feature = "f128")]
+        Format::F128 => to_bits(x.to_f128(rnd)),
+    })
+}
+
+fn run_to_int<T: SoftFloat>(dst: Int, x: u128, rnd: RoundingMode, exact: bool) -> Option<u128> {
+    let x: T = from_bits(x)?;
+    Some(match dst {
+        Int::I32 => x.to_i32(rnd, exact) as u32 as u128,
+        Int::U32 => x.to_u32(rnd, exact) as u128,
+        Int::I64 => x.to_i64(rnd, exact) as u64 as u128,
+        Int::U64 => x.to_u64(rnd, exact) as u128,
+    })
+}
+
+fn run_from_int<T: SoftFloat>(src: Int, x: u128, rnd: RoundingMode) -> Option<u128> {
+    Some(to_bits(match src {
+        Int::I32 => T::from_i32(u32::try_from(x).ok()? as i32, rnd),
+        Int::U32 => T::from_u32(u32::try_from(x).ok()?, rnd),
+        Int::I64 => T::from_i64(u64::try_from(x).ok()? as i64, rnd),
+        Int::U64 => T::from_u64(u64::try_from(x).ok()?, rnd),
+    }))
+}
+
+impl Function {
+    pub(crate) fn arity(&self) -> usize {
+        match self {
+            Function::Float(_, Op::MulAdd) => 3,
+            Function::Float(_, Op::Sqrt | Op::RoundToInt) => 1,
+            Function::Float(..) => 2,
+            _ => 1,
+        }
+    }
+
+    /// result of the function, or `None` if an operand does not fit its type
+    pub(crate) fn run(&self, operands: &[u128], rnd: RoundingMode, exact: bool) -> Option<u128> {
+        match *self {
+            Function::Float(format, op) => {
+                dispatch!(format, run_float::<_>(op, operands, rnd, exact))
+            }
+            Function::ToFloat(src, dst) => dispatch!(src, run_to_float::<_>(dst, operands[0], rnd)),
+            Function::ToInt(src, dst) => {
+                dispatch!(src, run_to_int::<_>(dst, operands[0], rnd, exact))
+            }
+            Function::FromInt(src, dst) => dispatch!(dst, run_from_int::<_>(src, operands[0], rnd)),
+        }
+    }
+
+    fn operand(&self) -> Operand {
+        match *self {
+            Function::Float(format, _)
+            | Function::ToFloat(format, _)
+            | Function::ToInt(format, _) => Operand::Float(format),
+            Function::FromInt(int, _) => Operand::Int(int),
+        }
+    }
+
+    fn digits(&self) -> Digits {
+        let float = |format| match format {
+            Format::F16 => 4,
+            Format::F32 => 8,
+            Format::F64 => 16,
+            #[cfg(feature = "f128")]
+            Format::F128 => 32,
+        };
+        let int = |int| match int {
+            Int::I32 | Int::U32 => 8,
+            Int::I64 | Int::U64 => 16,
+        };
+        let operand = match self.operand() {
+            Operand::Float(format) => float(format),
+            Operand::Int(x) => int(x),
+        };
+        let result = match *self {
+            Function::Float(
+                _,
+                Op::Eq | Op::Le | Op::Lt | Op::EqSignaling | Op::LeQuiet | Op::LtQuiet,
+            ) => 1,
+            Function::Float(format, _)
+            | Function::ToFloat(_, format)
+            | Function::FromInt(_, format) => float(format),
+            Function::ToInt(_, x) => int(x),
+        };
+        Digits {
+            operand: [operand; 3],
+            result,
+        }
+    }
+
+    fn matches(&self, vector: &TestVector, result: u128, flags: ExceptionFlags) -> bool {
+        if flags.to_bits() != vector.flags.to_bits() {
+            return false;
+        }
+        let result_format = match *self {
+            Function::Float(
+                _,
+                Op::Eq | Op::Le | Op::Lt | Op::EqSignaling | Op::LeQuiet | Op::LtQuiet,
+            ) => None,
+            Function::Float(format, _)
+            | Function::ToFloat(_, format)
+            | Function::FromInt(_, format) => Some(format),
+            Function::ToInt(..) => {
+                if flags.is_invalid() {
+                    return true;
+                }
+                None
+            }
+        };
+        result == vector.result
+            || matches!(result_format, Some(format)
+                if dispatch!(format, is_nan::<_>(result))
+                    && dispatch!(format, is_nan::<_>(vector.result)))
+    }
+}
+
+/// Class of the generated operands
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OperandClass {
+    Zero,
+    Subnormal,
+    /// normal numbers, half of them with exponents within the precision of one
+    Normal,
+    Infinity,
+    QuietNan,
+    SignalingNan,
+}
+
+impl OperandClass {
+    const ALL: [OperandClass; 6] = [
+        OperandClass::Zero,
+        OperandClass::Subnormal,
+        OperandClass::Normal,
+        OperandClass::Infinity,
+        OperandClass::QuietNan,
+        OperandClass::SignalingNan,
+    ];
+}
+
+/// Generator of test vectors for a TestFloat function, in the format of `testfloat_gen`
+///
+/// Each operand of a floating-point type is of a class chosen among the given ones, with a
+/// random sign and significand, and integer operands are random, with their extremes and
+/// small values more frequent. Results and flags are the ones of this crate. The sequence
+/// is determined by the seed, so it can be generated again.
+///
+/// The generator is an iterator of [`TestVector`]s, which [`line`](Self::line) formats.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    function: Function,
+    rnd: RoundingMode,
+    exact: bool,
+    classes: Vec<OperandClass>,
+    state: u64,
+}
+
+impl Generator {
+    /// Creates a generator for the TestFloat function named `function`, such as `f32_mulAdd`
+    /// or `f64_to_ui32`, with operands of every class.
+    ///
+    /// `rnd` and `exact` have the meaning of the `-r` and `-exact` options of `testfloat_gen`.
+    pub fn new(function: &str, rnd: RoundingMode, exact: bool) -> Result<Self, TestFloatError> {
+        let function: Function = function.parse()?;
+        Ok(Generator {
+            function,
+            rnd,
+            exact,
+            classes: OperandClass::ALL.to_vec(),
+            state: 0x2545f4914f6cdd1d,
+        })
+    }
+
+    /// Returns `self` generating floating-point operands of `classes` only.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `classes` is empty.
+    pub fn with_classes(self, classes: &[OperandClass]) -> Self {
+        assert!(!classes.is_empty(), "no operand class");
+        Generator {
+            classes: classes.to_vec(),
+            ..self
+        }
+    }
+
+    /// Returns `self` generating the sequence of `seed`.
+    pub fn with_seed(self, seed: u64) -> Self {
+        // xorshift never leaves zero
+        Generator {
+            state: seed | 1,
+            ..self
+        }
+    }
+
+    /// Formats `vector` as a line of `testfloat_gen` output, without the line break.
+    pub fn line(&self, vector: &TestVector) -> String {
+        let mut ret = String::new();
+        let digits = self.function.digits();
+        for (x, width) in vector.operands.iter().zip(digits.operand) {
+            ret.push_str(&format!("{:0w$X} ", x, w = width));
+        }
+        ret.push_str(&format!(
+            "{:0w$X} {:02X}",
+            vector.result,
+            vector.flags.to_bits(),
+            w = digits.result
+        ));
+        ret
+    }
+
+    /// Writes `count` vectors to `out`, one per line.
+    pub fn write<W: io::Write>(&mut self, count: usize, mut out: W) -> io::Result<()> {
+        for _ in 0..count {
+            let vector = self.next().unwrap();
+            writeln!(out, "{}", self.line(&vector))?;
+        }
+        Ok(())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    fn next_u128(&mut self) -> u128 {
+        (self.next_u64() as u128) << 64 | self.next_u64() as u128
+    }
+
+    fn operand(&mut self, kind: Operand) -> u128 {
+        match kind {
+            Operand::Float(format) => {
+                let i = self.next_u64() as usize % self.classes.len();
+                let class = self.classes[i];
+                let (x, y) = (self.next_u128(), self.next_u64());
+                dispatch!(format, float_operand::<_>(class, x, y))
+            }
+            Operand::Int(int) => {
+                let bits = match int {
+                    Int::I32 | Int::U32 => 32,
+                    Int::I64 | Int::U64 => 64,
+                };
+                let mask = u64::MAX >> (64 - bits);
+                let x = self.next_u64();
+                let ret = match x % 8 {
+                    // zero, one, the extremes of both signednesses, and their neighbours
+                    0 => [0, 1, mask, mask >> 1, !(mask >> 1) & mask][(x >> 3) as usize % 5]
+                        .wrapping_add((x >> 8) % 3)
+                        .wrapping_sub(1),
+                    // small magnitudes of either sign
+                    1 => ((x >> 8) % 256) ^ if x & 0x80 != 0 { mask } else { 0 },
+                    _ => x >> (64 - bits + (x >> 8) as u32 % bits),
+                };
+                (ret & mask) as u128
+            }
+        }
+    }
+}
+
+impl Iterator for Generator {
+    type Item = TestVector;
+
+    fn next(&mut self) -> Option<TestVector> {
+        let kind = self.function.operand();
+        let operands: Vec<u128> = (0..self.function.arity())
+            .map(|_| self.operand(kind))
+            .collect();
+        let (result, flags) = with_flags(|| self.function.run(&operands, self.rnd, self.exact));
+        Some(TestVector {
+            operands,
+            result: result.unwrap(),
+            flags,
+        })
+    }
+}
+
+/// Returns the bits of an operand of `class`, from random bits `x` and `y`.
+fn float_operand<T: SoftFloat>(class: OperandClass, x: u128, y: u64) -> u128 {
+    let mantissa_bits = T::MANTISSA_BITS;
+    let exponent_max = (1u128 << T::EXPONENT_BITS) - 1;
+    let bias = exponent_max >> 1;
+    let sign = (x >> 127) << T::SIGN_OFFSET;
+    let mantissa = x & ((1 << mantissa_bits) - 1);
+    let quiet = 1 << (mantissa_bits - 1);
+    let (exponent, mantissa) = match class {
+        OperandClass::Zero => (0, 0),
+        OperandClass::Subnormal => (0, mantissa.max(1)),
+        OperandClass::Normal => {
+            let y = y as u128;
+            let exponent = if y & 1 != 0 {
+                1 + (y >> 1) % (exponent_max - 1)
+            } else {
+                let span = 2 * mantissa_bits as u128 + 5;
+                bias + (y >> 1) % span - span / 2
+            };
+            (exponent, mantissa)
+        }
+        OperandClass::Infinity => (exponent_max, 0),
+        OperandClass::QuietNan => (exponent_max, mantissa | quiet),
+        OperandClass::SignalingNan => (exponent_max, (mantissa & !quiet).max(1)),
+    };
+    sign | exponent << T::EXPONENT_OFFSET | mantissa
+}
+
+/// Operands of a function
+#[derive(Copy, Clone, Debug)]
+enum Operand {
+    Float(Format),
+    Int(Int),
+}
+
+/// Hexadecimal digits of the fields of a line
+struct Digits {
+    operand: [usize; 3],
+    result: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testfloat_verify() {
+        let vectors = "\
+3F800000 3F800000 40000000 00
+7F7FFFFF 7F7FFFFF 7F800000 05
+
+7F800000 FF800000 7FC00000 10
+7F800001 3F800000 7FC00001 10
+3F800000 3F800000 40000001 00
+00000001 00000001 00000002 01
+";
+        let mismatches = verify("f32_add", RoundingMode::TiesToEven, false, vectors).unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].line, 6);
+        assert_eq!(mismatches[0].result, 0x40000000);
+        assert_eq!(mismatches[1].line, 7);
+        assert_eq!(mismatches[1].flags.to_bits(), 0);
+
+        let vectors = "\
+01000001 4B800000 01
+FFFFFFFF BF800000 00
+";
+        assert!(
+            verify("i32_to_f32", RoundingMode::TiesToEven, false, vectors)
+                .unwrap()
+                .is_empty()
+        );
+
+        let vectors = "\
+3FC00000 00000002 01
+7FC00000 80000000 10
+4F000000 00000000 10
+";
+        assert!(
+            verify("f32_to_i32", RoundingMode::TiesToEven, true, vectors)
+                .unwrap()
+                .is_empty()
+        );
+
+        let vectors = "\
+3FC00000 40000000 01
+BF000000 80000000 01
+";
+        assert!(
+            verify("f32_roundToInt", RoundingMode::TiesToEven, true, vectors)
+                .unwrap()
+                .is_empty()
+        );
+
+        let vectors = "\
+7FC00000 3F800000 0 10
+7FC00000 3F800000 0 00
+";
+        let mismatches = verify("f32_lt", RoundingMode::TiesToEven, false, vectors).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].line, 2);
+
+        assert!(verify(
+            "f64_to_f16",
+            RoundingMode::TowardZero,
+            false,
+            "3FF0000000000000 3C00 00"
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn testfloat_errors() {
+        assert_eq!(
+            verify("f32_foo", RoundingMode::TiesToEven, false, "").unwrap_err(),
+            TestFloatError::UnknownFunction("f32_foo".to_string())
+        );
+        assert_eq!(
+            verify("f32_add", RoundingMode::TiesToEven, false, "3F800000 00 00").unwrap_err(),
+            TestFloatError::InvalidLine(1)
+        );
+        assert_eq!(
+            verify(
+                "f16_sqrt",
+                RoundingMode::TiesToEven,
+                false,
+                "\n3F800000 3F800000 00"
+            )
+            .unwrap_err(),
+            TestFloatError::InvalidLine(2)
+        );
+
+        let err = "3F800000 3G800000 3F800000 00"
+            .parse::<TestVector>()
+            .unwrap_err();
+        assert_eq!(err.field(), Some(2));
+        assert_eq!(err.to_string(), "invalid test vector field 2");
+        let err = "3F800000 3F800000 100".parse::<TestVector>().unwrap_err();
+        assert_eq!(err.field(), Some(3));
+        let err = "3F800000 00".parse::<TestVector>().unwrap_err();
+        assert_eq!(err.field(), None);
+        assert_eq!(err.to_string(), "expected operands, a result and flags");
+        assert!(matches!(
+            "f32_to_i16".parse::<Function>(),
+            Err(TestFloatError::UnknownFunction(x)) if x == "f32_to_i16"
+        ));
+    }
+
+    #[test]
+    fn testfloat_generate() {
+        let rnd = RoundingMode::TowardPositive;
+        for function in [
+            "f16_add",
+            "f32_mulAdd",
+            "f64_sqrt",
+            "f32_lt_quiet",
+            "f64_to_f16",
+            "f32_to_ui32",
+            "i64_to_f64",
+            "ui32_to_f16",
+        ] {
+            let mut out = Vec::new();
+            let mut generator = Generator::new(function, rnd, true).unwrap();
+            generator.write(200, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(out.lines().count(), 200);
+            assert!(verify(function, rnd, true, &out).unwrap().is_empty());
+        }
+
+        let generator = Generator::new("f32_div", rnd, false)
+            .unwrap()
+            .with_classes(&[OperandClass::SignalingNan])
+            .with_seed(7);
+        let vectors: Vec<_> = generator.clone().take(20).collect();
+        for vector in &vectors {
+            assert!(vector
+                .operands
+                .iter()
+                .all(|&x| x & 0x7fc00000 == 0x7f800000));
+            assert!(vector.flags.is_invalid());
+        }
+        let line = generator.line(&vectors[0]);
+        assert_eq!(line.len(), "00000000 00000000 00000000 00".len());
+        assert_eq!(
+            generator.take(20).last().unwrap().operands,
+            vectors[19].operands
+        );
+
+        let vector = Generator::new("f32_eq", rnd, false)
+            .unwrap()
+            .with_classes(&[OperandClass::Zero])
+            .next()
+            .unwrap();
+        assert_eq!(vector.result, 1);
+        assert!(matches!(
+            Generator::new("f32_foo", rnd, false),
+            Err(TestFloatError::UnknownFunction(_))
+        ));
+    }
+}
@@ -0,0 +1,480 @@
+//! Operation tracing
+//!
+//! A hook set with [`set_trace_hook`] is called after every softfloat operation run by the
+//! thread, with its operands, result, rounding mode and the flags it raised. Operations run
+//! inside the hook are not traced.
+//!
+//! Only calls of softfloat functions are traced. Conversions which this crate rounds
+//! itself, such as [`from_i64_scaled`](crate::SoftFloat::from_i64_scaled),
+//! [`to_fixed`](crate::SoftFloat::to_fixed) and [`convert`](crate::SoftFloat::convert) to a
+//! format other than `F16`, `F32`, `F64` and `F128`, raise their flags without a record, so
+//! [`record`] does not log them either.
+//!
+//! ```
+//! use softfloat_wrapper::trace::{clear_trace_hook, set_trace_hook, OpRecord};
+//! use softfloat_wrapper::{FloatArith, RoundingMode, F32};
+//!
+//! fn print(record: OpRecord) {
+//!     println!("{} {:x?} = {:x} {:?}", record.op, record.operands(), record.result, record.flags);
+//! }
+//!
+//! set_trace_hook(print);
+//! let a = F32::from_bits(0x3f800000);
+//! a.div(F32::from_bits(0x40400000), RoundingMode::TiesToEven);
+//! clear_trace_hook();
+//! ```
+//!
+//! [`record`] logs the operations run by a closure, one per line, like `testfloat_gen` test
+//! vectors preceded by the function and its options. [`replay`] runs them again and returns
+//! the ones whose result or flags differ, to reproduce a bug on another machine or backend.
+//!
+//! ```
+//! use softfloat_wrapper::trace::{record, replay};
+//! use softfloat_wrapper::{FloatArith, RoundingMode, F32};
+//!
+//! let a = F32::from_bits(0x3f800000);
+//! let (_, log) = record(|| a.div(F32::from_bits(0x40400000), RoundingMode::TiesToEven));
+//! assert_eq!(log, "f32_div -rnear_even 3F800000 40400000 3EAAAAAB 01\n");
+//! assert!(replay(&log).unwrap().is_empty());
+//! ```
+
+use crate::testfloat::{Function, Mismatch, TestFloatError, TestVector};
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{with_flags, ExceptionFlags, RoundingMode, F16, F32, F64};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Write};
+
+/// An operation reported to the trace hook
+#[derive(Copy, Clone, Debug)]
+pub struct OpRecord {
+    /// name of the softfloat function, such as `f32_add`
+    pub op: &'static str,
+    operands: [u128; 3],
+    arity: usize,
+    /// result, as 0 or 1 for comparisons and in two's complement for signed integers
+    pub result: u128,
+    /// rounding mode, `None` for comparisons
+    pub rnd: Option<RoundingMode>,
+    /// whether a conversion to an integer raises inexact
+    pub exact: bool,
+    /// flags raised by the operation alone
+    pub flags: ExceptionFlags,
+}
+
+impl OpRecord {
+    /// Returns the operands, as the bits of floats or integers like the result.
+    pub fn operands(&self) -> &[u128] {
+        &self.operands[..self.arity]
+    }
+}
+
+impl fmt::Display for OpRecord {
+    /// Formats `self` as a line of the log of [`record`], without the line break.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+        if let Some(rnd) = self.rnd {
+            write!(f, " -r{}", rounding_name(rnd))?;
+        }
+        if self.exact {
+            write!(f, " -exact")?;
+        }
+        for x in self.operands() {
+            write!(f, " {:X}", x)?;
+        }
+        write!(f, " {:X} {:02X}", self.result, self.flags.to_bits())
+    }
+}
+
+/// Returns the name of `rnd` in the `-r` option of TestFloat.
+fn rounding_name(rnd: RoundingMode) -> &'static str {
+    match rnd {
+        RoundingMode::TiesToEven => "near_even",
+        RoundingMode::TowardZero => "minMag",
+        RoundingMode::TowardNegative => "min",
+        RoundingMode::TowardPositive => "max",
+        RoundingMode::TiesToAway => "near_maxMag",
+    }
+}
+
+fn parse_rounding(s: &str) -> Option<RoundingMode> {
+    [
+        RoundingMode::TiesToEven,
+        RoundingMode::TowardZero,
+        RoundingMode::TowardNegative,
+        RoundingMode::TowardPositive,
+        RoundingMode::TiesToAway,
+    ]
+    .into_iter()
+    .find(|&x| rounding_name(x) == s)
+}
+
+thread_local! {
+    static HOOK: Cell<Option<fn(OpRecord)>> = const { Cell::new(None) };
+    /// whether the hook was set or cleared since the running one was taken out
+    static REPLACED: Cell<bool> = const { Cell::new(false) };
+    static LOG: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Calls `hook` after every operation run by this thread, replacing the previous hook.
+pub fn set_trace_hook(hook: fn(OpRecord)) {
+    HOOK.with(|x| x.set(Some(hook)));
+    REPLACED.with(|x| x.set(true));
+}
+
+/// Stops calling the trace hook of this thread.
+pub fn clear_trace_hook() {
+    HOOK.with(|x| x.set(None));
+    REPLACED.with(|x| x.set(true));
+}
+
+/// Returns whether a trace hook is set for this thread.
+pub(crate) fn has_trace_hook() -> bool {
+    HOOK.with(Cell::get).is_some()
+}
+
+/// Takes the hook out while an operation is reported, and puts it back when dropped, even
+/// by a panic, unless it was set or cleared meanwhile.
+struct TakenHook {
+    hook: Option<fn(OpRecord)>,
+    replaced: bool,
+}
+
+impl TakenHook {
+    fn take() -> Self {
+        Self {
+            hook: HOOK.with(|x| x.take()),
+            replaced: REPLACED.with(|x| x.replace(false)),
+        }
+    }
+}
+
+impl Drop for TakenHook {
+    fn drop(&mut self) {
+        if !REPLACED.with(|x| x.replace(self.replaced)) {
+            HOOK.with(|x| x.set(self.hook));
+        } else {
+            REPLACED.with(|x| x.set(true));
+        }
+    }
+}
+
+fn log(record: OpRecord) {
+    LOG.with(|x| writeln!(x.borrow_mut(), "{}", record).unwrap());
+}
+
+/// Runs `f` and returns its result with the log of the operations it ran.
+///
+/// The trace hook is not called while `f` runs.
+pub fn record<R>(f: impl FnOnce() -> R) -> (R, String) {
+    let _hook = TakenHook::take();
+    HOOK.with(|x| x.set(Some(log)));
+    let _outer = OuterLog(LOG.with(|x| x.take()));
+    let ret = f();
+    (ret, LOG.with(|x| x.take()))
+}
+
+/// Puts the log of an enclosing [`record`] back when dropped, even by a panic.
+struct OuterLog(String);
+
+impl Drop for OuterLog {
+    fn drop(&mut self) {
+        LOG.with(|x| *x.borrow_mut() = std::mem::take(&mut self.0));
+    }
+}
+
+/// Runs every operation of `log`, as written by [`record`], and returns the ones whose
+/// result or flags differ from the logged ones.
+///
+/// Unlike [`verify`](crate::testfloat::verify), NaN payloads must match too. Blank lines are
+/// skipped.
+pub fn replay(log: &str) -> Result<Vec<Mismatch>, TestFloatError> {
+    let mut ret = Vec::new();
+    for (i, line) in log.lines().enumerate() {
+        let mut fields = line.split_whitespace().peekable();
+        let op = match fields.next() {
+            Some(x) => x,
+            None => continue,
+        };
+        let function: Function = op.parse()?;
+        let invalid = || TestFloatError::InvalidLine(i + 1);
+
+        // comparisons do not round
+        let mut rnd = RoundingMode::TiesToEven;
+        let mut exact = false;
+        while let Some(x) = fields.next_if(|x| x.starts_with('-')) {
+            if x == "-exact" {
+                exact = true;
+            } else {
+                rnd = x
+                    .strip_prefix("-r")
+                    .and_then(parse_rounding)
+                    .ok_or_else(invalid)?;
+            }
+        }
+        let vector: TestVector = fields
+            .collect::<Vec<_>>()
+            .join(" ")
+            .parse()
+            .map_err(|_| invalid())?;
+        if vector.operands.len() != function.arity() {
+            return Err(invalid());
+        }
+
+        let (result, flags) = with_flags(|| function.run(&vector.operands, rnd, exact));
+        let result = result.ok_or_else(invalid)?;
+        if result != vector.result || flags.to_bits() != vector.flags.to_bits() {
+            ret.push(Mismatch {
+                line: i + 1,
+                vector,
+                result,
+                flags,
+            });
+        }
+    }
+    Ok(ret)
+}
+
+/// Values which can be traced as operands or results
+pub(crate) trait TraceValue {
+    fn trace_bits(&self) -> u128;
+}
+
+impl<T: TraceValue + ?Sized> TraceValue for &T {
+    fn trace_bits(&self) -> u128 {
+        (**self).trace_bits()
+    }
+}
+
+/// Results which can be replaced by a trap handler or computed by a backend
+pub(crate) trait TraceResult: TraceValue {
+    #[cfg_attr(not(any(feature = "trap", feature = "backend")), allow(dead_code))]
+    fn from_trace_bits(x: u128) -> Self;
+}
+
+macro_rules! impl_trace_value {
+    ($($t:ty => |$x:ident| $bits:expr, |$y:ident| $from:expr),*) => {
+        $(
+            impl TraceValue for $t {
+                fn trace_bits(&self) -> u128 {
+                    let $x = self;
+                    $bits
+                }
+            }
+
+            impl TraceResult for $t {
+                fn from_trace_bits($y: u128) -> Self {
+                    $from
+                }
+            }
+        )*
+    };
+}
+
+impl_trace_value!(
+    bool => |x| *x as u128, |x| x != 0,
+    u32 => |x| *x as u128, |x| x as u32,
+    u64 => |x| *x as u128, |x| x as u64,
+    i32 => |x| *x as u32 as u128, |x| x as u32 as i32,
+    i64 => |x| *x as u64 as u128, |x| x as u64 as i64,
+    F16 => |x| x.to_bits() as u128, |x| F16::from_bits(x as u16),
+    F32 => |x| x.to_bits() as u128, |x| F32::from_bits(x as u32),
+    F64 => |x| x.to_bits() as u128, |x| F64::from_bits(x as u64)
+);
+
+#[cfg(feature = "f128")]
+impl_trace_value!(F128 => |x| x.to_bits(), |x| F128::from_bits(x));
+
+/// Runs `f`, and reports it to the statistics, the trap handlers and the hook.
+pub(crate) fn traced<R: TraceResult>(
+    op: &'static str,
+    rnd: Option<RoundingMode>,
+    exact: bool,
+    operands: &[u128],
+    f: impl FnOnce() -> R,
+) -> R {
+    // the hook is taken out while it runs, so that its own operations are not traced
+    let taken = TakenHook::take();
+    #[cfg(not(any(feature = "stats", feature = "trap")))]
+    if taken.hook.is_none() {
+        return f();
+    }
+    let (ret, flags) = with_flags(f);
+    flags.raise();
+    #[cfg(feature = "stats")]
+    crate::stats::count(op, flags);
+    #[allow(unused_mut)]
+    let mut record = OpRecord {
+        op,
+        operands: [0; 3],
+        arity: operands.len(),
+        result: ret.trace_bits(),
+        rnd,
+        exact,
+        flags,
+    };
+    record.operands[..operands.len()].copy_from_slice(operands);
+    #[cfg(feature = "trap")]
+    let ret = match crate::trap::trap(&record) {
+        Some(x) => {
+            record.result = x;
+            R::from_trace_bits(x)
+        }
+        None => ret,
+    };
+    if let Some(hook) = taken.hook {
+        hook(record);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatArith, FloatCompare, FloatConvert};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static RECORDS: RefCell<Vec<OpRecord>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn push(x: OpRecord) {
+        // operations of the hook itself are not traced
+        F32::from_bits(0).add(F32::from_bits(0), RoundingMode::TiesToEven);
+        RECORDS.with(|records| records.borrow_mut().push(x));
+    }
+
+    #[test]
+    fn trace_hook() {
+        let rnd = RoundingMode::TowardZero;
+        set_trace_hook(push);
+        let a = F32::from_bits(0x3f800000);
+        let b = a.div(F32::from_bits(0x40400000), rnd);
+        b.lt(a);
+        b.to_i64(rnd, true);
+        F64::from_i32(-2, rnd).fused_mul_add(F64::from_bits(0), F64::from_bits(0), rnd);
+        clear_trace_hook();
+        a.add(a, rnd);
+
+        let records = RECORDS.with(|records| records.take());
+        let ops: Vec<_> = records.iter().map(|x| x.op).collect();
+        assert_eq!(
+            ops,
+            [
+                "f32_div",
+                "f32_lt",
+                "f32_to_i64",
+                "i32_to_f64",
+                "f64_mulAdd"
+            ]
+        );
+        assert_eq!(records[0].operands(), [0x3f800000, 0x40400000]);
+        assert_eq!(records[0].result, 0x3eaaaaaa);
+        assert!(matches!(records[0].rnd, Some(RoundingMode::TowardZero)));
+        assert_eq!(records[0].flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        assert_eq!(records[1].result, 1);
+        assert!(records[1].rnd.is_none());
+        assert_eq!(records[2].flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        assert_eq!(records[3].operands(), [0xfffffffe]);
+        assert_eq!(records[4].operands(), [0xc000000000000000, 0, 0]);
+    }
+
+    thread_local! {
+        static CALLS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    #[test]
+    fn trace_hook_replaced() {
+        fn once(_: OpRecord) {
+            CALLS.with(|x| x.set(x.get() + 1));
+            clear_trace_hook();
+        }
+        fn panics(_: OpRecord) {
+            CALLS.with(|x| x.set(x.get() + 1));
+            panic!("hook");
+        }
+        let a = F32::from_bits(0x3f800000);
+        let add = || a.add(a, RoundingMode::TiesToEven);
+
+        // a hook clearing itself stays cleared
+        set_trace_hook(once);
+        add();
+        add();
+        assert_eq!(CALLS.with(|x| x.take()), 1);
+        assert!(HOOK.with(Cell::get).is_none());
+
+        // and a panicking one is put back
+        set_trace_hook(panics);
+        assert!(std::panic::catch_unwind(add).is_err());
+        assert!(std::panic::catch_unwind(add).is_err());
+        assert_eq!(CALLS.with(|x| x.take()), 2);
+        clear_trace_hook();
+        add();
+        assert_eq!(CALLS.with(|x| x.take()), 0);
+    }
+
+    #[test]
+    fn trace_record_panic() {
+        fn count(_: OpRecord) {
+            CALLS.with(|x| x.set(x.get() + 1));
+        }
+        let a = F32::from_bits(0x3f800000);
+        let add = || a.add(a, RoundingMode::TiesToEven);
+
+        set_trace_hook(count);
+        let (_, log) = record(|| {
+            add();
+            let inner = std::panic::catch_unwind(|| {
+                record(|| {
+                    add();
+                    panic!("record");
+                })
+            });
+            assert!(inner.is_err());
+            add();
+        });
+        assert_eq!(log.lines().count(), 2);
+
+        // the hook of the thread is back, and the log is not recorded anymore
+        add();
+        assert_eq!(CALLS.with(|x| x.take()), 1);
+        assert!(LOG.with(|x| x.borrow().is_empty()));
+        clear_trace_hook();
+    }
+
+    #[test]
+    fn trace_record_replay() {
+        let rnd = RoundingMode::TowardPositive;
+        let (ret, log) = record(|| {
+            let a = F64::from_bits(0x3ff0000000000000);
+            let b = F64::from_bits(0x4008000000000000);
+            a.lt(b);
+            F32::from_i64(-1, rnd);
+            a.div(b, rnd).to_i32(rnd, true)
+        });
+        assert_eq!(ret, 1);
+        assert_eq!(
+            log,
+            "\
+f64_lt 3FF0000000000000 4008000000000000 1 00
+i64_to_f32 -rmax FFFFFFFFFFFFFFFF BF800000 00
+f64_div -rmax 3FF0000000000000 4008000000000000 3FD5555555555556 01
+f64_to_i32 -rmax -exact 3FD5555555555556 1 01
+"
+        );
+        assert!(replay(&log).unwrap().is_empty());
+
+        let mismatches = replay("\nf32_add -rmin 3F800000 3F800000 40000001 00\n").unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].line, 2);
+        assert_eq!(mismatches[0].result, 0x40000000);
+        assert_eq!(
+            replay("f32_add -rnearest 3F800000 3F800000 40000000 00").unwrap_err(),
+            TestFloatError::InvalidLine(1)
+        );
+        assert_eq!(
+            replay("f32_foo 0 0").unwrap_err(),
+            TestFloatError::UnknownFunction("f32_foo".to_string())
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Trap handlers
+//!
+//! A handler set with [`set_trap_handler`] is called when an operation run by the thread raises
+//! its exception, like a trap of an ISA. It gets the operation as an [`OpRecord`], and may
+//! return the bits of a result to use instead of the one the operation computed, as in the
+//! alternate exception handling of IEEE 754. The flags stay raised either way.
+//!
+//! When an operation raises several exceptions, the handlers run in the order of
+//! [`Exception`] until one substitutes the result.
+//!
+//! Handlers trap the softfloat functions only, like the trace hook: an exception raised by
+//! a conversion this crate rounds itself, such as
+//! [`to_fixed`](crate::SoftFloat::to_fixed), leaves its flag raised without calling them.
+//!
+//! ```
+//! use softfloat_wrapper::trap::{clear_trap_handler, set_trap_handler, Exception};
+//! use softfloat_wrapper::{FloatArith, RoundingMode, F32};
+//!
+//! // saturate instead of overflowing to infinity
+//! set_trap_handler(Exception::Overflow, |record| {
+//!     Some(record.result & 0x8000_0000 | 0x7f7f_ffff)
+//! });
+//! let a = F32::from_bits(0x7f7fffff);
+//! assert_eq!(a.add(a, RoundingMode::TiesToEven).to_bits(), 0x7f7fffff);
+//! clear_trap_handler(Exception::Overflow);
+//! ```
+//!
+//! To find where NaNs come from during development, [`set_panic_on`] makes the thread panic
+//! instead, with the operation and its operands.
+//!
+//! ```should_panic
+//! use softfloat_wrapper::trap::set_panic_on;
+//! use softfloat_wrapper::{ExceptionFlags, FloatArith, FloatBits, RoundingMode, F32};
+//!
+//! set_panic_on(ExceptionFlags::from_bits(0x10));
+//! // panics with "f32_sub -rnear_even 7F800000 7F800000 FFC00000 10 raised NV"
+//! let inf = F32::infinity();
+//! inf.sub(inf, RoundingMode::TiesToEven);
+//! ```
+
+use crate::trace::OpRecord;
+use crate::ExceptionFlags;
+use std::cell::{Cell, RefCell};
+
+/// exceptions defined by standard, in order of precedence of their handlers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Exception {
+    /// invalid operation
+    Invalid,
+    /// division by zero
+    Infinite,
+    /// overflow
+    Overflow,
+    /// underflow
+    Underflow,
+    /// inexact
+    Inexact,
+}
+
+impl Exception {
+    const ALL: [Exception; 5] = [
+        Exception::Invalid,
+        Exception::Infinite,
+        Exception::Overflow,
+        Exception::Underflow,
+        Exception::Inexact,
+    ];
+
+    fn is_raised(&self, flags: ExceptionFlags) -> bool {
+        match self {
+            Exception::Invalid => flags.is_invalid(),
+            Exception::Infinite => flags.is_infinite(),
+            Exception::Overflow => flags.is_overflow(),
+            Exception::Underflow => flags.is_underflow(),
+            Exception::Inexact => flags.is_inexact(),
+        }
+    }
+}
+
+type Handler = Box<dyn FnMut(&OpRecord) -> Option<u128>>;
+
+thread_local! {
+    static HANDLERS: RefCell<[Option<Handler>; 5]> = RefCell::new(Default::default());
+    static PANIC_ON: Cell<ExceptionFlags> = const { Cell::new(ExceptionFlags(0)) };
+}
+
+/// Makes operations run by this thread panic when they raise one of `flags`, before any
+/// handler runs.
+///
+/// `ExceptionFlags::default()` turns it off.
+pub fn set_panic_on(flags: ExceptionFlags) {
+    PANIC_ON.with(|x| x.set(flags));
+}
+
+/// Calls `handler` when an operation run by this thread raises `exception`, replacing the
+/// previous handler of `exception`.
+///
+/// The bits returned by `handler` are truncated to the type of the result; comparisons are
+/// true if any of them is set.
+pub fn set_trap_handler(
+    exception: Exception,
+    handler: impl FnMut(&OpRecord) -> Option<u128> + 'static,
+) {
+    HANDLERS.with(|x| x.borrow_mut()[exception as usize] = Some(Box::new(handler)));
+}
+
+/// Stops calling the handler of `exception` in this thread.
+pub fn clear_trap_handler(exception: Exception) {
+    HANDLERS.with(|x| x.borrow_mut()[exception as usize] = None);
+}
+
+/// Returns whether this thread traps any exception, with a handler or a panic.
+pub(crate) fn has_trap_handler() -> bool {
+    PANIC_ON.with(Cell::get).to_bits() != 0
+        || HANDLERS.with(|x| x.borrow().iter().any(Option::is_some))
+}
+
+/// Takes the handler of an exception out while it runs, and puts it back when dropped, even
+/// by a panic, unless another one was set meanwhile.
+struct TakenHandler {
+    exception: Exception,
+    handler: Option<Handler>,
+}
+
+impl Drop for TakenHandler {
+    fn drop(&mut self) {
+        if let Some(handler) = self.handler.take() {
+            HANDLERS.with(|x| {
+                let slot = &mut x.borrow_mut()[self.exception as usize];
+                if slot.is_none() {
+                    *slot = Some(handler);
+                }
+            });
+        }
+    }
+}
+
+/// Runs the handlers of the exceptions raised by `record`, and returns the result substituted
+/// by one of them.
+pub(crate) fn trap(record: &OpRecord) -> Option<u128> {
+    let raised = record.flags & PANIC_ON.with(|x| x.get());
+    if raised.to_bits() != 0 {
+        panic!("{} raised {}", record, raised);
+    }
+    for exception in Exception::ALL {
+        if !exception.is_raised(record.flags) {
+            continue;
+        }
+        // the handler is taken out while it runs, so that its own operations do not call it
+        let mut taken = TakenHandler {
+            exception,
+            handler: HANDLERS.with(|x| x.borrow_mut()[exception as usize].take()),
+        };
+        let ret = match &mut taken.handler {
+            Some(handler) => handler(record),
+            None => continue,
+        };
+        drop(taken);
+        if ret.is_some() {
+            return ret;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        with_flags, FloatArith, FloatBits, FloatCompare, FloatConvert, RoundingMode, F32, F64,
+    };
+    use std::rc::Rc;
+
+    #[test]
+    fn trap_substitute() {
+        let rnd = RoundingMode::TiesToEven;
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let log = calls.clone();
+        set_trap_handler(Exception::Inexact, move |record| {
+            log.borrow_mut().push(record.op);
+            None
+        });
+        set_trap_handler(Exception::Infinite, |record| {
+            assert_eq!(record.op, "f64_div");
+            Some(0)
+        });
+
+        let a = F64::from_bits(0x3ff0000000000000);
+        let (ret, flags) = with_flags(|| a.div(F64::from_bits(0), rnd));
+        assert_eq!(ret.to_bits(), 0);
+        assert!(flags.is_infinite());
+        a.div(F64::from_bits(0x4008000000000000), rnd);
+        F32::from_bits(0x3fc00000).to_i32(rnd, false);
+        assert_eq!(*calls.borrow(), ["f64_div"]);
+
+        clear_trap_handler(Exception::Infinite);
+        assert!(a.div(F64::from_bits(0), rnd).is_infinity());
+        clear_trap_handler(Exception::Inexact);
+        a.div(F64::from_bits(0x4008000000000000), rnd);
+        assert_eq!(calls.borrow().len(), 1);
+    }
+
+    #[test]
+    fn trap_order() {
+        let rnd = RoundingMode::TiesToEven;
+        set_trap_handler(Exception::Overflow, |_| None);
+        set_trap_handler(Exception::Inexact, |_| Some(0x3f800000));
+        let a = F32::from_bits(0x7f7fffff);
+        assert_eq!(a.mul(a, rnd).to_bits(), 0x3f800000);
+        set_trap_handler(Exception::Overflow, |_| Some(0x7f7fffff));
+        assert_eq!(a.mul(a, rnd).to_bits(), 0x7f7fffff);
+
+        set_trap_handler(Exception::Invalid, |_| Some(3));
+        assert!(F32::quiet_nan().lt(a));
+        for exception in Exception::ALL {
+            clear_trap_handler(exception);
+        }
+    }
+
+    #[test]
+    fn trap_handler_panic() {
+        let rnd = RoundingMode::TiesToEven;
+        let calls = Rc::new(Cell::new(0));
+        let count = calls.clone();
+        set_trap_handler(Exception::Inexact, move |_| {
+            count.set(count.get() + 1);
+            panic!("trap");
+        });
+
+        // the handler aborts the operation, and still traps the next one
+        let a = F64::from_bits(0x3ff0000000000000);
+        let b = F64::from_bits(0x4008000000000000);
+        assert!(std::panic::catch_unwind(|| a.div(b, rnd)).is_err());
+        assert!(std::panic::catch_unwind(|| a.div(b, rnd)).is_err());
+        assert_eq!(calls.get(), 2);
+        clear_trap_handler(Exception::Inexact);
+        a.div(b, rnd);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn trap_panic() {
+        let rnd = RoundingMode::TiesToEven;
+        set_panic_on(ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID));
+        let inf = F64::infinity();
+        let ret = std::panic::catch_unwind(|| inf.mul(F64::from_bits(0), rnd));
+        let message = ret.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(
+            *message,
+            "f64_mul -rnear_even 7FF0000000000000 0 FFF8000000000000 10 raised NV"
+        );
+        // other exceptions do not panic
+        inf.div(F64::from_bits(0), rnd);
+        set_panic_on(ExceptionFlags::default());
+        assert!(inf.sub(inf, rnd).is_nan());
+    }
+}
@@ -0,0 +1,325 @@
+//! VAX floating point
+//!
+//! [`VaxF`], [`VaxD`] and [`VaxG`] are the F, D and G formats of the VAX: a sign, an
+//! exponent biased by 128 or 1024, and a fraction with a hidden bit, worth `0.1f * 2^(e -
+//! bias)`. There are no infinities, NaNs, subnormals or negative zero; an exponent of zero is
+//! a zero when the sign is clear, and the reserved operand when it is set.
+//!
+//! Bits are the values loaded from memory as little-endian integers, which puts the word with
+//! the sign and exponent in the low 16 bits.
+//!
+//! Arithmetic rounds to nearest with ties away from zero, as the VAX does. Overflows, invalid
+//! operations and divisions by zero raise their exception and give the reserved operand, and
+//! underflows give zero, as with floating underflow traps disabled. Conversions from and to
+//! IEEE formats round per the rounding mode given; infinities and NaNs convert to the
+//! reserved operand, and back to the default NaN, raising invalid.
+//!
+//! ```
+//! use softfloat_wrapper::vax::VaxF;
+//! use softfloat_wrapper::{RoundingMode, F32};
+//!
+//! let a = F32::from_bits(0xc2ed4000); // -118.625
+//! let b = VaxF::from_float(&a, RoundingMode::TiesToEven);
+//! assert_eq!(b.to_bits(), 0x4000c3ed);
+//! assert_eq!(b.to_float::<F32>(RoundingMode::TiesToEven).to_bits(), a.to_bits());
+//! ```
+
+use crate::exact::{add, div, mul, Unpacked};
+use crate::{round_pack, round_shift, unpack, ExceptionFlags, RoundingMode, SoftFloat};
+
+/// Reverses the order of the `words` 16-bit words of `x`, between the order in memory and
+/// the one with the sign in the most significant bit.
+fn swap_words(x: u64, words: u32) -> u64 {
+    (0..words).fold(0, |acc, i| acc << 16 | (x >> (16 * i) & 0xffff))
+}
+
+/// Splits `bits`, with the sign in the most significant bit, into its exact value, or
+/// `None` for the reserved operand.
+fn unpack_vax(bits: u64, exp_bits: u32, frac: u32) -> Option<Unpacked> {
+    let sign = bits >> (exp_bits + frac) & 1 != 0;
+    let exp = (bits >> frac) as i32 & ((1 << exp_bits) - 1);
+    if exp == 0 {
+        return if sign { None } else { Some((false, 0, 0)) };
+    }
+    let sig = bits & ((1 << frac) - 1) | 1 << frac;
+    let bias = 1 << (exp_bits - 1);
+    Some((sign, exp - bias - 1 - frac as i32, sig as u128))
+}
+
+/// Rounds `sig * 2^exp` per `rnd`, raising exception flags, and returns its bits with the
+/// sign in the most significant bit.
+///
+/// `sig` must either hold the value exactly, or hold at least two bits more than the
+/// precision with all discarded bits or'ed into its least significant bit.
+fn pack_vax((sign, exp, sig): Unpacked, exp_bits: u32, frac: u32, rnd: RoundingMode) -> u64 {
+    if sig == 0 {
+        return 0;
+    }
+    let bits = 128 - sig.leading_zeros() as i32;
+    let mut biased = exp + bits + (1 << (exp_bits - 1));
+    let (mut f, inexact) = round_shift(sign, sig, bits - frac as i32 - 1, rnd);
+    if f >> (frac + 1) != 0 {
+        f >>= 1;
+        biased += 1;
+    }
+
+    let mut flags = 0;
+    if inexact {
+        flags |= ExceptionFlags::FLAG_INEXACT;
+    }
+    let ret = if biased >= 1 << exp_bits {
+        flags |= ExceptionFlags::FLAG_OVERFLOW | ExceptionFlags::FLAG_INEXACT;
+        reserved(exp_bits, frac)
+    } else if biased < 1 {
+        flags |= ExceptionFlags::FLAG_UNDERFLOW | ExceptionFlags::FLAG_INEXACT;
+        0
+    } else {
+        (sign as u64) << (exp_bits + frac) | (biased as u64) << frac | f as u64 & ((1 << frac) - 1)
+    };
+    if flags != 0 {
+        ExceptionFlags::from_bits(flags).raise();
+    }
+    ret
+}
+
+/// the reserved operand, with the sign in the most significant bit
+fn reserved(exp_bits: u32, frac: u32) -> u64 {
+    1 << (exp_bits + frac)
+}
+
+/// Raises invalid, and returns the reserved operand.
+fn invalid(exp_bits: u32, frac: u32) -> u64 {
+    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+    reserved(exp_bits, frac)
+}
+
+macro_rules! vax_format {
+    ($(#[$doc:meta])* $name:ident, $bits:ty, $exp_bits:expr, $frac:expr) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug, Default)]
+        #[repr(transparent)]
+        pub struct $name($bits);
+
+        impl $name {
+            const EXP_BITS: u32 = $exp_bits;
+            const FRAC: u32 = $frac;
+            const WORDS: u32 = <$bits>::BITS / 16;
+
+            /// Creates a value from its bits, as loaded from memory.
+            pub const fn from_bits(v: $bits) -> Self {
+                Self(v)
+            }
+
+            /// Returns the bits of `self`, as stored to memory.
+            pub const fn to_bits(&self) -> $bits {
+                self.0
+            }
+
+            fn unpack(&self) -> Option<Unpacked> {
+                let bits = swap_words(self.0 as u64, Self::WORDS);
+                unpack_vax(bits, Self::EXP_BITS, Self::FRAC)
+            }
+
+            fn pack(bits: u64) -> Self {
+                Self(swap_words(bits, Self::WORDS) as $bits)
+            }
+
+            fn round(x: Unpacked, rnd: RoundingMode) -> Self {
+                Self::pack(pack_vax(x, Self::EXP_BITS, Self::FRAC, rnd))
+            }
+
+            fn invalid() -> Self {
+                Self::pack(invalid(Self::EXP_BITS, Self::FRAC))
+            }
+
+            /// Returns `true` if `self` is the reserved operand.
+            pub fn is_reserved(&self) -> bool {
+                self.unpack().is_none()
+            }
+
+            /// Returns `true` if `self` is zero, whatever its fraction.
+            pub fn is_zero(&self) -> bool {
+                matches!(self.unpack(), Some((_, _, 0)))
+            }
+
+            /// Returns `true` if `self` is negative.
+            pub fn is_negative(&self) -> bool {
+                matches!(self.unpack(), Some((true, _, _)))
+            }
+
+            /// Returns `self` with its sign inverted, which leaves zero and the reserved
+            /// operand as they are.
+            pub fn neg(&self) -> Self {
+                if self.is_zero() || self.is_reserved() {
+                    return *self;
+                }
+                let sign = reserved(Self::EXP_BITS, Self::FRAC);
+                Self::pack(swap_words(self.0 as u64, Self::WORDS) ^ sign)
+            }
+
+            /// Converts `x` exactly, or rounded per `rnd`. Infinities and NaNs raise invalid
+            /// and give the reserved operand.
+            pub fn from_float<T: SoftFloat>(x: &T, rnd: RoundingMode) -> Self {
+                if !x.is_finite() {
+                    return Self::invalid();
+                }
+                Self::round(unpack(x), rnd)
+            }
+
+            /// Converts `self` to `T`, rounded per `rnd`. The reserved operand raises invalid
+            /// and gives the default NaN.
+            pub fn to_float<T: SoftFloat>(&self, rnd: RoundingMode) -> T {
+                match self.unpack() {
+                    Some((sign, exp, sig)) => round_pack(sign, exp, sig, rnd),
+                    None => {
+                        ExceptionFlags::from_bits(ExceptionFlags::FLAG_INVALID).raise();
+                        T::quiet_nan()
+                    }
+                }
+            }
+
+            fn arith(&self, x: Self, f: impl FnOnce(Unpacked, Unpacked) -> Option<Unpacked>) -> Self {
+                let ret = match (self.unpack(), x.unpack()) {
+                    (Some(a), Some(b)) => f(a, b),
+                    _ => None,
+                };
+                match ret {
+                    Some(ret) => Self::round(ret, RoundingMode::TiesToAway),
+                    None => Self::invalid(),
+                }
+            }
+
+            /// Adds `x` to `self`.
+            pub fn add(&self, x: Self) -> Self {
+                self.arith(x, |a, b| Some(add(a, b)))
+            }
+
+            /// Subtracts `x` from `self`.
+            pub fn sub(&self, x: Self) -> Self {
+                self.arith(x, |a, (sign, exp, sig)| Some(add(a, (!sign, exp, sig))))
+            }
+
+            /// Multiplies `self` by `x`.
+            pub fn mul(&self, x: Self) -> Self {
+                self.arith(x, |a, b| Some(mul(a, b)))
+            }
+
+            /// Divides `self` by `x`. Dividing by zero raises the infinite flag and gives the
+            /// reserved operand.
+            pub fn div(&self, x: Self) -> Self {
+                if x.is_zero() && !self.is_reserved() {
+                    ExceptionFlags::from_bits(ExceptionFlags::FLAG_INFINITE).raise();
+                    return Self::pack(reserved(Self::EXP_BITS, Self::FRAC));
+                }
+                self.arith(x, div)
+            }
+        }
+    };
+}
+
+vax_format!(
+    /// VAX F_floating, of 32 bits with 24 significant bits
+    VaxF,
+    u32,
+    8,
+    23
+);
+
+vax_format!(
+    /// VAX D_floating, of 64 bits with 56 significant bits and the exponent range of `VaxF`
+    VaxD,
+    u64,
+    8,
+    55
+);
+
+vax_format!(
+    /// VAX G_floating, of 64 bits with 53 significant bits
+    VaxG,
+    u64,
+    11,
+    52
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_flags, FloatBits, F32, F64};
+
+    const ONE: VaxF = VaxF::from_bits(0x00004080);
+
+    #[test]
+    fn vax_convert() {
+        let rnd = RoundingMode::TiesToEven;
+        let a = F64::from_bits(0x3fb999999999999a); // 0.1
+        let (ret, flags) = with_flags(|| VaxD::from_float(&a, rnd));
+        assert_eq!(ret.to_bits(), 0xccd0cccccccc3ecc);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(ret.to_float::<F64>(rnd).to_bits(), a.to_bits());
+        let (ret, flags) = with_flags(|| VaxD::from_bits(0xccd1cccccccc3ecc).to_float::<F64>(rnd));
+        assert_eq!(ret.to_bits(), a.to_bits());
+        assert!(flags.is_inexact());
+        assert_eq!(
+            VaxG::from_float(&F64::from_bits(0x3ff0000000000000), rnd).to_bits(),
+            0x4010
+        );
+        assert_eq!(ONE.to_float::<F32>(rnd).to_bits(), 0x3f800000);
+
+        // dirty zeros
+        assert_eq!(
+            VaxF::from_bits(0x12340000).to_float::<F32>(rnd).to_bits(),
+            0
+        );
+        assert_eq!(
+            VaxF::from_float(&F32::from_bits(0x80000000), rnd).to_bits(),
+            0
+        );
+
+        let (ret, flags) = with_flags(|| VaxF::from_float(&F32::infinity(), rnd));
+        assert!(ret.is_reserved());
+        assert!(flags.is_invalid());
+        let (ret, flags) = with_flags(|| VaxF::from_bits(0x8000).to_float::<F32>(rnd));
+        assert!(ret.is_nan());
+        assert!(flags.is_invalid());
+        let (ret, flags) = with_flags(|| VaxF::from_float(&F32::from_bits(0x7f7fffff), rnd));
+        assert!(ret.is_reserved());
+        assert!(flags.is_overflow());
+        let (ret, flags) = with_flags(|| VaxF::from_float(&F32::from_bits(1), rnd));
+        assert!(ret.is_zero());
+        assert!(flags.is_underflow());
+        // the smallest VaxF is subnormal in F32
+        let ret = VaxF::from_bits(0x0080).to_float::<F32>(rnd);
+        assert_eq!(ret.to_bits(), 0x00200000);
+    }
+
+    #[test]
+    fn vax_arithmetic() {
+        assert_eq!(ONE.add(ONE).to_bits(), 0x00004100);
+        assert_eq!(ONE.sub(ONE).to_bits(), 0);
+        assert_eq!(ONE.neg().to_bits(), 0x0000c080);
+        assert!(!VaxF::default().neg().is_negative());
+        // ties round away from zero
+        let (ret, flags) = with_flags(|| ONE.add(VaxF::from_bits(0x00003480)));
+        assert_eq!(ret.to_bits(), 0x00014080);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        let three = VaxF::from_bits(0x00004140);
+        let ret = ONE.div(three).to_float::<F32>(RoundingMode::TiesToEven);
+        assert_eq!(ret.to_bits(), 0x3eaaaaab);
+        assert_eq!(three.mul(ONE.neg()).to_bits(), 0x0000c140);
+
+        let (ret, flags) = with_flags(|| ONE.div(VaxF::default()));
+        assert!(ret.is_reserved());
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INFINITE);
+        let (ret, flags) = with_flags(|| ONE.add(VaxF::from_bits(0x8000)));
+        assert!(ret.is_reserved());
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INVALID);
+        let max = VaxG::from_bits(0xffffffffffff7fff);
+        let (ret, flags) = with_flags(|| max.add(max));
+        assert!(ret.is_reserved());
+        assert!(flags.is_overflow());
+        let min = VaxD::from_bits(0x0080);
+        let (ret, flags) = with_flags(|| min.mul(min));
+        assert!(ret.is_zero());
+        assert!(flags.is_underflow());
+    }
+}
@@ -0,0 +1,239 @@
+//! Views of bit buffers as softfloat values
+//!
+//! [`Bits`] and [`BitsMut`] borrow slices of raw bits, such as guest register files or
+//! mapped tensors, and read and write them as values of a format without copying. The
+//! operations of [`BitsMut`] run in place, merging the exception flags of all elements like
+//! the kernels of [`batch`](crate::batch), and leave the exception flags of the thread as
+//! they were.
+//!
+//! ```
+//! use softfloat_wrapper::{F16Bits, F16BitsMut, RoundingMode};
+//!
+//! let mut regs: [u16; 4] = [0x3c00, 0x4000, 0x7bff, 0x0000];
+//! let src = [0x3c00; 4];
+//! let flags = F16BitsMut::new(&mut regs).add(F16Bits::new(&src), RoundingMode::TiesToEven);
+//! assert_eq!(regs, [0x4000, 0x4200, 0x7bff, 0x3c00]);
+//! assert!(flags.is_inexact());
+//! ```
+//!
+//! # Panics
+//!
+//! The operations panic if the operand views have different lengths.
+
+#[cfg(feature = "f128")]
+use crate::F128;
+use crate::{with_flags, ExceptionFlags, RoundingMode, SoftFloat, F16, F32, F64};
+
+/// A view of a slice of the bits of `T` values.
+#[derive(Debug)]
+pub struct Bits<'a, T: SoftFloat>(&'a [T::Payload]);
+
+/// A mutable view of a slice of the bits of `T` values.
+#[derive(Debug)]
+pub struct BitsMut<'a, T: SoftFloat>(&'a mut [T::Payload]);
+
+/// a view of the bits of `F16` values
+pub type F16Bits<'a> = Bits<'a, F16>;
+/// a view of the bits of `F32` values
+pub type F32Bits<'a> = Bits<'a, F32>;
+/// a view of the bits of `F64` values
+pub type F64Bits<'a> = Bits<'a, F64>;
+/// a view of the bits of `F128` values
+#[cfg(feature = "f128")]
+pub type F128Bits<'a> = Bits<'a, F128>;
+/// a mutable view of the bits of `F16` values
+pub type F16BitsMut<'a> = BitsMut<'a, F16>;
+/// a mutable view of the bits of `F32` values
+pub type F32BitsMut<'a> = BitsMut<'a, F32>;
+/// a mutable view of the bits of `F64` values
+pub type F64BitsMut<'a> = BitsMut<'a, F64>;
+/// a mutable view of the bits of `F128` values
+#[cfg(feature = "f128")]
+pub type F128BitsMut<'a> = BitsMut<'a, F128>;
+
+// derives would bound `T` rather than its payload
+impl<'a, T: SoftFloat> Clone for Bits<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: SoftFloat> Copy for Bits<'a, T> {}
+
+fn check_len(a: usize, b: usize) {
+    assert_eq!(a, b, "operand views have different lengths");
+}
+
+impl<'a, T: SoftFloat> Bits<'a, T> {
+    /// Creates a view of `bits`.
+    pub fn new(bits: &'a [T::Payload]) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bits the view borrows.
+    pub fn as_slice(&self) -> &'a [T::Payload] {
+        self.0
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the view has no values.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.0.get(index).map(|&x| T::from_bits(x))
+    }
+
+    /// Returns an iterator over the values.
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        self.0.iter().map(|&x| T::from_bits(x))
+    }
+}
+
+macro_rules! binary {
+    ($name:ident, $doc:literal) => {
+        #[doc = concat!("Replaces each value by ", $doc, " of it and the value of `x` at the same index, merging the exception flags.")]
+        pub fn $name(&mut self, x: Bits<'_, T>, rnd: RoundingMode) -> ExceptionFlags {
+            check_len(self.len(), x.len());
+            self.zip_with(x, |a, b| a.$name(b, rnd))
+        }
+    };
+}
+
+impl<'a, T: SoftFloat> BitsMut<'a, T> {
+    /// Creates a mutable view of `bits`.
+    pub fn new(bits: &'a mut [T::Payload]) -> Self {
+        Self(bits)
+    }
+
+    /// Returns a view of the same bits.
+    pub fn as_bits(&self) -> Bits<'_, T> {
+        Bits(self.0)
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the view has no values.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.as_bits().get(index)
+    }
+
+    /// Sets the value at `index` to `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, x: T) {
+        self.0[index] = x.to_bits();
+    }
+
+    /// Replaces each value `a` by `f(a)`, merging the exception flags.
+    pub fn map(&mut self, f: impl Fn(T) -> T) -> ExceptionFlags {
+        let (_, flags) = with_flags(|| {
+            for x in self.0.iter_mut() {
+                *x = f(T::from_bits(*x)).to_bits();
+            }
+        });
+        flags
+    }
+
+    fn zip_with(&mut self, y: Bits<'_, T>, f: impl Fn(T, T) -> T) -> ExceptionFlags {
+        let (_, flags) = with_flags(|| {
+            for (x, &y) in self.0.iter_mut().zip(y.0) {
+                *x = f(T::from_bits(*x), T::from_bits(y)).to_bits();
+            }
+        });
+        flags
+    }
+
+    binary!(add, "the sum");
+    binary!(sub, "the difference");
+    binary!(mul, "the product");
+    binary!(div, "the quotient");
+
+    /// Replaces each value `a` by `a * x + y` of the values at the same index, with a
+    /// single rounding, merging the exception flags.
+    pub fn fused_mul_add(
+        &mut self,
+        x: Bits<'_, T>,
+        y: Bits<'_, T>,
+        rnd: RoundingMode,
+    ) -> ExceptionFlags {
+        check_len(self.len(), x.len());
+        check_len(self.len(), y.len());
+        let (_, flags) = with_flags(|| {
+            for ((a, &x), &y) in self.0.iter_mut().zip(x.0).zip(y.0) {
+                let ret = T::from_bits(*a).fused_mul_add(T::from_bits(x), T::from_bits(y), rnd);
+                *a = ret.to_bits();
+            }
+        });
+        flags
+    }
+
+    /// Replaces each value by its square root, merging the exception flags.
+    pub fn sqrt(&mut self, rnd: RoundingMode) -> ExceptionFlags {
+        self.map(|a| a.sqrt(rnd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FloatBits;
+
+    #[test]
+    fn view_in_place() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+
+        let mut regs: [u32; 3] = [0x3f800000, 0x40800000, 0xbf800000];
+        let src = [0x40000000, 0x00000000, 0x3f800000];
+        let mut view = F32BitsMut::new(&mut regs);
+        assert_eq!(view.len(), 3);
+        assert_eq!(view.get(1).unwrap().to_bits(), 0x40800000);
+        assert!(view.get(3).is_none());
+
+        let flags = view.div(F32Bits::new(&src), rnd);
+        assert!(flags.is_infinite() && !flags.is_inexact());
+        assert_eq!(view.get(0).unwrap().to_bits(), 0x3f000000);
+        let flags = view.sqrt(rnd);
+        assert!(flags.is_invalid() && flags.is_inexact());
+        assert_eq!(view.get(0).unwrap().to_bits(), 0x3f3504f3);
+        assert!(view.get(1).unwrap().is_infinity());
+
+        view.set(0, F32::from_bits(0x3f800000));
+        view.set(1, F32::from_bits(0x40800000));
+        view.set(2, F32::from_bits(0x40400000));
+        let flags = view.fused_mul_add(F32Bits::new(&src), F32Bits::new(&src), rnd);
+        assert_eq!(flags.to_bits(), 0);
+        assert_eq!(regs, [0x40800000, 0x00000000, 0x40800000]);
+
+        flag.get();
+        assert_eq!(flag.to_bits(), 0);
+        let bits = F64Bits::new(&[0x3ff0000000000000, 0x4000000000000000]);
+        let v: Vec<u64> = bits.iter().map(|x| x.to_bits()).collect();
+        assert_eq!(v, bits.as_slice());
+    }
+
+    #[test]
+    #[should_panic]
+    fn view_length() {
+        let mut a = [0u16; 2];
+        F16BitsMut::new(&mut a).add(F16Bits::new(&[0]), RoundingMode::TiesToEven);
+    }
+}
@@ -0,0 +1,240 @@
+//! x87 status helpers
+//!
+//! The parts of the x87 control, status and tag words which depend on the values computed
+//! rather than on the exception flags: the precision control field, the C1 condition code,
+//! which tells whether an inexact result was rounded up, and the classification of the
+//! registers in the tag word. Registers are the 80-bit values stored by `fstp tbyte`.
+//!
+//! This crate has no extF80 type, so `F128` stands in for the registers: its exponent range
+//! is the one of the 80-bit format, and its 113-bit significand holds the 64 bits of a
+//! register exactly. Results of arithmetic are computed and rounded once to the precision
+//! control with the [`Chop`] of [`PrecisionControl::chop`], and values which are already
+//! exact, such as loaded ones, are rounded with [`PrecisionControl::round`]. The exponent
+//! range stays the one of the registers whatever the precision, like on the x87. [`round_c1`] works
+//! for these results as well as for the ones of the other formats, such as `fst` to memory.
+//!
+//! ```
+//! use softfloat_wrapper::x87::{round_c1, PrecisionControl};
+//! use softfloat_wrapper::{FloatConvert, RoundingMode, F128, F32, F64};
+//!
+//! let cw: u16 = 0x027f;
+//! let pc = PrecisionControl::from_control_word(cw).unwrap();
+//! assert_eq!(pc, PrecisionControl::Double);
+//!
+//! // 1 / 3 with 53-bit significands
+//! let one = F128::from_bits(0x3fff0000000000000000000000000000);
+//! let three = F128::from_bits(0x40008000000000000000000000000000);
+//! let ret = pc.chop().div(&one, &three, RoundingMode::TiesToEven);
+//! assert_eq!(ret.to_bits(), 0x3ffd5555555555555000000000000000);
+//!
+//! // 0.1 rounds up to single precision
+//! let a = F64::from_bits(0x3fb999999999999a);
+//! let (ret, c1) = round_c1(RoundingMode::TiesToEven, |rnd| a.to_f32(rnd));
+//! assert_eq!(ret.to_bits(), 0x3dcccccd);
+//! assert!(c1);
+//! ```
+
+use crate::{with_flags, Chop, RoundingMode, SoftFloat, F128};
+
+/// precision control field of the x87 control word
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrecisionControl {
+    /// 24-bit significands
+    Single,
+    /// 53-bit significands
+    Double,
+    /// 64-bit significands, the default
+    Extended,
+}
+
+impl PrecisionControl {
+    /// Decodes the `PC` field, bits 8 and 9 of the control word. The reserved encoding 1
+    /// gives `None`.
+    pub fn from_control_word(cw: u16) -> Option<Self> {
+        match (cw >> 8) & 3 {
+            0 => Some(PrecisionControl::Single),
+            2 => Some(PrecisionControl::Double),
+            3 => Some(PrecisionControl::Extended),
+            _ => None,
+        }
+    }
+
+    /// Returns `cw` with its `PC` field set to `self`.
+    pub fn to_control_word(&self, cw: u16) -> u16 {
+        let pc = match self {
+            PrecisionControl::Single => 0,
+            PrecisionControl::Double => 2,
+            PrecisionControl::Extended => 3,
+        };
+        cw & !0x0300 | pc << 8
+    }
+
+    /// Returns the number of bits results are rounded to, including the integer bit.
+    pub fn precision(&self) -> u32 {
+        match self {
+            PrecisionControl::Single => 24,
+            PrecisionControl::Double => 53,
+            PrecisionControl::Extended => 64,
+        }
+    }
+
+    /// Returns the format results are rounded to: the precision of `self`, with the
+    /// exponent range of the float type, which for `F128` is the one of the registers.
+    pub fn chop(&self) -> Chop {
+        Chop::new(self.precision())
+    }
+
+    /// Rounds `x` to the precision of `self`, raising exception flags for the rounding.
+    ///
+    /// `x` must be exact, such as a loaded value or the result of `frndint`: the result of
+    /// an `F128` operation was already rounded to 113 bits, and rounding it again may differ
+    /// from the x87 in the last bit. Arithmetic rounds once with [`chop`](Self::chop)
+    /// instead.
+    pub fn round(&self, x: &F128, rnd: RoundingMode) -> F128 {
+        self.chop().round(x, rnd)
+    }
+}
+
+/// class of a register in the tag word
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// a normal value
+    Valid = 0,
+    /// a zero
+    Zero = 1,
+    /// a NaN, an infinity, a denormal or an unsupported encoding
+    Special = 2,
+    /// an empty register
+    Empty = 3,
+}
+
+impl Tag {
+    /// Classifies the 80-bit register `bytes`, as `fxsave` does to rebuild the tag word.
+    pub fn of(bytes: [u8; 10]) -> Self {
+        let mut sig = [0; 8];
+        sig.copy_from_slice(&bytes[..8]);
+        let sig = u64::from_le_bytes(sig);
+        let exp = u16::from_le_bytes([bytes[8], bytes[9]]) & 0x7fff;
+        match exp {
+            0 if sig == 0 => Tag::Zero,
+            // denormals and pseudo-denormals
+            0 => Tag::Special,
+            0x7fff => Tag::Special,
+            // unnormals lack the integer bit
+            _ if sig >> 63 == 0 => Tag::Special,
+            _ => Tag::Valid,
+        }
+    }
+}
+
+/// Returns the tag word of the physical registers `regs`, `None` for the empty ones.
+pub fn tag_word(regs: [Option<[u8; 10]>; 8]) -> u16 {
+    regs.iter().enumerate().fold(0, |word, (i, reg)| {
+        let tag = reg.map_or(Tag::Empty, Tag::of);
+        word | (tag as u16) << (2 * i)
+    })
+}
+
+/// Runs `f` in `rnd`, and returns its result with the C1 condition code: whether the
+/// result was rounded away from zero.
+///
+/// `f` runs a second time toward zero to compare, without raising exception flags.
+pub fn round_c1<T: SoftFloat>(rnd: RoundingMode, f: impl Fn(RoundingMode) -> T) -> (T, bool) {
+    let ret = f(rnd);
+    let (rtz, _) = with_flags(|| f(RoundingMode::TowardZero));
+    let c1 = ret.to_bits() != rtz.to_bits();
+    (ret, c1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExceptionFlags, FloatArith, FloatBits, FloatConvert, F32, F64};
+
+    fn reg(sig: u64, se: u16) -> [u8; 10] {
+        let mut bytes = [0; 10];
+        bytes[..8].copy_from_slice(&sig.to_le_bytes());
+        bytes[8..].copy_from_slice(&se.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn x87_precision_control() {
+        assert_eq!(
+            PrecisionControl::from_control_word(0x037f),
+            Some(PrecisionControl::Extended)
+        );
+        assert_eq!(
+            PrecisionControl::from_control_word(0x007f),
+            Some(PrecisionControl::Single)
+        );
+        assert_eq!(PrecisionControl::from_control_word(0x017f), None);
+        assert_eq!(PrecisionControl::Double.to_control_word(0x037f), 0x027f);
+        assert_eq!(PrecisionControl::Single.precision(), 24);
+    }
+
+    #[test]
+    fn x87_precision_rounding() {
+        let rnd = RoundingMode::TiesToEven;
+        let third = F128::from_bits(0x3ffd5555555555555555555555555555);
+        let (ret, flags) = with_flags(|| PrecisionControl::Single.round(&third, rnd));
+        assert_eq!(ret.to_bits(), 0x3ffd5555560000000000000000000000);
+        assert_eq!(flags.to_bits(), ExceptionFlags::FLAG_INEXACT);
+        let ret = PrecisionControl::Double.round(&third, rnd);
+        assert_eq!(ret.to_bits(), 0x3ffd5555555555555000000000000000);
+        let ret = PrecisionControl::Extended.round(&third, rnd);
+        assert_eq!(ret.to_bits(), 0x3ffd5555555555555556000000000000);
+
+        // the exponent range is the one of the registers, beyond the one of F32
+        let big = F128::from_bits(0x43e55555555555555555555555555555); // 2^1000 / 3
+        let (ret, flags) = with_flags(|| PrecisionControl::Single.round(&big, rnd));
+        assert_eq!(ret.to_bits(), 0x43e55555560000000000000000000000);
+        assert!(!flags.is_overflow());
+
+        // the quotient is rounded once, to 24 bits
+        let one = F128::from_bits(0x3fff0000000000000000000000000000);
+        let three = F128::from_bits(0x40008000000000000000000000000000);
+        let ret = PrecisionControl::Single.chop().div(&one, &three, rnd);
+        assert_eq!(ret.to_bits(), 0x3ffd5555560000000000000000000000);
+        let (ret, c1) = round_c1(rnd, |rnd| PrecisionControl::Single.round(&third, rnd));
+        assert_eq!(ret.to_bits(), 0x3ffd5555560000000000000000000000);
+        assert!(c1);
+    }
+
+    #[test]
+    fn x87_tag_word() {
+        assert_eq!(Tag::of(reg(0x8000000000000000, 0x3fff)), Tag::Valid);
+        assert_eq!(Tag::of(reg(0, 0x8000)), Tag::Zero);
+        assert_eq!(Tag::of(reg(1, 0)), Tag::Special);
+        assert_eq!(Tag::of(reg(0x8000000000000000, 0)), Tag::Special);
+        assert_eq!(Tag::of(reg(0x4000000000000000, 0x4000)), Tag::Special);
+        assert_eq!(Tag::of(reg(0xc000000000000000, 0xffff)), Tag::Special);
+
+        let mut regs = [None; 8];
+        regs[0] = Some(reg(0, 0));
+        regs[7] = Some(reg(0x8000000000000000, 0x3fff));
+        assert_eq!(tag_word(regs), 0x3ffd);
+        assert_eq!(tag_word([None; 8]), 0xffff);
+    }
+
+    #[test]
+    fn x87_c1() {
+        let rnd = RoundingMode::TiesToEven;
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+
+        let a = F64::from_bits(0x3fb999999999999a); // 0.1
+        let (ret, c1) = round_c1(RoundingMode::TowardNegative, |rnd| a.neg().to_f32(rnd));
+        assert_eq!(ret.to_bits(), 0xbdcccccd);
+        assert!(c1);
+        let (_, c1) = round_c1(rnd, |rnd| a.to_f32(RoundingMode::TowardZero).to_f64(rnd));
+        assert!(!c1);
+        flag.get();
+        assert_eq!(flag.to_bits(), ExceptionFlags::FLAG_INEXACT);
+
+        // overflowing to infinity rounds up
+        let max = F32::from_bits(0x7f7fffff);
+        let (ret, c1) = round_c1(rnd, |rnd| max.add(max, rnd));
+        assert!(ret.is_infinity() && c1);
+    }
+}